@@ -0,0 +1,116 @@
+//! Benchmarks for the connection collection hot path: /proc/net parsing,
+//! socket inode to process matching, sorting and formatting.
+//!
+//! Run with `cargo bench`; everything reads from synthetic fixtures, so
+//! results don't depend on what the machine is running.
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use std::hint::black_box;
+use std::time::Duration;
+
+mod fixtures;
+
+use fixtures::ProcFixture;
+use network_monitor_core::models::ConnectionSnapshot;
+use network_monitor_core::services::{NetworkService, ProcessCache, SortedConnections};
+use network_monitor_core::utils::formatter::Formatter;
+
+fn proc_parsing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("proc_parsing");
+    for rows in [100, 1_000, 10_000] {
+        let fixture = ProcFixture::unowned_sockets(rows);
+        let service = NetworkService::with_proc_root(fixture.root());
+        group.throughput(Throughput::Elements(rows as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(rows), &rows, |b, _| {
+            b.iter(|| black_box(service.get_connections()))
+        });
+    }
+    group.finish();
+}
+
+fn inode_matching(c: &mut Criterion) {
+    let mut group = c.benchmark_group("inode_matching");
+    group.sample_size(20);
+    for processes in [50, 500] {
+        let fixture = ProcFixture::generate(processes, 8);
+        group.throughput(Throughput::Elements(processes as u64));
+
+        group.bench_with_input(
+            BenchmarkId::new("full_scan", processes),
+            &processes,
+            |b, _| {
+                b.iter_batched(
+                    || ProcessCache::with_proc_root(fixture.root()),
+                    |mut cache| black_box(cache.export_owners()),
+                    BatchSize::SmallInput,
+                )
+            },
+        );
+
+        // Nothing changed since the previous scan, so no fd directory is re-read
+        let mut cache = ProcessCache::with_proc_root(fixture.root());
+        cache.export_owners();
+        group.bench_with_input(
+            BenchmarkId::new("incremental_scan", processes),
+            &processes,
+            |b, _| b.iter(|| black_box(cache.export_owners())),
+        );
+
+        cache.set_update_interval(Duration::MAX);
+        group.throughput(Throughput::Elements(fixture.inodes().len() as u64));
+        group.bench_with_input(BenchmarkId::new("lookup", processes), &processes, |b, _| {
+            b.iter(|| {
+                for &inode in fixture.inodes() {
+                    black_box(cache.get_process_info(inode));
+                }
+            })
+        });
+    }
+    group.finish();
+}
+
+fn sorting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("sorting");
+    let snapshot = ConnectionSnapshot {
+        connections: fixtures::connections(5_000),
+        ..ConnectionSnapshot::default()
+    };
+    for (name, column) in [("program", 0), ("remote", 3), ("download", 6)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || SortedConnections::new(column, true),
+                |mut sorted| {
+                    sorted.update(&snapshot);
+                    sorted
+                },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+fn formatting(c: &mut Criterion) {
+    let mut group = c.benchmark_group("formatting");
+    let connections = fixtures::connections(5_000);
+    group.throughput(Throughput::Elements(connections.len() as u64));
+    group.bench_function("rates", |b| {
+        b.iter(|| {
+            for conn in &connections {
+                black_box(Formatter::format_bytes(conn.rx_rate));
+                black_box(Formatter::format_bytes(conn.tx_rate));
+            }
+        })
+    });
+    group.bench_function("process_display", |b| {
+        b.iter(|| {
+            for conn in &connections {
+                black_box(conn.get_process_display());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, proc_parsing, inode_matching, sorting, formatting);
+criterion_main!(benches);
@@ -0,0 +1,206 @@
+//! Synthetic /proc trees and connection lists for the benchmarks
+
+use network_monitor_core::models::{Connection, ConnectionState, Protocol};
+use std::fmt::Write as _;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+const PROC_NET_HEADER: &str = "  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode\n";
+const PROGRAMS: [&str; 6] = [
+    "firefox",
+    "sshd",
+    "systemd-resolve",
+    "chrome",
+    "postgres",
+    "nginx",
+];
+const TABLES: [&str; 4] = ["tcp", "tcp6", "udp", "udp6"];
+/// First PID and inode handed out, so fixture values look like real ones
+const FIRST_PID: usize = 1000;
+const FIRST_INODE: u64 = 100_000;
+
+static NEXT_FIXTURE: AtomicUsize = AtomicUsize::new(0);
+
+/// Small deterministic generator so every run benchmarks the same data
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self) -> u64 {
+        self.0 = self
+            .0
+            .wrapping_mul(6364136223846793005)
+            .wrapping_add(1442695040888963407);
+        self.0 >> 33
+    }
+}
+
+/// A /proc lookalike in a temporary directory: `net/{tcp,tcp6,udp,udp6}`
+/// tables plus `<pid>/{status,cmdline,fd/}` for each owning process.
+/// Removed again when dropped.
+pub struct ProcFixture {
+    root: PathBuf,
+    inodes: Vec<u64>,
+}
+
+impl ProcFixture {
+    /// `processes` processes holding `sockets_per_process` sockets each
+    pub fn generate(processes: usize, sockets_per_process: usize) -> Self {
+        let mut fixture = Self::empty();
+        let mut inode = FIRST_INODE;
+
+        for index in 0..processes {
+            let pid = FIRST_PID + index;
+            let program = PROGRAMS[index % PROGRAMS.len()];
+            let dir = fixture.root.join(pid.to_string());
+            let fd_dir = dir.join("fd");
+            fs::create_dir_all(&fd_dir).expect("create fixture fd directory");
+            fs::write(
+                dir.join("status"),
+                format!("Name:\t{program}\nUmask:\t0022\nState:\tS (sleeping)\nPid:\t{pid}\n"),
+            )
+            .expect("write fixture status");
+            fs::write(
+                dir.join("cmdline"),
+                format!("/usr/bin/{program}\0--worker\0{index}\0"),
+            )
+            .expect("write fixture cmdline");
+
+            // stdio descriptors that aren't sockets, as in real processes
+            for fd in 0..3 {
+                symlink("/dev/null", fd_dir.join(fd.to_string())).expect("create fixture fd");
+            }
+            for fd in 3..3 + sockets_per_process {
+                symlink(format!("socket:[{inode}]"), fd_dir.join(fd.to_string()))
+                    .expect("create fixture socket fd");
+                fixture.inodes.push(inode);
+                inode += 1;
+            }
+        }
+
+        let inodes = fixture.inodes.clone();
+        fixture.write_tables(&inodes);
+        fixture
+    }
+
+    /// `rows` sockets without an owner (inode 0, like TIME_WAIT entries), so
+    /// reading the tables doesn't involve the process scan
+    pub fn unowned_sockets(rows: usize) -> Self {
+        let fixture = Self::empty();
+        fixture.write_tables(&vec![0; rows]);
+        fixture
+    }
+
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Socket inodes owned by the fixture processes
+    pub fn inodes(&self) -> &[u64] {
+        &self.inodes
+    }
+
+    fn empty() -> Self {
+        let root = std::env::temp_dir().join(format!(
+            "network-monitor-bench-{}-{}",
+            std::process::id(),
+            NEXT_FIXTURE.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(root.join("net")).expect("create fixture net directory");
+        Self {
+            root,
+            inodes: Vec::new(),
+        }
+    }
+
+    /// Spread the sockets round-robin over the four tables
+    fn write_tables(&self, inodes: &[u64]) {
+        let mut rng = Lcg(inodes.len() as u64);
+        for (table_index, table) in TABLES.iter().enumerate() {
+            let ipv6 = table.ends_with('6');
+            let mut contents = String::from(PROC_NET_HEADER);
+            let rows = inodes
+                .iter()
+                .skip(table_index)
+                .step_by(TABLES.len())
+                .enumerate();
+            for (slot, inode) in rows {
+                let local = socket_addr(&mut rng, ipv6);
+                let remote = socket_addr(&mut rng, ipv6);
+                let state = if table.starts_with("tcp") {
+                    [0x01, 0x01, 0x01, 0x06, 0x08, 0x0A][(rng.next() % 6) as usize]
+                } else {
+                    0x07
+                };
+                let _ = writeln!(
+                    contents,
+                    "{slot:4}: {local} {remote} {state:02X} 00000000:00000000 00:00000000 00000000  1000        0 {inode} 1 0000000000000000 20 4 30 10 -1"
+                );
+            }
+            fs::write(self.root.join("net").join(table), contents).expect("write fixture table");
+        }
+    }
+}
+
+impl Drop for ProcFixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+/// Address in /proc/net hex notation
+fn socket_addr(rng: &mut Lcg, ipv6: bool) -> String {
+    let port = rng.next() % 65536;
+    if ipv6 {
+        format!(
+            "{:08X}{:08X}{:08X}{:08X}:{port:04X}",
+            rng.next() as u32,
+            rng.next() as u32,
+            rng.next() as u32,
+            rng.next() as u32
+        )
+    } else {
+        format!("{:08X}:{port:04X}", rng.next() as u32)
+    }
+}
+
+/// Parsed connections with varied programs, addresses, states and rates
+pub fn connections(count: usize) -> Vec<Connection> {
+    let mut rng = Lcg(count as u64);
+    let states = [
+        ConnectionState::Established,
+        ConnectionState::Listen,
+        ConnectionState::TimeWait,
+        ConnectionState::CloseWait,
+    ];
+    let protocols = [Protocol::Tcp, Protocol::Tcp6, Protocol::Udp, Protocol::Udp6];
+
+    (0..count)
+        .map(|index| {
+            let program = PROGRAMS[(rng.next() % PROGRAMS.len() as u64) as usize];
+            let pid = FIRST_PID + (rng.next() % 500) as usize;
+            let mut conn = Connection::new(
+                protocols[index % protocols.len()],
+                states[(rng.next() % states.len() as u64) as usize],
+                format!("192.168.1.{}:{}", rng.next() % 255, rng.next() % 65536),
+                format!(
+                    "{}.{}.{}.{}:443",
+                    rng.next() % 255,
+                    rng.next() % 255,
+                    rng.next() % 255,
+                    rng.next() % 255
+                ),
+                program,
+                pid.to_string(),
+                format!("/usr/bin/{program} --worker {index}"),
+            );
+            // Mostly idle sockets with a few busy ones, up to ~100 MB/s
+            if rng.next().is_multiple_of(4) {
+                conn.rx_rate = rng.next() % 100_000_000;
+                conn.tx_rate = rng.next() % 10_000_000;
+            }
+            conn
+        })
+        .collect()
+}
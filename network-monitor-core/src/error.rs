@@ -2,7 +2,6 @@ use std::sync::PoisonError;
 
 /// Custom error types for Network Monitor
 #[derive(Debug, thiserror::Error)]
-#[allow(dead_code)]
 pub enum NetworkMonitorError {
     #[error("Failed to read /proc filesystem: {0}")]
     ProcIo(#[from] std::io::Error),
@@ -19,6 +18,13 @@ pub enum NetworkMonitorError {
     #[error("Failed to parse hex value: {0}")]
     HexParseError(String),
 
+    #[error("Malformed /proc/net line, {field}: {source} in \"{line}\"")]
+    ProcNetLine {
+        field: &'static str,
+        line: String,
+        source: Box<NetworkMonitorError>,
+    },
+
     #[error("Invalid PID format: {0}")]
     InvalidPid(String),
 
@@ -28,6 +34,60 @@ pub enum NetworkMonitorError {
     #[error("Failed to resolve hostname: {0}")]
     ResolutionError(String),
 
+    #[error("Failed to read firewall log: {0}")]
+    FirewallLog(String),
+
+    #[error("Failed to read fail2ban ban list: {0}")]
+    Fail2ban(String),
+
+    #[error("Privileged helper unavailable: {0}")]
+    PrivilegedHelper(String),
+
+    #[error("Bandwidth limit failed: {0}")]
+    BandwidthLimit(String),
+
+    #[error("Process event subscription failed: {0}")]
+    ProcEvents(String),
+
+    #[error("Interface event subscription failed: {0}")]
+    LinkEvents(String),
+
+    #[error("Socket diagnostics query failed: {0}")]
+    SockDiag(String),
+
+    #[error("Failed to read conntrack flows: {0}")]
+    Conntrack(String),
+
+    #[error("Packet capture unavailable: {0}")]
+    Capture(String),
+
+    #[error("GeoIP database unusable: {0}")]
+    GeoIp(String),
+
+    #[error("cgroup accounting unavailable: {0}")]
+    CgroupAccounting(String),
+
+    #[error("connect tracing unavailable: {0}")]
+    ConnectTracing(String),
+
+    #[error("Netlink request failed: {0}")]
+    Netlink(String),
+
+    #[error("libproc query failed: {0}")]
+    Libproc(String),
+
+    #[error("Invalid fixture: {0}")]
+    Fixture(String),
+
+    #[error("Remote collection failed: {0}")]
+    Remote(String),
+
+    #[error("Invalid configuration: {0}")]
+    Config(String),
+
+    #[error("Skipped after repeated failures, retrying later")]
+    CircuitOpen,
+
     #[error("GTK initialization failed")]
     GtkInitError,
 
@@ -39,7 +99,6 @@ pub enum NetworkMonitorError {
 pub type Result<T> = std::result::Result<T, NetworkMonitorError>;
 
 /// Trait for converting mutex poisoning errors
-#[allow(dead_code)]
 pub trait MutexResult<T> {
     fn handle_mutex(self, context: &str) -> Result<T>;
 }
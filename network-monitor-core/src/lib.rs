@@ -0,0 +1,45 @@
+//! Connection monitoring for Linux, read straight from /proc.
+//!
+//! - [`NetworkService`] parses `/proc/net/{tcp,udp}{,6}` and maps each socket
+//!   inode to the process owning it
+//! - [`RateTracker`] turns the I/O counters of those processes into
+//!   per-second rates between passes
+//! - [`Collector`] backends combine the two into snapshots: procfs, netlink
+//!   sock_diag, libproc on macOS, or a recorded fixture for tests, picked
+//!   with [`Backend`]
+//! - [`ConnectionCollector`] runs a backend on a worker thread and delivers
+//!   every pass as a [`ConnectionSnapshot`]: the connections, interface
+//!   traffic and totals at the time it was taken
+//! - [`AddressResolver`] looks up hostnames for remote addresses in the
+//!   background
+//! - With the `ratatui` feature, [`widgets::ConnectionsTable`] draws
+//!   connections the way `nmt` does, for embedding in other terminal UIs
+//!
+//! ```no_run
+//! use network_monitor_core::services::{CacheLimits, CollectorEvent, CollectorRequest};
+//! use network_monitor_core::{Backend, ConnectionCollector};
+//!
+//! let (collector, events) = ConnectionCollector::spawn(Backend::Procfs, CacheLimits::default());
+//! collector.request(CollectorRequest::Refresh {
+//!     group_by_application: false,
+//! });
+//! if let Ok(CollectorEvent::Snapshot(Ok(snapshot))) = events.recv_blocking() {
+//!     for conn in &snapshot.connections {
+//!         println!("{} {} -> {} {}B/s", conn.program, conn.local, conn.remote, conn.rx_rate);
+//!     }
+//! }
+//! ```
+
+pub mod error;
+mod error_tests;
+pub mod models;
+pub mod services;
+pub mod utils;
+#[cfg(feature = "ratatui")]
+pub mod widgets;
+
+pub use error::{NetworkMonitorError, Result};
+pub use models::ConnectionSnapshot;
+pub use services::{
+    AddressResolver, Backend, Collector, ConnectionCollector, NetworkService, RateTracker,
+};
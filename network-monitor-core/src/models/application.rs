@@ -0,0 +1,144 @@
+use serde::{Deserialize, Serialize};
+
+/// Bytes sent and received so far by the sockets of one cgroup
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CgroupBytes {
+    pub tx: u64,
+    pub rx: u64,
+}
+
+/// Traffic of one application, added up over the cgroups it ran in, so
+/// processes that forked and exited between passes still count
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ApplicationTraffic {
+    /// Application ID or unit name, see [`application_name`]
+    pub name: String,
+    pub tx_rate: u64,
+    pub rx_rate: u64,
+    /// Transferred since accounting started
+    pub tx_bytes: u64,
+    pub rx_bytes: u64,
+}
+
+impl ApplicationTraffic {
+    /// Combined upload and download rate
+    pub fn rate(&self) -> u64 {
+        self.tx_rate.saturating_add(self.rx_rate)
+    }
+}
+
+/// Application a cgroup v2 path belongs to: the app ID of Flatpak, Snap and
+/// desktop-launched scopes (`app-gnome-firefox-2345.scope` is `firefox`),
+/// the unit name of services and sessions, and "system" for the root and
+/// `init.scope`
+pub fn application_name(cgroup: &str) -> String {
+    let Some(unit) = cgroup
+        .rsplit('/')
+        .find(|part| part.ends_with(".scope") || part.ends_with(".service"))
+    else {
+        return "system".to_string();
+    };
+    let unit = unescape(unit);
+    let name = unit
+        .strip_suffix(".scope")
+        .or_else(|| unit.strip_suffix(".service"))
+        .unwrap_or(&unit);
+
+    if name == "init" {
+        return "system".to_string();
+    }
+    if let Some(snap) = name.strip_prefix("snap.") {
+        // snap.NAME.APP-UUID
+        return snap.split('.').next().unwrap_or(snap).to_string();
+    }
+    if let Some(app) = name.strip_prefix("app-") {
+        // app-LAUNCHER-APPID-RANDOM.scope or app-LAUNCHER-APPID@RANDOM.service;
+        // launchers without a name of their own give app-APPID-RANDOM
+        let app = app.split('@').next().unwrap_or(app);
+        let app = match app.rsplit_once('-') {
+            Some((app, random)) if is_instance(random) => app,
+            _ => app,
+        };
+        return match app.split_once('-') {
+            Some((_, id)) if !id.is_empty() => id,
+            _ => app,
+        }
+        .to_string();
+    }
+    name.to_string()
+}
+
+/// Numbers and UUIDs systemd appends to make unit names unique
+fn is_instance(part: &str) -> bool {
+    !part.is_empty() && part.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Undo systemd's escaping of unit names, e.g. "\x2d" for "-"
+fn unescape(unit: &str) -> String {
+    let mut name = String::with_capacity(unit.len());
+    let mut rest = unit;
+    while let Some(index) = rest.find("\\x") {
+        name.push_str(&rest[..index]);
+        let code = rest
+            .get(index + 2..index + 4)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match code {
+            Some(byte) => {
+                name.push(char::from(byte));
+                rest = &rest[index + 4..];
+            }
+            None => {
+                name.push_str("\\x");
+                rest = &rest[index + 2..];
+            }
+        }
+    }
+    name.push_str(rest);
+    name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_application_name() {
+        let user = "/user.slice/user-1000.slice/user@1000.service/app.slice";
+        for (cgroup, name) in [
+            (
+                format!("{user}/app-flatpak-org.mozilla.firefox-2345.scope"),
+                "org.mozilla.firefox",
+            ),
+            (format!("{user}/app-gnome-firefox-2345.scope"), "firefox"),
+            (
+                format!("{user}/app-gnome-org.gnome.Console-3001.scope"),
+                "org.gnome.Console",
+            ),
+            (
+                format!("{user}/app-org.kde.konsole@0a1b2c3d.service"),
+                "org.kde.konsole",
+            ),
+            (
+                format!("{user}/app-gnome-google\\x2dchrome-4410.scope"),
+                "google-chrome",
+            ),
+            (
+                format!("{user}/snap.spotify.spotify-1c4d6a3e-6d7b-4c5a-9f3e-0a1b2c3d4e5f.scope"),
+                "spotify",
+            ),
+            (format!("{user}/app.slice/syncthing.service"), "syncthing"),
+            (
+                "/system.slice/NetworkManager.service".to_string(),
+                "NetworkManager",
+            ),
+            (
+                "/user.slice/user-1000.slice/session-2.scope".to_string(),
+                "session-2",
+            ),
+            ("/init.scope".to_string(), "system"),
+            ("/".to_string(), "system"),
+        ] {
+            assert_eq!(application_name(&cgroup), name, "{cgroup}");
+        }
+    }
+}
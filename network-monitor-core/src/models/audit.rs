@@ -0,0 +1,111 @@
+use super::{Connection, Direction, Scope};
+use crate::utils::parse_endpoint;
+use serde::Serialize;
+use std::net::IpAddr;
+
+/// A remote endpoint a program connected out to during an audit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditDestination {
+    /// "tcp" or "udp", IPv4 and IPv6 alike
+    pub protocol: &'static str,
+    pub address: IpAddr,
+    pub port: u16,
+    /// Hostname of the address, filled in when the report is written
+    pub host: Option<String>,
+    /// TLS server name seen on one of the connections
+    pub server_name: Option<String>,
+    pub scope: Option<Scope>,
+    /// Sockets opened to it while the audit ran, as far as collection passes
+    /// saw them
+    pub connections: u64,
+}
+
+impl AuditDestination {
+    /// Destination of `conn` when a local process opened it to another host;
+    /// loopback traffic never reaches a firewall so it is left out
+    pub fn of(conn: &Connection) -> Option<Self> {
+        if conn.direction != Some(Direction::Outbound) {
+            return None;
+        }
+        let remote = parse_endpoint(&conn.remote)?;
+        let address = remote.ip().to_canonical();
+        if address.is_unspecified() || address.is_loopback() {
+            return None;
+        }
+        Some(Self {
+            protocol: conn.protocol.transport(),
+            address,
+            port: remote.port(),
+            host: None,
+            server_name: conn.server_name.as_deref().map(str::to_string),
+            scope: conn.scope,
+            connections: 0,
+        })
+    }
+}
+
+/// A program and everywhere it connected out to during an audit
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AuditProgram {
+    pub program: String,
+    /// Executable, when the command line starts with its path
+    pub path: Option<String>,
+    /// Most connections first
+    pub destinations: Vec<AuditDestination>,
+}
+
+impl AuditProgram {
+    /// Program and executable of `conn`
+    pub fn identity(conn: &Connection) -> (String, Option<String>) {
+        let path = conn
+            .command
+            .split_whitespace()
+            .next()
+            .filter(|path| path.starts_with('/'))
+            .map(str::to_string);
+        (conn.program.to_string(), path)
+    }
+
+    /// Sockets opened to all its destinations
+    pub fn connections(&self) -> u64 {
+        self.destinations.iter().map(|dest| dest.connections).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    fn connection(remote: &str, direction: Option<Direction>) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp6,
+            ConnectionState::Established,
+            "[2001:db8::2]:51000",
+            remote,
+            "curl",
+            "100",
+            "/usr/bin/curl https://example.com",
+        );
+        conn.direction = direction;
+        conn
+    }
+
+    #[test]
+    fn test_only_outbound_connections_to_other_hosts_count() {
+        let outbound = Some(Direction::Outbound);
+        let dest = AuditDestination::of(&connection("[::ffff:93.184.216.34]:443", outbound));
+        let dest = dest.unwrap();
+        assert_eq!(dest.protocol, "tcp");
+        assert_eq!(dest.address, "93.184.216.34".parse::<IpAddr>().unwrap());
+        assert_eq!(dest.port, 443);
+        let inbound = Some(Direction::Inbound);
+        assert!(AuditDestination::of(&connection("[2001:db8::9]:40000", inbound)).is_none());
+        assert!(AuditDestination::of(&connection("[::1]:631", outbound)).is_none());
+        assert!(AuditDestination::of(&connection("[::]:*", None)).is_none());
+
+        let (program, path) = AuditProgram::identity(&connection("[::1]:631", None));
+        assert_eq!(program, "curl");
+        assert_eq!(path.as_deref(), Some("/usr/bin/curl"));
+    }
+}
@@ -0,0 +1,40 @@
+use serde::{Deserialize, Serialize};
+
+/// A connection attempt dropped or rejected by the kernel firewall
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlockedAttempt {
+    pub timestamp: String,
+    pub action: String,
+    pub in_interface: String,
+    pub out_interface: String,
+    pub protocol: String,
+    pub source: String,
+    pub destination: String,
+    pub program: String,
+    pub pid: String,
+}
+
+impl BlockedAttempt {
+    /// Whether the packet was leaving this host (as opposed to arriving)
+    pub fn is_outbound(&self) -> bool {
+        !self.out_interface.is_empty() && self.in_interface.is_empty()
+    }
+
+    /// Port on this host involved in the attempt
+    pub fn local_port(&self) -> Option<u16> {
+        let local = if self.is_outbound() {
+            &self.source
+        } else {
+            &self.destination
+        };
+        local.rsplit(':').next()?.parse().ok()
+    }
+
+    pub fn get_process_display(&self) -> String {
+        if self.pid != "N/A" {
+            format!("{}({})", self.program, self.pid)
+        } else {
+            self.program.clone()
+        }
+    }
+}
@@ -0,0 +1,305 @@
+use super::{ConnectionState, Direction, Protocol, Quic, Scope, SocketTimer};
+use crate::utils::parse_endpoint;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+/// Program shown for sockets whose owning process we aren't allowed to inspect
+pub const PERMISSION_DENIED: &str = "permission denied";
+
+/// Process information for caching
+#[derive(Debug, Clone)]
+pub struct ProcessInfo {
+    pub name: Arc<str>,
+    pub command: Arc<str>,
+    pub last_seen: std::time::Instant,
+}
+
+/// Process owning a socket inode, as reported by the privileged helper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SocketOwner {
+    pub pid: String,
+    pub name: String,
+    pub command: String,
+}
+
+/// Outgoing connection attempt seen by the connect tracer as it happened,
+/// before the socket shows up in /proc/net, if it ever does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConnectEvent {
+    pub pid: u32,
+    /// Name of the calling thread, which can be gone by the time we look
+    pub program: String,
+    pub protocol: Protocol,
+    pub remote: SocketAddr,
+}
+
+/// Network connection information. Text fields are shared `Arc<str>` so the
+/// many clones made while sorting, resolving and rendering don't allocate.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Connection {
+    pub protocol: Protocol,
+    pub state: ConnectionState,
+    pub local: Arc<str>,
+    pub remote: Arc<str>,
+    pub program: Arc<str>,
+    pub pid: Arc<str>,
+    pub command: Arc<str>,
+    /// Kernel inode of the socket; 0 when unknown, as for TIME_WAIT sockets
+    /// and connections replayed from older fixtures
+    #[serde(default)]
+    pub inode: u64,
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+    /// Bytes not yet acknowledged by the peer (Send-Q in netstat)
+    #[serde(default)]
+    pub send_queue: u32,
+    /// Bytes received but not yet read by the process (Recv-Q)
+    #[serde(default)]
+    pub recv_queue: u32,
+    /// Retransmit, keepalive or TIME_WAIT timer pending on a TCP socket
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timer: Option<SocketTimer>,
+    /// Congestion control algorithm of a TCP socket, e.g. "cubic" or "bbr"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub congestion: Option<Arc<str>>,
+    /// Server name from the TLS ClientHello, when packet capture saw it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub server_name: Option<Arc<str>>,
+    /// Destination asked of the proxy this connection goes to, when packet
+    /// capture saw the request
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub upstream: Option<Arc<str>>,
+    /// Whether this UDP flow is QUIC, guessed from the port or confirmed by
+    /// packet capture
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quic: Option<Quic>,
+    /// ISO 3166 code of the country GeoIP places the remote host in
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub country: Option<Arc<str>>,
+    /// Program of the local proxy this connection goes through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy: Option<Arc<str>>,
+    /// VPN tunnel interface the connection is routed through
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tunnel: Option<Arc<str>>,
+    /// Whether the remote side or a local process opened the connection;
+    /// None for listening sockets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub direction: Option<Direction>,
+    /// Whether the remote host is on the LAN, the internet or behind a VPN;
+    /// None for listening sockets
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scope: Option<Scope>,
+    /// Machine the connection is on, by its `[remotes]` name, when several
+    /// are collected from together
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub host: Option<Arc<str>>,
+    /// Rate in bytes per second the owning process is capped at, each way,
+    /// by a bandwidth limit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bandwidth_limit: Option<u64>,
+    /// Opened and closed again between two passes, so only known from the
+    /// connect tracer; kept around for a while and drawn greyed out
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub short_lived: bool,
+}
+
+impl Connection {
+    pub fn new(
+        protocol: Protocol,
+        state: ConnectionState,
+        local: impl Into<Arc<str>>,
+        remote: impl Into<Arc<str>>,
+        program: impl Into<Arc<str>>,
+        pid: impl Into<Arc<str>>,
+        command: impl Into<Arc<str>>,
+    ) -> Self {
+        Self {
+            protocol,
+            state,
+            local: local.into(),
+            remote: remote.into(),
+            program: program.into(),
+            pid: pid.into(),
+            command: command.into(),
+            inode: 0,
+            rx_rate: 0,
+            tx_rate: 0,
+            send_queue: 0,
+            recv_queue: 0,
+            timer: None,
+            congestion: None,
+            server_name: None,
+            upstream: None,
+            quic: None,
+            country: None,
+            proxy: None,
+            tunnel: None,
+            direction: None,
+            scope: None,
+            host: None,
+            bandwidth_limit: None,
+            short_lived: false,
+        }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.rx_rate > 0 || self.tx_rate > 0
+    }
+
+    /// Whether the owning process is hidden by /proc permissions
+    pub fn is_permission_denied(&self) -> bool {
+        &*self.program == PERMISSION_DENIED
+    }
+
+    /// UDP with a peer on port 443, or a local port 443 answering one:
+    /// browsers and CDNs speak QUIC there
+    pub fn looks_like_quic(&self) -> bool {
+        const HTTPS: u16 = 443;
+        let port = |addr: &str| parse_endpoint(addr).map(|addr| addr.port());
+        self.protocol.is_udp()
+            && parse_endpoint(&self.remote).is_some_and(|remote| !remote.ip().is_unspecified())
+            && (port(&self.remote) == Some(HTTPS) || port(&self.local) == Some(HTTPS))
+    }
+
+    /// Protocol column text: "udp (QUIC?)" for QUIC flows, else the protocol
+    pub fn protocol_label(&self) -> String {
+        match self.quic {
+            Some(quic) => format!("{} ({})", self.protocol, quic.label()),
+            None => self.protocol.to_string(),
+        }
+    }
+
+    pub fn get_process_display(&self) -> String {
+        if &*self.pid != "N/A" {
+            format!("{}({})", self.program, self.pid)
+        } else {
+            self.program.to_string()
+        }
+    }
+
+    /// Whether the remote end is this machine's loopback address, which the
+    /// resolver shows as LOCALHOST
+    pub fn is_loopback(&self) -> bool {
+        self.remote.starts_with("127.0.0.1:") || self.remote.starts_with("[::1]:")
+    }
+
+    /// Port of the local end, None when the socket has none
+    pub fn local_port(&self) -> Option<u16> {
+        self.local.rsplit_once(':')?.1.parse().ok()
+    }
+
+    /// Whether any displayed field contains `needle`, which must be
+    /// lowercase. "FIELD=VALUE" instead matches one field exactly: "port=N"
+    /// the sockets on local port N, "proto=udp" UDP over either IP version
+    /// (or "proto=udp6" IPv6 only), "scope=lan|wan|vpn", "state=", "pid=",
+    /// "program=" and "host=".
+    pub fn matches_filter(&self, needle: &str) -> bool {
+        if let Some((field, value)) = needle.split_once('=') {
+            let value = value.trim();
+            match field.trim() {
+                "port" => {
+                    return value
+                        .parse::<u16>()
+                        .is_ok_and(|port| self.local_port() == Some(port))
+                }
+                "proto" | "protocol" => {
+                    let protocol = self.protocol.as_str();
+                    return protocol == value || protocol.strip_suffix('6') == Some(value);
+                }
+                "scope" => return self.scope.is_some_and(|scope| scope.as_str() == value),
+                "state" => return self.state.to_string().eq_ignore_ascii_case(value),
+                "pid" => return &*self.pid == value,
+                "program" => return self.program.to_lowercase() == value,
+                "host" => {
+                    return self
+                        .host
+                        .as_deref()
+                        .is_some_and(|host| host.to_lowercase() == value)
+                }
+                _ => {}
+            }
+        }
+        let state = self.state.to_string();
+        [
+            &*self.program,
+            &*self.pid,
+            &*self.local,
+            &*self.remote,
+            &*self.command,
+            self.protocol.as_str(),
+            &state,
+            self.server_name.as_deref().unwrap_or_default(),
+            self.upstream.as_deref().unwrap_or_default(),
+            self.proxy.as_deref().unwrap_or_default(),
+            self.tunnel.as_deref().unwrap_or_default(),
+            self.direction.map_or("", Direction::as_str),
+            self.scope.map_or("", Scope::as_str),
+            self.quic.map_or("", Quic::label),
+            self.country.as_deref().unwrap_or_default(),
+            self.host.as_deref().unwrap_or_default(),
+        ]
+        .iter()
+        .any(|field| field.to_lowercase().contains(needle))
+    }
+}
+
+/// Process I/O statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessIO {
+    pub rx: u64,
+    pub tx: u64,
+}
+
+/// Per-PID I/O counters from one refresh
+pub type ProcessIOMap = HashMap<Arc<str>, ProcessIO>;
+
+impl ProcessIO {
+    pub fn new(rx: u64, tx: u64) -> Self {
+        Self { rx, tx }
+    }
+
+    pub fn zero() -> Self {
+        Self { rx: 0, tx: 0 }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_field_filters_match_exactly() {
+        let conn = Connection::new(
+            Protocol::Udp6,
+            ConnectionState::Established,
+            "[::1]:5353",
+            "[ff02::fb]:5353",
+            "avahi-daemon",
+            "612",
+            "avahi-daemon: running",
+        );
+        assert!(conn.matches_filter("proto=udp"));
+        assert!(conn.matches_filter("proto=udp6"));
+        assert!(!conn.matches_filter("proto=tcp"));
+        assert!(conn.matches_filter("port=5353"));
+        assert!(conn.matches_filter("state=established"));
+        assert!(conn.matches_filter("pid=612"));
+        assert!(!conn.matches_filter("pid=61"));
+        assert!(conn.matches_filter("program=avahi-daemon"));
+        assert!(!conn.matches_filter("program=avahi"));
+        assert!(!conn.matches_filter("host=nas"));
+        let on_nas = Connection {
+            host: Some("NAS".into()),
+            ..conn.clone()
+        };
+        assert!(on_nas.matches_filter("host=nas"));
+        assert!(on_nas.matches_filter("na"));
+        assert_ne!(on_nas.key(), conn.key());
+        // Other text with '=' is still searched for
+        assert!(!conn.matches_filter("user=root"));
+        assert!(conn.matches_filter("avahi"));
+    }
+}
@@ -0,0 +1,261 @@
+use super::{Connection, Protocol};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Identity of a connection across refreshes, for diffing, keeping the
+/// selection and idle tracking. It leaves out the process, which may only be
+/// found a refresh later, and the state, which changes over the connection's
+/// life.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ConnectionKey {
+    pub protocol: Protocol,
+    pub local: Arc<str>,
+    pub remote: Arc<str>,
+    /// Socket inode, 0 when unknown
+    pub inode: u64,
+    /// Machine of the connection, when several are collected from together
+    pub host: Option<Arc<str>>,
+}
+
+impl ConnectionKey {
+    /// Drop sockets listed more than once, keeping the first. /proc/net
+    /// tables are read a page at a time, so a socket can show up twice when
+    /// others close while the table is read. Connections without an inode
+    /// are all kept, since distinct ones can share a key.
+    pub fn dedup(connections: &mut Vec<Connection>) {
+        let mut seen = HashSet::new();
+        connections.retain(|conn| conn.inode == 0 || seen.insert(conn.key()));
+    }
+
+    /// Addresses the key was made of, whatever the socket
+    fn tuple(&self) -> (Protocol, Arc<str>, Arc<str>, Option<Arc<str>>) {
+        (
+            self.protocol,
+            self.local.clone(),
+            self.remote.clone(),
+            self.host.clone(),
+        )
+    }
+}
+
+impl Connection {
+    pub fn key(&self) -> ConnectionKey {
+        ConnectionKey {
+            protocol: self.protocol,
+            local: self.local.clone(),
+            remote: self.remote.clone(),
+            inode: self.inode,
+            host: self.host.clone(),
+        }
+    }
+}
+
+/// Keyed difference between two consecutive connection snapshots
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionDiff {
+    pub added: Vec<ConnectionKey>,
+    pub removed: Vec<ConnectionKey>,
+    /// Still present, but state, rates or process details differ
+    pub changed: Vec<ConnectionKey>,
+    /// Old and new key of changed connections whose key changed: the kernel
+    /// lists TIME_WAIT sockets without an inode, so a closing connection
+    /// loses its inode while keeping its addresses
+    pub rekeyed: Vec<(ConnectionKey, ConnectionKey)>,
+}
+
+impl ConnectionDiff {
+    pub fn between(old: &[Connection], new: &[Connection]) -> Self {
+        // Several sockets can share a key (e.g. SO_REUSEPORT listeners), so
+        // connections are matched up per key in order
+        let mut old_by_key: HashMap<ConnectionKey, Vec<&Connection>> = HashMap::new();
+        for conn in old {
+            old_by_key.entry(conn.key()).or_default().push(conn);
+        }
+
+        let mut diff = Self::default();
+        let mut matched: HashMap<ConnectionKey, usize> = HashMap::new();
+        let mut unmatched = Vec::new();
+        for conn in new {
+            let key = conn.key();
+            let index = matched.entry(key.clone()).or_insert(0);
+            match old_by_key.get(&key).and_then(|olds| olds.get(*index)) {
+                Some(previous) if *previous != conn => diff.changed.push(key),
+                Some(_) => {}
+                None => unmatched.push(key),
+            }
+            *index += 1;
+        }
+
+        // Old sockets left over, by addresses, for connections that went to
+        // TIME_WAIT and lost their inode
+        let mut left_over: HashMap<_, Vec<ConnectionKey>> = HashMap::new();
+        for (key, olds) in old_by_key {
+            let seen = matched.get(&key).copied().unwrap_or(0);
+            for _ in seen..olds.len() {
+                left_over.entry(key.tuple()).or_default().push(key.clone());
+            }
+        }
+        for key in unmatched {
+            let previous = match key.inode {
+                0 => left_over.get_mut(&key.tuple()).and_then(|olds| {
+                    let index = olds.iter().position(|old| old.inode != 0)?;
+                    Some(olds.remove(index))
+                }),
+                _ => None,
+            };
+            match previous {
+                Some(previous) => {
+                    diff.changed.push(key.clone());
+                    diff.rekeyed.push((previous, key));
+                }
+                None => diff.added.push(key),
+            }
+        }
+        diff.removed = left_over.into_values().flatten().collect();
+
+        diff
+    }
+
+    /// Key a connection known by `key` goes by after this diff
+    pub fn follow(&self, key: ConnectionKey) -> ConnectionKey {
+        self.rekeyed
+            .iter()
+            .find(|(old, _)| *old == key)
+            .map_or(key, |(_, new)| new.clone())
+    }
+
+    /// Nothing to redraw
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionState;
+
+    fn connection(local: &str, state: ConnectionState) -> Connection {
+        Connection::new(
+            Protocol::Tcp,
+            state,
+            local.to_string(),
+            "10.0.0.1:443".to_string(),
+            "curl".to_string(),
+            "42".to_string(),
+            "curl".to_string(),
+        )
+    }
+
+    #[test]
+    fn test_diff_added_removed_changed() {
+        let old = vec![
+            connection("10.0.0.2:1000", ConnectionState::Established),
+            connection("10.0.0.2:1001", ConnectionState::Established),
+            connection("10.0.0.2:1002", ConnectionState::Established),
+        ];
+        let new = vec![
+            connection("10.0.0.2:1002", ConnectionState::Established),
+            connection("10.0.0.2:1001", ConnectionState::TimeWait),
+            connection("10.0.0.2:1003", ConnectionState::SynSent),
+        ];
+
+        let diff = ConnectionDiff::between(&old, &new);
+        assert_eq!(diff.added, vec![new[2].key()]);
+        assert_eq!(diff.removed, vec![old[0].key()]);
+        assert_eq!(diff.changed, vec![new[1].key()]);
+    }
+
+    #[test]
+    fn test_diff_identical_snapshots() {
+        let snapshot = vec![connection("10.0.0.2:1000", ConnectionState::Established)];
+        assert!(ConnectionDiff::between(&snapshot, &snapshot).is_empty());
+    }
+
+    #[test]
+    fn test_process_found_later_is_a_change() {
+        let mut unknown = connection("10.0.0.2:1000", ConnectionState::Established);
+        unknown.inode = 31337;
+        unknown.program = "N/A".into();
+        unknown.pid = "N/A".into();
+        let mut found = connection("10.0.0.2:1000", ConnectionState::Established);
+        found.inode = 31337;
+
+        let diff = ConnectionDiff::between(&[unknown], &[found.clone()]);
+        assert_eq!(diff.changed, vec![found.key()]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+    }
+
+    #[test]
+    fn test_dedup_keeps_one_of_each_socket() {
+        let mut socket = connection("10.0.0.2:1000", ConnectionState::Established);
+        socket.inode = 4242;
+        let mut other = socket.clone();
+        other.inode = 4243;
+        let time_wait = connection("10.0.0.2:1001", ConnectionState::TimeWait);
+        // A live socket on the addresses of one in TIME_WAIT
+        let mut reused = connection("10.0.0.2:1001", ConnectionState::Established);
+        reused.inode = 4244;
+        let mut connections = vec![
+            socket.clone(),
+            time_wait.clone(),
+            socket.clone(),
+            other.clone(),
+            time_wait.clone(),
+            reused.clone(),
+        ];
+
+        ConnectionKey::dedup(&mut connections);
+        assert_eq!(
+            connections,
+            vec![socket, time_wait.clone(), other, time_wait, reused]
+        );
+    }
+
+    #[test]
+    fn test_connection_keeps_its_key_in_time_wait() {
+        let mut established = connection("10.0.0.2:1000", ConnectionState::Established);
+        established.inode = 4242;
+        let time_wait = connection("10.0.0.2:1000", ConnectionState::TimeWait);
+        assert_ne!(time_wait.key(), established.key());
+
+        let diff = ConnectionDiff::between(
+            std::slice::from_ref(&established),
+            std::slice::from_ref(&time_wait),
+        );
+        assert_eq!(diff.changed, vec![time_wait.key()]);
+        assert_eq!(diff.rekeyed, vec![(established.key(), time_wait.key())]);
+        assert!(diff.added.is_empty() && diff.removed.is_empty());
+        assert_eq!(diff.follow(established.key()), time_wait.key());
+
+        // A new socket on the same addresses is another connection
+        let mut reused = established.clone();
+        reused.inode = 4343;
+        let diff = ConnectionDiff::between(
+            std::slice::from_ref(&established),
+            &[time_wait.clone(), reused.clone()],
+        );
+        assert_eq!(diff.added, vec![reused.key()]);
+        assert_eq!(diff.rekeyed, vec![(established.key(), time_wait.key())]);
+        assert!(diff.removed.is_empty());
+
+        // and one already in TIME_WAIT stays another connection too
+        let diff = ConnectionDiff::between(
+            std::slice::from_ref(&time_wait),
+            &[time_wait.clone(), time_wait.clone()],
+        );
+        assert_eq!(diff.added, vec![time_wait.key()]);
+        assert!(diff.rekeyed.is_empty());
+    }
+
+    #[test]
+    fn test_diff_duplicate_keys() {
+        let listener = connection("0.0.0.0:53", ConnectionState::Listen);
+        let old = vec![listener.clone(), listener.clone()];
+        let new = vec![listener.clone()];
+
+        let diff = ConnectionDiff::between(&old, &new);
+        assert_eq!(diff.removed, vec![listener.key()]);
+        assert!(diff.added.is_empty());
+    }
+}
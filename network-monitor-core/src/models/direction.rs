@@ -0,0 +1,167 @@
+use super::{Connection, ConnectionState};
+use crate::utils::parse_endpoint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::SocketAddr;
+
+/// Which side opened a connection
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    /// Accepted on a local listening port: the remote host connected to us
+    Inbound,
+    /// Opened by a local process
+    Outbound,
+}
+
+impl Direction {
+    /// Arrow from the initiating side, reading local → remote
+    pub fn arrow(self) -> &'static str {
+        match self {
+            Direction::Inbound => "←",
+            Direction::Outbound => "→",
+        }
+    }
+
+    /// Arrow and short name, for table cells
+    pub fn label(self) -> &'static str {
+        match self {
+            Direction::Inbound => "← in",
+            Direction::Outbound => "→ out",
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Inbound => "inbound",
+            Direction::Outbound => "outbound",
+        }
+    }
+
+    /// Set the direction of each connection from the listening sockets among
+    /// them. Sockets without a peer get none. Backends without listening
+    /// sockets, like the router's, show every flow from the host that
+    /// started it, so those all come out outbound.
+    pub fn mark(connections: &mut [Connection]) {
+        let listeners: Vec<(&str, SocketAddr)> = connections
+            .iter()
+            .filter(|conn| is_listener(conn))
+            .filter_map(|conn| Some((conn.protocol.transport(), parse_endpoint(&conn.local)?)))
+            .collect();
+        for conn in connections.iter_mut() {
+            conn.direction = Self::of(conn, &listeners);
+        }
+    }
+
+    /// Inbound when the local end of `conn` is one of `listeners`, bound to
+    /// that address or to all of them
+    fn of(conn: &Connection, listeners: &[(&str, SocketAddr)]) -> Option<Self> {
+        if is_listener(conn) {
+            return None;
+        }
+        let local = parse_endpoint(&conn.local)?;
+        let accepted = listeners.iter().any(|(transport, listener)| {
+            *transport == conn.protocol.transport()
+                && listener.port() == local.port()
+                && (listener.ip().is_unspecified() || listener.ip() == local.ip())
+        });
+        Some(if accepted {
+            Direction::Inbound
+        } else {
+            Direction::Outbound
+        })
+    }
+}
+
+/// "inbound" or "outbound"
+impl fmt::Display for Direction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Listening TCP sockets, and UDP sockets without a peer, which is how UDP
+/// servers wait for datagrams
+fn is_listener(conn: &Connection) -> bool {
+    conn.state == ConnectionState::Listen
+        || parse_endpoint(&conn.remote)
+            .is_none_or(|remote| remote.ip().is_unspecified() || remote.port() == 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Protocol;
+
+    fn conn(protocol: Protocol, state: ConnectionState, local: &str, remote: &str) -> Connection {
+        Connection::new(protocol, state, local, remote, "app", "1", "app")
+    }
+
+    #[test]
+    fn test_accepted_connections_are_inbound() {
+        let mut connections = vec![
+            conn(
+                Protocol::Tcp,
+                ConnectionState::Listen,
+                "0.0.0.0:22",
+                "0.0.0.0:*",
+            ),
+            conn(
+                Protocol::Tcp6,
+                ConnectionState::Listen,
+                "[::1]:631",
+                "[::]:*",
+            ),
+            conn(
+                Protocol::Udp,
+                ConnectionState::Close,
+                "0.0.0.0:5353",
+                "0.0.0.0:*",
+            ),
+            // Someone logged in over SSH
+            conn(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "192.168.1.20:22",
+                "192.168.1.7:50514",
+            ),
+            // Our browser
+            conn(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "192.168.1.20:51000",
+                "93.184.216.34:443",
+            ),
+            // The listener is on loopback only
+            conn(
+                Protocol::Tcp6,
+                ConnectionState::Established,
+                "[fe80::1]:631",
+                "[fe80::2]:40000",
+            ),
+            // Same port number, other transport
+            conn(
+                Protocol::Udp,
+                ConnectionState::Established,
+                "192.168.1.20:22",
+                "192.168.1.7:9000",
+            ),
+        ];
+        Direction::mark(&mut connections);
+        let directions: Vec<_> = connections.iter().map(|conn| conn.direction).collect();
+        assert_eq!(
+            directions,
+            [
+                None,
+                None,
+                None,
+                Some(Direction::Inbound),
+                Some(Direction::Outbound),
+                Some(Direction::Outbound),
+                Some(Direction::Outbound),
+            ]
+        );
+        assert_eq!(Direction::Inbound.arrow(), "←");
+        assert_eq!(Direction::Outbound.to_string(), "outbound");
+    }
+}
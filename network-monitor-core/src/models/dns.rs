@@ -0,0 +1,148 @@
+use super::{Connection, ConnectionState, Protocol};
+use std::fmt;
+use std::time::{Duration, SystemTime};
+
+/// Port of plain DNS over UDP and TCP
+pub const DNS_PORT: u16 = 53;
+/// Port of DNS over TLS (RFC 7858)
+pub const DOT_PORT: u16 = 853;
+
+/// How a client talks to its resolver
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum DnsTransport {
+    /// Plain DNS on port 53, one short-lived UDP socket per lookup
+    Plain,
+    /// DNS over TLS on port 853, many lookups per connection
+    Tls,
+}
+
+impl DnsTransport {
+    /// Transport of `conn` when it is a query to a resolver
+    pub fn of(conn: &Connection) -> Option<Self> {
+        if conn.state == ConnectionState::Listen {
+            return None;
+        }
+        let (_, port) = conn.remote.rsplit_once(':')?;
+        match (port.parse().ok()?, conn.protocol) {
+            (DNS_PORT, _) => Some(Self::Plain),
+            (DOT_PORT, Protocol::Tcp | Protocol::Tcp6) => Some(Self::Tls),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for DnsTransport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Plain => "DNS",
+            Self::Tls => "DoT",
+        })
+    }
+}
+
+/// A process and the resolver it queries, with the sockets seen so far.
+/// Collection passes are seconds apart while a plain lookup takes
+/// milliseconds, so `queries` is a sample: it shows who looks names up and
+/// how busily, not an exact count.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DnsClient {
+    /// Process as shown in the connections table, e.g. "firefox(2300)"
+    pub process: String,
+    /// Resolver address without the port
+    pub resolver: String,
+    pub transport: DnsTransport,
+    /// New sockets to the resolver seen
+    pub queries: u64,
+    pub first_seen: SystemTime,
+    pub last_seen: SystemTime,
+}
+
+impl DnsClient {
+    /// Client of `conn`, first seen at `now`; None unless `conn` queries a
+    /// resolver
+    pub fn of(conn: &Connection, now: SystemTime) -> Option<Self> {
+        let transport = DnsTransport::of(conn)?;
+        let (address, _) = conn.remote.rsplit_once(':')?;
+        Some(Self {
+            process: conn.get_process_display(),
+            resolver: address
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .to_string(),
+            transport,
+            queries: 0,
+            first_seen: now,
+            last_seen: now,
+        })
+    }
+
+    /// Queries seen per minute between the first sighting and `now`,
+    /// averaged over at least a minute
+    pub fn queries_per_minute(&self, now: SystemTime) -> f64 {
+        let observed = now
+            .duration_since(self.first_seen)
+            .unwrap_or_default()
+            .max(Duration::from_secs(60));
+        self.queries as f64 / observed.as_secs_f64() * 60.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(protocol: Protocol, state: ConnectionState, remote: &str) -> Connection {
+        Connection::new(
+            protocol,
+            state,
+            "10.0.0.2:40000",
+            remote,
+            "firefox",
+            "2300",
+            "firefox",
+        )
+    }
+
+    #[test]
+    fn test_transport_by_remote_port() {
+        use ConnectionState::*;
+        use Protocol::*;
+        let of = |protocol, state, remote| DnsTransport::of(&connection(protocol, state, remote));
+        assert_eq!(
+            of(Udp, Established, "127.0.0.53:53"),
+            Some(DnsTransport::Plain)
+        );
+        assert_eq!(
+            of(Tcp6, SynSent, "[2606:4700::1111]:853"),
+            Some(DnsTransport::Tls)
+        );
+        assert_eq!(of(Udp, Established, "1.1.1.1:853"), None);
+        assert_eq!(of(Tcp, Listen, "0.0.0.0:53"), None);
+        assert_eq!(of(Tcp, Established, "93.184.216.34:443"), None);
+    }
+
+    #[test]
+    fn test_client_and_rate() {
+        let start = SystemTime::UNIX_EPOCH;
+        let conn = connection(
+            Protocol::Tcp6,
+            ConnectionState::Established,
+            "[2606:4700::1111]:853",
+        );
+        let mut client = DnsClient::of(&conn, start).unwrap();
+        assert_eq!(client.process, "firefox(2300)");
+        assert_eq!(client.resolver, "2606:4700::1111");
+        assert_eq!(client.transport.to_string(), "DoT");
+
+        client.queries = 30;
+        // Short sessions are averaged over a minute
+        assert_eq!(
+            client.queries_per_minute(start + Duration::from_secs(10)),
+            30.0
+        );
+        assert_eq!(
+            client.queries_per_minute(start + Duration::from_secs(600)),
+            3.0
+        );
+    }
+}
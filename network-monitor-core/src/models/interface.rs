@@ -0,0 +1,18 @@
+use serde::{Deserialize, Serialize};
+
+/// Traffic of one network interface: cumulative counters and the per-second
+/// rates since the previous pass
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct InterfaceStats {
+    pub name: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_rate: u64,
+    pub tx_rate: u64,
+}
+
+impl InterfaceStats {
+    pub fn is_loopback(&self) -> bool {
+        self.name == "lo"
+    }
+}
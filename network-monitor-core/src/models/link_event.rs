@@ -0,0 +1,60 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::time::SystemTime;
+
+/// Interface changes the UIs keep for their events view
+pub const LINK_EVENTS_KEPT: usize = 100;
+
+/// What happened to a network interface
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LinkChange {
+    /// A new interface appeared, e.g. a VPN tunnel or a plugged-in adapter
+    Added,
+    Removed,
+    /// Carrier came up: cable plugged in or wireless network joined
+    Connected,
+    Disconnected,
+    AddressAdded(IpAddr),
+    AddressRemoved(IpAddr),
+}
+
+/// Interface change reported by the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkEvent {
+    pub at: SystemTime,
+    pub interface: String,
+    pub change: LinkChange,
+}
+
+impl LinkEvent {
+    pub fn new(interface: impl Into<String>, change: LinkChange) -> Self {
+        Self {
+            at: SystemTime::now(),
+            interface: interface.into(),
+            change,
+        }
+    }
+
+    /// Whether the interface lost connectivity, worth drawing attention to
+    pub fn is_loss(&self) -> bool {
+        matches!(
+            self.change,
+            LinkChange::Removed | LinkChange::Disconnected | LinkChange::AddressRemoved(_)
+        )
+    }
+}
+
+/// "wlan0 disconnected", "new address 192.168.1.20 on eth0"
+impl fmt::Display for LinkEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.interface;
+        match &self.change {
+            LinkChange::Added => write!(f, "{name} added"),
+            LinkChange::Removed => write!(f, "{name} removed"),
+            LinkChange::Connected => write!(f, "{name} connected"),
+            LinkChange::Disconnected => write!(f, "{name} disconnected"),
+            LinkChange::AddressAdded(ip) => write!(f, "new address {ip} on {name}"),
+            LinkChange::AddressRemoved(ip) => write!(f, "address {ip} removed from {name}"),
+        }
+    }
+}
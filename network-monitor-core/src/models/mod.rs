@@ -0,0 +1,52 @@
+pub mod application;
+pub mod audit;
+pub mod blocked;
+pub mod connection;
+pub mod country;
+pub mod diff;
+pub mod direction;
+pub mod dns;
+pub mod interface;
+pub mod link_event;
+pub mod multicast;
+pub mod netstat;
+pub mod ports;
+pub mod process;
+pub mod protocol;
+pub mod quick_stats;
+pub mod route;
+pub mod scope;
+pub mod snapshot;
+pub mod subnet;
+pub mod talkers;
+pub mod timer;
+pub mod tunnel;
+pub mod wireless;
+
+pub use application::{application_name, ApplicationTraffic, CgroupBytes};
+pub use audit::{AuditDestination, AuditProgram};
+pub use blocked::BlockedAttempt;
+pub use connection::{
+    ConnectEvent, Connection, ProcessIO, ProcessIOMap, SocketOwner, PERMISSION_DENIED,
+};
+pub use country::{centroid, CountryTraffic, LocatedHost, COUNTRY_COLUMNS};
+pub use diff::ConnectionDiff;
+pub use diff::ConnectionKey;
+pub use direction::Direction;
+pub use dns::{DnsClient, DnsTransport};
+pub use interface::InterfaceStats;
+pub use link_event::{LinkChange, LinkEvent, LINK_EVENTS_KEPT};
+pub use multicast::MulticastGroup;
+pub use netstat::{ProtocolCounters, ProtocolStats};
+pub use ports::{port_summary, port_users, EphemeralPorts};
+pub use process::{ProcessAncestor, ProcessDetails, SandboxInfo, SandboxKind};
+pub use protocol::{ConnectionState, Protocol, Quic};
+pub use quick_stats::QuickStats;
+pub use route::{Route, RoutingTable};
+pub use scope::{LanRanges, Scope, ScopeTraffic, DEFAULT_LAN_RANGES};
+pub use snapshot::{ConnectionSnapshot, TrafficTotals};
+pub use subnet::{HostNames, Subnet};
+pub use talkers::{Talker, TopTalkers};
+pub use timer::{SocketTimer, TimerKind};
+pub use tunnel::{Tunnel, TunnelKind, TunnelPeer};
+pub use wireless::{SignalQuality, WirelessLink};
@@ -0,0 +1,235 @@
+use super::Connection;
+use crate::utils::parse_endpoint;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Discovery protocols behind most multicast chatter on a LAN: name, UDP
+/// port and the groups they are sent to
+const MULTICAST_SERVICES: [(&str, u16, [IpAddr; 2]); 4] = [
+    (
+        "mDNS",
+        5353,
+        [
+            IpAddr::V4(Ipv4Addr::new(224, 0, 0, 251)),
+            IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xfb)),
+        ],
+    ),
+    (
+        "SSDP",
+        1900,
+        [
+            IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)),
+            IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xc)),
+        ],
+    ),
+    (
+        "WS-Discovery",
+        3702,
+        [
+            IpAddr::V4(Ipv4Addr::new(239, 255, 255, 250)),
+            IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 0, 0xc)),
+        ],
+    ),
+    (
+        "LLMNR",
+        5355,
+        [
+            IpAddr::V4(Ipv4Addr::new(224, 0, 0, 252)),
+            IpAddr::V6(Ipv6Addr::new(0xff02, 0, 0, 0, 0, 0, 1, 3)),
+        ],
+    ),
+];
+
+/// Protocols sent to the IPv4 broadcast address, by UDP port
+const BROADCAST_SERVICES: [(&str, u16); 4] = [
+    ("DHCP server", 67),
+    ("DHCP client", 68),
+    ("NetBIOS names", 137),
+    ("NetBIOS datagrams", 138),
+];
+
+/// Multicast group joined on an interface, from /proc/net/igmp and igmp6,
+/// or broadcasts of a protocol some process listens for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MulticastGroup {
+    /// Group address, or 255.255.255.255 for broadcasts
+    pub address: IpAddr,
+    /// None for broadcasts, received on every interface
+    pub interface: Option<String>,
+    /// Sockets and kernel users that joined the group on the interface; 0
+    /// for broadcasts
+    pub users: u32,
+    /// Protocol using the group, such as "mDNS" or "all hosts"; None for
+    /// groups joined by applications of their own
+    pub service: Option<&'static str>,
+    /// Processes with a UDP socket bound to the group or to the port of its
+    /// protocol, as "name(pid)"
+    pub processes: Vec<String>,
+}
+
+impl MulticastGroup {
+    /// Group joined on `interface` by `users` sockets
+    pub fn joined(address: IpAddr, interface: &str, users: u32) -> Self {
+        Self {
+            address,
+            interface: Some(interface.to_string()),
+            users,
+            service: service_of(address),
+            processes: Vec::new(),
+        }
+    }
+
+    pub fn is_broadcast(&self) -> bool {
+        self.interface.is_none()
+    }
+
+    /// Whether a socket bound to `local` receives what is sent to the group
+    fn receives(&self, local: std::net::SocketAddr) -> bool {
+        if self.is_broadcast() {
+            return BROADCAST_SERVICES
+                .iter()
+                .any(|&(name, port)| Some(name) == self.service && port == local.port());
+        }
+        // IPv6 sockets may receive IPv4 traffic too, not the other way round
+        let family = local.is_ipv6() || self.address.is_ipv4();
+        local.ip().to_canonical() == self.address
+            || family
+                && MULTICAST_SERVICES.iter().any(|(_, port, groups)| {
+                    *port == local.port() && groups.contains(&self.address)
+                })
+    }
+
+    /// Fill in the processes of every group from the UDP sockets among
+    /// `connections`, adding a broadcast entry for each broadcast protocol
+    /// some process listens for
+    pub fn attribute(groups: &mut Vec<MulticastGroup>, connections: &[Connection]) {
+        for conn in connections.iter().filter(|conn| conn.protocol.is_udp()) {
+            let Some(local) = parse_endpoint(&conn.local) else {
+                continue;
+            };
+            for &(name, port) in &BROADCAST_SERVICES {
+                if port == local.port() && !groups.iter().any(|g| g.service == Some(name)) {
+                    groups.push(MulticastGroup {
+                        address: Ipv4Addr::BROADCAST.into(),
+                        interface: None,
+                        users: 0,
+                        service: Some(name),
+                        processes: Vec::new(),
+                    });
+                }
+            }
+            let process = conn.get_process_display();
+            for group in groups.iter_mut().filter(|group| group.receives(local)) {
+                if !group.processes.contains(&process) {
+                    group.processes.push(process.clone());
+                }
+            }
+        }
+    }
+}
+
+/// Well-known protocol of a multicast group
+fn service_of(address: IpAddr) -> Option<&'static str> {
+    if let Some((name, _, _)) = MULTICAST_SERVICES
+        .iter()
+        .find(|(_, _, groups)| groups.contains(&address))
+    {
+        return Some(name);
+    }
+    match address {
+        IpAddr::V4(ip) => match ip.octets() {
+            [224, 0, 0, 1] => Some("all hosts"),
+            [224, 0, 0, 2] => Some("all routers"),
+            [224, 0, 0, 22] => Some("IGMP reports"),
+            _ => None,
+        },
+        IpAddr::V6(ip) => match ip.segments() {
+            [0xff01 | 0xff02, 0, 0, 0, 0, 0, 0, 1] => Some("all nodes"),
+            [0xff01 | 0xff02 | 0xff05, 0, 0, 0, 0, 0, 0, 2] => Some("all routers"),
+            [0xff02, 0, 0, 0, 0, 0, 0, 0x16] => Some("MLD reports"),
+            [0xff02, 0, 0, 0, 0, 1, 0xff00..=0xffff, _] => Some("solicited node"),
+            _ => None,
+        },
+    }
+}
+
+/// "224.0.0.251 on eth0 (mDNS)", or "255.255.255.255 (DHCP client)"
+impl fmt::Display for MulticastGroup {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.address)?;
+        if let Some(interface) = &self.interface {
+            write!(f, " on {interface}")?;
+        }
+        if let Some(service) = self.service {
+            write!(f, " ({service})")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    fn udp(local: &str, program: &str, pid: &str) -> Connection {
+        Connection::new(
+            Protocol::Udp,
+            ConnectionState::Close,
+            local,
+            "0.0.0.0:0",
+            program,
+            pid,
+            program,
+        )
+    }
+
+    #[test]
+    fn test_groups_are_named_and_attributed() {
+        let mut groups = vec![
+            MulticastGroup::joined("224.0.0.251".parse().unwrap(), "eth0", 1),
+            MulticastGroup::joined("ff02::fb".parse().unwrap(), "eth0", 1),
+            MulticastGroup::joined("239.255.255.250".parse().unwrap(), "eth0", 2),
+            MulticastGroup::joined("ff02::1:ff00:2".parse().unwrap(), "eth0", 1),
+            MulticastGroup::joined("239.1.2.3".parse().unwrap(), "eth0", 1),
+        ];
+        let connections = vec![
+            udp("0.0.0.0:5353", "avahi-daemon", "812"),
+            udp("[::]:5353", "avahi-daemon", "812"),
+            udp("239.255.255.250:1900", "minissdpd", "900"),
+            udp("0.0.0.0:68", "dhclient", "700"),
+            udp("0.0.0.0:40000", "spotify", "1500"),
+        ];
+        MulticastGroup::attribute(&mut groups, &connections);
+
+        let shown: Vec<(String, Vec<String>)> = groups
+            .iter()
+            .map(|group| (group.to_string(), group.processes.clone()))
+            .collect();
+        let owned = |processes: &[&str]| processes.iter().map(|p| p.to_string()).collect();
+        assert_eq!(
+            shown,
+            [
+                (
+                    "224.0.0.251 on eth0 (mDNS)".into(),
+                    owned(&["avahi-daemon(812)"])
+                ),
+                (
+                    "ff02::fb on eth0 (mDNS)".into(),
+                    owned(&["avahi-daemon(812)"])
+                ),
+                (
+                    "239.255.255.250 on eth0 (SSDP)".into(),
+                    owned(&["minissdpd(900)"])
+                ),
+                ("ff02::1:ff00:2 on eth0 (solicited node)".into(), owned(&[])),
+                ("239.1.2.3 on eth0".into(), owned(&[])),
+                (
+                    "255.255.255.255 (DHCP client)".into(),
+                    owned(&["dhclient(700)"])
+                ),
+            ]
+        );
+        assert!(groups[5].is_broadcast());
+    }
+}
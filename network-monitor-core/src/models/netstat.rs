@@ -0,0 +1,125 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// Share of retransmitted segments from which [`ProtocolStats::is_degraded`]
+pub const RETRANSMIT_WARNING: f64 = 0.02;
+
+/// Kernel-wide TCP and UDP counters since boot, from /proc/net/snmp and
+/// /proc/net/netstat
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProtocolCounters {
+    /// TCP segments sent, retransmissions included
+    pub segments_sent: u64,
+    pub retransmits: u64,
+    /// TCP connections reset from ESTABLISHED or CLOSE_WAIT
+    pub resets: u64,
+    /// TCP and UDP packets dropped on receive, e.g. for bad checksums or
+    /// full socket buffers
+    pub errors: u64,
+    /// Retransmission timeouts; 0 without /proc/net/netstat
+    pub timeouts: u64,
+}
+
+impl ProtocolCounters {
+    /// Counters from `Table.Field` values as parsed by
+    /// [`parse_snmp`](crate::utils::parse_snmp); None without the Tcp table
+    pub fn from_tables(tables: &HashMap<String, u64>) -> Option<Self> {
+        let value = |key: &str| tables.get(key).copied().unwrap_or(0);
+        Some(Self {
+            segments_sent: *tables.get("Tcp.OutSegs")?,
+            retransmits: value("Tcp.RetransSegs"),
+            resets: value("Tcp.EstabResets"),
+            errors: value("Tcp.InErrs") + value("Udp.InErrors"),
+            timeouts: value("TcpExt.TCPTimeouts"),
+        })
+    }
+}
+
+/// Protocol counters with their per-second rates since the previous pass
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ProtocolStats {
+    pub totals: ProtocolCounters,
+    pub retransmits_per_sec: f64,
+    pub resets_per_sec: f64,
+    pub errors_per_sec: f64,
+    /// Share of the segments sent since the previous pass that were
+    /// retransmissions
+    pub retransmit_ratio: f64,
+}
+
+impl ProtocolStats {
+    /// Stats of `totals`, with rates over the `elapsed` time since the
+    /// `previous` counters; none on the first pass
+    pub fn new(totals: ProtocolCounters, previous: Option<(ProtocolCounters, Duration)>) -> Self {
+        let Some((previous, elapsed)) = previous else {
+            return Self {
+                totals,
+                ..Self::default()
+            };
+        };
+        let seconds = elapsed.as_secs_f64().max(0.001);
+        // A counter going backwards, e.g. after wrapping, counts as no change
+        let delta = |current: u64, previous: u64| current.saturating_sub(previous) as f64;
+        let retransmits = delta(totals.retransmits, previous.retransmits);
+        let sent = delta(totals.segments_sent, previous.segments_sent);
+        Self {
+            totals,
+            retransmits_per_sec: retransmits / seconds,
+            resets_per_sec: delta(totals.resets, previous.resets) / seconds,
+            errors_per_sec: delta(totals.errors, previous.errors) / seconds,
+            retransmit_ratio: if sent > 0.0 { retransmits / sent } else { 0.0 },
+        }
+    }
+
+    /// Whether recent retransmissions point at loss or congestion
+    pub fn is_degraded(&self) -> bool {
+        self.retransmit_ratio >= RETRANSMIT_WARNING
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(segments_sent: u64, retransmits: u64, resets: u64) -> ProtocolCounters {
+        ProtocolCounters {
+            segments_sent,
+            retransmits,
+            resets,
+            ..ProtocolCounters::default()
+        }
+    }
+
+    #[test]
+    fn test_rates_between_passes() {
+        let first = ProtocolStats::new(counters(1000, 10, 1), None);
+        assert_eq!(first.retransmits_per_sec, 0.0);
+        assert!(!first.is_degraded());
+
+        let previous = Some((counters(1000, 10, 1), Duration::from_secs(2)));
+        let stats = ProtocolStats::new(counters(1200, 20, 5), previous);
+        assert_eq!(stats.retransmits_per_sec, 5.0);
+        assert_eq!(stats.resets_per_sec, 2.0);
+        assert_eq!(stats.retransmit_ratio, 0.05);
+        assert!(stats.is_degraded());
+
+        // Nothing sent since the previous pass
+        let previous = Some((counters(1200, 20, 5), Duration::from_secs(2)));
+        let idle = ProtocolStats::new(counters(1200, 20, 5), previous);
+        assert_eq!(idle.retransmit_ratio, 0.0);
+    }
+
+    #[test]
+    fn test_counters_need_the_tcp_table() {
+        let mut tables = HashMap::new();
+        tables.insert("Udp.InErrors".to_string(), 3);
+        assert_eq!(ProtocolCounters::from_tables(&tables), None);
+        tables.insert("Tcp.OutSegs".to_string(), 500);
+        tables.insert("Tcp.InErrs".to_string(), 2);
+        let counters = ProtocolCounters::from_tables(&tables).unwrap();
+        assert_eq!(counters.segments_sent, 500);
+        assert_eq!(counters.errors, 5);
+        assert_eq!(counters.timeouts, 0);
+    }
+}
@@ -0,0 +1,210 @@
+use super::{Connection, ConnectionState, Protocol};
+use std::collections::HashSet;
+use std::ops::RangeInclusive;
+
+/// Linux's default net.ipv4.ip_local_port_range, assumed when it can't be read
+pub const DEFAULT_LOCAL_PORT_RANGE: RangeInclusive<u16> = 32768..=60999;
+/// Share of the range in use from which [`EphemeralPorts::is_near_exhaustion`]
+pub const EXHAUSTION_WARNING: f64 = 0.8;
+
+/// Local ports of the ephemeral range taken by TCP and UDP sockets. TCP and
+/// UDP have separate port spaces; connecting fails with EADDRNOTAVAIL once
+/// one of them runs out.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EphemeralPorts {
+    /// Ports the kernel picks from, net.ipv4.ip_local_port_range
+    pub range: RangeInclusive<u16>,
+    pub tcp: usize,
+    pub udp: usize,
+}
+
+impl EphemeralPorts {
+    /// Distinct local ports within `range` used by `connections`, not
+    /// counting listeners
+    pub fn from_connections(connections: &[Connection], range: RangeInclusive<u16>) -> Self {
+        let mut tcp = HashSet::new();
+        let mut udp = HashSet::new();
+        for conn in connections {
+            if conn.state == ConnectionState::Listen {
+                continue;
+            }
+            let Some(port) = conn.local_port().filter(|port| range.contains(port)) else {
+                continue;
+            };
+            match conn.protocol {
+                Protocol::Tcp | Protocol::Tcp6 => tcp.insert(port),
+                Protocol::Udp | Protocol::Udp6 => udp.insert(port),
+                // Port spaces of their own, rarely short of ports
+                _ => false,
+            };
+        }
+        Self {
+            range,
+            tcp: tcp.len(),
+            udp: udp.len(),
+        }
+    }
+
+    /// Number of ports in the range
+    pub fn capacity(&self) -> usize {
+        self.range.clone().len()
+    }
+
+    /// Ports taken in the fuller of the TCP and UDP port spaces
+    pub fn in_use(&self) -> usize {
+        self.tcp.max(self.udp)
+    }
+
+    /// Share of the range in use, from 0 to 1
+    pub fn usage(&self) -> f64 {
+        match self.capacity() {
+            0 => 0.0,
+            capacity => (self.in_use() as f64 / capacity as f64).min(1.0),
+        }
+    }
+
+    /// At least [`EXHAUSTION_WARNING`] of the range is taken
+    pub fn is_near_exhaustion(&self) -> bool {
+        self.usage() >= EXHAUSTION_WARNING
+    }
+}
+
+impl Default for EphemeralPorts {
+    fn default() -> Self {
+        Self {
+            range: DEFAULT_LOCAL_PORT_RANGE,
+            tcp: 0,
+            udp: 0,
+        }
+    }
+}
+
+/// Port of an "address:port" endpoint
+/// Sockets on local `port`, one per process and protocol with listeners
+/// first: who holds the port when binding it fails with EADDRINUSE
+pub fn port_users(connections: &[Connection], port: u16) -> Vec<&Connection> {
+    let mut users: Vec<&Connection> = connections
+        .iter()
+        .filter(|conn| conn.local_port() == Some(port))
+        .collect();
+    users.sort_by(|a, b| {
+        (b.state == ConnectionState::Listen)
+            .cmp(&(a.state == ConnectionState::Listen))
+            .then_with(|| a.program.cmp(&b.program))
+            .then_with(|| a.pid.cmp(&b.pid))
+            .then_with(|| a.protocol.cmp(&b.protocol))
+    });
+    let mut seen = HashSet::new();
+    users.retain(|conn| seen.insert((&conn.program, &conn.pid, conn.protocol)));
+    users
+}
+
+/// "Who is using port X?" answered in one line, e.g. "Port 8080: nginx(812)
+/// tcp LISTEN"
+pub fn port_summary(connections: &[Connection], port: u16) -> String {
+    let users = port_users(connections, port);
+    if users.is_empty() {
+        return format!("Port {port}: not in use");
+    }
+    let users: Vec<String> = users
+        .iter()
+        .map(|conn| {
+            format!(
+                "{} {} {}",
+                conn.get_process_display(),
+                conn.protocol,
+                conn.state
+            )
+        })
+        .collect();
+    format!("Port {port}: {}", users.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn connection(protocol: Protocol, state: ConnectionState, local: &str) -> Connection {
+        Connection::new(protocol, state, local, "10.0.0.1:80", "ab", "7", "ab")
+    }
+
+    #[test]
+    fn test_counts_distinct_ports_in_range_per_protocol() {
+        let connections = [
+            connection(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "10.0.0.2:40000",
+            ),
+            // Same local port to another destination
+            connection(Protocol::Tcp6, ConnectionState::TimeWait, "::1:40000"),
+            connection(Protocol::Tcp, ConnectionState::SynSent, "10.0.0.2:40001"),
+            connection(Protocol::Tcp, ConnectionState::Listen, "0.0.0.0:40002"),
+            connection(Protocol::Tcp, ConnectionState::Established, "10.0.0.2:22"),
+            connection(Protocol::Udp, ConnectionState::Close, "0.0.0.0:40000"),
+        ];
+        let ports = EphemeralPorts::from_connections(&connections, 40000..=40009);
+        assert_eq!((ports.tcp, ports.udp), (2, 1));
+        assert_eq!(ports.capacity(), 10);
+        assert_eq!(ports.in_use(), 2);
+        assert!((ports.usage() - 0.2).abs() < f64::EPSILON);
+        assert!(!ports.is_near_exhaustion());
+
+        let busy = EphemeralPorts {
+            tcp: 8,
+            ..ports.clone()
+        };
+        assert!(busy.is_near_exhaustion());
+        assert_eq!(EphemeralPorts::default().capacity(), 28232);
+    }
+
+    #[test]
+    fn test_port_users_list_listeners_first_once_per_process() {
+        let process = |program: &str, pid: &str, state, local: &str| {
+            Connection::new(
+                Protocol::Tcp,
+                state,
+                local,
+                "0.0.0.0:0",
+                program,
+                pid,
+                program,
+            )
+        };
+        let connections = [
+            process("curl", "300", ConnectionState::Established, "10.0.0.2:8080"),
+            process("nginx", "812", ConnectionState::Listen, "0.0.0.0:8080"),
+            process(
+                "nginx",
+                "812",
+                ConnectionState::Established,
+                "10.0.0.2:8080",
+            ),
+            process("nginx", "812", ConnectionState::Listen, "10.0.0.2:18080"),
+        ];
+        let users: Vec<_> = port_users(&connections, 8080)
+            .iter()
+            .map(|conn| (&*conn.program, conn.state))
+            .collect();
+        assert_eq!(
+            users,
+            [
+                ("nginx", ConnectionState::Listen),
+                ("curl", ConnectionState::Established)
+            ]
+        );
+        assert_eq!(
+            port_summary(&connections, 8080),
+            "Port 8080: nginx(812) tcp LISTEN, curl(300) tcp ESTABLISHED"
+        );
+        assert_eq!(port_summary(&connections, 9090), "Port 9090: not in use");
+
+        // The same sockets through a filter
+        let filtered: Vec<_> = connections
+            .iter()
+            .filter(|conn| conn.matches_filter("port=8080"))
+            .collect();
+        assert_eq!(filtered.len(), 3);
+        assert!(!connections[0].matches_filter("port=80"));
+    }
+}
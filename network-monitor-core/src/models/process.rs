@@ -0,0 +1,88 @@
+use serde::{Deserialize, Serialize};
+
+/// Application sandbox a process runs in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SandboxKind {
+    Flatpak,
+    Snap,
+}
+
+impl std::fmt::Display for SandboxKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SandboxKind::Flatpak => write!(f, "Flatpak"),
+            SandboxKind::Snap => write!(f, "Snap"),
+        }
+    }
+}
+
+/// Sandboxed application identity (e.g. Flatpak `org.mozilla.firefox`)
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SandboxInfo {
+    pub kind: SandboxKind,
+    pub app_id: String,
+}
+
+/// A process further up the parent chain
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessAncestor {
+    pub pid: String,
+    pub name: String,
+}
+
+/// Extra information about the process owning a connection, shown in the detail view
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessDetails {
+    pub pid: String,
+    pub name: String,
+    pub command: String,
+    pub sandbox: Option<SandboxInfo>,
+    /// Parent chain, nearest parent first
+    pub parents: Vec<ProcessAncestor>,
+    /// Working directory, None when /proc/[pid]/cwd isn't readable
+    pub cwd: Option<String>,
+    /// Selected environment variables hinting at the container, project or session
+    pub environment: Option<Vec<(String, String)>>,
+}
+
+impl ProcessDetails {
+    /// Human readable sandbox status
+    pub fn sandbox_display(&self) -> String {
+        match &self.sandbox {
+            Some(sandbox) => format!("{} · {}", sandbox.kind, sandbox.app_id),
+            None => "Not sandboxed".to_string(),
+        }
+    }
+
+    /// Working directory, or why it is missing
+    pub fn cwd_display(&self) -> String {
+        self.cwd
+            .clone()
+            .unwrap_or_else(|| "Not readable (permission denied)".to_string())
+    }
+
+    /// Environment hints as `KEY=value` pairs
+    pub fn environment_display(&self) -> String {
+        match &self.environment {
+            Some(vars) if vars.is_empty() => "No container or project hints".to_string(),
+            Some(vars) => vars
+                .iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(", "),
+            None => "Not readable (permission denied)".to_string(),
+        }
+    }
+
+    /// Parent chain from the oldest ancestor down to this process,
+    /// e.g. `systemd (1) → chrome (812) → chrome (905)`
+    pub fn parent_chain_display(&self) -> String {
+        self.parents
+            .iter()
+            .rev()
+            .map(|parent| format!("{} ({})", parent.name, parent.pid))
+            .chain(std::iter::once(format!("{} ({})", self.name, self.pid)))
+            .collect::<Vec<_>>()
+            .join(" → ")
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+
+/// Socket table a connection was read from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Protocol {
+    Tcp,
+    Tcp6,
+    Udp,
+    Udp6,
+    Sctp,
+    Sctp6,
+    Dccp,
+    Dccp6,
+}
+
+impl Protocol {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Protocol::Tcp => "tcp",
+            Protocol::Tcp6 => "tcp6",
+            Protocol::Udp => "udp",
+            Protocol::Udp6 => "udp6",
+            Protocol::Sctp => "sctp",
+            Protocol::Sctp6 => "sctp6",
+            Protocol::Dccp => "dccp",
+            Protocol::Dccp6 => "dccp6",
+        }
+    }
+
+    pub fn is_tcp(self) -> bool {
+        matches!(self, Protocol::Tcp | Protocol::Tcp6)
+    }
+
+    pub fn is_udp(self) -> bool {
+        matches!(self, Protocol::Udp | Protocol::Udp6)
+    }
+
+    pub fn is_ipv6(self) -> bool {
+        matches!(
+            self,
+            Protocol::Tcp6 | Protocol::Udp6 | Protocol::Sctp6 | Protocol::Dccp6
+        )
+    }
+
+    /// Transport without the address family, as firewall logs report it
+    pub fn transport(self) -> &'static str {
+        match self {
+            Protocol::Tcp | Protocol::Tcp6 => "tcp",
+            Protocol::Udp | Protocol::Udp6 => "udp",
+            Protocol::Sctp | Protocol::Sctp6 => "sctp",
+            Protocol::Dccp | Protocol::Dccp6 => "dccp",
+        }
+    }
+}
+
+impl std::fmt::Display for Protocol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Whether a UDP flow carries QUIC, the transport of HTTP/3
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quic {
+    /// UDP to or from port 443, which is QUIC nearly always
+    Likely,
+    /// Packet capture saw a QUIC Initial packet
+    Confirmed,
+}
+
+impl Quic {
+    pub fn label(self) -> &'static str {
+        match self {
+            Quic::Likely => "QUIC?",
+            Quic::Confirmed => "QUIC",
+        }
+    }
+}
+
+/// Kernel socket state (include/net/tcp_states.h), ordered by state code
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ConnectionState {
+    Established,
+    SynSent,
+    SynRecv,
+    FinWait1,
+    FinWait2,
+    TimeWait,
+    Close,
+    CloseWait,
+    LastAck,
+    Listen,
+    Closing,
+    NewSynRecv,
+    /// State code this version doesn't know about
+    Unknown(u8),
+}
+
+impl ConnectionState {
+    /// Map the hex `st` column of /proc/net/{tcp,udp}
+    pub fn from_code(code: u8) -> Self {
+        match code {
+            0x01 => ConnectionState::Established,
+            0x02 => ConnectionState::SynSent,
+            0x03 => ConnectionState::SynRecv,
+            0x04 => ConnectionState::FinWait1,
+            0x05 => ConnectionState::FinWait2,
+            0x06 => ConnectionState::TimeWait,
+            0x07 => ConnectionState::Close,
+            0x08 => ConnectionState::CloseWait,
+            0x09 => ConnectionState::LastAck,
+            0x0A => ConnectionState::Listen,
+            0x0B => ConnectionState::Closing,
+            0x0C => ConnectionState::NewSynRecv,
+            other => ConnectionState::Unknown(other),
+        }
+    }
+
+    /// Map the `ST` column of /proc/net/sctp/assocs, an association state
+    /// (include/net/sctp/constants.h), to its closest TCP equivalent
+    pub fn from_sctp_code(code: u8) -> Self {
+        match code {
+            0 => ConnectionState::Close,
+            // COOKIE_WAIT and COOKIE_ECHOED: the four-way handshake
+            1 | 2 => ConnectionState::SynSent,
+            3 => ConnectionState::Established,
+            // SHUTDOWN_PENDING: waiting for outstanding data to be acked
+            4 => ConnectionState::FinWait1,
+            5 => ConnectionState::FinWait2,
+            6 => ConnectionState::CloseWait,
+            7 => ConnectionState::LastAck,
+            other => ConnectionState::Unknown(other),
+        }
+    }
+}
+
+impl std::fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            ConnectionState::Established => "ESTABLISHED",
+            ConnectionState::SynSent => "SYN_SENT",
+            ConnectionState::SynRecv => "SYN_RECV",
+            ConnectionState::FinWait1 => "FIN_WAIT1",
+            ConnectionState::FinWait2 => "FIN_WAIT2",
+            ConnectionState::TimeWait => "TIME_WAIT",
+            ConnectionState::Close => "CLOSE",
+            ConnectionState::CloseWait => "CLOSE_WAIT",
+            ConnectionState::LastAck => "LAST_ACK",
+            ConnectionState::Listen => "LISTEN",
+            ConnectionState::Closing => "CLOSING",
+            ConnectionState::NewSynRecv => "NEW_SYN_RECV",
+            ConnectionState::Unknown(code) => return write!(f, "UNKNOWN({code})"),
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_protocol_display_and_transport() {
+        assert_eq!(Protocol::Tcp6.to_string(), "tcp6");
+        assert_eq!(Protocol::Udp6.transport(), "udp");
+        assert!(Protocol::Tcp.is_tcp());
+        assert!(!Protocol::Udp.is_tcp());
+        assert_eq!(Protocol::Sctp6.transport(), "sctp");
+        assert!(Protocol::Dccp6.is_ipv6());
+        assert!(!Protocol::Sctp.is_udp());
+    }
+
+    #[test]
+    fn test_state_from_code() {
+        assert_eq!(ConnectionState::from_code(0x0A), ConnectionState::Listen);
+        assert_eq!(ConnectionState::from_code(0x01).to_string(), "ESTABLISHED");
+        assert_eq!(ConnectionState::from_code(0xFF).to_string(), "UNKNOWN(255)");
+        assert_eq!(
+            ConnectionState::from_sctp_code(3),
+            ConnectionState::Established
+        );
+        assert_eq!(ConnectionState::from_sctp_code(1), ConnectionState::SynSent);
+    }
+
+    #[test]
+    fn test_serde_uses_kernel_names() {
+        assert_eq!(
+            serde_json::to_string(&ConnectionState::FinWait1).unwrap(),
+            "\"FIN_WAIT1\""
+        );
+        assert_eq!(
+            serde_json::from_str::<Protocol>("\"tcp6\"").unwrap(),
+            Protocol::Tcp6
+        );
+    }
+}
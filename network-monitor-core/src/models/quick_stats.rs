@@ -0,0 +1,81 @@
+use super::{ConnectionSnapshot, Talker};
+use serde::Serialize;
+
+/// Headline numbers of a snapshot for panel applets and status bars: the
+/// combined rates of all connections, session totals and connection counts
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize)]
+pub struct QuickStats {
+    /// Bytes per second
+    pub tx_rate: u64,
+    pub rx_rate: u64,
+    /// Bytes since the monitor started
+    pub sent: u64,
+    pub received: u64,
+    pub connections: usize,
+    /// Connections with traffic right now
+    pub active: usize,
+}
+
+impl QuickStats {
+    /// Of every connection of `snapshot`, whatever the UI filters out
+    pub fn of(snapshot: &ConnectionSnapshot) -> Self {
+        let rates = Talker::total(&snapshot.connections);
+        Self {
+            tx_rate: rates.tx_rate,
+            rx_rate: rates.rx_rate,
+            sent: snapshot.totals.sent,
+            received: snapshot.totals.received,
+            connections: snapshot.connections.len(),
+            active: snapshot
+                .connections
+                .iter()
+                .filter(|conn| conn.is_active())
+                .count(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Connection, ConnectionState, Protocol, TrafficTotals};
+
+    #[test]
+    fn test_stats_add_up_the_snapshot() {
+        let connection = |local: &str, rx_rate| {
+            let mut conn = Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                local,
+                "93.184.216.34:443",
+                "curl",
+                local.rsplit(':').next().unwrap(),
+                "curl",
+            );
+            conn.rx_rate = rx_rate;
+            conn
+        };
+        let totals = TrafficTotals {
+            sent: 10,
+            received: 4096,
+        };
+        let snapshot = ConnectionSnapshot::new(
+            vec![
+                connection("192.168.1.2:50000", 2048),
+                connection("192.168.1.2:50001", 0),
+            ],
+            totals,
+        );
+        assert_eq!(
+            QuickStats::of(&snapshot),
+            QuickStats {
+                tx_rate: 0,
+                rx_rate: 2048,
+                sent: 10,
+                received: 4096,
+                connections: 2,
+                active: 1,
+            }
+        );
+    }
+}
@@ -0,0 +1,175 @@
+use super::Connection;
+use crate::utils::parse_endpoint;
+use std::fmt;
+use std::net::IpAddr;
+
+/// One entry of the kernel's main routing table
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Route {
+    pub destination: IpAddr,
+    pub prefix_len: u8,
+    /// Next hop; None for directly connected networks
+    pub gateway: Option<IpAddr>,
+    pub interface: String,
+    pub metric: u32,
+}
+
+impl Route {
+    /// Whether this is a default route, matching every address of its family
+    pub fn is_default(&self) -> bool {
+        self.prefix_len == 0
+    }
+
+    /// Whether `ip` falls in the destination network
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.destination, ip) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+/// In the style of `ip route`: "default via 192.168.1.1 dev eth0 metric 100"
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_default() {
+            f.write_str("default")?;
+        } else {
+            write!(f, "{}/{}", self.destination, self.prefix_len)?;
+        }
+        if let Some(gateway) = self.gateway {
+            write!(f, " via {gateway}")?;
+        }
+        write!(f, " dev {}", self.interface)?;
+        if self.metric > 0 {
+            write!(f, " metric {}", self.metric)?;
+        }
+        Ok(())
+    }
+}
+
+/// IPv4 and IPv6 routes of the main table, in kernel order
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RoutingTable {
+    pub routes: Vec<Route>,
+}
+
+impl RoutingTable {
+    pub fn new(routes: Vec<Route>) -> Self {
+        Self { routes }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Route the kernel picks for `ip`: the longest matching prefix, then the
+    /// lowest metric. Policy rules and the local table aren't considered.
+    pub fn lookup(&self, ip: IpAddr) -> Option<&Route> {
+        let ip = ip.to_canonical();
+        self.routes
+            .iter()
+            .filter(|route| route.contains(ip))
+            .min_by_key(|route| (std::cmp::Reverse(route.prefix_len), route.metric))
+    }
+
+    /// Route taken by `conn` to its remote address; None for loopback
+    /// traffic, which never leaves the machine, and for listening sockets
+    pub fn route_for(&self, conn: &Connection) -> Option<&Route> {
+        let remote = parse_endpoint(&conn.remote)?.ip();
+        if remote.is_loopback() || remote.is_unspecified() {
+            return None;
+        }
+        self.lookup(remote)
+    }
+
+    /// Default routes, lowest metric first within each family
+    pub fn default_routes(&self) -> impl Iterator<Item = &Route> {
+        let mut defaults: Vec<&Route> = self.routes.iter().filter(|r| r.is_default()).collect();
+        defaults.sort_by_key(|route| (route.destination.is_ipv6(), route.metric));
+        defaults.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    fn route(destination: &str, prefix_len: u8, gateway: Option<&str>, iface: &str) -> Route {
+        Route {
+            destination: destination.parse().unwrap(),
+            prefix_len,
+            gateway: gateway.map(|gateway| gateway.parse().unwrap()),
+            interface: iface.to_string(),
+            metric: 100,
+        }
+    }
+
+    #[test]
+    fn test_longest_prefix_wins() {
+        let table = RoutingTable::new(vec![
+            route("0.0.0.0", 0, Some("192.168.1.1"), "eth0"),
+            route("192.168.1.0", 24, None, "eth0"),
+            route("10.8.0.0", 16, None, "wg0"),
+            route("10.8.4.0", 24, Some("10.8.0.1"), "wg0"),
+            route("::", 0, Some("fe80::1"), "eth0"),
+        ]);
+        let via = |ip: &str| {
+            table
+                .lookup(ip.parse().unwrap())
+                .map(|route| route.to_string())
+        };
+        assert_eq!(
+            via("93.184.216.34").as_deref(),
+            Some("default via 192.168.1.1 dev eth0 metric 100")
+        );
+        assert_eq!(
+            via("192.168.1.20").as_deref(),
+            Some("192.168.1.0/24 dev eth0 metric 100")
+        );
+        assert_eq!(
+            via("10.8.4.7").as_deref(),
+            Some("10.8.4.0/24 via 10.8.0.1 dev wg0 metric 100")
+        );
+        assert_eq!(
+            via("::ffff:10.8.9.9").as_deref(),
+            Some("10.8.0.0/16 dev wg0 metric 100")
+        );
+        assert_eq!(
+            via("2001:db8::1").as_deref(),
+            Some("default via fe80::1 dev eth0 metric 100")
+        );
+        assert_eq!(table.default_routes().count(), 2);
+    }
+
+    #[test]
+    fn test_route_for_connection() {
+        let table = RoutingTable::new(vec![route("0.0.0.0", 0, Some("192.168.1.1"), "eth0")]);
+        let conn = |remote: &str| {
+            Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "192.168.1.20:51000",
+                remote,
+                "curl",
+                "100",
+                "curl",
+            )
+        };
+        assert!(table.route_for(&conn("93.184.216.34:443")).is_some());
+        assert!(table.route_for(&conn("127.0.0.1:8080")).is_none());
+        assert!(table.route_for(&conn("0.0.0.0:*")).is_none());
+    }
+}
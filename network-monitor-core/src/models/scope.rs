@@ -0,0 +1,207 @@
+use super::{Connection, RoutingTable, Subnet, TrafficTotals};
+use crate::utils::parse_endpoint;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::net::IpAddr;
+
+/// Private, link-local, unique local and loopback ranges, which are LAN
+/// unless configured otherwise
+pub const DEFAULT_LAN_RANGES: [&str; 8] = [
+    "10.0.0.0/8",
+    "172.16.0.0/12",
+    "192.168.0.0/16",
+    "169.254.0.0/16",
+    "127.0.0.0/8",
+    "fc00::/7",
+    "fe80::/10",
+    "::1/128",
+];
+
+/// Where the remote end of a connection is, as far as traffic leaving the
+/// machine goes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// On a local network: a private range or one the machine is directly
+    /// connected to
+    Lan,
+    /// Out on the internet
+    Wan,
+    /// Routed through a VPN tunnel
+    Vpn,
+}
+
+impl Scope {
+    pub const ALL: [Scope; 3] = [Scope::Lan, Scope::Wan, Scope::Vpn];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Scope::Lan => "lan",
+            Scope::Wan => "wan",
+            Scope::Vpn => "vpn",
+        }
+    }
+
+    /// Upper case name, for table cells and the status bar
+    pub fn label(self) -> &'static str {
+        match self {
+            Scope::Lan => "LAN",
+            Scope::Wan => "WAN",
+            Scope::Vpn => "VPN",
+        }
+    }
+
+    /// Set the scope of each connection. Tunnelled connections are VPN, those
+    /// to an address in `lan` or on a network directly connected to a local
+    /// interface are LAN and the rest WAN. Sockets without a peer get none.
+    pub fn mark(connections: &mut [Connection], lan: &LanRanges, routes: &RoutingTable) {
+        for conn in connections {
+            conn.scope = Self::of(conn, lan, routes);
+        }
+    }
+
+    fn of(conn: &Connection, lan: &LanRanges, routes: &RoutingTable) -> Option<Self> {
+        let remote = parse_endpoint(&conn.remote)?.ip().to_canonical();
+        if remote.is_unspecified() {
+            return None;
+        }
+        if conn.tunnel.is_some() {
+            return Some(Scope::Vpn);
+        }
+        let on_link = routes
+            .lookup(remote)
+            .is_some_and(|route| route.gateway.is_none() && !route.is_default());
+        Some(if on_link || lan.contains(remote) {
+            Scope::Lan
+        } else {
+            Scope::Wan
+        })
+    }
+}
+
+/// "lan", "wan" or "vpn"
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Address ranges counted as LAN
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LanRanges {
+    subnets: Vec<Subnet>,
+}
+
+impl LanRanges {
+    /// The [`DEFAULT_LAN_RANGES`] and `extra` ones, such as a CGNAT range
+    /// used by a mesh VPN
+    pub fn with(extra: impl IntoIterator<Item = Subnet>) -> Self {
+        let mut ranges = Self::default();
+        ranges.subnets.extend(extra);
+        ranges
+    }
+
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        self.subnets.iter().any(|subnet| subnet.contains(ip))
+    }
+}
+
+impl Default for LanRanges {
+    fn default() -> Self {
+        Self {
+            subnets: DEFAULT_LAN_RANGES
+                .iter()
+                .filter_map(|range| range.parse().ok())
+                .collect(),
+        }
+    }
+}
+
+/// Current rates and session totals of the connections in one scope
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ScopeTraffic {
+    pub scope: Scope,
+    pub tx_rate: u64,
+    pub rx_rate: u64,
+    pub totals: TrafficTotals,
+}
+
+impl ScopeTraffic {
+    pub fn new(scope: Scope) -> Self {
+        Self {
+            scope,
+            tx_rate: 0,
+            rx_rate: 0,
+            totals: TrafficTotals::default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol, Route};
+
+    fn connection(remote: &str) -> Connection {
+        Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "192.168.1.2:50000",
+            remote,
+            "ssh",
+            "100",
+            "ssh",
+        )
+    }
+
+    #[test]
+    fn test_connections_are_classified() {
+        let routes = RoutingTable::new(vec![
+            Route {
+                destination: "0.0.0.0".parse().unwrap(),
+                prefix_len: 0,
+                gateway: Some("192.168.1.1".parse().unwrap()),
+                interface: "eth0".into(),
+                metric: 100,
+            },
+            Route {
+                destination: "100.64.20.0".parse().unwrap(),
+                prefix_len: 24,
+                gateway: None,
+                interface: "eth1".into(),
+                metric: 0,
+            },
+        ]);
+        let mut tunnelled = connection("10.8.0.1:22");
+        tunnelled.tunnel = Some("wg0".into());
+        let mut connections = vec![
+            connection("192.168.1.20:22"),
+            connection("[fe80::1]:22"),
+            connection("100.64.20.7:22"),
+            connection("100.64.30.7:22"),
+            connection("93.184.216.34:443"),
+            tunnelled,
+            connection("0.0.0.0:0"),
+        ];
+        Scope::mark(&mut connections, &LanRanges::default(), &routes);
+        let scopes: Vec<Option<Scope>> = connections.iter().map(|conn| conn.scope).collect();
+        assert_eq!(
+            scopes,
+            [
+                Some(Scope::Lan),
+                Some(Scope::Lan),
+                Some(Scope::Lan),
+                Some(Scope::Wan),
+                Some(Scope::Wan),
+                Some(Scope::Vpn),
+                None
+            ]
+        );
+
+        // Extra ranges count as LAN too
+        let lan = LanRanges::with(["100.64.0.0/10".parse().unwrap()]);
+        Scope::mark(&mut connections, &lan, &RoutingTable::default());
+        assert_eq!(connections[3].scope, Some(Scope::Lan));
+        assert_eq!(connections[4].scope, Some(Scope::Wan));
+    }
+}
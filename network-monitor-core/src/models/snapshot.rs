@@ -0,0 +1,254 @@
+use super::{
+    ApplicationTraffic, Connection, ConnectionDiff, ConnectionKey, CountryTraffic, Direction,
+    DnsClient, EphemeralPorts, InterfaceStats, MulticastGroup, ProtocolStats, Quic, RoutingTable,
+    ScopeTraffic, TopTalkers, Tunnel, WirelessLink,
+};
+use crate::utils::CacheStats;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+
+/// Bytes transferred so far, by the monitored processes or interfaces
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrafficTotals {
+    pub sent: u64,
+    pub received: u64,
+}
+
+/// One completed collection pass, as handed from the collector to the UIs:
+/// connections with rates, interface traffic and totals at `taken_at`
+#[derive(Debug, Clone)]
+pub struct ConnectionSnapshot {
+    /// Wall-clock time of the pass
+    pub taken_at: SystemTime,
+    pub connections: Vec<Connection>,
+    /// Per-interface traffic, from backends that track it (router)
+    pub interface_stats: Vec<InterfaceStats>,
+    pub totals: TrafficTotals,
+    /// Use of the ephemeral port range, from backends that see local sockets
+    pub ephemeral_ports: Option<EphemeralPorts>,
+    /// Kernel-wide retransmissions, resets and errors, from backends that
+    /// read /proc/net
+    pub protocol_stats: Option<ProtocolStats>,
+    /// WireGuard and TUN/TAP interfaces, from backends that see local ones
+    pub tunnels: Vec<Tunnel>,
+    /// Main routing table, from backends that read /proc/net
+    pub routes: RoutingTable,
+    /// Multicast groups joined per interface and broadcast protocols
+    /// listened for, with the processes behind them, from backends that read
+    /// /proc/net
+    pub multicast: Vec<MulticastGroup>,
+    /// Wi-Fi client interfaces, from backends watching the local machine
+    pub wireless: Vec<WirelessLink>,
+    /// Changes relative to the previous snapshot
+    pub diff: ConnectionDiff,
+    /// How long the collection pass took
+    pub collection_time: Duration,
+    /// Size of the socket owner cache after the pass
+    pub process_cache: CacheStats,
+    /// When each connection last appeared, changed state or had traffic;
+    /// filled in by the collector thread
+    pub last_activity: HashMap<ConnectionKey, SystemTime>,
+    /// Processes querying resolvers over the session, busiest first; filled
+    /// in by the collector thread
+    pub dns_clients: Vec<DnsClient>,
+    /// Traffic per remote country over the session, most first; filled in
+    /// by the collector thread once a GeoIP database is loaded
+    pub countries: Vec<CountryTraffic>,
+    /// Traffic to the LAN, the internet and VPNs, in that order; filled in
+    /// by the collector thread
+    pub scopes: Vec<ScopeTraffic>,
+    /// Traffic by application from cgroup accounting, busiest first; empty
+    /// without the privileges to attach it
+    pub applications: Vec<ApplicationTraffic>,
+}
+
+impl ConnectionSnapshot {
+    /// Snapshot of `connections` taken now, with the direction of each
+    /// worked out from the listening sockets among them and UDP flows on
+    /// the HTTPS port labeled as likely QUIC
+    pub fn new(mut connections: Vec<Connection>, totals: TrafficTotals) -> Self {
+        Direction::mark(&mut connections);
+        for conn in connections.iter_mut().filter(|conn| conn.looks_like_quic()) {
+            conn.quic.get_or_insert(Quic::Likely);
+        }
+        Self {
+            taken_at: SystemTime::now(),
+            connections,
+            totals,
+            ..Self::default()
+        }
+    }
+
+    /// Busiest `limit` processes, remote hosts and applications of this pass
+    pub fn top_talkers(&self, limit: usize) -> TopTalkers {
+        TopTalkers::from_connections(&self.connections, limit)
+            .with_applications(&self.applications, limit)
+    }
+
+    /// Number of TCP connections using each congestion control algorithm,
+    /// most used first; empty when the backend can't tell
+    pub fn congestion_controls(&self) -> Vec<(Arc<str>, usize)> {
+        let mut counts: HashMap<&Arc<str>, usize> = HashMap::new();
+        for algorithm in self
+            .connections
+            .iter()
+            .filter_map(|conn| conn.congestion.as_ref())
+        {
+            *counts.entry(algorithm).or_default() += 1;
+        }
+        let mut counts: Vec<(Arc<str>, usize)> = counts
+            .into_iter()
+            .map(|(algorithm, count)| (algorithm.clone(), count))
+            .collect();
+        counts.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        counts
+    }
+
+    /// How long `conn` had gone without activity at this pass, zero when
+    /// it isn't tracked
+    pub fn idle_for(&self, conn: &Connection) -> Duration {
+        self.last_activity
+            .get(&conn.key())
+            .and_then(|since| self.taken_at.duration_since(*since).ok())
+            .unwrap_or_default()
+    }
+
+    /// Connections idle for at least `after`; none when `after` is zero
+    pub fn idle_connections(&self, after: Duration) -> HashSet<ConnectionKey> {
+        if after.is_zero() {
+            return HashSet::new();
+        }
+        self.last_activity
+            .iter()
+            .filter(|(_, since)| {
+                self.taken_at
+                    .duration_since(**since)
+                    .is_ok_and(|idle| idle >= after)
+            })
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+
+    /// Time since the pass, zero if the clock went backwards
+    pub fn age(&self) -> Duration {
+        self.taken_at.elapsed().unwrap_or_default()
+    }
+}
+
+impl Default for ConnectionSnapshot {
+    /// Empty snapshot standing in before the first pass, taken at the epoch
+    fn default() -> Self {
+        Self {
+            taken_at: SystemTime::UNIX_EPOCH,
+            connections: Vec::new(),
+            interface_stats: Vec::new(),
+            totals: TrafficTotals::default(),
+            ephemeral_ports: None,
+            protocol_stats: None,
+            tunnels: Vec::new(),
+            routes: RoutingTable::default(),
+            multicast: Vec::new(),
+            wireless: Vec::new(),
+            diff: ConnectionDiff::default(),
+            collection_time: Duration::ZERO,
+            process_cache: CacheStats::default(),
+            last_activity: HashMap::new(),
+            dns_clients: Vec::new(),
+            countries: Vec::new(),
+            scopes: Vec::new(),
+            applications: Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new_snapshot_is_taken_now() {
+        let totals = TrafficTotals {
+            sent: 10,
+            received: 20,
+        };
+        let snapshot = ConnectionSnapshot::new(Vec::new(), totals);
+        assert!(snapshot.age() < Duration::from_secs(5));
+        assert_eq!(snapshot.totals, totals);
+        assert!(ConnectionSnapshot::default().age() > Duration::from_secs(3600));
+    }
+
+    #[test]
+    fn test_congestion_controls_are_counted() {
+        use crate::models::{ConnectionState, Protocol};
+
+        let connections = ["cubic", "bbr", "cubic", ""]
+            .into_iter()
+            .enumerate()
+            .map(|(port, algorithm)| {
+                let mut conn = Connection::new(
+                    Protocol::Tcp,
+                    ConnectionState::Established,
+                    format!("10.0.0.2:{}", 40000 + port),
+                    "93.184.216.34:443",
+                    "curl",
+                    "100",
+                    "curl",
+                );
+                conn.congestion = (!algorithm.is_empty()).then(|| algorithm.into());
+                conn
+            })
+            .collect();
+        let snapshot = ConnectionSnapshot::new(connections, TrafficTotals::default());
+        assert_eq!(
+            snapshot.congestion_controls(),
+            vec![("cubic".into(), 2), ("bbr".into(), 1)]
+        );
+    }
+
+    #[test]
+    fn test_udp_on_https_port_is_likely_quic() {
+        use crate::models::{ConnectionState, Protocol};
+
+        let udp = |protocol, local: &str, remote: &str| {
+            Connection::new(
+                protocol,
+                ConnectionState::Established,
+                local,
+                remote,
+                "firefox",
+                "2300",
+                "firefox",
+            )
+        };
+        let mut confirmed = udp(Protocol::Udp, "10.0.0.2:40001", "142.250.74.3:443");
+        confirmed.quic = Some(Quic::Confirmed);
+        let connections = vec![
+            udp(Protocol::Udp, "10.0.0.2:40000", "142.250.74.3:443"),
+            udp(Protocol::Udp6, "2001:db8::2:443", "2001:db8::9:50000"),
+            confirmed,
+            udp(Protocol::Tcp, "10.0.0.2:51000", "93.184.216.34:443"),
+            udp(Protocol::Udp, "0.0.0.0:443", "0.0.0.0:0"),
+            udp(Protocol::Udp, "10.0.0.2:40002", "9.9.9.9:53"),
+        ];
+        let snapshot = ConnectionSnapshot::new(connections, TrafficTotals::default());
+        let labels: Vec<String> = snapshot
+            .connections
+            .iter()
+            .map(Connection::protocol_label)
+            .collect();
+        assert_eq!(
+            labels,
+            [
+                "udp (QUIC?)",
+                "udp6 (QUIC?)",
+                "udp (QUIC)",
+                "tcp",
+                "udp",
+                "udp"
+            ]
+        );
+        assert!(snapshot.connections[0].matches_filter("quic"));
+    }
+}
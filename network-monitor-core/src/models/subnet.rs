@@ -0,0 +1,129 @@
+use std::fmt;
+use std::net::IpAddr;
+use std::str::FromStr;
+
+/// Network written as "ADDRESS/PREFIX", or a single host as a bare address
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subnet {
+    pub network: IpAddr,
+    pub prefix_len: u8,
+}
+
+impl Subnet {
+    /// Whether `ip` falls in this network; IPv4-mapped IPv6 addresses count
+    /// as IPv4
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        match (self.network, ip.to_canonical()) {
+            (IpAddr::V4(network), IpAddr::V4(ip)) => {
+                let mask = u32::MAX
+                    .checked_shl(32 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u32::from(network) & mask == u32::from(ip) & mask
+            }
+            (IpAddr::V6(network), IpAddr::V6(ip)) => {
+                let mask = u128::MAX
+                    .checked_shl(128 - u32::from(self.prefix_len))
+                    .unwrap_or(0);
+                u128::from(network) & mask == u128::from(ip) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for Subnet {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let (address, prefix_len) = text.split_once('/').unwrap_or((text, ""));
+        let network: IpAddr = address
+            .parse()
+            .map_err(|_| format!("'{text}' is not an address or ADDRESS/PREFIX"))?;
+        let max = if network.is_ipv4() { 32 } else { 128 };
+        let prefix_len = match prefix_len {
+            "" => max,
+            prefix_len => prefix_len
+                .parse()
+                .ok()
+                .filter(|&prefix_len| prefix_len <= max)
+                .ok_or_else(|| format!("invalid prefix length in '{text}' (0-{max})"))?,
+        };
+        Ok(Self {
+            network,
+            prefix_len,
+        })
+    }
+}
+
+impl fmt::Display for Subnet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.network, self.prefix_len)
+    }
+}
+
+/// Friendly names for addresses and networks, e.g. 192.168.1.10 → "NAS",
+/// shown instead of the address and ahead of its DNS name
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HostNames {
+    /// Most specific network first, so hosts win over their subnet
+    names: Vec<(Subnet, String)>,
+}
+
+impl HostNames {
+    pub fn new(names: impl IntoIterator<Item = (Subnet, String)>) -> Self {
+        let mut names: Vec<(Subnet, String)> = names.into_iter().collect();
+        names.sort_by_key(|(subnet, _)| std::cmp::Reverse(subnet.prefix_len));
+        Self { names }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Name of the most specific entry containing `ip`
+    pub fn name_of(&self, ip: IpAddr) -> Option<&str> {
+        self.names
+            .iter()
+            .find(|(subnet, _)| subnet.contains(ip))
+            .map(|(_, name)| name.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ip(text: &str) -> IpAddr {
+        text.parse().unwrap()
+    }
+
+    #[test]
+    fn test_subnets_parse_and_contain() {
+        let lan: Subnet = "192.168.1.0/24".parse().unwrap();
+        assert!(lan.contains(ip("192.168.1.77")));
+        assert!(lan.contains(ip("::ffff:192.168.1.77")));
+        assert!(!lan.contains(ip("192.168.2.1")));
+        let host: Subnet = "2001:db8::1".parse().unwrap();
+        assert_eq!(host.to_string(), "2001:db8::1/128");
+        assert!(host.contains(ip("2001:db8::1")));
+        assert!(!host.contains(ip("2001:db8::2")));
+        assert!("0.0.0.0/0"
+            .parse::<Subnet>()
+            .unwrap()
+            .contains(ip("8.8.8.8")));
+        assert!("10.0.0.0/33".parse::<Subnet>().is_err());
+        assert!("nas.local".parse::<Subnet>().is_err());
+    }
+
+    #[test]
+    fn test_most_specific_name_wins() {
+        let names = HostNames::new([
+            ("192.168.1.0/24".parse().unwrap(), "Home".to_string()),
+            ("192.168.1.10".parse().unwrap(), "NAS".to_string()),
+        ]);
+        assert_eq!(names.name_of(ip("192.168.1.10")), Some("NAS"));
+        assert_eq!(names.name_of(ip("192.168.1.11")), Some("Home"));
+        assert_eq!(names.name_of(ip("10.0.0.1")), None);
+    }
+}
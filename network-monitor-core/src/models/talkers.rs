@@ -0,0 +1,234 @@
+use super::{ApplicationTraffic, Connection};
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+/// A process or remote host with its current traffic
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Talker {
+    pub name: String,
+    pub tx_rate: u64,
+    pub rx_rate: u64,
+}
+
+impl Talker {
+    /// Combined upload and download rate, what talkers are ranked by
+    pub fn rate(&self) -> u64 {
+        self.tx_rate.saturating_add(self.rx_rate)
+    }
+
+    /// All of `connections` together, named "Total": like in
+    /// [`TopTalkers`], a process counts once however many sockets it has and
+    /// connections without a PID are added up
+    pub fn total(connections: &[Connection]) -> Self {
+        let mut processes: HashMap<&str, (u64, u64)> = HashMap::new();
+        let mut total = talker("Total");
+        for conn in connections {
+            if has_pid(conn) {
+                let (tx_rate, rx_rate) = processes.entry(&conn.pid).or_default();
+                *tx_rate = (*tx_rate).max(conn.tx_rate);
+                *rx_rate = (*rx_rate).max(conn.rx_rate);
+            } else {
+                total.tx_rate += conn.tx_rate;
+                total.rx_rate += conn.rx_rate;
+            }
+        }
+        for (tx_rate, rx_rate) in processes.into_values() {
+            total.tx_rate += tx_rate;
+            total.rx_rate += rx_rate;
+        }
+        total
+    }
+}
+
+/// Busiest processes, remote hosts and applications of one snapshot,
+/// highest rate first
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TopTalkers {
+    pub processes: Vec<Talker>,
+    pub hosts: Vec<Talker>,
+    /// From cgroup accounting, empty where it isn't available
+    pub applications: Vec<Talker>,
+}
+
+impl TopTalkers {
+    /// Up to `limit` processes and hosts with traffic. Sockets of a process
+    /// all carry its rate, so a process counts once and its rate is split
+    /// evenly between the hosts it talks to; connections without a PID
+    /// (router flows) have rates of their own and are added up.
+    pub fn from_connections(connections: &[Connection], limit: usize) -> Self {
+        let sockets_per_pid = sockets_per_pid(connections);
+
+        let mut processes: HashMap<String, Talker> = HashMap::new();
+        let mut hosts: HashMap<&str, Talker> = HashMap::new();
+        for conn in connections.iter().filter(|conn| conn.is_active()) {
+            let process = processes
+                .entry(conn.get_process_display())
+                .or_insert_with_key(|name| talker(name));
+            if has_pid(conn) {
+                process.tx_rate = process.tx_rate.max(conn.tx_rate);
+                process.rx_rate = process.rx_rate.max(conn.rx_rate);
+            } else {
+                process.tx_rate += conn.tx_rate;
+                process.rx_rate += conn.rx_rate;
+            }
+
+            if let Some(address) = remote_host(&conn.remote) {
+                let share = rate_share(conn, &sockets_per_pid);
+                let host = hosts.entry(address).or_insert_with(|| talker(address));
+                host.tx_rate += conn.tx_rate / share;
+                host.rx_rate += conn.rx_rate / share;
+            }
+        }
+
+        Self {
+            processes: ranked(processes.into_values(), limit),
+            hosts: ranked(hosts.into_values(), limit),
+            applications: Vec::new(),
+        }
+    }
+
+    /// Add up to `limit` of `applications` with traffic, ranked like the rest
+    pub fn with_applications(mut self, applications: &[ApplicationTraffic], limit: usize) -> Self {
+        self.applications = ranked(
+            applications.iter().map(|app| Talker {
+                name: app.name.clone(),
+                tx_rate: app.tx_rate,
+                rx_rate: app.rx_rate,
+            }),
+            limit,
+        );
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.processes.is_empty() && self.hosts.is_empty() && self.applications.is_empty()
+    }
+}
+
+fn talker(name: &str) -> Talker {
+    Talker {
+        name: name.to_string(),
+        ..Talker::default()
+    }
+}
+
+/// Number of sockets with a peer per PID, what the rate of a process is
+/// split by between its connections
+pub(crate) fn sockets_per_pid(connections: &[Connection]) -> HashMap<&str, u64> {
+    let mut sockets: HashMap<&str, u64> = HashMap::new();
+    for conn in connections {
+        if has_pid(conn) && remote_host(&conn.remote).is_some() {
+            *sockets.entry(&conn.pid).or_default() += 1;
+        }
+    }
+    sockets
+}
+
+/// What the rates of `conn` are divided by to get its own share of them:
+/// the socket count of its process, or 1 for flows with rates of their own
+pub(crate) fn rate_share(conn: &Connection, sockets_per_pid: &HashMap<&str, u64>) -> u64 {
+    if has_pid(conn) {
+        sockets_per_pid.get(&*conn.pid).copied().unwrap_or(1)
+    } else {
+        1
+    }
+}
+
+fn has_pid(conn: &Connection) -> bool {
+    &*conn.pid != "N/A"
+}
+
+/// Address part of a remote endpoint, None when there is no peer
+fn remote_host(remote: &str) -> Option<&str> {
+    let (address, _port) = remote.rsplit_once(':')?;
+    let address = address.trim_start_matches('[').trim_end_matches(']');
+    match address.parse::<IpAddr>() {
+        Ok(ip) if ip.is_unspecified() => None,
+        Ok(_) => Some(address),
+        Err(_) => (!address.is_empty() && address != "*").then_some(address),
+    }
+}
+
+/// Talkers with traffic, busiest first and by name on ties
+fn ranked(talkers: impl Iterator<Item = Talker>, limit: usize) -> Vec<Talker> {
+    let mut talkers: Vec<Talker> = talkers.filter(|talker| talker.rate() > 0).collect();
+    talkers.sort_by(|a, b| b.rate().cmp(&a.rate()).then_with(|| a.name.cmp(&b.name)));
+    talkers.truncate(limit);
+    talkers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    fn connection(program: &str, pid: &str, remote: &str, rx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            remote,
+            program,
+            pid,
+            program,
+        );
+        conn.rx_rate = rx_rate;
+        conn
+    }
+
+    #[test]
+    fn test_processes_count_once_and_split_between_hosts() {
+        let connections = [
+            connection("firefox", "100", "93.184.216.34:443", 3000),
+            connection("firefox", "100", "1.1.1.1:443", 3000),
+            connection("firefox", "100", "0.0.0.0:0", 3000),
+            connection("curl", "200", "1.1.1.1:443", 500),
+            connection("sshd", "300", "10.0.0.1:22", 0),
+        ];
+        let top = TopTalkers::from_connections(&connections, 5);
+
+        let processes: Vec<_> = top
+            .processes
+            .iter()
+            .map(|talker| (talker.name.as_str(), talker.rx_rate))
+            .collect();
+        assert_eq!(processes, [("firefox(100)", 3000), ("curl(200)", 500)]);
+
+        let hosts: Vec<_> = top
+            .hosts
+            .iter()
+            .map(|talker| (talker.name.as_str(), talker.rx_rate))
+            .collect();
+        assert_eq!(hosts, [("1.1.1.1", 2000), ("93.184.216.34", 1500)]);
+    }
+
+    #[test]
+    fn test_flows_without_pid_add_up_and_limit_applies() {
+        let connections = [
+            connection("192.168.1.10", "N/A", "[2001:db8::1]:443", 100),
+            connection("192.168.1.10", "N/A", "[2001:db8::1]:443", 50),
+            connection("192.168.1.11", "N/A", "198.51.100.7:80", 10),
+        ];
+        let top = TopTalkers::from_connections(&connections, 1);
+        assert_eq!(top.processes.len(), 1);
+        assert_eq!(top.processes[0].name, "192.168.1.10");
+        assert_eq!(top.processes[0].rate(), 150);
+        assert_eq!(top.hosts[0].name, "2001:db8::1");
+        assert!(TopTalkers::from_connections(&[], 5).is_empty());
+    }
+
+    #[test]
+    fn test_total_counts_each_process_once() {
+        let connections = [
+            connection("firefox", "100", "93.184.216.34:443", 3000),
+            connection("firefox", "100", "1.1.1.1:443", 3000),
+            connection("firefox", "100", "0.0.0.0:0", 3000),
+            connection("curl", "200", "1.1.1.1:443", 500),
+            connection("192.168.1.10", "N/A", "198.51.100.7:80", 100),
+            connection("192.168.1.11", "N/A", "198.51.100.7:80", 50),
+        ];
+        let total = Talker::total(&connections);
+        assert_eq!((total.tx_rate, total.rx_rate), (0, 3650));
+        assert_eq!(Talker::total(&[]).rate(), 0);
+    }
+}
@@ -0,0 +1,134 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+
+/// Timer pending on a TCP socket, as the `tr` column of /proc/net/tcp and
+/// `idiag_timer` of sock_diag number them
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimerKind {
+    /// Unacknowledged data will be sent again, or a loss probe
+    Retransmit,
+    /// Keepalive probe, or the SYN-ACK of a pending connection
+    Keepalive,
+    /// Lingering after the close
+    TimeWait,
+    /// Probing a peer that advertised a zero window
+    ZeroWindowProbe,
+}
+
+impl TimerKind {
+    /// Kind from its code; None for 0, no timer pending
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            1 => Some(TimerKind::Retransmit),
+            2 => Some(TimerKind::Keepalive),
+            3 => Some(TimerKind::TimeWait),
+            4 => Some(TimerKind::ZeroWindowProbe),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TimerKind::Retransmit => "retransmit",
+            TimerKind::Keepalive => "keepalive",
+            TimerKind::TimeWait => "timewait",
+            TimerKind::ZeroWindowProbe => "probe",
+        }
+    }
+}
+
+/// Pending timer of a TCP socket and how often it fired without an answer;
+/// a connection retransmitting again and again is hung on its peer
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SocketTimer {
+    pub kind: TimerKind,
+    /// Until the timer fires, in milliseconds; 0 when already overdue
+    pub expires_ms: u64,
+    /// Unanswered retransmissions, or zero window probes
+    pub retries: u32,
+}
+
+impl SocketTimer {
+    /// Timer of a /proc/net/tcp line from its `tr:tm->when` column, in
+    /// hundredths of a second, and its `retrnsmt` column; None when no
+    /// timer is pending or the columns don't parse
+    pub fn from_proc_net(timer: &str, retries: &str) -> Option<Self> {
+        let (code, when) = timer.split_once(':')?;
+        let kind = TimerKind::from_code(u8::from_str_radix(code, 16).ok()?)?;
+        let when = u64::from_str_radix(when, 16).ok()?;
+        Some(Self {
+            kind,
+            expires_ms: when.saturating_mul(10),
+            retries: u32::from_str_radix(retries, 16).unwrap_or(0),
+        })
+    }
+
+    /// Short cell text such as "retransmit 1.2s ×3"
+    pub fn label(&self) -> String {
+        let seconds = self.expires_ms as f64 / 1000.0;
+        let expires = if seconds < 10.0 {
+            format!("{seconds:.1}s")
+        } else {
+            format!("{seconds:.0}s")
+        };
+        if self.retries > 0 {
+            format!("{} {} ×{}", self.kind.as_str(), expires, self.retries)
+        } else {
+            format!("{} {}", self.kind.as_str(), expires)
+        }
+    }
+
+    /// Whether the peer stopped answering: data or zero window probes sent
+    /// again at least once
+    pub fn is_retrying(&self) -> bool {
+        self.retries > 0
+            && matches!(
+                self.kind,
+                TimerKind::Retransmit | TimerKind::ZeroWindowProbe
+            )
+    }
+}
+
+impl Ord for SocketTimer {
+    /// Most retries first when sorted descending, then by kind and expiry
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.retries
+            .cmp(&other.retries)
+            .then_with(|| self.kind.cmp(&other.kind))
+            .then_with(|| self.expires_ms.cmp(&other.expires_ms))
+    }
+}
+
+impl PartialOrd for SocketTimer {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timer_from_proc_net() {
+        let timer = SocketTimer::from_proc_net("01:00000078", "00000003").unwrap();
+        assert_eq!(
+            timer,
+            SocketTimer {
+                kind: TimerKind::Retransmit,
+                expires_ms: 1200,
+                retries: 3,
+            }
+        );
+        assert_eq!(timer.label(), "retransmit 1.2s ×3");
+        assert!(timer.is_retrying());
+
+        let keepalive = SocketTimer::from_proc_net("02:000AFC4C", "00000000").unwrap();
+        assert_eq!(keepalive.label(), "keepalive 7199s");
+        assert!(!keepalive.is_retrying());
+
+        assert_eq!(SocketTimer::from_proc_net("00:00000000", "00000000"), None);
+        assert_eq!(SocketTimer::from_proc_net("garbage", "0"), None);
+    }
+}
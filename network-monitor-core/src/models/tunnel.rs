@@ -0,0 +1,143 @@
+use super::Connection;
+use crate::utils::parse_endpoint;
+use std::fmt;
+use std::net::{IpAddr, SocketAddr};
+use std::time::{Duration, SystemTime};
+
+/// WireGuard renegotiates keys every two minutes while traffic flows, so an
+/// older handshake means the peer stopped answering (REJECT_AFTER_TIME)
+pub const HANDSHAKE_STALE_AFTER: Duration = Duration::from_secs(180);
+
+/// Kind of tunnel interface
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TunnelKind {
+    WireGuard,
+    /// TUN or TAP device of a userspace VPN such as OpenVPN
+    Tun,
+}
+
+impl fmt::Display for TunnelKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::WireGuard => "WireGuard",
+            Self::Tun => "TUN/TAP",
+        })
+    }
+}
+
+/// Remote end of a WireGuard tunnel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TunnelPeer {
+    /// Where the peer was last heard from; None until it first connects
+    pub endpoint: Option<SocketAddr>,
+    /// None before the first handshake
+    pub last_handshake: Option<SystemTime>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+}
+
+/// A VPN tunnel interface and the traffic through it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Tunnel {
+    pub name: String,
+    pub kind: TunnelKind,
+    /// Addresses of the interface; connections from them use the tunnel
+    pub addresses: Vec<IpAddr>,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    /// WireGuard peers; None for other tunnels or when they can't be read,
+    /// which needs CAP_NET_ADMIN
+    pub peers: Option<Vec<TunnelPeer>>,
+}
+
+impl Tunnel {
+    /// Tunnel `name` of `kind` with its counters, before addresses and peers
+    /// are known
+    pub fn new(name: impl Into<String>, kind: TunnelKind, rx_bytes: u64, tx_bytes: u64) -> Self {
+        Self {
+            name: name.into(),
+            kind,
+            addresses: Vec::new(),
+            rx_bytes,
+            tx_bytes,
+            peers: None,
+        }
+    }
+
+    /// Most recent handshake with any peer
+    pub fn last_handshake(&self) -> Option<SystemTime> {
+        self.peers
+            .iter()
+            .flatten()
+            .filter_map(|peer| peer.last_handshake)
+            .max()
+    }
+
+    /// Whether no peer completed a handshake lately, so nothing gets through
+    pub fn is_stale(&self, now: SystemTime) -> bool {
+        self.peers.as_ref().is_some_and(|_| {
+            self.last_handshake()
+                .and_then(|at| now.duration_since(at).ok())
+                .is_none_or(|age| age > HANDSHAKE_STALE_AFTER)
+        })
+    }
+
+    /// Whether `conn` is routed through this tunnel, going by its local
+    /// address: the kernel picks the tunnel's when routing through it
+    pub fn carries(&self, conn: &Connection) -> bool {
+        parse_endpoint(&conn.local).is_some_and(|local| self.addresses.contains(&local.ip()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    fn peer(handshake_secs: Option<u64>) -> TunnelPeer {
+        TunnelPeer {
+            endpoint: "203.0.113.5:51820".parse().ok(),
+            last_handshake: handshake_secs
+                .map(|secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs)),
+            rx_bytes: 0,
+            tx_bytes: 0,
+        }
+    }
+
+    #[test]
+    fn test_handshake_age() {
+        let now = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+        let mut tunnel = Tunnel::new("wg0", TunnelKind::WireGuard, 0, 0);
+        // Peers unknown: nothing to judge by
+        assert!(!tunnel.is_stale(now));
+
+        tunnel.peers = Some(vec![peer(None)]);
+        assert!(tunnel.is_stale(now));
+        tunnel.peers = Some(vec![peer(Some(700)), peer(Some(900))]);
+        assert_eq!(
+            tunnel.last_handshake(),
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(900))
+        );
+        assert!(!tunnel.is_stale(now));
+        assert!(tunnel.is_stale(now + HANDSHAKE_STALE_AFTER));
+    }
+
+    #[test]
+    fn test_carries_connections_from_its_address() {
+        let mut tunnel = Tunnel::new("wg0", TunnelKind::WireGuard, 0, 0);
+        tunnel.addresses = vec!["10.8.0.2".parse().unwrap()];
+        let conn = |local: &str| {
+            Connection::new(
+                Protocol::Tcp6,
+                ConnectionState::Established,
+                local,
+                "93.184.216.34:443",
+                "curl",
+                "100",
+                "curl",
+            )
+        };
+        assert!(tunnel.carries(&conn("::ffff:10.8.0.2:51000")));
+        assert!(!tunnel.carries(&conn("192.168.1.20:51000")));
+    }
+}
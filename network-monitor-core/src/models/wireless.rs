@@ -0,0 +1,124 @@
+use std::fmt;
+
+/// Signal strength of a wireless link, in the usual dBm bands
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SignalQuality {
+    Poor,
+    Fair,
+    Good,
+    Excellent,
+}
+
+impl SignalQuality {
+    pub fn from_dbm(dbm: i8) -> Self {
+        match dbm {
+            -50.. => Self::Excellent,
+            -60.. => Self::Good,
+            -70.. => Self::Fair,
+            _ => Self::Poor,
+        }
+    }
+}
+
+impl fmt::Display for SignalQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Poor => "poor",
+            Self::Fair => "fair",
+            Self::Good => "good",
+            Self::Excellent => "excellent",
+        })
+    }
+}
+
+/// Association of a wireless interface with its access point, as `iw dev
+/// IF link` shows it
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WirelessLink {
+    pub interface: String,
+    /// Network name; None while not associated
+    pub ssid: Option<String>,
+    /// Signal of the access point in dBm
+    pub signal_dbm: Option<i8>,
+    /// Transmit rate in kbit/s, what the link sends at right now
+    pub tx_bitrate: Option<u32>,
+    /// Receive rate in kbit/s
+    pub rx_bitrate: Option<u32>,
+    /// Channel frequency in MHz
+    pub frequency: Option<u32>,
+}
+
+impl WirelessLink {
+    pub fn is_connected(&self) -> bool {
+        self.ssid.is_some()
+    }
+
+    pub fn quality(&self) -> Option<SignalQuality> {
+        self.signal_dbm.map(SignalQuality::from_dbm)
+    }
+
+    /// "2.4 GHz", "5 GHz" or "6 GHz"
+    pub fn band(&self) -> Option<&'static str> {
+        match self.frequency? {
+            2400..=2500 => Some("2.4 GHz"),
+            4900..=5900 => Some("5 GHz"),
+            5925..=7125 => Some("6 GHz"),
+            _ => None,
+        }
+    }
+}
+
+/// `"HomeNet" -58 dBm (good) 866.7 Mbit/s 5 GHz`, or "not connected"
+impl fmt::Display for WirelessLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Some(ssid) = &self.ssid else {
+            return f.write_str("not connected");
+        };
+        write!(f, "\"{ssid}\"")?;
+        if let (Some(dbm), Some(quality)) = (self.signal_dbm, self.quality()) {
+            write!(f, " {dbm} dBm ({quality})")?;
+        }
+        if let Some(rate) = self.tx_bitrate {
+            write!(f, " {:.1} Mbit/s", f64::from(rate) / 1000.0)?;
+        }
+        if let Some(band) = self.band() {
+            write!(f, " {band}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_summary() {
+        let mut link = WirelessLink {
+            interface: "wlan0".to_string(),
+            ..WirelessLink::default()
+        };
+        assert_eq!(link.to_string(), "not connected");
+
+        link.ssid = Some("HomeNet".to_string());
+        link.signal_dbm = Some(-58);
+        link.tx_bitrate = Some(866_700);
+        link.frequency = Some(5180);
+        assert_eq!(
+            link.to_string(),
+            "\"HomeNet\" -58 dBm (good) 866.7 Mbit/s 5 GHz"
+        );
+        link.frequency = Some(2437);
+        assert_eq!(link.band(), Some("2.4 GHz"));
+        link.frequency = Some(5955);
+        assert_eq!(link.band(), Some("6 GHz"));
+    }
+
+    #[test]
+    fn test_signal_bands() {
+        assert_eq!(SignalQuality::from_dbm(-40), SignalQuality::Excellent);
+        assert_eq!(SignalQuality::from_dbm(-50), SignalQuality::Excellent);
+        assert_eq!(SignalQuality::from_dbm(-65), SignalQuality::Fair);
+        assert_eq!(SignalQuality::from_dbm(-82), SignalQuality::Poor);
+    }
+}
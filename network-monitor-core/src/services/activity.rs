@@ -0,0 +1,103 @@
+use crate::models::{ConnectionKey, ConnectionSnapshot, ConnectionState};
+use std::collections::HashMap;
+use std::time::SystemTime;
+
+/// Remembers when each connection last showed activity: when it appeared,
+/// changed state or moved traffic. Rates belong to the owning process, so a
+/// socket stays active while its process transfers anything.
+#[derive(Debug, Default)]
+pub struct ActivityTracker {
+    seen: HashMap<ConnectionKey, (ConnectionState, SystemTime)>,
+}
+
+impl ActivityTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Fill in `snapshot.last_activity` from what changed since the previous
+    /// snapshot. Connections that are gone are forgotten.
+    pub fn record(&mut self, snapshot: &mut ConnectionSnapshot) {
+        let mut seen = HashMap::with_capacity(snapshot.connections.len());
+        for conn in &snapshot.connections {
+            let key = conn.key();
+            let last_active = match self.seen.get(&key) {
+                Some((state, since)) if *state == conn.state && !conn.is_active() => *since,
+                _ => snapshot.taken_at,
+            };
+            // Sockets sharing a key count as active when any of them is
+            seen.entry(key)
+                .and_modify(|(_, since): &mut (ConnectionState, SystemTime)| {
+                    *since = (*since).max(last_active)
+                })
+                .or_insert((conn.state, last_active));
+        }
+        snapshot.last_activity = seen
+            .iter()
+            .map(|(key, (_, since))| (key.clone(), *since))
+            .collect();
+        self.seen = seen;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Connection, Protocol, TrafficTotals};
+    use std::time::Duration;
+
+    fn snapshot_at(secs: u64, connections: Vec<Connection>) -> ConnectionSnapshot {
+        ConnectionSnapshot {
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            ..ConnectionSnapshot::new(connections, TrafficTotals::default())
+        }
+    }
+
+    fn connection(local: &str, state: ConnectionState, rx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            state,
+            local,
+            "10.0.0.1:443",
+            "curl",
+            "42",
+            "curl",
+        );
+        conn.rx_rate = rx_rate;
+        conn
+    }
+
+    #[test]
+    fn test_state_changes_and_traffic_count_as_activity() {
+        let mut tracker = ActivityTracker::new();
+        let idle = |rx_rate| connection("10.0.0.2:5000", ConnectionState::Established, rx_rate);
+        let closing = connection("10.0.0.2:5000", ConnectionState::CloseWait, 0);
+
+        let mut first = snapshot_at(100, vec![idle(0)]);
+        tracker.record(&mut first);
+        assert_eq!(first.idle_for(&idle(0)), Duration::ZERO);
+
+        let mut quiet = snapshot_at(400, vec![idle(0)]);
+        tracker.record(&mut quiet);
+        assert_eq!(quiet.idle_for(&idle(0)), Duration::from_secs(300));
+        assert!(quiet
+            .idle_connections(Duration::from_secs(300))
+            .contains(&idle(0).key()));
+        assert!(quiet.idle_connections(Duration::ZERO).is_empty());
+
+        let mut busy = snapshot_at(500, vec![idle(10)]);
+        tracker.record(&mut busy);
+        assert_eq!(busy.idle_for(&idle(10)), Duration::ZERO);
+
+        let mut changed = snapshot_at(900, vec![closing.clone()]);
+        tracker.record(&mut changed);
+        assert_eq!(changed.idle_for(&closing), Duration::ZERO);
+
+        // Gone and back again starts over
+        let mut gone = snapshot_at(950, Vec::new());
+        tracker.record(&mut gone);
+        let mut back = snapshot_at(2000, vec![closing.clone()]);
+        tracker.record(&mut back);
+        assert_eq!(back.idle_for(&closing), Duration::ZERO);
+    }
+}
@@ -0,0 +1,111 @@
+use crate::models::{AuditDestination, AuditProgram, Connection, ConnectionKey};
+use std::collections::{BTreeMap, HashSet};
+use std::net::IpAddr;
+
+type Program = (String, Option<String>);
+type Endpoint = (&'static str, IpAddr, u16);
+
+/// Gathers every program that connects out to another host over a
+/// monitoring period and where to, to bootstrap firewall allowlists with
+#[derive(Debug, Default)]
+pub struct OutboundAudit {
+    programs: BTreeMap<Program, BTreeMap<Endpoint, AuditDestination>>,
+    /// Sockets of the previous pass, so each one is counted once
+    previous: HashSet<ConnectionKey>,
+}
+
+impl OutboundAudit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add the outbound connections of one collection pass
+    pub fn record(&mut self, connections: &[Connection]) {
+        let mut current = HashSet::with_capacity(connections.len());
+        for conn in connections {
+            let Some(seen) = AuditDestination::of(conn) else {
+                continue;
+            };
+            let dest = self
+                .programs
+                .entry(AuditProgram::identity(conn))
+                .or_default()
+                .entry((seen.protocol, seen.address, seen.port))
+                .or_insert_with(|| seen.clone());
+            if dest.server_name.is_none() {
+                dest.server_name = seen.server_name;
+            }
+            let key = conn.key();
+            if !self.previous.contains(&key) {
+                dest.connections += 1;
+            }
+            current.insert(key);
+        }
+        self.previous = current;
+    }
+
+    /// Programs by name, each with its busiest destinations first
+    pub fn report(&self) -> Vec<AuditProgram> {
+        self.programs
+            .iter()
+            .map(|((program, path), destinations)| {
+                let mut destinations: Vec<AuditDestination> =
+                    destinations.values().cloned().collect();
+                destinations.sort_by_key(|dest| std::cmp::Reverse(dest.connections));
+                AuditProgram {
+                    program: program.clone(),
+                    path: path.clone(),
+                    destinations,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Direction, Protocol};
+
+    fn outbound(program: &str, local_port: u16, remote: &str) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            format!("192.168.1.2:{local_port}"),
+            remote,
+            program,
+            "100",
+            format!("/usr/bin/{program}"),
+        );
+        conn.direction = Some(Direction::Outbound);
+        conn
+    }
+
+    #[test]
+    fn test_sockets_are_counted_once_per_destination() {
+        let mut audit = OutboundAudit::new();
+        let first = outbound("apt", 50000, "151.101.2.132:80");
+        audit.record(&[first.clone(), outbound("curl", 50001, "93.184.216.34:443")]);
+        // Still open, plus a second socket to the same mirror
+        audit.record(&[first, outbound("apt", 50002, "151.101.2.132:80")]);
+        audit.record(&[outbound("apt", 50003, "151.101.66.132:80")]);
+
+        let report = audit.report();
+        let programs: Vec<&str> = report.iter().map(|p| p.program.as_str()).collect();
+        assert_eq!(programs, ["apt", "curl"]);
+        assert_eq!(report[0].path.as_deref(), Some("/usr/bin/apt"));
+        let apt: Vec<(String, u64)> = report[0]
+            .destinations
+            .iter()
+            .map(|dest| (dest.address.to_string(), dest.connections))
+            .collect();
+        assert_eq!(
+            apt,
+            [
+                ("151.101.2.132".to_string(), 2),
+                ("151.101.66.132".to_string(), 1)
+            ]
+        );
+        assert_eq!(report[0].connections(), 3);
+    }
+}
@@ -0,0 +1,579 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, ConnectionSnapshot, InterfaceStats, Protocol, TrafficTotals};
+use crate::services::conntrack::{self, ConntrackFlow};
+use crate::services::packages::PACKAGES_LIST;
+use crate::services::remote::{CombinedCollector, SshCollector, DEFAULT_REMOTE_COMMAND};
+use crate::services::{
+    Attribution, CacheLimits, NetworkService, PackageNames, RateTracker, WarmStart,
+};
+use crate::utils::{CircuitBreaker, EnhancedErrorRecovery};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// Source of connection snapshots, driven by the collector thread. Tests can
+/// drive one directly, e.g. a [`FixtureCollector`] for deterministic data.
+pub trait Collector {
+    /// Gather the current connections with their rates
+    fn collect(&mut self) -> Result<ConnectionSnapshot>;
+
+    /// Map other users' sockets to processes through the privileged helper
+    fn enable_privileged_helper(&mut self) -> Result<()> {
+        Err(NetworkMonitorError::PrivilegedHelper(
+            "not used by this backend".to_string(),
+        ))
+    }
+
+    /// What the next session can start from; None for backends that don't
+    /// gain from it
+    fn warm_state(&self) -> Option<WarmStart> {
+        None
+    }
+
+    /// Pick up from what a previous session saved
+    fn warm_start(&mut self, _state: WarmStart) {}
+}
+
+/// Which collector to run, chosen at startup
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// Socket tables from /proc/net
+    #[cfg_attr(not(any(target_os = "macos", target_os = "android")), default)]
+    Procfs,
+    /// Socket dumps through netlink sock_diag
+    Netlink,
+    /// Socket descriptors of every process through libproc (macOS)
+    #[cfg_attr(target_os = "macos", default)]
+    Libproc,
+    /// Socket tables from /proc/net owned by app packages instead of
+    /// processes, for Android
+    #[cfg_attr(target_os = "android", default)]
+    Android,
+    /// Forwarded flows from conntrack and interface rates, no processes
+    Router,
+    /// Connections replayed from a JSON file
+    Fixture(PathBuf),
+    /// Another machine's connections from `command` run there over SSH, see
+    /// [`SshCollector`]
+    Ssh {
+        destination: String,
+        command: String,
+    },
+    /// Several machines at once, by name, see [`CombinedCollector`]
+    Combined(Vec<(String, Backend)>),
+}
+
+impl Backend {
+    /// Whether socket changes on this backend are noticed between refreshes,
+    /// see [`CollectorEvent::SocketsChanged`](crate::services::CollectorEvent)
+    pub fn watches_sockets(&self) -> bool {
+        cfg!(target_os = "linux")
+            && matches!(self, Backend::Procfs | Backend::Netlink | Backend::Android)
+    }
+
+    pub fn open(&self, limits: &CacheLimits) -> Result<Box<dyn Collector>> {
+        Ok(match self {
+            Backend::Procfs => Box::new(ProcfsCollector::new(limits)),
+            Backend::Netlink => Box::new(NetlinkCollector::new(limits)),
+            #[cfg(target_os = "macos")]
+            Backend::Libproc => Box::new(LibprocCollector::new()),
+            #[cfg(not(target_os = "macos"))]
+            Backend::Libproc => {
+                return Err(NetworkMonitorError::Libproc(
+                    "the libproc backend needs macOS".to_string(),
+                ))
+            }
+            Backend::Android => Box::new(AndroidCollector::new(limits)),
+            Backend::Router => Box::new(RouterCollector::new()),
+            Backend::Fixture(path) => Box::new(FixtureCollector::load(path)?),
+            Backend::Ssh {
+                destination,
+                command,
+            } => Box::new(SshCollector::new(destination, command)),
+            Backend::Combined(hosts) => Box::new(CombinedCollector::new(
+                hosts
+                    .iter()
+                    .map(|(name, backend)| Ok((name.clone(), backend.open(limits)?)))
+                    .collect::<Result<_>>()?,
+            )),
+        })
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    /// "procfs", "netlink", "libproc", "android", "router", "fixture:PATH" or
+    /// "ssh:DESTINATION", which runs [`DEFAULT_REMOTE_COMMAND`] there
+    fn from_str(backend: &str) -> std::result::Result<Self, Self::Err> {
+        match backend.trim() {
+            "procfs" => Ok(Backend::Procfs),
+            "netlink" => Ok(Backend::Netlink),
+            "libproc" => Ok(Backend::Libproc),
+            "android" => Ok(Backend::Android),
+            "router" => Ok(Backend::Router),
+            other => match (other.strip_prefix("fixture:"), other.strip_prefix("ssh:")) {
+                (Some(path), _) if !path.is_empty() => Ok(Backend::Fixture(PathBuf::from(path))),
+                (_, Some(destination)) if !destination.is_empty() => Ok(Backend::Ssh {
+                    destination: destination.to_string(),
+                    command: DEFAULT_REMOTE_COMMAND.to_string(),
+                }),
+                _ => Err(format!(
+                    "unknown backend '{other}' (use procfs, netlink, libproc, android, router, fixture:PATH or ssh:HOST)"
+                )),
+            },
+        }
+    }
+}
+
+impl fmt::Display for Backend {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Backend::Procfs => f.write_str("procfs"),
+            Backend::Netlink => f.write_str("netlink"),
+            Backend::Libproc => f.write_str("libproc"),
+            Backend::Android => f.write_str("android"),
+            Backend::Router => f.write_str("router"),
+            Backend::Fixture(path) => write!(f, "fixture:{}", path.display()),
+            Backend::Ssh { destination, .. } => write!(f, "ssh:{destination}"),
+            Backend::Combined(hosts) => {
+                let names: Vec<&str> = hosts.iter().map(|(name, _)| name.as_str()).collect();
+                write!(f, "combined:{}", names.join(","))
+            }
+        }
+    }
+}
+
+/// Reads /proc/net/{tcp,udp}{,6} and maps sockets to processes through /proc
+pub struct ProcfsCollector {
+    service: NetworkService,
+    rates: RateTracker,
+}
+
+impl ProcfsCollector {
+    pub fn new(limits: &CacheLimits) -> Self {
+        Self::with_service(NetworkService::new(), limits)
+    }
+
+    /// Collector reading a recorded /proc tree at `proc_root`
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>, limits: &CacheLimits) -> Self {
+        Self::with_service(NetworkService::with_proc_root(proc_root), limits)
+    }
+
+    fn with_service(service: NetworkService, limits: &CacheLimits) -> Self {
+        service.set_cache_limits(limits);
+        Self {
+            service,
+            rates: RateTracker::new(),
+        }
+    }
+}
+
+impl Collector for ProcfsCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let connections = self.service.get_connections()?;
+        self.rates.snapshot(&self.service, connections)
+    }
+
+    fn enable_privileged_helper(&mut self) -> Result<()> {
+        self.service.enable_privileged_helper()
+    }
+
+    fn warm_state(&self) -> Option<WarmStart> {
+        Some(self.rates.warm_state(&self.service))
+    }
+
+    fn warm_start(&mut self, state: WarmStart) {
+        self.rates.warm_start(&self.service, state);
+    }
+}
+
+/// Lists sockets through netlink sock_diag, like `ss`, and maps them to
+/// processes through /proc. Reads the /proc/net tables instead while
+/// sock_diag keeps failing.
+pub struct NetlinkCollector {
+    service: NetworkService,
+    rates: RateTracker,
+    netlink: CircuitBreaker,
+}
+
+impl NetlinkCollector {
+    pub fn new(limits: &CacheLimits) -> Self {
+        let service = NetworkService::new();
+        service.set_cache_limits(limits);
+        Self {
+            service,
+            rates: RateTracker::new(),
+            netlink: CircuitBreaker::default(),
+        }
+    }
+}
+
+impl Collector for NetlinkCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let service = &self.service;
+        let connections = EnhancedErrorRecovery::graceful_degradation(
+            || {
+                self.netlink
+                    .call(|| service.get_connections_netlink())
+                    .map(Ok)
+            },
+            || service.get_connections(),
+        );
+        self.rates.snapshot(&self.service, connections?)
+    }
+
+    fn enable_privileged_helper(&mut self) -> Result<()> {
+        self.service.enable_privileged_helper()
+    }
+
+    fn warm_state(&self) -> Option<WarmStart> {
+        Some(self.rates.warm_state(&self.service))
+    }
+
+    fn warm_start(&mut self, state: WarmStart) {
+        self.rates.warm_start(&self.service, state);
+    }
+}
+
+/// Reads /proc/net like [`ProcfsCollector`] but never walks /proc/*/fd, which
+/// Android closes to apps. Sockets are attributed to the app package of their
+/// UID when /data/system/packages.list is readable (rooted devices) and to
+/// the bare UID otherwise; without process I/O counters there are no rates.
+pub struct AndroidCollector {
+    service: NetworkService,
+    rates: RateTracker,
+}
+
+impl AndroidCollector {
+    pub fn new(limits: &CacheLimits) -> Self {
+        let packages = PackageNames::load(Path::new(PACKAGES_LIST)).unwrap_or_else(|e| {
+            tracing::info!("App package names unavailable: {e}");
+            PackageNames::default()
+        });
+        Self::with_packages(NetworkService::new(), packages, limits)
+    }
+
+    /// Collector reading a recorded /proc tree at `proc_root`
+    pub fn with_proc_root(
+        proc_root: impl Into<PathBuf>,
+        packages: PackageNames,
+        limits: &CacheLimits,
+    ) -> Self {
+        Self::with_packages(NetworkService::with_proc_root(proc_root), packages, limits)
+    }
+
+    fn with_packages(
+        mut service: NetworkService,
+        packages: PackageNames,
+        limits: &CacheLimits,
+    ) -> Self {
+        service.set_cache_limits(limits);
+        service.set_attribution(Attribution::Packages(packages));
+        Self {
+            service,
+            rates: RateTracker::new(),
+        }
+    }
+}
+
+impl Collector for AndroidCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let connections = self.service.get_connections()?;
+        self.rates.snapshot(&self.service, connections)
+    }
+}
+
+/// For routers and gateways: lists the flows conntrack tracks, including
+/// forwarded ones, instead of local sockets and never looks for processes.
+/// Each flow is shown under the host that started it, with rates from the
+/// conntrack byte counters (net.netfilter.nf_conntrack_acct=1); the totals
+/// and per-interface rates come from /proc/net/dev.
+pub struct RouterCollector {
+    proc_root: PathBuf,
+    /// Byte counters of each flow and interface at the previous pass
+    flows: HashMap<(Protocol, SocketAddr, SocketAddr), (u64, u64)>,
+    interfaces: HashMap<String, (u64, u64)>,
+    last_pass: Option<Instant>,
+}
+
+impl RouterCollector {
+    pub fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    /// Collector reading a recorded /proc tree at `proc_root`
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            proc_root: proc_root.into(),
+            flows: HashMap::new(),
+            interfaces: HashMap::new(),
+            last_pass: None,
+        }
+    }
+
+    fn flow_connection(&self, flow: &ConntrackFlow, seconds: Option<f64>) -> Connection {
+        let host = flow.source.ip().to_string();
+        let mut conn = Connection::new(
+            flow.protocol,
+            flow.state,
+            flow.source.to_string(),
+            flow.destination.to_string(),
+            host,
+            "N/A",
+            "N/A",
+        );
+        let key = (flow.protocol, flow.source, flow.destination);
+        if let (Some(seconds), Some(&(sent, received))) = (seconds, self.flows.get(&key)) {
+            conn.tx_rate = per_second(flow.sent.saturating_sub(sent), seconds);
+            conn.rx_rate = per_second(flow.received.saturating_sub(received), seconds);
+        }
+        conn
+    }
+}
+
+impl Default for RouterCollector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn per_second(bytes: u64, seconds: f64) -> u64 {
+    (bytes as f64 / seconds) as u64
+}
+
+impl Collector for RouterCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let now = Instant::now();
+        let seconds = self
+            .last_pass
+            .map(|last| now.duration_since(last).as_secs_f64().max(0.001));
+        self.last_pass = Some(now);
+
+        let flows = conntrack::read_flows(&self.proc_root).map_err(|e| {
+            NetworkMonitorError::Conntrack(format!(
+                "{}/net/nf_conntrack: {e} (is nf_conntrack loaded?)",
+                self.proc_root.display()
+            ))
+        })?;
+        let connections = flows
+            .iter()
+            .map(|flow| self.flow_connection(flow, seconds))
+            .collect();
+        self.flows = flows
+            .into_iter()
+            .map(|flow| {
+                (
+                    (flow.protocol, flow.source, flow.destination),
+                    (flow.sent, flow.received),
+                )
+            })
+            .collect();
+
+        let mut interfaces: Vec<InterfaceStats> =
+            conntrack::read_interfaces(&self.proc_root).unwrap_or_default();
+        for interface in &mut interfaces {
+            if let (Some(seconds), Some(&(rx, tx))) =
+                (seconds, self.interfaces.get(&interface.name))
+            {
+                interface.rx_rate = per_second(interface.rx_bytes.saturating_sub(rx), seconds);
+                interface.tx_rate = per_second(interface.tx_bytes.saturating_sub(tx), seconds);
+            }
+        }
+        self.interfaces = interfaces
+            .iter()
+            .map(|interface| {
+                (
+                    interface.name.clone(),
+                    (interface.rx_bytes, interface.tx_bytes),
+                )
+            })
+            .collect();
+
+        let external = interfaces
+            .iter()
+            .filter(|interface| !interface.is_loopback());
+        let totals = TrafficTotals {
+            sent: external.clone().map(|interface| interface.tx_bytes).sum(),
+            received: external.map(|interface| interface.rx_bytes).sum(),
+        };
+        let mut snapshot = ConnectionSnapshot::new(connections, totals);
+        snapshot.interface_stats = interfaces;
+        Ok(snapshot)
+    }
+}
+
+/// Lists the socket descriptors of every process through libproc on macOS.
+/// There are no per-process traffic counters there, so connections carry no
+/// rates and the totals are the interface byte counters.
+#[cfg(target_os = "macos")]
+#[derive(Debug, Default)]
+pub struct LibprocCollector;
+
+#[cfg(target_os = "macos")]
+impl LibprocCollector {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(target_os = "macos")]
+impl Collector for LibprocCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        use crate::services::libproc;
+
+        let connections = libproc::connections()?;
+        let (received, sent) = libproc::interface_totals().unwrap_or_default();
+        Ok(ConnectionSnapshot::new(
+            connections,
+            TrafficTotals { sent, received },
+        ))
+    }
+}
+
+/// Replays recorded passes in order, then keeps returning the last one.
+/// Rates come from the recording; totals add them up pass by pass.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureCollector {
+    passes: Vec<Vec<Connection>>,
+    next: usize,
+    totals: TrafficTotals,
+}
+
+impl FixtureCollector {
+    pub fn new(passes: Vec<Vec<Connection>>) -> Self {
+        Self {
+            passes,
+            ..Self::default()
+        }
+    }
+
+    /// Read a JSON array of passes, each an array of serialized [`Connection`]s
+    pub fn load(path: &Path) -> Result<Self> {
+        let invalid = |e: String| NetworkMonitorError::Fixture(format!("{}: {e}", path.display()));
+        let text = fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+        let passes = serde_json::from_str(&text).map_err(|e| invalid(e.to_string()))?;
+        Ok(Self::new(passes))
+    }
+}
+
+impl Collector for FixtureCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let connections = match self.passes.get(self.next).or(self.passes.last()) {
+            Some(pass) => pass.clone(),
+            None => Vec::new(),
+        };
+        self.next += 1;
+        self.totals.sent += connections.iter().map(|conn| conn.tx_rate).sum::<u64>();
+        self.totals.received += connections.iter().map(|conn| conn.rx_rate).sum::<u64>();
+        Ok(ConnectionSnapshot::new(connections, self.totals))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Direction, Protocol};
+
+    fn connection(program: &str, rx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            "93.184.216.34:443",
+            program,
+            "1200",
+            program,
+        );
+        conn.rx_rate = rx_rate;
+        conn
+    }
+
+    #[test]
+    fn test_fixture_replays_then_repeats_last_pass() {
+        let mut collector = FixtureCollector::new(vec![
+            vec![connection("firefox", 100)],
+            vec![connection("firefox", 50), connection("curl", 10)],
+        ]);
+        assert_eq!(collector.collect().unwrap().connections.len(), 1);
+        let second = collector.collect().unwrap();
+        assert_eq!(second.connections[1].program.as_ref(), "curl");
+        assert_eq!(second.totals.received, 160);
+        assert_eq!(collector.collect().unwrap().connections.len(), 2);
+        assert!(collector.enable_privileged_helper().is_err());
+
+        assert!(FixtureCollector::default()
+            .collect()
+            .unwrap()
+            .connections
+            .is_empty());
+    }
+
+    #[test]
+    fn test_fixture_file() {
+        let path = std::env::temp_dir().join(format!("nm-fixture-{}.json", std::process::id()));
+        // Recorded by a version that works out directions too
+        let mut passes = vec![vec![connection("sshd", 0)]];
+        passes[0][0].direction = Some(Direction::Outbound);
+        fs::write(&path, serde_json::to_string(&passes).unwrap()).unwrap();
+        let backend: Backend = format!("fixture:{}", path.display()).parse().unwrap();
+        let mut collector = backend.open(&CacheLimits::default()).unwrap();
+        assert_eq!(collector.collect().unwrap().connections, passes[0]);
+
+        fs::write(&path, "{").unwrap();
+        assert!(backend.open(&CacheLimits::default()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_parse_backend() {
+        assert_eq!("netlink".parse(), Ok(Backend::Netlink));
+        assert_eq!("libproc".parse(), Ok(Backend::Libproc));
+        assert_eq!("android".parse(), Ok(Backend::Android));
+        assert_eq!("router".parse(), Ok(Backend::Router));
+        assert_eq!(
+            "fixture:/tmp/a.json"
+                .parse::<Backend>()
+                .unwrap()
+                .to_string(),
+            "fixture:/tmp/a.json"
+        );
+        assert!("fixture:".parse::<Backend>().is_err());
+        assert_eq!(
+            "ssh:admin@web1".parse(),
+            Ok(Backend::Ssh {
+                destination: "admin@web1".to_string(),
+                command: DEFAULT_REMOTE_COMMAND.to_string()
+            })
+        );
+        assert_eq!(
+            "ssh:web1".parse::<Backend>().unwrap().to_string(),
+            "ssh:web1"
+        );
+        assert!("ssh:".parse::<Backend>().is_err());
+        assert!("ebpf".parse::<Backend>().is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_live_backends_collect() {
+        let limits = CacheLimits::default();
+        assert!(ProcfsCollector::new(&limits).collect().is_ok());
+        // Netlink sockets may be unavailable in restricted sandboxes
+        if let Err(e) = NetlinkCollector::new(&limits).collect() {
+            eprintln!("netlink backend unavailable: {e}");
+        }
+        assert!(Backend::Libproc.open(&limits).is_err());
+    }
+
+    #[test]
+    #[cfg(target_os = "macos")]
+    fn test_libproc_is_the_default() {
+        assert_eq!(Backend::default(), Backend::Libproc);
+        assert!(LibprocCollector::new().collect().is_ok());
+    }
+}
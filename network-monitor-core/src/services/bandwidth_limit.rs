@@ -0,0 +1,250 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::services::PrivilegedHelper;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Mount point of the cgroup v2 hierarchy
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// Parent of the cgroup each limited process is moved to, below the root
+const LIMIT_CGROUP: &str = "network-monitor";
+/// Rate and original cgroup of each limited process, one file per PID,
+/// readable by the unprivileged UIs
+const STATE_DIR: &str = "/run/network-monitor/limits";
+
+/// Rates offered by the limit menus, in bytes per second
+pub const LIMIT_PRESETS: [u64; 3] = [256 * 1024, 1024 * 1024, 5 * 1024 * 1024];
+
+/// Helper arguments applying or lifting a limit
+pub const LIMIT_REQUEST: &str = "limit";
+pub const UNLIMIT_REQUEST: &str = "unlimit";
+
+/// Ask the privileged helper to cap process `pid` at `bytes_per_sec` each
+/// way, or to lift its limit when None. pkexec asks for authentication
+/// unless the polkit rule is installed. Needs cgroup v2 and nftables, so
+/// Linux only.
+pub fn request(pid: &str, bytes_per_sec: Option<u64>) -> Result<()> {
+    let pid = parse_pid(pid)?;
+    match bytes_per_sec {
+        Some(rate) => PrivilegedHelper::run(&[LIMIT_REQUEST, &pid.to_string(), &rate.to_string()]),
+        None => PrivilegedHelper::run(&[UNLIMIT_REQUEST, &pid.to_string()]),
+    }
+}
+
+/// Rate of each limited process by PID. Processes that exited, or were moved
+/// out of their cgroup since, are left out.
+pub fn current_limits() -> HashMap<String, u64> {
+    let Ok(entries) = fs::read_dir(STATE_DIR) else {
+        return HashMap::new();
+    };
+    entries
+        .flatten()
+        .filter_map(|entry| {
+            let pid = entry.file_name().to_str()?.parse::<u32>().ok()?;
+            let (rate, _) = parse_state(&fs::read_to_string(entry.path()).ok()?)?;
+            let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+            (parse_cgroup(&cgroup)? == limit_cgroup(pid)).then(|| (pid.to_string(), rate))
+        })
+        .collect()
+}
+
+/// Move process `pid` to a cgroup of its own and police the traffic of that
+/// cgroup's sockets with nftables, in both directions. Children it starts
+/// later inherit the limit. Run by the helper as root.
+///
+/// tc's cgroup classifier only knows cgroup v1, so the rate is enforced by
+/// dropping what goes over it, which TCP answers by slowing down.
+pub fn apply(pid: u32, bytes_per_sec: u64) -> Result<()> {
+    if bytes_per_sec == 0 {
+        return Err(limit_error("the rate must be above zero"));
+    }
+    // Changing the rate keeps the cgroup the process came from
+    let original = match read_state(pid) {
+        Some((_, original)) => original,
+        None => {
+            let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup"))
+                .map_err(|e| limit_error(format!("process {pid}: {e}")))?;
+            parse_cgroup(&cgroup)
+                .ok_or_else(|| limit_error("cgroup v2 is not mounted"))?
+                .to_string()
+        }
+    };
+
+    let cgroup = cgroup_dir(&limit_cgroup(pid));
+    fs::create_dir_all(&cgroup).map_err(|e| limit_error(format!("creating the cgroup: {e}")))?;
+    fs::write(cgroup.join("cgroup.procs"), pid.to_string())
+        .map_err(|e| limit_error(format!("moving process {pid}: {e}")))?;
+    // The rules look the cgroup up when loaded, so it has to exist first
+    nft(&ruleset(pid, bytes_per_sec))?;
+
+    fs::create_dir_all(STATE_DIR)?;
+    fs::write(
+        Path::new(STATE_DIR).join(pid.to_string()),
+        format!("{bytes_per_sec}\n{original}\n"),
+    )?;
+    Ok(())
+}
+
+/// Drop the rules of process `pid` and move it, with any children started
+/// meanwhile, back to the cgroup it came from. Run by the helper as root.
+pub fn remove(pid: u32) -> Result<()> {
+    let original = read_state(pid).map(|(_, original)| original);
+    // Gone already when the rules were flushed by hand
+    if let Err(e) = nft(&format!("delete table inet {}\n", table_name(pid))) {
+        tracing::debug!("Removing the rules of {}: {}", pid, e);
+    }
+
+    let cgroup = cgroup_dir(&limit_cgroup(pid));
+    if let (Some(original), Ok(procs)) =
+        (&original, fs::read_to_string(cgroup.join("cgroup.procs")))
+    {
+        for member in procs.lines() {
+            if let Err(e) = fs::write(cgroup_dir(original).join("cgroup.procs"), member) {
+                tracing::warn!("Could not move {} back to {}: {}", member, original, e);
+            }
+        }
+    }
+    // Fails while processes are left in it
+    if let Err(e) = fs::remove_dir(&cgroup) {
+        if e.kind() != io::ErrorKind::NotFound {
+            return Err(limit_error(format!("removing the cgroup: {e}")));
+        }
+    }
+    match fs::remove_file(Path::new(STATE_DIR).join(pid.to_string())) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+/// PID of a connection's process; other values, like "N/A", can't be limited
+pub fn parse_pid(pid: &str) -> Result<u32> {
+    pid.parse()
+        .ok()
+        .filter(|&pid| pid > 1)
+        .ok_or_else(|| limit_error(format!("no process to limit ({pid})")))
+}
+
+fn limit_error(message: impl Into<String>) -> NetworkMonitorError {
+    NetworkMonitorError::BandwidthLimit(message.into())
+}
+
+/// Path of the cgroup of limited process `pid`, relative to the root
+fn limit_cgroup(pid: u32) -> String {
+    format!("/{LIMIT_CGROUP}/limit-{pid}")
+}
+
+fn cgroup_dir(cgroup: &str) -> PathBuf {
+    Path::new(CGROUP_ROOT).join(cgroup.trim_start_matches('/'))
+}
+
+fn table_name(pid: u32) -> String {
+    format!("network_monitor_limit_{pid}")
+}
+
+/// nftables table policing both directions of the sockets in the cgroup of
+/// `pid`. The table is created empty and deleted first so applying it again
+/// replaces the rate in one transaction.
+fn ruleset(pid: u32, bytes_per_sec: u64) -> String {
+    let table = table_name(pid);
+    let cgroup = limit_cgroup(pid);
+    let cgroup = cgroup.trim_start_matches('/');
+    // Let a quarter of a second through at once, so single segments of slow
+    // limits aren't always over
+    let burst = (bytes_per_sec / 4).max(64 * 1024);
+    let rule = format!(
+        "socket cgroupv2 level 2 \"{cgroup}\" limit rate over {bytes_per_sec} bytes/second burst {burst} bytes drop"
+    );
+    format!(
+        "table inet {table}\n\
+         delete table inet {table}\n\
+         table inet {table} {{\n\
+         \tchain output {{\n\
+         \t\ttype filter hook output priority 0; policy accept;\n\
+         \t\t{rule}\n\
+         \t}}\n\
+         \tchain input {{\n\
+         \t\ttype filter hook input priority 0; policy accept;\n\
+         \t\t{rule}\n\
+         \t}}\n\
+         }}\n"
+    )
+}
+
+fn nft(ruleset: &str) -> Result<()> {
+    let mut child = Command::new("nft")
+        .args(["-f", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| limit_error(format!("nft: {e}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(ruleset.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(limit_error(format!(
+            "nft: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        )))
+    }
+}
+
+/// Path of the unified hierarchy in /proc/[pid]/cgroup, the "0::" line
+fn parse_cgroup(text: &str) -> Option<&str> {
+    text.lines().find_map(|line| line.strip_prefix("0::"))
+}
+
+fn read_state(pid: u32) -> Option<(u64, String)> {
+    parse_state(&fs::read_to_string(Path::new(STATE_DIR).join(pid.to_string())).ok()?)
+}
+
+/// Rate and original cgroup, one per line
+fn parse_state(text: &str) -> Option<(u64, String)> {
+    let mut lines = text.lines();
+    let rate = lines.next()?.trim().parse().ok()?;
+    let original = lines.next()?.trim();
+    original
+        .starts_with('/')
+        .then(|| (rate, original.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ruleset() {
+        let ruleset = ruleset(4242, 1024 * 1024);
+        assert!(ruleset.starts_with(
+            "table inet network_monitor_limit_4242\ndelete table inet network_monitor_limit_4242\n"
+        ));
+        assert!(ruleset.contains(
+            "socket cgroupv2 level 2 \"network-monitor/limit-4242\" limit rate over 1048576 bytes/second burst 262144 bytes drop"
+        ));
+        assert!(ruleset.contains("hook output") && ruleset.contains("hook input"));
+    }
+
+    #[test]
+    fn test_parse_state_and_cgroup() {
+        assert_eq!(
+            parse_state("1048576\n/user.slice/user-1000.slice/session-2.scope\n"),
+            Some((
+                1_048_576,
+                "/user.slice/user-1000.slice/session-2.scope".to_string()
+            ))
+        );
+        assert_eq!(parse_state("fast\n/"), None);
+        assert_eq!(
+            parse_cgroup("1:name=systemd:/init.scope\n0::/network-monitor/limit-4242\n"),
+            Some("/network-monitor/limit-4242")
+        );
+        assert_eq!(parse_pid("4242").unwrap(), 4242);
+        assert!(parse_pid("N/A").is_err());
+        assert!(parse_pid("1").is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use std::fs::File;
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// Commands, types and flags of linux/bpf.h
+const BPF_MAP_CREATE: libc::c_long = 0;
+const BPF_MAP_LOOKUP_ELEM: libc::c_long = 1;
+const BPF_MAP_GET_NEXT_KEY: libc::c_long = 4;
+const BPF_PROG_LOAD: libc::c_long = 5;
+const BPF_MAP_LOOKUP_AND_DELETE_ELEM: libc::c_long = 21;
+const BPF_LINK_CREATE: libc::c_long = 28;
+pub const BPF_MAP_TYPE_LRU_HASH: u32 = 9;
+pub const BPF_MAP_TYPE_QUEUE: u32 = 22;
+pub const BPF_PROG_TYPE_CGROUP_SKB: u32 = 8;
+pub const BPF_PROG_TYPE_CGROUP_SOCK_ADDR: u32 = 18;
+pub const BPF_CGROUP_INET_INGRESS: u32 = 0;
+pub const BPF_CGROUP_INET_EGRESS: u32 = 1;
+pub const BPF_CGROUP_INET4_CONNECT: u32 = 10;
+pub const BPF_CGROUP_INET6_CONNECT: u32 = 11;
+pub const BPF_NOEXIST: i32 = 1;
+pub const BPF_EXIST: i32 = 2;
+const BPF_PSEUDO_MAP_FD: u8 = 1;
+pub const BPF_FUNC_MAP_LOOKUP_ELEM: i32 = 1;
+pub const BPF_FUNC_MAP_UPDATE_ELEM: i32 = 2;
+pub const BPF_FUNC_GET_CURRENT_PID_TGID: i32 = 14;
+pub const BPF_FUNC_GET_CURRENT_COMM: i32 = 16;
+pub const BPF_FUNC_SKB_CGROUP_ID: i32 = 79;
+pub const BPF_FUNC_MAP_PUSH_ELEM: i32 = 87;
+
+/// Instruction classes and operations
+pub const LDX_W: u8 = 0x61;
+pub const LDX_DW: u8 = 0x79;
+pub const STX_W: u8 = 0x63;
+pub const STX_DW: u8 = 0x7b;
+pub const ATOMIC_ADD_DW: u8 = 0xdb;
+const LD_IMM_DW: u8 = 0x18;
+pub const ADD_K: u8 = 0x07;
+pub const RSH_K: u8 = 0x77;
+pub const MOV_K: u8 = 0xb7;
+pub const MOV_X: u8 = 0xbf;
+pub const JA: u8 = 0x05;
+pub const JEQ_K: u8 = 0x15;
+pub const CALL: u8 = 0x85;
+pub const EXIT: u8 = 0x95;
+
+/// Size of the union bpf_attr fields used here
+const ATTR_SIZE: usize = 128;
+
+/// One instruction of struct bpf_insn
+pub type Insn = [u8; 8];
+
+/// The fields of union bpf_attr a command takes, by byte offset
+struct Attr([u8; ATTR_SIZE]);
+
+impl Attr {
+    fn new() -> Self {
+        Self([0; ATTR_SIZE])
+    }
+
+    fn u32(mut self, offset: usize, value: u32) -> Self {
+        self.0[offset..offset + 4].copy_from_slice(&value.to_ne_bytes());
+        self
+    }
+
+    fn pointer<T>(mut self, offset: usize, value: *const T) -> Self {
+        self.0[offset..offset + 8].copy_from_slice(&(value as u64).to_ne_bytes());
+        self
+    }
+}
+
+fn bpf(command: libc::c_long, attr: &Attr) -> io::Result<libc::c_long> {
+    // SAFETY: attr is a zero-padded bpf_attr of the size given, and its
+    // pointers refer to buffers alive for the call
+    let result = unsafe {
+        libc::syscall(
+            libc::SYS_bpf,
+            command,
+            attr.0.as_ptr(),
+            ATTR_SIZE as libc::c_uint,
+        )
+    };
+    if result < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(result)
+    }
+}
+
+/// Run `command`, owning the descriptor it returns
+fn bpf_fd(command: libc::c_long, attr: &Attr) -> io::Result<OwnedFd> {
+    let fd = bpf(command, attr)?;
+    // SAFETY: the kernel just returned this descriptor to us alone
+    Ok(unsafe { OwnedFd::from_raw_fd(fd as i32) })
+}
+
+pub fn create_map(
+    map_type: u32,
+    key_size: u32,
+    value_size: u32,
+    max_entries: u32,
+) -> io::Result<OwnedFd> {
+    let attr = Attr::new()
+        .u32(0, map_type)
+        .u32(4, key_size)
+        .u32(8, value_size)
+        .u32(12, max_entries);
+    bpf_fd(BPF_MAP_CREATE, &attr)
+}
+
+/// Load `instructions` as a GPL program of `program_type`, to be attached
+/// as `attach_type`
+pub fn load_program(
+    program_type: u32,
+    attach_type: u32,
+    instructions: &[Insn],
+) -> io::Result<OwnedFd> {
+    let license = b"GPL\0";
+    let attr = Attr::new()
+        .u32(0, program_type)
+        .u32(4, instructions.len() as u32)
+        .pointer(8, instructions.as_ptr())
+        .pointer(16, license.as_ptr())
+        .u32(68, attach_type);
+    bpf_fd(BPF_PROG_LOAD, &attr)
+}
+
+/// Attach `program` to `cgroup` and everything below it until the link
+/// is closed, which happens at the latest when the process exits
+pub fn link(program: &OwnedFd, cgroup: &File, attach_type: u32) -> io::Result<OwnedFd> {
+    let attr = Attr::new()
+        .u32(0, program.as_raw_fd() as u32)
+        .u32(4, cgroup.as_raw_fd() as u32)
+        .u32(8, attach_type);
+    bpf_fd(BPF_LINK_CREATE, &attr)
+}
+
+/// Every entry of a hash map of u64 keys and values; entries dropped while
+/// reading restart the walk, so it is bounded by `max_entries`
+pub fn read_u64_map(map: &OwnedFd, max_entries: u32) -> Vec<(u64, u64)> {
+    let mut entries = Vec::new();
+    let mut key: Option<u64> = None;
+    for _ in 0..max_entries {
+        let mut next = 0u64;
+        let attr = Attr::new()
+            .u32(0, map.as_raw_fd() as u32)
+            .pointer(
+                8,
+                key.as_ref()
+                    .map_or(std::ptr::null(), |key| key as *const u64),
+            )
+            .pointer(16, &raw mut next);
+        if bpf(BPF_MAP_GET_NEXT_KEY, &attr).is_err() {
+            break;
+        }
+        let mut value = 0u64;
+        let attr = Attr::new()
+            .u32(0, map.as_raw_fd() as u32)
+            .pointer(8, &next)
+            .pointer(16, &raw mut value);
+        if bpf(BPF_MAP_LOOKUP_ELEM, &attr).is_ok() {
+            entries.push((next, value));
+        }
+        key = Some(next);
+    }
+    entries
+}
+
+/// Take the oldest value out of a queue map, None once it is empty
+pub fn pop<const N: usize>(map: &OwnedFd) -> Option<[u8; N]> {
+    let mut value = [0u8; N];
+    let attr = Attr::new()
+        .u32(0, map.as_raw_fd() as u32)
+        .pointer(16, value.as_mut_ptr());
+    bpf(BPF_MAP_LOOKUP_AND_DELETE_ELEM, &attr).ok()?;
+    Some(value)
+}
+
+/// One instruction
+pub fn insn(code: u8, dst: u8, src: u8, offset: i16, imm: i32) -> Insn {
+    let registers = if cfg!(target_endian = "little") {
+        dst | src << 4
+    } else {
+        dst << 4 | src
+    };
+    let mut insn = [code, registers, 0, 0, 0, 0, 0, 0];
+    insn[2..4].copy_from_slice(&offset.to_ne_bytes());
+    insn[4..8].copy_from_slice(&imm.to_ne_bytes());
+    insn
+}
+
+/// The two instructions loading the address of `map` into register `dst`
+pub fn load_map(dst: u8, map: i32) -> [Insn; 2] {
+    [
+        insn(LD_IMM_DW, dst, BPF_PSEUDO_MAP_FD, 0, map),
+        insn(0, 0, 0, 0, 0),
+    ]
+}
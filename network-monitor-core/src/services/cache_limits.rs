@@ -0,0 +1,69 @@
+use std::env;
+
+/// Processes tracked by the socket owner cache
+const DEFAULT_PROCESS_ENTRIES: usize = 4096;
+/// Hostnames kept by the reverse DNS cache
+const DEFAULT_RESOLVER_ENTRIES: usize = 4096;
+
+const PROCESS_ENTRIES_VAR: &str = "NETWORK_MONITOR_PROCESS_CACHE_LIMIT";
+const RESOLVER_ENTRIES_VAR: &str = "NETWORK_MONITOR_RESOLVER_CACHE_LIMIT";
+
+/// Entry limits for the long-lived caches; the least recently used entries
+/// are evicted beyond them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CacheLimits {
+    pub process_entries: usize,
+    pub resolver_entries: usize,
+}
+
+impl CacheLimits {
+    /// Defaults, overridden by NETWORK_MONITOR_PROCESS_CACHE_LIMIT and
+    /// NETWORK_MONITOR_RESOLVER_CACHE_LIMIT when set
+    pub fn from_env() -> Self {
+        Self::from_lookup(|name| env::var(name).ok())
+    }
+
+    fn from_lookup(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        let limit = |name: &str, default: usize| match lookup(name) {
+            Some(value) => match value.trim().parse::<usize>() {
+                Ok(limit) if limit > 0 => limit,
+                _ => {
+                    tracing::warn!("Ignoring invalid {name}={value}, using {default}");
+                    default
+                }
+            },
+            None => default,
+        };
+
+        Self {
+            process_entries: limit(PROCESS_ENTRIES_VAR, DEFAULT_PROCESS_ENTRIES),
+            resolver_entries: limit(RESOLVER_ENTRIES_VAR, DEFAULT_RESOLVER_ENTRIES),
+        }
+    }
+}
+
+impl Default for CacheLimits {
+    fn default() -> Self {
+        Self {
+            process_entries: DEFAULT_PROCESS_ENTRIES,
+            resolver_entries: DEFAULT_RESOLVER_ENTRIES,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_limits_from_environment() {
+        let limits = CacheLimits::from_lookup(|name| match name {
+            PROCESS_ENTRIES_VAR => Some("128".to_string()),
+            RESOLVER_ENTRIES_VAR => Some("none".to_string()),
+            _ => None,
+        });
+        assert_eq!(limits.process_entries, 128);
+        assert_eq!(limits.resolver_entries, DEFAULT_RESOLVER_ENTRIES);
+        assert_eq!(CacheLimits::from_lookup(|_| None), CacheLimits::default());
+    }
+}
@@ -0,0 +1,661 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, Quic};
+use crate::utils::{parse_endpoint, LruCache};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+/// Server names, proxy destinations and QUIC flows remembered, each by
+/// local and remote endpoint
+const CAPTURED_ENTRIES: usize = 4096;
+
+/// Local and remote endpoint of a connection, IPv4-mapped addresses
+/// folded into IPv4 so dual-stack sockets match the packets on the wire
+type Endpoints = (SocketAddr, SocketAddr);
+
+/// What a captured packet tells about its connection
+#[derive(Debug, PartialEq, Eq)]
+enum Observation<'a> {
+    /// Server name of a TLS ClientHello
+    ServerName(&'a str),
+    /// Destination asked of a proxy, as "host:port"
+    Upstream(String),
+    /// QUIC Initial packet opening a connection
+    QuicInitial,
+}
+
+#[derive(Debug)]
+struct Captured {
+    server_names: LruCache<Endpoints, Arc<str>>,
+    upstreams: LruCache<Endpoints, Arc<str>>,
+    quic: LruCache<Endpoints, ()>,
+}
+
+/// Captures outgoing TLS ClientHellos on every interface and remembers the
+/// server name (SNI) each connection asked for, so HTTPS destinations can be
+/// named without reverse DNS. Plain HTTP and SOCKS5 requests to proxies give
+/// away the real destination of proxied connections the same way, and the
+/// Initial packets of outgoing QUIC connections confirm them as such. Needs
+/// CAP_NET_RAW; the capture thread stops when this is dropped.
+pub struct SniCapture {
+    captured: Arc<Mutex<Captured>>,
+    stop: Arc<AtomicBool>,
+}
+
+impl SniCapture {
+    /// Open a packet socket and start capturing on a background thread
+    pub fn start() -> Result<Self> {
+        let socket = linux::PacketSocket::open()?;
+        let captured = Arc::new(Mutex::new(Captured {
+            server_names: LruCache::new(CAPTURED_ENTRIES),
+            upstreams: LruCache::new(CAPTURED_ENTRIES),
+            quic: LruCache::new(CAPTURED_ENTRIES),
+        }));
+        let stop = Arc::new(AtomicBool::new(false));
+
+        let (thread_captured, thread_stop) = (Arc::clone(&captured), Arc::clone(&stop));
+        std::thread::Builder::new()
+            .name("sni-capture".to_string())
+            .spawn(move || {
+                let mut packet = vec![0u8; 65536];
+                while !thread_stop.load(Ordering::Relaxed) {
+                    let length = match socket.recv(&mut packet) {
+                        Ok(Some(length)) => length,
+                        // Timed out, check whether to stop
+                        Ok(None) => continue,
+                        Err(e) => {
+                            tracing::warn!("Packet capture stopped: {e}");
+                            break;
+                        }
+                    };
+                    let Some((endpoints, observation)) = observe(&packet[..length]) else {
+                        continue;
+                    };
+                    let Ok(mut captured) = thread_captured.lock() else {
+                        break;
+                    };
+                    match observation {
+                        Observation::ServerName(name) => {
+                            captured.server_names.insert(endpoints, Arc::from(name));
+                        }
+                        Observation::Upstream(target) => {
+                            captured.upstreams.insert(endpoints, Arc::from(target));
+                        }
+                        Observation::QuicInitial => {
+                            captured.quic.insert(endpoints, ());
+                        }
+                    }
+                }
+            })
+            .map_err(|e| NetworkMonitorError::Capture(e.to_string()))?;
+
+        Ok(Self { captured, stop })
+    }
+
+    /// Fill in `server_name` and `upstream` for the connections whose
+    /// ClientHello or proxy request was seen, and confirm those whose QUIC
+    /// Initial was
+    pub fn annotate(&self, connections: &mut [Connection]) {
+        let Ok(captured) = self.captured.lock() else {
+            return;
+        };
+        if captured.server_names.is_empty()
+            && captured.upstreams.is_empty()
+            && captured.quic.is_empty()
+        {
+            return;
+        }
+        for conn in connections {
+            let Some(endpoints) = parse_endpoint(&conn.local).zip(parse_endpoint(&conn.remote))
+            else {
+                continue;
+            };
+            if let Some(name) = captured.server_names.peek(&endpoints) {
+                conn.server_name = Some(Arc::clone(name));
+            }
+            if let Some(target) = captured.upstreams.peek(&endpoints) {
+                conn.upstream = Some(Arc::clone(target));
+            }
+            if conn.protocol.is_udp() && captured.quic.peek(&endpoints).is_some() {
+                conn.quic = Some(Quic::Confirmed);
+            }
+        }
+    }
+}
+
+impl Drop for SniCapture {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Endpoints, from the sender's point of view, and what an IP packet
+/// starting a TLS ClientHello, a proxy request or a QUIC connection tells
+/// about them
+fn observe(packet: &[u8]) -> Option<(Endpoints, Observation<'_>)> {
+    let (protocol, source, destination, payload) = transport_payload(packet)?;
+    if payload.is_empty() {
+        return None;
+    }
+    let observation = if protocol == libc::IPPROTO_UDP as u8 {
+        is_quic_initial(payload).then_some(Observation::QuicInitial)?
+    } else {
+        match server_name(payload) {
+            Some(name) => Observation::ServerName(name),
+            None => Observation::Upstream(proxy_request(payload)?),
+        }
+    };
+    Some(((source, destination), observation))
+}
+
+/// Transport protocol, source, destination and payload of a TCP segment or
+/// UDP datagram in an IPv4 or IPv6 packet. IPv4 fragments past the first
+/// and IPv6 extension headers are skipped, ClientHellos and QUIC Initials
+/// practically never use them.
+fn transport_payload(packet: &[u8]) -> Option<(u8, SocketAddr, SocketAddr, &[u8])> {
+    const TCP: u8 = libc::IPPROTO_TCP as u8;
+    const UDP: u8 = libc::IPPROTO_UDP as u8;
+    let (protocol, source, destination, segment): (u8, IpAddr, IpAddr, &[u8]) = match packet
+        .first()?
+        >> 4
+    {
+        4 => {
+            let header_len = usize::from(packet[0] & 0x0f) * 4;
+            let total_len = usize::from(u16::from_be_bytes([*packet.get(2)?, *packet.get(3)?]));
+            let fragment_offset = u16::from_be_bytes([*packet.get(6)?, *packet.get(7)?]) & 0x1fff;
+            let protocol = *packet.get(9)?;
+            if !matches!(protocol, TCP | UDP) || fragment_offset != 0 {
+                return None;
+            }
+            let source: [u8; 4] = packet.get(12..16)?.try_into().ok()?;
+            let destination: [u8; 4] = packet.get(16..20)?.try_into().ok()?;
+            (
+                protocol,
+                Ipv4Addr::from(source).into(),
+                Ipv4Addr::from(destination).into(),
+                packet.get(header_len..total_len.min(packet.len()))?,
+            )
+        }
+        6 => {
+            let protocol = *packet.get(6)?;
+            if !matches!(protocol, TCP | UDP) {
+                return None;
+            }
+            let payload_len = usize::from(u16::from_be_bytes([*packet.get(4)?, *packet.get(5)?]));
+            let source: [u8; 16] = packet.get(8..24)?.try_into().ok()?;
+            let destination: [u8; 16] = packet.get(24..40)?.try_into().ok()?;
+            (
+                protocol,
+                Ipv6Addr::from(source).to_canonical(),
+                Ipv6Addr::from(destination).to_canonical(),
+                packet.get(40..(40 + payload_len).min(packet.len()))?,
+            )
+        }
+        _ => return None,
+    };
+    let source_port = u16::from_be_bytes([*segment.first()?, *segment.get(1)?]);
+    let destination_port = u16::from_be_bytes([*segment.get(2)?, *segment.get(3)?]);
+    let data_offset = match protocol {
+        UDP => 8,
+        _ => usize::from(segment.get(12)? >> 4) * 4,
+    };
+    Some((
+        protocol,
+        SocketAddr::new(source, source_port),
+        SocketAddr::new(destination, destination_port),
+        segment.get(data_offset..)?,
+    ))
+}
+
+/// Whether a UDP payload is the Initial packet of a QUIC version 1 or 2
+/// connection (RFC 9000 17.2.2, RFC 9369): a long header with the fixed
+/// bit set, the Initial type of its version and a valid connection ID
+/// length. Clients pad Initials to 1200 bytes, which rules out most other
+/// protocols that happen to start the same way.
+pub fn is_quic_initial(payload: &[u8]) -> bool {
+    const VERSION_1: u32 = 0x0000_0001;
+    const VERSION_2: u32 = 0x6b33_43cf;
+    const MIN_INITIAL_LEN: usize = 1200;
+    const MAX_CONNECTION_ID_LEN: u8 = 20;
+
+    let (Some(&first), Some(version), Some(&id_len)) =
+        (payload.first(), payload.get(1..5), payload.get(5))
+    else {
+        return false;
+    };
+    let long_header = first & 0xc0 == 0xc0;
+    let packet_type = (first >> 4) & 0x03;
+    let initial = match u32::from_be_bytes(version.try_into().unwrap_or_default()) {
+        VERSION_1 => packet_type == 0,
+        VERSION_2 => packet_type == 1,
+        _ => false,
+    };
+    long_header && initial && id_len <= MAX_CONNECTION_ID_LEN && payload.len() >= MIN_INITIAL_LEN
+}
+
+/// Reads big-endian length-prefixed fields off the front of a buffer
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Option<&'a [u8]> {
+        let (head, rest) = (self.0.get(..len)?, self.0.get(len..)?);
+        self.0 = rest;
+        Some(head)
+    }
+
+    fn u8(&mut self) -> Option<usize> {
+        Some(usize::from(self.take(1)?[0]))
+    }
+
+    fn u16(&mut self) -> Option<usize> {
+        let bytes = self.take(2)?;
+        Some(usize::from(u16::from_be_bytes([bytes[0], bytes[1]])))
+    }
+
+    /// Field preceded by its length in `len_bytes` bytes
+    fn prefixed(&mut self, len_bytes: usize) -> Option<&'a [u8]> {
+        let len = match len_bytes {
+            1 => self.u8()?,
+            _ => self.u16()?,
+        };
+        self.take(len)
+    }
+}
+
+/// Host name of the server_name extension of a TLS ClientHello starting at
+/// `payload` (RFC 8446 4.1.2, RFC 6066 3). A ClientHello split over several
+/// segments is read as far as the first one goes.
+pub fn server_name(payload: &[u8]) -> Option<&str> {
+    /// Record content type of handshake messages
+    const HANDSHAKE: u8 = 22;
+    const CLIENT_HELLO: u8 = 1;
+    const SERVER_NAME: usize = 0;
+    const HOST_NAME: usize = 0;
+
+    let mut record = Reader(payload);
+    if record.u8()? != usize::from(HANDSHAKE) {
+        return None;
+    }
+    // Legacy version, then the record length, which may exceed the segment
+    record.take(4)?;
+    if record.u8()? != usize::from(CLIENT_HELLO) {
+        return None;
+    }
+    // Handshake length, legacy version and random
+    record.take(3 + 2 + 32)?;
+    record.prefixed(1)?; // session id
+    record.prefixed(2)?; // cipher suites
+    record.prefixed(1)?; // compression methods
+
+    // Stop at the end of the segment when the extensions run past it
+    let extensions_len = record.u16()?;
+    let mut extensions = Reader(record.0.get(..extensions_len.min(record.0.len()))?);
+    while let Some(kind) = extensions.u16() {
+        let data = extensions.prefixed(2)?;
+        if kind != SERVER_NAME {
+            continue;
+        }
+        let mut names = Reader(Reader(data).prefixed(2)?);
+        while let Some(name_type) = names.u8() {
+            let name = names.prefixed(2)?;
+            if name_type == HOST_NAME {
+                return std::str::from_utf8(name)
+                    .ok()
+                    .filter(|name| !name.is_empty());
+            }
+        }
+    }
+    None
+}
+
+/// Destination of a proxy request starting at `payload`, as "host:port":
+/// an HTTP CONNECT or absolute-form request, or a SOCKS5 CONNECT (RFC 1928)
+pub fn proxy_request(payload: &[u8]) -> Option<String> {
+    if let [5, 1, 0, kind, address @ ..] = payload {
+        return socks5_destination(*kind, address);
+    }
+    let line = payload.split(|&byte| byte == b'\r').next()?;
+    let mut parts = std::str::from_utf8(line).ok()?.split(' ');
+    let (method, target, version) = (parts.next()?, parts.next()?, parts.next()?);
+    if !version.starts_with("HTTP/") {
+        return None;
+    }
+    if method == "CONNECT" {
+        return Some(target.to_string());
+    }
+    let authority = target
+        .strip_prefix("http://")?
+        .split('/')
+        .next()
+        .filter(|authority| !authority.is_empty())?;
+    // Without a port, or a bare IPv6 address in brackets
+    if authority.ends_with(']') || !authority.contains(':') {
+        Some(format!("{authority}:80"))
+    } else {
+        Some(authority.to_string())
+    }
+}
+
+/// "host:port" of the address in a SOCKS5 request, which must end there
+fn socks5_destination(kind: u8, address: &[u8]) -> Option<String> {
+    const IPV4: u8 = 1;
+    const DOMAIN: u8 = 3;
+    const IPV6: u8 = 4;
+
+    let (host, port) = match kind {
+        IPV4 if address.len() == 4 + 2 => {
+            let ip: [u8; 4] = address[..4].try_into().ok()?;
+            (Ipv4Addr::from(ip).to_string(), &address[4..])
+        }
+        IPV6 if address.len() == 16 + 2 => {
+            let ip: [u8; 16] = address[..16].try_into().ok()?;
+            (format!("[{}]", Ipv6Addr::from(ip)), &address[16..])
+        }
+        DOMAIN => {
+            let (&len, rest) = address.split_first()?;
+            let len = usize::from(len);
+            if rest.len() != len + 2 {
+                return None;
+            }
+            (
+                std::str::from_utf8(&rest[..len]).ok()?.to_string(),
+                &rest[len..],
+            )
+        }
+        _ => return None,
+    };
+    Some(format!("{host}:{}", u16::from_be_bytes([port[0], port[1]])))
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use crate::error::{NetworkMonitorError, Result};
+    use crate::services::netlink::Socket;
+    use std::io;
+
+    /// How often a blocked receive wakes up to check whether to stop
+    const RECEIVE_TIMEOUT_SECS: libc::time_t = 1;
+
+    /// AF_PACKET socket receiving the IP packets of every interface, both
+    /// directions, without link-layer headers
+    pub struct PacketSocket(Socket);
+
+    impl PacketSocket {
+        pub fn open() -> Result<Self> {
+            let socket = Socket::open(
+                libc::AF_PACKET,
+                libc::SOCK_DGRAM,
+                i32::from((libc::ETH_P_ALL as u16).to_be()),
+            )
+            .map_err(error)?;
+            socket
+                .set_receive_timeout(RECEIVE_TIMEOUT_SECS)
+                .map_err(error)?;
+            Ok(Self(socket))
+        }
+
+        /// Next packet into `buffer`, truncated to its size; None on timeout
+        pub fn recv(&self, buffer: &mut [u8]) -> Result<Option<usize>> {
+            match self.0.recv(buffer) {
+                Ok(received) => Ok(Some(received)),
+                Err(e)
+                    if matches!(
+                        e.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+                    ) =>
+                {
+                    Ok(None)
+                }
+                Err(e) => Err(error(e)),
+            }
+        }
+    }
+
+    fn error(error: io::Error) -> NetworkMonitorError {
+        let hint = if error.kind() == io::ErrorKind::PermissionDenied {
+            " (needs root or CAP_NET_RAW)"
+        } else {
+            ""
+        };
+        NetworkMonitorError::Capture(format!("{error}{hint}"))
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod linux {
+    use crate::error::{NetworkMonitorError, Result};
+
+    pub struct PacketSocket;
+
+    impl PacketSocket {
+        pub fn open() -> Result<Self> {
+            Err(NetworkMonitorError::Capture(
+                "packet capture is only supported on Linux".to_string(),
+            ))
+        }
+
+        pub fn recv(&self, _buffer: &mut [u8]) -> Result<Option<usize>> {
+            Ok(None)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    /// ClientHello for `name` with a cipher suite and an extension before SNI
+    fn client_hello_record(name: &str) -> Vec<u8> {
+        let mut server_name = Vec::new();
+        server_name.extend_from_slice(&((name.len() + 3) as u16).to_be_bytes());
+        server_name.push(0);
+        server_name.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        server_name.extend_from_slice(name.as_bytes());
+
+        let mut extensions = Vec::new();
+        // supported_versions: TLS 1.3
+        extensions.extend_from_slice(&[0x00, 0x2b, 0x00, 0x03, 0x02, 0x03, 0x04]);
+        extensions.extend_from_slice(&[0x00, 0x00]);
+        extensions.extend_from_slice(&(server_name.len() as u16).to_be_bytes());
+        extensions.extend_from_slice(&server_name);
+
+        let mut hello = vec![0x03, 0x03];
+        hello.extend_from_slice(&[0xaa; 32]);
+        hello.push(0); // session id
+        hello.extend_from_slice(&[0x00, 0x02, 0x13, 0x01]);
+        hello.extend_from_slice(&[0x01, 0x00]);
+        hello.extend_from_slice(&(extensions.len() as u16).to_be_bytes());
+        hello.extend_from_slice(&extensions);
+
+        let mut handshake = vec![1, 0];
+        handshake.extend_from_slice(&(hello.len() as u16).to_be_bytes());
+        handshake.extend_from_slice(&hello);
+
+        let mut record = vec![22, 0x03, 0x01];
+        record.extend_from_slice(&(handshake.len() as u16).to_be_bytes());
+        record.extend_from_slice(&handshake);
+        record
+    }
+
+    /// IPv4 packet from 10.0.0.2:51000 to 93.184.216.34:443 carrying `payload`
+    fn ipv4_packet(payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&((20 + 20 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 6, 0, 0]);
+        packet.extend_from_slice(&[10, 0, 0, 2, 93, 184, 216, 34]);
+        packet.extend_from_slice(&51000u16.to_be_bytes());
+        packet.extend_from_slice(&443u16.to_be_bytes());
+        packet.extend_from_slice(&[0; 8]);
+        packet.extend_from_slice(&[0x50, 0x18, 0xff, 0xff, 0, 0, 0, 0]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    /// IPv4 packet from 10.0.0.2:40000 to 142.250.74.3:443 carrying a UDP
+    /// datagram with `payload`
+    fn ipv4_udp_packet(payload: &[u8]) -> Vec<u8> {
+        let mut packet = vec![0x45, 0];
+        packet.extend_from_slice(&((20 + 8 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0, 0x40, 0, 64, 17, 0, 0]);
+        packet.extend_from_slice(&[10, 0, 0, 2, 142, 250, 74, 3]);
+        packet.extend_from_slice(&40000u16.to_be_bytes());
+        packet.extend_from_slice(&443u16.to_be_bytes());
+        packet.extend_from_slice(&((8 + payload.len()) as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0]);
+        packet.extend_from_slice(payload);
+        packet
+    }
+
+    /// Padded QUIC Initial of `version` with an 8 byte connection ID
+    fn quic_initial(first: u8, version: u32) -> Vec<u8> {
+        let mut initial = vec![first];
+        initial.extend_from_slice(&version.to_be_bytes());
+        initial.push(8);
+        initial.resize(1200, 0);
+        initial
+    }
+
+    #[test]
+    fn test_quic_initial_packets() {
+        assert!(is_quic_initial(&quic_initial(0xc3, 1)));
+        // Version 2 numbers its Initial type 1
+        assert!(is_quic_initial(&quic_initial(0xd3, 0x6b33_43cf)));
+        assert!(!is_quic_initial(&quic_initial(0xc3, 0x6b33_43cf)));
+        // Handshake packet, short header, unpadded, unknown version
+        assert!(!is_quic_initial(&quic_initial(0xe3, 1)));
+        assert!(!is_quic_initial(&quic_initial(0x43, 1)));
+        assert!(!is_quic_initial(&quic_initial(0xc3, 1)[..300]));
+        assert!(!is_quic_initial(&quic_initial(0xc3, 0x0a0a_0a0a)));
+        assert!(!is_quic_initial(&[]));
+
+        let packet = ipv4_udp_packet(&quic_initial(0xc3, 1));
+        let ((source, destination), observation) = observe(&packet).unwrap();
+        assert_eq!(source, "10.0.0.2:40000".parse().unwrap());
+        assert_eq!(destination, "142.250.74.3:443".parse().unwrap());
+        assert_eq!(observation, Observation::QuicInitial);
+        // A ClientHello over UDP isn't TLS over TCP
+        assert_eq!(observe(&ipv4_udp_packet(&client_hello_record("a.b"))), None);
+    }
+
+    #[test]
+    fn test_server_name_of_client_hello() {
+        let record = client_hello_record("example.com");
+        assert_eq!(server_name(&record), Some("example.com"));
+        // Cut off before the extension: nothing to read yet
+        assert_eq!(server_name(&record[..60]), None);
+        // Other records, e.g. application data
+        assert_eq!(server_name(&[23, 3, 3, 0, 5, 1, 2, 3, 4, 5]), None);
+        assert_eq!(server_name(b"GET / HTTP/1.1\r\n"), None);
+        assert_eq!(server_name(&[]), None);
+    }
+
+    #[test]
+    fn test_proxy_request_destinations() {
+        let request = |payload: &[u8]| proxy_request(payload);
+        assert_eq!(
+            request(b"CONNECT example.com:443 HTTP/1.1\r\nHost: example.com:443\r\n\r\n")
+                .as_deref(),
+            Some("example.com:443")
+        );
+        assert_eq!(
+            request(b"GET http://example.org/index.html HTTP/1.1\r\n").as_deref(),
+            Some("example.org:80")
+        );
+        assert_eq!(
+            request(b"GET http://[2001:db8::1]:8000/ HTTP/1.1\r\n").as_deref(),
+            Some("[2001:db8::1]:8000")
+        );
+        // Requests to the server itself and responses aren't proxy requests
+        assert_eq!(request(b"GET /index.html HTTP/1.1\r\n"), None);
+        assert_eq!(request(b"HTTP/1.1 200 Connection established\r\n"), None);
+
+        let mut socks = vec![5, 1, 0, 3, 11];
+        socks.extend_from_slice(b"example.com");
+        socks.extend_from_slice(&443u16.to_be_bytes());
+        assert_eq!(request(&socks).as_deref(), Some("example.com:443"));
+        assert_eq!(
+            request(&[5, 1, 0, 1, 93, 184, 216, 34, 0, 80]).as_deref(),
+            Some("93.184.216.34:80")
+        );
+        // The method greeting before the request
+        assert_eq!(request(&[5, 1, 0]), None);
+        assert_eq!(request(&[5, 2, 0, 2]), None);
+    }
+
+    #[test]
+    fn test_client_hello_in_ipv4_packet() {
+        let packet = ipv4_packet(&client_hello_record("example.com"));
+        let ((source, destination), observation) = observe(&packet).unwrap();
+        assert_eq!(source, "10.0.0.2:51000".parse().unwrap());
+        assert_eq!(destination, "93.184.216.34:443".parse().unwrap());
+        assert_eq!(observation, Observation::ServerName("example.com"));
+        assert_eq!(observe(&ipv4_packet(&[])), None);
+    }
+
+    #[test]
+    fn test_annotate_matches_dual_stack_sockets() {
+        let capture = SniCapture {
+            captured: Arc::new(Mutex::new(Captured {
+                server_names: LruCache::new(4),
+                upstreams: LruCache::new(4),
+                quic: LruCache::new(4),
+            })),
+            stop: Arc::new(AtomicBool::new(false)),
+        };
+        let packet = ipv4_packet(&client_hello_record("example.com"));
+        let (endpoints, _) = observe(&packet).unwrap();
+        {
+            let mut captured = capture.captured.lock().unwrap();
+            captured
+                .server_names
+                .insert(endpoints, Arc::from("example.com"));
+            captured
+                .upstreams
+                .insert(endpoints, Arc::from("example.com:443"));
+        }
+
+        let mut connections = vec![
+            Connection::new(
+                Protocol::Tcp6,
+                ConnectionState::Established,
+                "::ffff:10.0.0.2:51000",
+                "::ffff:93.184.216.34:443",
+                "curl",
+                "100",
+                "curl",
+            ),
+            Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "10.0.0.2:51001",
+                "93.184.216.34:443",
+                "curl",
+                "100",
+                "curl",
+            ),
+        ];
+        let quic = ipv4_udp_packet(&quic_initial(0xc3, 1));
+        let (quic_endpoints, _) = observe(&quic).unwrap();
+        capture
+            .captured
+            .lock()
+            .unwrap()
+            .quic
+            .insert(quic_endpoints, ());
+        connections.push(Connection::new(
+            Protocol::Udp,
+            ConnectionState::Established,
+            "10.0.0.2:40000",
+            "142.250.74.3:443",
+            "firefox",
+            "2300",
+            "firefox",
+        ));
+        capture.annotate(&mut connections);
+        assert_eq!(connections[2].quic, Some(Quic::Confirmed));
+        assert_eq!(connections[0].server_name.as_deref(), Some("example.com"));
+        assert_eq!(connections[0].upstream.as_deref(), Some("example.com:443"));
+        assert_eq!(connections[1].server_name, None);
+    }
+}
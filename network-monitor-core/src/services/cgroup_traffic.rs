@@ -0,0 +1,313 @@
+use crate::models::{application_name, ApplicationTraffic, CgroupBytes};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+
+/// Mount point of the cgroup v2 hierarchy
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+
+/// Turns the byte counters of each cgroup into rates and session totals per
+/// application. Counters live in the kernel for as long as the cgroup, so
+/// processes forking and exiting between two passes are still accounted for.
+#[derive(Debug, Default)]
+pub struct ApplicationAccounting {
+    previous: HashMap<u64, CgroupBytes>,
+    previous_at: Option<Instant>,
+    /// Application of each cgroup ID with traffic
+    names: HashMap<u64, String>,
+    totals: HashMap<String, CgroupBytes>,
+}
+
+impl ApplicationAccounting {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Applications with traffic so far, busiest first, from the counters of
+    /// each cgroup ID read `now`. Rates start with the second call.
+    pub fn update(
+        &mut self,
+        counters: HashMap<u64, CgroupBytes>,
+        now: Instant,
+    ) -> Vec<ApplicationTraffic> {
+        // New cgroups are only named by walking the hierarchy again
+        if counters.keys().any(|id| !self.names.contains_key(id)) {
+            for (id, path) in cgroup_paths(Path::new(CGROUP_ROOT)) {
+                self.names
+                    .entry(id)
+                    .or_insert_with(|| application_name(&path));
+            }
+            self.names.retain(|id, _| counters.contains_key(id));
+        }
+        self.account(counters, now)
+    }
+
+    fn account(
+        &mut self,
+        counters: HashMap<u64, CgroupBytes>,
+        now: Instant,
+    ) -> Vec<ApplicationTraffic> {
+        let elapsed = self
+            .previous_at
+            .map(|at| now.duration_since(at).as_secs_f64().max(0.001));
+        let mut rates: HashMap<&str, CgroupBytes> = HashMap::new();
+        for (id, bytes) in &counters {
+            let previous = self.previous.get(id).copied().unwrap_or_default();
+            let sent = bytes.tx.saturating_sub(previous.tx);
+            let received = bytes.rx.saturating_sub(previous.rx);
+            // Cgroups that were gone before they could be named
+            let name = self.names.get(id).map_or("other", String::as_str);
+            let total = self.totals.entry(name.to_string()).or_default();
+            total.tx += sent;
+            total.rx += received;
+            if let Some(elapsed) = elapsed {
+                let rate = rates.entry(name).or_default();
+                rate.tx += (sent as f64 / elapsed) as u64;
+                rate.rx += (received as f64 / elapsed) as u64;
+            }
+        }
+
+        let mut applications: Vec<ApplicationTraffic> = self
+            .totals
+            .iter()
+            .map(|(name, total)| {
+                let rate = rates.get(name.as_str()).copied().unwrap_or_default();
+                ApplicationTraffic {
+                    name: name.clone(),
+                    tx_rate: rate.tx,
+                    rx_rate: rate.rx,
+                    tx_bytes: total.tx,
+                    rx_bytes: total.rx,
+                }
+            })
+            .filter(|app| app.tx_bytes > 0 || app.rx_bytes > 0)
+            .collect();
+        applications.sort_by(|a, b| {
+            b.rate()
+                .cmp(&a.rate())
+                .then_with(|| (b.tx_bytes + b.rx_bytes).cmp(&(a.tx_bytes + a.rx_bytes)))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        self.previous = counters;
+        self.previous_at = Some(now);
+        applications
+    }
+}
+
+/// ID and path of every cgroup below `root`, the ID being the inode number
+/// of its directory as the kernel reports it
+fn cgroup_paths(root: &Path) -> Vec<(u64, String)> {
+    use std::os::unix::fs::MetadataExt;
+
+    let mut paths = Vec::new();
+    let mut pending = vec![root.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let Ok(metadata) = std::fs::metadata(&dir) else {
+            continue;
+        };
+        let path = dir.strip_prefix(root).unwrap_or(&dir);
+        paths.push((metadata.ino(), format!("/{}", path.display())));
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        pending.extend(
+            entries
+                .flatten()
+                .filter(|entry| entry.file_type().is_ok_and(|kind| kind.is_dir()))
+                .map(|entry| entry.path()),
+        );
+    }
+    paths
+}
+
+#[cfg(target_os = "linux")]
+pub use bpf::CgroupTraffic;
+
+#[cfg(target_os = "linux")]
+mod bpf {
+    use super::CGROUP_ROOT;
+    use crate::error::{NetworkMonitorError, Result};
+    use crate::models::CgroupBytes;
+    use crate::services::bpf::*;
+    use std::collections::HashMap;
+    use std::fs::File;
+    use std::os::fd::{AsRawFd, OwnedFd};
+
+    /// Cgroups counted at once; the least recently used are dropped beyond
+    const MAX_CGROUPS: u32 = 16384;
+
+    /// Byte counters of every cgroup's sockets, kept by a cgroup_skb
+    /// program attached at the root of the hierarchy in each direction.
+    /// The programs are detached when this is dropped or the process exits.
+    pub struct CgroupTraffic {
+        /// Bytes received and sent, by cgroup ID
+        received: OwnedFd,
+        sent: OwnedFd,
+        _links: [OwnedFd; 2],
+    }
+
+    impl CgroupTraffic {
+        /// Load and attach the programs. Needs CAP_BPF and CAP_NET_ADMIN,
+        /// and Linux 5.7 for the links detaching them on exit.
+        pub fn attach() -> Result<Self> {
+            let root = File::open(CGROUP_ROOT)
+                .map_err(|e| accounting_error(format!("{CGROUP_ROOT}: {e}")))?;
+            let received = create_counters()?;
+            let sent = create_counters()?;
+            let ingress = load_counter(&received, BPF_CGROUP_INET_INGRESS)?;
+            let egress = load_counter(&sent, BPF_CGROUP_INET_EGRESS)?;
+            let attach = |program, attach_type| {
+                link(program, &root, attach_type)
+                    .map_err(|e| accounting_error(format!("attaching the counter program: {e}")))
+            };
+            Ok(Self {
+                _links: [
+                    attach(&ingress, BPF_CGROUP_INET_INGRESS)?,
+                    attach(&egress, BPF_CGROUP_INET_EGRESS)?,
+                ],
+                received,
+                sent,
+            })
+        }
+
+        /// Bytes sent and received so far by each cgroup ID
+        pub fn counters(&self) -> HashMap<u64, CgroupBytes> {
+            let mut counters: HashMap<u64, CgroupBytes> = HashMap::new();
+            for (id, bytes) in read_u64_map(&self.received, MAX_CGROUPS) {
+                counters.entry(id).or_default().rx = bytes;
+            }
+            for (id, bytes) in read_u64_map(&self.sent, MAX_CGROUPS) {
+                counters.entry(id).or_default().tx = bytes;
+            }
+            counters
+        }
+    }
+
+    /// u64 counters keyed by cgroup ID
+    fn create_counters() -> Result<OwnedFd> {
+        create_map(BPF_MAP_TYPE_LRU_HASH, 8, 8, MAX_CGROUPS)
+            .map_err(|e| accounting_error(format!("creating the counter map: {e}")))
+    }
+
+    fn load_counter(map: &OwnedFd, attach_type: u32) -> Result<OwnedFd> {
+        load_program(
+            BPF_PROG_TYPE_CGROUP_SKB,
+            attach_type,
+            &program(map.as_raw_fd()),
+        )
+        .map_err(|e| accounting_error(format!("loading the counter program: {e}")))
+    }
+
+    /// Add the length of each packet to the counter of its socket's cgroup
+    /// in `map`, then let the packet through:
+    ///
+    /// ```text
+    /// key = bpf_skb_cgroup_id(skb); len = skb->len
+    /// if (counter = lookup(map, key)) atomic_add(counter, len)
+    /// else update(map, key, len, BPF_NOEXIST)
+    /// return 1
+    /// ```
+    pub(super) fn program(map: i32) -> Vec<Insn> {
+        let load_map = load_map(1, map);
+        let mut program = vec![
+            insn(MOV_X, 6, 1, 0, 0),
+            insn(CALL, 0, 0, 0, BPF_FUNC_SKB_CGROUP_ID),
+            insn(STX_DW, 10, 0, -8, 0),
+            insn(LDX_W, 1, 6, 0, 0),
+            insn(STX_DW, 10, 1, -16, 0),
+        ];
+        program.extend(load_map);
+        program.extend([
+            insn(MOV_X, 2, 10, 0, 0),
+            insn(ADD_K, 2, 0, 0, -8),
+            insn(CALL, 0, 0, 0, BPF_FUNC_MAP_LOOKUP_ELEM),
+            // Not counted yet: insert
+            insn(JEQ_K, 0, 0, 3, 0),
+            insn(LDX_DW, 1, 10, -16, 0),
+            insn(ATOMIC_ADD_DW, 0, 1, 0, 0),
+            insn(JA, 0, 0, 8, 0),
+        ]);
+        program.extend(load_map);
+        program.extend([
+            insn(MOV_X, 2, 10, 0, 0),
+            insn(ADD_K, 2, 0, 0, -8),
+            insn(MOV_X, 3, 10, 0, 0),
+            insn(ADD_K, 3, 0, 0, -16),
+            insn(MOV_K, 4, 0, 0, BPF_NOEXIST),
+            insn(CALL, 0, 0, 0, BPF_FUNC_MAP_UPDATE_ELEM),
+            insn(MOV_K, 0, 0, 0, 1),
+            insn(EXIT, 0, 0, 0, 0),
+        ]);
+        program
+    }
+
+    fn accounting_error(message: String) -> NetworkMonitorError {
+        NetworkMonitorError::CgroupAccounting(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn bytes(tx: u64, rx: u64) -> CgroupBytes {
+        CgroupBytes { tx, rx }
+    }
+
+    #[test]
+    fn test_applications_add_up_their_cgroups() {
+        let mut accounting = ApplicationAccounting::new();
+        accounting.names = HashMap::from([
+            (10, "firefox".to_string()),
+            (11, "firefox".to_string()),
+            (20, "syncthing".to_string()),
+        ]);
+        let start = Instant::now();
+        let first = accounting.account(
+            HashMap::from([(10, bytes(100, 1000)), (20, bytes(50, 0))]),
+            start,
+        );
+        assert_eq!(first[0].name, "firefox");
+        assert_eq!((first[0].tx_bytes, first[0].rx_bytes), (100, 1000));
+        assert_eq!(first[0].rate(), 0);
+
+        // A second firefox scope appears, syncthing's is gone, and a cgroup
+        // that couldn't be named shows up as "other"
+        let second = accounting.account(
+            HashMap::from([
+                (10, bytes(300, 3000)),
+                (11, bytes(100, 0)),
+                (30, bytes(0, 4)),
+            ]),
+            start + Duration::from_secs(2),
+        );
+        let firefox = &second[0];
+        assert_eq!(firefox.name, "firefox");
+        assert_eq!((firefox.tx_rate, firefox.rx_rate), (150, 1000));
+        assert_eq!((firefox.tx_bytes, firefox.rx_bytes), (400, 3000));
+        assert_eq!(second[1].name, "other");
+        let syncthing = second.iter().find(|app| app.name == "syncthing").unwrap();
+        assert_eq!((syncthing.rate(), syncthing.tx_bytes), (0, 50));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_program_jumps_land_on_instructions() {
+        let program = bpf::program(3);
+        assert_eq!(program.len(), 24);
+        // The lookup miss goes to the insert, the hit to the return
+        assert_eq!(program[10][0], 0x15);
+        assert_eq!(
+            10 + 1 + i16::from_ne_bytes([program[10][2], program[10][3]]),
+            14
+        );
+        assert_eq!(program[13][0], 0x05);
+        assert_eq!(
+            13 + 1 + i16::from_ne_bytes([program[13][2], program[13][3]]),
+            22
+        );
+        assert_eq!(program[22][0], 0xb7);
+        assert_eq!(program[23][0], 0x95);
+    }
+}
@@ -0,0 +1,442 @@
+use crate::models::{
+    Connection, ConnectionDiff, ConnectionKey, ConnectionSnapshot, LanRanges, LinkEvent, Scope,
+    Subnet,
+};
+use crate::services::{
+    bandwidth_limit, ActivityTracker, Backend, CacheLimits, Collector, CountryTracker, DnsTracker,
+    GeoIpDatabase, ProcessInspector, ProxyDetector, ScopeTracker, SniCapture, WarmStart,
+};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+/// Work requested from the collector thread
+#[derive(Debug, Clone)]
+pub enum CollectorRequest {
+    Refresh {
+        group_by_application: bool,
+    },
+    EnablePrivilegedHelper,
+    /// Start capturing TLS server names, see [`SniCapture`]
+    EnableCapture,
+    /// Look up the country of remote hosts in the GeoIP files at these
+    /// paths, see [`GeoIpDatabase`]
+    EnableGeoIp(Vec<PathBuf>),
+    /// Count these ranges as LAN on top of the private ones, see [`Scope`]
+    AddLanRanges(Vec<Subnet>),
+    /// Cap process `pid` at a rate in bytes per second, or lift its limit
+    /// when None; see [`bandwidth_limit`](crate::services::bandwidth_limit).
+    /// Refreshes go on while it is applied.
+    LimitBandwidth {
+        pid: String,
+        bytes_per_sec: Option<u64>,
+    },
+    /// Collect from this backend from now on, such as another machine's
+    /// [`Backend::Ssh`], starting the session's history over
+    SwitchBackend(Backend),
+    /// Start from the [`WarmStart`] state the previous session saved at this
+    /// path, when recent enough, and save it there once stopped
+    WarmStart(PathBuf),
+}
+
+/// Results sent back from the collector thread
+#[derive(Debug)]
+pub enum CollectorEvent {
+    /// Boxed, snapshots being much larger than the other events
+    Snapshot(std::result::Result<Box<ConnectionSnapshot>, String>),
+    PrivilegedHelper(std::result::Result<(), String>),
+    Capture(std::result::Result<(), String>),
+    GeoIp(std::result::Result<(), String>),
+    /// Outcome of a [`CollectorRequest::LimitBandwidth`], with its PID
+    BandwidthLimit {
+        pid: String,
+        result: std::result::Result<(), String>,
+    },
+    /// Outcome of a [`CollectorRequest::SwitchBackend`], with the backend
+    /// collected from
+    BackendSwitched {
+        backend: Backend,
+        result: std::result::Result<(), String>,
+    },
+    /// An interface went up or down or its addresses changed; sent as it
+    /// happens for local backends on Linux
+    Link(LinkEvent),
+    /// Sockets were opened or closed since the last snapshot, so a refresh
+    /// would show new rows; sent for backends that
+    /// [watch sockets](Backend::watches_sockets)
+    SocketsChanged,
+}
+
+/// Runs a [`Collector`] backend on a worker thread so the UI main loop never
+/// blocks on it. The thread owns the backend and exits when this is dropped.
+pub struct ConnectionCollector {
+    requests: async_channel::Sender<CollectorRequest>,
+    thread: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+impl ConnectionCollector {
+    /// Start the worker on `backend`; events arrive on the returned receiver.
+    /// A backend that fails to open reports the error on every refresh.
+    /// Interface changes are watched unless the backend replays a fixture.
+    pub fn spawn(
+        backend: Backend,
+        limits: CacheLimits,
+    ) -> (Self, async_channel::Receiver<CollectorEvent>) {
+        let (requests, request_receiver) = async_channel::unbounded();
+        let (event_sender, events) = async_channel::unbounded();
+        let watching = Arc::new(AtomicBool::new(backend.watches_sockets()));
+        #[cfg(target_os = "linux")]
+        {
+            if !matches!(backend, Backend::Fixture(_) | Backend::Ssh { .. }) {
+                Self::watch_links(event_sender.clone());
+            }
+            Self::watch_sockets(event_sender.clone(), watching.clone());
+        }
+
+        let thread = thread::Builder::new()
+            .name("connection-collector".to_string())
+            .spawn(move || Self::run(backend, limits, request_receiver, event_sender, watching))
+            .expect("failed to spawn connection collector thread");
+
+        (
+            Self {
+                requests,
+                thread: Mutex::new(Some(thread)),
+            },
+            events,
+        )
+    }
+
+    /// Forward interface changes from a thread of their own, which ends once
+    /// the events are no longer received
+    #[cfg(target_os = "linux")]
+    fn watch_links(events: async_channel::Sender<CollectorEvent>) {
+        let mut listener = match crate::services::link_events::LinkEventListener::subscribe() {
+            Ok(listener) => listener,
+            Err(e) => {
+                tracing::debug!("Not watching interfaces: {}", e);
+                return;
+            }
+        };
+        thread::Builder::new()
+            .name("link-events".to_string())
+            .spawn(move || {
+                while !events.is_closed() {
+                    for event in listener.receive() {
+                        if events.send_blocking(CollectorEvent::Link(event)).is_err() {
+                            return;
+                        }
+                    }
+                }
+            })
+            .expect("failed to spawn link event thread");
+    }
+
+    /// Send [`CollectorEvent::SocketsChanged`] from a thread of its own while
+    /// `watching`, which follows the backend collected from
+    #[cfg(target_os = "linux")]
+    fn watch_sockets(events: async_channel::Sender<CollectorEvent>, watching: Arc<AtomicBool>) {
+        use crate::services::socket_events::{SocketWatcher, WATCH_TICK};
+        thread::Builder::new()
+            .name("socket-events".to_string())
+            .spawn(move || {
+                let mut watcher = SocketWatcher::new();
+                while !events.is_closed() {
+                    thread::sleep(WATCH_TICK);
+                    if watching.load(Ordering::Relaxed)
+                        && watcher.changed()
+                        && events
+                            .send_blocking(CollectorEvent::SocketsChanged)
+                            .is_err()
+                    {
+                        return;
+                    }
+                }
+            })
+            .expect("failed to spawn socket event thread");
+    }
+
+    /// Apply a bandwidth limit from a thread of its own, as pkexec may wait
+    /// for a password for as long as it takes, and report the outcome
+    fn limit_bandwidth(
+        events: async_channel::Sender<CollectorEvent>,
+        pid: String,
+        bytes_per_sec: Option<u64>,
+    ) {
+        let spawned = {
+            let (events, pid) = (events.clone(), pid.clone());
+            thread::Builder::new()
+                .name("bandwidth-limit".to_string())
+                .spawn(move || {
+                    let result =
+                        bandwidth_limit::request(&pid, bytes_per_sec).map_err(|e| e.to_string());
+                    let _ = events.send_blocking(CollectorEvent::BandwidthLimit { pid, result });
+                })
+        };
+        if let Err(e) = spawned {
+            let result = Err(format!("could not start the helper: {e}"));
+            let _ = events.send_blocking(CollectorEvent::BandwidthLimit { pid, result });
+        }
+    }
+
+    /// Queue a request; ignored if the worker has gone away
+    pub fn request(&self, request: CollectorRequest) {
+        let _ = self.requests.try_send(request);
+    }
+
+    /// Let the worker finish the requests queued so far and wait for it to
+    /// exit, saving the [`CollectorRequest::WarmStart`] state. Later
+    /// requests are ignored.
+    pub fn stop(&self) {
+        self.requests.close();
+        let thread = self.thread.lock().ok().and_then(|mut thread| thread.take());
+        if let Some(thread) = thread {
+            let _ = thread.join();
+        }
+    }
+
+    fn run(
+        backend: Backend,
+        limits: CacheLimits,
+        requests: async_channel::Receiver<CollectorRequest>,
+        events: async_channel::Sender<CollectorEvent>,
+        watching: Arc<AtomicBool>,
+    ) {
+        let open = |backend: &Backend| {
+            backend
+                .open(&limits)
+                .map_err(|e| format!("Could not open the {backend} backend: {e}"))
+        };
+        let mut collector = open(&backend);
+        let mut previous: Vec<Connection> = Vec::new();
+        let mut activity = ActivityTracker::new();
+        let mut dns = DnsTracker::new();
+        let mut capture: Option<SniCapture> = None;
+        let mut geoip: Option<GeoIpDatabase> = None;
+        let mut countries = CountryTracker::new();
+        let mut lan = LanRanges::default();
+        let mut scopes = ScopeTracker::new();
+        let mut warm_start: Option<PathBuf> = None;
+
+        while let Ok(request) = requests.recv_blocking() {
+            let backend = collector.as_mut().map_err(|e| e.clone());
+            let event = match request {
+                CollectorRequest::Refresh {
+                    group_by_application,
+                } => CollectorEvent::Snapshot(
+                    backend
+                        .and_then(|backend| Self::collect(backend.as_mut(), group_by_application))
+                        .map(|mut snapshot| {
+                            if let Some(capture) = &capture {
+                                capture.annotate(&mut snapshot.connections);
+                            }
+                            if let Some(geoip) = &geoip {
+                                geoip.annotate(&mut snapshot.connections);
+                            }
+                            Scope::mark(&mut snapshot.connections, &lan, &snapshot.routes);
+                            snapshot.diff =
+                                ConnectionDiff::between(&previous, &snapshot.connections);
+                            previous.clone_from(&snapshot.connections);
+                            activity.record(&mut snapshot);
+                            dns.record(&mut snapshot);
+                            scopes.record(&mut snapshot);
+                            if geoip.is_some() {
+                                countries.record(&mut snapshot);
+                            }
+                            Box::new(snapshot)
+                        }),
+                ),
+                CollectorRequest::EnablePrivilegedHelper => {
+                    CollectorEvent::PrivilegedHelper(backend.and_then(|backend| {
+                        backend
+                            .enable_privileged_helper()
+                            .map_err(|e| e.to_string())
+                    }))
+                }
+                CollectorRequest::EnableCapture => {
+                    let started = match &capture {
+                        Some(_) => Ok(()),
+                        None => SniCapture::start().map(|started| capture = Some(started)),
+                    };
+                    CollectorEvent::Capture(started.map_err(|e| e.to_string()))
+                }
+                CollectorRequest::EnableGeoIp(paths) => {
+                    let loaded = GeoIpDatabase::load(&paths).map(|database| geoip = Some(database));
+                    CollectorEvent::GeoIp(loaded.map_err(|e| e.to_string()))
+                }
+                CollectorRequest::AddLanRanges(ranges) => {
+                    // Nothing to report; the next refresh applies them
+                    lan = LanRanges::with(ranges);
+                    continue;
+                }
+                CollectorRequest::LimitBandwidth { pid, bytes_per_sec } => {
+                    Self::limit_bandwidth(events.clone(), pid, bytes_per_sec);
+                    continue;
+                }
+                CollectorRequest::SwitchBackend(backend) => {
+                    collector = open(&backend);
+                    watching.store(backend.watches_sockets(), Ordering::Relaxed);
+                    // What was seen on the previous machine doesn't carry over
+                    previous.clear();
+                    activity = ActivityTracker::new();
+                    dns = DnsTracker::new();
+                    countries = CountryTracker::new();
+                    scopes = ScopeTracker::new();
+                    let result = collector.as_ref().map(|_| ()).map_err(Clone::clone);
+                    CollectorEvent::BackendSwitched { backend, result }
+                }
+                CollectorRequest::WarmStart(path) => {
+                    match WarmStart::load(&path) {
+                        Ok(Some(mut state)) => {
+                            previous = std::mem::take(&mut state.connections);
+                            if let Ok(backend) = backend {
+                                backend.warm_start(state);
+                            }
+                        }
+                        Ok(None) => {}
+                        Err(e) => {
+                            tracing::debug!("Not warm starting from {}: {}", path.display(), e)
+                        }
+                    }
+                    warm_start = Some(path);
+                    continue;
+                }
+            };
+            if events.send_blocking(event).is_err() {
+                break;
+            }
+        }
+
+        let state = collector.ok().and_then(|collector| collector.warm_state());
+        if let (Some(path), Some(mut state)) = (warm_start, state) {
+            state.connections = previous;
+            if let Err(e) = state.save(&path) {
+                tracing::warn!("Could not save {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    fn collect(
+        collector: &mut dyn Collector,
+        group_by_application: bool,
+    ) -> std::result::Result<ConnectionSnapshot, String> {
+        let started = Instant::now();
+        let mut snapshot = collector
+            .collect()
+            .map_err(|e| format!("Failed to get connections: {e}"))?;
+        ConnectionKey::dedup(&mut snapshot.connections);
+
+        if group_by_application {
+            ProcessInspector::group_by_application(&mut snapshot.connections);
+        }
+        ProxyDetector::mark(&mut snapshot.connections);
+        snapshot.collection_time = started.elapsed();
+        Ok(snapshot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collector_delivers_snapshot() {
+        let (collector, events) =
+            ConnectionCollector::spawn(Backend::default(), CacheLimits::default());
+        collector.request(CollectorRequest::Refresh {
+            group_by_application: false,
+        });
+        // Interface and socket changes may come first on a busy machine
+        let event = std::iter::from_fn(|| events.recv_blocking().ok()).find(|event| {
+            !matches!(
+                event,
+                CollectorEvent::Link(_) | CollectorEvent::SocketsChanged
+            )
+        });
+        match event {
+            Some(CollectorEvent::Snapshot(result)) => assert!(result.is_ok()),
+            other => panic!("unexpected collector event: {other:?}"),
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_stopping_saves_the_warm_start() {
+        let path = std::env::temp_dir().join(format!("nm-warm-start-{}.json", std::process::id()));
+        let (collector, events) =
+            ConnectionCollector::spawn(Backend::Procfs, CacheLimits::default());
+        collector.request(CollectorRequest::WarmStart(path.clone()));
+        collector.request(CollectorRequest::Refresh {
+            group_by_application: false,
+        });
+        let snapshot =
+            std::iter::from_fn(|| events.recv_blocking().ok()).find_map(|event| match event {
+                CollectorEvent::Snapshot(result) => Some(result.unwrap()),
+                _ => None,
+            });
+        collector.stop();
+
+        let state = WarmStart::load(&path).unwrap().unwrap();
+        assert_eq!(state.connections.len(), snapshot.unwrap().connections.len());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_missing_fixture_fails_every_request() {
+        let backend = Backend::Fixture("/nonexistent/fixture.json".into());
+        let (collector, events) = ConnectionCollector::spawn(backend, CacheLimits::default());
+        collector.request(CollectorRequest::Refresh {
+            group_by_application: false,
+        });
+        collector.request(CollectorRequest::EnablePrivilegedHelper);
+        match events.recv_blocking() {
+            Ok(CollectorEvent::Snapshot(Err(e))) => assert!(e.contains("fixture"), "{e}"),
+            other => panic!("unexpected collector event: {other:?}"),
+        }
+        assert!(matches!(
+            events.recv_blocking(),
+            Ok(CollectorEvent::PrivilegedHelper(Err(_)))
+        ));
+    }
+
+    #[test]
+    fn test_switching_backends() {
+        let path = std::env::temp_dir().join(format!("nm-switch-{}.json", std::process::id()));
+        let conn = Connection::new(
+            crate::models::Protocol::Tcp,
+            crate::models::ConnectionState::Established,
+            "10.0.0.5:22",
+            "10.0.0.9:51000",
+            "sshd",
+            "812",
+            "sshd",
+        );
+        std::fs::write(&path, serde_json::to_string(&[[conn]]).unwrap()).unwrap();
+
+        let missing = Backend::Fixture("/nonexistent/fixture.json".into());
+        let (collector, events) = ConnectionCollector::spawn(missing, CacheLimits::default());
+        collector.request(CollectorRequest::SwitchBackend(Backend::Fixture(
+            path.clone(),
+        )));
+        collector.request(CollectorRequest::Refresh {
+            group_by_application: false,
+        });
+        match events.recv_blocking() {
+            Ok(CollectorEvent::BackendSwitched { backend, result }) => {
+                assert_eq!(backend, Backend::Fixture(path.clone()));
+                assert_eq!(result, Ok(()));
+            }
+            other => panic!("unexpected collector event: {other:?}"),
+        }
+        match events.recv_blocking() {
+            Ok(CollectorEvent::Snapshot(Ok(snapshot))) => {
+                assert_eq!(snapshot.connections.len(), 1);
+                assert_eq!(snapshot.diff.added.len(), 1);
+            }
+            other => panic!("unexpected collector event: {other:?}"),
+        }
+        std::fs::remove_file(&path).unwrap();
+    }
+}
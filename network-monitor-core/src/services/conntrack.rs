@@ -0,0 +1,200 @@
+use crate::error::Result;
+use crate::models::{ConnectionState, InterfaceStats, Protocol};
+use std::fs;
+use std::net::{IpAddr, SocketAddr};
+use std::path::Path;
+
+/// One tracked TCP or UDP flow from /proc/net/nf_conntrack. Byte counters
+/// are only non-zero with net.netfilter.nf_conntrack_acct=1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConntrackFlow {
+    pub protocol: Protocol,
+    pub state: ConnectionState,
+    /// Initiator of the flow, usually the LAN host behind the router
+    pub source: SocketAddr,
+    pub destination: SocketAddr,
+    /// Bytes in the original direction, from the initiator
+    pub sent: u64,
+    /// Bytes in the reply direction
+    pub received: u64,
+}
+
+/// Read the conntrack table under `proc_root`
+pub fn read_flows(proc_root: &Path) -> Result<Vec<ConntrackFlow>> {
+    let text = fs::read_to_string(proc_root.join("net/nf_conntrack"))?;
+    Ok(text.lines().filter_map(parse_flow).collect())
+}
+
+/// Parse one nf_conntrack line, e.g.
+/// `ipv4 2 tcp 6 431999 ESTABLISHED src=.. dst=.. sport=.. dport=.. packets=.. bytes=.. src=..`;
+/// None for other protocols and malformed lines
+pub fn parse_flow(line: &str) -> Option<ConntrackFlow> {
+    let mut words = line.split_whitespace();
+    let family = words.next()?;
+    let transport = words.nth(1)?;
+    let v6 = match family {
+        "ipv4" => false,
+        "ipv6" => true,
+        _ => return None,
+    };
+    let protocol = match (transport, v6) {
+        ("tcp", false) => Protocol::Tcp,
+        ("tcp", true) => Protocol::Tcp6,
+        ("udp", false) => Protocol::Udp,
+        ("udp", true) => Protocol::Udp6,
+        _ => return None,
+    };
+
+    // Protocol number and timeout, then the TCP state
+    let mut words = words.skip(2).peekable();
+    let mut state = match words.peek() {
+        Some(word) if !word.contains('=') && !word.starts_with('[') => {
+            let state = tcp_state(word);
+            words.next();
+            state
+        }
+        _ => ConnectionState::Established,
+    };
+
+    // The original tuple comes first, the reply tuple repeats the same keys
+    let (mut src, mut dst, mut sport, mut dport) = (None, None, None, None);
+    let mut bytes = Vec::with_capacity(2);
+    for word in words {
+        if word == "[UNREPLIED]" && !protocol.is_tcp() {
+            state = ConnectionState::Close;
+        }
+        let Some((key, value)) = word.split_once('=') else {
+            continue;
+        };
+        match key {
+            "src" if src.is_none() => src = value.parse::<IpAddr>().ok(),
+            "dst" if dst.is_none() => dst = value.parse::<IpAddr>().ok(),
+            "sport" if sport.is_none() => sport = value.parse::<u16>().ok(),
+            "dport" if dport.is_none() => dport = value.parse::<u16>().ok(),
+            "bytes" => bytes.push(value.parse::<u64>().ok()?),
+            _ => {}
+        }
+    }
+
+    Some(ConntrackFlow {
+        protocol,
+        state,
+        source: SocketAddr::new(src?, sport?),
+        destination: SocketAddr::new(dst?, dport?),
+        sent: bytes.first().copied().unwrap_or(0),
+        received: bytes.get(1).copied().unwrap_or(0),
+    })
+}
+
+/// Map the TCP states conntrack prints
+fn tcp_state(state: &str) -> ConnectionState {
+    match state {
+        "SYN_SENT" | "SYN_SENT2" => ConnectionState::SynSent,
+        "SYN_RECV" => ConnectionState::SynRecv,
+        "ESTABLISHED" => ConnectionState::Established,
+        "FIN_WAIT" => ConnectionState::FinWait1,
+        "CLOSE_WAIT" => ConnectionState::CloseWait,
+        "LAST_ACK" => ConnectionState::LastAck,
+        "TIME_WAIT" => ConnectionState::TimeWait,
+        "CLOSE" => ConnectionState::Close,
+        _ => ConnectionState::Unknown(0),
+    }
+}
+
+/// Byte counters of every interface from /proc/net/dev, without rates
+pub fn read_interfaces(proc_root: &Path) -> Result<Vec<InterfaceStats>> {
+    let text = fs::read_to_string(proc_root.join("net/dev"))?;
+    Ok(parse_net_dev(&text))
+}
+
+/// Parse /proc/net/dev: two header lines, then `name: rx_bytes ... tx_bytes ...`
+/// with eight receive columns before the transmit ones
+pub fn parse_net_dev(text: &str) -> Vec<InterfaceStats> {
+    text.lines()
+        .skip(2)
+        .filter_map(|line| {
+            let (name, counters) = line.split_once(':')?;
+            let counters: Vec<u64> = counters
+                .split_whitespace()
+                .map(|value| value.parse().ok())
+                .collect::<Option<_>>()?;
+            Some(InterfaceStats {
+                name: name.trim().to_string(),
+                rx_bytes: *counters.first()?,
+                tx_bytes: *counters.get(8)?,
+                ..InterfaceStats::default()
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tcp_flow() {
+        let flow = parse_flow(
+            "ipv4     2 tcp      6 431999 ESTABLISHED src=192.168.1.10 dst=93.184.216.34 \
+             sport=51000 dport=443 packets=10 bytes=1200 src=93.184.216.34 dst=203.0.113.5 \
+             sport=443 dport=51000 packets=8 bytes=9000 [ASSURED] mark=0 zone=0 use=2",
+        )
+        .unwrap();
+        assert_eq!(flow.protocol, Protocol::Tcp);
+        assert_eq!(flow.state, ConnectionState::Established);
+        assert_eq!(flow.source, "192.168.1.10:51000".parse().unwrap());
+        assert_eq!(flow.destination, "93.184.216.34:443".parse().unwrap());
+        assert_eq!((flow.sent, flow.received), (1200, 9000));
+    }
+
+    #[test]
+    fn test_parse_udp_and_ipv6_flows() {
+        let flow = parse_flow(
+            "ipv4     2 udp      17 29 src=192.168.1.10 dst=192.168.1.1 sport=5353 dport=53 \
+             [UNREPLIED] src=192.168.1.1 dst=192.168.1.10 sport=53 dport=5353 mark=0 use=2",
+        )
+        .unwrap();
+        assert_eq!(flow.state, ConnectionState::Close);
+        assert_eq!((flow.sent, flow.received), (0, 0));
+
+        let flow = parse_flow(
+            "ipv6     10 tcp      6 86 TIME_WAIT src=2001:0db8:0000:0000:0000:0000:0000:0002 \
+             dst=2606:2800:0220:0001:0248:1893:25c8:1946 sport=51010 dport=443 \
+             src=2606:2800:0220:0001:0248:1893:25c8:1946 dst=2001:0db8:0000:0000:0000:0000:0000:0002 \
+             sport=443 dport=51010 [ASSURED] use=1",
+        )
+        .unwrap();
+        assert_eq!(flow.protocol, Protocol::Tcp6);
+        assert_eq!(flow.state, ConnectionState::TimeWait);
+        assert_eq!(flow.source, "[2001:db8::2]:51010".parse().unwrap());
+    }
+
+    #[test]
+    fn test_skips_other_protocols_and_garbage() {
+        assert!(parse_flow(
+            "ipv4     2 icmp     1 29 src=192.168.1.10 dst=1.1.1.1 type=8 code=0 id=7 \
+             src=1.1.1.1 dst=192.168.1.10 type=0 code=0 id=7 use=1"
+        )
+        .is_none());
+        assert!(parse_flow("ipv4 2 tcp 6 10 ESTABLISHED src=garbage").is_none());
+        assert!(parse_flow("").is_none());
+    }
+
+    #[test]
+    fn test_parse_net_dev() {
+        let text = "\
+Inter-|   Receive                                                |  Transmit
+ face |bytes    packets errs drop fifo frame compressed multicast|bytes    packets errs drop fifo colls carrier compressed
+    lo:  123456     100    0    0    0     0          0         0   123456     100    0    0    0     0       0          0
+  eth0: 9876543    5000    0    0    0     0          0         0  1234567    4000    0    0    0     0       0          0
+broken: 1 2
+";
+        let interfaces = parse_net_dev(text);
+        assert_eq!(interfaces.len(), 2);
+        assert_eq!(interfaces[1].name, "eth0");
+        assert_eq!(
+            (interfaces[1].rx_bytes, interfaces[1].tx_bytes),
+            (9_876_543, 1_234_567)
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use crate::models::talkers::{rate_share, sockets_per_pid};
+use crate::models::{ConnectionSnapshot, CountryTraffic};
+use crate::utils::parse_endpoint;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Adds up traffic per remote country over the session, from connections
+/// whose country was filled in by a
+/// [`GeoIpDatabase`](crate::services::GeoIpDatabase). Countries stay listed
+/// once seen so their totals aren't lost when the last connection closes.
+#[derive(Debug, Default)]
+pub struct CountryTracker {
+    countries: HashMap<Arc<str>, CountryTraffic>,
+    last_pass: Option<SystemTime>,
+}
+
+impl CountryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the connections of `snapshot` per country, add the traffic
+    /// since the previous pass to the totals and fill in
+    /// `snapshot.countries`, most traffic first
+    pub fn record(&mut self, snapshot: &mut ConnectionSnapshot) {
+        let elapsed = self
+            .last_pass
+            .and_then(|last| snapshot.taken_at.duration_since(last).ok())
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.last_pass = Some(snapshot.taken_at);
+
+        for country in self.countries.values_mut() {
+            *country = CountryTraffic {
+                totals: country.totals,
+                ..CountryTraffic::new(country.country.clone())
+            };
+        }
+        let sockets_per_pid = sockets_per_pid(&snapshot.connections);
+        let mut hosts: HashSet<(&str, std::net::IpAddr)> = HashSet::new();
+        for conn in &snapshot.connections {
+            let Some(code) = &conn.country else {
+                continue;
+            };
+            let country = self
+                .countries
+                .entry(code.clone())
+                .or_insert_with(|| CountryTraffic::new(code.clone()));
+            let share = rate_share(conn, &sockets_per_pid);
+            country.connections += 1;
+            country.tx_rate += conn.tx_rate / share;
+            country.rx_rate += conn.rx_rate / share;
+            if let Some(remote) = parse_endpoint(&conn.remote) {
+                if hosts.insert((code, remote.ip())) {
+                    country.hosts += 1;
+                }
+            }
+        }
+        for country in self.countries.values_mut() {
+            country.totals.sent += (country.tx_rate as f64 * elapsed) as u64;
+            country.totals.received += (country.rx_rate as f64 * elapsed) as u64;
+        }
+
+        let mut countries: Vec<CountryTraffic> = self.countries.values().cloned().collect();
+        countries.sort_by(|a, b| {
+            b.total()
+                .cmp(&a.total())
+                .then_with(|| b.connections.cmp(&a.connections))
+                .then_with(|| a.country.cmp(&b.country))
+        });
+        snapshot.countries = countries;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Connection, ConnectionState, Protocol, TrafficTotals};
+    use std::time::Duration;
+
+    fn connection(pid: &str, remote: &str, country: &str, rx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            remote,
+            "firefox",
+            pid,
+            "firefox",
+        );
+        conn.rx_rate = rx_rate;
+        conn.country = Some(country.into());
+        conn
+    }
+
+    fn snapshot_at(secs: u64, connections: Vec<Connection>) -> ConnectionSnapshot {
+        ConnectionSnapshot {
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            ..ConnectionSnapshot::new(connections, TrafficTotals::default())
+        }
+    }
+
+    #[test]
+    fn test_traffic_adds_up_per_country() {
+        let mut tracker = CountryTracker::new();
+        let connections = vec![
+            connection("100", "8.8.8.8:443", "US", 3000),
+            connection("100", "8.8.8.4:443", "US", 3000),
+            connection("100", "2.16.40.1:443", "DE", 3000),
+            connection("200", "8.8.8.8:443", "US", 0),
+        ];
+        let mut snapshot = snapshot_at(0, connections.clone());
+        tracker.record(&mut snapshot);
+        let us = &snapshot.countries[0];
+        assert_eq!((&*us.country, us.connections, us.hosts), ("US", 3, 2));
+        assert_eq!(us.rx_rate, 2000);
+        assert_eq!(us.totals.received, 0);
+
+        let mut snapshot = snapshot_at(2, connections);
+        tracker.record(&mut snapshot);
+        let totals: Vec<_> = snapshot
+            .countries
+            .iter()
+            .map(|country| (&*country.country, country.totals.received))
+            .collect();
+        assert_eq!(totals, [("US", 4000), ("DE", 2000)]);
+
+        // Totals outlive the connections
+        let mut snapshot = snapshot_at(4, Vec::new());
+        tracker.record(&mut snapshot);
+        assert_eq!(snapshot.countries[0].connections, 0);
+        assert_eq!(snapshot.countries[0].totals.received, 4000);
+    }
+}
@@ -0,0 +1,115 @@
+use crate::models::{ConnectionSnapshot, DnsClient, DnsTransport};
+use std::collections::{HashMap, HashSet};
+use std::time::Duration;
+
+/// Clients not seen for this long are dropped, so short-lived processes
+/// don't pile up over a long session
+const FORGET_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// Follows which processes query which resolvers over the session, from the
+/// port 53 and 853 connections of each snapshot. Every socket that appears
+/// counts as a query.
+#[derive(Debug, Default)]
+pub struct DnsTracker {
+    clients: HashMap<(String, String, DnsTransport), DnsClient>,
+}
+
+impl DnsTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count the resolver connections added in `snapshot` and fill in
+    /// `snapshot.dns_clients`, busiest first. Expects the snapshot's diff to
+    /// be filled in.
+    pub fn record(&mut self, snapshot: &mut ConnectionSnapshot) {
+        let now = snapshot.taken_at;
+        let added: HashSet<_> = snapshot.diff.added.iter().collect();
+        for conn in &snapshot.connections {
+            let Some(seen) = DnsClient::of(conn, now) else {
+                continue;
+            };
+            let client = self
+                .clients
+                .entry((seen.process.clone(), seen.resolver.clone(), seen.transport))
+                .or_insert(seen);
+            client.last_seen = now;
+            if added.contains(&conn.key()) {
+                client.queries += 1;
+            }
+        }
+        self.clients.retain(|_, client| {
+            now.duration_since(client.last_seen)
+                .is_ok_and(|age| age < FORGET_AFTER)
+        });
+
+        let mut clients: Vec<DnsClient> = self.clients.values().cloned().collect();
+        clients.sort_by(|a, b| {
+            b.queries
+                .cmp(&a.queries)
+                .then_with(|| a.process.cmp(&b.process))
+                .then_with(|| a.resolver.cmp(&b.resolver))
+        });
+        snapshot.dns_clients = clients;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Connection, ConnectionDiff, ConnectionState, Protocol, TrafficTotals};
+    use std::time::SystemTime;
+
+    fn lookup(program: &str, local_port: u16) -> Connection {
+        Connection::new(
+            Protocol::Udp,
+            ConnectionState::Established,
+            format!("127.0.0.1:{local_port}"),
+            "127.0.0.53:53",
+            program,
+            "100",
+            program,
+        )
+    }
+
+    fn snapshot_at(
+        secs: u64,
+        previous: &[Connection],
+        connections: Vec<Connection>,
+    ) -> ConnectionSnapshot {
+        ConnectionSnapshot {
+            taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+            diff: ConnectionDiff::between(previous, &connections),
+            ..ConnectionSnapshot::new(connections, TrafficTotals::default())
+        }
+    }
+
+    #[test]
+    fn test_new_sockets_count_as_queries() {
+        let mut tracker = DnsTracker::new();
+        let first = vec![lookup("curl", 40000), lookup("firefox", 40001)];
+        let mut snapshot = snapshot_at(0, &[], first.clone());
+        tracker.record(&mut snapshot);
+        assert_eq!(snapshot.dns_clients.len(), 2);
+
+        // firefox's socket lingers and curl opens two more
+        let second = vec![
+            lookup("curl", 40002),
+            lookup("curl", 40003),
+            lookup("firefox", 40001),
+        ];
+        let mut snapshot = snapshot_at(3, &first, second.clone());
+        tracker.record(&mut snapshot);
+        let counts: Vec<_> = snapshot
+            .dns_clients
+            .iter()
+            .map(|client| (client.process.as_str(), client.queries))
+            .collect();
+        assert_eq!(counts, [("curl(100)", 3), ("firefox(100)", 1)]);
+
+        // Clients quiet for an hour are forgotten
+        let mut snapshot = snapshot_at(3 + 3600, &second, Vec::new());
+        tracker.record(&mut snapshot);
+        assert!(snapshot.dns_clients.is_empty());
+    }
+}
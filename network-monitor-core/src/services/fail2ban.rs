@@ -0,0 +1,226 @@
+use crate::error::{NetworkMonitorError, Result};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+/// Banned IP addresses mapped to the fail2ban jail that banned them
+#[derive(Debug, Clone, Default)]
+pub struct BanList {
+    banned: HashMap<IpAddr, String>,
+}
+
+impl BanList {
+    /// Record `ip` as banned by `jail`; anything but an IP address is ignored
+    pub fn insert(&mut self, ip: &str, jail: &str) {
+        if let Ok(ip) = ip.parse::<IpAddr>() {
+            self.banned.insert(ip.to_canonical(), jail.to_string());
+        }
+    }
+
+    /// Get the jail banning the IP of an address as the tables show it:
+    /// `ip:port`, with IPv6 bracketed or not and `*` for any port, or a bare
+    /// IP. A trailing group is always read as the port, so IPv6 addresses
+    /// only match exactly, never another address sharing their prefix.
+    pub fn jail_for(&self, addr: &str) -> Option<&str> {
+        // Cells are styled on every redraw, most often with nothing banned
+        if self.is_empty() {
+            return None;
+        }
+        let ip = addr
+            .rsplit_once(':')
+            .filter(|(_, port)| *port == "*" || port.parse::<u16>().is_ok())
+            .and_then(|(ip, _)| {
+                ip.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<IpAddr>()
+                    .ok()
+            })
+            .or_else(|| addr.parse().ok())?;
+        self.banned.get(&ip.to_canonical()).map(String::as_str)
+    }
+
+    pub fn len(&self) -> usize {
+        self.banned.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.banned.is_empty()
+    }
+}
+
+/// Service for reading fail2ban's currently banned addresses
+pub struct Fail2banService {
+    enabled: RefCell<bool>,
+    ban_list: RefCell<BanList>,
+    last_refresh: RefCell<Option<Instant>>,
+    refresh_interval: Duration,
+}
+
+impl Fail2banService {
+    pub fn new(enabled: bool) -> Self {
+        Self {
+            enabled: RefCell::new(enabled),
+            ban_list: RefCell::new(BanList::default()),
+            last_refresh: RefCell::new(None),
+            refresh_interval: Duration::from_secs(30),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        *self.enabled.borrow()
+    }
+
+    /// Enable or disable ban list correlation
+    pub fn set_enabled(&self, enabled: bool) {
+        *self.enabled.borrow_mut() = enabled;
+        *self.last_refresh.borrow_mut() = None;
+        if !enabled {
+            *self.ban_list.borrow_mut() = BanList::default();
+        }
+    }
+
+    /// Get the current ban list, querying fail2ban-client at most every 30 seconds.
+    /// Returns an empty list while disabled.
+    pub fn get_ban_list(&self) -> Result<BanList> {
+        if !self.is_enabled() {
+            return Ok(BanList::default());
+        }
+
+        let stale = self
+            .last_refresh
+            .borrow()
+            .is_none_or(|last| last.elapsed() >= self.refresh_interval);
+        if stale {
+            // Record the attempt first so a failing client isn't re-run every tick
+            *self.last_refresh.borrow_mut() = Some(Instant::now());
+            *self.ban_list.borrow_mut() = self.query_fail2ban()?;
+        }
+
+        Ok(self.ban_list.borrow().clone())
+    }
+
+    /// Query every jail's banned IP list through fail2ban-client
+    fn query_fail2ban(&self) -> Result<BanList> {
+        let status = run_fail2ban_client(&["status"])?;
+        let mut ban_list = BanList::default();
+
+        for jail in parse_jail_list(&status) {
+            let jail_status = run_fail2ban_client(&["status", &jail])?;
+            for ip in parse_banned_ips(&jail_status) {
+                ban_list.insert(&ip, &jail);
+            }
+        }
+
+        Ok(ban_list)
+    }
+}
+
+impl Default for Fail2banService {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+fn run_fail2ban_client(args: &[&str]) -> Result<String> {
+    let output = Command::new("fail2ban-client")
+        .args(args)
+        .output()
+        .map_err(|e| {
+            NetworkMonitorError::Fail2ban(format!("fail2ban-client not available: {e}"))
+        })?;
+
+    if !output.status.success() {
+        return Err(NetworkMonitorError::Fail2ban(format!(
+            "fail2ban-client {} failed (root access is usually required): {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Extract jail names from the `Jail list:` line of `fail2ban-client status`
+pub fn parse_jail_list(status: &str) -> Vec<String> {
+    status
+        .lines()
+        .find_map(|line| line.split_once("Jail list:"))
+        .map(|(_, jails)| {
+            jails
+                .split(',')
+                .map(str::trim)
+                .filter(|jail| !jail.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Extract addresses from the `Banned IP list:` line of `fail2ban-client status <jail>`
+pub fn parse_banned_ips(jail_status: &str) -> Vec<String> {
+    jail_status
+        .lines()
+        .find_map(|line| line.split_once("Banned IP list:"))
+        .map(|(_, ips)| ips.split_whitespace().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_jail_list() {
+        let status = "Status\n|- Number of jail:\t2\n`- Jail list:\tsshd, recidive\n";
+        assert_eq!(parse_jail_list(status), vec!["sshd", "recidive"]);
+        assert!(parse_jail_list("Status\n|- Number of jail:\t0\n`- Jail list:\t\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_banned_ips() {
+        let status = "Status for the jail: sshd\n`- Actions\n   |- Currently banned:\t2\n   `- Banned IP list:\t203.0.113.5 2001:db8::1\n";
+        assert_eq!(parse_banned_ips(status), vec!["203.0.113.5", "2001:db8::1"]);
+    }
+
+    #[test]
+    fn test_jail_for_address() {
+        let mut ban_list = BanList::default();
+        ban_list.insert("203.0.113.5", "sshd");
+        ban_list.insert("2001:db8::1", "recidive");
+
+        assert_eq!(ban_list.jail_for("203.0.113.5:22"), Some("sshd"));
+        assert_eq!(ban_list.jail_for("203.0.113.5"), Some("sshd"));
+        assert_eq!(ban_list.jail_for("2001:db8::1:443"), Some("recidive"));
+        assert_eq!(ban_list.jail_for("198.51.100.1:22"), None);
+    }
+
+    #[test]
+    fn test_jail_for_ipv6_matches_the_exact_address() {
+        let mut ban_list = BanList::default();
+        ban_list.insert("2001:db8::1", "recidive");
+        ban_list.insert("203.0.113.5", "sshd");
+        ban_list.insert("not-an-address", "sshd");
+        assert_eq!(ban_list.len(), 2);
+
+        assert_eq!(ban_list.jail_for("[2001:db8::1]:443"), Some("recidive"));
+        assert_eq!(ban_list.jail_for("[2001:db8::1]:*"), Some("recidive"));
+        assert_eq!(ban_list.jail_for("2001:db8::1"), Some("recidive"));
+        assert_eq!(
+            ban_list.jail_for("2001:0db8:0:0:0:0:0:1:443"),
+            Some("recidive")
+        );
+        // Addresses that merely contain the banned one
+        assert_eq!(ban_list.jail_for("[2001:db8::1:443]:22"), None);
+        assert_eq!(ban_list.jail_for("[2001:db8::10]:443"), None);
+        // IPv4 connections on IPv6 sockets
+        assert_eq!(ban_list.jail_for("[::ffff:203.0.113.5]:22"), Some("sshd"));
+    }
+
+    #[test]
+    fn test_disabled_service_returns_empty_list() {
+        let service = Fail2banService::new(false);
+        assert!(service.get_ban_list().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,215 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{BlockedAttempt, Connection};
+use std::fs;
+use std::process::Command;
+
+/// Plain-text log files written by syslog daemons and ulogd (NFLOG)
+const LOG_FILES: [&str; 3] = [
+    "/var/log/kern.log",
+    "/var/log/ulogd/ulogd_syslogemu.log",
+    "/var/log/ulogd_syslogemu.log",
+];
+
+/// Service for reading kernel firewall drop/reject log entries
+pub struct FirewallLogService {
+    max_entries: usize,
+}
+
+impl FirewallLogService {
+    pub fn new() -> Self {
+        Self { max_entries: 500 }
+    }
+
+    /// Get the most recent blocked attempts, newest first, correlated with
+    /// the processes owning the local port where possible
+    pub fn get_blocked_attempts(&self, connections: &[Connection]) -> Result<Vec<BlockedAttempt>> {
+        let log_data = self.read_kernel_log()?;
+
+        let mut attempts: Vec<BlockedAttempt> =
+            log_data.lines().filter_map(parse_log_line).collect();
+        attempts.reverse();
+        attempts.truncate(self.max_entries);
+
+        correlate_processes(&mut attempts, connections);
+        Ok(attempts)
+    }
+
+    /// Read kernel messages from journald, falling back to log files
+    fn read_kernel_log(&self) -> Result<String> {
+        let lines = self.max_entries.to_string();
+        if let Ok(output) = Command::new("journalctl")
+            .args(["-k", "--no-pager", "-q", "-o", "short-iso", "-n", &lines])
+            .args(["--grep", "PROTO="])
+            .output()
+        {
+            // journalctl exits with 1 when --grep has no matches
+            if output.status.success() || output.stderr.is_empty() {
+                return Ok(String::from_utf8_lossy(&output.stdout).into_owned());
+            }
+        }
+
+        for path in LOG_FILES {
+            if let Ok(data) = fs::read_to_string(path) {
+                let matching: Vec<&str> = data.lines().filter(|l| l.contains("PROTO=")).collect();
+                let start = matching.len().saturating_sub(self.max_entries);
+                return Ok(matching[start..].join("\n"));
+            }
+        }
+
+        Err(NetworkMonitorError::FirewallLog(
+            "no readable journal or kernel log (try adding your user to the systemd-journal or adm group)"
+                .to_string(),
+        ))
+    }
+}
+
+impl Default for FirewallLogService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parse a netfilter LOG/NFLOG line such as
+/// `2024-05-01T10:00:00+0000 host kernel: [UFW BLOCK] IN=eth0 OUT= SRC=1.2.3.4 DST=10.0.0.2 ... PROTO=TCP SPT=443 DPT=51234`
+pub fn parse_log_line(line: &str) -> Option<BlockedAttempt> {
+    let fields_start = line.find("IN=")?;
+    let head = &line[..fields_start];
+
+    let mut in_interface = String::new();
+    let mut out_interface = String::new();
+    let mut src = None;
+    let mut dst = None;
+    let mut protocol = None;
+    let mut spt = None;
+    let mut dpt = None;
+
+    for field in line[fields_start..].split_whitespace() {
+        if let Some((key, value)) = field.split_once('=') {
+            match key {
+                "IN" => in_interface = value.to_string(),
+                "OUT" => out_interface = value.to_string(),
+                "SRC" => src = Some(value),
+                "DST" => dst = Some(value),
+                "PROTO" => protocol = Some(value.to_lowercase()),
+                "SPT" => spt = Some(value),
+                "DPT" => dpt = Some(value),
+                _ => {}
+            }
+        }
+    }
+
+    let (timestamp, prefix) = match head.find("kernel:") {
+        Some(pos) => (
+            // Everything before the hostname is the timestamp
+            head[..pos]
+                .trim()
+                .rsplit_once(' ')
+                .map_or("", |(stamp, _host)| stamp.trim())
+                .to_string(),
+            head[pos + "kernel:".len()..].trim(),
+        ),
+        None => (String::new(), head.trim()),
+    };
+    // Strip the optional "[12345.678901]" uptime stamp printed before the prefix
+    let prefix = match prefix
+        .strip_prefix('[')
+        .and_then(|rest| rest.split_once(']'))
+    {
+        Some((uptime, rest)) if uptime.trim().parse::<f64>().is_ok() => rest.trim(),
+        _ => prefix,
+    };
+
+    let join = |addr: &str, port: Option<&str>| match port {
+        Some(port) => format!("{addr}:{port}"),
+        None => addr.to_string(),
+    };
+
+    Some(BlockedAttempt {
+        timestamp,
+        action: if prefix.is_empty() {
+            "BLOCK".to_string()
+        } else {
+            prefix.to_string()
+        },
+        in_interface,
+        out_interface,
+        protocol: protocol?,
+        source: join(src?, spt),
+        destination: join(dst?, dpt),
+        program: "N/A".to_string(),
+        pid: "N/A".to_string(),
+    })
+}
+
+/// Attach the owning process to attempts whose local port matches a known socket
+pub fn correlate_processes(attempts: &mut [BlockedAttempt], connections: &[Connection]) {
+    for attempt in attempts.iter_mut() {
+        let Some(port) = attempt.local_port() else {
+            continue;
+        };
+        let owner = connections.iter().find(|conn| {
+            &*conn.pid != "N/A"
+                && conn.protocol.transport() == attempt.protocol
+                && conn.local.rsplit(':').next().and_then(|p| p.parse().ok()) == Some(port)
+        });
+        if let Some(conn) = owner {
+            attempt.program = conn.program.to_string();
+            attempt.pid = conn.pid.to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    const UFW_LINE: &str = "2024-05-01T10:00:00+0000 host kernel: [UFW BLOCK] IN=eth0 OUT= MAC=00:11 SRC=203.0.113.5 DST=192.168.1.10 LEN=60 PROTO=TCP SPT=44321 DPT=22 WINDOW=0 SYN URGP=0";
+
+    #[test]
+    fn test_parse_inbound_line() {
+        let attempt = parse_log_line(UFW_LINE).unwrap();
+        assert_eq!(attempt.timestamp, "2024-05-01T10:00:00+0000");
+        assert_eq!(attempt.action, "[UFW BLOCK]");
+        assert_eq!(attempt.protocol, "tcp");
+        assert_eq!(attempt.source, "203.0.113.5:44321");
+        assert_eq!(attempt.destination, "192.168.1.10:22");
+        assert!(!attempt.is_outbound());
+        assert_eq!(attempt.local_port(), Some(22));
+    }
+
+    #[test]
+    fn test_parse_outbound_line_with_uptime_stamp() {
+        let line = "May  1 10:00:00 host kernel: [ 1234.567890] nft reject: IN= OUT=wlan0 SRC=10.0.0.2 DST=1.1.1.1 PROTO=UDP SPT=5353 DPT=53";
+        let attempt = parse_log_line(line).unwrap();
+        assert_eq!(attempt.timestamp, "May  1 10:00:00");
+        assert_eq!(attempt.action, "nft reject:");
+        assert!(attempt.is_outbound());
+        assert_eq!(attempt.local_port(), Some(5353));
+    }
+
+    #[test]
+    fn test_parse_icmp_and_garbage() {
+        let line = "kernel: DROP IN=eth0 OUT= SRC=1.2.3.4 DST=5.6.7.8 PROTO=ICMP TYPE=8";
+        let attempt = parse_log_line(line).unwrap();
+        assert_eq!(attempt.source, "1.2.3.4");
+        assert!(parse_log_line("kernel: usb 1-1: new device").is_none());
+        assert!(parse_log_line("IN=eth0 OUT= SRC=1.2.3.4").is_none());
+    }
+
+    #[test]
+    fn test_correlate_processes() {
+        let mut attempts = vec![parse_log_line(UFW_LINE).unwrap()];
+        let connections = vec![Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Listen,
+            "0.0.0.0:22".to_string(),
+            "0.0.0.0:0".to_string(),
+            "sshd".to_string(),
+            "812".to_string(),
+            "/usr/sbin/sshd".to_string(),
+        )];
+        correlate_processes(&mut attempts, &connections);
+        assert_eq!(attempts[0].get_process_display(), "sshd(812)");
+    }
+}
@@ -0,0 +1,132 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::services::netlink::{self, attributes, for_each_message, NLA_HEADER_LEN};
+
+/// struct genlmsghdr
+pub const GENL_HEADER_LEN: usize = 4;
+
+/// Generic netlink controller resolving family names (linux/genetlink.h)
+const GENL_ID_CTRL: u16 = 0x10;
+const CTRL_CMD_GETFAMILY: u8 = 3;
+const CTRL_ATTR_FAMILY_ID: u16 = 1;
+const CTRL_ATTR_FAMILY_NAME: u16 = 2;
+
+/// Socket talking to the families of generic netlink, such as wireguard
+/// and nl80211
+pub struct GenericNetlink {
+    socket: netlink::Socket,
+    buffer: Vec<u8>,
+}
+
+impl GenericNetlink {
+    pub fn open() -> Result<Self> {
+        Ok(Self {
+            socket: netlink::Socket::netlink(libc::NETLINK_GENERIC, 0).map_err(error)?,
+            // Dumps of devices with many peers or stations come in large datagrams
+            buffer: vec![0u8; 32 * 1024],
+        })
+    }
+
+    /// Number of family `name`, which fails when its module isn't loaded
+    pub fn family_id(&mut self, name: &str) -> Result<u16> {
+        let mut value = name.as_bytes().to_vec();
+        value.push(0);
+        self.send(&request(
+            GENL_ID_CTRL,
+            libc::NLM_F_REQUEST,
+            CTRL_CMD_GETFAMILY,
+            1,
+            &[(CTRL_ATTR_FAMILY_NAME, &value)],
+        ))?;
+        let mut family = None;
+        for_each_message(self.receive()?, |kind, payload| {
+            if kind == GENL_ID_CTRL {
+                family = attributes(payload.get(GENL_HEADER_LEN..).unwrap_or_default())
+                    .find(|(kind, _)| *kind == CTRL_ATTR_FAMILY_ID)
+                    .and_then(|(_, value)| {
+                        Some(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?))
+                    });
+            }
+        })?;
+        family.ok_or_else(|| NetworkMonitorError::Netlink(format!("no {name} family")))
+    }
+
+    pub fn send(&self, message: &[u8]) -> Result<()> {
+        self.socket.send(message).map_err(error)
+    }
+
+    /// Next datagram of replies
+    pub fn receive(&mut self) -> Result<&[u8]> {
+        let received = self.socket.recv(&mut self.buffer).map_err(error)?;
+        Ok(&self.buffer[..received])
+    }
+
+    /// Send the dump `request` and call `message` with the payload of each
+    /// reply of `family`, after its genlmsghdr, until the dump ends
+    pub fn dump(
+        &mut self,
+        request: &[u8],
+        family: u16,
+        mut message: impl FnMut(&[u8]),
+    ) -> Result<()> {
+        self.send(request)?;
+        loop {
+            let done = for_each_message(self.receive()?, |kind, payload| {
+                if kind == family {
+                    message(payload.get(GENL_HEADER_LEN..).unwrap_or_default());
+                }
+            })?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn error(e: std::io::Error) -> NetworkMonitorError {
+    NetworkMonitorError::Netlink(e.to_string())
+}
+
+/// Generic netlink request for `command` carrying `attributes`
+pub fn request(
+    family: u16,
+    flags: i32,
+    command: u8,
+    version: u8,
+    attributes: &[(u16, &[u8])],
+) -> Vec<u8> {
+    let mut message = Vec::new();
+    // nlmsghdr, its length filled in below
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&family.to_ne_bytes());
+    message.extend_from_slice(&(flags as u16).to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    // genlmsghdr
+    message.extend_from_slice(&[command, version, 0, 0]);
+    // nlattr, each padded to 4 bytes
+    for (kind, value) in attributes {
+        message.extend_from_slice(&((NLA_HEADER_LEN + value.len()) as u16).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(value);
+        message.resize((message.len() + 3) & !3, 0);
+    }
+    let total_len = message.len() as u32;
+    message[..4].copy_from_slice(&total_len.to_ne_bytes());
+    message
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::netlink::NLMSG_HEADER_LEN;
+
+    #[test]
+    fn test_request_layout() {
+        let request = request(0x1a, libc::NLM_F_REQUEST, 0, 1, &[(2, b"wg0\0")]);
+        assert_eq!(request.len(), NLMSG_HEADER_LEN + GENL_HEADER_LEN + 8);
+        assert_eq!(u32::from_ne_bytes(request[..4].try_into().unwrap()), 28);
+        let attributes: Vec<_> =
+            attributes(&request[NLMSG_HEADER_LEN + GENL_HEADER_LEN..]).collect();
+        assert_eq!(attributes, [(2, &b"wg0\0"[..])]);
+    }
+}
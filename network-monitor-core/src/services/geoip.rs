@@ -0,0 +1,188 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::Connection;
+use crate::utils::parse_endpoint;
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+use std::sync::Arc;
+
+/// First address, last address and country of a range
+type Range<T> = (T, T, Arc<str>);
+
+/// Country of IP address ranges, from CSV files with one range per line:
+/// "FIRST,LAST,CC" where FIRST and LAST are addresses (DB-IP lite, Tor's
+/// geoip6) or IPv4 addresses as integers (Tor's geoip). Lines starting with
+/// '#' and ranges of unknown countries ("??") are skipped.
+#[derive(Debug, Default)]
+pub struct GeoIpDatabase {
+    v4: Vec<Range<u32>>,
+    v6: Vec<Range<u128>>,
+}
+
+impl GeoIpDatabase {
+    /// Read the ranges of every file in `paths`, such as one for IPv4 and
+    /// one for IPv6
+    pub fn load(paths: &[impl AsRef<Path>]) -> Result<Self> {
+        let mut database = Self::default();
+        for path in paths {
+            let path = path.as_ref();
+            let text = fs::read_to_string(path).map_err(|e| {
+                NetworkMonitorError::GeoIp(format!("could not read {}: {e}", path.display()))
+            })?;
+            database
+                .add(&text)
+                .map_err(|e| NetworkMonitorError::GeoIp(format!("{}: {e}", path.display())))?;
+        }
+        Ok(database)
+    }
+
+    /// Database of the ranges in `text`
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut database = Self::default();
+        database.add(text).map_err(NetworkMonitorError::GeoIp)?;
+        Ok(database)
+    }
+
+    fn add(&mut self, text: &str) -> std::result::Result<(), String> {
+        let mut countries: HashMap<String, Arc<str>> = HashMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let fields: Vec<&str> = line
+                .split(',')
+                .map(|f| f.trim().trim_matches('"'))
+                .collect();
+            let [first, last, code, ..] = fields[..] else {
+                return Err(format!("line {}: expected FIRST,LAST,COUNTRY", number + 1));
+            };
+            if code.len() != 2 || !code.bytes().all(|b| b.is_ascii_alphabetic()) {
+                continue;
+            }
+            let country = countries
+                .entry(code.to_ascii_uppercase())
+                .or_insert_with_key(|code| code.as_str().into())
+                .clone();
+            let invalid = || format!("line {}: invalid range {first}-{last}", number + 1);
+            if let (Ok(first), Ok(last)) = (first.parse::<u32>(), last.parse::<u32>()) {
+                self.v4.push((first, last, country));
+                continue;
+            }
+            match (first.parse::<IpAddr>(), last.parse::<IpAddr>()) {
+                (Ok(IpAddr::V4(first)), Ok(IpAddr::V4(last))) => {
+                    self.v4.push((first.into(), last.into(), country))
+                }
+                (Ok(IpAddr::V6(first)), Ok(IpAddr::V6(last))) => {
+                    self.v6.push((first.into(), last.into(), country))
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        self.v4.sort_by_key(|range| range.0);
+        self.v6.sort_by_key(|range| range.0);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.v4.is_empty() && self.v6.is_empty()
+    }
+
+    /// Number of IPv4 and IPv6 ranges
+    pub fn len(&self) -> usize {
+        self.v4.len() + self.v6.len()
+    }
+
+    /// Country of `ip`, None for addresses in no range such as private ones
+    pub fn country(&self, ip: IpAddr) -> Option<&Arc<str>> {
+        match ip.to_canonical() {
+            IpAddr::V4(ip) => lookup(&self.v4, ip.into()),
+            IpAddr::V6(ip) => lookup(&self.v6, ip.into()),
+        }
+    }
+
+    /// Fill in the country of the remote end of every connection
+    pub fn annotate(&self, connections: &mut [Connection]) {
+        for conn in connections {
+            conn.country = parse_endpoint(&conn.remote)
+                .filter(|remote| !remote.ip().is_unspecified())
+                .and_then(|remote| self.country(remote.ip()))
+                .cloned();
+        }
+    }
+}
+
+/// Country of the range containing `address` among `ranges`, sorted by
+/// their first address
+fn lookup<T: Ord + Copy>(ranges: &[Range<T>], address: T) -> Option<&Arc<str>> {
+    let after = ranges.partition_point(|range| range.0 <= address);
+    let (_, last, country) = ranges.get(after.checked_sub(1)?)?;
+    (address <= *last).then_some(country)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    const DATABASE: &str = "\
+# Tor geoip format, IPv4 addresses as integers
+16777216,16777471,AU
+134744064,134744319,US
+\"2.16.0.0\",\"2.16.255.255\",\"de\"
+2001:db8::,2001:db8::ffff:ffff:ffff:ffff,NL
+10.0.0.0,10.255.255.255,??
+";
+
+    #[test]
+    fn test_ranges_are_looked_up() {
+        let database = GeoIpDatabase::parse(DATABASE).unwrap();
+        let country = |ip: &str| database.country(ip.parse().unwrap()).map(|c| c.to_string());
+
+        assert_eq!(country("1.0.0.1").as_deref(), Some("AU"));
+        assert_eq!(country("8.8.8.8").as_deref(), Some("US"));
+        assert_eq!(country("2.16.40.1").as_deref(), Some("DE"));
+        assert_eq!(country("::ffff:8.8.8.4").as_deref(), Some("US"));
+        assert_eq!(country("2001:db8::1").as_deref(), Some("NL"));
+        assert_eq!(country("1.0.1.0"), None);
+        assert_eq!(country("10.0.0.1"), None);
+        assert_eq!(country("0.0.0.1"), None);
+    }
+
+    #[test]
+    fn test_connections_get_the_country_of_their_peer() {
+        let database = GeoIpDatabase::parse(DATABASE).unwrap();
+        let mut connections = [
+            Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                "192.168.1.2:50000",
+                "8.8.8.8:443",
+                "curl",
+                "100",
+                "curl",
+            ),
+            Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Listen,
+                "0.0.0.0:22",
+                "0.0.0.0:0",
+                "sshd",
+                "1",
+                "sshd",
+            ),
+        ];
+        database.annotate(&mut connections);
+        assert_eq!(connections[0].country.as_deref(), Some("US"));
+        assert_eq!(connections[1].country, None);
+    }
+
+    #[test]
+    fn test_malformed_ranges_are_rejected() {
+        let error = GeoIpDatabase::parse("1.0.0.0,2001:db8::,AU").unwrap_err();
+        assert!(error.to_string().contains("line 1"), "{error}");
+        assert!(GeoIpDatabase::parse("1.0.0.0").is_err());
+        assert!(GeoIpDatabase::parse("# empty\n").unwrap().is_empty());
+    }
+}
@@ -0,0 +1,447 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, ConnectionState, Protocol};
+use std::io;
+use std::mem::{size_of, MaybeUninit};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Flavor of proc_pidfdinfo returning a struct socket_fdinfo (sys/proc_info.h)
+const PROC_PIDFDSOCKETINFO: libc::c_int = 3;
+/// soi_kind values for inet sockets, plain and with TCP state
+const SOCKINFO_IN: libc::c_int = 1;
+const SOCKINFO_TCP: libc::c_int = 2;
+/// insi_vflag bits
+const INI_IPV4: u8 = 0x1;
+const INI_IPV6: u8 = 0x2;
+
+/// struct proc_fileinfo
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct ProcFileInfo {
+    fi_openflags: u32,
+    fi_status: u32,
+    fi_offset: i64,
+    fi_type: i32,
+    fi_guardflags: u32,
+}
+
+/// struct sockbuf_info
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SockbufInfo {
+    sbi_cc: u32,
+    sbi_hiwat: u32,
+    sbi_mbcnt: u32,
+    sbi_mbmax: u32,
+    sbi_lowat: u32,
+    sbi_flags: i16,
+    sbi_timeo: i16,
+}
+
+/// struct in_sockinfo; addresses are in4in6_addr/in6_addr unions, IPv4 in
+/// the last four bytes
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct InSockInfo {
+    insi_fport: i32,
+    insi_lport: i32,
+    insi_gencnt: u64,
+    insi_flags: u32,
+    insi_flow: u32,
+    insi_vflag: u8,
+    insi_ip_ttl: u8,
+    rfu_1: u32,
+    insi_faddr: [u8; 16],
+    insi_laddr: [u8; 16],
+    insi_v4: u8,
+    insi_v6: [u32; 3],
+}
+
+/// struct tcp_sockinfo
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct TcpSockInfo {
+    tcpsi_ini: InSockInfo,
+    tcpsi_state: i32,
+    tcpsi_timer: [i32; 4],
+    tcpsi_mss: i32,
+    tcpsi_flags: u32,
+    rfu_1: u32,
+    tcpsi_tp: u64,
+}
+
+/// soi_proto; the unix socket member, two 255 byte addresses, sizes it
+#[repr(C)]
+#[derive(Clone, Copy)]
+union SocketProto {
+    pri_in: InSockInfo,
+    pri_tcp: TcpSockInfo,
+    pri_un: [u64; 66],
+}
+
+/// struct socket_info
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SocketInfo {
+    soi_stat: libc::vinfo_stat,
+    soi_so: u64,
+    soi_pcb: u64,
+    soi_type: i32,
+    soi_protocol: i32,
+    soi_family: i32,
+    soi_options: i16,
+    soi_linger: i16,
+    soi_state: i16,
+    soi_qlen: i16,
+    soi_incqlen: i16,
+    soi_qlimit: i16,
+    soi_timeo: i16,
+    soi_error: u16,
+    soi_oobmark: u32,
+    soi_rcv: SockbufInfo,
+    soi_snd: SockbufInfo,
+    soi_kind: i32,
+    rfu_1: u32,
+    soi_proto: SocketProto,
+}
+
+/// struct socket_fdinfo
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct SocketFdInfo {
+    pfi: ProcFileInfo,
+    psi: SocketInfo,
+}
+
+// The kernel rejects buffers that aren't exactly PROC_PIDFDSOCKETINFO_SIZE
+const _: () = assert!(size_of::<SocketFdInfo>() == 792);
+
+/// TCP and UDP sockets of every process we may inspect, with their owners.
+/// Processes of other users are skipped unless running as root.
+pub fn connections() -> Result<Vec<Connection>> {
+    let mut connections = Vec::new();
+    for pid in all_pids()? {
+        let sockets: Vec<Socket> = socket_fds(pid)
+            .into_iter()
+            .filter_map(|fd| socket(pid, fd))
+            .collect();
+        if sockets.is_empty() {
+            continue;
+        }
+        let (name, command) = process_details(pid);
+        let pid = pid.to_string();
+        connections.extend(sockets.into_iter().map(|socket| {
+            Connection::new(
+                socket.protocol,
+                socket.state,
+                socket.local,
+                socket.remote,
+                name.as_str(),
+                pid.as_str(),
+                command.as_str(),
+            )
+        }));
+    }
+    Ok(connections)
+}
+
+/// Bytes received and sent on all non-loopback interfaces, from the 64-bit
+/// counters of sysctl NET_RT_IFLIST2
+pub fn interface_totals() -> Result<(u64, u64)> {
+    let mut mib = [libc::CTL_NET, libc::PF_ROUTE, 0, 0, libc::NET_RT_IFLIST2, 0];
+    let table = sysctl(&mut mib)?;
+
+    let (mut received, mut sent) = (0, 0);
+    let mut offset = 0;
+    while let Some(header) = table.get(offset..offset + 4) {
+        let len = u16::from_ne_bytes([header[0], header[1]]) as usize;
+        if len == 0 {
+            break;
+        }
+        if header[3] as libc::c_int == libc::RTM_IFINFO2
+            && offset + size_of::<libc::if_msghdr2>() <= table.len()
+        {
+            // SAFETY: the bounds were checked above; read_unaligned copes with
+            // the packing of the routing messages
+            let message =
+                unsafe { (table.as_ptr().add(offset) as *const libc::if_msghdr2).read_unaligned() };
+            if message.ifm_flags & libc::IFF_LOOPBACK == 0 {
+                received += message.ifm_data.ifi_ibytes;
+                sent += message.ifm_data.ifi_obytes;
+            }
+        }
+        offset += len;
+    }
+    Ok((received, sent))
+}
+
+/// One inet socket as libproc reports it
+struct Socket {
+    protocol: Protocol,
+    state: ConnectionState,
+    local: String,
+    remote: String,
+}
+
+fn all_pids() -> Result<Vec<libc::pid_t>> {
+    // SAFETY: a null buffer asks for the number of processes
+    let count = unsafe { libc::proc_listallpids(std::ptr::null_mut(), 0) };
+    if count <= 0 {
+        return Err(os_error("proc_listallpids"));
+    }
+    // Room for processes started in between
+    let mut pids: Vec<libc::pid_t> = vec![0; count as usize + 64];
+    // SAFETY: pids is valid for writes of the given size in bytes
+    let count = unsafe {
+        libc::proc_listallpids(
+            pids.as_mut_ptr() as *mut libc::c_void,
+            (pids.len() * size_of::<libc::pid_t>()) as libc::c_int,
+        )
+    };
+    if count <= 0 {
+        return Err(os_error("proc_listallpids"));
+    }
+    pids.truncate(count as usize);
+    Ok(pids)
+}
+
+/// Socket descriptors of `pid`, empty if we may not inspect it
+fn socket_fds(pid: libc::pid_t) -> Vec<i32> {
+    // SAFETY: a null buffer asks for the size of the descriptor table
+    let size =
+        unsafe { libc::proc_pidinfo(pid, libc::PROC_PIDLISTFDS, 0, std::ptr::null_mut(), 0) };
+    if size <= 0 {
+        return Vec::new();
+    }
+    let mut fds: Vec<libc::proc_fdinfo> =
+        Vec::with_capacity(size as usize / size_of::<libc::proc_fdinfo>() + 16);
+    // SAFETY: fds has capacity for the given size in bytes
+    let size = unsafe {
+        libc::proc_pidinfo(
+            pid,
+            libc::PROC_PIDLISTFDS,
+            0,
+            fds.as_mut_ptr() as *mut libc::c_void,
+            (fds.capacity() * size_of::<libc::proc_fdinfo>()) as libc::c_int,
+        )
+    };
+    if size <= 0 {
+        return Vec::new();
+    }
+    // SAFETY: the kernel initialized `size` bytes of whole proc_fdinfo records
+    unsafe { fds.set_len(size as usize / size_of::<libc::proc_fdinfo>()) };
+    fds.iter()
+        .filter(|fd| fd.proc_fdtype == libc::PROX_FDTYPE_SOCKET as u32)
+        .map(|fd| fd.proc_fd)
+        .collect()
+}
+
+fn socket(pid: libc::pid_t, fd: i32) -> Option<Socket> {
+    let mut info = MaybeUninit::<SocketFdInfo>::uninit();
+    // SAFETY: info is valid for writes of a whole socket_fdinfo
+    let size = unsafe {
+        libc::proc_pidfdinfo(
+            pid,
+            fd,
+            PROC_PIDFDSOCKETINFO,
+            info.as_mut_ptr() as *mut libc::c_void,
+            size_of::<SocketFdInfo>() as libc::c_int,
+        )
+    };
+    if size as usize != size_of::<SocketFdInfo>() {
+        return None;
+    }
+    // SAFETY: the kernel filled the whole struct
+    let info = unsafe { info.assume_init() };
+    decode(&info.psi)
+}
+
+/// Connection fields of an inet TCP or UDP socket, None for anything else
+fn decode(info: &SocketInfo) -> Option<Socket> {
+    let v6 = match info.soi_family {
+        libc::AF_INET => false,
+        libc::AF_INET6 => true,
+        _ => return None,
+    };
+    let (protocol, state, inet) = match (info.soi_protocol, info.soi_kind) {
+        (libc::IPPROTO_TCP, SOCKINFO_TCP) => {
+            // SAFETY: soi_kind says the union holds a tcp_sockinfo
+            let tcp = unsafe { info.soi_proto.pri_tcp };
+            let protocol = if v6 { Protocol::Tcp6 } else { Protocol::Tcp };
+            (protocol, tcp_state(tcp.tcpsi_state), tcp.tcpsi_ini)
+        }
+        (libc::IPPROTO_UDP, SOCKINFO_IN) => {
+            // SAFETY: soi_kind says the union holds an in_sockinfo
+            let inet = unsafe { info.soi_proto.pri_in };
+            let protocol = if v6 { Protocol::Udp6 } else { Protocol::Udp };
+            // Like the Linux tables: connected UDP sockets are established
+            let state = if inet.insi_fport == 0 {
+                ConnectionState::Close
+            } else {
+                ConnectionState::Established
+            };
+            (protocol, state, inet)
+        }
+        _ => return None,
+    };
+
+    let address = |bytes: [u8; 16], port: i32| {
+        let ip = if inet.insi_vflag & INI_IPV6 != 0 && inet.insi_vflag & INI_IPV4 == 0 {
+            IpAddr::V6(Ipv6Addr::from(bytes))
+        } else {
+            IpAddr::V4(Ipv4Addr::new(bytes[12], bytes[13], bytes[14], bytes[15]))
+        };
+        // Ports are kept in network byte order
+        format!("{}:{}", ip, u16::from_be(port as u16))
+    };
+    Some(Socket {
+        protocol,
+        state,
+        local: address(inet.insi_laddr, inet.insi_lport),
+        remote: address(inet.insi_faddr, inet.insi_fport),
+    })
+}
+
+/// Map the TSI_S_* states of tcp_sockinfo
+fn tcp_state(state: i32) -> ConnectionState {
+    match state {
+        0 => ConnectionState::Close,
+        1 => ConnectionState::Listen,
+        2 => ConnectionState::SynSent,
+        3 => ConnectionState::SynRecv,
+        4 => ConnectionState::Established,
+        5 => ConnectionState::CloseWait,
+        6 => ConnectionState::FinWait1,
+        7 => ConnectionState::Closing,
+        8 => ConnectionState::LastAck,
+        9 => ConnectionState::FinWait2,
+        10 => ConnectionState::TimeWait,
+        other => ConnectionState::Unknown(other as u8),
+    }
+}
+
+/// Process name and command line; the arguments come from sysctl
+/// KERN_PROCARGS2, which only answers for our own processes unless root
+fn process_details(pid: libc::pid_t) -> (String, String) {
+    let mut buffer = [0u8; 256];
+    // SAFETY: buffer is valid for writes of its full length
+    let len = unsafe {
+        libc::proc_name(
+            pid,
+            buffer.as_mut_ptr() as *mut libc::c_void,
+            buffer.len() as u32,
+        )
+    };
+    let name = match len {
+        1.. => String::from_utf8_lossy(&buffer[..len as usize]).into_owned(),
+        _ => "N/A".to_string(),
+    };
+
+    let command = sysctl(&mut [libc::CTL_KERN, libc::KERN_PROCARGS2, pid])
+        .ok()
+        .and_then(|args| parse_procargs(&args))
+        .unwrap_or_else(|| name.clone());
+    (name, command)
+}
+
+/// Decode a KERN_PROCARGS2 buffer: argc, the executable path, padding, then
+/// argc NUL-terminated arguments
+fn parse_procargs(args: &[u8]) -> Option<String> {
+    let argc = i32::from_ne_bytes(args.get(..4)?.try_into().ok()?) as usize;
+    let mut rest = args.get(4..)?;
+    // Skip the executable path and the NULs padding it
+    let path_end = rest.iter().position(|&byte| byte == 0)?;
+    rest = &rest[path_end..];
+    let start = rest.iter().position(|&byte| byte != 0)?;
+    rest = &rest[start..];
+
+    let words: Vec<String> = rest
+        .split(|&byte| byte == 0)
+        .take(argc)
+        .map(|word| String::from_utf8_lossy(word).into_owned())
+        .collect();
+    (!words.is_empty()).then(|| words.join(" "))
+}
+
+/// Read a whole sysctl value
+fn sysctl(mib: &mut [libc::c_int]) -> Result<Vec<u8>> {
+    let mut size = 0;
+    // SAFETY: a null buffer asks for the size of the value
+    let status = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            std::ptr::null_mut(),
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if status < 0 {
+        return Err(os_error("sysctl"));
+    }
+    let mut value = vec![0u8; size];
+    // SAFETY: value is valid for writes of `size` bytes
+    let status = unsafe {
+        libc::sysctl(
+            mib.as_mut_ptr(),
+            mib.len() as u32,
+            value.as_mut_ptr() as *mut libc::c_void,
+            &mut size,
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    if status < 0 {
+        return Err(os_error("sysctl"));
+    }
+    value.truncate(size);
+    Ok(value)
+}
+
+fn os_error(call: &str) -> NetworkMonitorError {
+    NetworkMonitorError::Libproc(format!("{call}: {}", io::Error::last_os_error()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    #[test]
+    fn test_tcp_states() {
+        assert_eq!(tcp_state(1), ConnectionState::Listen);
+        assert_eq!(tcp_state(4), ConnectionState::Established);
+        assert_eq!(tcp_state(10), ConnectionState::TimeWait);
+        assert_eq!(tcp_state(42), ConnectionState::Unknown(42));
+    }
+
+    #[test]
+    fn test_parse_procargs() {
+        let mut args = 2i32.to_ne_bytes().to_vec();
+        args.extend_from_slice(b"/usr/bin/curl\0\0\0curl\0-I\0TERM=xterm\0");
+        assert_eq!(parse_procargs(&args).as_deref(), Some("curl -I"));
+        assert_eq!(parse_procargs(&[1, 0]), None);
+    }
+
+    #[test]
+    fn test_finds_own_listener() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let local = listener.local_addr().unwrap().to_string();
+        let connections = connections().unwrap();
+        let own = connections
+            .iter()
+            .find(|conn| *conn.local == *local)
+            .expect("listener not reported");
+        assert_eq!(own.state, ConnectionState::Listen);
+        assert_eq!(*own.pid, *std::process::id().to_string());
+        // proc_name truncates to MAXCOMLEN
+        let exe = std::env::current_exe().unwrap();
+        let exe = exe.file_name().unwrap().to_string_lossy();
+        assert!(exe.starts_with(&*own.program), "{} vs {exe}", own.program);
+    }
+
+    #[test]
+    fn test_interface_totals() {
+        assert!(interface_totals().is_ok());
+    }
+}
@@ -0,0 +1,304 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{LinkChange, LinkEvent};
+use crate::services::netlink::{self, attributes, for_each_message, read_u32, NLMSG_HEADER_LEN};
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// struct ifinfomsg
+const IFINFO_LEN: usize = 16;
+/// struct ifaddrmsg
+const IFADDR_LEN: usize = 8;
+
+/// Message types and multicast groups (linux/rtnetlink.h)
+const RTM_NEWLINK: u16 = 16;
+const RTM_DELLINK: u16 = 17;
+const RTM_GETLINK: u16 = 18;
+const RTM_NEWADDR: u16 = 20;
+const RTM_DELADDR: u16 = 21;
+const RTM_GETADDR: u16 = 22;
+const RTMGRP_LINK: u32 = 0x1;
+const RTMGRP_IPV4_IFADDR: u32 = 0x10;
+const RTMGRP_IPV6_IFADDR: u32 = 0x100;
+
+const IFLA_IFNAME: u16 = 3;
+const IFA_ADDRESS: u16 = 1;
+const IFA_LOCAL: u16 = 2;
+/// Carrier present (linux/if.h)
+const IFF_LOWER_UP: u32 = 0x10000;
+
+/// Largest datagram the kernel sends for rtnetlink dumps
+const BUFFER_LEN: usize = 32 * 1024;
+
+/// Subscription to interface and address changes through rtnetlink, which
+/// needs no privileges. Receives time out after a second so the thread
+/// reading them can notice when to stop.
+pub struct LinkEventListener {
+    socket: netlink::Socket,
+    tracker: LinkTracker,
+    buffer: Vec<u8>,
+}
+
+impl LinkEventListener {
+    /// Subscribe, then read the current interfaces and addresses so that
+    /// only later changes are reported
+    pub fn subscribe() -> Result<Self> {
+        let socket = netlink::Socket::netlink(libc::NETLINK_ROUTE, 0).map_err(error)?;
+        socket.set_receive_timeout(1).map_err(error)?;
+        socket
+            .bind(RTMGRP_LINK | RTMGRP_IPV4_IFADDR | RTMGRP_IPV6_IFADDR)
+            .map_err(error)?;
+
+        let mut listener = Self {
+            socket,
+            tracker: LinkTracker::default(),
+            buffer: vec![0; BUFFER_LEN],
+        };
+        listener.dump(RTM_GETLINK)?;
+        listener.dump(RTM_GETADDR)?;
+        Ok(listener)
+    }
+
+    /// Changes reported within the next second
+    pub fn receive(&mut self) -> Vec<LinkEvent> {
+        match self.socket.recv(&mut self.buffer) {
+            Ok(received) => self.tracker.handle(&self.buffer[..received]),
+            // EAGAIN on timeout; ENOBUFS when events were dropped, in which
+            // case the next ones still apply
+            Err(_) => Vec::new(),
+        }
+    }
+
+    /// Learn the current state from a dump of `kind`, without reporting it
+    fn dump(&mut self, kind: u16) -> Result<()> {
+        self.socket.send(&dump_request(kind)).map_err(error)?;
+        loop {
+            let received = self.socket.recv(&mut self.buffer).map_err(error)?;
+            let done = for_each_message(&self.buffer[..received], |kind, payload| {
+                self.tracker.message(kind, payload);
+            })
+            .map_err(|e| NetworkMonitorError::LinkEvents(e.to_string()))?;
+            if done {
+                return Ok(());
+            }
+        }
+    }
+}
+
+fn error(e: io::Error) -> NetworkMonitorError {
+    NetworkMonitorError::LinkEvents(e.to_string())
+}
+
+/// Netlink request dumping every link or address: a header and a struct
+/// rtgenmsg for any family, padded to four bytes
+fn dump_request(kind: u16) -> Vec<u8> {
+    let total_len = NLMSG_HEADER_LEN + 4;
+    let mut message = Vec::with_capacity(total_len);
+    message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+    message.extend_from_slice(&kind.to_ne_bytes());
+    message.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    message.extend_from_slice(&1u32.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&[libc::AF_UNSPEC as u8, 0, 0, 0]);
+    message
+}
+
+/// Interfaces known from earlier messages, to tell changes from the
+/// repeated notifications the kernel sends, e.g. for address lifetimes
+#[derive(Debug, Default)]
+pub struct LinkTracker {
+    links: HashMap<u32, Link>,
+}
+
+#[derive(Debug)]
+struct Link {
+    name: String,
+    connected: bool,
+    addresses: HashSet<IpAddr>,
+}
+
+impl LinkTracker {
+    /// Apply the rtnetlink messages of `datagram`, returning what changed
+    pub fn handle(&mut self, datagram: &[u8]) -> Vec<LinkEvent> {
+        let mut events = Vec::new();
+        // Notifications carry no errors; what a broken datagram held before
+        // the break still applies
+        let _ = for_each_message(datagram, |kind, payload| {
+            events.extend(self.message(kind, payload));
+        });
+        events
+    }
+
+    fn message(&mut self, kind: u16, payload: &[u8]) -> Option<LinkEvent> {
+        match kind {
+            RTM_NEWLINK | RTM_DELLINK => self.link_message(kind, payload),
+            RTM_NEWADDR | RTM_DELADDR => self.address_message(kind, payload),
+            _ => None,
+        }
+    }
+
+    fn link_message(&mut self, kind: u16, payload: &[u8]) -> Option<LinkEvent> {
+        let index = read_u32(payload, 4)?;
+        let flags = read_u32(payload, 8)?;
+        if kind == RTM_DELLINK {
+            let link = self.links.remove(&index)?;
+            return Some(LinkEvent::new(link.name, LinkChange::Removed));
+        }
+        let name = attributes(payload.get(IFINFO_LEN..)?)
+            .find(|&(kind, _)| kind == IFLA_IFNAME)
+            .map(|(_, value)| c_string(value))?;
+        let connected = flags & libc::IFF_UP as u32 != 0 && flags & IFF_LOWER_UP != 0;
+        match self.links.entry(index) {
+            Entry::Vacant(entry) => {
+                entry.insert(Link {
+                    name: name.clone(),
+                    connected,
+                    addresses: HashSet::new(),
+                });
+                Some(LinkEvent::new(name, LinkChange::Added))
+            }
+            Entry::Occupied(mut entry) => {
+                let link = entry.get_mut();
+                link.name = name;
+                if link.connected == connected {
+                    return None;
+                }
+                link.connected = connected;
+                let change = if connected {
+                    LinkChange::Connected
+                } else {
+                    LinkChange::Disconnected
+                };
+                Some(LinkEvent::new(link.name.clone(), change))
+            }
+        }
+    }
+
+    fn address_message(&mut self, kind: u16, payload: &[u8]) -> Option<LinkEvent> {
+        let family = i32::from(*payload.first()?);
+        let index = read_u32(payload, 4)?;
+        // IFA_LOCAL is the interface's own address on point-to-point links,
+        // where IFA_ADDRESS is the peer's
+        let mut address = None;
+        for (attribute, value) in attributes(payload.get(IFADDR_LEN..)?) {
+            match attribute {
+                IFA_LOCAL => address = ip(family, value),
+                IFA_ADDRESS if address.is_none() => address = ip(family, value),
+                _ => {}
+            }
+        }
+        let address = address?;
+        let link = self.links.get_mut(&index)?;
+        let change = if kind == RTM_NEWADDR {
+            link.addresses
+                .insert(address)
+                .then_some(LinkChange::AddressAdded(address))?
+        } else {
+            link.addresses
+                .remove(&address)
+                .then_some(LinkChange::AddressRemoved(address))?
+        };
+        Some(LinkEvent::new(link.name.clone(), change))
+    }
+}
+
+/// NUL-terminated interface name
+fn c_string(value: &[u8]) -> String {
+    let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+    String::from_utf8_lossy(&value[..end]).into_owned()
+}
+
+fn ip(family: i32, value: &[u8]) -> Option<IpAddr> {
+    match family {
+        libc::AF_INET => Some(Ipv4Addr::from(<[u8; 4]>::try_from(value).ok()?).into()),
+        libc::AF_INET6 => Some(Ipv6Addr::from(<[u8; 16]>::try_from(value).ok()?).into()),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::netlink::testing::{attribute, message};
+
+    fn link(kind: u16, index: u32, name: &str, flags: u32) -> Vec<u8> {
+        let mut payload = vec![0u8; 4];
+        payload.extend_from_slice(&index.to_ne_bytes());
+        payload.extend_from_slice(&flags.to_ne_bytes());
+        payload.extend_from_slice(&0u32.to_ne_bytes());
+        payload.extend(attribute(IFLA_IFNAME, format!("{name}\0").as_bytes()));
+        message(kind, &payload)
+    }
+
+    fn address(kind: u16, index: u32, ip: IpAddr) -> Vec<u8> {
+        let (family, octets) = match ip {
+            IpAddr::V4(ip) => (libc::AF_INET, ip.octets().to_vec()),
+            IpAddr::V6(ip) => (libc::AF_INET6, ip.octets().to_vec()),
+        };
+        let mut payload = vec![family as u8, 24, 0, 0];
+        payload.extend_from_slice(&index.to_ne_bytes());
+        payload.extend(attribute(IFA_ADDRESS, &octets));
+        message(kind, &payload)
+    }
+
+    #[test]
+    fn test_only_changes_are_reported() {
+        let up = libc::IFF_UP as u32 | IFF_LOWER_UP;
+        let mut tracker = LinkTracker::default();
+        let events = |tracker: &mut LinkTracker, datagram: Vec<u8>| -> Vec<String> {
+            tracker
+                .handle(&datagram)
+                .iter()
+                .map(ToString::to_string)
+                .collect()
+        };
+
+        assert_eq!(
+            events(&mut tracker, link(RTM_NEWLINK, 3, "wlan0", up)),
+            ["wlan0 added"]
+        );
+        // Other attribute changes repeat the same state
+        assert!(events(&mut tracker, link(RTM_NEWLINK, 3, "wlan0", up)).is_empty());
+        assert_eq!(
+            events(
+                &mut tracker,
+                link(RTM_NEWLINK, 3, "wlan0", libc::IFF_UP as u32)
+            ),
+            ["wlan0 disconnected"]
+        );
+
+        let ip: IpAddr = "192.168.1.20".parse().unwrap();
+        let mut datagram = link(RTM_NEWLINK, 3, "wlan0", up);
+        datagram.extend(address(RTM_NEWADDR, 3, ip));
+        assert_eq!(
+            events(&mut tracker, datagram),
+            ["wlan0 connected", "new address 192.168.1.20 on wlan0"]
+        );
+        // Lifetime updates of a known address
+        assert!(events(&mut tracker, address(RTM_NEWADDR, 3, ip)).is_empty());
+        assert_eq!(
+            events(&mut tracker, address(RTM_DELADDR, 3, ip)),
+            ["address 192.168.1.20 removed from wlan0"]
+        );
+        assert_eq!(
+            events(&mut tracker, link(RTM_DELLINK, 3, "wlan0", 0)),
+            ["wlan0 removed"]
+        );
+        // Addresses of interfaces never seen are ignored
+        assert!(events(
+            &mut tracker,
+            address(RTM_NEWADDR, 9, "fe80::1".parse().unwrap())
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_truncated_messages_are_ignored() {
+        let mut tracker = LinkTracker::default();
+        let datagram = link(RTM_NEWLINK, 3, "eth0", 0);
+        assert!(tracker.handle(&datagram[..datagram.len() - 2]).is_empty());
+        assert!(tracker.handle(&datagram[..10]).is_empty());
+        assert_eq!(tracker.handle(&datagram).len(), 1);
+    }
+}
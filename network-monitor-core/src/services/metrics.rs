@@ -0,0 +1,117 @@
+use crate::utils::CacheStats;
+use std::time::Duration;
+
+/// Weight of the newest sample in the running average
+const AVERAGE_WEIGHT: f64 = 0.2;
+
+/// Recent durations of one repeated operation
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timing {
+    pub last: Duration,
+    /// Exponential moving average, so old spikes fade out
+    pub average: Duration,
+    pub max: Duration,
+    pub samples: u64,
+}
+
+impl Timing {
+    pub fn record(&mut self, duration: Duration) {
+        self.average = if self.samples == 0 {
+            duration
+        } else {
+            self.average.mul_f64(1.0 - AVERAGE_WEIGHT) + duration.mul_f64(AVERAGE_WEIGHT)
+        };
+        self.last = duration;
+        self.max = self.max.max(duration);
+        self.samples += 1;
+    }
+
+    /// e.g. "last 12.3 ms · avg 10.1 ms · max 40.2 ms"
+    pub fn summary(&self) -> String {
+        if self.samples == 0 {
+            return "no samples yet".to_string();
+        }
+        format!(
+            "last {} · avg {} · max {}",
+            format_millis(self.last),
+            format_millis(self.average),
+            format_millis(self.max)
+        )
+    }
+}
+
+fn format_millis(duration: Duration) -> String {
+    format!("{:.1} ms", duration.as_secs_f64() * 1000.0)
+}
+
+/// Self-profiling numbers for the diagnostics overlay. Timings are recorded by
+/// the UI; cache figures and resolver latency are copied in from the services.
+#[derive(Debug, Clone, Default)]
+pub struct Metrics {
+    /// /proc scan and rate calculation on the collector thread
+    pub collection: Timing,
+    /// Building the table for a refresh on the UI thread
+    pub render: Timing,
+    /// Reverse DNS lookups on the resolver workers
+    pub resolver_latency: Timing,
+    pub process_cache: CacheStats,
+    pub resolver_cache: CacheStats,
+}
+
+impl Metrics {
+    /// Label and value rows, in display order
+    pub fn rows(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("Collection", self.collection.summary()),
+            ("Render", self.render.summary()),
+            ("DNS lookups", self.resolver_latency.summary()),
+            ("Process cache", cache_summary(&self.process_cache)),
+            ("Resolver cache", cache_summary(&self.resolver_cache)),
+        ]
+    }
+}
+
+fn cache_summary(stats: &CacheStats) -> String {
+    match stats.hit_rate() {
+        Some(rate) => format!("{} · {:.0}% hits", stats.summary(), rate * 100.0),
+        None => stats.summary(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timing_tracks_last_average_and_max() {
+        let mut timing = Timing::default();
+        assert_eq!(timing.summary(), "no samples yet");
+
+        timing.record(Duration::from_millis(10));
+        assert_eq!(timing.average, Duration::from_millis(10));
+        timing.record(Duration::from_millis(60));
+        assert_eq!(timing.last, Duration::from_millis(60));
+        assert_eq!(timing.max, Duration::from_millis(60));
+        assert_eq!(timing.average, Duration::from_millis(20));
+        assert_eq!(timing.summary(), "last 60.0 ms · avg 20.0 ms · max 60.0 ms");
+    }
+
+    #[test]
+    fn test_cache_rows_include_hit_rate() {
+        let metrics = Metrics {
+            resolver_cache: CacheStats {
+                entries: 3,
+                capacity: 10,
+                hits: 3,
+                misses: 1,
+                ..CacheStats::default()
+            },
+            ..Metrics::default()
+        };
+        let rows = metrics.rows();
+        assert_eq!(rows[4].0, "Resolver cache");
+        assert!(rows[4].1.ends_with("75% hits"), "{}", rows[4].1);
+        // No lookups yet, so no rate
+        assert!(!rows[3].1.contains("hits"));
+    }
+}
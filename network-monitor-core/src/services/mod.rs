@@ -0,0 +1,91 @@
+pub mod activity;
+pub mod audit;
+pub mod backend;
+pub mod bandwidth_limit;
+#[cfg(target_os = "linux")]
+mod bpf;
+pub mod cache_limits;
+pub mod capture;
+pub mod cgroup_traffic;
+pub mod collector;
+pub mod conntrack;
+pub mod countries;
+pub mod dns;
+pub mod fail2ban;
+pub mod firewall;
+#[cfg(target_os = "linux")]
+pub mod genetlink;
+pub mod geoip;
+#[cfg(target_os = "macos")]
+pub mod libproc;
+#[cfg(target_os = "linux")]
+pub mod link_events;
+pub mod metrics;
+#[cfg(target_os = "linux")]
+pub mod netlink;
+pub mod network;
+pub mod packages;
+pub mod permissions;
+pub mod privileged_helper;
+#[cfg(target_os = "linux")]
+pub mod proc_events;
+pub mod process_cache;
+pub mod process_inspector;
+pub mod proxy;
+pub mod rates;
+pub mod refresh_pacer;
+pub mod remote;
+pub mod resolver;
+pub mod scopes;
+pub mod short_lived;
+#[cfg(target_os = "linux")]
+pub mod sock_diag;
+#[cfg(target_os = "linux")]
+pub mod socket_events;
+pub mod sorted_connections;
+#[cfg(test)]
+mod tests;
+pub mod tunnels;
+pub mod warm_start;
+#[cfg(target_os = "linux")]
+pub mod wireguard;
+#[cfg(target_os = "linux")]
+pub mod wireless;
+
+pub use activity::ActivityTracker;
+pub use audit::OutboundAudit;
+#[cfg(target_os = "macos")]
+pub use backend::LibprocCollector;
+pub use backend::{
+    AndroidCollector, Backend, Collector, FixtureCollector, NetlinkCollector, ProcfsCollector,
+    RouterCollector,
+};
+pub use cache_limits::CacheLimits;
+pub use capture::SniCapture;
+pub use cgroup_traffic::ApplicationAccounting;
+#[cfg(target_os = "linux")]
+pub use cgroup_traffic::CgroupTraffic;
+pub use collector::{CollectorEvent, CollectorRequest, ConnectionCollector};
+pub use countries::CountryTracker;
+pub use dns::DnsTracker;
+pub use fail2ban::{BanList, Fail2banService};
+pub use firewall::FirewallLogService;
+pub use geoip::GeoIpDatabase;
+pub use metrics::{Metrics, Timing};
+pub use network::{Attribution, NetworkService};
+pub use packages::PackageNames;
+pub use permissions::PermissionReport;
+pub use privileged_helper::PrivilegedHelper;
+pub use process_cache::ProcessCache;
+pub use process_inspector::ProcessInspector;
+pub use proxy::ProxyDetector;
+pub use rates::RateTracker;
+pub use refresh_pacer::RefreshPacer;
+pub use remote::{CombinedCollector, SshCollector};
+pub use resolver::AddressResolver;
+pub use scopes::ScopeTracker;
+#[cfg(target_os = "linux")]
+pub use short_lived::ConnectTracer;
+pub use short_lived::ShortLivedTracker;
+pub use sorted_connections::SortedConnections;
+pub use warm_start::WarmStart;
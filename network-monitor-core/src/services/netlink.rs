@@ -0,0 +1,224 @@
+//! Sockets and message framing shared by the netlink families the monitor
+//! talks to: sock_diag, the proc connector, rtnetlink and generic netlink
+
+use crate::error::{NetworkMonitorError, Result};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+/// struct nlmsghdr
+pub const NLMSG_HEADER_LEN: usize = 16;
+/// struct nlattr, and struct rtattr of the same layout
+pub const NLA_HEADER_LEN: usize = 4;
+/// Attribute type bits, without the nested and byte order flags
+const NLA_TYPE_MASK: u16 = 0x3fff;
+
+/// Datagram socket owned by one reader. Errors name the failed call and
+/// keep the kind of the OS error, for callers telling timeouts apart.
+pub struct Socket(OwnedFd);
+
+impl Socket {
+    /// Netlink socket of `protocol`, e.g. NETLINK_ROUTE; `flags` such as
+    /// SOCK_NONBLOCK are added to the type
+    pub fn netlink(protocol: i32, flags: i32) -> io::Result<Self> {
+        Self::open(libc::AF_NETLINK, libc::SOCK_DGRAM | flags, protocol)
+    }
+
+    /// Socket of any `domain`, closed on exec
+    pub fn open(domain: i32, kind: i32, protocol: i32) -> io::Result<Self> {
+        // SAFETY: plain socket(2) call, the returned descriptor is owned below
+        let fd = unsafe { libc::socket(domain, kind | libc::SOCK_CLOEXEC, protocol) };
+        if fd < 0 {
+            return Err(os_error("socket"));
+        }
+        // SAFETY: fd is a freshly created descriptor nobody else owns
+        Ok(Self(unsafe { OwnedFd::from_raw_fd(fd) }))
+    }
+
+    /// Join the netlink multicast `groups`
+    pub fn bind(&self, groups: u32) -> io::Result<()> {
+        // SAFETY: sockaddr_nl is plain data, all-zero is a valid value
+        let mut address: libc::sockaddr_nl = unsafe { std::mem::zeroed() };
+        address.nl_family = libc::AF_NETLINK as libc::sa_family_t;
+        address.nl_groups = groups;
+        // SAFETY: address points to a properly sized sockaddr_nl
+        let bound = unsafe {
+            libc::bind(
+                self.0.as_raw_fd(),
+                &address as *const libc::sockaddr_nl as *const libc::sockaddr,
+                std::mem::size_of::<libc::sockaddr_nl>() as libc::socklen_t,
+            )
+        };
+        if bound < 0 {
+            return Err(os_error("bind"));
+        }
+        Ok(())
+    }
+
+    /// Make blocking receives fail with WouldBlock after `seconds`
+    pub fn set_receive_timeout(&self, seconds: libc::time_t) -> io::Result<()> {
+        let timeout = libc::timeval {
+            tv_sec: seconds,
+            tv_usec: 0,
+        };
+        // SAFETY: timeout is a timeval of the size given
+        let set = unsafe {
+            libc::setsockopt(
+                self.0.as_raw_fd(),
+                libc::SOL_SOCKET,
+                libc::SO_RCVTIMEO,
+                &timeout as *const libc::timeval as *const libc::c_void,
+                std::mem::size_of::<libc::timeval>() as libc::socklen_t,
+            )
+        };
+        if set < 0 {
+            return Err(os_error("setsockopt"));
+        }
+        Ok(())
+    }
+
+    pub fn send(&self, message: &[u8]) -> io::Result<()> {
+        // SAFETY: message is a valid buffer of the given length
+        let sent = unsafe {
+            libc::send(
+                self.0.as_raw_fd(),
+                message.as_ptr() as *const libc::c_void,
+                message.len(),
+                0,
+            )
+        };
+        if sent < 0 {
+            return Err(os_error("send"));
+        }
+        Ok(())
+    }
+
+    /// Next datagram into `buffer`, truncated to its size; returns its length
+    pub fn recv(&self, buffer: &mut [u8]) -> io::Result<usize> {
+        // SAFETY: buffer is valid for writes of its full length
+        let received = unsafe {
+            libc::recv(
+                self.0.as_raw_fd(),
+                buffer.as_mut_ptr() as *mut libc::c_void,
+                buffer.len(),
+                0,
+            )
+        };
+        if received < 0 {
+            return Err(os_error("recv"));
+        }
+        Ok(received as usize)
+    }
+}
+
+fn os_error(call: &str) -> io::Error {
+    let error = io::Error::last_os_error();
+    io::Error::new(error.kind(), format!("{call}: {error}"))
+}
+
+/// Call `message` with the type and payload of each netlink message in
+/// `datagram`; true once the end of a dump is reached
+pub fn for_each_message(datagram: &[u8], mut message: impl FnMut(u16, &[u8])) -> Result<bool> {
+    let mut offset = 0;
+    while let Some(header) = datagram.get(offset..offset + NLMSG_HEADER_LEN) {
+        let len = u32::from_ne_bytes(header[0..4].try_into().unwrap_or_default()) as usize;
+        let kind = u16::from_ne_bytes(header[4..6].try_into().unwrap_or_default());
+        if len < NLMSG_HEADER_LEN {
+            return Err(NetworkMonitorError::Netlink(format!(
+                "truncated netlink message ({len} bytes)"
+            )));
+        }
+        let payload = datagram
+            .get(offset + NLMSG_HEADER_LEN..offset + len)
+            .unwrap_or_default();
+        match i32::from(kind) {
+            libc::NLMSG_DONE => return Ok(true),
+            libc::NLMSG_ERROR => {
+                let errno = payload
+                    .get(0..4)
+                    .and_then(|bytes| bytes.try_into().ok())
+                    .map_or(0, i32::from_ne_bytes);
+                // An error of 0 acknowledges a request
+                if errno == 0 {
+                    return Ok(true);
+                }
+                return Err(NetworkMonitorError::Netlink(
+                    io::Error::from_raw_os_error(-errno).to_string(),
+                ));
+            }
+            _ => message(kind, payload),
+        }
+        // Messages are padded to 4 bytes
+        offset += (len + 3) & !3;
+    }
+    Ok(false)
+}
+
+/// Type and value of each attribute in `data`
+pub fn attributes(mut data: &[u8]) -> impl Iterator<Item = (u16, &[u8])> {
+    std::iter::from_fn(move || {
+        let header = data.get(..NLA_HEADER_LEN)?;
+        let len = usize::from(u16::from_ne_bytes([header[0], header[1]]));
+        let kind = u16::from_ne_bytes([header[2], header[3]]) & NLA_TYPE_MASK;
+        let value = data.get(NLA_HEADER_LEN..len)?;
+        // Attributes are padded to 4 bytes too
+        data = data.get((len + 3) & !3..).unwrap_or_default();
+        Some((kind, value))
+    })
+}
+
+/// Native-endian u32 at `offset` of a message, if it's long enough
+pub fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_ne_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+/// Attribute and message builders for the parsers' tests
+#[cfg(test)]
+pub mod testing {
+    use super::*;
+
+    pub fn attribute(kind: u16, value: &[u8]) -> Vec<u8> {
+        let mut attribute = Vec::new();
+        attribute.extend_from_slice(&((NLA_HEADER_LEN + value.len()) as u16).to_ne_bytes());
+        attribute.extend_from_slice(&kind.to_ne_bytes());
+        attribute.extend_from_slice(value);
+        attribute.resize((attribute.len() + 3) & !3, 0);
+        attribute
+    }
+
+    pub fn message(kind: u16, payload: &[u8]) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&((NLMSG_HEADER_LEN + payload.len()) as u32).to_ne_bytes());
+        message.extend_from_slice(&kind.to_ne_bytes());
+        message.extend_from_slice(&[0; 10]);
+        message.extend_from_slice(payload);
+        message
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::{attribute, message};
+    use super::*;
+
+    #[test]
+    fn test_messages_and_attributes() {
+        let mut datagram = message(16, &attribute(3, b"eth0\0"));
+        datagram.extend(message(libc::NLMSG_DONE as u16, &[0; 4]));
+        let mut seen = Vec::new();
+        assert!(for_each_message(&datagram, |kind, payload| {
+            seen.extend(
+                attributes(payload).map(|(attribute, value)| (kind, attribute, value.to_vec())),
+            );
+        })
+        .unwrap());
+        assert_eq!(seen, [(16, 3, b"eth0\0".to_vec())]);
+
+        assert!(
+            for_each_message(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], |_, _| {}).is_err()
+        );
+        assert_eq!(read_u32(&7u32.to_ne_bytes(), 0), Some(7));
+        assert_eq!(read_u32(&[0; 3], 0), None);
+    }
+}
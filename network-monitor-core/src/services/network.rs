@@ -0,0 +1,781 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::ports::DEFAULT_LOCAL_PORT_RANGE;
+use crate::models::{
+    CgroupBytes, ConnectEvent, Connection, ConnectionState, MulticastGroup, ProcessIO,
+    ProcessIOMap, Protocol, ProtocolCounters, Route, RoutingTable, SocketOwner, Tunnel,
+    WirelessLink, PERMISSION_DENIED,
+};
+use crate::services::permissions::credentials_in;
+#[cfg(target_os = "linux")]
+use crate::services::sock_diag;
+use crate::services::{tunnels, PackageNames};
+use crate::utils::{
+    parse_endpoint, parse_igmp, parse_igmp6, parse_ipv6_routes, parse_port_range,
+    parse_proc_net_line, parse_routes, parse_sctp_assoc, parse_sctp_endpoint, parse_snmp,
+    proc_net_lines, CacheStats, CircuitBreaker, EnhancedErrorRecovery, ErrorRecovery,
+};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Read;
+use std::ops::RangeInclusive;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Failed reads in a row after which a /proc/net table is left alone for a while
+const TABLE_FAILURE_THRESHOLD: u32 = 3;
+/// How long a failing /proc/net table is skipped, e.g. tcp6 with IPv6 disabled
+const TABLE_RETRY_AFTER: Duration = Duration::from_secs(30);
+
+/// How sockets are matched to an owner
+#[derive(Debug, Clone, Default)]
+pub enum Attribution {
+    /// Process holding the socket inode, found through /proc/*/fd
+    #[default]
+    Processes,
+    /// App package of the socket UID, for Android where /proc/*/fd of other
+    /// apps can't be read
+    Packages(PackageNames),
+}
+
+/// Service for monitoring network connections
+pub struct NetworkService {
+    last_update_time: std::cell::RefCell<Instant>,
+    /// /proc/net table contents, kept to avoid reallocating every refresh
+    read_buffer: std::cell::RefCell<Vec<u8>>,
+    /// Per /proc/net table, so one that can't be read is skipped instead of
+    /// retried and logged every refresh
+    table_breakers: std::cell::RefCell<HashMap<&'static str, CircuitBreaker>>,
+    process_cache: std::cell::RefCell<crate::services::ProcessCache>,
+    effective_uid: u32,
+    privileged: bool,
+    /// Normally /proc; benchmarks and tests point this at a fixture tree
+    proc_root: PathBuf,
+    attribution: Attribution,
+    /// Traffic counters attached on first use when running privileged
+    #[cfg(target_os = "linux")]
+    cgroup_traffic: std::cell::OnceCell<Option<crate::services::CgroupTraffic>>,
+    /// Connect tracer attached on first use when running privileged
+    #[cfg(target_os = "linux")]
+    connect_tracer: std::cell::OnceCell<Option<crate::services::ConnectTracer>>,
+}
+
+impl NetworkService {
+    pub fn new() -> Self {
+        Self::with_process_cache(PathBuf::from("/proc"), crate::services::ProcessCache::new())
+    }
+
+    /// Service reading sockets and processes from `proc_root` instead of /proc
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        let proc_root = proc_root.into();
+        let process_cache = crate::services::ProcessCache::with_proc_root(&proc_root);
+        Self::with_process_cache(proc_root, process_cache)
+    }
+
+    fn with_process_cache(
+        proc_root: PathBuf,
+        process_cache: crate::services::ProcessCache,
+    ) -> Self {
+        let (effective_uid, privileged) = credentials_in(&proc_root);
+
+        Self {
+            last_update_time: std::cell::RefCell::new(Instant::now()),
+            read_buffer: std::cell::RefCell::new(Vec::new()),
+            table_breakers: std::cell::RefCell::new(HashMap::new()),
+            process_cache: std::cell::RefCell::new(process_cache),
+            effective_uid,
+            privileged,
+            proc_root,
+            attribution: Attribution::default(),
+            #[cfg(target_os = "linux")]
+            cgroup_traffic: std::cell::OnceCell::new(),
+            #[cfg(target_os = "linux")]
+            connect_tracer: std::cell::OnceCell::new(),
+        }
+    }
+
+    /// Change how sockets are matched to owners
+    pub fn set_attribution(&mut self, attribution: Attribution) {
+        self.attribution = attribution;
+    }
+
+    /// Get all network connections using native Rust socket APIs
+    pub fn get_connections(&self) -> Result<Vec<Connection>> {
+        self.process_cache.borrow_mut().apply_process_events();
+        let mut connections = ErrorRecovery::get_connections_with_fallback(
+            || self.get_tcp_connections(),
+            || self.get_udp_connections(),
+        );
+        connections.extend(self.get_sctp_connections());
+        connections.extend(self.get_dccp_connections());
+
+        Ok(connections)
+    }
+
+    /// Like `get_connections`, but list sockets through netlink sock_diag
+    /// instead of the /proc/net tables
+    #[cfg(target_os = "linux")]
+    pub fn get_connections_netlink(&self) -> Result<Vec<Connection>> {
+        self.process_cache.borrow_mut().apply_process_events();
+        let mut algorithms = HashMap::new();
+        let mut connections: Vec<Connection> = sock_diag::dump_all()?
+            .into_iter()
+            .map(|socket| {
+                let mut conn = self.socket_connection(
+                    socket.protocol,
+                    socket.state,
+                    format!("{}:{}", socket.local.ip(), socket.local.port()),
+                    format!("{}:{}", socket.remote.ip(), socket.remote.port()),
+                    socket.inode,
+                    Some(socket.uid),
+                );
+                conn.send_queue = socket.send_queue;
+                conn.recv_queue = socket.recv_queue;
+                conn.timer = socket.timer;
+                conn.congestion = socket.congestion.map(|name| intern(&mut algorithms, name));
+                conn
+            })
+            .collect();
+        connections.extend(self.get_sctp_connections());
+        connections.extend(self.get_dccp_connections());
+        Ok(connections)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub fn get_connections_netlink(&self) -> Result<Vec<Connection>> {
+        Err(crate::error::NetworkMonitorError::SockDiag(
+            "netlink sock_diag needs Linux".to_string(),
+        ))
+    }
+
+    /// Resolve other users' processes through the pkexec helper (prompts for authentication)
+    pub fn enable_privileged_helper(&self) -> Result<()> {
+        let helper = crate::services::PrivilegedHelper::spawn()?;
+        self.process_cache
+            .borrow_mut()
+            .set_privileged_helper(helper)
+    }
+
+    /// Apply the process cache entry limit
+    pub fn set_cache_limits(&self, limits: &crate::services::CacheLimits) {
+        self.process_cache
+            .borrow_mut()
+            .set_entry_limit(limits.process_entries);
+    }
+
+    /// Socket owners known to the process cache, to start the next session with
+    pub fn socket_owners(&self) -> HashMap<u64, SocketOwner> {
+        self.process_cache.borrow().owners()
+    }
+
+    /// Start from socket owners a previous session saved, with the I/O
+    /// counters it read `age` ago. Returns the PIDs still running the same
+    /// program, whose counters can be compared.
+    pub fn warm_start(&self, owners: HashMap<u64, SocketOwner>, age: Duration) -> HashSet<String> {
+        let now = Instant::now();
+        *self.last_update_time.borrow_mut() = now.checked_sub(age).unwrap_or(now);
+        self.process_cache.borrow_mut().import_saved_owners(owners)
+    }
+
+    /// Size of the process cache
+    pub fn cache_stats(&self) -> CacheStats {
+        self.process_cache.borrow().stats()
+    }
+
+    /// Get TCP connections from /proc/net/tcp
+    fn get_tcp_connections(&self) -> Result<Vec<Connection>> {
+        let mut connections = Vec::new();
+
+        // Try IPv4 and IPv6 TCP connections
+        self.read_proc_net(
+            "net/tcp",
+            Protocol::Tcp,
+            ConnectionState::Listen,
+            &mut connections,
+        )?;
+        self.read_proc_net(
+            "net/tcp6",
+            Protocol::Tcp6,
+            ConnectionState::Listen,
+            &mut connections,
+        )?;
+
+        Ok(connections)
+    }
+
+    /// Get UDP connections from /proc/net/udp
+    fn get_udp_connections(&self) -> Result<Vec<Connection>> {
+        let mut connections = Vec::new();
+
+        // Try IPv4 and IPv6 UDP connections
+        self.read_proc_net(
+            "net/udp",
+            Protocol::Udp,
+            ConnectionState::Close,
+            &mut connections,
+        )?;
+        self.read_proc_net(
+            "net/udp6",
+            Protocol::Udp6,
+            ConnectionState::Close,
+            &mut connections,
+        )?;
+
+        Ok(connections)
+    }
+
+    /// SCTP listeners and associations from net/sctp/eps and
+    /// net/sctp/assocs; none when the sctp module isn't loaded
+    fn get_sctp_connections(&self) -> Vec<Connection> {
+        let mut connections = Vec::new();
+        for (table, listening) in [("net/sctp/eps", true), ("net/sctp/assocs", false)] {
+            let path = self.proc_root.join(table);
+            let Ok(text) = fs::read_to_string(&path) else {
+                continue;
+            };
+            for line in text.lines().skip(1) {
+                let socket = if listening {
+                    parse_sctp_endpoint(line)
+                } else {
+                    parse_sctp_assoc(line)
+                        .map_err(|e| tracing::debug!("Skipping line of {}: {}", path.display(), e))
+                        .ok()
+                };
+                let Some(socket) = socket else {
+                    continue;
+                };
+                let protocol = match parse_endpoint(&socket.local) {
+                    Some(local) if local.is_ipv6() => Protocol::Sctp6,
+                    _ => Protocol::Sctp,
+                };
+                let mut conn = self.socket_connection(
+                    protocol,
+                    socket.state.unwrap_or(ConnectionState::Established),
+                    socket.local,
+                    socket.remote,
+                    socket.inode,
+                    socket.uid,
+                );
+                conn.send_queue = socket.tx_queue;
+                conn.recv_queue = socket.rx_queue;
+                connections.push(conn);
+            }
+        }
+        connections
+    }
+
+    /// DCCP sockets from net/dccp and net/dccp6 where the kernel provides
+    /// them. They are laid out like net/tcp, and DCCP numbers its states
+    /// after their TCP counterparts.
+    fn get_dccp_connections(&self) -> Vec<Connection> {
+        let mut connections = Vec::new();
+        for (table, protocol) in [("net/dccp", Protocol::Dccp), ("net/dccp6", Protocol::Dccp6)] {
+            if !self.proc_root.join(table).exists() {
+                continue;
+            }
+            if let Err(e) =
+                self.read_proc_net(table, protocol, ConnectionState::Listen, &mut connections)
+            {
+                tracing::debug!("Could not read {}: {}", table, e);
+            }
+        }
+        connections
+    }
+
+    /// Ports the kernel assigns to outgoing sockets, the Linux default when
+    /// net.ipv4.ip_local_port_range can't be read
+    pub fn local_port_range(&self) -> RangeInclusive<u16> {
+        let path = self.proc_root.join("sys/net/ipv4/ip_local_port_range");
+        fs::read_to_string(&path)
+            .map_err(NetworkMonitorError::from)
+            .and_then(|text| parse_port_range(&text))
+            .unwrap_or_else(|e| {
+                tracing::debug!("Could not read {}: {}", path.display(), e);
+                DEFAULT_LOCAL_PORT_RANGE
+            })
+    }
+
+    /// Kernel-wide TCP and UDP counters from net/snmp, with the extended
+    /// TCP ones of net/netstat when readable; None without net/snmp
+    pub fn protocol_counters(&self) -> Option<ProtocolCounters> {
+        let path = self.proc_root.join("net/snmp");
+        let mut tables = match fs::read_to_string(&path) {
+            Ok(text) => parse_snmp(&text),
+            Err(e) => {
+                tracing::debug!("Could not read {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        if let Ok(text) = fs::read_to_string(self.proc_root.join("net/netstat")) {
+            tables.extend(parse_snmp(&text));
+        }
+        ProtocolCounters::from_tables(&tables)
+    }
+
+    /// IPv4 and IPv6 routes of the main table, from net/route and
+    /// net/ipv6_route; a missing file leaves its family out
+    pub fn routing_table(&self) -> RoutingTable {
+        let mut routes = Vec::new();
+        for (table, parse) in [
+            ("net/route", parse_routes as fn(&str) -> Vec<Route>),
+            ("net/ipv6_route", parse_ipv6_routes),
+        ] {
+            let path = self.proc_root.join(table);
+            match fs::read_to_string(&path) {
+                Ok(text) => routes.extend(parse(&text)),
+                Err(e) => tracing::debug!("Could not read {}: {}", path.display(), e),
+            }
+        }
+        RoutingTable::new(routes)
+    }
+
+    /// Multicast groups joined on each interface, from net/igmp and
+    /// net/igmp6; a missing file leaves its family out
+    pub fn multicast_groups(&self) -> Vec<MulticastGroup> {
+        let mut groups = Vec::new();
+        for (table, parse) in [
+            ("net/igmp", parse_igmp as fn(&str) -> Vec<MulticastGroup>),
+            ("net/igmp6", parse_igmp6),
+        ] {
+            let path = self.proc_root.join(table);
+            match fs::read_to_string(&path) {
+                Ok(text) => groups.extend(parse(&text)),
+                Err(e) => tracing::debug!("Could not read {}: {}", path.display(), e),
+            }
+        }
+        groups
+    }
+
+    /// WireGuard and TUN/TAP interfaces with their counters, addresses and,
+    /// for WireGuard, peers. Sysfs is read next to the proc root; addresses
+    /// and peers are only looked up on the live system.
+    pub fn tunnels(&self) -> Vec<Tunnel> {
+        let mut tunnels = tunnels::read_tunnels(&self.proc_root.with_file_name("sys"));
+        if tunnels.is_empty() || self.proc_root != Path::new("/proc") {
+            return tunnels;
+        }
+        let mut addresses = tunnels::interface_addresses();
+        for tunnel in &mut tunnels {
+            tunnel.addresses = addresses.remove(&tunnel.name).unwrap_or_default();
+            #[cfg(target_os = "linux")]
+            if tunnel.kind == crate::models::TunnelKind::WireGuard {
+                tunnel.peers = crate::services::wireguard::peers(&tunnel.name)
+                    .map_err(|e| tracing::debug!("Could not read {} peers: {}", tunnel.name, e))
+                    .ok();
+            }
+        }
+        tunnels
+    }
+
+    /// Bandwidth limits by PID; only looked up on the live system
+    pub fn bandwidth_limits(&self) -> HashMap<String, u64> {
+        if self.proc_root != Path::new("/proc") {
+            return HashMap::new();
+        }
+        crate::services::bandwidth_limit::current_limits()
+    }
+
+    /// Bytes sent and received by each cgroup ID since counting started:
+    /// counted here when running privileged, by the privileged helper
+    /// otherwise. None without either, and off the live system.
+    pub fn cgroup_traffic(&self) -> Option<HashMap<u64, CgroupBytes>> {
+        if self.proc_root != Path::new("/proc") {
+            return None;
+        }
+        #[cfg(target_os = "linux")]
+        if self.privileged {
+            return self
+                .cgroup_traffic
+                .get_or_init(|| {
+                    crate::services::CgroupTraffic::attach()
+                        .map_err(|e| tracing::info!("No per-application accounting: {}", e))
+                        .ok()
+                })
+                .as_ref()
+                .map(crate::services::CgroupTraffic::counters);
+        }
+        self.process_cache.borrow_mut().helper_cgroup_traffic()
+    }
+
+    /// Connection attempts made since the previous call: traced here when
+    /// running privileged, by the privileged helper otherwise. None without
+    /// either, and off the live system.
+    pub fn connect_events(&self) -> Option<Vec<ConnectEvent>> {
+        if self.proc_root != Path::new("/proc") {
+            return None;
+        }
+        #[cfg(target_os = "linux")]
+        if self.privileged {
+            return self
+                .connect_tracer
+                .get_or_init(|| {
+                    crate::services::ConnectTracer::attach()
+                        .map_err(|e| tracing::info!("No short-lived connections: {}", e))
+                        .ok()
+                })
+                .as_ref()
+                .map(crate::services::ConnectTracer::events);
+        }
+        self.process_cache.borrow_mut().helper_connect_events()
+    }
+
+    /// Congestion control algorithm of each TCP socket by local and remote
+    /// address, for backends whose tables don't carry it; only looked up on
+    /// the live system
+    pub fn congestion_controls(&self) -> HashMap<(Arc<str>, Arc<str>), Arc<str>> {
+        if self.proc_root != Path::new("/proc") {
+            return HashMap::new();
+        }
+        #[cfg(target_os = "linux")]
+        {
+            let sockets = sock_diag::dump_tcp().unwrap_or_else(|e| {
+                tracing::debug!("Could not read congestion control algorithms: {}", e);
+                Vec::new()
+            });
+            let mut algorithms = HashMap::new();
+            sockets
+                .into_iter()
+                .filter_map(|socket| {
+                    let algorithm = intern(&mut algorithms, socket.congestion?);
+                    let local = format!("{}:{}", socket.local.ip(), socket.local.port());
+                    let remote = format!("{}:{}", socket.remote.ip(), socket.remote.port());
+                    Some(((local.into(), remote.into()), algorithm))
+                })
+                .collect()
+        }
+        #[cfg(not(target_os = "linux"))]
+        HashMap::new()
+    }
+
+    /// Wi-Fi client interfaces and their link quality; only looked up on
+    /// the live system
+    pub fn wireless_links(&self) -> Vec<WirelessLink> {
+        if self.proc_root != Path::new("/proc") {
+            return Vec::new();
+        }
+        #[cfg(target_os = "linux")]
+        return crate::services::wireless::links().unwrap_or_else(|e| {
+            tracing::debug!("Could not query wireless interfaces: {}", e);
+            Vec::new()
+        });
+        #[cfg(not(target_os = "linux"))]
+        Vec::new()
+    }
+
+    /// Parse one /proc/net table into `connections`. The file is read into a
+    /// buffer reused across refreshes and lines are parsed in place.
+    fn read_proc_net(
+        &self,
+        table: &'static str,
+        protocol: Protocol,
+        default_state: ConnectionState,
+        connections: &mut Vec<Connection>,
+    ) -> Result<()> {
+        let path = self.proc_root.join(table);
+        let mut buffer = self.read_buffer.borrow_mut();
+        let mut breakers = self.table_breakers.borrow_mut();
+        let breaker = breakers
+            .entry(table)
+            .or_insert_with(|| CircuitBreaker::new(TABLE_FAILURE_THRESHOLD, TABLE_RETRY_AFTER));
+
+        // One quick retry covers reads interrupted while the kernel rebuilds the table
+        let read = breaker.call(|| {
+            EnhancedErrorRecovery::retry_with_backoff(
+                || {
+                    buffer.clear();
+                    fs::File::open(&path)?.read_to_end(&mut buffer)?;
+                    Ok(())
+                },
+                1,
+                Duration::from_millis(5),
+            )
+        });
+        match read {
+            Ok(()) => {}
+            Err(NetworkMonitorError::CircuitOpen) => return Ok(()),
+            Err(e) => {
+                // Log the start of an outage and when the table gets skipped, not every pass
+                if breaker.failure_count() == 1 {
+                    tracing::warn!("Could not read {}: {}", path.display(), e);
+                } else if breaker.failure_count() == TABLE_FAILURE_THRESHOLD {
+                    tracing::warn!(
+                        "Skipping {} for {}s after repeated failures",
+                        path.display(),
+                        TABLE_RETRY_AFTER.as_secs()
+                    );
+                } else {
+                    tracing::debug!("Could not read {}: {}", path.display(), e);
+                }
+                return Ok(());
+            }
+        }
+
+        for line in proc_net_lines(&buffer) {
+            match self.parse_proc_net_line(line, protocol, default_state) {
+                Ok(conn) => connections.push(conn),
+                // One garbled row shouldn't hide the rest of the table
+                Err(e) => tracing::debug!("Skipping line of {}: {}", path.display(), e),
+            }
+        }
+        Ok(())
+    }
+
+    /// Connection for a line of /proc/net/tcp|udp
+    fn parse_proc_net_line(
+        &self,
+        line: &[u8],
+        protocol: Protocol,
+        default_state: ConnectionState,
+    ) -> Result<Connection> {
+        let socket = parse_proc_net_line(line)?;
+        let mut conn = self.socket_connection(
+            protocol,
+            socket.state.unwrap_or(default_state),
+            socket.local,
+            socket.remote,
+            socket.inode,
+            socket.uid,
+        );
+        conn.send_queue = socket.tx_queue;
+        conn.recv_queue = socket.rx_queue;
+        conn.timer = socket.timer;
+        Ok(conn)
+    }
+
+    /// Connection for a socket, with the process owning its inode
+    fn socket_connection(
+        &self,
+        protocol: Protocol,
+        state: ConnectionState,
+        local_addr: String,
+        remote_addr: String,
+        inode: u64,
+        socket_uid: Option<u32>,
+    ) -> Connection {
+        if let Attribution::Packages(packages) = &self.attribution {
+            let package: Arc<str> = match socket_uid {
+                Some(uid) => packages.name(uid),
+                None => Arc::from("N/A"),
+            };
+            let mut conn = Connection::new(
+                protocol,
+                state,
+                local_addr,
+                remote_addr,
+                package.clone(),
+                "N/A",
+                package,
+            );
+            conn.inode = inode;
+            return conn;
+        }
+
+        let (mut program, pid, command) = self.process_cache.borrow_mut().get_process_info(inode);
+
+        // Sockets of other users can't be mapped to a process without privileges
+        let privileged = self.privileged || self.process_cache.borrow().has_privileged_helper();
+        if &*program == "N/A"
+            && inode != 0
+            && !privileged
+            && socket_uid.is_some_and(|uid| uid != self.effective_uid)
+        {
+            program = PERMISSION_DENIED.into();
+        }
+
+        let mut conn = Connection::new(
+            protocol,
+            state,
+            local_addr,
+            remote_addr,
+            program,
+            pid,
+            command,
+        );
+        conn.inode = inode;
+        conn
+    }
+
+    /// Get process info for a given socket inode
+    #[allow(dead_code)]
+    fn get_process_info_for_inode(&self, inode: u64) -> (String, String, String) {
+        if inode == 0 {
+            return ("N/A".to_string(), "N/A".to_string(), "N/A".to_string());
+        }
+
+        // Scan /proc/*/fd for socket inodes
+        if let Ok(proc_dir) = fs::read_dir("/proc") {
+            for entry in proc_dir.flatten() {
+                let path = entry.path();
+                if let Some(pid_str) = path.file_name().and_then(|n| n.to_str()) {
+                    if pid_str.chars().all(|c| c.is_ascii_digit()) {
+                        if let Some((program, command)) = self.check_process_fd(&path, inode) {
+                            return (program, pid_str.to_string(), command);
+                        }
+                    }
+                }
+            }
+        }
+
+        ("N/A".to_string(), "N/A".to_string(), "N/A".to_string())
+    }
+
+    /// Check process file descriptors for matching socket inode
+    #[allow(dead_code)]
+    fn check_process_fd(&self, proc_path: &Path, target_inode: u64) -> Option<(String, String)> {
+        let fd_path = proc_path.join("fd");
+        if let Ok(fd_dir) = fs::read_dir(&fd_path) {
+            for fd_entry in fd_dir.flatten() {
+                let fd_link_path = fd_entry.path();
+                if let Ok(link_target) = fs::read_link(&fd_link_path) {
+                    if let Some(link_str) = link_target.to_str() {
+                        if link_str.starts_with("socket:[") && link_str.ends_with(']') {
+                            let inode_str = &link_str[8..link_str.len() - 1];
+                            if let Ok(inode) = inode_str.parse::<u64>() {
+                                if inode == target_inode {
+                                    let pid_str = proc_path
+                                        .file_name()
+                                        .and_then(|n| n.to_str())
+                                        .unwrap_or("unknown")
+                                        .to_string();
+                                    let program = self.get_process_name(&pid_str);
+                                    let command = self.get_process_path(&pid_str);
+                                    return Some((program, command));
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Get process name from /proc/[pid]/status
+    #[allow(dead_code)]
+    fn get_process_name(&self, pid: &str) -> String {
+        let status_path = format!("/proc/{pid}/status");
+        if let Ok(status_data) = fs::read_to_string(&status_path) {
+            for line in status_data.lines() {
+                if let Some(name) = line.strip_prefix("Name:\t") {
+                    return name.to_string();
+                }
+            }
+        }
+        "N/A".to_string()
+    }
+
+    /// Get I/O statistics for a process
+    pub fn get_process_io(&self, pid: &str) -> ProcessIO {
+        let io_path = self.proc_root.join(pid).join("io");
+        // Skip if we can't access the process io file (permission denied for other users' processes)
+        if let Ok(io_data) = fs::read_to_string(&io_path) {
+            let mut rx_bytes = 0u64;
+            let mut tx_bytes = 0u64;
+
+            for line in io_data.lines() {
+                if line.starts_with("rchar:") {
+                    if let Some(value) = line.split_whitespace().nth(1) {
+                        rx_bytes = value.parse().unwrap_or(0);
+                    }
+                } else if line.starts_with("wchar:") {
+                    if let Some(value) = line.split_whitespace().nth(1) {
+                        tx_bytes = value.parse().unwrap_or(0);
+                    }
+                }
+            }
+
+            ProcessIO::new(rx_bytes, tx_bytes)
+        } else {
+            ProcessIO::zero()
+        }
+    }
+
+    /// Get process command line
+    pub fn get_process_path(&self, pid: &str) -> String {
+        let cmdline_path = self.proc_root.join(pid).join("cmdline");
+        if let Ok(cmdline) = fs::read_to_string(&cmdline_path) {
+            if !cmdline.is_empty() {
+                cmdline.replace('\0', " ")
+            } else {
+                format!("[{pid}]")
+            }
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    /// Update connection rates based on previous I/O data
+    pub fn update_connection_rates(
+        &self,
+        connections: Vec<Connection>,
+        prev_io: &ProcessIOMap,
+    ) -> Result<(Vec<Connection>, ProcessIOMap)> {
+        self.update_connection_rates_with(connections, prev_io, |pid| self.get_process_io(pid))
+    }
+
+    /// [`update_connection_rates`](Self::update_connection_rates) with the
+    /// I/O counters of a PID read by `read_io`
+    pub(crate) fn update_connection_rates_with(
+        &self,
+        connections: Vec<Connection>,
+        prev_io: &ProcessIOMap,
+        mut read_io: impl FnMut(&str) -> ProcessIO,
+    ) -> Result<(Vec<Connection>, ProcessIOMap)> {
+        let mut current_io = HashMap::new();
+        let mut updated_connections = Vec::new();
+
+        // Calculate time elapsed since last update
+        let now = Instant::now();
+        let time_elapsed = {
+            let last_time = *self.last_update_time.borrow();
+            let elapsed = now.duration_since(last_time);
+            // Update the last update time
+            *self.last_update_time.borrow_mut() = now;
+            elapsed
+        };
+
+        // Convert elapsed time to seconds as f64 for rate calculation
+        let elapsed_seconds = time_elapsed.as_secs_f64();
+        // Avoid division by zero
+        let elapsed_seconds = elapsed_seconds.max(0.001);
+
+        for mut conn in connections {
+            if &*conn.pid != "N/A" {
+                // Read /proc/[pid]/io once per process, however many sockets it owns
+                let io = current_io
+                    .entry(conn.pid.clone())
+                    .or_insert_with(|| read_io(&conn.pid));
+
+                // Calculate rates based on previous I/O data and time elapsed
+                if let Some(prev) = prev_io.get(&conn.pid) {
+                    let rx_diff = io.rx.saturating_sub(prev.rx) as f64;
+                    let tx_diff = io.tx.saturating_sub(prev.tx) as f64;
+
+                    // Calculate per-second rates
+                    conn.rx_rate = (rx_diff / elapsed_seconds) as u64;
+                    conn.tx_rate = (tx_diff / elapsed_seconds) as u64;
+                }
+            }
+
+            updated_connections.push(conn);
+        }
+
+        Ok((updated_connections, current_io))
+    }
+}
+
+impl Default for NetworkService {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Shared copy of an algorithm name, so the few names there are aren't
+/// allocated once per socket
+#[cfg(target_os = "linux")]
+fn intern(names: &mut HashMap<String, Arc<str>>, name: String) -> Arc<str> {
+    names
+        .entry(name)
+        .or_insert_with_key(|name| Arc::from(name.as_str()))
+        .clone()
+}
@@ -0,0 +1,94 @@
+use crate::error::Result;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+/// Where Android records the UID of every installed app
+pub const PACKAGES_LIST: &str = "/data/system/packages.list";
+
+/// Android gives each user a range of this many UIDs; the app ID is the same
+/// in all of them
+const PER_USER_RANGE: u32 = 100_000;
+
+/// App package names by Android app ID, from packages.list
+#[derive(Debug, Clone, Default)]
+pub struct PackageNames {
+    by_app_id: HashMap<u32, Arc<str>>,
+}
+
+impl PackageNames {
+    /// Read `path`, normally [`PACKAGES_LIST`]; apps can't unless the device is rooted
+    pub fn load(path: &Path) -> Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    /// Parse "package uid debuggable data_dir seinfo gids" lines. Packages
+    /// sharing a UID keep the first name listed.
+    pub fn parse(text: &str) -> Self {
+        let mut by_app_id = HashMap::new();
+        for line in text.lines() {
+            let mut fields = line.split_whitespace();
+            let (Some(package), Some(uid)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+            if let Ok(uid) = uid.parse::<u32>() {
+                by_app_id
+                    .entry(uid % PER_USER_RANGE)
+                    .or_insert_with(|| Arc::from(package));
+            }
+        }
+        Self { by_app_id }
+    }
+
+    pub fn len(&self) -> usize {
+        self.by_app_id.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_app_id.is_empty()
+    }
+
+    /// Package owning `uid` in any user profile; system UIDs get their role,
+    /// anything else its number
+    pub fn name(&self, uid: u32) -> Arc<str> {
+        let app_id = uid % PER_USER_RANGE;
+        if let Some(package) = self.by_app_id.get(&app_id) {
+            return package.clone();
+        }
+        match app_id {
+            0 => Arc::from("root"),
+            1000 => Arc::from("system"),
+            _ => Arc::from(format!("uid {uid}")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PACKAGES: &str = "\
+com.termux 10123 0 /data/user/0/com.termux default:targetSdkVersion=28 3003
+org.mozilla.firefox 10150 0 /data/user/0/org.mozilla.firefox default:targetSdkVersion=34 3003
+com.android.shell.shared 10150 0 /data/user/0/x platform none
+garbled
+";
+
+    #[test]
+    fn test_names_by_uid() {
+        let packages = PackageNames::parse(PACKAGES);
+        assert_eq!(packages.len(), 2);
+        assert_eq!(&*packages.name(10123), "com.termux");
+        // Same app in the work profile of user 10
+        assert_eq!(&*packages.name(1_010_150), "org.mozilla.firefox");
+        assert_eq!(&*packages.name(1000), "system");
+        assert_eq!(&*packages.name(10999), "uid 10999");
+    }
+
+    #[test]
+    fn test_unreadable_list() {
+        assert!(PackageNames::load(Path::new("/nonexistent/packages.list")).is_err());
+        assert!(PackageNames::default().is_empty());
+    }
+}
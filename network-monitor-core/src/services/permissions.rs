@@ -0,0 +1,132 @@
+use crate::utils::{parse_decimal, parse_hex_u64};
+use std::fs;
+use std::path::Path;
+
+/// CAP_SYS_PTRACE: needed to read other users' /proc/[pid]/fd and /proc/[pid]/io
+const CAP_SYS_PTRACE: u32 = 19;
+
+/// Startup check of what the current user can and can't see in /proc
+#[derive(Debug, Clone)]
+pub struct PermissionReport {
+    pub missing: Vec<String>,
+}
+
+impl PermissionReport {
+    /// Inspect our own credentials and probe /proc/[pid]/fd of every process
+    pub fn check() -> Self {
+        let (_, privileged) = current_credentials();
+
+        let mut total_processes = 0;
+        let mut unreadable_processes = 0;
+        if let Ok(proc_dir) = fs::read_dir("/proc") {
+            for entry in proc_dir.flatten() {
+                let path = entry.path();
+                let is_pid = path
+                    .file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.chars().all(|c| c.is_ascii_digit()));
+                if !is_pid {
+                    continue;
+                }
+                total_processes += 1;
+                if fs::read_dir(path.join("fd")).is_err() {
+                    unreadable_processes += 1;
+                }
+            }
+        }
+
+        let mut missing = Vec::new();
+        if unreadable_processes > 0 {
+            missing.push(format!(
+                "Process name and PID of sockets owned by other users ({unreadable_processes} of {total_processes} processes unreadable)"
+            ));
+        }
+        if !privileged {
+            missing.push("TX/RX rates of other users' processes (/proc/[pid]/io)".to_string());
+        }
+
+        Self { missing }
+    }
+
+    /// Whether some connection details will be unavailable
+    pub fn is_limited(&self) -> bool {
+        !self.missing.is_empty()
+    }
+
+    /// One-line description of the limitations, for status bars
+    pub fn summary(&self) -> String {
+        if self.is_limited() {
+            format!(
+                "Running unprivileged: {} (run as root or grant CAP_SYS_PTRACE for full details)",
+                self.missing.join("; ")
+            )
+        } else {
+            "Full access to process information".to_string()
+        }
+    }
+}
+
+/// Effective UID of this process and whether it may inspect other users' processes
+pub fn current_credentials() -> (u32, bool) {
+    credentials_in(Path::new("/proc"))
+}
+
+/// Like `current_credentials`, from `self/status` under `proc_root`. Without
+/// one, the process' own effective UID is used and no capabilities assumed.
+pub fn credentials_in(proc_root: &Path) -> (u32, bool) {
+    let status = fs::read_to_string(proc_root.join("self/status")).unwrap_or_default();
+    // SAFETY: geteuid(2) has no preconditions and cannot fail
+    let effective_uid = parse_effective_uid(&status).unwrap_or_else(|| unsafe { libc::geteuid() });
+    let privileged = effective_uid == 0
+        || parse_effective_caps(&status).is_some_and(|caps| caps & (1 << CAP_SYS_PTRACE) != 0);
+    (effective_uid, privileged)
+}
+
+/// Effective UID from the `Uid:` line of /proc/[pid]/status
+pub fn parse_effective_uid(status: &str) -> Option<u32> {
+    let uids = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+    parse_decimal(uids.split_whitespace().nth(1)?, "effective uid").ok()
+}
+
+/// Effective capability mask from the `CapEff:` line of /proc/[pid]/status
+pub fn parse_effective_caps(status: &str) -> Option<u64> {
+    let caps = status
+        .lines()
+        .find_map(|line| line.strip_prefix("CapEff:"))?;
+    parse_hex_u64(caps.trim(), "CapEff").ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STATUS: &str = "Name:\tnmt\nUid:\t1000\t1001\t1000\t1000\nGid:\t1000\t1000\t1000\t1000\nCapEff:\t0000000000080000\n";
+
+    #[test]
+    fn test_parse_effective_uid() {
+        assert_eq!(parse_effective_uid(STATUS), Some(1001));
+        assert_eq!(parse_effective_uid("Name:\tnmt\n"), None);
+    }
+
+    #[test]
+    fn test_parse_effective_caps() {
+        let caps = parse_effective_caps(STATUS).unwrap();
+        assert_ne!(caps & (1 << CAP_SYS_PTRACE), 0);
+        assert_eq!(parse_effective_caps("CapEff:\tzz\n"), None);
+    }
+
+    #[test]
+    fn test_tree_without_status_is_not_assumed_root() {
+        // SAFETY: geteuid(2) has no preconditions and cannot fail
+        let euid = unsafe { libc::geteuid() };
+        let (uid, privileged) = credentials_in(Path::new("/nonexistent"));
+        assert_eq!(uid, euid);
+        assert_eq!(privileged, euid == 0);
+    }
+
+    #[test]
+    fn test_check_reports_consistently() {
+        let report = PermissionReport::check();
+        assert_eq!(report.is_limited(), !report.missing.is_empty());
+    }
+}
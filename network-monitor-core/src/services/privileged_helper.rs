@@ -0,0 +1,297 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{CgroupBytes, ConnectEvent, SocketOwner};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// Install locations matching the exec.path of the polkit action
+const HELPER_PATHS: [&str; 2] = [
+    "/usr/local/libexec/network-monitor-helper",
+    "/usr/libexec/network-monitor-helper",
+];
+
+/// Line-based requests understood by `network-monitor-helper`: socket
+/// owners, the byte counters of each cgroup, and the connection attempts
+/// since the last request
+pub const SCAN_REQUEST: &str = "scan";
+pub const TRAFFIC_REQUEST: &str = "traffic";
+pub const CONNECTS_REQUEST: &str = "connects";
+
+/// polkit rule letting one user start the helper without a password
+pub const POLKIT_RULE_PATH: &str = "/etc/polkit-1/rules.d/50-network-monitor.rules";
+
+/// File capabilities that let the helper run without pkexec: reading other
+/// users' /proc/[pid]/fd, subscribing to process events and attaching the
+/// cgroup traffic counters and connect tracer
+const HELPER_CAPABILITIES: &str = "cap_bpf,cap_dac_read_search,cap_net_admin,cap_sys_ptrace+ep";
+
+/// Group allowed to run the helper once it has capabilities. Whoever runs it
+/// then sees every socket owner and connection attempt on the computer, so
+/// it is left executable by root and this group alone.
+pub const HELPER_GROUP: &str = "netmon";
+
+/// Run as root with the group, user, helper path and capabilities as
+/// arguments. chown drops file capabilities, so setcap comes last.
+const GRANT_SCRIPT: &str = r#"set -e
+getent group "$1" >/dev/null || groupadd --system "$1"
+usermod -a -G "$1" "$2"
+chown "root:$1" "$3"
+chmod 0750 "$3"
+setcap "$4" "$3"
+"#;
+
+/// Handle to the `network-monitor-helper` process running as root via pkexec.
+/// The helper is started once per session, so polkit asks for authentication only once.
+pub struct PrivilegedHelper {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl PrivilegedHelper {
+    /// Launch the helper, directly when it has been granted capabilities and
+    /// through pkexec otherwise. Users outside the [`HELPER_GROUP`], or added
+    /// to it since they logged in, go through pkexec too.
+    pub fn spawn() -> Result<Self> {
+        let helper_path = Self::helper_path()?;
+        let direct =
+            has_file_capabilities(&helper_path).then(|| Self::start(Command::new(&helper_path)));
+        let mut child = match direct {
+            Some(Err(e)) if e.kind() == std::io::ErrorKind::PermissionDenied => None,
+            direct => direct,
+        }
+        .unwrap_or_else(|| {
+            let mut command = Command::new("pkexec");
+            command.arg(&helper_path);
+            Self::start(command)
+        })
+        .map_err(|e| NetworkMonitorError::PrivilegedHelper(format!("pkexec: {e}")))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(NetworkMonitorError::PrivilegedHelper(
+                "helper pipes unavailable".to_string(),
+            ));
+        };
+
+        Ok(Self {
+            child,
+            stdin: Some(stdin),
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    fn start(mut command: Command) -> std::io::Result<Child> {
+        command.stdin(Stdio::piped()).stdout(Stdio::piped()).spawn()
+    }
+
+    /// Locate the helper binary, preferring the installed copies covered by the polkit policy
+    fn helper_path() -> Result<PathBuf> {
+        if let Some(path) = HELPER_PATHS.iter().map(PathBuf::from).find(|p| p.exists()) {
+            return Ok(path);
+        }
+
+        // Development builds: the helper sits next to the current executable
+        let sibling = std::env::current_exe()?.with_file_name("network-monitor-helper");
+        if sibling.exists() {
+            Ok(sibling)
+        } else {
+            Err(NetworkMonitorError::PrivilegedHelper(
+                "network-monitor-helper is not installed".to_string(),
+            ))
+        }
+    }
+
+    /// Let `user` start the helper without authenticating from now on, by
+    /// writing a polkit rule as root. pkexec prompts for the password once.
+    pub fn install_polkit_rule(user: &str) -> Result<()> {
+        let rule = polkit_rule(user)?;
+        let mut child = Command::new("pkexec")
+            .args(["tee", POLKIT_RULE_PATH])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .spawn()
+            .map_err(|e| NetworkMonitorError::PrivilegedHelper(format!("pkexec: {e}")))?;
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(rule.as_bytes())?;
+        }
+        check_status(child.wait()?.success(), "writing the polkit rule")
+    }
+
+    /// Grant the helper the capabilities it needs so it runs without pkexec,
+    /// returning its path. Only root and the [`HELPER_GROUP`] may run it from
+    /// then on; `user` is added to the group, which applies from their next
+    /// login.
+    pub fn grant_capabilities(user: &str) -> Result<PathBuf> {
+        check_user_name(user)?;
+        let helper_path = Self::helper_path()?;
+        let status = Command::new("pkexec")
+            .args(["sh", "-c", GRANT_SCRIPT, "sh", HELPER_GROUP, user])
+            .arg(&helper_path)
+            .arg(HELPER_CAPABILITIES)
+            .status()
+            .map_err(|e| NetworkMonitorError::PrivilegedHelper(format!("pkexec: {e}")))?;
+        check_status(status.success(), "setcap")?;
+        Ok(helper_path)
+    }
+
+    /// Run the helper once as root for a single request such as a bandwidth
+    /// limit, failing with what it printed. Always through pkexec: the file
+    /// capabilities don't let it write to cgroups or firewall tables.
+    pub fn run(args: &[&str]) -> Result<()> {
+        let output = Command::new("pkexec")
+            .arg(Self::helper_path()?)
+            .args(args)
+            .output()
+            .map_err(|e| NetworkMonitorError::PrivilegedHelper(format!("pkexec: {e}")))?;
+        if output.status.success() {
+            return Ok(());
+        }
+        let message = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        Err(NetworkMonitorError::PrivilegedHelper(
+            if message.is_empty() {
+                "the request failed or authentication was cancelled".to_string()
+            } else {
+                message
+            },
+        ))
+    }
+
+    /// Ask the helper for a fresh socket inode to process map.
+    /// Blocks until the user has answered the polkit prompt on first use.
+    pub fn scan(&mut self) -> Result<HashMap<u64, SocketOwner>> {
+        self.request(SCAN_REQUEST)
+    }
+
+    /// Ask the helper for the bytes sent and received by each cgroup ID so
+    /// far; None when it can't attach the counters
+    pub fn cgroup_traffic(&mut self) -> Result<Option<HashMap<u64, CgroupBytes>>> {
+        self.request(TRAFFIC_REQUEST)
+    }
+
+    /// Ask the helper for the connection attempts made since the previous
+    /// request; None when it can't attach the connect tracer
+    pub fn connect_events(&mut self) -> Result<Option<Vec<ConnectEvent>>> {
+        self.request(CONNECTS_REQUEST)
+    }
+
+    /// Send `request` and parse the JSON line answering it
+    fn request<T: DeserializeOwned>(&mut self, request: &str) -> Result<T> {
+        let stdin = self.stdin.as_mut().ok_or_else(|| {
+            NetworkMonitorError::PrivilegedHelper("helper already closed".to_string())
+        })?;
+        writeln!(stdin, "{request}")?;
+        stdin.flush()?;
+
+        let mut line = String::new();
+        if self.stdout.read_line(&mut line)? == 0 {
+            return Err(NetworkMonitorError::PrivilegedHelper(
+                "helper exited (authentication cancelled?)".to_string(),
+            ));
+        }
+
+        serde_json::from_str(&line).map_err(|e| {
+            NetworkMonitorError::PrivilegedHelper(format!("invalid helper response: {e}"))
+        })
+    }
+}
+
+/// Rule allowing both helper actions of our polkit policy for `user` alone
+fn polkit_rule(user: &str) -> Result<String> {
+    // The name ends up in JavaScript
+    check_user_name(user)?;
+    Ok(format!(
+        r#"// Installed by Network Monitor: start network-monitor-helper without a password
+polkit.addRule(function(action, subject) {{
+    if ((action.id == "org.grigio.NetworkMonitor.helper" ||
+         action.id == "org.grigio.NetworkMonitor.helper-packaged") &&
+        subject.user == "{user}" && subject.local && subject.active) {{
+        return polkit.Result.YES;
+    }}
+}});
+"#
+    ))
+}
+
+/// Only accept plain user names, which are passed on to rules and commands
+fn check_user_name(user: &str) -> Result<()> {
+    let valid = !user.is_empty()
+        && !user.starts_with('-')
+        && user
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'));
+    if valid {
+        Ok(())
+    } else {
+        Err(NetworkMonitorError::PrivilegedHelper(format!(
+            "unsupported user name '{user}'"
+        )))
+    }
+}
+
+fn check_status(success: bool, what: &str) -> Result<()> {
+    if success {
+        Ok(())
+    } else {
+        Err(NetworkMonitorError::PrivilegedHelper(format!(
+            "{what} failed or authentication was cancelled"
+        )))
+    }
+}
+
+/// Whether `path` carries file capabilities, as set by `setcap`
+#[cfg(target_os = "linux")]
+fn has_file_capabilities(path: &Path) -> bool {
+    use std::os::unix::ffi::OsStrExt;
+
+    let Ok(path) = std::ffi::CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: both names are NUL-terminated and a zero-sized query reads nothing
+    let size = unsafe {
+        libc::getxattr(
+            path.as_ptr(),
+            c"security.capability".as_ptr(),
+            std::ptr::null_mut(),
+            0,
+        )
+    };
+    size > 0
+}
+
+/// File capabilities are a Linux feature
+#[cfg(not(target_os = "linux"))]
+fn has_file_capabilities(_path: &Path) -> bool {
+    false
+}
+
+impl Drop for PrivilegedHelper {
+    fn drop(&mut self) {
+        // Closing stdin makes the helper exit; reap it so no zombie is left behind
+        drop(self.stdin.take());
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_polkit_rule_names_one_user() {
+        let rule = polkit_rule("alice").unwrap();
+        assert!(rule.contains("subject.user == \"alice\""), "{rule}");
+        assert!(rule.contains("org.grigio.NetworkMonitor.helper-packaged"));
+        assert!(polkit_rule("alice\" || true || \"").is_err());
+        assert!(polkit_rule("").is_err());
+        assert!(check_user_name("-alice").is_err());
+    }
+
+    #[test]
+    fn test_plain_file_has_no_capabilities() {
+        assert!(!has_file_capabilities(Path::new("/proc/self/status")));
+        assert!(!has_file_capabilities(Path::new("/nonexistent")));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::services::netlink::{self, NLMSG_HEADER_LEN};
+
+/// Multicast group and callback ID of the kernel proc connector (linux/connector.h)
+const CN_IDX_PROC: u32 = 1;
+const CN_VAL_PROC: u32 = 1;
+/// Operation sent to start receiving events (linux/cn_proc.h)
+const PROC_CN_MCAST_LISTEN: u32 = 1;
+
+const PROC_EVENT_FORK: u32 = 0x0000_0001;
+const PROC_EVENT_EXEC: u32 = 0x0000_0002;
+const PROC_EVENT_EXIT: u32 = 0x8000_0000;
+
+/// struct cn_msg without its payload
+const CN_MSG_HEADER_LEN: usize = 20;
+/// what, cpu and timestamp_ns fields of struct proc_event
+const PROC_EVENT_HEADER_LEN: usize = 16;
+
+/// Process lifecycle change relevant to the socket owner cache. Only thread group
+/// leaders are reported, threads share their process' descriptors.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ProcEvent {
+    Fork(String),
+    Exec(String),
+    Exit(String),
+}
+
+/// Non-blocking subscription to fork/exec/exit notifications from the kernel
+/// proc connector. Subscribing needs CAP_NET_ADMIN.
+pub struct ProcEventListener {
+    socket: netlink::Socket,
+}
+
+impl ProcEventListener {
+    pub fn subscribe() -> Result<Self> {
+        let error = |e: std::io::Error| NetworkMonitorError::ProcEvents(e.to_string());
+        let socket = netlink::Socket::netlink(libc::NETLINK_CONNECTOR, libc::SOCK_NONBLOCK)
+            .map_err(error)?;
+        socket.bind(CN_IDX_PROC).map_err(error)?;
+        socket.send(&listen_request()).map_err(error)?;
+        Ok(Self { socket })
+    }
+
+    /// Drain all pending events without blocking. Fails when events were
+    /// dropped (ENOBUFS) or can't be received, leaving the caller to find
+    /// out what changed some other way.
+    pub fn poll(&self) -> Result<Vec<ProcEvent>> {
+        let mut events = Vec::new();
+        let mut buffer = [0u8; 4096];
+        loop {
+            match self.socket.recv(&mut buffer) {
+                Ok(0) => return Ok(events),
+                Ok(received) => events.extend(parse_proc_event(&buffer[..received])),
+                Err(e) if e.kind() == std::io::ErrorKind::WouldBlock => return Ok(events),
+                Err(e) => return Err(NetworkMonitorError::ProcEvents(e.to_string())),
+            }
+        }
+    }
+}
+
+/// Netlink message asking the connector to start multicasting process events
+fn listen_request() -> Vec<u8> {
+    let total_len = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN + 4;
+    let mut message = Vec::with_capacity(total_len);
+    // nlmsghdr
+    message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+    message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+    message.extend_from_slice(&0u16.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&std::process::id().to_ne_bytes());
+    // cn_msg
+    message.extend_from_slice(&CN_IDX_PROC.to_ne_bytes());
+    message.extend_from_slice(&CN_VAL_PROC.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&4u16.to_ne_bytes());
+    message.extend_from_slice(&0u16.to_ne_bytes());
+    message.extend_from_slice(&PROC_CN_MCAST_LISTEN.to_ne_bytes());
+    message
+}
+
+/// Decode one netlink datagram carrying a struct proc_event
+pub fn parse_proc_event(datagram: &[u8]) -> Option<ProcEvent> {
+    let read_u32 = |offset| netlink::read_u32(datagram, offset);
+
+    let event = NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN;
+    let data = event + PROC_EVENT_HEADER_LEN;
+    match read_u32(event)? {
+        // fork: parent_pid, parent_tgid, child_pid, child_tgid
+        PROC_EVENT_FORK => {
+            let (pid, tgid) = (read_u32(data + 8)?, read_u32(data + 12)?);
+            (pid == tgid).then(|| ProcEvent::Fork(pid.to_string()))
+        }
+        // exec/exit: process_pid, process_tgid, ...
+        PROC_EVENT_EXEC => {
+            let (pid, tgid) = (read_u32(data)?, read_u32(data + 4)?);
+            (pid == tgid).then(|| ProcEvent::Exec(pid.to_string()))
+        }
+        PROC_EVENT_EXIT => {
+            let (pid, tgid) = (read_u32(data)?, read_u32(data + 4)?);
+            (pid == tgid).then(|| ProcEvent::Exit(pid.to_string()))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datagram(what: u32, data: &[u32]) -> Vec<u8> {
+        let mut datagram = vec![0u8; NLMSG_HEADER_LEN + CN_MSG_HEADER_LEN];
+        datagram.extend_from_slice(&what.to_ne_bytes());
+        datagram.extend_from_slice(&[0u8; 12]); // cpu, timestamp_ns
+        for value in data {
+            datagram.extend_from_slice(&value.to_ne_bytes());
+        }
+        datagram
+    }
+
+    #[test]
+    fn test_parse_proc_events() {
+        assert_eq!(
+            parse_proc_event(&datagram(PROC_EVENT_FORK, &[100, 100, 200, 200])),
+            Some(ProcEvent::Fork("200".to_string()))
+        );
+        assert_eq!(
+            parse_proc_event(&datagram(PROC_EVENT_EXEC, &[200, 200])),
+            Some(ProcEvent::Exec("200".to_string()))
+        );
+        assert_eq!(
+            parse_proc_event(&datagram(PROC_EVENT_EXIT, &[200, 200, 0, 0])),
+            Some(ProcEvent::Exit("200".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_ignores_threads_and_short_messages() {
+        // New thread 201 in process 200
+        assert_eq!(
+            parse_proc_event(&datagram(PROC_EVENT_FORK, &[200, 200, 201, 200])),
+            None
+        );
+        assert_eq!(parse_proc_event(&[0u8; 8]), None);
+    }
+
+    #[test]
+    fn test_receives_child_exit() {
+        // Subscription needs CAP_NET_ADMIN; nothing to check otherwise
+        let Ok(listener) = ProcEventListener::subscribe() else {
+            return;
+        };
+        let mut child = std::process::Command::new("true").spawn().unwrap();
+        let pid = child.id().to_string();
+        child.wait().unwrap();
+        std::thread::sleep(std::time::Duration::from_millis(100));
+
+        assert!(listener.poll().unwrap().contains(&ProcEvent::Exit(pid)));
+    }
+
+    #[test]
+    fn test_listen_request_layout() {
+        let request = listen_request();
+        assert_eq!(request.len(), 40);
+        assert_eq!(&request[..4], &40u32.to_ne_bytes());
+    }
+}
@@ -0,0 +1,877 @@
+use crate::models::connection::ProcessInfo;
+use crate::models::{CgroupBytes, ConnectEvent, SocketOwner};
+use crate::services::privileged_helper::PrivilegedHelper;
+#[cfg(target_os = "linux")]
+use crate::services::proc_events::{ProcEvent, ProcEventListener};
+use crate::services::CacheLimits;
+use crate::utils::{CacheStats, LruCache};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Rescan every process regardless of fd changes at this interval, to catch
+/// sockets swapped without changing the descriptor count and missed execs.
+/// Not needed while proc connector events are received.
+const FULL_RESCAN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Sockets no process is known for walk /proc again at most this often
+const UNKNOWN_SOCKET_WALK_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Below this many processes the scan stays single-threaded
+const PARALLEL_SCAN_THRESHOLD: usize = 256;
+/// Upper bound on scan threads; fd reads are syscall bound, more threads don't help
+const MAX_SCAN_THREADS: usize = 8;
+
+/// What a process' fd directory looked like when its sockets were last read
+struct FdSnapshot {
+    /// Start time of the process, telling it from a later one with its PID
+    started: Option<u64>,
+    modified: Option<SystemTime>,
+    fd_count: usize,
+    inodes: Vec<u64>,
+}
+
+/// Previous state of one PID, handed to a scan worker
+struct ScanInput {
+    pid: String,
+    snapshot: Option<FdSnapshot>,
+    cached: Option<ProcessInfo>,
+}
+
+/// Cache for mapping socket inodes to process information
+pub struct ProcessCache {
+    /// PIDs are shared with every connection of the process
+    inode_to_pid: HashMap<u64, Arc<str>>,
+    /// Bounded so long sessions with many short-lived processes stay small
+    pid_to_process: LruCache<String, ProcessInfo>,
+    fd_snapshots: HashMap<String, FdSnapshot>,
+    /// Sockets the last walk found no owner for, not worth walking again for
+    unowned: HashSet<u64>,
+    /// Walk all of /proc on the next lookup: at startup, and after process
+    /// events were missed
+    walk_due: bool,
+    last_update: Instant,
+    last_full_scan: Instant,
+    update_interval: Duration,
+    helper: Option<PrivilegedHelper>,
+    #[cfg(target_os = "linux")]
+    events: Option<ProcEventListener>,
+    /// Shared "N/A" for sockets without a known owner
+    unknown: Arc<str>,
+    /// Normally /proc; benchmarks point this at a synthetic tree
+    proc_root: PathBuf,
+    /// Socket lookups answered from the maps vs. not found in them
+    hits: u64,
+    misses: u64,
+}
+
+impl ProcessCache {
+    pub fn new() -> Self {
+        // Only available with CAP_NET_ADMIN (root or the privileged helper)
+        #[cfg(target_os = "linux")]
+        return Self {
+            events: ProcEventListener::subscribe().ok(),
+            ..Self::with_proc_root("/proc")
+        };
+        #[cfg(not(target_os = "linux"))]
+        Self::with_proc_root("/proc")
+    }
+
+    /// Cache reading processes from `proc_root` instead of /proc
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        Self {
+            inode_to_pid: HashMap::new(),
+            pid_to_process: LruCache::new(CacheLimits::default().process_entries),
+            fd_snapshots: HashMap::new(),
+            unowned: HashSet::new(),
+            walk_due: true,
+            last_update: Instant::now(),
+            last_full_scan: Instant::now(),
+            update_interval: Duration::from_secs(5), // Update every 5 seconds
+            helper: None,
+            #[cfg(target_os = "linux")]
+            events: None,
+            unknown: Arc::from("N/A"),
+            proc_root: proc_root.into(),
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Whether the proc connector keeps the maps current between full rescans
+    fn receives_process_events(&self) -> bool {
+        #[cfg(target_os = "linux")]
+        return self.events.is_some();
+        #[cfg(not(target_os = "linux"))]
+        return false;
+    }
+
+    /// Apply pending fork/exec/exit notifications: exited processes are dropped
+    /// and new and exec'd ones are read again, details and descriptors, so the
+    /// maps stay current without walking /proc. Missed events make the next
+    /// lookup walk it.
+    #[cfg(target_os = "linux")]
+    pub fn apply_process_events(&mut self) {
+        let events = match self.events.as_ref().map(ProcEventListener::poll) {
+            Some(Ok(events)) => events,
+            Some(Err(e)) => {
+                tracing::debug!("Missed process events, walking /proc again: {}", e);
+                self.walk_due = true;
+                return;
+            }
+            None => return,
+        };
+        self.apply_events(events);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn apply_events(&mut self, events: Vec<ProcEvent>) {
+        let mut gone = HashSet::new();
+        let mut started = HashSet::new();
+        for event in events {
+            match event {
+                ProcEvent::Exit(pid) => {
+                    started.remove(&pid);
+                    gone.insert(pid);
+                }
+                ProcEvent::Fork(pid) | ProcEvent::Exec(pid) => {
+                    started.insert(pid);
+                }
+            }
+        }
+        for pid in &gone {
+            self.pid_to_process.remove(pid);
+            self.fd_snapshots.remove(pid);
+        }
+
+        // A child shares its parent's descriptors and exec closes some, so
+        // started processes have theirs read again
+        let now = Instant::now();
+        let mut found = Vec::new();
+        for pid in &started {
+            let input = ScanInput {
+                pid: pid.clone(),
+                snapshot: None,
+                cached: None,
+            };
+            match Self::scan_process(&self.proc_root, input, now, true) {
+                Some(result) => found.push(result),
+                // Gone already, or a kernel thread
+                None => {
+                    self.pid_to_process.remove(pid);
+                    self.fd_snapshots.remove(pid);
+                }
+            }
+        }
+        gone.extend(started);
+
+        // Sockets of the processes gone or read again are forgotten in one
+        // pass, before those read are added back
+        if !gone.is_empty() {
+            self.inode_to_pid
+                .retain(|_, owner| !gone.contains(&**owner));
+        }
+        let mut evicted = HashSet::new();
+        for (pid, process_info, snapshot) in found {
+            let shared_pid: Arc<str> = Arc::from(pid.as_str());
+            for inode in &snapshot.inodes {
+                self.inode_to_pid.insert(*inode, shared_pid.clone());
+            }
+            if let Some((pid, _)) = self.pid_to_process.insert(pid.clone(), process_info) {
+                self.fd_snapshots.remove(&pid);
+                evicted.insert(pid);
+            }
+            self.fd_snapshots.insert(pid, snapshot);
+        }
+        if !evicted.is_empty() {
+            self.inode_to_pid
+                .retain(|_, owner| !evicted.contains(&**owner));
+        }
+    }
+
+    /// Apply pending fork/exec/exit notifications, which only Linux sends
+    #[cfg(not(target_os = "linux"))]
+    pub fn apply_process_events(&mut self) {}
+
+    /// Get process info for a given socket inode
+    pub fn get_process_info(&mut self, inode: u64) -> (Arc<str>, Arc<str>, Arc<str>) {
+        if inode == 0 {
+            return (
+                self.unknown.clone(),
+                self.unknown.clone(),
+                self.unknown.clone(),
+            );
+        }
+
+        // Update cache if needed - handle errors gracefully
+        if self.update_due() {
+            // Ignore cache update errors to prevent app crashes
+            let _ = self.update_cache();
+        }
+
+        if let Some(owner) = self.cached_owner(inode) {
+            self.hits += 1;
+            return owner;
+        }
+
+        // Sockets opened since the last walk by processes that neither
+        // forked nor exec'd since are only found by walking again
+        self.misses += 1;
+        if !self.unowned.contains(&inode)
+            && self.last_update.elapsed() >= UNKNOWN_SOCKET_WALK_INTERVAL
+        {
+            let _ = self.update_cache();
+            if let Some(owner) = self.cached_owner(inode) {
+                return owner;
+            }
+            self.unowned.insert(inode);
+        }
+        (
+            self.unknown.clone(),
+            self.unknown.clone(),
+            self.unknown.clone(),
+        )
+    }
+
+    /// Name, PID and command of the owner of `inode`, if the maps have it
+    fn cached_owner(&mut self, inode: u64) -> Option<(Arc<str>, Arc<str>, Arc<str>)> {
+        let pid = self.inode_to_pid.get(&inode)?;
+        let process_info = self.pid_to_process.get(&**pid)?;
+        Some((
+            process_info.name.clone(),
+            pid.clone(),
+            process_info.command.clone(),
+        ))
+    }
+
+    /// Whether the maps need a walk of /proc or a helper scan before
+    /// lookups; the proc connector keeps them current otherwise
+    fn update_due(&self) -> bool {
+        self.walk_due
+            || ((self.helper.is_some() || !self.receives_process_events())
+                && self.last_update.elapsed() > self.update_interval)
+    }
+
+    /// Update the cache by scanning /proc filesystem, or through the privileged helper when enabled
+    fn update_cache(&mut self) -> std::result::Result<(), crate::error::NetworkMonitorError> {
+        self.unowned.clear();
+        if let Some(helper) = self.helper.as_mut() {
+            match helper.scan() {
+                Ok(owners) => {
+                    self.import_owners(owners);
+                    return Ok(());
+                }
+                Err(e) => {
+                    // Fall back to unprivileged scanning for the rest of the session
+                    tracing::warn!("Privileged helper failed, scanning locally: {}", e);
+                    self.helper = None;
+                }
+            }
+        }
+
+        self.scan_proc();
+        Ok(())
+    }
+
+    /// Incrementally rebuild the maps from /proc: only processes that are new or
+    /// whose fd directory changed get their descriptors read again, unless
+    /// process events were missed
+    fn scan_proc(&mut self) {
+        self.apply_process_events();
+        let full_scan = if self.receives_process_events() {
+            self.walk_due
+        } else {
+            self.last_full_scan.elapsed() > FULL_RESCAN_INTERVAL
+        };
+        let now = Instant::now();
+        let mut old_snapshots = std::mem::take(&mut self.fd_snapshots);
+        let mut old_processes: HashMap<String, ProcessInfo> = self.pid_to_process.drain().collect();
+
+        let mut inputs = Vec::new();
+        if let Ok(proc_dir) = fs::read_dir(&self.proc_root) {
+            for entry in proc_dir.flatten() {
+                let Some(pid) = entry.file_name().to_str().map(str::to_string) else {
+                    continue;
+                };
+                if !pid.chars().all(|c| c.is_ascii_digit()) {
+                    continue;
+                }
+                let snapshot = old_snapshots.remove(&pid);
+                let cached = old_processes.remove(&pid);
+                inputs.push(ScanInput {
+                    pid,
+                    snapshot,
+                    cached,
+                });
+            }
+        }
+
+        let results = Self::scan_processes_parallel(&self.proc_root, inputs, now, full_scan);
+
+        let mut new_inode_to_pid = HashMap::new();
+        for (pid, process_info, snapshot) in results {
+            let shared_pid: Arc<str> = Arc::from(pid.as_str());
+            for inode in &snapshot.inodes {
+                new_inode_to_pid.insert(*inode, shared_pid.clone());
+            }
+            if let Some((evicted, _)) = self.pid_to_process.insert(pid.clone(), process_info) {
+                self.fd_snapshots.remove(&evicted);
+            }
+            self.fd_snapshots.insert(pid, snapshot);
+        }
+
+        // Sockets of processes evicted over the limit fall back to direct lookup
+        let processes = &self.pid_to_process;
+        new_inode_to_pid.retain(|_, pid| processes.contains_key(&**pid));
+        self.inode_to_pid = new_inode_to_pid;
+        self.last_update = now;
+        self.walk_due = false;
+        if full_scan {
+            self.last_full_scan = now;
+        }
+    }
+
+    /// Split the per-process fd work across a few scoped threads; small process
+    /// lists are scanned inline since spawning would cost more than it saves
+    fn scan_processes_parallel(
+        proc_root: &Path,
+        inputs: Vec<ScanInput>,
+        now: Instant,
+        full_scan: bool,
+    ) -> Vec<(String, ProcessInfo, FdSnapshot)> {
+        let workers = thread::available_parallelism()
+            .map_or(1, |n| n.get())
+            .min(MAX_SCAN_THREADS);
+        if workers <= 1 || inputs.len() < PARALLEL_SCAN_THRESHOLD {
+            return inputs
+                .into_iter()
+                .filter_map(|input| Self::scan_process(proc_root, input, now, full_scan))
+                .collect();
+        }
+
+        let chunk_size = inputs.len().div_ceil(workers);
+        let mut inputs = inputs.into_iter();
+        let chunks: Vec<Vec<ScanInput>> = std::iter::from_fn(|| {
+            let chunk: Vec<_> = inputs.by_ref().take(chunk_size).collect();
+            (!chunk.is_empty()).then_some(chunk)
+        })
+        .collect();
+
+        thread::scope(|scope| {
+            let handles: Vec<_> = chunks
+                .into_iter()
+                .map(|chunk| {
+                    scope.spawn(move || {
+                        chunk
+                            .into_iter()
+                            .filter_map(|input| {
+                                Self::scan_process(proc_root, input, now, full_scan)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .flat_map(|handle| handle.join().unwrap_or_default())
+                .collect()
+        })
+    }
+
+    /// Refresh one process; its fds are only listed and read again when the
+    /// process or its fd directory changed. procfs doesn't always bump the fd
+    /// directory mtime, so the descriptor count is compared too: the size of
+    /// the directory since Linux 6.2, so only older kernels list every
+    /// process' descriptors to count them.
+    fn scan_process(
+        proc_root: &Path,
+        input: ScanInput,
+        now: Instant,
+        full_scan: bool,
+    ) -> Option<(String, ProcessInfo, FdSnapshot)> {
+        let ScanInput {
+            pid,
+            snapshot,
+            cached,
+        } = input;
+        let fd_path = proc_root.join(&pid).join("fd");
+        let metadata = fs::metadata(&fd_path).ok();
+        let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+        let started = Self::start_time(proc_root, &pid);
+        let mut fd_entries = None;
+        let fd_count = match metadata.map_or(0, |m| m.len()) {
+            0 => fd_entries.insert(Self::read_fd_entries(&fd_path)).len(),
+            size => size as usize,
+        };
+
+        let unchanged = snapshot.as_ref().is_some_and(|snapshot| {
+            snapshot.started == started
+                && snapshot.modified == modified
+                && snapshot.fd_count == fd_count
+        });
+
+        match (cached, snapshot) {
+            (Some(mut process_info), Some(snapshot)) if unchanged && !full_scan => {
+                process_info.last_seen = now;
+                Some((pid, process_info, snapshot))
+            }
+            _ => {
+                let (name, command) = Self::get_process_details(proc_root, &pid);
+                if name.is_empty() || name == "N/A" {
+                    return None;
+                }
+                let process_info = ProcessInfo {
+                    name: name.into(),
+                    command: command.into(),
+                    last_seen: now,
+                };
+                let fd_entries = fd_entries.unwrap_or_else(|| Self::read_fd_entries(&fd_path));
+                let snapshot = FdSnapshot {
+                    started,
+                    modified,
+                    fd_count,
+                    inodes: Self::socket_inodes(&fd_entries),
+                };
+                Some((pid, process_info, snapshot))
+            }
+        }
+    }
+
+    /// Start time of a process in clock ticks since boot, field 22 of
+    /// /proc/[pid]/stat
+    fn start_time(proc_root: &Path, pid: &str) -> Option<u64> {
+        let stat = fs::read_to_string(proc_root.join(pid).join("stat")).ok()?;
+        // The name before it is in parentheses and may contain spaces
+        let (_, fields) = stat.rsplit_once(')')?;
+        fields.split_whitespace().nth(19)?.parse().ok()
+    }
+
+    /// Get process details from /proc
+    fn get_process_details(proc_root: &Path, pid: &str) -> (String, String) {
+        let name = Self::get_process_name(proc_root, pid);
+        let command = Self::get_process_command(proc_root, pid);
+        (name, command)
+    }
+
+    /// Get process name from /proc/[pid]/status
+    fn get_process_name(proc_root: &Path, pid: &str) -> String {
+        let status_path = proc_root.join(pid).join("status");
+        if let Ok(status_data) = fs::read_to_string(&status_path) {
+            for line in status_data.lines() {
+                if let Some(name) = line.strip_prefix("Name:\t") {
+                    return name.to_string();
+                }
+            }
+        }
+        "N/A".to_string()
+    }
+
+    /// Get process command from /proc/[pid]/cmdline
+    fn get_process_command(proc_root: &Path, pid: &str) -> String {
+        let cmdline_path = proc_root.join(pid).join("cmdline");
+        if let Ok(cmdline) = fs::read_to_string(&cmdline_path) {
+            if !cmdline.is_empty() {
+                cmdline.replace('\0', " ")
+            } else {
+                format!("[{pid}]")
+            }
+        } else {
+            "N/A".to_string()
+        }
+    }
+
+    /// List /proc/[pid]/fd; empty when the directory is unreadable
+    fn read_fd_entries(fd_path: &Path) -> Vec<PathBuf> {
+        fs::read_dir(fd_path)
+            .map(|fd_dir| fd_dir.flatten().map(|fd_entry| fd_entry.path()).collect())
+            .unwrap_or_default()
+    }
+
+    /// Resolve fd links to socket inodes
+    fn socket_inodes(fd_entries: &[PathBuf]) -> Vec<u64> {
+        let mut inodes = Vec::new();
+        for fd_link_path in fd_entries {
+            // Skip file descriptors we can't read (permission denied for other users' processes)
+            if let Ok(link_target) = fs::read_link(fd_link_path) {
+                if let Some(link_str) = link_target.to_str() {
+                    if link_str.starts_with("socket:[") && link_str.ends_with(']') {
+                        let inode_str = &link_str[8..link_str.len() - 1];
+                        if let Ok(inode) = inode_str.parse::<u64>() {
+                            inodes.push(inode);
+                        }
+                    }
+                }
+            }
+        }
+        inodes
+    }
+
+    /// Resolve socket owners through a privileged helper from now on
+    pub fn set_privileged_helper(&mut self, helper: PrivilegedHelper) -> crate::error::Result<()> {
+        self.helper = Some(helper);
+        self.update_cache()?;
+        if self.helper.is_none() {
+            return Err(crate::error::NetworkMonitorError::PrivilegedHelper(
+                "authentication failed or was cancelled".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    pub fn has_privileged_helper(&self) -> bool {
+        self.helper.is_some()
+    }
+
+    /// Bytes of each cgroup ID, counted by the privileged helper when enabled
+    pub fn helper_cgroup_traffic(&mut self) -> Option<HashMap<u64, CgroupBytes>> {
+        self.helper
+            .as_mut()?
+            .cgroup_traffic()
+            .map_err(|e| tracing::debug!("No cgroup traffic from the helper: {}", e))
+            .ok()
+            .flatten()
+    }
+
+    /// Connection attempts traced by the privileged helper since the last
+    /// call; None without a helper or when it can't trace them
+    pub fn helper_connect_events(&mut self) -> Option<Vec<ConnectEvent>> {
+        self.helper
+            .as_mut()?
+            .connect_events()
+            .map_err(|e| tracing::debug!("No connect events from the helper: {}", e))
+            .ok()
+            .flatten()
+    }
+
+    /// Scan /proc now and return every known socket inode with its owner (used by the helper)
+    pub fn export_owners(&mut self) -> HashMap<u64, SocketOwner> {
+        let _ = self.update_cache();
+        self.owners()
+    }
+
+    /// Every known socket inode with its owner, as of the last scan
+    pub fn owners(&self) -> HashMap<u64, SocketOwner> {
+        self.inode_to_pid
+            .iter()
+            .filter_map(|(inode, pid)| {
+                let process = self.pid_to_process.peek(&**pid)?;
+                Some((
+                    *inode,
+                    SocketOwner {
+                        pid: pid.to_string(),
+                        name: process.name.to_string(),
+                        command: process.command.to_string(),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Start from owners saved by a previous session, leaving out processes
+    /// that exited or whose PID now belongs to another program. Returns the
+    /// PIDs kept.
+    pub fn import_saved_owners(
+        &mut self,
+        mut owners: HashMap<u64, SocketOwner>,
+    ) -> HashSet<String> {
+        let mut live: HashMap<String, bool> = HashMap::new();
+        owners.retain(|_, owner| {
+            *live.entry(owner.pid.clone()).or_insert_with(|| {
+                Self::get_process_name(&self.proc_root, &owner.pid) == owner.name
+            })
+        });
+        self.import_owners(owners);
+        live.into_iter()
+            .filter_map(|(pid, alive)| alive.then_some(pid))
+            .collect()
+    }
+
+    /// Replace the cache contents with a map received from the privileged helper
+    fn import_owners(&mut self, owners: HashMap<u64, SocketOwner>) {
+        self.inode_to_pid.clear();
+        self.pid_to_process.clear();
+        self.fd_snapshots.clear();
+        let now = Instant::now();
+        for (inode, owner) in owners {
+            if !self.pid_to_process.contains_key(&owner.pid) {
+                self.pid_to_process.insert(
+                    owner.pid.clone(),
+                    ProcessInfo {
+                        name: owner.name.into(),
+                        command: owner.command.into(),
+                        last_seen: now,
+                    },
+                );
+            }
+            self.inode_to_pid.insert(inode, owner.pid.into());
+        }
+        let processes = &self.pid_to_process;
+        self.inode_to_pid
+            .retain(|_, pid| processes.contains_key(&**pid));
+        self.last_update = now;
+        self.walk_due = false;
+    }
+
+    /// Clear the cache
+    pub fn clear(&mut self) {
+        self.inode_to_pid.clear();
+        self.pid_to_process.clear();
+        self.fd_snapshots.clear();
+        self.walk_due = true;
+        self.last_update = Instant::now();
+    }
+
+    /// Set cache update interval
+    pub fn set_update_interval(&mut self, interval: Duration) {
+        self.update_interval = interval;
+    }
+
+    /// Limit how many processes are tracked
+    pub fn set_entry_limit(&mut self, entries: usize) {
+        self.pid_to_process.set_capacity(entries);
+        let processes = &self.pid_to_process;
+        self.fd_snapshots
+            .retain(|pid, _| processes.contains_key(pid.as_str()));
+        self.inode_to_pid
+            .retain(|_, pid| processes.contains_key(&**pid));
+    }
+
+    /// Tracked processes, the approximate memory held for them and how many
+    /// socket lookups the cache answered
+    pub fn stats(&self) -> CacheStats {
+        let mut stats = self
+            .pid_to_process
+            .stats(|pid, process| pid.len() + process.name.len() + process.command.len());
+        let socket_bytes: usize = self
+            .fd_snapshots
+            .values()
+            .map(|snapshot| snapshot.inodes.capacity() * std::mem::size_of::<u64>())
+            .sum();
+        stats.hits = self.hits;
+        stats.misses = self.misses;
+        stats.approx_bytes += socket_bytes
+            + self.inode_to_pid.len() * std::mem::size_of::<(u64, Arc<str>)>()
+            + self.fd_snapshots.len() * std::mem::size_of::<(String, FdSnapshot)>();
+        stats
+    }
+}
+
+impl Default for ProcessCache {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::net::TcpListener;
+
+    fn socket_inode(listener: &TcpListener) -> u64 {
+        use std::os::fd::AsRawFd;
+        let link = fs::read_link(format!("/proc/self/fd/{}", listener.as_raw_fd())).unwrap();
+        let link = link.to_string_lossy();
+        link[8..link.len() - 1].parse().unwrap()
+    }
+
+    #[test]
+    fn test_saved_owners_of_other_programs_are_dropped() {
+        let pid = std::process::id().to_string();
+        let name = ProcessCache::get_process_name(Path::new("/proc"), &pid);
+        let owner = |pid: &str, name: &str| SocketOwner {
+            pid: pid.to_string(),
+            name: name.to_string(),
+            command: name.to_string(),
+        };
+        let mut cache = ProcessCache::with_proc_root("/proc");
+        let live = cache.import_saved_owners(HashMap::from([
+            (1, owner(&pid, &name)),
+            (2, owner(&pid, &name)),
+            // Another program runs as PID 1 by now
+            (3, owner("1", "not-init")),
+        ]));
+        assert_eq!(live, HashSet::from([pid.clone()]));
+        assert_eq!(cache.get_process_info(1).1.as_ref(), pid);
+        assert_eq!(cache.owners().len(), 2);
+
+        let live = cache.import_saved_owners(HashMap::from([(4, owner("999999999", "gone"))]));
+        assert!(live.is_empty());
+        assert!(cache.owners().is_empty());
+    }
+
+    #[test]
+    fn test_incremental_scan_picks_up_new_sockets() {
+        let mut cache = ProcessCache::new();
+        cache.scan_proc();
+        let pid = std::process::id().to_string();
+        assert!(cache.fd_snapshots.contains_key(&pid));
+
+        // A new descriptor changes the fd count, so our process is rescanned
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let inode = socket_inode(&listener);
+        cache.scan_proc();
+        assert_eq!(
+            cache.inode_to_pid.get(&inode).map(|owner| &**owner),
+            Some(pid.as_str())
+        );
+    }
+
+    #[test]
+    fn test_sockets_of_one_process_share_strings() {
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        let second = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut cache = ProcessCache::new();
+        cache.scan_proc();
+
+        let (name_a, pid_a, command_a) = cache.get_process_info(socket_inode(&first));
+        let (name_b, pid_b, command_b) = cache.get_process_info(socket_inode(&second));
+        assert_eq!(&*pid_a, std::process::id().to_string());
+        assert!(Arc::ptr_eq(&pid_a, &pid_b));
+        assert!(Arc::ptr_eq(&name_a, &name_b));
+        assert!(Arc::ptr_eq(&command_a, &command_b));
+    }
+
+    #[test]
+    fn test_entry_limit_bounds_tracked_processes() {
+        let _listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut cache = ProcessCache::new();
+        cache.set_entry_limit(1);
+        cache.scan_proc();
+
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.capacity), (1, 1));
+        assert!(cache.fd_snapshots.len() <= 1);
+        assert!(cache
+            .inode_to_pid
+            .values()
+            .all(|pid| cache.pid_to_process.contains_key(&**pid)));
+    }
+
+    #[test]
+    fn test_unknown_sockets_walk_proc_again() {
+        let mut cache = ProcessCache::with_proc_root("/proc");
+        cache.set_update_interval(Duration::MAX);
+        let first = TcpListener::bind("127.0.0.1:0").unwrap();
+        assert_eq!(
+            &*cache.get_process_info(socket_inode(&first)).1,
+            std::process::id().to_string()
+        );
+
+        // Right after a walk a new socket waits for the next one
+        let second = TcpListener::bind("127.0.0.1:0").unwrap();
+        assert_eq!(&*cache.get_process_info(socket_inode(&second)).1, "N/A");
+        cache.last_update -= UNKNOWN_SOCKET_WALK_INTERVAL;
+        assert_eq!(
+            &*cache.get_process_info(socket_inode(&second)).1,
+            std::process::id().to_string()
+        );
+
+        // A socket no process owns is looked for once
+        cache.last_update -= UNKNOWN_SOCKET_WALK_INTERVAL;
+        assert_eq!(&*cache.get_process_info(1).1, "N/A");
+        let walked = cache.last_update;
+        cache.last_update -= UNKNOWN_SOCKET_WALK_INTERVAL;
+        cache.get_process_info(1);
+        assert!(cache.last_update < walked);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_process_events_update_the_maps() {
+        let pid = std::process::id().to_string();
+        let mut cache = ProcessCache::with_proc_root("/proc");
+        cache.scan_proc();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let inode = socket_inode(&listener);
+        assert!(!cache.inode_to_pid.contains_key(&inode));
+
+        // Exec'd processes have their descriptors read again
+        cache.apply_events(vec![ProcEvent::Exec(pid.clone())]);
+        assert_eq!(
+            cache.inode_to_pid.get(&inode).map(|owner| &**owner),
+            Some(&*pid)
+        );
+
+        cache.apply_events(vec![ProcEvent::Exit(pid.clone())]);
+        assert!(!cache.inode_to_pid.contains_key(&inode));
+        assert!(!cache.pid_to_process.contains_key(&pid));
+        assert!(!cache.fd_snapshots.contains_key(&pid));
+
+        // Exiting right after starting leaves nothing behind
+        cache.apply_events(vec![
+            ProcEvent::Fork(pid.clone()),
+            ProcEvent::Exit(pid.clone()),
+        ]);
+        assert!(!cache.inode_to_pid.contains_key(&inode));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_only_changed_processes_are_read_again() {
+        let root = std::env::temp_dir().join(format!("nm-fd-proc-{}", std::process::id()));
+        let process = root.join("100");
+        fs::create_dir_all(process.join("fd")).unwrap();
+        fs::write(process.join("status"), "Name:\tdaemon\n").unwrap();
+        fs::write(process.join("cmdline"), "daemon\0").unwrap();
+        let start = |ticks: u64| {
+            let fields: Vec<String> = (3..22).map(|field| field.to_string()).collect();
+            let stat = format!("100 (my daemon) S {} {ticks} 0\n", fields[1..].join(" "));
+            fs::write(process.join("stat"), stat).unwrap();
+        };
+        let fd = |inode: u64| {
+            let link = process.join("fd").join("3");
+            let _ = fs::remove_file(&link);
+            std::os::unix::fs::symlink(format!("socket:[{inode}]"), link).unwrap();
+        };
+        start(500);
+        fd(5);
+        assert_eq!(ProcessCache::start_time(&root, "100"), Some(500));
+
+        let mut cache = ProcessCache::with_proc_root(&root);
+        cache.scan_proc();
+        assert_eq!(cache.inode_to_pid.get(&5).map(|pid| &**pid), Some("100"));
+
+        // Same start time, directory time and count: the fds aren't read
+        let modified = fs::metadata(process.join("fd"))
+            .unwrap()
+            .modified()
+            .unwrap();
+        fd(6);
+        fs::File::open(process.join("fd"))
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        cache.scan_proc();
+        assert_eq!(cache.inode_to_pid.get(&5).map(|pid| &**pid), Some("100"));
+
+        // Another process got the PID
+        start(900);
+        cache.scan_proc();
+        assert_eq!(cache.inode_to_pid.get(&6).map(|pid| &**pid), Some("100"));
+        assert!(!cache.inode_to_pid.contains_key(&5));
+        fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_parallel_scan_returns_every_process() {
+        let pid = std::process::id().to_string();
+        let inputs = (0..PARALLEL_SCAN_THRESHOLD * 2)
+            .map(|_| ScanInput {
+                pid: pid.clone(),
+                snapshot: None,
+                cached: None,
+            })
+            .collect();
+        let results = ProcessCache::scan_processes_parallel(
+            Path::new("/proc"),
+            inputs,
+            Instant::now(),
+            false,
+        );
+        assert_eq!(results.len(), PARALLEL_SCAN_THRESHOLD * 2);
+        assert!(results.iter().all(|(result_pid, _, _)| *result_pid == pid));
+    }
+}
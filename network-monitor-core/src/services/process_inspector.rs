@@ -0,0 +1,321 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, ProcessAncestor, ProcessDetails, SandboxInfo, SandboxKind};
+use crate::utils::validate_pid;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::Arc;
+
+/// Guard against malformed PPid loops
+const MAX_PARENT_DEPTH: usize = 64;
+
+/// Environment variables worth showing in the detail view: container runtimes,
+/// toolboxes, language environments and remote sessions
+const ENVIRONMENT_HINTS: [&str; 12] = [
+    "container",
+    "container_uuid",
+    "TOOLBOX_PATH",
+    "DISTROBOX_ENTER_PATH",
+    "KUBERNETES_SERVICE_HOST",
+    "FLATPAK_ID",
+    "SNAP_NAME",
+    "VIRTUAL_ENV",
+    "CONDA_DEFAULT_ENV",
+    "SSH_CONNECTION",
+    "TMUX",
+    "STY",
+];
+
+/// PID and name a grouped connection is attributed to
+type ApplicationRoot = (Arc<str>, Arc<str>);
+
+/// Name, parent PID and executable of a process, as used for the parent chain
+#[derive(Debug, Clone)]
+struct ProcessEntry {
+    name: String,
+    ppid: String,
+    exe: Option<String>,
+}
+
+/// Collects on-demand details about a single process for the detail view
+pub struct ProcessInspector;
+
+impl ProcessInspector {
+    /// Inspect /proc/[pid] for the given process
+    pub fn inspect(pid: &str) -> Result<ProcessDetails> {
+        validate_pid(pid)?;
+        let entry = Self::read_entry(pid)
+            .ok_or_else(|| NetworkMonitorError::ProcessNotFound(pid.to_string()))?;
+        let command = fs::read_to_string(format!("/proc/{pid}/cmdline"))
+            .map(|cmdline| cmdline.replace('\0', " ").trim_end().to_string())
+            .unwrap_or_else(|_| "N/A".to_string());
+
+        Ok(ProcessDetails {
+            pid: pid.to_string(),
+            name: entry.name,
+            command,
+            sandbox: Self::detect_sandbox(pid),
+            parents: Self::parent_chain(&entry.ppid),
+            cwd: fs::read_link(format!("/proc/{pid}/cwd"))
+                .ok()
+                .map(|path| path.to_string_lossy().into_owned()),
+            environment: fs::read(format!("/proc/{pid}/environ"))
+                .ok()
+                .map(|environ| parse_environment_hints(&environ)),
+        })
+    }
+
+    /// Walk PPid links up to init, nearest parent first
+    fn parent_chain(ppid: &str) -> Vec<ProcessAncestor> {
+        let mut parents = Vec::new();
+        let mut pid = ppid.to_string();
+        while pid != "0" && parents.len() < MAX_PARENT_DEPTH {
+            let Some(entry) = Self::read_entry(&pid) else {
+                break;
+            };
+            parents.push(ProcessAncestor {
+                pid: pid.clone(),
+                name: entry.name,
+            });
+            pid = entry.ppid;
+        }
+        parents
+    }
+
+    /// Attribute each connection to the outermost ancestor running the same
+    /// application (e.g. all `chrome --type=utility` children to the main chrome PID)
+    pub fn group_by_application(connections: &mut [Connection]) {
+        let mut entries: HashMap<String, Option<ProcessEntry>> = HashMap::new();
+        // Shared per application so grouped connections don't each allocate
+        let mut roots: HashMap<Arc<str>, Option<ApplicationRoot>> = HashMap::new();
+
+        for conn in connections.iter_mut() {
+            if validate_pid(&conn.pid).is_err() {
+                continue;
+            }
+            let root = roots
+                .entry(conn.pid.clone())
+                .or_insert_with(|| {
+                    Self::application_root(&conn.pid, &mut entries)
+                        .map(|(pid, name)| (pid.into(), name.into()))
+                })
+                .clone();
+            if let Some((pid, name)) = root {
+                conn.pid = pid;
+                conn.program = name;
+            }
+        }
+    }
+
+    /// Climb while the parent runs the same executable (or has the same name when
+    /// the executable link isn't readable)
+    fn application_root(
+        pid: &str,
+        entries: &mut HashMap<String, Option<ProcessEntry>>,
+    ) -> Option<(String, String)> {
+        let mut lookup = |pid: &str| {
+            entries
+                .entry(pid.to_string())
+                .or_insert_with(|| Self::read_entry(pid))
+                .clone()
+        };
+
+        let mut root_pid = pid.to_string();
+        let mut root = lookup(pid)?;
+        for _ in 0..MAX_PARENT_DEPTH {
+            if root.ppid == "0" {
+                break;
+            }
+            let Some(parent) = lookup(&root.ppid) else {
+                break;
+            };
+            let same_application = match (&root.exe, &parent.exe) {
+                (Some(exe), Some(parent_exe)) => exe == parent_exe,
+                _ => root.name == parent.name,
+            };
+            if !same_application {
+                break;
+            }
+            root_pid = std::mem::replace(&mut root, parent).ppid;
+        }
+        Some((root_pid, root.name))
+    }
+
+    fn read_entry(pid: &str) -> Option<ProcessEntry> {
+        let status = fs::read_to_string(format!("/proc/{pid}/status")).ok()?;
+        let (name, ppid) = parse_name_and_ppid(&status)?;
+        let exe = fs::read_link(format!("/proc/{pid}/exe"))
+            .ok()
+            .map(|path| path.to_string_lossy().into_owned());
+        Some(ProcessEntry { name, ppid, exe })
+    }
+
+    /// Detect Flatpak/Snap confinement from /.flatpak-info or the process cgroup
+    fn detect_sandbox(pid: &str) -> Option<SandboxInfo> {
+        // /.flatpak-info inside the sandbox is authoritative but only readable
+        // for our own processes (or with privileges)
+        if let Ok(info) = fs::read_to_string(format!("/proc/{pid}/root/.flatpak-info")) {
+            if let Some(app_id) = parse_flatpak_info(&info) {
+                return Some(SandboxInfo {
+                    kind: SandboxKind::Flatpak,
+                    app_id,
+                });
+            }
+        }
+
+        let cgroup = fs::read_to_string(format!("/proc/{pid}/cgroup")).ok()?;
+        parse_sandbox_from_cgroup(&cgroup)
+    }
+}
+
+/// Name and parent PID from /proc/[pid]/status
+pub fn parse_name_and_ppid(status: &str) -> Option<(String, String)> {
+    let name = status.lines().find_map(|line| line.strip_prefix("Name:"))?;
+    let ppid = status.lines().find_map(|line| line.strip_prefix("PPid:"))?;
+    Some((name.trim().to_string(), ppid.trim().to_string()))
+}
+
+/// Pick the ENVIRONMENT_HINTS variables out of a NUL separated /proc/[pid]/environ
+pub fn parse_environment_hints(environ: &[u8]) -> Vec<(String, String)> {
+    let mut hints: Vec<(String, String)> = environ
+        .split(|&byte| byte == 0)
+        .filter_map(|entry| {
+            let entry = String::from_utf8_lossy(entry);
+            let (key, value) = entry.split_once('=')?;
+            ENVIRONMENT_HINTS
+                .contains(&key)
+                .then(|| (key.to_string(), value.to_string()))
+        })
+        .collect();
+    hints.sort_by_key(|(key, _)| ENVIRONMENT_HINTS.iter().position(|hint| hint == key));
+    hints
+}
+
+/// Read the application ID from the `[Application]` section of .flatpak-info
+pub fn parse_flatpak_info(info: &str) -> Option<String> {
+    let mut in_application = false;
+    for line in info.lines().map(str::trim) {
+        if line.starts_with('[') {
+            in_application = line == "[Application]";
+        } else if in_application {
+            if let Some(name) = line.strip_prefix("name=") {
+                return Some(name.to_string());
+            }
+        }
+    }
+    None
+}
+
+/// Recognize systemd scopes created by Flatpak (`app-flatpak-<id>-<n>.scope`)
+/// and snapd (`snap.<name>.<app>-<uuid>.scope`)
+pub fn parse_sandbox_from_cgroup(cgroup: &str) -> Option<SandboxInfo> {
+    for unit in cgroup
+        .lines()
+        .filter_map(|line| line.rsplit('/').next())
+        .filter_map(|unit| unit.strip_suffix(".scope"))
+    {
+        if let Some(rest) = unit.strip_prefix("app-flatpak-") {
+            // Strip the trailing instance number
+            let app_id = rest.rsplit_once('-').map_or(rest, |(id, _)| id);
+            return Some(SandboxInfo {
+                kind: SandboxKind::Flatpak,
+                app_id: app_id.to_string(),
+            });
+        }
+        if let Some(rest) = unit.strip_prefix("snap.") {
+            let app_id = rest.split('.').next().unwrap_or(rest);
+            return Some(SandboxInfo {
+                kind: SandboxKind::Snap,
+                app_id: app_id.to_string(),
+            });
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    #[test]
+    fn test_parse_flatpak_info() {
+        let info = "[Runtime]\nname=org.freedesktop.Platform\n\n[Application]\nname=org.mozilla.firefox\nruntime=runtime/org.freedesktop.Platform\n";
+        assert_eq!(
+            parse_flatpak_info(info).as_deref(),
+            Some("org.mozilla.firefox")
+        );
+        assert_eq!(parse_flatpak_info("[Runtime]\nname=x\n"), None);
+    }
+
+    #[test]
+    fn test_parse_flatpak_cgroup() {
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/app-flatpak-org.mozilla.firefox-2345.scope\n";
+        let sandbox = parse_sandbox_from_cgroup(cgroup).unwrap();
+        assert_eq!(sandbox.kind, SandboxKind::Flatpak);
+        assert_eq!(sandbox.app_id, "org.mozilla.firefox");
+    }
+
+    #[test]
+    fn test_parse_snap_cgroup() {
+        let cgroup = "0::/user.slice/user-1000.slice/user@1000.service/app.slice/snap.spotify.spotify-1c4d6a3e-6d7b-4c5a-9f3e-0a1b2c3d4e5f.scope\n";
+        let sandbox = parse_sandbox_from_cgroup(cgroup).unwrap();
+        assert_eq!(sandbox.kind, SandboxKind::Snap);
+        assert_eq!(sandbox.app_id, "spotify");
+    }
+
+    #[test]
+    fn test_unsandboxed_cgroup() {
+        let cgroup = "0::/user.slice/user-1000.slice/session-2.scope\n";
+        assert_eq!(parse_sandbox_from_cgroup(cgroup), None);
+    }
+
+    #[test]
+    fn test_parse_name_and_ppid() {
+        let status = "Name:\tchrome\nUmask:\t0022\nState:\tS (sleeping)\nPid:\t905\nPPid:\t812\n";
+        assert_eq!(
+            parse_name_and_ppid(status),
+            Some(("chrome".to_string(), "812".to_string()))
+        );
+        assert_eq!(parse_name_and_ppid("Name:\tchrome\n"), None);
+    }
+
+    #[test]
+    fn test_parse_environment_hints() {
+        let environ = b"HOME=/home/user\0VIRTUAL_ENV=/home/user/proj/.venv\0container=podman\0PATH=/usr/bin\0";
+        assert_eq!(
+            parse_environment_hints(environ),
+            vec![
+                ("container".to_string(), "podman".to_string()),
+                (
+                    "VIRTUAL_ENV".to_string(),
+                    "/home/user/proj/.venv".to_string()
+                ),
+            ]
+        );
+        assert!(parse_environment_hints(b"").is_empty());
+    }
+
+    #[test]
+    fn test_inspect_current_process() {
+        let details = ProcessInspector::inspect(&std::process::id().to_string()).unwrap();
+        assert!(!details.name.is_empty());
+        assert!(!details.parents.is_empty());
+        assert!(details.cwd.is_some());
+        assert!(ProcessInspector::inspect("abc").is_err());
+    }
+
+    #[test]
+    fn test_group_by_application_keeps_unknown_pids() {
+        let mut connections = vec![Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Listen,
+            "127.0.0.1:80".to_string(),
+            "0.0.0.0:0".to_string(),
+            "N/A".to_string(),
+            "N/A".to_string(),
+            "N/A".to_string(),
+        )];
+        ProcessInspector::group_by_application(&mut connections);
+        assert_eq!(&*connections[0].pid, "N/A");
+    }
+}
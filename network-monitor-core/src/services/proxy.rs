@@ -0,0 +1,173 @@
+use crate::models::{Connection, ConnectionState, Protocol};
+use crate::utils::{parse_endpoint, validate_pid};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Default ports of SOCKS, Squid, HTTP alternate, Privoxy and Tor
+const PROXY_PORTS: [u16; 6] = [1080, 3128, 8080, 8118, 9050, 9150];
+
+/// Spots proxies running on this machine: a loopback listener whose process
+/// also has connections leaving the machine, either on a well-known proxy
+/// port or serving several processes. The clients' connections point at the
+/// proxy rather than their real destination, so they are flagged instead of
+/// being mistaken for local traffic.
+pub struct ProxyDetector;
+
+impl ProxyDetector {
+    /// Set `proxy` on the connections going through a local proxy
+    pub fn mark(connections: &mut [Connection]) {
+        let is_tcp = |conn: &Connection| matches!(conn.protocol, Protocol::Tcp | Protocol::Tcp6);
+
+        // Owner of each listening port
+        let listeners: HashMap<u16, (Arc<str>, Arc<str>)> = connections
+            .iter()
+            .filter(|conn| conn.state == ConnectionState::Listen && is_tcp(conn))
+            .filter(|conn| validate_pid(&conn.pid).is_ok())
+            .filter_map(|conn| {
+                let port = parse_endpoint(&conn.local)?.port();
+                Some((port, (conn.pid.clone(), conn.program.clone())))
+            })
+            .collect();
+        if listeners.is_empty() {
+            return;
+        }
+
+        // Listener port of each connection from another local process
+        let client_of = |conn: &Connection| {
+            if conn.state == ConnectionState::Listen || !is_tcp(conn) {
+                return None;
+            }
+            let remote = parse_endpoint(&conn.remote)?;
+            let (pid, _) = listeners.get(&remote.port())?;
+            (remote.ip().is_loopback() && *pid != conn.pid).then_some(remote.port())
+        };
+
+        let forwarding: HashSet<&Arc<str>> = connections
+            .iter()
+            .filter(|conn| conn.state == ConnectionState::Established && is_tcp(conn))
+            .filter(|conn| {
+                parse_endpoint(&conn.remote).is_some_and(|remote| !remote.ip().is_loopback())
+            })
+            .map(|conn| &conn.pid)
+            .collect();
+        let mut clients: HashMap<u16, HashSet<&Arc<str>>> = HashMap::new();
+        for conn in connections.iter() {
+            if let Some(port) = client_of(conn) {
+                clients.entry(port).or_default().insert(&conn.pid);
+            }
+        }
+        let proxies: HashMap<u16, Arc<str>> = clients
+            .iter()
+            .filter_map(|(port, clients)| {
+                let (pid, program) = &listeners[port];
+                let serves_many = PROXY_PORTS.contains(port) || clients.len() > 1;
+                (forwarding.contains(pid) && serves_many).then(|| (*port, program.clone()))
+            })
+            .collect();
+        if proxies.is_empty() {
+            return;
+        }
+
+        let ports: Vec<Option<u16>> = connections.iter().map(client_of).collect();
+        for (conn, port) in connections.iter_mut().zip(ports) {
+            if let Some(program) = port.and_then(|port| proxies.get(&port)) {
+                conn.proxy = Some(program.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tcp(
+        state: ConnectionState,
+        local: &str,
+        remote: &str,
+        program: &str,
+        pid: &str,
+    ) -> Connection {
+        Connection::new(Protocol::Tcp, state, local, remote, program, pid, program)
+    }
+
+    #[test]
+    fn test_clients_of_forwarding_listener_are_marked() {
+        use ConnectionState::*;
+        let mut connections = vec![
+            tcp(Listen, "127.0.0.1:8118", "0.0.0.0:*", "privoxy", "800"),
+            tcp(
+                Established,
+                "10.0.0.2:51000",
+                "93.184.216.34:443",
+                "privoxy",
+                "800",
+            ),
+            tcp(
+                Established,
+                "127.0.0.1:40000",
+                "127.0.0.1:8118",
+                "curl",
+                "100",
+            ),
+            // The proxy's side of the same connection
+            tcp(
+                Established,
+                "127.0.0.1:8118",
+                "127.0.0.1:40000",
+                "privoxy",
+                "800",
+            ),
+            // A development server that isn't forwarding anything
+            tcp(Listen, "127.0.0.1:3000", "0.0.0.0:*", "node", "900"),
+            tcp(
+                Established,
+                "127.0.0.1:40001",
+                "127.0.0.1:3000",
+                "firefox",
+                "200",
+            ),
+        ];
+        ProxyDetector::mark(&mut connections);
+        let marked: Vec<_> = connections
+            .iter()
+            .map(|conn| conn.proxy.as_deref())
+            .collect();
+        assert_eq!(marked, [None, None, Some("privoxy"), None, None, None]);
+    }
+
+    #[test]
+    fn test_unusual_ports_need_several_clients() {
+        use ConnectionState::*;
+        let mut connections = vec![
+            tcp(Listen, "127.0.0.1:7890", "0.0.0.0:*", "clash", "800"),
+            tcp(
+                Established,
+                "10.0.0.2:51000",
+                "93.184.216.34:443",
+                "clash",
+                "800",
+            ),
+            tcp(
+                Established,
+                "127.0.0.1:40000",
+                "127.0.0.1:7890",
+                "curl",
+                "100",
+            ),
+        ];
+        ProxyDetector::mark(&mut connections);
+        assert_eq!(connections[2].proxy, None);
+
+        connections.push(tcp(
+            Established,
+            "127.0.0.1:40001",
+            "127.0.0.1:7890",
+            "firefox",
+            "200",
+        ));
+        ProxyDetector::mark(&mut connections);
+        assert_eq!(connections[2].proxy.as_deref(), Some("clash"));
+        assert_eq!(connections[3].proxy.as_deref(), Some("clash"));
+    }
+}
@@ -0,0 +1,144 @@
+use crate::error::Result;
+use crate::models::{
+    Connection, ConnectionSnapshot, ConnectionState, EphemeralPorts, MulticastGroup, ProcessIOMap,
+    ProtocolCounters, ProtocolStats, TrafficTotals,
+};
+use crate::services::{ApplicationAccounting, NetworkService, ShortLivedTracker, WarmStart};
+use std::time::Instant;
+
+/// Turns the cumulative I/O counters of each process, the kernel's protocol
+/// counters and the cgroup counters into per-second rates by comparing
+/// successive collection passes. The first pass has no rates yet. Adds the
+/// short-lived connections the connect tracer saw in between.
+#[derive(Debug, Default)]
+pub struct RateTracker {
+    previous: ProcessIOMap,
+    protocol: Option<(ProtocolCounters, Instant)>,
+    applications: ApplicationAccounting,
+    short_lived: ShortLivedTracker,
+}
+
+impl RateTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Snapshot of `connections`, with `rx_rate` and `tx_rate` from what
+    /// their processes transferred since the previous call, the use of the
+    /// service's ephemeral port range and the kernel's protocol counters
+    pub fn snapshot(
+        &mut self,
+        service: &NetworkService,
+        connections: Vec<Connection>,
+    ) -> Result<ConnectionSnapshot> {
+        let (connections, current) =
+            service.update_connection_rates(connections, &self.previous)?;
+        self.previous = current;
+        let mut snapshot = ConnectionSnapshot::new(connections, self.totals());
+        snapshot.process_cache = service.cache_stats();
+        snapshot.ephemeral_ports = Some(EphemeralPorts::from_connections(
+            &snapshot.connections,
+            service.local_port_range(),
+        ));
+        snapshot.protocol_stats = service.protocol_counters().map(|counters| {
+            let now = Instant::now();
+            let previous = self
+                .protocol
+                .map(|(previous, at)| (previous, now.duration_since(at)));
+            self.protocol = Some((counters, now));
+            ProtocolStats::new(counters, previous)
+        });
+        snapshot.tunnels = service.tunnels();
+        let limits = service.bandwidth_limits();
+        for conn in &mut snapshot.connections {
+            conn.tunnel = snapshot
+                .tunnels
+                .iter()
+                .find(|tunnel| tunnel.carries(conn))
+                .map(|tunnel| tunnel.name.as_str().into());
+            conn.bandwidth_limit = limits.get(&*conn.pid).copied();
+        }
+        // The netlink backend read the algorithms along with the sockets
+        let missing_congestion = |conn: &Connection| {
+            conn.protocol.is_tcp()
+                && conn.state == ConnectionState::Established
+                && conn.congestion.is_none()
+        };
+        if snapshot.connections.iter().any(missing_congestion) {
+            let algorithms = service.congestion_controls();
+            for conn in snapshot.connections.iter_mut() {
+                if missing_congestion(conn) {
+                    conn.congestion = algorithms
+                        .get(&(conn.local.clone(), conn.remote.clone()))
+                        .cloned();
+                }
+            }
+        }
+        snapshot.routes = service.routing_table();
+        snapshot.multicast = service.multicast_groups();
+        MulticastGroup::attribute(&mut snapshot.multicast, &snapshot.connections);
+        snapshot.wireless = service.wireless_links();
+        if let Some(counters) = service.cgroup_traffic() {
+            snapshot.applications = self.applications.update(counters, Instant::now());
+        }
+        if let Some(events) = service.connect_events() {
+            let short_lived =
+                self.short_lived
+                    .update(events, &snapshot.connections, Instant::now());
+            snapshot.connections.extend(short_lived);
+        }
+        Ok(snapshot)
+    }
+
+    /// What the next session needs to measure rates from its first pass
+    pub fn warm_state(&self, service: &NetworkService) -> WarmStart {
+        WarmStart::new(service.socket_owners(), self.previous.clone())
+    }
+
+    /// Measure the first pass against the counters of a previous session
+    pub fn warm_start(&mut self, service: &NetworkService, state: WarmStart) {
+        let age = state.age();
+        let live = service.warm_start(state.owners, age);
+        self.previous = state
+            .io
+            .into_iter()
+            .filter(|(pid, _)| live.contains(&**pid))
+            .collect();
+    }
+
+    /// Bytes written and read so far by the processes of the last pass
+    pub fn totals(&self) -> TrafficTotals {
+        TrafficTotals {
+            sent: self.previous.values().map(|io| io.tx).sum(),
+            received: self.previous.values().map(|io| io.rx).sum(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Protocol;
+
+    #[test]
+    fn test_first_pass_has_totals_but_no_rates() {
+        let service = NetworkService::new();
+        let connection = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "127.0.0.1:1234",
+            "127.0.0.1:5678",
+            "test",
+            std::process::id().to_string(),
+            "test",
+        );
+        let mut rates = RateTracker::new();
+        let snapshot = rates.snapshot(&service, vec![connection]).unwrap();
+        assert_eq!(snapshot.connections[0].rx_rate, 0);
+        assert_eq!(snapshot.connections[0].tx_rate, 0);
+        // Reading our own /proc files already counts as I/O
+        assert!(snapshot.totals.received > 0);
+        assert_eq!(snapshot.totals, rates.totals());
+        assert_eq!(snapshot.ephemeral_ports.unwrap().tcp, 0);
+    }
+}
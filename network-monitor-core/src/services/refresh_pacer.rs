@@ -0,0 +1,137 @@
+use std::time::Duration;
+
+/// A collection pass may take up to 1/N of the refresh interval
+const COLLECTION_BUDGET_DIVISOR: u32 = 4;
+/// Never back off beyond this multiple of the base interval
+const MAX_BACKOFF_FACTOR: u32 = 8;
+/// Stretch the interval this many times while slowed down
+const SLOWDOWN_FACTOR: u32 = 4;
+
+/// Picks the auto-refresh interval from how long collection passes take, so a
+/// slow /proc scan lowers the refresh rate instead of keeping the collector busy
+#[derive(Debug, Clone)]
+pub struct RefreshPacer {
+    base: Duration,
+    interval: Duration,
+    slowed: bool,
+}
+
+impl RefreshPacer {
+    pub fn new(base: Duration) -> Self {
+        Self {
+            base,
+            interval: base,
+            slowed: false,
+        }
+    }
+
+    /// Change the requested interval, dropping any back-off
+    pub fn set_base(&mut self, base: Duration) {
+        self.base = base;
+        self.interval = base;
+    }
+
+    pub fn interval(&self) -> Duration {
+        if self.slowed {
+            self.interval * SLOWDOWN_FACTOR
+        } else {
+            self.interval
+        }
+    }
+
+    /// Refresh less often, e.g. while nobody is looking or on battery,
+    /// on top of any back-off. Returns true when that changed.
+    pub fn set_slowed(&mut self, slowed: bool) -> bool {
+        let changed = slowed != self.slowed;
+        self.slowed = slowed;
+        changed
+    }
+
+    pub fn is_slowed(&self) -> bool {
+        self.slowed
+    }
+
+    /// Whether the interval is currently longer than requested
+    pub fn is_backed_off(&self) -> bool {
+        self.interval > self.base
+    }
+
+    /// Account for one finished pass. Backs off at once when the pass exceeded
+    /// its budget and recovers by halving once passes are fast again.
+    /// Returns true when the interval changed.
+    pub fn record(&mut self, collection_time: Duration) -> bool {
+        let wanted = (collection_time * COLLECTION_BUDGET_DIVISOR)
+            .clamp(self.base, self.base * MAX_BACKOFF_FACTOR);
+        let next = if wanted >= self.interval {
+            wanted
+        } else {
+            (self.interval / 2).max(wanted)
+        };
+
+        // Whole seconds keep the UI indicator and timers stable
+        let next = Duration::from_secs(next.as_secs_f64().ceil() as u64).max(self.base);
+        let changed = next != self.interval;
+        self.interval = next;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_passes_keep_base_interval() {
+        let mut pacer = RefreshPacer::new(Duration::from_secs(2));
+        assert!(!pacer.record(Duration::from_millis(40)));
+        assert_eq!(pacer.interval(), Duration::from_secs(2));
+        assert!(!pacer.is_backed_off());
+    }
+
+    #[test]
+    fn test_slow_pass_backs_off_and_recovers() {
+        let mut pacer = RefreshPacer::new(Duration::from_secs(2));
+        assert!(pacer.record(Duration::from_millis(2500)));
+        assert_eq!(pacer.interval(), Duration::from_secs(10));
+        assert!(pacer.is_backed_off());
+
+        // Capped at 8x the base interval
+        pacer.record(Duration::from_secs(30));
+        assert_eq!(pacer.interval(), Duration::from_secs(16));
+
+        // Halves per fast pass back down to the base
+        pacer.record(Duration::from_millis(50));
+        assert_eq!(pacer.interval(), Duration::from_secs(8));
+        pacer.record(Duration::from_millis(50));
+        pacer.record(Duration::from_millis(50));
+        assert_eq!(pacer.interval(), Duration::from_secs(2));
+        assert!(!pacer.is_backed_off());
+    }
+
+    #[test]
+    fn test_set_base_drops_back_off() {
+        let mut pacer = RefreshPacer::new(Duration::from_secs(2));
+        pacer.record(Duration::from_secs(3));
+        assert!(pacer.is_backed_off());
+
+        pacer.set_base(Duration::from_secs(5));
+        assert_eq!(pacer.interval(), Duration::from_secs(5));
+        assert!(!pacer.is_backed_off());
+    }
+
+    #[test]
+    fn test_slowdown_stretches_the_interval() {
+        let mut pacer = RefreshPacer::new(Duration::from_secs(2));
+        assert!(pacer.set_slowed(true));
+        assert!(!pacer.set_slowed(true));
+        assert_eq!(pacer.interval(), Duration::from_secs(8));
+        assert!(!pacer.is_backed_off());
+
+        // Backing off still works while slowed
+        pacer.record(Duration::from_millis(2500));
+        assert_eq!(pacer.interval(), Duration::from_secs(40));
+
+        assert!(pacer.set_slowed(false));
+        assert_eq!(pacer.interval(), Duration::from_secs(10));
+    }
+}
@@ -0,0 +1,295 @@
+//! Collection on other machines over SSH. The remote end runs the bundled
+//! collector, `nmt --serve`, which answers every request line on its stdin
+//! with one line of JSON on its stdout: the connections of a pass, with
+//! rates worked out over there, and the machine's traffic totals.
+//! [`CombinedCollector`] puts several machines in one table.
+
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, ConnectionSnapshot, TrafficTotals};
+use crate::services::Collector;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+use std::sync::Arc;
+
+/// Run on the remote machine when its host doesn't name another command
+pub const DEFAULT_REMOTE_COMMAND: &str = "nmt --serve";
+
+/// One pass as it travels from `nmt --serve` to an [`SshCollector`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct RemotePass {
+    connections: Vec<Connection>,
+    totals: TrafficTotals,
+}
+
+/// Answer each line read from `input` with a pass of `collector` on
+/// `output`, until `input` ends. Failed passes are sent as a JSON string
+/// holding the error, so the other end can show it.
+pub fn serve(
+    collector: &mut dyn Collector,
+    input: impl BufRead,
+    mut output: impl Write,
+) -> io::Result<()> {
+    for line in input.lines() {
+        line?;
+        let reply = match collector.collect() {
+            Ok(snapshot) => serde_json::to_string(&RemotePass {
+                connections: snapshot.connections,
+                totals: snapshot.totals,
+            }),
+            Err(e) => serde_json::to_string(&e.to_string()),
+        }
+        .map_err(io::Error::other)?;
+        writeln!(output, "{reply}")?;
+        output.flush()?;
+    }
+    Ok(())
+}
+
+/// Snapshot from one line written by [`serve`]
+fn parse_pass(line: &str) -> Result<ConnectionSnapshot> {
+    let invalid = |e: serde_json::Error| NetworkMonitorError::Remote(format!("bad reply: {e}"));
+    if line.starts_with('"') {
+        let error: String = serde_json::from_str(line).map_err(invalid)?;
+        return Err(NetworkMonitorError::Remote(error));
+    }
+    let pass: RemotePass = serde_json::from_str(line).map_err(invalid)?;
+    Ok(ConnectionSnapshot::new(pass.connections, pass.totals))
+}
+
+/// Collects on another machine by running `command` there through `ssh`,
+/// which must log in without prompting, e.g. with a key loaded in an agent.
+/// The session stays open between passes so rates are measured remotely,
+/// and is started again on the next pass when it drops.
+pub struct SshCollector {
+    destination: String,
+    command: String,
+    session: Option<Session>,
+}
+
+struct Session {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl SshCollector {
+    /// `destination` as ssh takes it: a host, user@host or an alias from
+    /// ~/.ssh/config
+    pub fn new(destination: &str, command: &str) -> Self {
+        Self {
+            destination: destination.to_string(),
+            command: command.to_string(),
+            session: None,
+        }
+    }
+
+    fn start(&self) -> Result<Session> {
+        let mut child = Command::new("ssh")
+            .args(["-T", "-o", "BatchMode=yes", "--", &self.destination])
+            .arg(&self.command)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| NetworkMonitorError::Remote(format!("could not run ssh: {e}")))?;
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            return Err(NetworkMonitorError::Remote("ssh has no pipes".to_string()));
+        };
+        Ok(Session {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+        })
+    }
+
+    /// Why the session ended: the last thing ssh or the command printed
+    fn ended(&self, mut session: Session) -> NetworkMonitorError {
+        drop(session.stdin);
+        let _ = session.child.wait();
+        let mut stderr = String::new();
+        if let Some(mut pipe) = session.child.stderr.take() {
+            let _ = pipe.read_to_string(&mut stderr);
+        }
+        let reason = stderr
+            .lines()
+            .map(str::trim)
+            .rfind(|line| !line.is_empty())
+            .unwrap_or("connection closed");
+        NetworkMonitorError::Remote(format!("{}: {reason}", self.destination))
+    }
+}
+
+impl Collector for SshCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let mut session = match self.session.take() {
+            Some(session) => session,
+            None => self.start()?,
+        };
+        let mut line = String::new();
+        let answered = writeln!(session.stdin)
+            .and_then(|()| session.stdin.flush())
+            .and_then(|()| session.stdout.read_line(&mut line));
+        match answered {
+            Ok(read) if read > 0 => {
+                self.session = Some(session);
+                parse_pass(line.trim_end())
+            }
+            _ => Err(self.ended(session)),
+        }
+    }
+}
+
+impl Drop for SshCollector {
+    fn drop(&mut self) {
+        if let Some(mut session) = self.session.take() {
+            let _ = session.child.kill();
+            let _ = session.child.wait();
+        }
+    }
+}
+
+/// Collects from several machines in turn, marking each connection with the
+/// [`Connection::host`] it is on and adding up their totals. A machine that
+/// fails is left out of the pass; the pass only fails when all of them do.
+pub struct CombinedCollector {
+    hosts: Vec<(Arc<str>, Box<dyn Collector>)>,
+}
+
+impl CombinedCollector {
+    pub fn new(hosts: Vec<(String, Box<dyn Collector>)>) -> Self {
+        Self {
+            hosts: hosts
+                .into_iter()
+                .map(|(name, collector)| (name.into(), collector))
+                .collect(),
+        }
+    }
+}
+
+impl Collector for CombinedCollector {
+    fn collect(&mut self) -> Result<ConnectionSnapshot> {
+        let mut connections = Vec::new();
+        let mut totals = TrafficTotals::default();
+        let mut failure = None;
+        for (name, collector) in &mut self.hosts {
+            match collector.collect() {
+                Ok(snapshot) => {
+                    totals.sent += snapshot.totals.sent;
+                    totals.received += snapshot.totals.received;
+                    connections.extend(snapshot.connections.into_iter().map(|mut conn| {
+                        conn.host = Some(name.clone());
+                        conn
+                    }));
+                }
+                Err(e) => {
+                    tracing::warn!("Collection on {} failed: {}", name, e);
+                    failure = Some(e);
+                }
+            }
+        }
+        match failure {
+            Some(e) if connections.is_empty() => Err(e),
+            _ => Ok(ConnectionSnapshot::new(connections, totals)),
+        }
+    }
+
+    /// Started for every machine that uses one, typically this one
+    fn enable_privileged_helper(&mut self) -> Result<()> {
+        let results: Vec<Result<()>> = self
+            .hosts
+            .iter_mut()
+            .map(|(_, collector)| collector.enable_privileged_helper())
+            .collect();
+        if results.iter().any(Result::is_ok) {
+            return Ok(());
+        }
+        results.into_iter().next().unwrap_or(Ok(()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+    use crate::services::FixtureCollector;
+
+    /// Fails every pass, like a backend that can't read the socket tables
+    struct Broken;
+
+    impl Collector for Broken {
+        fn collect(&mut self) -> Result<ConnectionSnapshot> {
+            Err(NetworkMonitorError::Netlink(
+                "permission denied".to_string(),
+            ))
+        }
+    }
+
+    #[test]
+    fn test_passes_travel_as_json_lines() {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.5:22",
+            "10.0.0.9:51000",
+            "sshd",
+            "812",
+            "sshd: admin",
+        );
+        conn.tx_rate = 1500;
+        let mut collector = FixtureCollector::new(vec![vec![conn.clone()]]);
+        let mut output = Vec::new();
+        serve(&mut collector, &b"\n\n"[..], &mut output).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        let lines: Vec<&str> = output.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        let snapshot = parse_pass(lines[1]).unwrap();
+        assert_eq!(snapshot.connections.len(), 1);
+        assert_eq!(snapshot.connections[0].remote, conn.remote);
+        assert_eq!(snapshot.connections[0].tx_rate, 1500);
+        assert_eq!(snapshot.totals.sent, 3000);
+
+        let mut output = Vec::new();
+        serve(&mut Broken, &b"\n"[..], &mut output).unwrap();
+        let error = parse_pass(String::from_utf8(output).unwrap().trim_end()).unwrap_err();
+        assert!(error.to_string().contains("permission denied"), "{error}");
+        assert!(parse_pass("Welcome to web1!").is_err());
+    }
+
+    #[test]
+    fn test_combined_passes_name_the_host() {
+        let conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Listen,
+            "0.0.0.0:22",
+            "0.0.0.0:*",
+            "sshd",
+            "812",
+            "sshd",
+        );
+        let mut combined = CombinedCollector::new(vec![
+            (
+                "local".to_string(),
+                Box::new(FixtureCollector::new(vec![vec![conn.clone()]])) as Box<dyn Collector>,
+            ),
+            ("web1".to_string(), Box::new(Broken)),
+            (
+                "db".to_string(),
+                Box::new(FixtureCollector::new(vec![vec![conn]])),
+            ),
+        ]);
+        let snapshot = combined.collect().unwrap();
+        let hosts: Vec<&str> = snapshot
+            .connections
+            .iter()
+            .filter_map(|conn| conn.host.as_deref())
+            .collect();
+        assert_eq!(hosts, ["local", "db"]);
+        // The same socket on two machines is two connections
+        assert_ne!(snapshot.connections[0].key(), snapshot.connections[1].key());
+
+        let mut broken = CombinedCollector::new(vec![("web1".to_string(), Box::new(Broken))]);
+        assert!(broken.collect().is_err());
+    }
+}
@@ -0,0 +1,604 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{Connection, HostNames};
+use crate::services::{CacheLimits, Timing};
+use crate::utils::{CacheStats, CircuitBreaker, LruCache};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{self, Receiver, SyncSender, TrySendError};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Number of threads performing reverse lookups
+const RESOLVER_WORKERS: usize = 4;
+/// Lookups waiting for a worker; further requests are dropped and retried on a later refresh
+const RESOLVER_QUEUE_CAPACITY: usize = 256;
+/// Failed lookups in a row after which lookups pause, e.g. without network
+const LOOKUP_FAILURE_THRESHOLD: u32 = 5;
+/// How long lookups pause after repeated failures
+const LOOKUP_RETRY_AFTER: Duration = Duration::from_secs(30);
+/// Saved hostnames older than this are looked up again instead of loaded
+const SAVED_CACHE_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Resolver cache file written between sessions
+#[derive(Serialize, Deserialize)]
+struct SavedCache {
+    saved_at: SystemTime,
+    /// IP and hostname, or None when the lookup found no name
+    hostnames: Vec<(String, Option<String>)>,
+}
+
+/// Service for resolving IP addresses to hostnames
+#[derive(Clone)]
+pub struct AddressResolver {
+    /// Hostname per IP; `None` when the lookup found no name
+    cache: Arc<Mutex<LruCache<String, Option<String>>>>,
+    /// IPs queued or being looked up, so each is only requested once
+    pending: Arc<Mutex<HashSet<String>>>,
+    resolve_hosts: Arc<Mutex<bool>>,
+    notifier: Arc<Mutex<Option<async_channel::Sender<()>>>>,
+    /// Work queue of the lookup pool, started on the first lookup
+    queue: Arc<Mutex<Option<SyncSender<String>>>>,
+    /// Duration of completed lookups
+    latency: Arc<Mutex<Timing>>,
+    /// Pauses lookups while `host` is missing or DNS doesn't answer
+    breaker: Arc<Mutex<CircuitBreaker>>,
+    /// Names given in the config, shown whether resolution is on or not
+    host_names: Arc<Mutex<HostNames>>,
+    /// Bumped whenever the names shown may have changed
+    generation: Arc<AtomicU64>,
+}
+
+impl AddressResolver {
+    pub fn new(resolve_hosts: bool) -> Self {
+        Self {
+            cache: Arc::new(Mutex::new(LruCache::new(
+                CacheLimits::default().resolver_entries,
+            ))),
+            pending: Arc::new(Mutex::new(HashSet::new())),
+            resolve_hosts: Arc::new(Mutex::new(resolve_hosts)),
+            notifier: Arc::new(Mutex::new(None)),
+            queue: Arc::new(Mutex::new(None)),
+            latency: Arc::new(Mutex::new(Timing::default())),
+            breaker: Arc::new(Mutex::new(CircuitBreaker::new(
+                LOOKUP_FAILURE_THRESHOLD,
+                LOOKUP_RETRY_AFTER,
+            ))),
+            host_names: Arc::new(Mutex::new(HostNames::default())),
+            generation: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    /// Changes whenever a lookup completes or the names shown change
+    /// otherwise, so anything ordered by names knows to sort again
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    fn names_changed(&self) {
+        self.generation.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Show `names` instead of the addresses they cover, ahead of DNS names
+    pub fn set_host_names(&self, names: HostNames) {
+        if let Ok(mut host_names) = self.host_names.lock() {
+            *host_names = names;
+        }
+        self.names_changed();
+    }
+
+    /// Configured name of a bare IP address
+    fn host_name(&self, ip: &str) -> Option<String> {
+        let ip = ip.parse().ok()?;
+        let names = self.host_names.lock().ok()?;
+        names.name_of(ip).map(str::to_string)
+    }
+
+    /// Signal `sender` whenever a background lookup completes, so the UI can redraw
+    pub fn set_notifier(&self, sender: async_channel::Sender<()>) {
+        if let Ok(mut notifier) = self.notifier.lock() {
+            *notifier = Some(sender);
+        }
+    }
+
+    /// Resolve an address to hostname if resolution is enabled
+    pub fn resolve_address(&self, addr: &str) -> String {
+        // Handle special cases
+        if addr == "0.0.0.0:*" || addr == "*:*" || addr == "[::]:*" {
+            return "ANY".to_string();
+        } else if addr.starts_with("127.0.0.1:") || addr.starts_with("[::1]:") {
+            return "LOCALHOST".to_string();
+        } else if addr.starts_with("224.0.0.251:") {
+            return "MDNS".to_string();
+        }
+
+        let (ip_part, port) = split_host_port(addr);
+        if let Some(name) = self.host_name(ip_part) {
+            return if port.is_empty() {
+                name
+            } else {
+                format!("{name}:{port}")
+            };
+        }
+
+        // Check if resolution is disabled with timeout
+        let resolve_hosts = match self.resolve_hosts.lock() {
+            Ok(guard) => *guard,
+            Err(_) => return addr.to_string(), // Mutex poisoned, return original
+        };
+        if !resolve_hosts {
+            return addr.to_string();
+        }
+
+        match self.hostname(ip_part) {
+            Some(hostname) if port.is_empty() => hostname,
+            Some(hostname) => format!("{hostname}:{port}"),
+            None => addr.to_string(),
+        }
+    }
+
+    /// Remote address of `conn` as [`resolve_address`](Self::resolve_address)
+    /// shows it, falling back to the TLS server name when the address has no
+    /// hostname. Addresses stay as they are while resolution is off.
+    /// Connections through a proxy show the destination asked of it when
+    /// known, tagged with the proxy, and those through a VPN are tagged with
+    /// its interface.
+    pub fn resolve_remote(&self, conn: &Connection) -> String {
+        let resolved = self.resolve_address(&conn.remote);
+        let shown = match (&conn.upstream, &conn.server_name) {
+            (Some(upstream), _) => upstream.to_string(),
+            // The port tells which local proxy
+            (None, _) if conn.proxy.is_some() => conn.remote.to_string(),
+            (None, Some(name)) if resolved == *conn.remote && self.get_resolve_hosts() => {
+                format!("{name}:{}", split_host_port(&conn.remote).1)
+            }
+            _ => resolved.clone(),
+        };
+        let shown = match (&conn.proxy, &conn.upstream) {
+            (Some(proxy), _) => format!("{shown} [proxy:{proxy}]"),
+            (None, Some(_)) => format!("{shown} [proxy:{resolved}]"),
+            (None, None) => shown,
+        };
+        match &conn.tunnel {
+            Some(tunnel) => format!("{shown} [tunnel:{tunnel}]"),
+            None => shown,
+        }
+    }
+
+    /// Hostname of a bare IP address (IPv6 without brackets) if resolution is
+    /// enabled, the address itself until its lookup completes
+    pub fn resolve_host(&self, ip: &str) -> String {
+        if let Some(name) = self.host_name(ip) {
+            return name;
+        }
+        if !self.get_resolve_hosts() {
+            return ip.to_string();
+        }
+        self.hostname(ip).unwrap_or_else(|| ip.to_string())
+    }
+
+    /// Cached hostname of `ip`, queueing a lookup when it isn't known yet
+    fn hostname(&self, ip: &str) -> Option<String> {
+        // Check cache first with timeout
+        {
+            let mut cache = self.cache.lock().ok()?;
+            if let Some(hostname) = cache.get(ip) {
+                return hostname.clone();
+            }
+        }
+
+        // Queue a lookup unless one for this IP is already pending
+        let mut pending = self.pending.lock().ok()?;
+        if pending.insert(ip.to_string()) && !self.enqueue(ip.to_string()) {
+            // Queue full: forget the request so a later refresh asks again
+            pending.remove(ip);
+        }
+        None
+    }
+
+    /// Hand `ip` to the lookup pool, returning false if the queue is full
+    fn enqueue(&self, ip: String) -> bool {
+        let Ok(mut queue) = self.queue.lock() else {
+            return false;
+        };
+        let sender = queue.get_or_insert_with(|| self.start_workers());
+        match sender.try_send(ip) {
+            Ok(()) => true,
+            Err(TrySendError::Full(_)) => false,
+            Err(TrySendError::Disconnected(_)) => {
+                // Every worker is gone; start a fresh pool for the next request
+                *queue = None;
+                false
+            }
+        }
+    }
+
+    /// Spawn the lookup workers. They exit once every resolver clone is dropped.
+    fn start_workers(&self) -> SyncSender<String> {
+        let (sender, receiver) = mpsc::sync_channel(RESOLVER_QUEUE_CAPACITY);
+        let receiver = Arc::new(Mutex::new(receiver));
+
+        for _ in 0..RESOLVER_WORKERS {
+            let receiver = receiver.clone();
+            let cache = self.cache.clone();
+            let pending = self.pending.clone();
+            let notifier = self.notifier.clone();
+            let latency = self.latency.clone();
+            let breaker = self.breaker.clone();
+            let generation = self.generation.clone();
+
+            thread::spawn(move || {
+                while let Some(ip) = next_request(&receiver) {
+                    // Failed and skipped lookups aren't cached, so a later refresh asks again
+                    if let Some(hostname) = guarded_lookup(&breaker, &latency, &ip) {
+                        if let Ok(mut cache) = cache.lock() {
+                            cache.insert(ip.clone(), hostname);
+                        }
+                        generation.fetch_add(1, Ordering::Relaxed);
+                    }
+
+                    // Remove from pending with error handling
+                    if let Ok(mut pending) = pending.lock() {
+                        pending.remove(&ip);
+                    }
+
+                    if let Ok(notifier) = notifier.lock() {
+                        if let Some(sender) = notifier.as_ref() {
+                            let _ = sender.try_send(());
+                        }
+                    }
+                }
+            });
+        }
+
+        sender
+    }
+
+    /// Set whether to resolve hostnames
+    pub fn set_resolve_hosts(&self, resolve: bool) {
+        if let Ok(mut resolve_hosts) = self.resolve_hosts.lock() {
+            *resolve_hosts = resolve;
+            if !resolve {
+                if let Ok(mut cache) = self.cache.lock() {
+                    cache.clear();
+                }
+            }
+        }
+        self.names_changed();
+    }
+
+    /// Get current resolve hosts setting
+    pub fn get_resolve_hosts(&self) -> bool {
+        self.resolve_hosts
+            .lock()
+            .map(|guard| *guard)
+            .unwrap_or(false) // Default to false if mutex is poisoned
+    }
+
+    /// Limit how many hostnames are kept
+    pub fn set_cache_limit(&self, entries: usize) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.set_capacity(entries);
+        }
+    }
+
+    /// Cached hostnames and the approximate memory they take
+    pub fn cache_stats(&self) -> CacheStats {
+        self.cache
+            .lock()
+            .map(|cache| {
+                cache.stats(|ip, hostname| ip.len() + hostname.as_ref().map_or(0, String::len))
+            })
+            .unwrap_or_default()
+    }
+
+    /// How long reverse lookups have been taking
+    pub fn lookup_latency(&self) -> Timing {
+        self.latency
+            .lock()
+            .map(|latency| *latency)
+            .unwrap_or_default()
+    }
+
+    /// Fill the cache from a file written by `save_cache`, returning how many
+    /// hostnames were loaded. Missing or stale files load nothing.
+    pub fn load_cache(&self, path: &Path) -> io::Result<usize> {
+        let saved: SavedCache = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e),
+        };
+        let age = saved.saved_at.elapsed().unwrap_or(Duration::MAX);
+        if age > SAVED_CACHE_MAX_AGE {
+            return Ok(0);
+        }
+        let mut cache = self
+            .cache
+            .lock()
+            .map_err(|_| io::Error::other("resolver cache lock poisoned"))?;
+        let count = saved.hostnames.len();
+        for (ip, hostname) in saved.hostnames {
+            cache.insert(ip, hostname);
+        }
+        self.names_changed();
+        Ok(count)
+    }
+
+    /// Write the cached hostnames to `path` so the next session starts with
+    /// them. Nothing is written while resolution is off.
+    pub fn save_cache(&self, path: &Path) -> io::Result<()> {
+        if !self.get_resolve_hosts() {
+            return Ok(());
+        }
+        let hostnames = self
+            .cache
+            .lock()
+            .map_err(|_| io::Error::other("resolver cache lock poisoned"))?
+            .iter()
+            .map(|(ip, hostname)| (ip.clone(), hostname.clone()))
+            .collect();
+        let saved = SavedCache {
+            saved_at: SystemTime::now(),
+            hostnames,
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write next to the target and rename so readers never see half a file
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_vec(&saved)?)?;
+        fs::rename(&partial, path)
+    }
+
+    /// Clear the resolution cache
+    pub fn clear_cache(&self) {
+        if let Ok(mut cache) = self.cache.lock() {
+            cache.clear();
+        }
+        self.names_changed();
+    }
+}
+
+/// Block until the next queued IP, or `None` once the queue is closed
+fn next_request(receiver: &Mutex<Receiver<String>>) -> Option<String> {
+    receiver.lock().ok()?.recv().ok()
+}
+
+/// Split "ip:port" or "[ipv6]:port" into the bare IP and the port
+fn split_host_port(addr: &str) -> (&str, &str) {
+    match addr.rsplit_once(':') {
+        Some((ip, port)) => {
+            let ip = ip
+                .strip_prefix('[')
+                .and_then(|ip| ip.strip_suffix(']'))
+                .unwrap_or(ip);
+            (ip, port)
+        }
+        None => (addr, ""),
+    }
+}
+
+/// Look `ip` up unless lookups are paused; None when skipped or failed.
+/// Only the first failure and the pause are logged.
+fn guarded_lookup(
+    breaker: &Mutex<CircuitBreaker>,
+    latency: &Mutex<Timing>,
+    ip: &str,
+) -> Option<Option<String>> {
+    // Not held during the lookup, which can take seconds
+    if !breaker.lock().ok()?.allows_call() {
+        return None;
+    }
+
+    let started = Instant::now();
+    let result = lookup_hostname(ip);
+    if let Ok(mut latency) = latency.lock() {
+        latency.record(started.elapsed());
+    }
+
+    let mut breaker = breaker.lock().ok()?;
+    match result {
+        Ok(hostname) => {
+            breaker.record_success();
+            Some(hostname)
+        }
+        Err(e) => {
+            breaker.record_failure();
+            if breaker.failure_count() == 1 {
+                tracing::warn!("Hostname lookup failed: {}", e);
+            } else if breaker.failure_count() == LOOKUP_FAILURE_THRESHOLD {
+                tracing::warn!(
+                    "Pausing hostname lookups for {}s after repeated failures",
+                    breaker.timeout().as_secs()
+                );
+            }
+            None
+        }
+    }
+}
+
+/// Reverse-resolve `ip` using the host command with a timeout. An address
+/// without a name is Ok(None); a missing `host` or DNS timing out is an error.
+pub fn lookup_hostname(ip: &str) -> Result<Option<String>> {
+    let output = std::process::Command::new("timeout")
+        .args(["5s", "host", ip])
+        .output()
+        .map_err(|e| NetworkMonitorError::ResolutionError(format!("could not run host: {e}")))?;
+    match output.status.code() {
+        // timeout(1) exit codes: the command timed out, or couldn't be run
+        Some(124) => Err(NetworkMonitorError::ResolutionError(format!(
+            "lookup of {ip} timed out"
+        ))),
+        Some(126 | 127) => Err(NetworkMonitorError::ResolutionError(
+            "the host command is not installed".to_string(),
+        )),
+        _ => Ok(parse_host_output(&String::from_utf8_lossy(&output.stdout))),
+    }
+}
+
+/// Extract the hostname from `host` output
+fn parse_host_output(output: &str) -> Option<String> {
+    let mut result = None;
+    for line in output.lines() {
+        if line.contains("domain name pointer") || line.contains("is an alias for") {
+            let parts: Vec<&str> = line.split_whitespace().collect();
+            for (i, part) in parts.iter().enumerate() {
+                if (*part == "pointer" || *part == "alias") && i + 1 < parts.len() {
+                    result = Some(parts[i + 1].trim_end_matches('.').to_string());
+                    break;
+                }
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    #[test]
+    fn test_split_host_port() {
+        assert_eq!(split_host_port("10.0.0.1:443"), ("10.0.0.1", "443"));
+        assert_eq!(split_host_port("[2001:db8::1]:22"), ("2001:db8::1", "22"));
+        assert_eq!(split_host_port("10.0.0.1"), ("10.0.0.1", ""));
+    }
+
+    #[test]
+    fn test_server_name_stands_in_for_missing_hostname() {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            "192.0.2.7:443",
+            "curl",
+            "100",
+            "curl",
+        );
+        conn.server_name = Some("example.com".into());
+        let resolver = AddressResolver::new(false);
+        assert_eq!(resolver.resolve_remote(&conn), "192.0.2.7:443");
+
+        // Shown while the lookup is pending, or when it finds no name
+        resolver.set_resolve_hosts(true);
+        assert_eq!(resolver.resolve_remote(&conn), "example.com:443");
+    }
+
+    #[test]
+    fn test_configured_names_come_first() {
+        let resolver = AddressResolver::new(false);
+        resolver.set_host_names(HostNames::new([
+            ("192.168.1.10".parse().unwrap(), "NAS".to_string()),
+            ("2001:db8::/32".parse().unwrap(), "Lab".to_string()),
+        ]));
+        assert_eq!(resolver.resolve_address("192.168.1.10:445"), "NAS:445");
+        assert_eq!(resolver.resolve_address("[2001:db8::5]:22"), "Lab:22");
+        assert_eq!(
+            resolver.resolve_address("192.168.1.11:445"),
+            "192.168.1.11:445"
+        );
+        assert_eq!(resolver.resolve_host("192.168.1.10"), "NAS");
+        assert_eq!(resolver.resolve_host("2001:db8::5"), "Lab");
+    }
+
+    #[test]
+    fn test_proxied_connections_are_tagged() {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "127.0.0.1:40000",
+            "127.0.0.1:8118",
+            "curl",
+            "100",
+            "curl",
+        );
+        let resolver = AddressResolver::new(false);
+        conn.proxy = Some("privoxy".into());
+        assert_eq!(
+            resolver.resolve_remote(&conn),
+            "127.0.0.1:8118 [proxy:privoxy]"
+        );
+        conn.upstream = Some("example.com:443".into());
+        assert_eq!(
+            resolver.resolve_remote(&conn),
+            "example.com:443 [proxy:privoxy]"
+        );
+
+        // A proxy elsewhere on the network, seen through its requests
+        conn.proxy = None;
+        conn.remote = "10.0.0.1:3128".into();
+        assert_eq!(
+            resolver.resolve_remote(&conn),
+            "example.com:443 [proxy:10.0.0.1:3128]"
+        );
+    }
+
+    #[test]
+    fn test_parse_host_output() {
+        let output = "1.1.1.1.in-addr.arpa domain name pointer one.one.one.one.\n";
+        assert_eq!(
+            parse_host_output(output).as_deref(),
+            Some("one.one.one.one")
+        );
+        assert_eq!(
+            parse_host_output("Host 1.0.0.10.in-addr.arpa not found"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_paused_lookups_are_skipped() {
+        let breaker = Mutex::new(CircuitBreaker::new(1, Duration::from_secs(60)));
+        breaker.lock().unwrap().record_failure();
+        let latency = Mutex::new(Timing::default());
+        assert_eq!(guarded_lookup(&breaker, &latency, "9.9.9.9"), None);
+        assert_eq!(latency.lock().unwrap().samples, 0);
+    }
+
+    #[test]
+    fn test_cache_survives_save_and_load() {
+        let path = std::env::temp_dir()
+            .join(format!("nm-resolver-{}", std::process::id()))
+            .join("resolver.json");
+        let resolver = AddressResolver::new(true);
+        resolver
+            .cache
+            .lock()
+            .unwrap()
+            .insert("9.9.9.9".to_string(), Some("dns9.quad9.net".to_string()));
+        resolver.save_cache(&path).unwrap();
+
+        let restored = AddressResolver::new(true);
+        assert_eq!(restored.load_cache(&path).unwrap(), 1);
+        assert_eq!(restored.resolve_address("9.9.9.9:53"), "dns9.quad9.net:53");
+        assert_eq!(restored.resolve_host("9.9.9.9"), "dns9.quad9.net");
+        assert_eq!(
+            restored
+                .load_cache(&path.with_file_name("missing"))
+                .unwrap(),
+            0
+        );
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn test_ports_of_one_ip_share_a_lookup() {
+        let resolver = AddressResolver::new(true);
+        // One-slot queue with no workers, so requests stay pending
+        let (sender, _receiver) = mpsc::sync_channel(1);
+        *resolver.queue.lock().unwrap() = Some(sender);
+
+        resolver.resolve_address("192.0.2.1:80");
+        resolver.resolve_address("192.0.2.1:443");
+        assert_eq!(resolver.pending.lock().unwrap().len(), 1);
+
+        // A full queue drops the request instead of tracking it
+        resolver.resolve_address("192.0.2.2:80");
+        assert!(!resolver.pending.lock().unwrap().contains("192.0.2.2"));
+    }
+}
@@ -0,0 +1,100 @@
+use crate::models::talkers::{rate_share, sockets_per_pid};
+use crate::models::{ConnectionSnapshot, Scope, ScopeTraffic};
+use std::time::SystemTime;
+
+/// Adds up traffic to the LAN, the internet and VPNs over the session, from
+/// connections whose [`Scope`] was marked
+#[derive(Debug)]
+pub struct ScopeTracker {
+    scopes: [ScopeTraffic; 3],
+    last_pass: Option<SystemTime>,
+}
+
+impl ScopeTracker {
+    pub fn new() -> Self {
+        Self {
+            scopes: Scope::ALL.map(ScopeTraffic::new),
+            last_pass: None,
+        }
+    }
+
+    /// Rates of the connections of `snapshot` per scope, added to the totals
+    /// for the time since the previous pass, into `snapshot.scopes`
+    pub fn record(&mut self, snapshot: &mut ConnectionSnapshot) {
+        let elapsed = self
+            .last_pass
+            .and_then(|last| snapshot.taken_at.duration_since(last).ok())
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.last_pass = Some(snapshot.taken_at);
+
+        for traffic in &mut self.scopes {
+            traffic.tx_rate = 0;
+            traffic.rx_rate = 0;
+        }
+        let sockets_per_pid = sockets_per_pid(&snapshot.connections);
+        for conn in &snapshot.connections {
+            let Some(scope) = conn.scope else {
+                continue;
+            };
+            let share = rate_share(conn, &sockets_per_pid);
+            let traffic = &mut self.scopes[scope as usize];
+            traffic.tx_rate += conn.tx_rate / share;
+            traffic.rx_rate += conn.rx_rate / share;
+        }
+        for traffic in &mut self.scopes {
+            traffic.totals.sent += (traffic.tx_rate as f64 * elapsed) as u64;
+            traffic.totals.received += (traffic.rx_rate as f64 * elapsed) as u64;
+        }
+        snapshot.scopes = self.scopes.to_vec();
+    }
+}
+
+impl Default for ScopeTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{Connection, ConnectionState, Protocol, TrafficTotals};
+    use std::time::Duration;
+
+    fn connection(pid: &str, scope: Scope, rx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "192.168.1.2:51000",
+            "192.168.1.20:445",
+            "smbclient",
+            pid,
+            "smbclient",
+        );
+        conn.rx_rate = rx_rate;
+        conn.scope = Some(scope);
+        conn
+    }
+
+    #[test]
+    fn test_traffic_adds_up_per_scope() {
+        let mut tracker = ScopeTracker::new();
+        let connections = vec![
+            connection("100", Scope::Lan, 1000),
+            connection("200", Scope::Wan, 500),
+            connection("300", Scope::Wan, 250),
+        ];
+        for secs in [0, 4] {
+            let mut snapshot = ConnectionSnapshot {
+                taken_at: SystemTime::UNIX_EPOCH + Duration::from_secs(secs),
+                ..ConnectionSnapshot::new(connections.clone(), TrafficTotals::default())
+            };
+            tracker.record(&mut snapshot);
+            let rates: Vec<_> = snapshot.scopes.iter().map(|s| s.rx_rate).collect();
+            assert_eq!(rates, [1000, 750, 0]);
+            let lan = snapshot.scopes[Scope::Lan as usize];
+            assert_eq!(lan.totals.received, 1000 * secs);
+        }
+    }
+}
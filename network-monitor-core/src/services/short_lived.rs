@@ -0,0 +1,348 @@
+use crate::models::{ConnectEvent, Connection, ConnectionState, Direction};
+use std::collections::HashSet;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long a short-lived connection stays in the table after it was seen
+pub const SHORT_LIVED_FOR: Duration = Duration::from_secs(60);
+
+/// Finds the connections that opened and closed between two passes: those
+/// the connect tracer saw that neither the pass before nor the one after
+/// listed. Each attempt is judged a pass late, as it may have happened
+/// after /proc/net was read.
+#[derive(Debug, Default)]
+pub struct ShortLivedTracker {
+    /// Attempts reported with the last pass
+    pending: Vec<ConnectEvent>,
+    /// Process and remote address of each connection of the last pass
+    previous: HashSet<(Arc<str>, Arc<str>)>,
+    /// Short-lived connections found, and when
+    recent: Vec<(Connection, Instant)>,
+}
+
+impl ShortLivedTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Short-lived connections of the last [`SHORT_LIVED_FOR`], newest
+    /// first, given the attempts reported since the previous call and the
+    /// `connections` listed now
+    pub fn update(
+        &mut self,
+        events: Vec<ConnectEvent>,
+        connections: &[Connection],
+        now: Instant,
+    ) -> Vec<Connection> {
+        let current: HashSet<(Arc<str>, Arc<str>)> = connections
+            .iter()
+            .map(|conn| (conn.pid.clone(), conn.remote.clone()))
+            .collect();
+        for event in std::mem::replace(&mut self.pending, events) {
+            let conn = short_lived_connection(&event);
+            let key = (conn.pid.clone(), conn.remote.clone());
+            if current.contains(&key) || self.previous.contains(&key) {
+                continue;
+            }
+            // Repeated attempts, like a retry loop, show up once
+            self.recent
+                .retain(|(seen, _)| seen.pid != conn.pid || seen.key() != conn.key());
+            self.recent.push((conn, now));
+        }
+        self.previous = current;
+        self.recent
+            .retain(|(_, seen)| now.duration_since(*seen) < SHORT_LIVED_FOR);
+        self.recent
+            .iter()
+            .rev()
+            .map(|(conn, _)| conn.clone())
+            .collect()
+    }
+}
+
+/// Closed connection standing in for an attempt; the local port was only
+/// picked after the tracer saw it
+fn short_lived_connection(event: &ConnectEvent) -> Connection {
+    let local = match event.remote.ip() {
+        IpAddr::V4(_) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+        IpAddr::V6(_) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+    };
+    let mut conn = Connection::new(
+        event.protocol,
+        ConnectionState::Close,
+        format!("{local}:0"),
+        // Formatted like the addresses of /proc/net
+        format!("{}:{}", event.remote.ip(), event.remote.port()),
+        event.program.as_str(),
+        event.pid.to_string(),
+        event.program.as_str(),
+    );
+    conn.direction = Some(Direction::Outbound);
+    conn.short_lived = true;
+    conn
+}
+
+#[cfg(target_os = "linux")]
+pub use bpf::ConnectTracer;
+
+#[cfg(target_os = "linux")]
+mod bpf {
+    use crate::error::{NetworkMonitorError, Result};
+    use crate::models::{ConnectEvent, Protocol};
+    use crate::services::bpf::*;
+    use std::fs::File;
+    use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+    use std::os::fd::{AsRawFd, OwnedFd};
+
+    /// Mount point of the cgroup v2 hierarchy
+    const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+    /// Attempts kept between two reads; the oldest are dropped beyond
+    const QUEUE_SIZE: u32 = 4096;
+    /// PID, family, protocol, port, address and comm of an attempt
+    const EVENT_SIZE: usize = 48;
+
+    /// Every connect() of a TCP or UDP socket on the system, queued by a
+    /// cgroup sock_addr program attached at the root of the hierarchy for
+    /// each address family. Detached when dropped or the process exits.
+    pub struct ConnectTracer {
+        queue: OwnedFd,
+        _links: [OwnedFd; 2],
+    }
+
+    impl ConnectTracer {
+        /// Load and attach the programs. Needs CAP_BPF and CAP_NET_ADMIN.
+        pub fn attach() -> Result<Self> {
+            let root = File::open(CGROUP_ROOT)
+                .map_err(|e| tracing_error(format!("{CGROUP_ROOT}: {e}")))?;
+            let queue = create_map(BPF_MAP_TYPE_QUEUE, 0, EVENT_SIZE as u32, QUEUE_SIZE)
+                .map_err(|e| tracing_error(format!("creating the event queue: {e}")))?;
+            let mut links = Vec::new();
+            for (attach_type, ipv6) in [
+                (BPF_CGROUP_INET4_CONNECT, false),
+                (BPF_CGROUP_INET6_CONNECT, true),
+            ] {
+                let program = load_program(
+                    BPF_PROG_TYPE_CGROUP_SOCK_ADDR,
+                    attach_type,
+                    &program(queue.as_raw_fd(), ipv6),
+                )
+                .map_err(|e| tracing_error(format!("loading the connect program: {e}")))?;
+                links.push(
+                    link(&program, &root, attach_type).map_err(|e| {
+                        tracing_error(format!("attaching the connect program: {e}"))
+                    })?,
+                );
+            }
+            let Ok(links) = links.try_into() else {
+                unreachable!("one link per address family");
+            };
+            Ok(Self {
+                queue,
+                _links: links,
+            })
+        }
+
+        /// Attempts since the previous call, oldest first
+        pub fn events(&self) -> Vec<ConnectEvent> {
+            std::iter::from_fn(|| pop::<EVENT_SIZE>(&self.queue))
+                .take(QUEUE_SIZE as usize)
+                .filter_map(|event| parse_event(&event))
+                .collect()
+        }
+    }
+
+    /// Attempt queued by [`program`], None for other protocols
+    pub(super) fn parse_event(event: &[u8; EVENT_SIZE]) -> Option<ConnectEvent> {
+        let word = |offset: usize| {
+            u32::from_ne_bytes(event[offset..offset + 4].try_into().unwrap_or_default())
+        };
+        let ipv6 = word(4) == libc::AF_INET6 as u32;
+        let protocol = socket_protocol(ipv6, word(8))?;
+        // user_port holds the port in network order in its low 16 bits
+        let port = u16::from_be(word(12) as u16);
+        let address: [u8; 16] = event[16..32].try_into().ok()?;
+        let ip = if ipv6 {
+            Ipv6Addr::from(address).into()
+        } else {
+            Ipv4Addr::new(address[0], address[1], address[2], address[3]).into()
+        };
+        let comm = &event[32..];
+        let comm = &comm[..comm.iter().position(|&b| b == 0).unwrap_or(comm.len())];
+        Some(ConnectEvent {
+            pid: word(0),
+            program: String::from_utf8_lossy(comm).into_owned(),
+            protocol,
+            remote: SocketAddr::new(ip, port),
+        })
+    }
+
+    /// Queue the caller and destination of each connect(), overwriting the
+    /// oldest attempt when the queue is full, and let the call go ahead:
+    ///
+    /// ```text
+    /// event = { pid, ctx->user_family, ctx->protocol, ctx->user_port,
+    ///           ctx->user_ip4 or ctx->user_ip6, current comm }
+    /// push(queue, event, BPF_EXIST)
+    /// return 1
+    /// ```
+    pub(super) fn program(queue: i32, ipv6: bool) -> Vec<Insn> {
+        let mut program = vec![
+            insn(MOV_X, 6, 1, 0, 0),
+            insn(CALL, 0, 0, 0, BPF_FUNC_GET_CURRENT_PID_TGID),
+            insn(RSH_K, 0, 0, 0, 32),
+            insn(STX_W, 10, 0, -48, 0),
+        ];
+        // user_family, protocol and user_port
+        for (field, offset) in [(0, -44), (36, -40), (24, -36)] {
+            program.extend([insn(LDX_W, 1, 6, field, 0), insn(STX_W, 10, 1, offset, 0)]);
+        }
+        if ipv6 {
+            for word in 0..4 {
+                program.extend([
+                    insn(LDX_W, 1, 6, 8 + 4 * word, 0),
+                    insn(STX_W, 10, 1, -32 + 4 * word, 0),
+                ]);
+            }
+        } else {
+            program.extend([
+                insn(LDX_W, 1, 6, 4, 0),
+                insn(STX_W, 10, 1, -32, 0),
+                insn(MOV_K, 1, 0, 0, 0),
+                insn(STX_W, 10, 1, -28, 0),
+                insn(STX_W, 10, 1, -24, 0),
+                insn(STX_W, 10, 1, -20, 0),
+            ]);
+        }
+        program.extend([
+            insn(MOV_X, 1, 10, 0, 0),
+            insn(ADD_K, 1, 0, 0, -16),
+            insn(MOV_K, 2, 0, 0, 16),
+            insn(CALL, 0, 0, 0, BPF_FUNC_GET_CURRENT_COMM),
+        ]);
+        program.extend(load_map(1, queue));
+        program.extend([
+            insn(MOV_X, 2, 10, 0, 0),
+            insn(ADD_K, 2, 0, 0, -48),
+            insn(MOV_K, 3, 0, 0, BPF_EXIST),
+            insn(CALL, 0, 0, 0, BPF_FUNC_MAP_PUSH_ELEM),
+            insn(MOV_K, 0, 0, 0, 1),
+            insn(EXIT, 0, 0, 0, 0),
+        ]);
+        program
+    }
+
+    /// Protocol of a traced socket from its address family and IP protocol
+    /// number; None for anything but TCP and UDP
+    fn socket_protocol(ipv6: bool, protocol: u32) -> Option<Protocol> {
+        match (ipv6, protocol as i32) {
+            (false, libc::IPPROTO_TCP) => Some(Protocol::Tcp),
+            (false, libc::IPPROTO_UDP) => Some(Protocol::Udp),
+            (true, libc::IPPROTO_TCP) => Some(Protocol::Tcp6),
+            (true, libc::IPPROTO_UDP) => Some(Protocol::Udp6),
+            _ => None,
+        }
+    }
+
+    fn tracing_error(message: String) -> NetworkMonitorError {
+        NetworkMonitorError::ConnectTracing(message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::Protocol;
+    use std::net::SocketAddr;
+
+    fn connection(pid: &str, remote: &str) -> Connection {
+        Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "192.168.1.2:40000",
+            remote,
+            "curl",
+            pid,
+            "curl",
+        )
+    }
+
+    fn attempt(pid: u32, remote: &str) -> ConnectEvent {
+        ConnectEvent {
+            pid,
+            program: "curl".to_string(),
+            protocol: Protocol::Tcp,
+            remote: remote.parse::<SocketAddr>().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_attempts_nobody_listed_are_short_lived() {
+        let mut tracker = ShortLivedTracker::new();
+        let start = Instant::now();
+        let listed = connection("100", "93.184.216.34:443");
+        let events = vec![
+            attempt(100, "93.184.216.34:443"),
+            attempt(200, "1.1.1.1:53"),
+            attempt(200, "1.1.1.1:53"),
+            // Made after /proc/net was read, listed by the next pass
+            attempt(300, "10.0.0.1:22"),
+        ];
+        assert!(tracker
+            .update(events, std::slice::from_ref(&listed), start)
+            .is_empty());
+
+        let later = connection("300", "10.0.0.1:22");
+        let found = tracker.update(Vec::new(), &[later], start + Duration::from_secs(1));
+        assert_eq!(found.len(), 1);
+        let short = &found[0];
+        assert!(short.short_lived);
+        assert_eq!((&*short.pid, &*short.remote), ("200", "1.1.1.1:53"));
+        assert_eq!(&*short.local, "0.0.0.0:0");
+        assert_eq!(short.direction, Some(Direction::Outbound));
+
+        // Still shown for a while after, then dropped
+        let kept = tracker.update(Vec::new(), &[], start + Duration::from_secs(30));
+        assert_eq!(kept.len(), 1);
+        assert!(tracker
+            .update(
+                Vec::new(),
+                &[],
+                start + SHORT_LIVED_FOR + Duration::from_secs(2)
+            )
+            .is_empty());
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_events_read_back_as_queued() {
+        let mut event = [0u8; 48];
+        event[0..4].copy_from_slice(&4242u32.to_ne_bytes());
+        event[4..8].copy_from_slice(&(libc::AF_INET6 as u32).to_ne_bytes());
+        event[8..12].copy_from_slice(&(libc::IPPROTO_TCP as u32).to_ne_bytes());
+        event[12..16].copy_from_slice(&u32::from(443u16.to_be()).to_ne_bytes());
+        event[16..32].copy_from_slice(&"2606:4700::1111".parse::<Ipv6Addr>().unwrap().octets());
+        event[32..36].copy_from_slice(b"curl");
+        assert_eq!(
+            bpf::parse_event(&event),
+            Some(attempt(4242, "[2606:4700::1111]:443")).map(|mut event| {
+                event.protocol = Protocol::Tcp6;
+                event
+            })
+        );
+
+        // Raw and ICMP sockets can connect too
+        event[8..12].copy_from_slice(&(libc::IPPROTO_ICMPV6 as u32).to_ne_bytes());
+        assert_eq!(bpf::parse_event(&event), None);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_programs_fill_the_whole_event() {
+        for (ipv6, len) in [(false, 28), (true, 30)] {
+            let program = bpf::program(3, ipv6);
+            assert_eq!(program.len(), len);
+            assert_eq!(program[len - 1][0], 0x95);
+        }
+    }
+}
@@ -0,0 +1,248 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{ConnectionState, Protocol, SocketTimer, TimerKind};
+use crate::services::netlink::{self, attributes, for_each_message, NLMSG_HEADER_LEN};
+use std::io;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+
+/// Request type for inet sockets (linux/sock_diag.h)
+const SOCK_DIAG_BY_FAMILY: u16 = 20;
+/// struct inet_diag_req_v2
+const REQUEST_LEN: usize = 56;
+/// struct inet_diag_msg
+const MESSAGE_LEN: usize = 72;
+/// Report sockets in every TCP state; UDP sockets use the same numbering
+const ALL_STATES: u32 = u32::MAX;
+/// Attribute of TCP sockets naming their congestion control algorithm
+const INET_DIAG_CONG: u16 = 4;
+
+/// One socket as reported by the kernel
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SocketEntry {
+    pub protocol: Protocol,
+    pub state: ConnectionState,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+    pub uid: u32,
+    pub inode: u64,
+    /// Bytes waiting in the send and receive queues
+    pub send_queue: u32,
+    pub recv_queue: u32,
+    pub timer: Option<SocketTimer>,
+    /// Congestion control algorithm of TCP sockets, e.g. "cubic"
+    pub congestion: Option<String>,
+}
+
+/// List TCP and UDP sockets of both address families through
+/// NETLINK_SOCK_DIAG, the interface `ss` uses, instead of /proc/net tables
+pub fn dump_all() -> Result<Vec<SocketEntry>> {
+    let mut sockets = Vec::new();
+    for protocol in [Protocol::Tcp, Protocol::Tcp6, Protocol::Udp, Protocol::Udp6] {
+        dump(protocol, &mut sockets)?;
+    }
+    Ok(sockets)
+}
+
+/// List the TCP sockets of both address families alone
+pub fn dump_tcp() -> Result<Vec<SocketEntry>> {
+    let mut sockets = Vec::new();
+    for protocol in [Protocol::Tcp, Protocol::Tcp6] {
+        dump(protocol, &mut sockets)?;
+    }
+    Ok(sockets)
+}
+
+fn dump(protocol: Protocol, sockets: &mut Vec<SocketEntry>) -> Result<()> {
+    let socket = netlink::Socket::netlink(libc::NETLINK_SOCK_DIAG, 0).map_err(error)?;
+    socket.send(&dump_request(protocol)).map_err(error)?;
+    let mut buffer = vec![0u8; 32 * 1024];
+    loop {
+        let received = socket.recv(&mut buffer).map_err(error)?;
+        if parse_dump(&buffer[..received], protocol, sockets)? {
+            return Ok(());
+        }
+    }
+}
+
+fn error(e: io::Error) -> NetworkMonitorError {
+    NetworkMonitorError::SockDiag(e.to_string())
+}
+
+/// Netlink message asking for every socket of `protocol`, and the
+/// congestion control algorithm of TCP sockets
+fn dump_request(protocol: Protocol) -> Vec<u8> {
+    let total_len = NLMSG_HEADER_LEN + REQUEST_LEN;
+    let mut message = Vec::with_capacity(total_len);
+    // nlmsghdr
+    message.extend_from_slice(&(total_len as u32).to_ne_bytes());
+    message.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+    message.extend_from_slice(&((libc::NLM_F_REQUEST | libc::NLM_F_DUMP) as u16).to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    message.extend_from_slice(&0u32.to_ne_bytes());
+    // inet_diag_req_v2: family, protocol, extensions, padding, states
+    message.push(family(protocol) as u8);
+    if protocol.is_tcp() {
+        message.push(libc::IPPROTO_TCP as u8);
+        message.push(1 << (INET_DIAG_CONG - 1));
+    } else {
+        message.push(libc::IPPROTO_UDP as u8);
+        message.push(0);
+    }
+    message.push(0);
+    message.extend_from_slice(&ALL_STATES.to_ne_bytes());
+    // inet_diag_sockid left zeroed: no filter
+    message.resize(total_len, 0);
+    message
+}
+
+fn family(protocol: Protocol) -> i32 {
+    if protocol.is_ipv6() {
+        libc::AF_INET6
+    } else {
+        libc::AF_INET
+    }
+}
+
+/// Decode the inet_diag_msg records of one datagram into `sockets`. Returns
+/// true once the dump is complete.
+pub fn parse_dump(
+    datagram: &[u8],
+    protocol: Protocol,
+    sockets: &mut Vec<SocketEntry>,
+) -> Result<bool> {
+    for_each_message(datagram, |_, payload| {
+        sockets.extend(parse_message(payload, protocol));
+    })
+}
+
+/// Decode one struct inet_diag_msg
+fn parse_message(message: &[u8], protocol: Protocol) -> Option<SocketEntry> {
+    if message.len() < MESSAGE_LEN {
+        return None;
+    }
+    let read_u32 = |offset: usize| {
+        u32::from_ne_bytes(message[offset..offset + 4].try_into().unwrap_or_default())
+    };
+    let port = |offset: usize| u16::from_be_bytes([message[offset], message[offset + 1]]);
+    let address = |offset: usize| -> IpAddr {
+        let bytes = &message[offset..offset + 16];
+        if family(protocol) == libc::AF_INET {
+            IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3]))
+        } else {
+            let octets: [u8; 16] = bytes.try_into().unwrap_or_default();
+            IpAddr::V6(Ipv6Addr::from(octets))
+        }
+    };
+
+    Some(SocketEntry {
+        protocol,
+        state: ConnectionState::from_code(message[1]),
+        local: SocketAddr::new(address(8), port(4)),
+        remote: SocketAddr::new(address(24), port(6)),
+        uid: read_u32(64),
+        inode: read_u32(68) as u64,
+        send_queue: read_u32(60),
+        recv_queue: read_u32(56),
+        timer: TimerKind::from_code(message[2]).map(|kind| SocketTimer {
+            kind,
+            expires_ms: read_u32(52).into(),
+            retries: message[3].into(),
+        }),
+        congestion: attributes(&message[MESSAGE_LEN..])
+            .find(|&(kind, _)| kind == INET_DIAG_CONG)
+            .and_then(|(_, name)| {
+                let name = name.split(|&b| b == 0).next()?;
+                (!name.is_empty()).then(|| String::from_utf8_lossy(name).into_owned())
+            }),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// nlmsghdr plus inet_diag_msg for 127.0.0.1:8080 -> 127.0.0.1:54321
+    fn tcp_message(state: u8, inode: u32) -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&((NLMSG_HEADER_LEN + MESSAGE_LEN) as u32).to_ne_bytes());
+        message.extend_from_slice(&SOCK_DIAG_BY_FAMILY.to_ne_bytes());
+        message.extend_from_slice(&[0; 10]);
+        message.extend_from_slice(&[libc::AF_INET as u8, state, 0, 0]);
+        message.extend_from_slice(&8080u16.to_be_bytes());
+        message.extend_from_slice(&54321u16.to_be_bytes());
+        let mut src = [0u8; 16];
+        src[..4].copy_from_slice(&[127, 0, 0, 1]);
+        message.extend_from_slice(&src);
+        message.extend_from_slice(&src);
+        message.extend_from_slice(&[0; 12]);
+        message.extend_from_slice(&[0; 4]);
+        message.extend_from_slice(&16u32.to_ne_bytes());
+        message.extend_from_slice(&1024u32.to_ne_bytes());
+        message.extend_from_slice(&1000u32.to_ne_bytes());
+        message.extend_from_slice(&inode.to_ne_bytes());
+        message
+    }
+
+    fn done_message() -> Vec<u8> {
+        let mut message = Vec::new();
+        message.extend_from_slice(&(NLMSG_HEADER_LEN as u32 + 4).to_ne_bytes());
+        message.extend_from_slice(&(libc::NLMSG_DONE as u16).to_ne_bytes());
+        message.extend_from_slice(&[0; 14]);
+        message
+    }
+
+    #[test]
+    fn test_parse_dump() {
+        let mut datagram = tcp_message(1, 4242);
+        // Retransmitting for the second time, again in 400ms
+        datagram[NLMSG_HEADER_LEN + 2..NLMSG_HEADER_LEN + 4].copy_from_slice(&[1, 2]);
+        datagram[NLMSG_HEADER_LEN + 52..NLMSG_HEADER_LEN + 56]
+            .copy_from_slice(&400u32.to_ne_bytes());
+        datagram.extend(tcp_message(10, 4243));
+        let mut sockets = Vec::new();
+        assert!(!parse_dump(&datagram, Protocol::Tcp, &mut sockets).unwrap());
+        assert_eq!(sockets.len(), 2);
+        assert_eq!(sockets[0].state, ConnectionState::Established);
+        assert_eq!(sockets[0].local, "127.0.0.1:8080".parse().unwrap());
+        assert_eq!(sockets[0].remote, "127.0.0.1:54321".parse().unwrap());
+        assert_eq!((sockets[0].uid, sockets[0].inode), (1000, 4242));
+        assert_eq!((sockets[0].send_queue, sockets[0].recv_queue), (1024, 16));
+        assert_eq!(
+            sockets[0].timer,
+            Some(SocketTimer {
+                kind: TimerKind::Retransmit,
+                expires_ms: 400,
+                retries: 2,
+            })
+        );
+        assert_eq!(sockets[1].state, ConnectionState::Listen);
+        assert_eq!(sockets[1].timer, None);
+
+        assert!(parse_dump(&done_message(), Protocol::Tcp, &mut sockets).unwrap());
+    }
+
+    #[test]
+    fn test_parse_dump_rejects_garbage() {
+        let mut sockets = Vec::new();
+        assert!(parse_dump(
+            &[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+            Protocol::Udp,
+            &mut sockets
+        )
+        .is_err());
+        // A record too short to be an inet_diag_msg is skipped
+        let mut short = tcp_message(1, 1);
+        short.truncate(NLMSG_HEADER_LEN + 8);
+        let len = short.len() as u32;
+        short[0..4].copy_from_slice(&len.to_ne_bytes());
+        assert!(!parse_dump(&short, Protocol::Tcp, &mut sockets).unwrap());
+        assert!(sockets.is_empty());
+    }
+
+    #[test]
+    fn test_request_layout() {
+        let request = dump_request(Protocol::Udp6);
+        assert_eq!(request.len(), NLMSG_HEADER_LEN + REQUEST_LEN);
+        assert_eq!(request[NLMSG_HEADER_LEN], libc::AF_INET6 as u8);
+        assert_eq!(request[NLMSG_HEADER_LEN + 1], libc::IPPROTO_UDP as u8);
+    }
+}
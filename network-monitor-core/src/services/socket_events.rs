@@ -0,0 +1,118 @@
+//! Notices that the socket tables changed between refreshes, so a long
+//! refresh interval doesn't hold back new and closed connections.
+//!
+//! /proc/net files raise no inotify events, and the kernel only announces
+//! socket destruction (sock_diag destroy groups, root only) and process
+//! lifecycle (proc connector, root only, and busy with processes that never
+//! open a socket). What any user can read cheaply is the kernel's count of
+//! sockets in /proc/net/sockstat and sockstat6, a few hundred bytes, so
+//! those are compared every [`WATCH_TICK`] instead.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// How often socket counts are compared
+pub const WATCH_TICK: Duration = Duration::from_millis(250);
+
+/// Protocols whose sockets show up in the table
+const PROTOCOLS: [&str; 4] = ["TCP", "UDP", "TCP6", "UDP6"];
+
+/// Compares socket counts against those of the last check
+#[derive(Debug)]
+pub struct SocketWatcher {
+    proc_root: PathBuf,
+    counts: Vec<u64>,
+}
+
+impl Default for SocketWatcher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SocketWatcher {
+    pub fn new() -> Self {
+        Self::with_proc_root("/proc")
+    }
+
+    pub fn with_proc_root(proc_root: impl Into<PathBuf>) -> Self {
+        let mut watcher = Self {
+            proc_root: proc_root.into(),
+            counts: Vec::new(),
+        };
+        watcher.counts = watcher.read_counts();
+        watcher
+    }
+
+    /// Whether sockets were opened or closed since the last call. Changes
+    /// that cancel out within a tick go unnoticed until the next refresh.
+    pub fn changed(&mut self) -> bool {
+        let counts = self.read_counts();
+        let changed = counts != self.counts;
+        self.counts = counts;
+        changed
+    }
+
+    fn read_counts(&self) -> Vec<u64> {
+        ["sockstat", "sockstat6"]
+            .iter()
+            .filter_map(|name| fs::read_to_string(self.proc_root.join("net").join(name)).ok())
+            .flat_map(|text| socket_counts(&text))
+            .collect()
+    }
+}
+
+/// Sockets in use and in TIME_WAIT by protocol from sockstat, e.g. 4 and 1
+/// from "TCP: inuse 4 orphan 0 tw 1 alloc 4 mem 221"
+pub fn socket_counts(text: &str) -> Vec<u64> {
+    text.lines()
+        .filter_map(|line| line.split_once(':'))
+        .filter(|(protocol, _)| PROTOCOLS.contains(protocol))
+        .flat_map(|(_, fields)| {
+            let fields: Vec<&str> = fields.split_whitespace().collect();
+            fields
+                .chunks(2)
+                .filter(|pair| matches!(pair[0], "inuse" | "tw"))
+                .filter_map(|pair| pair.get(1)?.parse().ok())
+                .collect::<Vec<u64>>()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SOCKSTAT: &str = "sockets: used 16
+TCP: inuse 4 orphan 0 tw 1 alloc 4 mem 221
+UDP: inuse 2 mem 0
+UDPLITE: inuse 0
+RAW: inuse 0
+FRAG: inuse 0 memory 0
+";
+
+    #[test]
+    fn test_counts_of_table_protocols() {
+        assert_eq!(socket_counts(SOCKSTAT), [4, 1, 2]);
+        assert_eq!(socket_counts("TCP6: inuse 3\nUDP6: inuse 1\n"), [3, 1]);
+    }
+
+    #[test]
+    fn test_changes_are_reported_once() {
+        let proc_root = std::env::temp_dir().join(format!("nm-sockstat-{}", std::process::id()));
+        let net = proc_root.join("net");
+        fs::create_dir_all(&net).unwrap();
+        fs::write(net.join("sockstat"), SOCKSTAT).unwrap();
+        let mut watcher = SocketWatcher::with_proc_root(&proc_root);
+        assert!(!watcher.changed());
+
+        // Memory use changes all the time, sockets don't
+        fs::write(net.join("sockstat"), SOCKSTAT.replace("mem 221", "mem 230")).unwrap();
+        assert!(!watcher.changed());
+        fs::write(net.join("sockstat"), SOCKSTAT.replace("inuse 4", "inuse 5")).unwrap();
+        assert!(watcher.changed());
+        assert!(!watcher.changed());
+        fs::remove_dir_all(proc_root).unwrap();
+    }
+}
@@ -0,0 +1,374 @@
+//! The rows of a connection table, filtered and sorted again only when the
+//! connections, the criteria or the resolved names change, not on every
+//! redraw
+
+use crate::models::{
+    Connection, ConnectionKey, ConnectionSnapshot, ConnectionState, Direction, Protocol, Scope,
+    SocketTimer,
+};
+use crate::services::AddressResolver;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Table columns whose order depends on resolved names
+const LOCAL_COLUMN: usize = 2;
+const REMOTE_COLUMN: usize = 3;
+
+/// Value a connection is ordered by for the sort column
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortKey {
+    Text(String),
+    Protocol(Protocol),
+    State(ConnectionState),
+    Rate(u64),
+    Queue(u32),
+    Direction(Option<Direction>),
+    Timer(Option<SocketTimer>),
+    Scope(Option<Scope>),
+    Host(Option<Arc<str>>),
+    None,
+}
+
+impl SortKey {
+    /// Key of `conn` for a table column (program, protocol, local, remote,
+    /// state, upload, download, command, send queue, receive queue,
+    /// direction, timer, scope, host); addresses are ordered as `resolver`
+    /// shows them, when given
+    fn of(conn: &Connection, column: usize, resolver: Option<&AddressResolver>) -> Self {
+        match column {
+            0 => SortKey::Text(conn.get_process_display()),
+            1 => SortKey::Protocol(conn.protocol),
+            LOCAL_COLUMN => SortKey::Text(match resolver {
+                Some(resolver) => resolver.resolve_address(&conn.local),
+                None => conn.local.to_string(),
+            }),
+            REMOTE_COLUMN => SortKey::Text(match resolver {
+                Some(resolver) => resolver.resolve_remote(conn),
+                None => conn.remote.to_string(),
+            }),
+            4 => SortKey::State(conn.state),
+            5 => SortKey::Rate(conn.tx_rate),
+            6 => SortKey::Rate(conn.rx_rate),
+            7 => SortKey::Text(conn.command.to_string()),
+            8 => SortKey::Queue(conn.send_queue),
+            9 => SortKey::Queue(conn.recv_queue),
+            10 => SortKey::Direction(conn.direction),
+            11 => SortKey::Timer(conn.timer),
+            12 => SortKey::Scope(conn.scope),
+            13 => SortKey::Host(conn.host.clone()),
+            _ => SortKey::None,
+        }
+    }
+}
+
+/// Connections of the last snapshot that pass the filters, in table order.
+/// Rows keep their place between snapshots as long as the order allows, so
+/// the table doesn't shuffle rows with equal keys on every refresh.
+#[derive(Default)]
+pub struct SortedConnections {
+    column: usize,
+    ascending: bool,
+    /// Lowercased text connections must match, see
+    /// [`Connection::matches_filter`]
+    filter: Option<String>,
+    hide_loopback: bool,
+    /// Connections left out, such as the idle ones while they are hidden
+    hidden: HashSet<ConnectionKey>,
+    /// Shows the names local and remote addresses are sorted by
+    resolver: Option<AddressResolver>,
+    /// Every connection of the last snapshot
+    collected: Vec<Connection>,
+    /// New keys of connections whose key changed in the last snapshot, see
+    /// [`ConnectionDiff::rekeyed`](crate::models::ConnectionDiff::rekeyed)
+    rekeyed: HashMap<ConnectionKey, ConnectionKey>,
+    rows: Vec<Connection>,
+    /// Snapshot the connections came from
+    taken_at: Option<SystemTime>,
+    /// Resolver generation the rows were sorted with
+    generation: u64,
+    /// Criteria changed since the rows were built
+    stale: bool,
+}
+
+impl SortedConnections {
+    pub fn new(column: usize, ascending: bool) -> Self {
+        Self {
+            column,
+            ascending,
+            ..Self::default()
+        }
+    }
+
+    /// Sort local and remote addresses by the names `resolver` shows rather
+    /// than by address
+    pub fn with_resolver(mut self, resolver: AddressResolver) -> Self {
+        self.resolver = Some(resolver);
+        self
+    }
+
+    pub fn set_sort(&mut self, column: usize, ascending: bool) {
+        if (column, ascending) != (self.column, self.ascending) {
+            (self.column, self.ascending) = (column, ascending);
+            self.stale = true;
+        }
+    }
+
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        if filter != self.filter {
+            self.filter = filter;
+            self.stale = true;
+        }
+    }
+
+    /// Leave out connections to this machine, except through a local proxy
+    pub fn set_hide_loopback(&mut self, hide: bool) {
+        if hide != self.hide_loopback {
+            self.hide_loopback = hide;
+            self.stale = true;
+        }
+    }
+
+    pub fn set_hidden(&mut self, hidden: HashSet<ConnectionKey>) {
+        if hidden != self.hidden {
+            self.hidden = hidden;
+            self.stale = true;
+        }
+    }
+
+    /// Take the connections of `snapshot` unless they're those already
+    /// taken or unchanged, then [`refresh`](Self::refresh)
+    pub fn update(&mut self, snapshot: &ConnectionSnapshot) -> bool {
+        if self.taken_at != Some(snapshot.taken_at) {
+            // The first snapshot may match a warm start the rows never saw
+            if self.taken_at.is_none() || !snapshot.diff.is_empty() {
+                self.collected.clone_from(&snapshot.connections);
+                self.stale = true;
+            }
+            self.rekeyed = snapshot.diff.rekeyed.iter().cloned().collect();
+            self.taken_at = Some(snapshot.taken_at);
+        }
+        self.refresh()
+    }
+
+    /// Rebuild the rows if the criteria changed, or the names they're
+    /// sorted by. Returns whether they were rebuilt.
+    pub fn refresh(&mut self) -> bool {
+        let generation = self
+            .resolver
+            .as_ref()
+            .map_or(0, AddressResolver::generation);
+        let renamed =
+            generation != self.generation && matches!(self.column, LOCAL_COLUMN | REMOTE_COLUMN);
+        if !self.stale && !renamed {
+            return false;
+        }
+        self.generation = generation;
+        self.stale = false;
+        self.rebuild();
+        true
+    }
+
+    /// Forget the connections, as when switching to another machine
+    pub fn clear(&mut self) {
+        self.collected.clear();
+        self.rekeyed.clear();
+        self.rows.clear();
+        self.taken_at = None;
+    }
+
+    pub fn filter(&self) -> Option<&str> {
+        self.filter.as_deref()
+    }
+
+    /// Connections to show, in order
+    pub fn rows(&self) -> &[Connection] {
+        &self.rows
+    }
+
+    /// Every connection of the last snapshot, filtered or not
+    pub fn collected(&self) -> &[Connection] {
+        &self.collected
+    }
+
+    /// Key a connection known by `key` goes by since the last snapshot
+    pub fn follow(&self, key: ConnectionKey) -> ConnectionKey {
+        self.rekeyed.get(&key).cloned().unwrap_or(key)
+    }
+
+    fn shows(&self, conn: &Connection) -> bool {
+        (!self.hide_loopback || conn.proxy.is_some() || !conn.is_loopback())
+            && !self.hidden.contains(&conn.key())
+            && self
+                .filter
+                .as_deref()
+                .is_none_or(|filter| conn.matches_filter(filter))
+    }
+
+    /// Take the shown connections in the current row order, append new ones
+    /// and only sort when the order is actually broken
+    fn rebuild(&mut self) {
+        let mut incoming: HashMap<ConnectionKey, VecDeque<Connection>> = HashMap::new();
+        let mut arrival_order = Vec::new();
+        for conn in self.collected.iter().filter(|conn| self.shows(conn)) {
+            let key = conn.key();
+            arrival_order.push(key.clone());
+            incoming.entry(key).or_default().push_back(conn.clone());
+        }
+
+        let mut merged = Vec::with_capacity(arrival_order.len());
+        for conn in &self.rows {
+            let key = self.follow(conn.key());
+            if let Some(updated) = incoming.get_mut(&key).and_then(VecDeque::pop_front) {
+                merged.push(updated);
+            }
+        }
+        for key in arrival_order {
+            if let Some(added) = incoming.get_mut(&key).and_then(VecDeque::pop_front) {
+                merged.push(added);
+            }
+        }
+
+        // Resolve and format once per connection instead of on every comparison
+        let (column, ascending) = (self.column, self.ascending);
+        let mut keyed: Vec<(SortKey, Connection)> = merged
+            .into_iter()
+            .map(|conn| (SortKey::of(&conn, column, self.resolver.as_ref()), conn))
+            .collect();
+        let order = |a: &SortKey, b: &SortKey| {
+            if ascending {
+                a.cmp(b)
+            } else {
+                b.cmp(a)
+            }
+        };
+        if !keyed.is_sorted_by(|(a, _), (b, _)| order(a, b) != Ordering::Greater) {
+            keyed.sort_by(|(a, _), (b, _)| order(a, b));
+        }
+        self.rows = keyed.into_iter().map(|(_, conn)| conn).collect();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::ConnectionDiff;
+
+    fn connection(program: &str, remote: &str, tx_rate: u64) -> Connection {
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:40000",
+            remote,
+            program,
+            "N/A",
+            program,
+        );
+        conn.tx_rate = tx_rate;
+        conn
+    }
+
+    fn snapshot(connections: Vec<Connection>, previous: &[Connection]) -> ConnectionSnapshot {
+        ConnectionSnapshot {
+            taken_at: SystemTime::now(),
+            diff: ConnectionDiff::between(previous, &connections),
+            connections,
+            ..ConnectionSnapshot::default()
+        }
+    }
+
+    fn programs(sorted: &SortedConnections) -> Vec<&str> {
+        sorted.rows().iter().map(|conn| &*conn.program).collect()
+    }
+
+    #[test]
+    fn test_rows_are_filtered_and_sorted() {
+        let connections = vec![
+            connection("curl", "10.0.0.9:443", 10),
+            connection("firefox", "93.184.216.34:443", 30),
+            connection("ssh", "127.0.0.1:22", 20),
+        ];
+        // By upload rate, largest first
+        let mut sorted = SortedConnections::new(5, false);
+        assert!(sorted.update(&snapshot(connections.clone(), &[])));
+        assert_eq!(programs(&sorted), ["firefox", "ssh", "curl"]);
+
+        sorted.set_hide_loopback(true);
+        sorted.set_hidden(HashSet::from([connections[1].key()]));
+        assert!(sorted.refresh());
+        assert_eq!(programs(&sorted), ["curl"]);
+
+        sorted.set_hidden(HashSet::new());
+        sorted.set_filter(Some(":443".to_string()));
+        sorted.set_sort(0, true);
+        assert!(sorted.refresh());
+        assert_eq!(programs(&sorted), ["curl", "firefox"]);
+        assert_eq!(sorted.collected().len(), 3);
+    }
+
+    #[test]
+    fn test_unchanged_snapshots_are_not_sorted_again() {
+        let connections = vec![
+            connection("curl", "10.0.0.9:443", 10),
+            connection("firefox", "93.184.216.34:443", 30),
+        ];
+        let mut sorted = SortedConnections::new(5, false);
+        let first = snapshot(connections.clone(), &[]);
+        assert!(sorted.update(&first));
+        assert!(!sorted.update(&first));
+        assert!(!sorted.refresh());
+        assert!(!sorted.update(&snapshot(connections.clone(), &connections)));
+
+        // Criteria set to what they already are change nothing either
+        sorted.set_sort(5, false);
+        sorted.set_filter(None);
+        assert!(!sorted.refresh());
+    }
+
+    #[test]
+    fn test_rows_keep_their_place_while_in_order() {
+        let mut old = vec![
+            connection("curl", "10.0.0.9:443", 0),
+            connection("wget", "10.0.0.8:443", 0),
+        ];
+        let mut sorted = SortedConnections::new(5, false);
+        sorted.update(&snapshot(old.clone(), &[]));
+        assert_eq!(programs(&sorted), ["curl", "wget"]);
+
+        // Equal rates arriving in another order don't swap rows
+        let new: Vec<Connection> = old.iter().rev().cloned().collect();
+        old.push(connection("apt", "10.0.0.7:80", 0));
+        sorted.update(&snapshot(new.clone(), &old));
+        assert_eq!(programs(&sorted), ["curl", "wget"]);
+
+        // A new busiest connection breaks the order and sorts again
+        let mut busier = new.clone();
+        busier.push(connection("apt", "10.0.0.7:80", 50));
+        sorted.update(&snapshot(busier, &new));
+        assert_eq!(programs(&sorted), ["apt", "curl", "wget"]);
+    }
+
+    #[test]
+    fn test_rows_follow_connections_into_time_wait() {
+        let mut open = vec![
+            connection("curl", "10.0.0.9:443", 0),
+            connection("wget", "10.0.0.8:443", 0),
+        ];
+        open[0].inode = 4242;
+        let mut sorted = SortedConnections::new(5, false);
+        sorted.update(&snapshot(open.clone(), &[]));
+        assert_eq!(programs(&sorted), ["curl", "wget"]);
+
+        // The kernel lists it without its inode once closed
+        let mut closing = open.clone();
+        closing[0].inode = 0;
+        closing[0].state = ConnectionState::TimeWait;
+        closing.reverse();
+        let closed = snapshot(closing, &open);
+        sorted.update(&closed);
+        assert_eq!(programs(&sorted), ["curl", "wget"]);
+        assert_eq!(sorted.follow(open[0].key()), closed.connections[1].key());
+        assert_eq!(sorted.follow(open[1].key()), open[1].key());
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod service_tests {
-    use crate::models::{Connection, ProcessIO};
+    use crate::models::{Connection, ConnectionState, ProcessIO, Protocol};
     use crate::services::NetworkService;
     use std::collections::HashMap;
 
@@ -73,8 +73,8 @@ mod service_tests {
     fn test_update_connection_rates_with_data() {
         let service = NetworkService::new();
         let connections = vec![Connection::new(
-            "tcp".to_string(),
-            "ESTABLISHED".to_string(),
+            Protocol::Tcp,
+            ConnectionState::Established,
             "127.0.0.1:1234".to_string(),
             "127.0.0.1:5678".to_string(),
             "test".to_string(),
@@ -98,8 +98,8 @@ mod service_tests {
         let current_pid = std::process::id().to_string();
 
         let connections = vec![Connection::new(
-            "tcp".to_string(),
-            "ESTABLISHED".to_string(),
+            Protocol::Tcp,
+            ConnectionState::Established,
             "127.0.0.1:1234".to_string(),
             "127.0.0.1:5678".to_string(),
             "test".to_string(),
@@ -108,7 +108,7 @@ mod service_tests {
         )];
 
         let mut prev_io = HashMap::new();
-        prev_io.insert(current_pid.clone(), ProcessIO::new(1000, 2000));
+        prev_io.insert(current_pid.as_str().into(), ProcessIO::new(1000, 2000));
 
         let result = service.update_connection_rates(connections, &prev_io);
         assert!(result.is_ok());
@@ -122,6 +122,48 @@ mod service_tests {
         // u64 values are always valid, no need to check for negativity
     }
 
+    #[test]
+    fn test_update_connection_rates_reads_io_once_per_pid() {
+        let service = NetworkService::new();
+        let connection = |local: &str, pid: &str| {
+            Connection::new(
+                Protocol::Tcp,
+                ConnectionState::Established,
+                local.to_string(),
+                "127.0.0.1:5678".to_string(),
+                "test".to_string(),
+                pid.to_string(),
+                "test".to_string(),
+            )
+        };
+        let connections = vec![
+            connection("127.0.0.1:1234", "100"),
+            connection("127.0.0.1:1235", "200"),
+            connection("127.0.0.1:1236", "100"),
+            connection("127.0.0.1:1237", "N/A"),
+        ];
+
+        let mut prev_io = HashMap::new();
+        prev_io.insert("100".into(), ProcessIO::new(0, 0));
+
+        let mut reads: HashMap<String, usize> = HashMap::new();
+        let (updated, current) = service
+            .update_connection_rates_with(connections, &prev_io, |pid| {
+                *reads.entry(pid.to_string()).or_default() += 1;
+                ProcessIO::new(4000, 2000)
+            })
+            .unwrap();
+        assert_eq!(
+            reads,
+            HashMap::from([("100".to_string(), 1), ("200".to_string(), 1)])
+        );
+        assert_eq!(current.len(), 2);
+        // Both sockets of PID 100 share its single read
+        assert!(updated[0].rx_rate > 0);
+        assert_eq!(updated[0].rx_rate, updated[2].rx_rate);
+        assert_eq!(updated[0].tx_rate, updated[2].tx_rate);
+    }
+
     #[test]
     fn test_default_implementation() {
         let service = NetworkService::default();
@@ -129,4 +171,24 @@ mod service_tests {
         // Should not panic
         assert!(result.is_ok() || result.is_err());
     }
+
+    #[test]
+    fn test_failing_table_is_skipped_for_a_while() {
+        let fixtures =
+            std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc/net");
+        let root = std::env::temp_dir().join(format!("nm-breaker-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("net")).unwrap();
+        std::fs::copy(fixtures.join("tcp"), root.join("net/tcp")).unwrap();
+
+        let service = NetworkService::with_proc_root(&root);
+        let tcp_only = service.get_connections().unwrap().len();
+        for _ in 0..2 {
+            assert_eq!(service.get_connections().unwrap().len(), tcp_only);
+        }
+
+        // tcp6 failed three times in a row, so it isn't read again right away
+        std::fs::copy(fixtures.join("tcp6"), root.join("net/tcp6")).unwrap();
+        assert_eq!(service.get_connections().unwrap().len(), tcp_only);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
 }
@@ -0,0 +1,95 @@
+use crate::models::{Tunnel, TunnelKind};
+use std::collections::HashMap;
+use std::fs;
+use std::net::IpAddr;
+use std::path::Path;
+
+/// WireGuard and TUN/TAP interfaces listed in `sys_root`/class/net, with
+/// their byte counters; empty where sysfs isn't available
+pub fn read_tunnels(sys_root: &Path) -> Vec<Tunnel> {
+    let Ok(entries) = fs::read_dir(sys_root.join("class/net")) else {
+        return Vec::new();
+    };
+    let mut tunnels: Vec<Tunnel> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let dir = entry.path();
+            let kind = tunnel_kind(&dir)?;
+            let counter = |name: &str| {
+                fs::read_to_string(dir.join("statistics").join(name))
+                    .ok()
+                    .and_then(|text| text.trim().parse().ok())
+                    .unwrap_or(0)
+            };
+            Some(Tunnel::new(
+                entry.file_name().to_string_lossy(),
+                kind,
+                counter("rx_bytes"),
+                counter("tx_bytes"),
+            ))
+        })
+        .collect();
+    tunnels.sort_by(|a, b| a.name.cmp(&b.name));
+    tunnels
+}
+
+/// Kind of the interface in `dir`, None unless it is a tunnel
+fn tunnel_kind(dir: &Path) -> Option<TunnelKind> {
+    let uevent = fs::read_to_string(dir.join("uevent")).unwrap_or_default();
+    if uevent.lines().any(|line| line == "DEVTYPE=wireguard") {
+        Some(TunnelKind::WireGuard)
+    } else if dir.join("tun_flags").exists() {
+        Some(TunnelKind::Tun)
+    } else {
+        None
+    }
+}
+
+/// Addresses of every local interface by name
+#[cfg(target_os = "linux")]
+pub fn interface_addresses() -> HashMap<String, Vec<IpAddr>> {
+    use std::ffi::CStr;
+    use std::net::{Ipv4Addr, Ipv6Addr};
+
+    let mut addresses: HashMap<String, Vec<IpAddr>> = HashMap::new();
+    let mut list: *mut libc::ifaddrs = std::ptr::null_mut();
+    // SAFETY: getifaddrs fills `list`, freed below
+    if unsafe { libc::getifaddrs(&mut list) } != 0 {
+        tracing::debug!("getifaddrs failed: {}", std::io::Error::last_os_error());
+        return addresses;
+    }
+    let mut entry = list;
+    while !entry.is_null() {
+        // SAFETY: entries of the list stay valid until freeifaddrs
+        let ifaddr = unsafe { &*entry };
+        entry = ifaddr.ifa_next;
+        if ifaddr.ifa_addr.is_null() {
+            continue;
+        }
+        // SAFETY: ifa_addr points at a sockaddr of the family it names, and
+        // ifa_name at a NUL-terminated string
+        let (ip, name) = unsafe {
+            let ip: IpAddr = match i32::from((*ifaddr.ifa_addr).sa_family) {
+                libc::AF_INET => {
+                    let addr = &*(ifaddr.ifa_addr as *const libc::sockaddr_in);
+                    Ipv4Addr::from(u32::from_be(addr.sin_addr.s_addr)).into()
+                }
+                libc::AF_INET6 => {
+                    let addr = &*(ifaddr.ifa_addr as *const libc::sockaddr_in6);
+                    Ipv6Addr::from(addr.sin6_addr.s6_addr).into()
+                }
+                _ => continue,
+            };
+            (ip, CStr::from_ptr(ifaddr.ifa_name).to_string_lossy())
+        };
+        addresses.entry(name.into_owned()).or_default().push(ip);
+    }
+    // SAFETY: `list` came from getifaddrs and isn't used afterwards
+    unsafe { libc::freeifaddrs(list) };
+    addresses
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn interface_addresses() -> HashMap<String, Vec<IpAddr>> {
+    HashMap::new()
+}
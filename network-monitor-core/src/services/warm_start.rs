@@ -0,0 +1,123 @@
+//! State carried over from one session to the next, so the first refresh
+//! after a restart finds socket owners in the cache, measures rates against
+//! the counters read just before exit and only marks connections opened in
+//! between as new
+
+use crate::models::{Connection, ProcessIOMap, SocketOwner};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
+
+/// Older state is from another session rather than a restart, and its PIDs
+/// are likely reused
+const MAX_AGE: Duration = Duration::from_secs(120);
+
+/// Changes on every boot, when inodes and PIDs start over
+const BOOT_ID: &str = "/proc/sys/kernel/random/boot_id";
+
+/// What a collector knew when it stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WarmStart {
+    saved_at: SystemTime,
+    boot_id: String,
+    /// Owner of each socket inode
+    pub owners: HashMap<u64, SocketOwner>,
+    /// Cumulative I/O counters by PID, from the last refresh
+    pub io: ProcessIOMap,
+    /// Connections of the last refresh
+    pub connections: Vec<Connection>,
+}
+
+impl WarmStart {
+    pub fn new(owners: HashMap<u64, SocketOwner>, io: ProcessIOMap) -> Self {
+        Self {
+            saved_at: SystemTime::now(),
+            boot_id: boot_id(),
+            owners,
+            io,
+            connections: Vec::new(),
+        }
+    }
+
+    /// How long ago the state was saved
+    pub fn age(&self) -> Duration {
+        self.saved_at.elapsed().unwrap_or_default()
+    }
+
+    /// State saved at `path` by the previous session; None when there is
+    /// none or it is too old or from before a reboot to use
+    pub fn load(path: &Path) -> io::Result<Option<Self>> {
+        let saved: Self = match fs::read(path) {
+            Ok(bytes) => serde_json::from_slice(&bytes)?,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e),
+        };
+        let recent = saved.saved_at.elapsed().is_ok_and(|age| age <= MAX_AGE);
+        Ok((recent && saved.boot_id == boot_id()).then_some(saved))
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        // Write next to the target and rename so readers never see half a file
+        let partial = path.with_extension("json.tmp");
+        fs::write(&partial, serde_json::to_vec(self)?)?;
+        fs::rename(&partial, path)
+    }
+}
+
+fn boot_id() -> String {
+    fs::read_to_string(BOOT_ID)
+        .map(|id| id.trim().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, ProcessIO, Protocol};
+
+    #[test]
+    fn test_only_recent_state_of_this_boot_loads() {
+        let path = std::env::temp_dir().join(format!("nm-warm-{}.json", std::process::id()));
+        let owner = SocketOwner {
+            pid: "812".to_string(),
+            name: "sshd".to_string(),
+            command: "sshd: admin".to_string(),
+        };
+        let mut state = WarmStart::new(
+            HashMap::from([(4242, owner)]),
+            ProcessIOMap::from([("812".into(), ProcessIO::new(100, 200))]),
+        );
+        state.connections.push(Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.5:22",
+            "10.0.0.9:51000",
+            "sshd",
+            "812",
+            "sshd: admin",
+        ));
+        state.save(&path).unwrap();
+        let loaded = WarmStart::load(&path).unwrap().unwrap();
+        assert_eq!(loaded.owners[&4242].name, "sshd");
+        assert_eq!(loaded.io["812"].tx, 200);
+        assert_eq!(loaded.connections.len(), 1);
+
+        state.saved_at -= MAX_AGE * 2;
+        state.save(&path).unwrap();
+        assert!(WarmStart::load(&path).unwrap().is_none());
+
+        state.saved_at = SystemTime::now();
+        state.boot_id = "another boot".to_string();
+        state.save(&path).unwrap();
+        assert!(WarmStart::load(&path).unwrap().is_none());
+
+        fs::remove_file(&path).unwrap();
+        assert!(WarmStart::load(&path).unwrap().is_none());
+    }
+}
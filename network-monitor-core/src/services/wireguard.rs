@@ -0,0 +1,162 @@
+use crate::error::Result;
+use crate::models::TunnelPeer;
+use crate::services::genetlink::{self, GenericNetlink};
+use crate::services::netlink::{attributes, for_each_message};
+use std::net::{Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, SystemTime};
+
+/// linux/wireguard.h
+const WG_GENL_NAME: &str = "wireguard";
+const WG_GENL_VERSION: u8 = 1;
+const WG_CMD_GET_DEVICE: u8 = 0;
+const WGDEVICE_A_IFNAME: u16 = 2;
+const WGDEVICE_A_PEERS: u16 = 8;
+const WGPEER_A_ENDPOINT: u16 = 4;
+const WGPEER_A_LAST_HANDSHAKE_TIME: u16 = 6;
+const WGPEER_A_RX_BYTES: u16 = 7;
+const WGPEER_A_TX_BYTES: u16 = 8;
+
+/// Peers of WireGuard interface `interface`, as `wg show` lists them, through
+/// the wireguard generic netlink family. Needs CAP_NET_ADMIN.
+pub fn peers(interface: &str) -> Result<Vec<TunnelPeer>> {
+    let mut netlink = GenericNetlink::open()?;
+    let family = netlink.family_id(WG_GENL_NAME)?;
+
+    let mut name = interface.as_bytes().to_vec();
+    name.push(0);
+    netlink.send(&genetlink::request(
+        family,
+        libc::NLM_F_REQUEST | libc::NLM_F_DUMP,
+        WG_CMD_GET_DEVICE,
+        WG_GENL_VERSION,
+        &[(WGDEVICE_A_IFNAME, &name)],
+    ))?;
+
+    // Devices with many peers are split over several messages
+    let mut peers = Vec::new();
+    loop {
+        if parse_device_dump(netlink.receive()?, family, &mut peers)? {
+            return Ok(peers);
+        }
+    }
+}
+
+/// Add the peers in the device messages of `datagram` to `peers`; true once
+/// the dump is complete
+fn parse_device_dump(datagram: &[u8], family: u16, peers: &mut Vec<TunnelPeer>) -> Result<bool> {
+    for_each_message(datagram, |kind, payload| {
+        if kind != family {
+            return;
+        }
+        let device = payload
+            .get(genetlink::GENL_HEADER_LEN..)
+            .unwrap_or_default();
+        for (_, list) in attributes(device).filter(|(kind, _)| *kind == WGDEVICE_A_PEERS) {
+            // One nested attribute per peer, typed by its index
+            peers.extend(attributes(list).map(|(_, peer)| parse_peer(peer)));
+        }
+    })
+}
+
+fn parse_peer(attributes_data: &[u8]) -> TunnelPeer {
+    let mut peer = TunnelPeer {
+        endpoint: None,
+        last_handshake: None,
+        rx_bytes: 0,
+        tx_bytes: 0,
+    };
+    let u64_of = |value: &[u8]| {
+        value
+            .get(..8)
+            .and_then(|bytes| bytes.try_into().ok())
+            .map_or(0, u64::from_ne_bytes)
+    };
+    for (kind, value) in attributes(attributes_data) {
+        match kind {
+            WGPEER_A_ENDPOINT => peer.endpoint = parse_sockaddr(value),
+            WGPEER_A_LAST_HANDSHAKE_TIME => {
+                // struct __kernel_timespec; zero before the first handshake
+                let seconds = u64_of(value);
+                let nanos = u64_of(value.get(8..).unwrap_or_default());
+                if seconds != 0 {
+                    peer.last_handshake = Some(
+                        SystemTime::UNIX_EPOCH
+                            + Duration::from_secs(seconds)
+                            + Duration::from_nanos(nanos),
+                    );
+                }
+            }
+            WGPEER_A_RX_BYTES => peer.rx_bytes = u64_of(value),
+            WGPEER_A_TX_BYTES => peer.tx_bytes = u64_of(value),
+            _ => {}
+        }
+    }
+    peer
+}
+
+/// Address in a struct sockaddr_in or sockaddr_in6
+fn parse_sockaddr(value: &[u8]) -> Option<SocketAddr> {
+    let family = i32::from(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?));
+    let port = u16::from_be_bytes(value.get(2..4)?.try_into().ok()?);
+    let ip = match family {
+        libc::AF_INET => {
+            let octets: [u8; 4] = value.get(4..8)?.try_into().ok()?;
+            Ipv4Addr::from(octets).into()
+        }
+        libc::AF_INET6 => {
+            let octets: [u8; 16] = value.get(8..24)?.try_into().ok()?;
+            Ipv6Addr::from(octets).into()
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::netlink::testing::{attribute, message};
+
+    #[test]
+    fn test_parse_device_dump() {
+        let family = 0x1a;
+        let mut endpoint = Vec::new();
+        endpoint.extend_from_slice(&(libc::AF_INET as u16).to_ne_bytes());
+        endpoint.extend_from_slice(&51820u16.to_be_bytes());
+        endpoint.extend_from_slice(&[203, 0, 113, 5]);
+        endpoint.extend_from_slice(&[0; 8]);
+        let mut handshake = 1_700_000_000u64.to_ne_bytes().to_vec();
+        handshake.extend_from_slice(&0u64.to_ne_bytes());
+
+        let mut peer = attribute(WGPEER_A_ENDPOINT, &endpoint);
+        peer.extend(attribute(WGPEER_A_LAST_HANDSHAKE_TIME, &handshake));
+        peer.extend(attribute(WGPEER_A_RX_BYTES, &4096u64.to_ne_bytes()));
+        peer.extend(attribute(WGPEER_A_TX_BYTES, &1024u64.to_ne_bytes()));
+        // A peer that never connected
+        let idle = attribute(WGPEER_A_TX_BYTES, &0u64.to_ne_bytes());
+        let mut list = attribute(0x8000, &peer);
+        list.extend(attribute(0x8001, &idle));
+
+        let mut device = vec![WG_CMD_GET_DEVICE, WG_GENL_VERSION, 0, 0];
+        device.extend(attribute(WGDEVICE_A_IFNAME, b"wg0\0"));
+        device.extend(attribute(WGDEVICE_A_PEERS | 0x8000, &list));
+
+        let mut datagram = message(family, &device);
+        datagram.extend(message(libc::NLMSG_DONE as u16, &[0; 4]));
+        let mut peers = Vec::new();
+        assert!(parse_device_dump(&datagram, family, &mut peers).unwrap());
+        assert_eq!(peers.len(), 2);
+        assert_eq!(peers[0].endpoint, "203.0.113.5:51820".parse().ok());
+        assert_eq!(
+            peers[0].last_handshake,
+            Some(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!((peers[0].rx_bytes, peers[0].tx_bytes), (4096, 1024));
+        assert_eq!(peers[1].endpoint, None);
+        assert_eq!(peers[1].last_handshake, None);
+
+        // Without CAP_NET_ADMIN the dump is refused
+        let denied = message(libc::NLMSG_ERROR as u16, &(-libc::EPERM).to_ne_bytes());
+        assert!(parse_device_dump(&denied, family, &mut peers).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::error::Result;
+use crate::models::WirelessLink;
+use crate::services::genetlink::{self, GenericNetlink};
+use crate::services::netlink::attributes;
+
+/// linux/nl80211.h
+const NL80211_GENL_NAME: &str = "nl80211";
+const NL80211_CMD_GET_INTERFACE: u8 = 5;
+const NL80211_CMD_GET_STATION: u8 = 17;
+const NL80211_ATTR_IFINDEX: u16 = 3;
+const NL80211_ATTR_IFNAME: u16 = 4;
+const NL80211_ATTR_IFTYPE: u16 = 5;
+const NL80211_ATTR_STA_INFO: u16 = 21;
+const NL80211_ATTR_WIPHY_FREQ: u16 = 38;
+const NL80211_ATTR_SSID: u16 = 52;
+const NL80211_IFTYPE_STATION: u32 = 2;
+const NL80211_STA_INFO_SIGNAL: u16 = 7;
+const NL80211_STA_INFO_TX_BITRATE: u16 = 8;
+const NL80211_STA_INFO_RX_BITRATE: u16 = 14;
+const NL80211_RATE_INFO_BITRATE: u16 = 1;
+const NL80211_RATE_INFO_BITRATE32: u16 = 5;
+
+/// Wireless client interfaces with their network, signal, bitrates and
+/// frequency, through the nl80211 generic netlink family. Needs no
+/// privileges; fails where there is no wireless hardware.
+pub fn links() -> Result<Vec<WirelessLink>> {
+    let mut netlink = GenericNetlink::open()?;
+    let family = netlink.family_id(NL80211_GENL_NAME)?;
+    let dump = |command: u8, attributes: &[(u16, &[u8])]| {
+        genetlink::request(
+            family,
+            libc::NLM_F_REQUEST | libc::NLM_F_DUMP,
+            command,
+            0,
+            attributes,
+        )
+    };
+
+    let mut interfaces = Vec::new();
+    netlink.dump(&dump(NL80211_CMD_GET_INTERFACE, &[]), family, |payload| {
+        interfaces.extend(parse_interface(payload));
+    })?;
+    for (index, link) in &mut interfaces {
+        if !link.is_connected() {
+            continue;
+        }
+        // The access point is the only station of a client interface
+        let index = index.to_ne_bytes();
+        netlink.dump(
+            &dump(NL80211_CMD_GET_STATION, &[(NL80211_ATTR_IFINDEX, &index)]),
+            family,
+            |payload| parse_station(payload, link),
+        )?;
+    }
+
+    let mut links: Vec<WirelessLink> = interfaces.into_iter().map(|(_, link)| link).collect();
+    links.sort_by(|a, b| a.interface.cmp(&b.interface));
+    Ok(links)
+}
+
+/// Index and link of a client interface; None for access points, monitors
+/// and the like
+fn parse_interface(payload: &[u8]) -> Option<(u32, WirelessLink)> {
+    let mut index = None;
+    let mut kind = None;
+    let mut link = WirelessLink::default();
+    for (attribute, value) in attributes(payload) {
+        match attribute {
+            NL80211_ATTR_IFINDEX => index = u32_of(value),
+            NL80211_ATTR_IFTYPE => kind = u32_of(value),
+            NL80211_ATTR_IFNAME => {
+                let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+                link.interface = String::from_utf8_lossy(&value[..end]).into_owned();
+            }
+            NL80211_ATTR_SSID => link.ssid = Some(String::from_utf8_lossy(value).into_owned()),
+            NL80211_ATTR_WIPHY_FREQ => link.frequency = u32_of(value),
+            _ => {}
+        }
+    }
+    (kind == Some(NL80211_IFTYPE_STATION) && !link.interface.is_empty()).then_some((index?, link))
+}
+
+/// Fill in the signal and bitrates of the station in `payload`
+fn parse_station(payload: &[u8], link: &mut WirelessLink) {
+    let Some((_, info)) = attributes(payload).find(|(kind, _)| *kind == NL80211_ATTR_STA_INFO)
+    else {
+        return;
+    };
+    for (attribute, value) in attributes(info) {
+        match attribute {
+            NL80211_STA_INFO_SIGNAL => link.signal_dbm = value.first().map(|&dbm| dbm as i8),
+            NL80211_STA_INFO_TX_BITRATE => link.tx_bitrate = bitrate(value),
+            NL80211_STA_INFO_RX_BITRATE => link.rx_bitrate = bitrate(value),
+            _ => {}
+        }
+    }
+}
+
+/// Rate in kbit/s of a nested rate info, given in units of 100 kbit/s; the
+/// 32-bit attribute is only there for rates the 16-bit one can't hold
+fn bitrate(rate_info: &[u8]) -> Option<u32> {
+    let mut rate = None;
+    for (attribute, value) in attributes(rate_info) {
+        match attribute {
+            NL80211_RATE_INFO_BITRATE32 => return u32_of(value).map(|rate| rate * 100),
+            NL80211_RATE_INFO_BITRATE => {
+                rate = Some(u32::from(u16::from_ne_bytes(value.get(..2)?.try_into().ok()?)) * 100)
+            }
+            _ => {}
+        }
+    }
+    rate
+}
+
+fn u32_of(value: &[u8]) -> Option<u32> {
+    Some(u32::from_ne_bytes(value.get(..4)?.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::netlink::testing::attribute;
+
+    #[test]
+    fn test_parse_interface() {
+        let mut payload = attribute(NL80211_ATTR_IFINDEX, &3u32.to_ne_bytes());
+        payload.extend(attribute(NL80211_ATTR_IFNAME, b"wlan0\0"));
+        payload.extend(attribute(
+            NL80211_ATTR_IFTYPE,
+            &NL80211_IFTYPE_STATION.to_ne_bytes(),
+        ));
+        payload.extend(attribute(NL80211_ATTR_WIPHY_FREQ, &5180u32.to_ne_bytes()));
+        payload.extend(attribute(NL80211_ATTR_SSID, b"HomeNet"));
+        let (index, link) = parse_interface(&payload).unwrap();
+        assert_eq!(index, 3);
+        assert_eq!(link.interface, "wlan0");
+        assert_eq!(link.ssid.as_deref(), Some("HomeNet"));
+        assert_eq!(link.frequency, Some(5180));
+
+        // An access point run by hostapd
+        let mut payload = attribute(NL80211_ATTR_IFINDEX, &4u32.to_ne_bytes());
+        payload.extend(attribute(NL80211_ATTR_IFNAME, b"wlan1\0"));
+        payload.extend(attribute(NL80211_ATTR_IFTYPE, &3u32.to_ne_bytes()));
+        assert!(parse_interface(&payload).is_none());
+    }
+
+    #[test]
+    fn test_parse_station() {
+        let mut tx_rate = attribute(NL80211_RATE_INFO_BITRATE, &8667u16.to_ne_bytes());
+        tx_rate.extend(attribute(
+            NL80211_RATE_INFO_BITRATE32,
+            &8667u32.to_ne_bytes(),
+        ));
+        let rx_rate = attribute(NL80211_RATE_INFO_BITRATE, &5400u16.to_ne_bytes());
+        let mut info = attribute(NL80211_STA_INFO_SIGNAL, &[(-58i8) as u8]);
+        info.extend(attribute(NL80211_STA_INFO_TX_BITRATE | 0x8000, &tx_rate));
+        info.extend(attribute(NL80211_STA_INFO_RX_BITRATE | 0x8000, &rx_rate));
+        let payload = attribute(NL80211_ATTR_STA_INFO | 0x8000, &info);
+
+        let mut link = WirelessLink::default();
+        parse_station(&payload, &mut link);
+        assert_eq!(link.signal_dbm, Some(-58));
+        assert_eq!(link.tx_bitrate, Some(866_700));
+        assert_eq!(link.rx_bitrate, Some(540_000));
+    }
+}
@@ -0,0 +1,607 @@
+use std::fmt;
+use std::str::FromStr;
+
+/// Whether traffic is counted in bytes or bits
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DataUnit {
+    #[default]
+    Bytes,
+    /// Always in powers of 1000 like link speeds, whatever the prefixes
+    Bits,
+}
+
+/// Multiples used for large values
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Prefixes {
+    /// Powers of 1024 written KB, MB, ...
+    #[default]
+    Jedec,
+    /// Powers of 1000 written kB, MB, ...
+    Si,
+    /// Powers of 1024 written KiB, MiB, ...
+    Iec,
+}
+
+/// Decimal and thousands separators of displayed numbers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum NumberStyle {
+    /// 1234.5, no grouping: the original output
+    #[default]
+    Plain,
+    /// 1,234.5 (English, Chinese, Japanese, ...)
+    Point,
+    /// 1.234,5 (German, Spanish, Italian, ...)
+    Comma,
+    /// 1 234,5 with a no-break space (French, Russian, Polish, ...)
+    Space,
+}
+
+impl DataUnit {
+    pub const ALL: [DataUnit; 2] = [DataUnit::Bytes, DataUnit::Bits];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DataUnit::Bytes => "bytes",
+            DataUnit::Bits => "bits",
+        }
+    }
+}
+
+impl Prefixes {
+    pub const ALL: [Prefixes; 3] = [Prefixes::Jedec, Prefixes::Si, Prefixes::Iec];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Prefixes::Jedec => "jedec",
+            Prefixes::Si => "si",
+            Prefixes::Iec => "iec",
+        }
+    }
+}
+
+impl NumberStyle {
+    pub const ALL: [NumberStyle; 4] = [
+        NumberStyle::Plain,
+        NumberStyle::Point,
+        NumberStyle::Comma,
+        NumberStyle::Space,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NumberStyle::Plain => "plain",
+            NumberStyle::Point => "point",
+            NumberStyle::Comma => "comma",
+            NumberStyle::Space => "space",
+        }
+    }
+
+    /// Style of the user's locale, from LC_ALL, LC_NUMERIC or LANG in that
+    /// order like setlocale; plain for the C locale or none at all
+    pub fn system() -> Self {
+        ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .into_iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .map_or(NumberStyle::Plain, |locale| Self::from_locale(&locale))
+    }
+
+    /// Style of a locale name such as "de_DE.UTF-8"; languages not listed
+    /// here write numbers like English
+    pub fn from_locale(locale: &str) -> Self {
+        let name = locale.split(['.', '@']).next().unwrap_or_default();
+        let (language, territory) = name.split_once('_').unwrap_or((name, ""));
+        match (language, territory) {
+            ("" | "C" | "POSIX", _) => NumberStyle::Plain,
+            ("es", "MX" | "US") | ("de" | "it" | "fr", "CH") => NumberStyle::Point,
+            (
+                "de" | "es" | "it" | "nl" | "pt" | "da" | "id" | "tr" | "el" | "ro" | "hr" | "sl"
+                | "sr" | "vi" | "is" | "ca",
+                _,
+            ) => NumberStyle::Comma,
+            (
+                "fr" | "ru" | "pl" | "cs" | "sk" | "sv" | "fi" | "nb" | "nn" | "no" | "uk" | "hu"
+                | "bg" | "lt" | "lv" | "et" | "be" | "kk",
+                _,
+            ) => NumberStyle::Space,
+            _ => NumberStyle::Point,
+        }
+    }
+
+    /// `value` with `decimals` digits after the separator, grouped by
+    /// thousands
+    pub fn format(self, value: f64, decimals: usize) -> String {
+        let plain = format!("{value:.decimals$}");
+        let (decimal, group) = match self {
+            NumberStyle::Plain => return plain,
+            NumberStyle::Point => ('.', ','),
+            NumberStyle::Comma => (',', '.'),
+            NumberStyle::Space => (',', '\u{a0}'),
+        };
+        let (integer, fraction) = plain.split_once('.').unwrap_or((&plain, ""));
+        let (sign, digits) = match integer.strip_prefix('-') {
+            Some(digits) => ("-", digits),
+            None => ("", integer),
+        };
+        let mut text = String::from(sign);
+        for (i, digit) in digits.chars().enumerate() {
+            if i > 0 && (digits.len() - i) % 3 == 0 {
+                text.push(group);
+            }
+            text.push(digit);
+        }
+        if !fraction.is_empty() {
+            text.push(decimal);
+            text.push_str(fraction);
+        }
+        text
+    }
+}
+
+impl fmt::Display for DataUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for Prefixes {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl fmt::Display for NumberStyle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for DataUnit {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|unit| unit.name() == name)
+            .ok_or_else(|| format!("unknown unit '{name}' (use bytes or bits)"))
+    }
+}
+
+impl FromStr for Prefixes {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().to_ascii_lowercase();
+        Self::ALL
+            .into_iter()
+            .find(|prefixes| prefixes.name() == name)
+            .ok_or_else(|| format!("unknown prefixes '{name}' (use jedec, si or iec)"))
+    }
+}
+
+impl FromStr for NumberStyle {
+    type Err = String;
+
+    /// A style by name, or "locale" for [`NumberStyle::system`]
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        let name = name.trim().to_ascii_lowercase();
+        if name == "locale" {
+            return Ok(Self::system());
+        }
+        Self::ALL
+            .into_iter()
+            .find(|style| style.name() == name)
+            .ok_or_else(|| {
+                format!("unknown number format '{name}' (use locale, plain, point, comma or space)")
+            })
+    }
+}
+
+/// How traffic amounts are displayed and exported. The default matches the
+/// original output: bytes with 1024-based KB/MB and plain numbers.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Units {
+    pub unit: DataUnit,
+    pub prefixes: Prefixes,
+    /// Separators of displayed amounts; raw counts never use them
+    pub numbers: NumberStyle,
+}
+
+impl Units {
+    pub fn new(unit: DataUnit, prefixes: Prefixes) -> Self {
+        Self {
+            unit,
+            prefixes,
+            numbers: NumberStyle::Plain,
+        }
+    }
+
+    pub fn with_numbers(self, numbers: NumberStyle) -> Self {
+        Self { numbers, ..self }
+    }
+
+    fn base(self) -> f64 {
+        match (self.unit, self.prefixes) {
+            (DataUnit::Bits, _) | (DataUnit::Bytes, Prefixes::Si) => 1000.0,
+            (DataUnit::Bytes, Prefixes::Jedec | Prefixes::Iec) => 1024.0,
+        }
+    }
+
+    /// Unit symbols from 1 up to base^4
+    fn symbols(self) -> [&'static str; 5] {
+        match (self.unit, self.prefixes) {
+            (DataUnit::Bytes, Prefixes::Jedec) => ["B", "KB", "MB", "GB", "TB"],
+            (DataUnit::Bytes, Prefixes::Si) => ["B", "kB", "MB", "GB", "TB"],
+            (DataUnit::Bytes, Prefixes::Iec) => ["B", "KiB", "MiB", "GiB", "TiB"],
+            (DataUnit::Bits, Prefixes::Jedec) => ["bit", "Kbit", "Mbit", "Gbit", "Tbit"],
+            (DataUnit::Bits, Prefixes::Si | Prefixes::Iec) => {
+                ["bit", "kbit", "Mbit", "Gbit", "Tbit"]
+            }
+        }
+    }
+
+    /// A byte count in this unit, unscaled: bytes or bits
+    pub fn count(self, bytes: u64) -> u64 {
+        match self.unit {
+            DataUnit::Bytes => bytes,
+            DataUnit::Bits => bytes.saturating_mul(8),
+        }
+    }
+
+    /// Rate with the largest fitting multiple, e.g. "1.5KB/s" or "12.0Mbit/s"
+    pub fn format_rate(self, bytes_per_sec: u64) -> String {
+        let base = self.base();
+        let symbols = self.symbols();
+        let mut value = self.count(bytes_per_sec) as f64;
+        for symbol in &symbols[..4] {
+            if value < base {
+                return format!("{}{symbol}/s", self.numbers.format(value, 1));
+            }
+            value /= base;
+        }
+        format!("{}{}/s", self.numbers.format(value, 1), symbols[4])
+    }
+
+    /// Total amount, never scaled past the M multiple so it reads steadily as
+    /// it grows, e.g. "1.5 KB" or "2048.00 MB"
+    pub fn format_total(self, bytes: u64) -> String {
+        let base = self.base();
+        let symbols = self.symbols();
+        let value = self.count(bytes) as f64;
+        let numbers = self.numbers;
+        if value < base {
+            format!("{} {}", numbers.format(value, 1), symbols[0])
+        } else if value < base * base {
+            format!("{} {}", numbers.format(value / base, 1), symbols[1])
+        } else {
+            format!(
+                "{} {}",
+                numbers.format(value / (base * base), 2),
+                symbols[2]
+            )
+        }
+    }
+}
+
+/// Utility for formatting byte values and other common formatting tasks
+pub struct Formatter;
+
+impl Formatter {
+    /// Format bytes as human readable string with rate (per second)
+    pub fn format_bytes(bytes_val: u64) -> String {
+        Units::default().format_rate(bytes_val)
+    }
+
+    /// Format a rate of bytes per second in bits per second with the largest
+    /// fitting multiple of 1000, e.g. "12.0Mbit/s"
+    pub fn format_bits(bytes_val: u64) -> String {
+        Units::new(DataUnit::Bits, Prefixes::default()).format_rate(bytes_val)
+    }
+
+    /// Format bytes as human readable string (total)
+    pub fn format_bytes_total(bytes_val: u64) -> String {
+        Units::default().format_total(bytes_val)
+    }
+
+    /// Format bytes with custom precision
+    pub fn format_bytes_precise(bytes_val: u64, precision: usize) -> String {
+        let mut bytes_val = bytes_val as f64;
+        let units = ["B", "KB", "MB", "GB", "TB"];
+
+        for unit in &units {
+            if bytes_val < 1024.0 {
+                return format!("{bytes_val:.precision$}{unit}/s", precision = precision);
+            }
+            bytes_val /= 1024.0;
+        }
+        format!("{bytes_val:.precision$}PB/s", precision = precision)
+    }
+
+    /// Format duration in seconds to human readable string
+    pub fn format_duration(seconds: u64) -> String {
+        if seconds < 60 {
+            format!("{}s", seconds)
+        } else if seconds < 3600 {
+            let minutes = seconds / 60;
+            let secs = seconds % 60;
+            format!("{}m {}s", minutes, secs)
+        } else if seconds < 86400 {
+            let hours = seconds / 3600;
+            let minutes = (seconds % 3600) / 60;
+            format!("{}h {}m", hours, minutes)
+        } else {
+            let days = seconds / 86400;
+            let hours = (seconds % 86400) / 3600;
+            format!("{}d {}h", days, hours)
+        }
+    }
+
+    /// Format connection count with proper pluralization
+    pub fn format_connection_count(count: usize) -> String {
+        match count {
+            0 => "No connections".to_string(),
+            1 => "1 connection".to_string(),
+            _ => format!("{} connections", count),
+        }
+    }
+
+    /// Names with their counts, e.g. congestion control algorithms as
+    /// "cubic 40, bbr 12"
+    pub fn format_counts<T: AsRef<str>>(counts: &[(T, usize)]) -> String {
+        counts
+            .iter()
+            .map(|(name, count)| format!("{} {}", name.as_ref(), count))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// Format protocol name consistently
+    pub fn format_protocol(protocol: &str) -> String {
+        match protocol.to_uppercase().as_str() {
+            "TCP" => "TCP".to_string(),
+            "TCP6" => "TCP6".to_string(),
+            "UDP" => "UDP".to_string(),
+            "UDP6" => "UDP6".to_string(),
+            _ => protocol.to_uppercase(),
+        }
+    }
+
+    /// Format state name consistently
+    pub fn format_state(state: &str) -> String {
+        match state {
+            "ESTABLISHED" => "ESTABLISHED".to_string(),
+            "LISTEN" => "LISTEN".to_string(),
+            "TIME_WAIT" => "TIME_WAIT".to_string(),
+            "CLOSE_WAIT" => "CLOSE_WAIT".to_string(),
+            "SYN_SENT" => "SYN_SENT".to_string(),
+            "SYN_RECV" => "SYN_RECV".to_string(),
+            "FIN_WAIT1" => "FIN_WAIT1".to_string(),
+            "FIN_WAIT2" => "FIN_WAIT2".to_string(),
+            "CLOSED" => "CLOSED".to_string(),
+            "CLOSING" => "CLOSING".to_string(),
+            "LAST_ACK" => "LAST_ACK".to_string(),
+            "NEW_SYN_RECV" => "NEW_SYN_RECV".to_string(),
+            _ => state.to_string(),
+        }
+    }
+
+    /// Truncate string to fit within max length with ellipsis
+    pub fn truncate_string(s: &str, max_len: usize) -> String {
+        if s.len() <= max_len {
+            s.to_string()
+        } else if max_len <= 3 {
+            "...".to_string()
+        } else {
+            format!("{}...", &s[..max_len - 3])
+        }
+    }
+
+    /// Format PID consistently
+    pub fn format_pid(pid: &str) -> String {
+        if pid == "N/A" {
+            "N/A".to_string()
+        } else {
+            pid.to_string()
+        }
+    }
+
+    /// Format program name with fallback
+    pub fn format_program(program: &str) -> String {
+        if program.is_empty() || program == "N/A" {
+            "Unknown".to_string()
+        } else {
+            program.to_string()
+        }
+    }
+}
+
+/// Convenience functions for backward compatibility and easier access
+pub fn format_bytes(bytes_val: u64) -> String {
+    Formatter::format_bytes(bytes_val)
+}
+
+pub fn format_bytes_total(bytes_val: u64) -> String {
+    Formatter::format_bytes_total(bytes_val)
+}
+
+pub fn format_bits(bytes_val: u64) -> String {
+    Formatter::format_bits(bytes_val)
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    Formatter::format_duration(seconds)
+}
+
+pub fn format_connection_count(count: usize) -> String {
+    Formatter::format_connection_count(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0.0B/s");
+        assert_eq!(format_bytes(512), "512.0B/s");
+        assert_eq!(format_bytes(1024), "1.0KB/s");
+        assert_eq!(format_bytes(1536), "1.5KB/s");
+        assert_eq!(format_bytes(1024 * 1024), "1.0MB/s");
+        assert_eq!(format_bytes(1024 * 1024 * 1024), "1.0GB/s");
+    }
+
+    #[test]
+    fn test_format_bits() {
+        assert_eq!(format_bits(0), "0.0bit/s");
+        assert_eq!(format_bits(100), "800.0bit/s");
+        assert_eq!(format_bits(125), "1.0Kbit/s");
+        assert_eq!(format_bits(128), "1.0Kbit/s");
+        assert_eq!(format_bits(125_000), "1.0Mbit/s");
+        assert_eq!(format_bits(125_000_000 * 3), "3.0Gbit/s");
+    }
+
+    #[test]
+    fn test_format_bytes_total() {
+        assert_eq!(format_bytes_total(0), "0.0 B");
+        assert_eq!(format_bytes_total(512), "512.0 B");
+        assert_eq!(format_bytes_total(1024), "1.0 KB");
+        assert_eq!(format_bytes_total(1536), "1.5 KB");
+        assert_eq!(format_bytes_total(1024 * 1024), "1.00 MB");
+    }
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(0), "0s");
+        assert_eq!(format_duration(30), "30s");
+        assert_eq!(format_duration(60), "1m 0s");
+        assert_eq!(format_duration(90), "1m 30s");
+        assert_eq!(format_duration(3600), "1h 0m");
+        assert_eq!(format_duration(3661), "1h 1m");
+        assert_eq!(format_duration(86400), "1d 0h");
+        assert_eq!(format_duration(90061), "1d 1h");
+    }
+
+    #[test]
+    fn test_format_connection_count() {
+        assert_eq!(format_connection_count(0), "No connections");
+        assert_eq!(format_connection_count(1), "1 connection");
+        assert_eq!(format_connection_count(5), "5 connections");
+    }
+
+    #[test]
+    fn test_format_counts() {
+        assert_eq!(
+            Formatter::format_counts(&[("cubic", 40), ("bbr", 12)]),
+            "cubic 40, bbr 12"
+        );
+        assert_eq!(Formatter::format_counts::<&str>(&[]), "");
+    }
+
+    #[test]
+    fn test_format_protocol() {
+        assert_eq!(Formatter::format_protocol("tcp"), "TCP");
+        assert_eq!(Formatter::format_protocol("TCP6"), "TCP6");
+        assert_eq!(Formatter::format_protocol("udp"), "UDP");
+        assert_eq!(Formatter::format_protocol("unknown"), "UNKNOWN");
+    }
+
+    #[test]
+    fn test_format_state() {
+        assert_eq!(Formatter::format_state("ESTABLISHED"), "ESTABLISHED");
+        assert_eq!(Formatter::format_state("LISTEN"), "LISTEN");
+        assert_eq!(Formatter::format_state("unknown"), "unknown");
+    }
+
+    #[test]
+    fn test_truncate_string() {
+        assert_eq!(Formatter::truncate_string("short", 10), "short");
+        assert_eq!(Formatter::truncate_string("exactlyten", 10), "exactlyten");
+        let result = Formatter::truncate_string("thisislonger", 10);
+        assert_eq!(result, "thisisl...");
+        assert_eq!(Formatter::truncate_string("abc", 2), "...");
+        assert_eq!(Formatter::truncate_string("abc", 3), "abc");
+    }
+
+    #[test]
+    fn test_format_pid() {
+        assert_eq!(Formatter::format_pid("1234"), "1234");
+        assert_eq!(Formatter::format_pid("N/A"), "N/A");
+    }
+
+    #[test]
+    fn test_format_program() {
+        assert_eq!(Formatter::format_program("firefox"), "firefox");
+        assert_eq!(Formatter::format_program(""), "Unknown");
+        assert_eq!(Formatter::format_program("N/A"), "Unknown");
+    }
+
+    #[test]
+    fn test_format_units() {
+        let bits_si = Units::new(DataUnit::Bits, Prefixes::Si);
+        assert_eq!(bits_si.format_rate(100), "800.0bit/s");
+        assert_eq!(bits_si.format_rate(125_000), "1.0Mbit/s");
+        assert_eq!(bits_si.format_total(1_000_000), "8.00 Mbit");
+
+        // Bits stay decimal with binary prefixes selected
+        let bits_iec = Units::new(DataUnit::Bits, Prefixes::Iec);
+        assert_eq!(bits_iec.format_rate(125_000), "1.0Mbit/s");
+        assert_eq!(bits_iec.format_total(125_000), "1.00 Mbit");
+        let bits_jedec = Units::new(DataUnit::Bits, Prefixes::Jedec);
+        assert_eq!(bits_jedec.format_total(1500), "12.0 Kbit");
+
+        let iec = Units::new(DataUnit::Bytes, Prefixes::Iec);
+        assert_eq!(iec.format_rate(1536), "1.5KiB/s");
+        assert_eq!(iec.format_total(1024 * 1024), "1.00 MiB");
+
+        let si = Units::new(DataUnit::Bytes, Prefixes::Si);
+        assert_eq!(si.format_rate(1500), "1.5kB/s");
+        assert_eq!(si.count(1500), 1500);
+        assert_eq!(bits_si.count(1500), 12_000);
+    }
+
+    #[test]
+    fn test_number_styles() {
+        assert_eq!(NumberStyle::Plain.format(1234.5, 1), "1234.5");
+        assert_eq!(NumberStyle::Point.format(1234.5, 1), "1,234.5");
+        assert_eq!(NumberStyle::Comma.format(1234.5, 1), "1.234,5");
+        assert_eq!(NumberStyle::Space.format(1234.5, 1), "1\u{a0}234,5");
+        assert_eq!(NumberStyle::Comma.format(-1234567.0, 0), "-1.234.567");
+        assert_eq!(NumberStyle::Point.format(999.0, 2), "999.00");
+
+        let comma = Units::default().with_numbers(NumberStyle::Comma);
+        assert_eq!(comma.format_rate(1536), "1,5KB/s");
+        assert_eq!(comma.format_total(2048 * 1024 * 1024), "2.048,00 MB");
+    }
+
+    #[test]
+    fn test_number_style_of_locale() {
+        assert_eq!(NumberStyle::from_locale("de_DE.UTF-8"), NumberStyle::Comma);
+        assert_eq!(
+            NumberStyle::from_locale("fr_FR.UTF-8@euro"),
+            NumberStyle::Space
+        );
+        assert_eq!(NumberStyle::from_locale("fr_CH.UTF-8"), NumberStyle::Point);
+        assert_eq!(NumberStyle::from_locale("en_US.UTF-8"), NumberStyle::Point);
+        assert_eq!(NumberStyle::from_locale("ja_JP"), NumberStyle::Point);
+        assert_eq!(NumberStyle::from_locale("C.UTF-8"), NumberStyle::Plain);
+        assert_eq!(NumberStyle::from_locale("POSIX"), NumberStyle::Plain);
+    }
+
+    #[test]
+    fn test_parse_units() {
+        assert_eq!("Bits".parse(), Ok(DataUnit::Bits));
+        assert_eq!("iec".parse(), Ok(Prefixes::Iec));
+        assert!("nibbles".parse::<DataUnit>().is_err());
+        assert!("metric".parse::<Prefixes>().is_err());
+        assert_eq!("Comma".parse(), Ok(NumberStyle::Comma));
+        assert!("locale".parse::<NumberStyle>().is_ok());
+        assert!("roman".parse::<NumberStyle>().is_err());
+    }
+
+    #[test]
+    fn test_format_bytes_precise() {
+        assert_eq!(Formatter::format_bytes_precise(1024, 2), "1.00KB/s");
+        assert_eq!(Formatter::format_bytes_precise(1536, 3), "1.500KB/s");
+    }
+}
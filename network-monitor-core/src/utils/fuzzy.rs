@@ -0,0 +1,58 @@
+//! Fuzzy matching of what is typed in a search box, such as the GUI's command
+//! palette, against the names on offer
+
+/// Score of `text` for `query`, None unless every character of the query
+/// appears in `text` in order, ignoring case. Higher scores are better
+/// matches: characters starting a word or following the previous match count
+/// extra, so "cd" ranks "Clear DNS" above "Cached".
+pub fn fuzzy_score(query: &str, text: &str) -> Option<u32> {
+    let mut query = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars() {
+        let Some(&wanted) = query.peek() else {
+            break;
+        };
+        let matched = c.to_lowercase().eq(wanted.to_lowercase());
+        if matched {
+            query.next();
+            score += 1;
+            if previous.is_none_or(|previous| !previous.is_alphanumeric()) {
+                score += 4;
+            }
+            if previous_matched {
+                score += 2;
+            }
+        }
+        previous = Some(c);
+        previous_matched = matched;
+    }
+    query.peek().is_none().then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_characters_match_in_order() {
+        assert_eq!(fuzzy_score("", "Preferences"), Some(0));
+        assert!(fuzzy_score("pref", "Preferences").is_some());
+        assert!(fuzzy_score("PRF", "Preferences").is_some());
+        assert!(fuzzy_score("show dns", "Show DNS").is_some());
+        assert_eq!(fuzzy_score("fp", "Preferences"), None);
+        assert_eq!(fuzzy_score("prefs!", "Preferences"), None);
+    }
+
+    #[test]
+    fn test_word_starts_rank_higher() {
+        let score = |text| fuzzy_score("cd", text).unwrap_or(0);
+        assert!(score("Clear DNS") > score("Cached"));
+        assert!(fuzzy_score("ref", "Refresh") > fuzzy_score("ref", "Preferences"));
+    }
+}
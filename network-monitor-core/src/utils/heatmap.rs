@@ -0,0 +1,85 @@
+/// Heat of the TX and RX rates shared by the GTK and TUI tables, so the
+/// busiest flows stand out without reading the numbers
+use crate::models::Connection;
+
+/// Steps from a trickle to the busiest flow; idle rates are level 0
+pub const HEAT_LEVELS: usize = 5;
+
+/// Heat of `rate` relative to `peak`, the busiest rate shown: 0 without
+/// traffic, [`HEAT_LEVELS`] within half a decade of the peak and one step
+/// less for every further half decade, down to 1
+pub fn heat_level(rate: u64, peak: u64) -> usize {
+    if rate == 0 || peak == 0 {
+        return 0;
+    }
+    let half_decades = (2.0 * (peak as f64 / rate as f64).log10()).max(0.0) as usize;
+    HEAT_LEVELS - half_decades.min(HEAT_LEVELS - 1)
+}
+
+/// Busiest upload and download rates among the connections of a table, which
+/// the heat of each rate is relative to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RatePeaks {
+    pub tx_rate: u64,
+    pub rx_rate: u64,
+}
+
+impl RatePeaks {
+    pub fn of(connections: &[Connection]) -> Self {
+        connections
+            .iter()
+            .fold(Self::default(), |peaks, conn| Self {
+                tx_rate: peaks.tx_rate.max(conn.tx_rate),
+                rx_rate: peaks.rx_rate.max(conn.rx_rate),
+            })
+    }
+
+    pub fn tx_heat(&self, conn: &Connection) -> usize {
+        heat_level(conn.tx_rate, self.tx_rate)
+    }
+
+    pub fn rx_heat(&self, conn: &Connection) -> usize {
+        heat_level(conn.rx_rate, self.rx_rate)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol};
+
+    #[test]
+    fn test_heat_falls_by_half_decades() {
+        assert_eq!(heat_level(0, 1_000_000), 0);
+        assert_eq!(heat_level(1_000_000, 1_000_000), 5);
+        assert_eq!(heat_level(400_000, 1_000_000), 5);
+        assert_eq!(heat_level(200_000, 1_000_000), 4);
+        assert_eq!(heat_level(100_000, 1_000_000), 3);
+        assert_eq!(heat_level(20_000, 1_000_000), 2);
+        assert_eq!(heat_level(10_000, 1_000_000), 1);
+        assert_eq!(heat_level(1, 1_000_000), 1);
+        // Stale peaks never make a rate hotter than the top
+        assert_eq!(heat_level(2_000, 1_000), 5);
+    }
+
+    #[test]
+    fn test_peaks_are_per_direction() {
+        let mut upload = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            "1.1.1.1:443",
+            "rsync",
+            "100",
+            "rsync",
+        );
+        let mut download = upload.clone();
+        upload.tx_rate = 5_000;
+        download.rx_rate = 80_000;
+        download.tx_rate = 50;
+        let peaks = RatePeaks::of(&[upload.clone(), download.clone()]);
+        assert_eq!((peaks.tx_rate, peaks.rx_rate), (5_000, 80_000));
+        assert_eq!((peaks.tx_heat(&upload), peaks.rx_heat(&upload)), (5, 0));
+        assert_eq!((peaks.tx_heat(&download), peaks.rx_heat(&download)), (1, 5));
+    }
+}
@@ -0,0 +1,229 @@
+use crate::utils::formatter::Formatter;
+use std::borrow::Borrow;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+/// Size of a bounded cache, for the diagnostics view
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub capacity: usize,
+    /// Entries dropped to stay within `capacity` since the cache was created
+    pub evictions: u64,
+    /// Rough heap usage: entry payloads plus map bookkeeping
+    pub approx_bytes: usize,
+    /// Lookups answered from the cache, and ones that weren't
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl CacheStats {
+    /// One-line description, e.g. "120 / 4096 entries · 18.2 KB · 0 evicted"
+    pub fn summary(&self) -> String {
+        format!(
+            "{} / {} entries · {} · {} evicted",
+            self.entries,
+            self.capacity,
+            Formatter::format_bytes_total(self.approx_bytes as u64),
+            self.evictions
+        )
+    }
+
+    /// Share of lookups that were hits; None before the first lookup
+    pub fn hit_rate(&self) -> Option<f64> {
+        let lookups = self.hits + self.misses;
+        (lookups > 0).then(|| self.hits as f64 / lookups as f64)
+    }
+}
+
+/// Map holding at most `capacity` entries; inserting into a full map evicts
+/// the least recently used one. Reads through `get` count as a use.
+#[derive(Debug)]
+pub struct LruCache<K, V> {
+    entries: HashMap<K, (V, u64)>,
+    /// Keys by last use, oldest first
+    order: BTreeMap<u64, K>,
+    tick: u64,
+    capacity: usize,
+    evictions: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            order: BTreeMap::new(),
+            tick: 0,
+            capacity: capacity.max(1),
+            evictions: 0,
+            hits: 0,
+            misses: 0,
+        }
+    }
+
+    /// Look up `key` and mark it as most recently used
+    pub fn get<Q>(&mut self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let Some((value, last_use)) = self.entries.get_mut(key) else {
+            self.misses += 1;
+            return None;
+        };
+        self.hits += 1;
+        self.tick += 1;
+        if let Some(key) = self.order.remove(last_use) {
+            self.order.insert(self.tick, key);
+        }
+        *last_use = self.tick;
+        Some(value)
+    }
+
+    /// Look up `key` without affecting eviction order
+    pub fn peek<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.get(key).map(|(value, _)| value)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.entries.contains_key(key)
+    }
+
+    /// Insert or replace `key`, returning the entry evicted to make room
+    pub fn insert(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.tick += 1;
+        if let Some((_, last_use)) = self.entries.insert(key.clone(), (value, self.tick)) {
+            self.order.remove(&last_use);
+        }
+        self.order.insert(self.tick, key);
+
+        if self.entries.len() > self.capacity {
+            self.evict_oldest()
+        } else {
+            None
+        }
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (value, last_use) = self.entries.remove(key)?;
+        self.order.remove(&last_use);
+        Some(value)
+    }
+
+    /// Remove every entry, keeping the capacity and eviction count
+    pub fn drain(&mut self) -> impl Iterator<Item = (K, V)> + '_ {
+        self.order.clear();
+        self.entries.drain().map(|(key, (value, _))| (key, value))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+
+    /// Change the limit, evicting the least recently used entries above it
+    pub fn set_capacity(&mut self, capacity: usize) {
+        self.capacity = capacity.max(1);
+        while self.entries.len() > self.capacity {
+            self.evict_oldest();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, (value, _))| (key, value))
+    }
+
+    /// Entry counts plus an estimate of the memory held, given the heap bytes
+    /// owned by each entry's key and value
+    pub fn stats(&self, entry_heap_bytes: impl Fn(&K, &V) -> usize) -> CacheStats {
+        // Each key is stored twice: in the map and in the recency index
+        let per_entry = 2 * std::mem::size_of::<K>() + std::mem::size_of::<(V, u64)>() + 8;
+        let heap: usize = self
+            .iter()
+            .map(|(key, value)| entry_heap_bytes(key, value))
+            .sum();
+        CacheStats {
+            entries: self.len(),
+            capacity: self.capacity,
+            evictions: self.evictions,
+            approx_bytes: self.len() * per_entry + heap,
+            hits: self.hits,
+            misses: self.misses,
+        }
+    }
+
+    fn evict_oldest(&mut self) -> Option<(K, V)> {
+        let (_, key) = self.order.pop_first()?;
+        let (value, _) = self.entries.remove(&key)?;
+        self.evictions += 1;
+        Some((key, value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        // Reading "a" makes "b" the oldest entry
+        assert_eq!(cache.get("a"), Some(&1));
+        assert_eq!(cache.insert("c", 3), Some(("b", 2)));
+        assert!(cache.contains_key("a"));
+        assert!(cache.contains_key("c"));
+        assert_eq!(cache.get("b"), None);
+
+        let stats = cache.stats(|_, _| 0);
+        assert_eq!((stats.evictions, stats.hits, stats.misses), (1, 1, 1));
+        assert_eq!(stats.hit_rate(), Some(0.5));
+    }
+
+    #[test]
+    fn test_replacing_a_key_does_not_evict() {
+        let mut cache = LruCache::new(2);
+        cache.insert("a", 1);
+        cache.insert("b", 2);
+        assert_eq!(cache.insert("a", 10), None);
+        assert_eq!(cache.peek("a"), Some(&10));
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_shrinking_capacity_evicts_oldest() {
+        let mut cache = LruCache::new(3);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.insert(3, "three");
+        cache.set_capacity(1);
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains_key(&3));
+
+        let stats = cache.stats(|_, value| value.len());
+        assert_eq!((stats.entries, stats.capacity, stats.evictions), (1, 1, 2));
+    }
+}
@@ -0,0 +1,14 @@
+pub mod formatter;
+pub mod fuzzy;
+pub mod heatmap;
+pub mod lru;
+pub mod parsing;
+pub mod recovery;
+pub mod viewport;
+
+// Export formatter for both GTK and TUI
+pub use fuzzy::fuzzy_score;
+pub use heatmap::{heat_level, RatePeaks, HEAT_LEVELS};
+pub use lru::{CacheStats, LruCache};
+pub use parsing::*;
+pub use recovery::*;
@@ -0,0 +1,774 @@
+/// Helper utilities for common parsing operations
+use crate::error::{NetworkMonitorError, Result};
+use crate::models::{ConnectionState, MulticastGroup, Route, SocketTimer};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+/// Parse a hexadecimal string to u8 with proper error context
+pub fn parse_hex_u8(input: &str, context: &str) -> Result<u8> {
+    u8::from_str_radix(input, 16).map_err(|e| {
+        NetworkMonitorError::HexParseError(format!(
+            "{}: Failed to parse hex '{}': {}",
+            context, input, e
+        ))
+    })
+}
+
+/// Parse a hexadecimal string to u16 with proper error context
+pub fn parse_hex_u16(input: &str, context: &str) -> Result<u16> {
+    u16::from_str_radix(input, 16).map_err(|e| {
+        NetworkMonitorError::HexParseError(format!(
+            "{}: Failed to parse hex '{}': {}",
+            context, input, e
+        ))
+    })
+}
+
+/// Parse a hexadecimal string to u32 with proper error context
+pub fn parse_hex_u32(input: &str, context: &str) -> Result<u32> {
+    u32::from_str_radix(input, 16).map_err(|e| {
+        NetworkMonitorError::HexParseError(format!(
+            "{}: Failed to parse hex '{}': {}",
+            context, input, e
+        ))
+    })
+}
+
+/// Parse a hexadecimal string to u64 with proper error context
+pub fn parse_hex_u64(input: &str, context: &str) -> Result<u64> {
+    u64::from_str_radix(input, 16).map_err(|e| {
+        NetworkMonitorError::HexParseError(format!(
+            "{}: Failed to parse hex '{}': {}",
+            context, input, e
+        ))
+    })
+}
+
+/// Parse a decimal string with proper error context
+pub fn parse_decimal<T>(input: &str, context: &str) -> Result<T>
+where
+    T: std::str::FromStr,
+    T::Err: std::fmt::Display,
+{
+    input.parse::<T>().map_err(|e| {
+        NetworkMonitorError::ParseError(format!("{}: Failed to parse '{}': {}", context, input, e))
+    })
+}
+
+/// Parse a port from hexadecimal string
+pub fn parse_port(port_hex: &str) -> Result<u16> {
+    parse_hex_u16(port_hex, "port")
+}
+
+/// Parse an inode from string
+pub fn parse_inode(inode_str: &str) -> Result<u64> {
+    parse_decimal(inode_str, "inode")
+}
+
+/// Parse the `tx_queue:rx_queue` column of /proc/net/{tcp,udp}, two hex
+/// byte counts
+pub fn parse_socket_queues(input: &str) -> Result<(u32, u32)> {
+    let (tx, rx) = input.split_once(':').ok_or_else(|| {
+        NetworkMonitorError::ParseError(format!("queues: expected tx:rx, got '{}'", input))
+    })?;
+    Ok((
+        parse_hex_u32(tx, "tx_queue")?,
+        parse_hex_u32(rx, "rx_queue")?,
+    ))
+}
+
+/// Parse the counter tables of /proc/net/snmp or /proc/net/netstat: pairs
+/// of lines, `Table: Field ...` then `Table: value ...`, into values keyed
+/// `Table.Field`. Negative values such as Tcp MaxConn are left out.
+pub fn parse_snmp(text: &str) -> std::collections::HashMap<String, u64> {
+    let mut values = std::collections::HashMap::new();
+    let mut lines = text.lines();
+    while let (Some(header), Some(row)) = (lines.next(), lines.next()) {
+        let (Some((table, fields)), Some((row_table, counters))) =
+            (header.split_once(':'), row.split_once(':'))
+        else {
+            break;
+        };
+        if table != row_table {
+            break;
+        }
+        for (field, counter) in fields.split_whitespace().zip(counters.split_whitespace()) {
+            if let Ok(counter) = counter.parse() {
+                values.insert(format!("{table}.{field}"), counter);
+            }
+        }
+    }
+    values
+}
+
+/// Parse a port range written as two numbers, as in
+/// /proc/sys/net/ipv4/ip_local_port_range
+pub fn parse_port_range(text: &str) -> Result<std::ops::RangeInclusive<u16>> {
+    let mut ports = text.split_whitespace();
+    let (Some(first), Some(last), None) = (ports.next(), ports.next(), ports.next()) else {
+        return Err(NetworkMonitorError::ParseError(format!(
+            "port range: expected two ports in '{}'",
+            text.trim()
+        )));
+    };
+    let first = parse_decimal(first, "port range")?;
+    let last = parse_decimal(last, "port range")?;
+    if first > last {
+        return Err(NetworkMonitorError::ParseError(format!(
+            "port range: {first} is above {last}"
+        )));
+    }
+    Ok(first..=last)
+}
+
+/// Route flags of /proc/net/route and /proc/net/ipv6_route
+const RTF_UP: u32 = 0x1;
+const RTF_GATEWAY: u32 = 0x2;
+const RTF_REJECT: u32 = 0x200;
+const RTF_LOCAL: u32 = 0x8000_0000;
+
+/// Parse the IPv4 routes of /proc/net/route, whose addresses and masks are
+/// little-endian hex; routes that are down or malformed are left out
+pub fn parse_routes(text: &str) -> Vec<Route> {
+    text.lines()
+        .skip(1)
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [interface, destination, gateway, flags, _, _, metric, mask, ..] = fields[..]
+            else {
+                return None;
+            };
+            let flags = parse_hex_u32(flags, "route flags").ok()?;
+            if flags & RTF_UP == 0 || flags & RTF_REJECT != 0 {
+                return None;
+            }
+            Some(Route {
+                destination: parse_ipv4_hex(destination).ok()?.into(),
+                prefix_len: parse_hex_u32(mask, "route mask").ok()?.count_ones() as u8,
+                gateway: (flags & RTF_GATEWAY != 0)
+                    .then(|| parse_ipv4_hex(gateway).ok().map(IpAddr::from))
+                    .flatten(),
+                interface: interface.to_string(),
+                metric: parse_decimal(metric, "route metric").ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse the IPv6 routes of /proc/net/ipv6_route, whose addresses are plain
+/// hex; down, unreachable and local-table routes are left out
+pub fn parse_ipv6_routes(text: &str) -> Vec<Route> {
+    let address = |hex: &str| u128::from_str_radix(hex, 16).ok().map(Ipv6Addr::from);
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [destination, prefix_len, _, _, gateway, metric, _, _, flags, interface] =
+                fields[..]
+            else {
+                return None;
+            };
+            let flags = parse_hex_u32(flags, "route flags").ok()?;
+            if flags & RTF_UP == 0 || flags & (RTF_REJECT | RTF_LOCAL) != 0 {
+                return None;
+            }
+            Some(Route {
+                destination: address(destination)?.into(),
+                prefix_len: parse_hex_u8(prefix_len, "route prefix").ok()?,
+                gateway: address(gateway)
+                    .filter(|gateway| !gateway.is_unspecified())
+                    .map(IpAddr::from),
+                interface: interface.to_string(),
+                metric: parse_hex_u32(metric, "route metric").ok()?,
+            })
+        })
+        .collect()
+}
+
+/// Parse the IPv4 groups of /proc/net/igmp: a line per interface, then an
+/// indented line per group it joined, its address little-endian hex
+pub fn parse_igmp(text: &str) -> Vec<MulticastGroup> {
+    let mut interface = None;
+    let mut groups = Vec::new();
+    for line in text.lines().skip(1) {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        if !line.starts_with('\t') {
+            interface = fields.get(1).map(|name| name.trim_end_matches(':'));
+            continue;
+        }
+        let (Some(interface), [group, users, ..]) = (interface, &fields[..]) else {
+            continue;
+        };
+        let (Ok(address), Ok(users)) = (parse_ipv4_hex(group), users.parse()) else {
+            continue;
+        };
+        groups.push(MulticastGroup::joined(address.into(), interface, users));
+    }
+    groups
+}
+
+/// Parse the IPv6 groups of /proc/net/igmp6, a line per interface and
+/// group with the address in plain hex
+pub fn parse_igmp6(text: &str) -> Vec<MulticastGroup> {
+    text.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [_, interface, group, users, ..] = fields[..] else {
+                return None;
+            };
+            let address = u128::from_str_radix(group, 16).ok().map(Ipv6Addr::from)?;
+            Some(MulticastGroup::joined(
+                address.into(),
+                interface,
+                users.parse().ok()?,
+            ))
+        })
+        .collect()
+}
+
+/// Two hex digits starting at byte offset `at`; an error instead of a
+/// slicing panic when a multi-byte character straddles the pair
+fn hex_pair(ip_hex: &str, at: usize) -> Result<&str> {
+    ip_hex.get(at..at + 2).ok_or_else(|| {
+        NetworkMonitorError::InvalidAddress(format!("Non-hex characters in address: {}", ip_hex))
+    })
+}
+
+/// Parse IPv4 address from hex string (little-endian format)
+pub fn parse_ipv4_hex(ip_hex: &str) -> Result<std::net::Ipv4Addr> {
+    if ip_hex.len() != 8 {
+        return Err(NetworkMonitorError::InvalidAddress(format!(
+            "Invalid IPv4 hex length: {} (expected 8)",
+            ip_hex.len()
+        )));
+    }
+
+    let mut bytes = [0u8; 4];
+    for (i, chunk) in (0..ip_hex.len()).step_by(2).enumerate() {
+        bytes[3 - i] = parse_hex_u8(hex_pair(ip_hex, chunk)?, "IPv4 byte")?;
+    }
+    Ok(std::net::Ipv4Addr::from(bytes))
+}
+
+/// Parse IPv6 address from hex string: four 32-bit words, each in host
+/// (little-endian) byte order like the IPv4 form
+pub fn parse_ipv6_hex(ip_hex: &str) -> Result<std::net::Ipv6Addr> {
+    if ip_hex.len() != 32 {
+        return Err(NetworkMonitorError::InvalidAddress(format!(
+            "Invalid IPv6 hex length: {} (expected 32)",
+            ip_hex.len()
+        )));
+    }
+
+    let mut bytes = [0u8; 16];
+    for (i, chunk) in (0..ip_hex.len()).step_by(2).enumerate() {
+        bytes[i / 4 * 4 + 3 - i % 4] = parse_hex_u8(hex_pair(ip_hex, chunk)?, "IPv6 byte")?;
+    }
+    Ok(std::net::Ipv6Addr::from(bytes))
+}
+
+/// Parse TCP state from hex value, None if it isn't valid hex
+pub fn parse_tcp_state(state_hex: &str) -> Option<ConnectionState> {
+    parse_hex_u8(state_hex, "TCP state")
+        .ok()
+        .map(ConnectionState::from_code)
+}
+
+/// Validate that a string contains only digits (for PID validation)
+pub fn validate_pid(pid_str: &str) -> Result<()> {
+    if pid_str.chars().all(|c| c.is_ascii_digit()) {
+        Ok(())
+    } else {
+        Err(NetworkMonitorError::InvalidPid(format!(
+            "PID contains non-digit characters: {}",
+            pid_str
+        )))
+    }
+}
+
+/// Split socket address into IP and port components
+pub fn split_socket_addr(addr_str: &str) -> Result<(&str, &str)> {
+    match addr_str.split_once(':') {
+        Some((ip, port)) if !port.contains(':') => Ok((ip, port)),
+        _ => Err(NetworkMonitorError::InvalidAddress(format!(
+            "Invalid socket address format: {}",
+            addr_str
+        ))),
+    }
+}
+
+/// Parse a /proc/net socket address like `0100007F:0277` into `127.0.0.1:631`
+pub fn parse_socket_addr(addr_str: &str) -> Result<String> {
+    let (ip_hex, port_hex) = split_socket_addr(addr_str)?;
+    let port = parse_port(port_hex)?;
+
+    let ip = match ip_hex.len() {
+        8 => std::net::IpAddr::V4(parse_ipv4_hex(ip_hex)?),
+        32 => std::net::IpAddr::V6(parse_ipv6_hex(ip_hex)?),
+        len => {
+            return Err(NetworkMonitorError::InvalidAddress(format!(
+                "Invalid IP hex length: {} (expected 8 or 32)",
+                len
+            )))
+        }
+    };
+
+    Ok(format!("{ip}:{port}"))
+}
+
+/// Leading columns of a /proc/net/{tcp,udp} line we use:
+/// sl, local_address, rem_address, st, tx/rx queue, tr/when, retrnsmt, uid, timeout, inode
+pub const PROC_NET_FIELDS: usize = 10;
+
+/// Data lines of a /proc/net table, header skipped, borrowed from the read buffer
+pub fn proc_net_lines(data: &[u8]) -> impl Iterator<Item = &[u8]> {
+    let mut rest = data;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        let (line, next) = match memchr::memchr(b'\n', rest) {
+            Some(end) => (&rest[..end], &rest[end + 1..]),
+            None => (rest, &rest[rest.len()..]),
+        };
+        rest = next;
+        Some(line)
+    })
+    .skip(1)
+}
+
+/// Split a /proc/net line into its leading columns without allocating;
+/// None for short or non UTF-8 lines
+pub fn proc_net_fields(line: &[u8]) -> Option<[&str; PROC_NET_FIELDS]> {
+    let mut words = line
+        .split(u8::is_ascii_whitespace)
+        .filter(|word| !word.is_empty());
+    let mut fields = [""; PROC_NET_FIELDS];
+    for field in fields.iter_mut() {
+        *field = std::str::from_utf8(words.next()?).ok()?;
+    }
+    Some(fields)
+}
+
+/// Socket row of /proc/net/{tcp,udp}[6], before it is matched to a process
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProcNetSocket {
+    pub local: String,
+    pub remote: String,
+    /// None when the state column isn't valid hex
+    pub state: Option<ConnectionState>,
+    pub uid: Option<u32>,
+    /// 0 when missing, e.g. for TIME_WAIT sockets
+    pub inode: u64,
+    /// Bytes in the send and receive queues; 0 when unreadable
+    pub tx_queue: u32,
+    pub rx_queue: u32,
+    /// None when no timer is pending, always for UDP
+    pub timer: Option<SocketTimer>,
+}
+
+/// Parse a /proc/net/{tcp,udp}[6] data line. Errors name the column that
+/// failed and carry the line, so a skipped row can be diagnosed from the log.
+pub fn parse_proc_net_line(line: &[u8]) -> Result<ProcNetSocket> {
+    let malformed = |field, source| NetworkMonitorError::ProcNetLine {
+        field,
+        line: String::from_utf8_lossy(line).trim().to_string(),
+        source: Box::new(source),
+    };
+
+    let fields = proc_net_fields(line).ok_or_else(|| {
+        malformed(
+            "columns",
+            NetworkMonitorError::ParseError(format!("expected {} UTF-8 columns", PROC_NET_FIELDS)),
+        )
+    })?;
+
+    let (tx_queue, rx_queue) = parse_socket_queues(fields[4]).unwrap_or_default();
+    Ok(ProcNetSocket {
+        local: parse_socket_addr(fields[1]).map_err(|e| malformed("local_address", e))?,
+        remote: parse_socket_addr(fields[2]).map_err(|e| malformed("rem_address", e))?,
+        state: parse_tcp_state(fields[3]),
+        uid: parse_decimal(fields[7], "uid").ok(),
+        inode: parse_inode(fields[9]).unwrap_or(0),
+        tx_queue,
+        rx_queue,
+        timer: SocketTimer::from_proc_net(fields[5], fields[6]),
+    })
+}
+
+/// Parse a /proc/net/sctp/assocs data line. Associations may span several
+/// addresses on either side; the remote primary path, marked with `*`, and
+/// the first local address are kept.
+pub fn parse_sctp_assoc(line: &str) -> Result<ProcNetSocket> {
+    let malformed = |field: &'static str| NetworkMonitorError::ProcNetLine {
+        field,
+        line: line.trim().to_string(),
+        source: Box::new(NetworkMonitorError::ParseError(format!(
+            "bad {field} column"
+        ))),
+    };
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [_, _, _, _, state, _, _, tx_queue, rx_queue, uid, inode, local_port, remote_port, ref addresses @ ..] =
+        fields[..]
+    else {
+        return Err(malformed("columns"));
+    };
+    let split = addresses
+        .iter()
+        .position(|&field| field == "<->")
+        .ok_or_else(|| malformed("LADDRS"))?;
+    let local = sctp_addresses(&addresses[..split])
+        .next()
+        .ok_or_else(|| malformed("LADDRS"))?;
+    let mut remotes = sctp_addresses(&addresses[split + 1..]);
+    let remote = addresses[split + 1..]
+        .iter()
+        .find_map(|field| field.strip_prefix('*')?.parse().ok())
+        .or_else(|| remotes.next())
+        .ok_or_else(|| malformed("RADDRS"))?;
+    let port = |port: &str, field| port.parse::<u16>().map_err(|_| malformed(field));
+    Ok(ProcNetSocket {
+        local: format!("{local}:{}", port(local_port, "LPORT")?),
+        remote: format!("{remote}:{}", port(remote_port, "RPORT")?),
+        state: state.parse().ok().map(ConnectionState::from_sctp_code),
+        uid: parse_decimal(uid, "uid").ok(),
+        inode: inode.parse().unwrap_or(0),
+        tx_queue: tx_queue.parse().unwrap_or(0),
+        rx_queue: rx_queue.parse().unwrap_or(0),
+        timer: None,
+    })
+}
+
+/// Parse a /proc/net/sctp/eps data line into a listening socket; None for
+/// endpoints that don't listen, whose associations are listed in assocs
+pub fn parse_sctp_endpoint(line: &str) -> Option<ProcNetSocket> {
+    let fields: Vec<&str> = line.split_whitespace().collect();
+    let [_, _, _, state, _, local_port, uid, inode, ref addresses @ ..] = fields[..] else {
+        return None;
+    };
+    if state.parse::<u8>().ok().map(ConnectionState::from_code) != Some(ConnectionState::Listen) {
+        return None;
+    }
+    let local = sctp_addresses(addresses).next()?;
+    let any: IpAddr = if local.is_ipv6() {
+        Ipv6Addr::UNSPECIFIED.into()
+    } else {
+        Ipv4Addr::UNSPECIFIED.into()
+    };
+    Some(ProcNetSocket {
+        local: format!("{local}:{}", local_port.parse::<u16>().ok()?),
+        remote: format!("{any}:0"),
+        state: Some(ConnectionState::Listen),
+        uid: parse_decimal(uid, "uid").ok(),
+        inode: inode.parse().unwrap_or(0),
+        tx_queue: 0,
+        rx_queue: 0,
+        timer: None,
+    })
+}
+
+/// Leading addresses of an SCTP address list, primary marks stripped
+fn sctp_addresses<'a>(fields: &'a [&str]) -> impl Iterator<Item = IpAddr> + 'a {
+    fields
+        .iter()
+        .map_while(|field| field.trim_start_matches('*').parse().ok())
+}
+
+/// Socket address of a connection field such as "10.0.0.2:443",
+/// "2001:db8::1:443" or "[2001:db8::1]:443", with IPv4-mapped IPv6
+/// addresses folded into IPv4; None for wildcards like "0.0.0.0:*"
+pub fn parse_endpoint(addr: &str) -> Option<std::net::SocketAddr> {
+    let (ip, port) = addr.rsplit_once(':')?;
+    let ip: std::net::IpAddr = ip
+        .trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()?;
+    Some(std::net::SocketAddr::new(
+        ip.to_canonical(),
+        port.parse().ok()?,
+    ))
+}
+
+/// Normalize common address patterns for better readability
+pub fn normalize_address(addr: &str) -> std::borrow::Cow<'static, str> {
+    match addr {
+        "0.0.0.0:*" | "*:*" => std::borrow::Cow::Borrowed("ANY"),
+        "127.0.0.1:*" | "[::1]:*" => std::borrow::Cow::Borrowed("LOCALHOST"),
+        _ => std::borrow::Cow::Owned(addr.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex_u16("1234", "test").unwrap(), 0x1234);
+        assert_eq!(parse_hex_u8("FF", "test").unwrap(), 255);
+        assert!(parse_hex_u16("ZZ", "test").is_err());
+    }
+
+    #[test]
+    fn test_parse_decimal() {
+        assert_eq!(parse_decimal::<u32>("1234", "test").unwrap(), 1234);
+        assert!(parse_decimal::<u32>("abc", "test").is_err());
+    }
+
+    #[test]
+    fn test_parse_port() {
+        assert_eq!(parse_port("1234").unwrap(), 0x1234);
+        assert!(parse_port("ZZZZ").is_err());
+    }
+
+    #[test]
+    fn test_parse_endpoint() {
+        assert_eq!(parse_endpoint("10.0.0.2:443"), "10.0.0.2:443".parse().ok());
+        assert_eq!(
+            parse_endpoint("::ffff:10.0.0.2:443"),
+            "10.0.0.2:443".parse().ok()
+        );
+        assert_eq!(
+            parse_endpoint("[2001:db8::1]:443"),
+            parse_endpoint("2001:db8::1:443")
+        );
+        assert_eq!(parse_endpoint("0.0.0.0:*"), None);
+    }
+
+    #[test]
+    fn test_parse_port_range() {
+        assert_eq!(parse_port_range("32768\t60999\n").unwrap(), 32768..=60999);
+        assert!(parse_port_range("32768").is_err());
+        assert!(parse_port_range("60999 32768").is_err());
+        assert!(parse_port_range("1024 70000").is_err());
+    }
+
+    #[test]
+    fn test_parse_socket_queues() {
+        assert_eq!(
+            parse_socket_queues("00000400:0000001A").unwrap(),
+            (1024, 26)
+        );
+        assert!(parse_socket_queues("00000400").is_err());
+        assert!(parse_socket_queues("0000040G:00000000").is_err());
+    }
+
+    #[test]
+    fn test_parse_snmp() {
+        let text = "Tcp: RtoAlgorithm MaxConn OutSegs RetransSegs
+Tcp: 1 -1 5000 42
+Udp: InDatagrams InErrors
+Udp: 900 3
+";
+        let values = parse_snmp(text);
+        assert_eq!(values["Tcp.OutSegs"], 5000);
+        assert_eq!(values["Tcp.RetransSegs"], 42);
+        assert_eq!(values["Udp.InErrors"], 3);
+        assert!(!values.contains_key("Tcp.MaxConn"));
+        // A header without its values ends the tables
+        assert!(parse_snmp("Tcp: OutSegs\nUdp: 1\n").is_empty());
+    }
+
+    #[test]
+    fn test_parse_sctp() {
+        let line = "ffff8881 ffff8882 2   1   3  0       7        0        0       0 26645 3868  \
+                    2905  10.0.0.1 10.0.1.1 <-> 10.0.0.2 *10.0.1.2 \t    7500    10    10";
+        let socket = parse_sctp_assoc(line).unwrap();
+        assert_eq!(socket.local, "10.0.0.1:3868");
+        assert_eq!(socket.remote, "10.0.1.2:2905");
+        assert_eq!(socket.state, Some(ConnectionState::Established));
+        assert_eq!((socket.uid, socket.inode), (Some(0), 26645));
+
+        let line = "ffff8881 ffff8882 2   1   1  0       8        0        0       0 26646 38412 \
+                    3868  2001:db8::1 <-> 2001:db8::2 \t    7500";
+        let socket = parse_sctp_assoc(line).unwrap();
+        assert_eq!(socket.remote, "2001:db8::2:3868");
+        assert_eq!(socket.state, Some(ConnectionState::SynSent));
+        assert!(parse_sctp_assoc("ffff8881 ffff8882 2 1 3 0 7 0 0 0 1 2 3 10.0.0.1").is_err());
+
+        let listening = parse_sctp_endpoint("ffff8881 ffff8882 1   10  31   3868   0 26000 :: ");
+        assert_eq!(listening.unwrap().remote, ":::0");
+        assert!(parse_sctp_endpoint("ffff8881 ffff8882 1   7   31   3868   0 26001 :: ").is_none());
+    }
+
+    #[test]
+    fn test_parse_igmp() {
+        let text = "Idx\tDevice    : Count Querier\tGroup    Users Timer\tReporter\n\
+                    1\tlo        :     1      V3\n\
+                    \t\t\t\t010000E0     1 0:00000000\t\t0\n\
+                    2\teth0      :     2      V3\n\
+                    \t\t\t\tFB0000E0     1 0:00000000\t\t0\n\
+                    \t\t\t\t010000E0     1 0:00000000\t\t0\n\
+                    3\twg0       :     0      V3\n";
+        let groups: Vec<String> = parse_igmp(text).iter().map(|g| g.to_string()).collect();
+        assert_eq!(
+            groups,
+            [
+                "224.0.0.1 on lo (all hosts)",
+                "224.0.0.251 on eth0 (mDNS)",
+                "224.0.0.1 on eth0 (all hosts)",
+            ]
+        );
+
+        let text = "2    eth0            ff0200000000000000000000000000fb     2 00000004 0\n\
+                    2    eth0            ff0200000000000000000001ff000002     1 00000004 0\n";
+        let groups = parse_igmp6(text);
+        assert_eq!(groups[0].to_string(), "ff02::fb on eth0 (mDNS)");
+        assert_eq!(groups[0].users, 2);
+        assert_eq!(groups[1].service, Some("solicited node"));
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        let text =
+            "Iface\tDestination\tGateway \tFlags\tRefCnt\tUse\tMetric\tMask\t\tMTU\tWindow\tIRTT
+eth0\t00000000\t0101A8C0\t0003\t0\t0\t100\t00000000\t0\t0\t0
+eth0\t0001A8C0\t00000000\t0001\t0\t0\t100\t00FFFFFF\t0\t0\t0
+wg0\t0000080A\t00000000\t0000\t0\t0\t0\t0000FFFF\t0\t0\t0
+";
+        let routes = parse_routes(text);
+        assert_eq!(routes.len(), 2); // wg0's route is down
+        assert_eq!(
+            routes[0].to_string(),
+            "default via 192.168.1.1 dev eth0 metric 100"
+        );
+        assert_eq!(routes[1].to_string(), "192.168.1.0/24 dev eth0 metric 100");
+
+        let text = "\
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 fe800000000000000000000000000001 00000400 00000001 00000000 00000003     eth0
+20010db8000000000000000000000000 40 00000000000000000000000000000000 00 00000000000000000000000000000000 00000100 00000001 00000000 00000001     eth0
+20010db8000000000000000000000002 80 00000000000000000000000000000000 00 00000000000000000000000000000000 00000000 00000002 00000000 80200001     eth0
+00000000000000000000000000000000 00 00000000000000000000000000000000 00 00000000000000000000000000000000 ffffffff 00000001 00000000 00200200       lo
+";
+        let routes = parse_ipv6_routes(text);
+        assert_eq!(routes.len(), 2);
+        assert_eq!(
+            routes[0].to_string(),
+            "default via fe80::1 dev eth0 metric 1024"
+        );
+        assert_eq!(routes[1].to_string(), "2001:db8::/64 dev eth0 metric 256");
+    }
+
+    #[test]
+    fn test_parse_ipv4_hex() {
+        let ip = parse_ipv4_hex("0100007F").unwrap(); // 127.0.0.1 in little-endian
+        assert_eq!(ip.to_string(), "127.0.0.1");
+        assert!(parse_ipv4_hex("123").is_err()); // Wrong length
+        assert!(parse_ipv4_hex("0é00007F").is_err()); // Multi-byte character
+    }
+
+    #[test]
+    fn test_parse_ipv6_hex() {
+        let ip = parse_ipv6_hex("00000000000000000000000001000000").unwrap();
+        assert_eq!(ip.to_string(), "::1");
+        let ip = parse_ipv6_hex("B80D0120000000000000000002000000").unwrap();
+        assert_eq!(ip.to_string(), "2001:db8::2");
+        assert!(parse_ipv6_hex("123").is_err()); // Wrong length
+    }
+
+    #[test]
+    fn test_parse_tcp_state() {
+        assert_eq!(parse_tcp_state("01"), Some(ConnectionState::Established));
+        assert_eq!(parse_tcp_state("0A"), Some(ConnectionState::Listen));
+        assert_eq!(parse_tcp_state("FF"), Some(ConnectionState::Unknown(255)));
+        assert_eq!(parse_tcp_state("ZZ"), None);
+    }
+
+    #[test]
+    fn test_validate_pid() {
+        assert!(validate_pid("1234").is_ok());
+        assert!(validate_pid("0").is_ok());
+        assert!(validate_pid("abc").is_err());
+        assert!(validate_pid("12a4").is_err());
+    }
+
+    #[test]
+    fn test_split_socket_addr() {
+        let (ip, port) = split_socket_addr("0100007F:1234").unwrap();
+        assert_eq!(ip, "0100007F");
+        assert_eq!(port, "1234");
+        assert!(split_socket_addr("invalid").is_err());
+        assert!(split_socket_addr("01:02:03").is_err());
+    }
+
+    #[test]
+    fn test_parse_socket_addr() {
+        assert_eq!(
+            parse_socket_addr("0100007F:1234").unwrap(),
+            "127.0.0.1:4660"
+        );
+        assert_eq!(
+            parse_socket_addr("00000000000000000000000001000000:0050").unwrap(),
+            "::1:80"
+        );
+        assert!(parse_socket_addr("0100007F").is_err());
+        assert!(parse_socket_addr("01007F:1234").is_err());
+        assert!(parse_socket_addr("0100007F:ZZZZ").is_err());
+    }
+
+    #[test]
+    fn test_normalize_address() {
+        assert_eq!(normalize_address("0.0.0.0:*"), "ANY");
+        assert_eq!(normalize_address("127.0.0.1:*"), "LOCALHOST");
+        assert_eq!(normalize_address("192.168.1.1:8080"), "192.168.1.1:8080");
+    }
+
+    const PROC_NET_TCP: &[u8] = b"  sl  local_address rem_address   st tx_queue rx_queue tr tm->when retrnsmt   uid  timeout inode
+   0: 0100007F:0277 00000000:0000 0A 00000000:00000000 00:00000000 00000000     0        0 23456 1 0000000000000000 100 0 0 10 0
+   1: 0200000A:D431 0101A8C0:01BB 01 00000400:00000010 02:00000A1F 00000000  1000        0 34567 2 0000000000000000 20 4 30 10 -1
+   2: truncated
+";
+
+    #[test]
+    fn test_proc_net_lines_and_fields() {
+        let lines: Vec<&[u8]> = proc_net_lines(PROC_NET_TCP).collect();
+        assert_eq!(lines.len(), 3);
+
+        let fields = proc_net_fields(lines[1]).unwrap();
+        assert_eq!(fields[1], "0200000A:D431");
+        assert_eq!(fields[3], "01");
+        assert_eq!(fields[7], "1000");
+        assert_eq!(fields[9], "34567");
+        assert!(proc_net_fields(lines[2]).is_none());
+    }
+
+    #[test]
+    fn test_parse_proc_net_line() {
+        let lines: Vec<&[u8]> = proc_net_lines(PROC_NET_TCP).collect();
+
+        let socket = parse_proc_net_line(lines[1]).unwrap();
+        assert_eq!(socket.local, "10.0.0.2:54321");
+        assert_eq!(socket.remote, "192.168.1.1:443");
+        assert_eq!(socket.state, Some(ConnectionState::Established));
+        assert_eq!(socket.uid, Some(1000));
+        assert_eq!(socket.inode, 34567);
+        assert_eq!((socket.tx_queue, socket.rx_queue), (1024, 16));
+        // Keepalive due in 25.91s
+        assert_eq!(
+            socket.timer.map(|timer| (timer.kind, timer.expires_ms)),
+            Some((crate::models::TimerKind::Keepalive, 25_910))
+        );
+        assert_eq!(parse_proc_net_line(lines[0]).unwrap().timer, None);
+
+        let err = parse_proc_net_line(lines[2]).unwrap_err();
+        assert!(matches!(
+            err,
+            NetworkMonitorError::ProcNetLine {
+                field: "columns",
+                ..
+            }
+        ));
+
+        let line = b"0: 0100007F:0277 0101A8C0 0A 00000000:00000000 00:00000000 00000000 0 0 1";
+        let err = parse_proc_net_line(line).unwrap_err();
+        let message = err.to_string();
+        assert!(message.contains("rem_address"), "{message}");
+        assert!(message.contains("0101A8C0 0A"), "{message}");
+    }
+
+    #[test]
+    fn test_proc_net_lines_without_trailing_newline() {
+        let data = b"header\nfirst\nsecond";
+        let lines: Vec<&[u8]> = proc_net_lines(data).collect();
+        assert_eq!(lines, vec![&b"first"[..], &b"second"[..]]);
+    }
+}
@@ -3,7 +3,6 @@ use crate::error::{NetworkMonitorError, Result};
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone)]
-#[allow(dead_code)]
 pub enum CircuitState {
     Closed,
     Open,
@@ -11,7 +10,6 @@ pub enum CircuitState {
 }
 
 #[derive(Debug)]
-#[allow(dead_code)]
 pub struct CircuitBreaker {
     failure_count: u32,
     failure_threshold: u32,
@@ -21,7 +19,6 @@ pub struct CircuitBreaker {
 }
 
 impl CircuitBreaker {
-    #[allow(dead_code)]
     pub fn new(failure_threshold: u32, timeout: Duration) -> Self {
         Self {
             failure_count: 0,
@@ -32,50 +29,51 @@ impl CircuitBreaker {
         }
     }
 
-    #[allow(dead_code)]
     pub fn call<F, T>(&mut self, f: F) -> Result<T>
     where
         F: FnOnce() -> Result<T>,
     {
-        match self.state {
-            CircuitState::Open => {
-                if let Some(last_failure) = self.last_failure {
-                    if last_failure.elapsed() > self.timeout {
-                        self.state = CircuitState::HalfOpen;
-                    } else {
-                        return Err(NetworkMonitorError::ParseError(
-                            "Circuit breaker is open".to_string(),
-                        ));
-                    }
-                }
-            }
-            CircuitState::HalfOpen => {
-                // Allow one request to test the waters
-            }
-            CircuitState::Closed => {
-                // Normal operation
-            }
+        if !self.allows_call() {
+            return Err(NetworkMonitorError::CircuitOpen);
         }
 
         match f() {
             Ok(result) => {
-                self.on_success();
+                self.record_success();
                 Ok(result)
             }
             Err(err) => {
-                self.on_failure();
+                self.record_failure();
                 Err(err)
             }
         }
     }
 
-    fn on_success(&mut self) {
+    /// Whether an attempt may go ahead; an open circuit lets one through
+    /// again once the timeout has passed. For callers that can't hold the
+    /// breaker while the attempt runs, paired with `record_success` or
+    /// `record_failure`.
+    pub fn allows_call(&mut self) -> bool {
+        match self.state {
+            CircuitState::Open => match self.last_failure {
+                Some(last_failure) if last_failure.elapsed() <= self.timeout => false,
+                _ => {
+                    self.state = CircuitState::HalfOpen;
+                    true
+                }
+            },
+            // Half open allows one request to test the waters
+            CircuitState::HalfOpen | CircuitState::Closed => true,
+        }
+    }
+
+    pub fn record_success(&mut self) {
         self.failure_count = 0;
         self.state = CircuitState::Closed;
         self.last_failure = None;
     }
 
-    fn on_failure(&mut self) {
+    pub fn record_failure(&mut self) {
         self.failure_count += 1;
         self.last_failure = Some(Instant::now());
 
@@ -84,7 +82,17 @@ impl CircuitBreaker {
         }
     }
 
-    #[allow(dead_code)]
+    /// Failures in a row; 1 on the first of an outage and the threshold
+    /// when the circuit just opened, so callers can log only those
+    pub fn failure_count(&self) -> u32 {
+        self.failure_count
+    }
+
+    /// How long an open circuit rejects calls
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
     pub fn is_open(&self) -> bool {
         matches!(self.state, CircuitState::Open)
     }
@@ -100,12 +108,10 @@ impl Default for CircuitBreaker {
 pub struct ErrorRecovery;
 
 /// Enhanced error recovery utilities with multiple strategies
-#[allow(dead_code)]
 pub struct EnhancedErrorRecovery;
 
 impl ErrorRecovery {
     /// Attempt to read a file with fallback to default value
-    #[allow(dead_code)]
     pub fn read_file_with_fallback(path: &str, fallback: &str) -> String {
         match std::fs::read_to_string(path) {
             Ok(content) => content,
@@ -114,7 +120,6 @@ impl ErrorRecovery {
     }
 
     /// Parse a line with fallback to default value
-    #[allow(dead_code)]
     pub fn parse_line_with_fallback<T>(
         line: &str,
         parser: impl Fn(&str) -> Result<T>,
@@ -134,95 +139,22 @@ impl ErrorRecovery {
         if let Ok(tcp) = get_tcp() {
             connections.extend(tcp);
         } else {
-            eprintln!("Warning: Failed to get TCP connections, continuing with UDP");
+            tracing::warn!("Failed to get TCP connections, continuing with UDP");
         }
 
         // Try UDP connections, continue on failure
         if let Ok(udp) = get_udp() {
             connections.extend(udp);
         } else {
-            eprintln!("Warning: Failed to get UDP connections");
+            tracing::warn!("Failed to get UDP connections");
         }
 
         connections
     }
-
-    /// Parse proc net line with error recovery
-    #[allow(dead_code)]
-    pub fn parse_proc_net_line_with_recovery(
-        line: &str,
-        protocol: &str,
-        default_state: &str,
-    ) -> Option<crate::models::Connection> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 10 {
-            return None;
-        }
-
-        // Parse with fallbacks for each component
-        let local_addr = Self::parse_socket_addr_with_fallback(parts[1], "0.0.0.0:0");
-        let remote_addr = Self::parse_socket_addr_with_fallback(parts[2], "0.0.0.0:0");
-
-        let state = if parts.len() > 3 {
-            crate::utils::parse_tcp_state(parts[3])
-        } else {
-            default_state.to_string()
-        };
-
-        let inode = parts[9].parse::<u64>().unwrap_or(0);
-
-        // Get process info with fallback
-        let (program, pid, command) = if inode > 0 {
-            // In a real implementation, this would use the process cache
-            // For now, provide fallback values
-            ("Unknown".to_string(), "N/A".to_string(), "N/A".to_string())
-        } else {
-            ("N/A".to_string(), "N/A".to_string(), "N/A".to_string())
-        };
-
-        Some(crate::models::Connection::new(
-            protocol.to_string(),
-            state,
-            local_addr,
-            remote_addr,
-            program,
-            pid,
-            command,
-        ))
-    }
-
-    /// Parse socket address with fallback
-    #[allow(dead_code)]
-    fn parse_socket_addr_with_fallback(addr_str: &str, fallback: &str) -> String {
-        match crate::utils::split_socket_addr(addr_str) {
-            Ok((ip_hex, port_hex)) => {
-                let port = crate::utils::parse_port(port_hex).unwrap_or(0);
-
-                let ip = if ip_hex.len() == 8 {
-                    crate::utils::parse_ipv4_hex(ip_hex)
-                        .map(std::net::IpAddr::V4)
-                        .ok()
-                } else if ip_hex.len() == 32 {
-                    crate::utils::parse_ipv6_hex(ip_hex)
-                        .map(std::net::IpAddr::V6)
-                        .ok()
-                } else {
-                    None
-                };
-
-                match ip {
-                    Some(ip) => format!("{ip}:{port}"),
-                    None => fallback.to_string(),
-                }
-            }
-            Err(_) => fallback.to_string(),
-        }
-    }
 }
 
 impl EnhancedErrorRecovery {
     /// Retry operation with exponential backoff
-    #[allow(dead_code)]
     pub fn retry_with_backoff<T, F>(
         mut operation: F,
         max_retries: usize,
@@ -241,7 +173,7 @@ impl EnhancedErrorRecovery {
                         return Err(e);
                     }
 
-                    eprintln!(
+                    tracing::debug!(
                         "Attempt {} failed: {}, retrying in {:?}",
                         attempt + 1,
                         e,
@@ -258,7 +190,6 @@ impl EnhancedErrorRecovery {
     }
 
     /// Execute multiple operations, returning first successful result
-    #[allow(dead_code)]
     pub fn try_operations<T, F>(operations: Vec<F>) -> Result<T>
     where
         F: FnOnce() -> Result<T>,
@@ -271,7 +202,7 @@ impl EnhancedErrorRecovery {
                 Err(e) => {
                     let error_msg = format!("{}", e);
                     last_error = Some(e);
-                    eprintln!("Operation failed: {}", error_msg);
+                    tracing::warn!("Operation failed: {}", error_msg);
                 }
             }
         }
@@ -282,22 +213,22 @@ impl EnhancedErrorRecovery {
     }
 
     /// Graceful degradation for critical operations
-    #[allow(dead_code)]
     pub fn graceful_degradation<T>(
         primary: impl FnOnce() -> Result<T>,
         fallback: impl FnOnce() -> T,
     ) -> T {
         match primary() {
             Ok(result) => result,
+            // Already reported when the circuit opened
+            Err(NetworkMonitorError::CircuitOpen) => fallback(),
             Err(e) => {
-                eprintln!("Critical operation failed, using degraded mode: {}", e);
+                tracing::error!("Critical operation failed, using degraded mode: {}", e);
                 fallback()
             }
         }
     }
 
     /// Circuit breaker wrapper for unreliable operations
-    #[allow(dead_code)]
     pub fn with_circuit_breaker<T>(
         circuit_breaker: &mut CircuitBreaker,
         operation: impl FnOnce() -> Result<T>,
@@ -306,7 +237,6 @@ impl EnhancedErrorRecovery {
     }
 
     /// Timeout wrapper for operations that might hang
-    #[allow(dead_code)]
     pub fn with_timeout<T, F>(operation: F, _timeout: Duration) -> Result<T>
     where
         F: FnOnce() -> Result<T> + Send + 'static,
@@ -323,7 +253,6 @@ impl EnhancedErrorRecovery {
     }
 
     /// Batch operation with partial failure handling
-    #[allow(dead_code)]
     pub fn batch_with_partial_failure<T, F>(
         items: Vec<T>,
         operation: F,
@@ -341,7 +270,7 @@ impl EnhancedErrorRecovery {
                 Err(e) => {
                     let error_msg = format!("{}", e);
                     failed.push(e);
-                    eprintln!("Item failed: {}", error_msg);
+                    tracing::warn!("Item failed: {}", error_msg);
                 }
             }
         }
@@ -372,7 +301,11 @@ mod tests {
         assert!(cb.is_open());
 
         // Should fail when circuit is open
-        assert!(cb.call(|| Ok(42)).is_err());
+        assert!(matches!(
+            cb.call(|| Ok(42)),
+            Err(NetworkMonitorError::CircuitOpen)
+        ));
+        assert_eq!(cb.failure_count(), 2);
 
         // Wait for timeout
         std::thread::sleep(Duration::from_millis(150));
@@ -0,0 +1,58 @@
+/// Row window arithmetic shared by the GTK and TUI tables, so only rows near
+/// the visible area are turned into widgets
+use std::ops::Range;
+
+/// Rows to build for a viewport showing `viewport_rows` rows from `first_row`,
+/// padded by `overscan` rows on both sides and clamped to `len`
+pub fn visible_range(
+    first_row: usize,
+    viewport_rows: usize,
+    overscan: usize,
+    len: usize,
+) -> Range<usize> {
+    let wanted = viewport_rows + 2 * overscan;
+    let start = first_row
+        .saturating_sub(overscan)
+        .min(len.saturating_sub(wanted));
+    start..(start + wanted).min(len)
+}
+
+/// Scroll offset that keeps `selected` on screen, moving as little as possible
+pub fn scroll_offset(
+    offset: usize,
+    selected: Option<usize>,
+    viewport_rows: usize,
+    len: usize,
+) -> usize {
+    let mut offset = offset.min(len.saturating_sub(viewport_rows));
+    if let Some(selected) = selected {
+        if selected < offset {
+            offset = selected;
+        } else if viewport_rows > 0 && selected >= offset + viewport_rows {
+            offset = selected + 1 - viewport_rows;
+        }
+    }
+    offset
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_visible_range_pads_and_clamps() {
+        assert_eq!(visible_range(50, 20, 5, 1000), 45..75);
+        assert_eq!(visible_range(2, 20, 5, 1000), 0..30);
+        assert_eq!(visible_range(995, 20, 5, 1000), 970..1000);
+        assert_eq!(visible_range(0, 20, 5, 12), 0..12);
+    }
+
+    #[test]
+    fn test_scroll_offset_follows_selection() {
+        assert_eq!(scroll_offset(0, Some(5), 10, 100), 0);
+        assert_eq!(scroll_offset(0, Some(15), 10, 100), 6);
+        assert_eq!(scroll_offset(20, Some(3), 10, 100), 3);
+        assert_eq!(scroll_offset(95, None, 10, 100), 90);
+        assert_eq!(scroll_offset(4, None, 10, 3), 0);
+    }
+}
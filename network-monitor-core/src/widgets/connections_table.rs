@@ -0,0 +1,608 @@
+use crate::models::{Connection, Direction, Protocol, Scope};
+use crate::utils::formatter::Units;
+use crate::utils::heatmap::{RatePeaks, HEAT_LEVELS};
+use crate::utils::viewport::{scroll_offset, visible_range};
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Span;
+use ratatui::widgets::{Block, Borders, Row, StatefulWidget, Table, TableState};
+use std::time::{Duration, Instant};
+
+/// Header of each column, by column index
+const TITLES: [&str; 14] = [
+    "Process(ID)",
+    "Protocol",
+    "Source",
+    "Destination",
+    "Status",
+    "TX",
+    "RX",
+    "Path",
+    "SendQ",
+    "RecvQ",
+    "Dir",
+    "Timer",
+    "Scope",
+    "Host",
+];
+
+/// Stable minimum width of each column
+const WIDTHS: [usize; 14] = [15, 13, 18, 22, 12, 10, 12, 40, 8, 8, 5, 16, 5, 10];
+
+/// TX and RX colors by heat level, an xterm-256 ramp from pale yellow to red
+const HEAT_COLORS: [Color; HEAT_LEVELS] = [
+    Color::Indexed(223),
+    Color::Indexed(221),
+    Color::Indexed(214),
+    Color::Indexed(208),
+    Color::Indexed(196),
+];
+
+/// How long a column layout is reused while the area keeps its size
+const LAYOUT_TTL: Duration = Duration::from_millis(500);
+
+/// Columns that fit the last rendered width, so borderline widths don't
+/// make them flicker from frame to frame
+#[derive(Debug, Clone)]
+struct ColumnLayout {
+    width: u16,
+    first_column: usize,
+    row_count: usize,
+    visible_columns: Vec<usize>,
+    calculated: Instant,
+}
+
+impl ColumnLayout {
+    fn is_valid(&self, width: u16, first_column: usize, row_count: usize) -> bool {
+        self.width == width
+            && self.first_column == first_column
+            && self.calculated.elapsed() < LAYOUT_TTL
+            && (row_count == 0 || self.row_count == row_count)
+    }
+}
+
+/// Selection, sorting and scrolling of a [`ConnectionsTable`], kept between frames
+#[derive(Debug, Clone, Default)]
+pub struct ConnectionsTableState {
+    /// Selected row and first row shown, as indices into all connections
+    pub rows: TableState,
+    /// Column the connections are sorted by; its header gets an arrow
+    pub sort_column: usize,
+    pub sort_ascending: bool,
+    /// Position of the leftmost shown column among the table's columns
+    pub horizontal_scroll: usize,
+    layout: Option<ColumnLayout>,
+}
+
+impl ConnectionsTableState {
+    pub fn new(sort_column: usize, sort_ascending: bool) -> Self {
+        Self {
+            sort_column,
+            sort_ascending,
+            ..Self::default()
+        }
+    }
+
+    /// Sort by `column`, flipping the direction when it already is
+    pub fn toggle_sort(&mut self, column: usize) {
+        if self.sort_column == column {
+            self.sort_ascending = !self.sort_ascending;
+        } else {
+            self.sort_column = column;
+            self.sort_ascending = true;
+        }
+    }
+
+    /// Scroll 5 columns at a time for faster navigation
+    pub fn scroll_left(&mut self) {
+        self.horizontal_scroll = self.horizontal_scroll.saturating_sub(5);
+    }
+
+    /// Scroll 5 columns right without passing the last of `column_count`
+    pub fn scroll_right(&mut self, column_count: usize) {
+        self.horizontal_scroll = (self.horizontal_scroll + 5).min(column_count.saturating_sub(1));
+    }
+}
+
+/// The connection table of `nmt` as a ratatui widget: one row per
+/// connection, colored by protocol, with only the rows and columns that fit
+/// the area built. TX and RX rates are shaded from pale yellow to red by how
+/// close they come to the busiest of the table.
+///
+/// ```
+/// use network_monitor_core::widgets::{ConnectionsTable, ConnectionsTableState};
+/// use ratatui::{buffer::Buffer, layout::Rect, widgets::StatefulWidget};
+///
+/// let mut state = ConnectionsTableState::default();
+/// let area = Rect::new(0, 0, 100, 10);
+/// let mut buffer = Buffer::empty(area);
+/// ConnectionsTable::new(&[]).render(area, &mut buffer, &mut state);
+/// ```
+pub struct ConnectionsTable<'a> {
+    connections: &'a [Connection],
+    columns: &'a [usize],
+    units: Units,
+    title: &'a str,
+    remote_label: Option<&'a dyn Fn(&Connection) -> String>,
+    flagged: Option<&'a dyn Fn(&Connection) -> bool>,
+    dimmed: Option<&'a dyn Fn(&Connection) -> bool>,
+}
+
+impl<'a> ConnectionsTable<'a> {
+    /// Table over `connections`, shown in the order given
+    pub fn new(connections: &'a [Connection]) -> Self {
+        Self {
+            connections,
+            columns: &[0, 1, 2, 3, 4, 5, 6, 7],
+            units: Units::default(),
+            title: "Network Connections",
+            remote_label: None,
+            flagged: None,
+            dimmed: None,
+        }
+    }
+
+    /// Column indices to show, in display order: program, protocol, local,
+    /// remote, state, TX, RX, command line, send queue and receive queue.
+    /// The queues are left out unless asked for.
+    pub fn columns(mut self, columns: &'a [usize]) -> Self {
+        self.columns = columns;
+        self
+    }
+
+    /// Units for the TX and RX rates
+    pub fn units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn title(mut self, title: &'a str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Text of the destination column, e.g. a resolved hostname; the remote
+    /// address as is without one
+    pub fn remote_label(mut self, label: &'a dyn Fn(&Connection) -> String) -> Self {
+        self.remote_label = Some(label);
+        self
+    }
+
+    /// Connections to draw in red, e.g. those with a banned remote address
+    pub fn flagged(mut self, flagged: &'a dyn Fn(&Connection) -> bool) -> Self {
+        self.flagged = Some(flagged);
+        self
+    }
+
+    /// Connections to draw in gray, e.g. those idle for a while; short-lived
+    /// connections always are
+    pub fn dimmed(mut self, dimmed: &'a dyn Fn(&Connection) -> bool) -> Self {
+        self.dimmed = Some(dimmed);
+        self
+    }
+
+    /// Columns from `first_column` on that fit `available` cells
+    fn fit_columns(&self, first_column: usize, available: usize) -> Vec<usize> {
+        let mut visible_columns = Vec::new();
+        let mut used = 0;
+        for &column in self.columns.iter().skip(first_column) {
+            // Small buffer to prevent flickering when the width is borderline
+            let required = column_width(column) + 2;
+            if used + required > available && !visible_columns.is_empty() {
+                break;
+            }
+            visible_columns.push(column);
+            used += required;
+        }
+        visible_columns
+    }
+
+    fn header(&self, visible_columns: &[usize], state: &ConnectionsTableState) -> Row<'a> {
+        let cells = visible_columns.iter().map(|&column| {
+            let title = TITLES.get(column).copied().unwrap_or("");
+            if column == state.sort_column {
+                let arrow = if state.sort_ascending { "↑" } else { "↓" };
+                Span::styled(
+                    format!("{title} {arrow}"),
+                    Style::default()
+                        .fg(Color::Cyan)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                Span::styled(title, Style::default().fg(Color::Gray))
+            }
+        });
+        Row::new(cells)
+            .style(Style::default().add_modifier(Modifier::REVERSED))
+            .height(1)
+    }
+
+    fn row(
+        &self,
+        conn: &Connection,
+        selected: bool,
+        visible_columns: &[usize],
+        last_width: usize,
+        peaks: RatePeaks,
+    ) -> Row<'a> {
+        let flagged = self.flagged.is_some_and(|flagged| flagged(conn));
+        let dimmed = conn.short_lived || self.dimmed.is_some_and(|dimmed| dimmed(conn));
+        let color = if flagged {
+            Color::Red
+        } else if dimmed {
+            Color::DarkGray
+        } else {
+            match conn.protocol {
+                Protocol::Tcp | Protocol::Tcp6 => Color::Green,
+                Protocol::Udp | Protocol::Udp6 => Color::Yellow,
+                Protocol::Sctp | Protocol::Sctp6 | Protocol::Dccp | Protocol::Dccp6 => Color::Cyan,
+            }
+        };
+        let style = if selected {
+            Style::default()
+                .fg(color)
+                .add_modifier(Modifier::BOLD)
+                .bg(Color::DarkGray)
+        } else if conn.is_active() {
+            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        } else if conn.is_permission_denied() {
+            Style::default().fg(color).add_modifier(Modifier::DIM)
+        } else {
+            Style::default().fg(color)
+        };
+
+        let cells = visible_columns.iter().enumerate().map(|(i, &column)| {
+            let text = self.cell(conn, column);
+            // The last column gets all the remaining space instead of truncating
+            let text = if i + 1 == visible_columns.len() {
+                truncate(text, last_width.max(100))
+            } else {
+                truncate(text, column_width(column))
+            };
+            // Flagged and dimmed rows keep their color throughout
+            let heat = match column {
+                5 if !flagged && !dimmed => peaks.tx_heat(conn),
+                6 if !flagged && !dimmed => peaks.rx_heat(conn),
+                _ => 0,
+            };
+            match heat {
+                0 => Span::raw(text),
+                level => Span::styled(text, Style::default().fg(HEAT_COLORS[level - 1])),
+            }
+        });
+        Row::new(cells).style(style)
+    }
+
+    fn cell(&self, conn: &Connection, column: usize) -> String {
+        match column {
+            0 if conn.bandwidth_limit.is_some() => {
+                format!("{} [Limited]", conn.get_process_display())
+            }
+            0 => conn.get_process_display(),
+            1 => conn.protocol_label(),
+            2 => conn.local.to_string(),
+            3 => match self.remote_label {
+                Some(label) => label(conn),
+                None => conn.remote.to_string(),
+            },
+            4 => conn.state.to_string(),
+            5 => self.units.format_rate(conn.tx_rate),
+            6 => self.units.format_rate(conn.rx_rate),
+            7 => conn.command.to_string(),
+            8 => conn.send_queue.to_string(),
+            9 => conn.recv_queue.to_string(),
+            10 => conn.direction.map_or("", Direction::label).to_string(),
+            11 => conn.timer.map(|timer| timer.label()).unwrap_or_default(),
+            12 => conn.scope.map_or("", Scope::label).to_string(),
+            13 => conn.host.as_deref().unwrap_or_default().to_string(),
+            _ => String::new(),
+        }
+    }
+}
+
+impl StatefulWidget for ConnectionsTable<'_> {
+    type State = ConnectionsTableState;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        let total_columns = self.columns.len();
+        let first_column = state.horizontal_scroll.min(total_columns.saturating_sub(1));
+        let available = area.width.saturating_sub(2) as usize; // Borders
+
+        let visible_columns = match &state.layout {
+            Some(layout) if layout.is_valid(area.width, first_column, self.connections.len()) => {
+                layout.visible_columns.clone()
+            }
+            _ => {
+                let visible_columns = self.fit_columns(first_column, available);
+                state.layout = Some(ColumnLayout {
+                    width: area.width,
+                    first_column,
+                    row_count: self.connections.len(),
+                    visible_columns: visible_columns.clone(),
+                    calculated: Instant::now(),
+                });
+                visible_columns
+            }
+        };
+
+        if visible_columns.is_empty() {
+            let table = Table::new(Vec::<Row>::new(), [Constraint::Min(10)])
+                .header(Row::new([Span::raw("No space")]))
+                .block(Block::default().borders(Borders::ALL).title(self.title));
+            StatefulWidget::render(table, area, buf, &mut TableState::default());
+            return;
+        }
+
+        let used: usize = visible_columns
+            .iter()
+            .map(|&column| column_width(column) + 2)
+            .sum();
+        let remaining_width = available.saturating_sub(used);
+
+        // Fixed widths for stability; the last column takes what is left
+        let constraints: Vec<_> = visible_columns
+            .iter()
+            .enumerate()
+            .map(|(i, &column)| {
+                if i + 1 == visible_columns.len() && remaining_width > 0 {
+                    Constraint::Min(remaining_width as u16)
+                } else {
+                    Constraint::Length(column_width(column) as u16)
+                }
+            })
+            .collect();
+
+        // Only build rows that fit in the table: borders and header take 3 lines
+        let viewport_rows = area.height.saturating_sub(3) as usize;
+        let selected = state.rows.selected();
+        let offset = scroll_offset(
+            state.rows.offset(),
+            selected,
+            viewport_rows,
+            self.connections.len(),
+        );
+        let window = visible_range(offset, viewport_rows, 0, self.connections.len());
+        let peaks = RatePeaks::of(self.connections);
+
+        let rows: Vec<_> = self.connections[window.clone()]
+            .iter()
+            .zip(window.clone())
+            .map(|(conn, i)| {
+                self.row(
+                    conn,
+                    selected == Some(i),
+                    &visible_columns,
+                    remaining_width,
+                    peaks,
+                )
+            })
+            .collect();
+
+        let table = Table::new(rows, constraints)
+            .header(self.header(&visible_columns, state))
+            .block(Block::default().borders(Borders::ALL).title(format!(
+                "{} (scroll: ← → | col {}/{})",
+                self.title,
+                first_column + 1,
+                total_columns
+            )))
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+        // Rows were built for the window only, so render with a window-relative selection
+        let mut window_state = TableState::default().with_selected(
+            selected
+                .filter(|&i| window.contains(&i))
+                .map(|i| i - window.start),
+        );
+        StatefulWidget::render(table, area, buf, &mut window_state);
+        *state.rows.offset_mut() = offset;
+    }
+}
+
+fn column_width(column: usize) -> usize {
+    WIDTHS.get(column).copied().unwrap_or(10)
+}
+
+/// Cut `text` to `width` characters, marking the cut with "..."
+fn truncate(text: String, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text;
+    }
+    let kept: String = text.chars().take(width.saturating_sub(3)).collect();
+    format!("{kept}...")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, SocketTimer, TimerKind};
+
+    fn connection(program: &str, remote: &str) -> Connection {
+        Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "192.168.1.10:51000".to_string(),
+            remote.to_string(),
+            program.to_string(),
+            "42".to_string(),
+            format!("/usr/bin/{program}"),
+        )
+    }
+
+    fn render(table: ConnectionsTable, state: &mut ConnectionsTableState, area: Rect) -> String {
+        let mut buffer = Buffer::empty(area);
+        table.render(area, &mut buffer, state);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_renders_rows_and_sort_arrow() {
+        let connections = [
+            connection("firefox", "93.184.216.34:443"),
+            connection("curl", "1.1.1.1:443"),
+        ];
+        let mut state = ConnectionsTableState::new(0, true);
+        let text = render(
+            ConnectionsTable::new(&connections).columns(&[0, 3]),
+            &mut state,
+            Rect::new(0, 0, 80, 6),
+        );
+        assert!(text.contains("Process(ID) ↑"), "{text}");
+        assert!(text.contains("firefox"), "{text}");
+        assert!(text.contains("1.1.1.1:443"), "{text}");
+        assert!(text.contains("col 1/2"), "{text}");
+    }
+
+    #[test]
+    fn test_remote_label_and_scrolling() {
+        let connections: Vec<_> = (0..20)
+            .map(|i| connection(&format!("app{i}"), "10.0.0.1:22"))
+            .collect();
+        let label = |_: &Connection| "gateway.lan".to_string();
+        let mut state = ConnectionsTableState::default();
+        state.rows.select(Some(15));
+        let text = render(
+            ConnectionsTable::new(&connections)
+                .columns(&[0, 3])
+                .remote_label(&label),
+            &mut state,
+            Rect::new(0, 0, 80, 8),
+        );
+        // Five rows fit, so the selection scrolls the window to rows 11-15
+        assert_eq!(state.rows.offset(), 11);
+        assert!(text.contains("app15") && !text.contains("app10 "), "{text}");
+        assert!(text.contains("gateway.lan"), "{text}");
+    }
+
+    #[test]
+    fn test_queue_columns() {
+        let mut stalled = connection("rsync", "10.0.0.5:873");
+        stalled.send_queue = 1448;
+        stalled.recv_queue = 26;
+        let text = render(
+            ConnectionsTable::new(&[stalled]).columns(&[0, 8, 9]),
+            &mut ConnectionsTableState::default(),
+            Rect::new(0, 0, 80, 5),
+        );
+        assert!(text.contains("SendQ"), "{text}");
+        assert!(text.contains("RecvQ"), "{text}");
+        assert!(text.contains("1448"), "{text}");
+        assert!(text.contains("26"), "{text}");
+    }
+
+    #[test]
+    fn test_direction_column() {
+        let mut ssh = connection("sshd", "10.0.0.5:50514");
+        ssh.direction = Some(Direction::Inbound);
+        let text = render(
+            ConnectionsTable::new(&[ssh]).columns(&[0, 10]),
+            &mut ConnectionsTableState::default(),
+            Rect::new(0, 0, 80, 5),
+        );
+        assert!(text.contains("Dir"), "{text}");
+        assert!(text.contains("← in"), "{text}");
+    }
+
+    #[test]
+    fn test_timer_column() {
+        let mut hung = connection("rsync", "10.0.0.5:873");
+        hung.timer = Some(SocketTimer {
+            kind: TimerKind::Retransmit,
+            expires_ms: 12_800,
+            retries: 6,
+        });
+        let text = render(
+            ConnectionsTable::new(&[hung]).columns(&[0, 11]),
+            &mut ConnectionsTableState::default(),
+            Rect::new(0, 0, 80, 5),
+        );
+        assert!(text.contains("Timer"), "{text}");
+        assert!(text.contains("retransmit 13s ×6"), "{text}");
+    }
+
+    #[test]
+    fn test_dimmed_rows_are_gray_unless_flagged() {
+        let connections = [
+            connection("sshd", "10.0.0.1:22"),
+            connection("curl", "1.1.1.1:443"),
+        ];
+        let dimmed = |_: &Connection| true;
+        let flagged = |conn: &Connection| &*conn.program == "curl";
+        let area = Rect::new(0, 0, 80, 6);
+        let mut buffer = Buffer::empty(area);
+        ConnectionsTable::new(&connections)
+            .columns(&[0, 3])
+            .dimmed(&dimmed)
+            .flagged(&flagged)
+            .render(area, &mut buffer, &mut ConnectionsTableState::default());
+        // Rows start below the border and the header
+        assert_eq!(buffer[(1, 2)].fg, Color::DarkGray);
+        assert_eq!(buffer[(1, 3)].fg, Color::Red);
+    }
+
+    #[test]
+    fn test_short_lived_rows_are_gray() {
+        let mut short_lived = connection("curl", "1.1.1.1:443");
+        short_lived.short_lived = true;
+        let connections = [connection("sshd", "10.0.0.1:22"), short_lived];
+        let area = Rect::new(0, 0, 80, 6);
+        let mut buffer = Buffer::empty(area);
+        ConnectionsTable::new(&connections).columns(&[0, 3]).render(
+            area,
+            &mut buffer,
+            &mut ConnectionsTableState::default(),
+        );
+        assert_ne!(buffer[(1, 2)].fg, Color::DarkGray);
+        assert_eq!(buffer[(1, 3)].fg, Color::DarkGray);
+    }
+
+    #[test]
+    fn test_rates_are_shaded_by_heat() {
+        let mut busy = connection("rsync", "10.0.0.5:873");
+        busy.rx_rate = 1_000_000;
+        let mut light = connection("curl", "1.1.1.1:443");
+        light.rx_rate = 1_000;
+        let connections = [busy, light, connection("sshd", "10.0.0.1:22")];
+        let area = Rect::new(0, 0, 80, 7);
+        let mut buffer = Buffer::empty(area);
+        ConnectionsTable::new(&connections).columns(&[6, 0]).render(
+            area,
+            &mut buffer,
+            &mut ConnectionsTableState::default(),
+        );
+        assert_eq!(buffer[(1, 2)].fg, HEAT_COLORS[HEAT_LEVELS - 1]);
+        assert_eq!(buffer[(1, 3)].fg, HEAT_COLORS[0]);
+        // Idle rates and other columns keep the row color
+        assert_eq!(buffer[(1, 4)].fg, Color::Green);
+        assert_eq!(buffer[(20, 2)].fg, Color::Green);
+    }
+
+    #[test]
+    fn test_state_scrolls_and_toggles_sort() {
+        let mut state = ConnectionsTableState::new(5, true);
+        state.toggle_sort(5);
+        assert!(!state.sort_ascending);
+        state.toggle_sort(2);
+        assert_eq!((state.sort_column, state.sort_ascending), (2, true));
+
+        state.scroll_right(8);
+        state.scroll_right(8);
+        assert_eq!(state.horizontal_scroll, 7);
+        state.scroll_left();
+        assert_eq!(state.horizontal_scroll, 2);
+    }
+
+    #[test]
+    fn test_truncate_counts_characters() {
+        assert_eq!(truncate("héllo wörld".to_string(), 8), "héllo...");
+        assert_eq!(truncate("short".to_string(), 8), "short");
+    }
+}
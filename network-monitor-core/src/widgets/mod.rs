@@ -0,0 +1,7 @@
+//! Ratatui widgets for embedding the monitor's views in other terminal
+//! dashboards, behind the `ratatui` feature
+pub mod connections_table;
+pub mod top_talkers;
+
+pub use connections_table::{ConnectionsTable, ConnectionsTableState};
+pub use top_talkers::TopTalkersPanel;
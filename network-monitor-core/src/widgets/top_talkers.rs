@@ -0,0 +1,182 @@
+use crate::models::{Talker, TopTalkers};
+use crate::utils::formatter::Units;
+use ratatui::buffer::Buffer;
+use ratatui::layout::{Constraint, Direction, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, Widget};
+
+/// Busiest processes and remote hosts side by side, one per line with their
+/// upload and download rates, and applications next to them when cgroup
+/// accounting counts them. Rows beyond the area's height are left out.
+///
+/// ```
+/// use network_monitor_core::models::TopTalkers;
+/// use network_monitor_core::widgets::TopTalkersPanel;
+/// use ratatui::{buffer::Buffer, layout::Rect, widgets::Widget};
+///
+/// let area = Rect::new(0, 0, 80, 7);
+/// let mut buffer = Buffer::empty(area);
+/// TopTalkersPanel::new(&TopTalkers::default()).render(area, &mut buffer);
+/// ```
+pub struct TopTalkersPanel<'a> {
+    talkers: &'a TopTalkers,
+    units: Units,
+    title: &'a str,
+    host_label: Option<&'a dyn Fn(&str) -> String>,
+}
+
+impl<'a> TopTalkersPanel<'a> {
+    pub fn new(talkers: &'a TopTalkers) -> Self {
+        Self {
+            talkers,
+            units: Units::default(),
+            title: "Top Talkers",
+            host_label: None,
+        }
+    }
+
+    /// Units for the rates
+    pub fn units(mut self, units: Units) -> Self {
+        self.units = units;
+        self
+    }
+
+    pub fn title(mut self, title: &'a str) -> Self {
+        self.title = title;
+        self
+    }
+
+    /// Text shown for a host address, e.g. its resolved hostname
+    pub fn host_label(mut self, label: &'a dyn Fn(&str) -> String) -> Self {
+        self.host_label = Some(label);
+        self
+    }
+
+    fn column(&self, heading: &'static str, talkers: &[Talker], hosts: bool) -> Paragraph<'a> {
+        let mut lines = vec![Line::from(Span::styled(
+            heading,
+            Style::default()
+                .fg(Color::Gray)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        if talkers.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "no traffic",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for talker in talkers {
+            let name = match self.host_label {
+                Some(label) if hosts => label(&talker.name),
+                _ => talker.name.clone(),
+            };
+            lines.push(Line::from(vec![
+                Span::styled(
+                    format!("↑{:>10} ", self.units.format_rate(talker.tx_rate)),
+                    Style::default().fg(Color::Red),
+                ),
+                Span::styled(
+                    format!("↓{:>10} ", self.units.format_rate(talker.rx_rate)),
+                    Style::default().fg(Color::Cyan),
+                ),
+                Span::raw(name),
+            ]));
+        }
+        Paragraph::new(lines)
+    }
+}
+
+impl Widget for TopTalkersPanel<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let block = Block::default().borders(Borders::ALL).title(self.title);
+        let inner = block.inner(area);
+        block.render(area, buf);
+
+        let applications = !self.talkers.applications.is_empty();
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints(if applications {
+                vec![Constraint::Ratio(1, 3); 3]
+            } else {
+                vec![Constraint::Percentage(50); 2]
+            })
+            .split(inner);
+        self.column("Processes", &self.talkers.processes, false)
+            .render(columns[0], buf);
+        self.column("Hosts", &self.talkers.hosts, true)
+            .render(columns[1], buf);
+        if applications {
+            self.column("Applications", &self.talkers.applications, false)
+                .render(columns[2], buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(panel: TopTalkersPanel, area: Rect) -> String {
+        let mut buffer = Buffer::empty(area);
+        panel.render(area, &mut buffer);
+        (0..area.height)
+            .map(|y| {
+                (0..area.width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_renders_processes_and_labelled_hosts() {
+        let talkers = TopTalkers {
+            processes: vec![Talker {
+                name: "firefox(100)".to_string(),
+                tx_rate: 2048,
+                rx_rate: 0,
+            }],
+            hosts: vec![Talker {
+                name: "93.184.216.34".to_string(),
+                tx_rate: 0,
+                rx_rate: 2048,
+            }],
+            applications: Vec::new(),
+        };
+        let label = |host: &str| format!("example.com ({host})");
+        let text = render(
+            TopTalkersPanel::new(&talkers).host_label(&label),
+            Rect::new(0, 0, 120, 5),
+        );
+        assert!(text.contains("Top Talkers"), "{text}");
+        assert!(text.contains("firefox(100)"), "{text}");
+        assert!(text.contains("example.com (93.184.216.34)"), "{text}");
+        assert!(text.contains("2.0KB/s"), "{text}");
+    }
+
+    #[test]
+    fn test_empty_columns_say_so() {
+        let text = render(
+            TopTalkersPanel::new(&TopTalkers::default()),
+            Rect::new(0, 0, 60, 4),
+        );
+        assert_eq!(text.matches("no traffic").count(), 2, "{text}");
+    }
+
+    #[test]
+    fn test_applications_get_a_column_of_their_own() {
+        let talkers = TopTalkers {
+            applications: vec![Talker {
+                name: "org.mozilla.firefox".to_string(),
+                tx_rate: 0,
+                rx_rate: 4096,
+            }],
+            ..TopTalkers::default()
+        };
+        let text = render(TopTalkersPanel::new(&talkers), Rect::new(0, 0, 150, 4));
+        assert!(text.contains("Applications"), "{text}");
+        assert!(text.contains("org.mozilla.firefox"), "{text}");
+    }
+}
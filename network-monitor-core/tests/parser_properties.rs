@@ -0,0 +1,116 @@
+//! Generated /proc/net input, well-formed and garbage, for the address and
+//! table parsers: valid values round-trip, anything else is an error or a
+//! skipped row, never a panic.
+
+use network_monitor_core::services::NetworkService;
+use network_monitor_core::utils::{parse_ipv4_hex, parse_ipv6_hex, split_socket_addr};
+use proptest::prelude::*;
+use std::fs;
+use std::net::{Ipv4Addr, Ipv6Addr};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+static NEXT_ROOT: AtomicUsize = AtomicUsize::new(0);
+
+/// IPv4 as the kernel prints it: one little-endian word
+fn ipv4_hex(ip: Ipv4Addr) -> String {
+    format!("{:08X}", u32::from_le_bytes(ip.octets()))
+}
+
+/// IPv6 as the kernel prints it: four little-endian words
+fn ipv6_hex(ip: Ipv6Addr) -> String {
+    ip.octets()
+        .chunks(4)
+        .map(|word| format!("{:08X}", u32::from_le_bytes(word.try_into().unwrap())))
+        .collect()
+}
+
+/// A socket address column, valid or not
+fn address_column() -> impl Strategy<Value = String> {
+    prop_oneof![
+        (any::<Ipv4Addr>(), any::<u16>())
+            .prop_map(|(ip, port)| format!("{}:{port:04X}", ipv4_hex(ip))),
+        (any::<Ipv6Addr>(), any::<u16>())
+            .prop_map(|(ip, port)| format!("{}:{port:04X}", ipv6_hex(ip))),
+        "[0-9A-Fa-f]{0,34}:[0-9A-Fa-f]{0,6}",
+        "[^ \n]{1,40}",
+    ]
+}
+
+/// A /proc/net row: the leading columns from the generators, the rest as the
+/// kernel writes them, sometimes truncated or with a stray token
+fn table_row() -> impl Strategy<Value = String> {
+    (
+        address_column(),
+        address_column(),
+        prop_oneof!["[0-9A-F]{2}", "[^ \n]{0,4}"],
+        prop_oneof!["[0-9]{1,5}", "[^ \n]{1,6}"],
+        prop_oneof!["[0-9]{1,7}", "[^ \n]{1,8}"],
+        0..12usize,
+    )
+        .prop_map(|(local, remote, state, uid, inode, keep)| {
+            let fields = [
+                "0:".to_string(),
+                local,
+                remote,
+                state,
+                "00000000:00000000".to_string(),
+                "00:00000000".to_string(),
+                "00000000".to_string(),
+                uid,
+                "0".to_string(),
+                inode,
+                "1 0000000000000000 100 0 0 10 0".to_string(),
+            ];
+            fields[..keep].join(" ")
+        })
+}
+
+proptest! {
+    #[test]
+    fn ipv4_round_trips(ip in any::<Ipv4Addr>()) {
+        prop_assert_eq!(parse_ipv4_hex(&ipv4_hex(ip)).unwrap(), ip);
+    }
+
+    #[test]
+    fn ipv6_round_trips(ip in any::<Ipv6Addr>()) {
+        prop_assert_eq!(parse_ipv6_hex(&ipv6_hex(ip)).unwrap(), ip);
+    }
+
+    #[test]
+    fn address_parsers_never_panic(input in "\\PC{0,40}") {
+        let _ = parse_ipv4_hex(&input);
+        let _ = parse_ipv6_hex(&input);
+        if let Ok((ip, port)) = split_socket_addr(&input) {
+            prop_assert!(!port.contains(':'));
+            prop_assert_eq!(format!("{ip}:{port}"), input);
+        }
+    }
+
+    #[test]
+    fn hex_of_wrong_length_is_an_error(input in "[0-9A-F]{0,40}") {
+        prop_assume!(input.len() != 8 && input.len() != 32);
+        prop_assert!(parse_ipv4_hex(&input).is_err());
+        prop_assert!(parse_ipv6_hex(&input).is_err());
+    }
+}
+
+proptest! {
+    // Every case writes a table to disk
+    #![proptest_config(ProptestConfig::with_cases(64))]
+
+    #[test]
+    fn tables_parse_without_failing(rows in prop::collection::vec(table_row(), 0..12)) {
+        let root = std::env::temp_dir().join(format!(
+            "nm-prop-proc-{}-{}",
+            std::process::id(),
+            NEXT_ROOT.fetch_add(1, Ordering::Relaxed)
+        ));
+        fs::create_dir_all(root.join("net")).unwrap();
+        let table = format!("  sl  local_address rem_address   st\n{}\n", rows.join("\n"));
+        fs::write(root.join("net/tcp"), table).unwrap();
+
+        let connections = NetworkService::with_proc_root(&root).get_connections();
+        fs::remove_dir_all(&root).unwrap();
+        prop_assert!(connections.unwrap().len() <= rows.len());
+    }
+}
@@ -0,0 +1,276 @@
+//! Parsing of a recorded /proc tree under tests/fixtures/proc: socket tables,
+//! fd links, status, cmdline and io files of three processes, read as user
+//! 1000 without privileges. The tables include IPv6 and IPv4-mapped
+//! addresses, uncommon TCP states, a non-empty send queue and malformed
+//! rows, and the ephemeral port range is narrowed to 50000-51009. The
+//! protocol counters are a desktop's after a day of use, the conntrack
+//! table and interface counters those of a small router. The sysfs tree
+//! next to it has a WireGuard and a TUN interface, and the routing tables
+//! send everything but the LAN and the tunnel's network through 10.0.0.1.
+//! Avahi listens for mDNS on eth0's multicast groups. A Diameter server
+//! has a multihomed SCTP association, and the kernel lists DCCP sockets.
+
+use network_monitor_core::models::{
+    Connection, ConnectionState, Direction, Protocol, TunnelKind, PERMISSION_DENIED,
+};
+use network_monitor_core::services::{
+    AndroidCollector, CacheLimits, Collector, NetworkService, PackageNames, ProcfsCollector,
+    RouterCollector,
+};
+use std::fs;
+use std::path::PathBuf;
+
+fn fixture_root() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/proc")
+}
+
+fn connection(
+    protocol: Protocol,
+    state: ConnectionState,
+    local: &str,
+    remote: &str,
+    owner: Option<(&str, &str, &str)>,
+) -> Connection {
+    let (program, pid, command) = owner.unwrap_or(("N/A", "N/A", "N/A"));
+    Connection::new(protocol, state, local, remote, program, pid, command)
+}
+
+#[test]
+fn test_recorded_tables() {
+    use ConnectionState::*;
+    use Protocol::*;
+
+    let cupsd = Some(("cupsd", "1200", "/usr/sbin/cupsd -l "));
+    let firefox = Some(("firefox", "2300", "/usr/lib/firefox/firefox "));
+    let avahi = Some(("avahi-daemon", "3100", "avahi-daemon: running [host.local]"));
+    let denied = Some((PERMISSION_DENIED, "N/A", "N/A"));
+
+    let service = NetworkService::with_proc_root(fixture_root());
+    let mut expected = vec![
+        connection(Tcp, Listen, "127.0.0.1:631", "0.0.0.0:0", cupsd),
+        connection(
+            Tcp,
+            Established,
+            "10.0.0.2:51000",
+            "93.184.216.34:443",
+            firefox,
+        ),
+        // Sockets without an inode belong to no process
+        connection(Tcp, TimeWait, "10.0.0.2:51002", "93.184.216.34:443", None),
+        // Root's socket with no readable owner
+        connection(Tcp, Established, "10.0.0.2:22", "10.0.0.10:54321", denied),
+        // Our own socket whose process has gone away
+        connection(Tcp, NewSynRecv, "10.0.0.2:8080", "10.0.0.11:57345", None),
+        connection(Tcp, Unknown(0xFF), "10.0.0.2:8081", "10.0.0.11:57346", None),
+        // Rows 6 to 9 are garbled and skipped
+        connection(Tcp6, Listen, "::1:631", ":::0", cupsd),
+        connection(
+            Tcp6,
+            Established,
+            "2001:db8::2:51010",
+            "2606:2800:220:1:248:1893:25c8:1946:443",
+            firefox,
+        ),
+        connection(
+            Tcp6,
+            CloseWait,
+            "::ffff:10.0.0.2:22",
+            "::ffff:10.0.0.12:50000",
+            denied,
+        ),
+        connection(Udp, Close, "0.0.0.0:5353", "0.0.0.0:0", avahi),
+        connection(
+            Udp,
+            Established,
+            "10.0.0.2:40000",
+            "142.250.74.3:443",
+            firefox,
+        ),
+        // The SCTP endpoint that isn't listening is the association's
+        connection(Sctp, Listen, "10.0.0.2:3868", "0.0.0.0:0", None),
+        // Remote primary path marked with a star
+        connection(Sctp, Established, "10.0.0.2:3868", "10.0.1.20:40000", None),
+        connection(Dccp, Listen, "10.0.0.2:5000", "0.0.0.0:0", None),
+    ];
+    let inodes = [
+        21001, 31001, 0, 40001, 40002, 0, 21002, 31002, 40010, 50001, 31003, 60001, 60002, 60003,
+    ];
+    for (conn, inode) in expected.iter_mut().zip(inodes) {
+        conn.inode = inode;
+    }
+    // Firefox has a full segment waiting to be acknowledged
+    expected[1].send_queue = 1448;
+    expected[12].recv_queue = 120;
+    assert_eq!(service.get_connections().unwrap(), expected);
+}
+
+#[test]
+fn test_recorded_process_files() {
+    let service = NetworkService::with_proc_root(fixture_root());
+    let io = service.get_process_io("2300");
+    assert_eq!((io.rx, io.tx), (987_654_321, 12_345_678));
+    // avahi-daemon has no io file, like another user's process
+    let io = service.get_process_io("3100");
+    assert_eq!((io.rx, io.tx), (0, 0));
+
+    assert_eq!(service.get_process_path("1200"), "/usr/sbin/cupsd -l ");
+    assert_eq!(service.get_process_path("9999"), "N/A");
+}
+
+#[test]
+fn test_unchanged_counters_have_no_rates() {
+    // Totals are the counters of firefox and cupsd; avahi-daemon's io is unreadable
+    let mut collector = ProcfsCollector::with_proc_root(fixture_root(), &CacheLimits::default());
+    collector.collect().unwrap();
+    let snapshot = collector.collect().unwrap();
+    assert_eq!(snapshot.connections.len(), 14);
+    assert!(snapshot.connections.iter().all(|conn| !conn.is_active()));
+    // The recording has no listeners on the ports of its connections, so
+    // they all count as opened from here
+    assert_eq!(snapshot.connections[0].direction, None);
+    assert_eq!(snapshot.connections[1].direction, Some(Direction::Outbound));
+    // The SCTP association reached the Diameter listener
+    assert_eq!(snapshot.connections[12].direction, Some(Direction::Inbound));
+    assert_eq!(snapshot.totals.received, 987_654_321 + 52_000);
+    assert_eq!(snapshot.totals.sent, 12_345_678 + 4_100);
+
+    // Outgoing TCP ports 51000 and 51002 fall in the range; 51010 and the
+    // UDP port 40000 don't
+    let ports = snapshot.ephemeral_ports.unwrap();
+    assert_eq!(ports.range, 50000..=51009);
+    assert_eq!((ports.tcp, ports.udp), (2, 0));
+
+    // Kernel counters from net/snmp and net/netstat, unchanged between passes
+    let protocol = snapshot.protocol_stats.unwrap();
+    assert_eq!(protocol.totals.segments_sent, 1_420_080);
+    assert_eq!(protocol.totals.retransmits, 3101);
+    assert_eq!(protocol.totals.resets, 389);
+    assert_eq!(protocol.totals.errors, 2 + 5);
+    assert_eq!(protocol.totals.timeouts, 287);
+    assert_eq!(protocol.retransmits_per_sec, 0.0);
+
+    // Tunnels from the sysfs tree next to the proc one; addresses and peers
+    // are only looked up on the live system
+    let tunnels: Vec<_> = snapshot
+        .tunnels
+        .iter()
+        .map(|tunnel| {
+            (
+                tunnel.name.as_str(),
+                tunnel.kind,
+                tunnel.rx_bytes,
+                tunnel.tx_bytes,
+            )
+        })
+        .collect();
+    assert_eq!(
+        tunnels,
+        [
+            ("tun0", TunnelKind::Tun, 5000, 7000),
+            ("wg0", TunnelKind::WireGuard, 1_048_576, 262_144),
+        ]
+    );
+    assert!(snapshot.tunnels.iter().all(|tunnel| tunnel.peers.is_none()));
+    assert!(snapshot
+        .connections
+        .iter()
+        .all(|conn| conn.tunnel.is_none()));
+
+    // Routes of net/route and net/ipv6_route; the local ::1 route is left out
+    let routes: Vec<String> = snapshot
+        .routes
+        .routes
+        .iter()
+        .map(|r| r.to_string())
+        .collect();
+    assert_eq!(
+        routes,
+        [
+            "default via 10.0.0.1 dev eth0 metric 100",
+            "10.0.0.0/24 dev eth0 metric 100",
+            "10.8.0.0/16 dev wg0",
+            "2001:db8::/64 dev eth0 metric 256",
+            "default via fe80::1 dev eth0 metric 1024",
+        ]
+    );
+    let https = snapshot
+        .connections
+        .iter()
+        .find(|conn| conn.remote.as_ref() == "93.184.216.34:443")
+        .unwrap();
+    assert_eq!(
+        snapshot.routes.route_for(https).unwrap().gateway,
+        Some([10, 0, 0, 1].into())
+    );
+
+    // Groups of net/igmp and net/igmp6, with avahi's mDNS socket behind the
+    // IPv4 mDNS group; its socket is IPv4 only
+    let groups: Vec<(String, Vec<String>)> = snapshot
+        .multicast
+        .iter()
+        .map(|group| (group.to_string(), group.processes.clone()))
+        .collect();
+    let avahi = vec!["avahi-daemon(3100)".to_string()];
+    assert_eq!(
+        groups,
+        [
+            ("224.0.0.1 on lo (all hosts)".to_string(), vec![]),
+            ("224.0.0.251 on eth0 (mDNS)".to_string(), avahi),
+            ("224.0.0.1 on eth0 (all hosts)".to_string(), vec![]),
+            ("ff02::fb on eth0 (mDNS)".to_string(), vec![]),
+            ("ff02::1 on eth0 (all nodes)".to_string(), vec![]),
+        ]
+    );
+}
+
+#[test]
+fn test_android_attributes_sockets_to_packages() {
+    let packages = PackageNames::parse("com.termux 1000 0 /data/user/0/com.termux default 3003\n");
+    let mut collector =
+        AndroidCollector::with_proc_root(fixture_root(), packages, &CacheLimits::default());
+    let snapshot = collector.collect().unwrap();
+    assert_eq!(snapshot.connections.len(), 14);
+
+    let firefox = &snapshot.connections[1];
+    assert_eq!(firefox.remote.as_ref(), "93.184.216.34:443");
+    assert_eq!((&*firefox.program, &*firefox.pid), ("com.termux", "N/A"));
+    let sshd = &snapshot.connections[3];
+    assert_eq!(&*sshd.program, "root");
+    assert_eq!(snapshot.totals.received, 0);
+}
+
+#[test]
+fn test_router_lists_flows_by_host() {
+    let mut collector = RouterCollector::with_proc_root(fixture_root());
+    let snapshot = collector.collect().unwrap();
+    // The ICMP flow is left out
+    let hosts: Vec<&str> = snapshot
+        .connections
+        .iter()
+        .map(|conn| &*conn.program)
+        .collect();
+    assert_eq!(hosts, ["192.168.1.10", "192.168.1.11", "2001:db8::2"]);
+    assert_eq!(snapshot.connections[0].remote.as_ref(), "93.184.216.34:443");
+    assert_eq!(snapshot.connections[2].state, ConnectionState::TimeWait);
+
+    // Loopback doesn't count towards the totals
+    assert_eq!(snapshot.interface_stats.len(), 3);
+    assert_eq!(snapshot.totals.received, 9_876_543 + 2_000_000);
+    assert_eq!(snapshot.totals.sent, 1_234_567 + 8_000_000);
+
+    let snapshot = collector.collect().unwrap();
+    assert!(snapshot.connections.iter().all(|conn| !conn.is_active()));
+    assert!(snapshot
+        .interface_stats
+        .iter()
+        .all(|interface| interface.rx_rate == 0));
+}
+
+#[test]
+fn test_tree_without_tables() {
+    let root = std::env::temp_dir().join(format!("nm-empty-proc-{}", std::process::id()));
+    fs::create_dir_all(root.join("net")).unwrap();
+    let service = NetworkService::with_proc_root(&root);
+    assert!(service.get_connections().unwrap().is_empty());
+    fs::remove_dir_all(&root).unwrap();
+}
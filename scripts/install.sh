@@ -26,14 +26,20 @@ cd "$PROJECT_DIR"
 if [ "$EUID" -eq 0 ]; then
     INSTALL_TYPE="system"
     BIN_DIR="/usr/local/bin"
+    LIBEXEC_DIR="/usr/local/libexec"
+    POLKIT_DIR="/usr/share/polkit-1/actions"
     APPLICATIONS_DIR="/usr/share/applications"
     ICON_DIR="/usr/share/icons/hicolor"
+    SCHEMA_DIR="/usr/share/glib-2.0/schemas"
+    SEARCH_PROVIDER_DIR="/usr/share/gnome-shell/search-providers"
+    DBUS_SERVICE_DIR="/usr/share/dbus-1/services"
     echo "Installing Network Monitor system-wide..."
 else
     INSTALL_TYPE="local"
     BIN_DIR="$HOME/.local/bin"
     APPLICATIONS_DIR="$HOME/.local/share/applications"
     ICON_DIR="$HOME/.local/share/icons/hicolor"
+    SCHEMA_DIR="$HOME/.local/share/glib-2.0/schemas"
     echo "Installing Network Monitor locally for user $USER..."
 fi
 
@@ -46,6 +52,7 @@ if [ "$INSTALL_TYPE" = "system" ]; then
     fi
     GTK_BINARY_PATH="target/release/network-monitor"
     TUI_BINARY_PATH="target/release/nmt"
+    HELPER_BINARY_PATH="target/release/network-monitor-helper"
 else
     if ! cargo build; then
         echo "Error: Failed to build debug binaries"
@@ -71,6 +78,37 @@ chmod 755 "$BIN_DIR/network-monitor"
 cp "$TUI_BINARY_PATH" "$BIN_DIR/"
 chmod 755 "$BIN_DIR/nmt"
 
+# Install the privileged helper and its polkit policy (system-wide only)
+if [ "$INSTALL_TYPE" = "system" ]; then
+    echo "Installing privileged helper to $LIBEXEC_DIR..."
+    mkdir -p "$LIBEXEC_DIR" "$POLKIT_DIR"
+    cp "$HELPER_BINARY_PATH" "$LIBEXEC_DIR/"
+    chmod 755 "$LIBEXEC_DIR/network-monitor-helper"
+    cp data/org.grigio.NetworkMonitor.policy "$POLKIT_DIR/"
+    chmod 644 "$POLKIT_DIR/org.grigio.NetworkMonitor.policy"
+fi
+
+# Install the GNOME Shell search provider and the D-Bus service starting the
+# monitor for it (system-wide only: the Shell reads providers from /usr/share)
+if [ "$INSTALL_TYPE" = "system" ]; then
+    echo "Installing GNOME Shell search provider to $SEARCH_PROVIDER_DIR..."
+    mkdir -p "$SEARCH_PROVIDER_DIR" "$DBUS_SERVICE_DIR"
+    cp data/org.grigio.NetworkMonitor.search-provider.ini "$SEARCH_PROVIDER_DIR/"
+    chmod 644 "$SEARCH_PROVIDER_DIR/org.grigio.NetworkMonitor.search-provider.ini"
+    sed "s|^Exec=[^ ]*|Exec=$BIN_DIR/network-monitor|" data/org.grigio.NetworkMonitor.service \
+        > "$DBUS_SERVICE_DIR/org.grigio.NetworkMonitor.service"
+    chmod 644 "$DBUS_SERVICE_DIR/org.grigio.NetworkMonitor.service"
+fi
+
+# Install the GSettings schema that stores the GTK4 preferences
+echo "Installing settings schema to $SCHEMA_DIR..."
+mkdir -p "$SCHEMA_DIR"
+cp data/org.grigio.NetworkMonitor.gschema.xml "$SCHEMA_DIR/"
+chmod 644 "$SCHEMA_DIR/org.grigio.NetworkMonitor.gschema.xml"
+if ! glib-compile-schemas "$SCHEMA_DIR"; then
+    echo "Warning: Could not compile settings schemas in $SCHEMA_DIR"
+fi
+
 # Install desktop file with correct Exec path
 DESKTOP_FILE="network-monitor.desktop"
 
@@ -150,6 +188,9 @@ echo ""
 echo "Installed binaries:"
 echo "  - network-monitor GTK4 GUI"
 echo "  - nmt Terminal UI"
+if [ "$INSTALL_TYPE" = "system" ]; then
+    echo "  - network-monitor-helper privileged helper (polkit)"
+fi
 echo ""
 echo "IMPORTANT: To see the correct icon in GNOME Shell:"
 echo "1. Restart GNOME Shell: Alt+F2, type r, press Enter"
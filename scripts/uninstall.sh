@@ -19,14 +19,20 @@ set -e
 if [ "$EUID" -eq 0 ]; then
     INSTALL_TYPE="system"
     BIN_DIR="/usr/local/bin"
+    LIBEXEC_DIR="/usr/local/libexec"
+    POLKIT_DIR="/usr/share/polkit-1/actions"
     APPLICATIONS_DIR="/usr/share/applications"
     ICON_DIR="/usr/share/icons/hicolor"
+    SCHEMA_DIR="/usr/share/glib-2.0/schemas"
+    SEARCH_PROVIDER_DIR="/usr/share/gnome-shell/search-providers"
+    DBUS_SERVICE_DIR="/usr/share/dbus-1/services"
     echo "Uninstalling Network Monitor from system..."
 else
     INSTALL_TYPE="local"
     BIN_DIR="$HOME/.local/bin"
     APPLICATIONS_DIR="$HOME/.local/share/applications"
     ICON_DIR="$HOME/.local/share/icons/hicolor"
+    SCHEMA_DIR="$HOME/.local/share/glib-2.0/schemas"
     echo "Uninstalling Network Monitor from user $USER's local installation..."
 fi
 
@@ -49,6 +55,27 @@ else
     echo "Warning: TUI binary not found at $TUI_BINARY_PATH"
 fi
 
+# Remove the privileged helper and its polkit policy
+if [ "$INSTALL_TYPE" = "system" ]; then
+    if [ -f "$LIBEXEC_DIR/network-monitor-helper" ]; then
+        echo "Removing privileged helper: $LIBEXEC_DIR/network-monitor-helper"
+        rm -f "$LIBEXEC_DIR/network-monitor-helper"
+    fi
+    rm -f "$POLKIT_DIR/org.grigio.NetworkMonitor.policy"
+    # Rule written by the first-run setup assistant, if it was used
+    rm -f /etc/polkit-1/rules.d/50-network-monitor.rules
+    # GNOME Shell search provider and the D-Bus service starting it
+    rm -f "$SEARCH_PROVIDER_DIR/org.grigio.NetworkMonitor.search-provider.ini"
+    rm -f "$DBUS_SERVICE_DIR/org.grigio.NetworkMonitor.service"
+fi
+
+# Remove the settings schema; saved preferences stay in dconf
+if [ -f "$SCHEMA_DIR/org.grigio.NetworkMonitor.gschema.xml" ]; then
+    echo "Removing settings schema: $SCHEMA_DIR/org.grigio.NetworkMonitor.gschema.xml"
+    rm -f "$SCHEMA_DIR/org.grigio.NetworkMonitor.gschema.xml"
+    glib-compile-schemas "$SCHEMA_DIR" 2>/dev/null || echo "Warning: Could not recompile settings schemas"
+fi
+
 # Remove desktop file
 DESKTOP_FILE="$APPLICATIONS_DIR/network-monitor.desktop"
 REMOVED_DESKTOP=false
@@ -0,0 +1,1762 @@
+use crate::config::{Config, RecordingConfig};
+use crate::error::{NetworkMonitorError, Result};
+use crate::logging;
+use crate::models::{
+    AuditDestination, AuditProgram, Connection, ConnectionSnapshot, Direction, HostNames,
+    QuickStats, Scope, ScopeTraffic, Subnet, TopTalkers,
+};
+use crate::paths::{self, Paths};
+use crate::recording::{RecordKind, Schedule};
+use crate::services::remote::DEFAULT_REMOTE_COMMAND;
+use crate::services::{
+    AddressResolver, Backend, CacheLimits, CollectorEvent, CollectorRequest, ConnectionCollector,
+    OutboundAudit, SortedConnections,
+};
+use crate::signals;
+use crate::utils::formatter::{format_duration, DataUnit, NumberStyle, Prefixes, Units};
+use clap::{ArgAction, Args, ValueEnum};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::level_filters::LevelFilter;
+
+/// Processes listed in the tooltip of the status bar line
+const STATUSBAR_PROCESSES: usize = 3;
+
+/// Column names accepted by --sort and --columns, by table column index
+pub const COLUMN_NAMES: [&str; 14] = [
+    "program",
+    "protocol",
+    "local",
+    "remote",
+    "state",
+    "tx",
+    "rx",
+    "command",
+    "sendq",
+    "recvq",
+    "direction",
+    "timer",
+    "scope",
+    "host",
+];
+/// Table headers, by column index
+pub const COLUMN_TITLES: [&str; 14] = [
+    "Process(ID)",
+    "Protocol",
+    "Source",
+    "Destination",
+    "Status",
+    "TX",
+    "RX",
+    "Path",
+    "SendQ",
+    "RecvQ",
+    "Dir",
+    "Timer",
+    "Scope",
+    "Host",
+];
+/// Columns shown unless configured otherwise; the socket queues, the
+/// direction, the timer, the scope and the host are opt-in
+pub const DEFAULT_COLUMNS: [usize; 8] = [0, 1, 2, 3, 4, 5, 6, 7];
+/// Machine of each connection, shown first while all hosts are combined
+pub const HOST_COLUMN: usize = 13;
+/// Name of this machine among the hosts to collect from
+pub const LOCAL_HOST: &str = "local";
+/// Name of all hosts combined in one table
+pub const ALL_HOSTS: &str = "all";
+/// Alternative names matching the table headers
+const COLUMN_ALIASES: [(&str, usize); 14] = [
+    ("process", 0),
+    ("proto", 1),
+    ("source", 2),
+    ("destination", 3),
+    ("status", 4),
+    ("upload", 5),
+    ("download", 6),
+    ("path", 7),
+    ("send-q", 8),
+    ("recv-q", 9),
+    ("dir", 10),
+    ("timers", 11),
+    ("network", 12),
+    ("machine", 13),
+];
+/// Shortest accepted refresh interval
+const MIN_REFRESH: Duration = Duration::from_millis(100);
+/// Time without traffic or state changes before a connection is dimmed,
+/// unless configured otherwise
+pub const IDLE_AFTER: Duration = Duration::from_secs(10 * 60);
+
+/// Table column index for a column name or header alias
+pub fn parse_column(name: &str) -> std::result::Result<usize, String> {
+    let name = name.trim().to_ascii_lowercase();
+    COLUMN_NAMES
+        .iter()
+        .position(|&column| column == name)
+        .or_else(|| {
+            COLUMN_ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == name)
+                .map(|&(_, column)| column)
+        })
+        .ok_or_else(|| {
+            format!(
+                "unknown column '{name}' (expected one of: {})",
+                COLUMN_NAMES.join(", ")
+            )
+        })
+}
+
+pub fn parse_refresh(seconds: &str) -> std::result::Result<Duration, String> {
+    let seconds: f64 = seconds
+        .trim()
+        .parse()
+        .map_err(|_| format!("'{seconds}' is not a number of seconds"))?;
+    refresh_from_secs(seconds)
+}
+
+fn refresh_from_secs(seconds: f64) -> std::result::Result<Duration, String> {
+    match Duration::try_from_secs_f64(seconds) {
+        Ok(interval) if interval >= MIN_REFRESH => Ok(interval),
+        _ => Err(format!(
+            "refresh interval must be at least {} seconds",
+            MIN_REFRESH.as_secs_f64()
+        )),
+    }
+}
+
+/// Sort column and direction, written as "COLUMN" or "COLUMN:asc|desc"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SortSpec {
+    pub column: usize,
+    pub ascending: bool,
+}
+
+impl Default for SortSpec {
+    /// Busiest downloads first
+    fn default() -> Self {
+        Self {
+            column: 6,
+            ascending: false,
+        }
+    }
+}
+
+impl FromStr for SortSpec {
+    type Err = String;
+
+    /// Without a direction the column sorts descending, like a first header click
+    fn from_str(spec: &str) -> std::result::Result<Self, Self::Err> {
+        let (column, direction) = spec.split_once(':').unwrap_or((spec, "desc"));
+        let ascending = match direction.trim().to_ascii_lowercase().as_str() {
+            "asc" => true,
+            "desc" => false,
+            other => {
+                return Err(format!(
+                    "unknown sort direction '{other}' (use asc or desc)"
+                ))
+            }
+        };
+        Ok(Self {
+            column: parse_column(column)?,
+            ascending,
+        })
+    }
+}
+
+/// Format of the snapshot printed by --once and the report of --audit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned table with formatted rates
+    #[default]
+    Text,
+    /// Array of objects, rates in bytes (or bits) per second
+    Json,
+    /// Header row plus one row per connection, rates in bytes (or bits) per
+    /// second
+    Csv,
+}
+
+/// Page shown by the interface
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, ValueEnum)]
+pub enum View {
+    /// Live connections table
+    #[default]
+    Connections,
+    /// Firewall drops and rejects
+    Blocked,
+    /// Processes querying DNS resolvers
+    Dns,
+    /// Main routing table
+    Routes,
+    /// Interfaces going up or down and address changes
+    Events,
+    /// Multicast groups and broadcast listeners
+    Multicast,
+    /// Traffic per remote country, with --geoip
+    Countries,
+}
+
+impl View {
+    /// ViewStack page name in the GTK window
+    pub fn page_name(self) -> &'static str {
+        match self {
+            View::Connections => "connections",
+            View::Blocked => "blocked",
+            View::Dns => "dns",
+            View::Routes => "routes",
+            View::Events => "events",
+            View::Multicast => "multicast",
+            View::Countries => "countries",
+        }
+    }
+}
+
+/// Options understood by both network-monitor and nmt
+#[derive(Debug, Clone, Default, Args)]
+pub struct CommonArgs {
+    /// Seconds between automatic refreshes
+    #[arg(long, value_name = "SECONDS", value_parser = parse_refresh)]
+    pub refresh: Option<Duration>,
+
+    /// Show IP addresses instead of resolving hostnames
+    #[arg(long)]
+    pub no_resolve: bool,
+
+    /// Only show connections whose process, PID, addresses, protocol, state,
+    /// path, direction (inbound, outbound) or country code contain TEXT
+    /// (case-insensitive)
+    #[arg(long, value_name = "TEXT")]
+    pub filter: Option<String>,
+
+    /// Initial sort, e.g. "rx:desc" or "program:asc"
+    #[arg(long, value_name = "COLUMN[:asc|desc]")]
+    pub sort: Option<SortSpec>,
+
+    /// Comma-separated columns to show: program, protocol, local, remote,
+    /// state, tx, rx, command, sendq, recvq, direction, timer
+    #[arg(long, value_name = "COLUMNS", value_delimiter = ',', value_parser = parse_column)]
+    pub columns: Option<Vec<usize>>,
+
+    /// Page to show
+    #[arg(long, value_enum, value_name = "VIEW")]
+    pub view: Option<View>,
+
+    /// Gray out connections without traffic or state changes for MINUTES
+    /// (default 10, 0 never does)
+    #[arg(long, value_name = "MINUTES")]
+    pub idle_minutes: Option<u64>,
+
+    /// Leave idle connections out instead of graying them out
+    #[arg(long)]
+    pub hide_idle: bool,
+
+    /// Count traffic in bytes or bits
+    #[arg(long, value_name = "bytes|bits")]
+    pub units: Option<DataUnit>,
+
+    /// Multiples for large values: jedec (1024, KB), si (1000, kB) or iec
+    /// (1024, KiB); bits always use multiples of 1000
+    #[arg(long, value_name = "jedec|si|iec")]
+    pub prefixes: Option<Prefixes>,
+
+    /// Separators of displayed numbers: locale (default), plain (1234.5),
+    /// point (1,234.5), comma (1.234,5) or space (1 234,5)
+    #[arg(long, value_name = "STYLE")]
+    pub numbers: Option<NumberStyle>,
+
+    /// Start with the settings of a `[profiles.NAME]` config section; other
+    /// options still take precedence
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
+    /// Where connections come from: procfs, netlink (sock_diag, like ss),
+    /// libproc on macOS, android (owners by app package), router (conntrack
+    /// flows by host), fixture:PATH to replay a JSON recording or ssh:HOST to
+    /// run `nmt --serve` on another machine
+    #[arg(long, value_name = "BACKEND")]
+    pub backend: Option<Backend>,
+
+    /// Capture the TLS handshakes of new connections to name HTTPS servers
+    /// that have no reverse DNS, and confirm QUIC flows by their Initial
+    /// packets (Linux, needs root or CAP_NET_RAW)
+    #[arg(long)]
+    pub capture_sni: bool,
+
+    /// CSV file of IP ranges by country to place remote hosts in, such as
+    /// Tor's geoip and geoip6 or DB-IP's lite country database; repeat for
+    /// more files
+    #[arg(long, value_name = "PATH")]
+    pub geoip: Vec<PathBuf>,
+
+    /// Format of the snapshot printed by --once or the report of --audit
+    #[arg(long, value_enum, value_name = "FORMAT", requires = "report")]
+    pub output: Option<OutputFormat>,
+
+    /// Print one snapshot to stdout and exit instead of starting the interface
+    #[arg(long, group = "report")]
+    pub once: bool,
+
+    /// Watch connections for MINUTES instead of starting the interface, then
+    /// print every program that connected out to another host and where, to
+    /// start firewall allowlists from
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        group = "report",
+        value_parser = clap::value_parser!(u64).range(1..)
+    )]
+    pub audit: Option<u64>,
+
+    /// Check /proc and netlink access, DNS resolution, GeoIP files and the
+    /// config instead of starting the interface, and print what to fix
+    #[arg(long, conflicts_with = "report")]
+    pub doctor: bool,
+
+    /// Read settings from PATH instead of ~/.config/network-monitor/config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Log messages at LEVEL and above: off, error, warn, info, debug, trace
+    #[arg(long, value_name = "LEVEL")]
+    pub log_level: Option<LevelFilter>,
+
+    /// Log more: -v for info, -vv for debug, -vvv for trace
+    #[arg(short, long, action = ArgAction::Count, conflicts_with = "log_level")]
+    pub verbose: u8,
+
+    /// Append log messages to PATH
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+}
+
+impl CommonArgs {
+    /// Load the config file and apply these options on top of it
+    pub fn settings(&self) -> Result<Settings> {
+        let config = Config::load(self.config.as_deref())?;
+        // Resolve every profile up front so mistakes in any of them show at
+        // startup, not when it's picked from the menu
+        let profiles = config
+            .profiles
+            .keys()
+            .map(|name| {
+                let settings = Settings::merge(&CommonArgs::default(), config.with_profile(name)?)?;
+                Ok((name.clone(), settings))
+            })
+            .collect::<Result<_>>()?;
+
+        let profile = self.profile.clone().or_else(|| config.profile.clone());
+        let config = match &profile {
+            Some(name) => config.with_profile(name)?,
+            None => config,
+        };
+        Ok(Settings {
+            profile,
+            profiles,
+            ..Settings::merge(self, config)?
+        })
+    }
+}
+
+/// Startup settings from the config file and command line. Unset values fall
+/// back to each binary's own defaults.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Settings {
+    pub refresh: Option<Duration>,
+    pub resolve_hostnames: Option<bool>,
+    /// Lowercased substring connections must contain
+    pub filter: Option<String>,
+    pub sort: Option<SortSpec>,
+    /// Column indices to show, in display order
+    pub columns: Option<Vec<usize>>,
+    pub unit: Option<DataUnit>,
+    pub prefixes: Option<Prefixes>,
+    pub numbers: Option<NumberStyle>,
+    pub view: Option<View>,
+    /// Time without activity before a connection counts as idle, zero for never
+    pub idle_after: Option<Duration>,
+    pub hide_idle: Option<bool>,
+    pub backend: Backend,
+    /// Whether to capture TLS server names, see
+    /// [`SniCapture`](crate::services::SniCapture)
+    pub capture_sni: bool,
+    /// GeoIP files to look up the country of remote hosts in, see
+    /// [`GeoIpDatabase`](crate::services::GeoIpDatabase)
+    pub geoip: Vec<PathBuf>,
+    /// Ranges counted as LAN besides the private ones, see
+    /// [`Scope`](crate::models::Scope)
+    pub lan: Vec<Subnet>,
+    /// Names from the `[hosts]` config section, shown instead of addresses
+    pub host_names: HostNames,
+    /// Other machines to switch to, by name, from the `[remotes]` config
+    /// section
+    pub remotes: BTreeMap<String, Backend>,
+    /// Name of the selected profile
+    pub profile: Option<String>,
+    /// Settings of every configured profile, for switching at runtime
+    pub profiles: BTreeMap<String, Settings>,
+    /// None for the default level
+    pub log_level: Option<LevelFilter>,
+    pub log_file: Option<PathBuf>,
+    /// Cache, data, state and export directories
+    pub paths: Paths,
+    /// Keys per action from the `[keys]` config section
+    pub keys: BTreeMap<String, Vec<String>>,
+    /// Scheduled recordings from the `[recording]` config section
+    pub recording: Option<Schedule>,
+}
+
+impl Settings {
+    fn merge(args: &CommonArgs, config: Config) -> Result<Self> {
+        let invalid = |e: String| NetworkMonitorError::Config(e);
+
+        let refresh = match args.refresh {
+            Some(refresh) => Some(refresh),
+            None => config
+                .refresh
+                .map(refresh_from_secs)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let sort = match args.sort {
+            Some(sort) => Some(sort),
+            None => config
+                .sort
+                .as_deref()
+                .map(SortSpec::from_str)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let columns = match &args.columns {
+            Some(columns) => Some(columns.clone()),
+            None => config
+                .columns
+                .map(|names| names.iter().map(|name| parse_column(name)).collect())
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let unit = match args.units {
+            Some(unit) => Some(unit),
+            None => config
+                .units
+                .as_deref()
+                .map(DataUnit::from_str)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let prefixes = match args.prefixes {
+            Some(prefixes) => Some(prefixes),
+            None => config
+                .prefixes
+                .as_deref()
+                .map(Prefixes::from_str)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let numbers = match args.numbers {
+            Some(numbers) => Some(numbers),
+            None => config
+                .numbers
+                .as_deref()
+                .map(NumberStyle::from_str)
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let view = match args.view {
+            Some(view) => Some(view),
+            None => config
+                .view
+                .as_deref()
+                .map(|view| {
+                    View::from_str(view, true).map_err(|_| {
+                        format!("unknown view '{view}' (use connections, blocked, dns, routes, events, multicast or countries)")
+                    })
+                })
+                .transpose()
+                .map_err(invalid)?,
+        };
+        let remote_command = config
+            .remote_command
+            .clone()
+            .unwrap_or_else(|| DEFAULT_REMOTE_COMMAND.to_string());
+        let backend = match &args.backend {
+            Some(backend) => backend.clone(),
+            None => config
+                .backend
+                .as_deref()
+                .map(Backend::from_str)
+                .transpose()
+                .map_err(invalid)?
+                .unwrap_or_default(),
+        };
+        // ssh:HOST runs the same command as the [remotes]
+        let backend = match backend {
+            Backend::Ssh { destination, .. } => Backend::Ssh {
+                destination,
+                command: remote_command.clone(),
+            },
+            backend => backend,
+        };
+        let log_level = match (args.log_level, args.verbose) {
+            (Some(level), _) => Some(level),
+            (None, 0) => config
+                .log_level
+                .as_deref()
+                .map(|level| {
+                    level
+                        .parse()
+                        .map_err(|_| format!("unknown log level '{level}'"))
+                })
+                .transpose()
+                .map_err(invalid)?,
+            (None, verbose) => Some(logging::verbosity_level(verbose)),
+        };
+        let host_names = config
+            .hosts
+            .iter()
+            .map(|(address, name)| {
+                let subnet = address
+                    .parse::<Subnet>()
+                    .map_err(|e| format!("[hosts]: {e}"))?;
+                Ok((subnet, name.clone()))
+            })
+            .collect::<std::result::Result<Vec<_>, String>>()
+            .map(HostNames::new)
+            .map_err(invalid)?;
+        let lan = config
+            .lan
+            .unwrap_or_default()
+            .iter()
+            .map(|range| range.parse::<Subnet>().map_err(|e| format!("lan: {e}")))
+            .collect::<std::result::Result<_, _>>()
+            .map_err(invalid)?;
+        let remotes = config
+            .remotes
+            .into_iter()
+            .map(|(name, destination)| {
+                if destination.trim().is_empty() {
+                    return Err(format!("[remotes]: no SSH destination for '{name}'"));
+                }
+                if name == LOCAL_HOST || name == ALL_HOSTS {
+                    return Err(format!(
+                        "[remotes]: '{name}' names {}, pick another name",
+                        if name == LOCAL_HOST {
+                            "this machine"
+                        } else {
+                            "all hosts"
+                        }
+                    ));
+                }
+                let backend = Backend::Ssh {
+                    destination: destination.trim().to_string(),
+                    command: remote_command.clone(),
+                };
+                Ok((name, backend))
+            })
+            .collect::<std::result::Result<_, String>>()
+            .map_err(invalid)?;
+        let paths = Paths::new(&config.paths);
+        let recording = Self::recording(config.recording, &paths).map_err(invalid)?;
+        let filter = args
+            .filter
+            .clone()
+            .or(config.filter)
+            .map(|filter| filter.trim().to_lowercase())
+            .filter(|filter| !filter.is_empty());
+
+        Ok(Self {
+            refresh,
+            resolve_hostnames: if args.no_resolve {
+                Some(false)
+            } else {
+                config.resolve_hostnames
+            },
+            filter,
+            sort,
+            columns: columns.filter(|columns| !columns.is_empty()),
+            unit,
+            prefixes,
+            numbers,
+            view,
+            idle_after: args
+                .idle_minutes
+                .or(config.idle_minutes)
+                .map(|minutes| Duration::from_secs(minutes.saturating_mul(60))),
+            hide_idle: if args.hide_idle {
+                Some(true)
+            } else {
+                config.hide_idle
+            },
+            backend,
+            capture_sni: args.capture_sni || config.capture_sni.unwrap_or(false),
+            geoip: if args.geoip.is_empty() {
+                config
+                    .geoip
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|path| paths::expand_home(path))
+                    .collect()
+            } else {
+                args.geoip.clone()
+            },
+            lan,
+            host_names,
+            remotes,
+            profile: None,
+            profiles: BTreeMap::new(),
+            log_level,
+            log_file: args.log_file.clone().or(config.log_file),
+            paths,
+            keys: config
+                .keys
+                .into_iter()
+                .map(|(action, keys)| (action, keys.into_vec()))
+                .collect(),
+            recording,
+        })
+    }
+
+    fn recording(
+        config: RecordingConfig,
+        paths: &Paths,
+    ) -> std::result::Result<Option<Schedule>, String> {
+        let Some(minutes) = config.every_minutes else {
+            return Ok(None);
+        };
+        if minutes == 0 {
+            return Err("[recording]: every_minutes must be at least 1".to_string());
+        }
+        let kind = config
+            .record
+            .as_deref()
+            .map(RecordKind::from_str)
+            .transpose()
+            .map_err(|e| format!("[recording]: {e}"))?
+            .unwrap_or_default();
+        let format = config
+            .format
+            .as_deref()
+            .map(|format| {
+                OutputFormat::from_str(format, true).map_err(|_| {
+                    format!("[recording]: unknown format '{format}' (use text, json or csv)")
+                })
+            })
+            .transpose()?
+            .unwrap_or(OutputFormat::Json);
+        Ok(Some(Schedule {
+            every: Duration::from_secs(minutes.saturating_mul(60)),
+            kind,
+            format,
+            dir: config
+                .dir
+                .as_deref()
+                .map_or_else(|| paths.export_dir.clone(), paths::expand_home),
+        }))
+    }
+
+    pub fn matches(&self, conn: &Connection) -> bool {
+        self.filter
+            .as_deref()
+            .is_none_or(|filter| conn.matches_filter(filter))
+    }
+
+    /// Columns to display, the defaults unless --columns chose others
+    pub fn columns(&self) -> Vec<usize> {
+        self.columns
+            .clone()
+            .unwrap_or_else(|| DEFAULT_COLUMNS.to_vec())
+    }
+
+    /// Time without activity before a connection counts as idle, zero when
+    /// connections never do
+    pub fn idle_after(&self) -> Duration {
+        self.idle_after.unwrap_or(IDLE_AFTER)
+    }
+
+    /// Machines to switch between by name: this one, the `[remotes]` and,
+    /// when there are any, all of them combined in one table
+    pub fn hosts(&self) -> Vec<(String, Backend)> {
+        let mut hosts: Vec<(String, Backend)> =
+            std::iter::once((LOCAL_HOST.to_string(), self.backend.clone()))
+                .chain(self.remotes.clone())
+                .collect();
+        if hosts.len() > 1 {
+            let combined = Backend::Combined(hosts.clone());
+            hosts.push((ALL_HOSTS.to_string(), combined));
+        }
+        hosts
+    }
+
+    /// Units to display, defaulting to bytes with JEDEC prefixes in the
+    /// number style of the locale
+    pub fn units(&self) -> Units {
+        Units::new(
+            self.unit.unwrap_or_default(),
+            self.prefixes.unwrap_or_default(),
+        )
+        .with_numbers(self.numbers.unwrap_or_else(NumberStyle::system))
+    }
+}
+
+/// Take two samples `refresh` apart so rates are meaningful, then print the
+/// filtered, sorted connections to stdout
+pub fn run_once(
+    settings: &Settings,
+    format: OutputFormat,
+    default_refresh: Duration,
+    default_resolve: bool,
+) -> Result<()> {
+    let (collector, events, resolver) = start_headless(settings, default_resolve);
+    let first = next_snapshot(&collector, &events)?;
+    // Queue the lookups now so most names are known by the second sample
+    for conn in first
+        .connections
+        .iter()
+        .filter(|conn| settings.matches(conn))
+    {
+        resolver.resolve_address(&conn.remote);
+    }
+    thread::sleep(settings.refresh.unwrap_or(default_refresh));
+
+    // Ordered as the tables order them, addresses by the names shown
+    let sort = settings.sort.unwrap_or_default();
+    let mut rows =
+        SortedConnections::new(sort.column, sort.ascending).with_resolver(resolver.clone());
+    rows.set_filter(settings.filter.clone());
+    rows.update(&next_snapshot(&collector, &events)?);
+
+    let mut stdout = io::stdout().lock();
+    write_connections(
+        &mut stdout,
+        rows.rows(),
+        &settings.columns(),
+        format,
+        settings.units(),
+        |conn| resolver.resolve_remote(conn),
+    )?;
+    stdout.flush()?;
+    let _ = resolver.save_cache(&settings.paths.resolver_cache());
+    Ok(())
+}
+
+/// Sample connections every refresh interval for `period`, then print the
+/// programs that connected out to other hosts and their destinations
+pub fn run_audit(
+    settings: &Settings,
+    period: Duration,
+    format: OutputFormat,
+    default_refresh: Duration,
+    default_resolve: bool,
+) -> Result<()> {
+    let (collector, events, resolver) = start_headless(settings, default_resolve);
+    let refresh = settings.refresh.unwrap_or(default_refresh);
+    let deadline = Instant::now() + period;
+    let stop = signals::stop_on_signals()?;
+    eprintln!(
+        "Auditing outbound connections for {}",
+        format_duration(period.as_secs())
+    );
+    let mut audit = OutboundAudit::new();
+    loop {
+        let connections: Vec<Connection> = next_snapshot(&collector, &events)?
+            .connections
+            .into_iter()
+            .filter(|conn| settings.matches(conn))
+            .collect();
+        audit.record(&connections);
+        // Queue the lookups as hosts show up so names are known at the end
+        for dest in connections.iter().filter_map(AuditDestination::of) {
+            resolver.resolve_host(&dest.address.to_string());
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            break;
+        }
+        // Killed early, the report covers what was seen so far
+        if signals::sleep_unless_stopped(&stop, refresh.min(remaining)) {
+            eprintln!("Stopped early, reporting what was seen so far");
+            break;
+        }
+    }
+
+    let mut report = audit.report();
+    for dest in report
+        .iter_mut()
+        .flat_map(|program| &mut program.destinations)
+    {
+        let address = dest.address.to_string();
+        dest.host = Some(resolver.resolve_host(&address)).filter(|host| *host != address);
+    }
+    let mut stdout = io::stdout().lock();
+    write_audit(&mut stdout, &report, format)?;
+    stdout.flush()?;
+    let _ = resolver.save_cache(&settings.paths.resolver_cache());
+    Ok(())
+}
+
+/// Print a line of JSON for a waybar custom module every refresh interval
+/// until stdout closes, from the connections matching the filter
+pub fn run_statusbar(settings: &Settings, default_refresh: Duration) -> Result<()> {
+    let units = settings.units();
+    print_every(settings, default_refresh, |snapshot| {
+        statusbar_line(snapshot, units)
+    })
+}
+
+/// List the `count` busiest processes every refresh interval until
+/// interrupted: as a table redrawn in place, or with `plain` as bare lines
+/// and a blank line after each list, for conky and scripts
+pub fn run_top_processes(
+    settings: &Settings,
+    count: usize,
+    plain: bool,
+    default_refresh: Duration,
+) -> Result<()> {
+    let units = settings.units();
+    print_every(settings, default_refresh, |snapshot| {
+        let list = top_processes(&snapshot.connections, count, units);
+        if plain {
+            format!("{list}\n")
+        } else {
+            let header = format!(
+                "{:<24} {:>10} {:>10}",
+                COLUMN_TITLES[0], COLUMN_TITLES[5], COLUMN_TITLES[6]
+            );
+            // Clear the terminal and home the cursor, like watch(1)
+            format!("\x1b[2J\x1b[H{header}\n{list}")
+        }
+    })
+}
+
+/// Print `text` of the connections matching the filter every refresh
+/// interval until stdout closes or a signal stops it
+fn print_every(
+    settings: &Settings,
+    default_refresh: Duration,
+    text: impl Fn(&ConnectionSnapshot) -> String,
+) -> Result<()> {
+    let (collector, events, _) = start_headless(settings, false);
+    let refresh = settings.refresh.unwrap_or(default_refresh);
+    let stop = signals::stop_on_signals()?;
+    // Rates need a previous sample
+    next_snapshot(&collector, &events)?;
+    let mut stdout = io::stdout().lock();
+    loop {
+        if signals::sleep_unless_stopped(&stop, refresh) {
+            return Ok(());
+        }
+        let mut snapshot = next_snapshot(&collector, &events)?;
+        snapshot.connections.retain(|conn| settings.matches(conn));
+        let written = writeln!(stdout, "{}", text(&snapshot)).and_then(|()| stdout.flush());
+        match written {
+            // The reader went away, e.g. the bar was reloaded
+            Err(e) if e.kind() == io::ErrorKind::BrokenPipe => return Ok(()),
+            written => written?,
+        }
+    }
+}
+
+/// Up to `count` processes with traffic, busiest first, one per line with
+/// their upload and download rates
+pub fn top_processes(connections: &[Connection], count: usize, units: Units) -> String {
+    TopTalkers::from_connections(connections, count)
+        .processes
+        .iter()
+        .map(|process| {
+            format!(
+                "{:<24} {:>10} {:>10}",
+                process.name,
+                units.format_rate(process.tx_rate),
+                units.format_rate(process.rx_rate)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// What waybar reads from a custom module with `"return-type": "json"`
+#[derive(Serialize)]
+struct StatusbarLine {
+    text: String,
+    tooltip: String,
+    /// "active" while there is traffic, "idle" otherwise, for styling
+    class: &'static str,
+}
+
+/// Total rates and the busiest process, with more in the tooltip
+pub fn statusbar_line(snapshot: &ConnectionSnapshot, units: Units) -> String {
+    let stats = QuickStats::of(snapshot);
+    let top = TopTalkers::from_connections(&snapshot.connections, STATUSBAR_PROCESSES);
+    let mut text = format!(
+        "↑ {} ↓ {}",
+        units.format_rate(stats.tx_rate),
+        units.format_rate(stats.rx_rate)
+    );
+    if let Some(process) = top.processes.first() {
+        text.push_str(&format!(" {}", process.name));
+    }
+    let mut tooltip: Vec<String> = top
+        .processes
+        .iter()
+        .map(|process| {
+            format!(
+                "{}: ↑ {} ↓ {}",
+                process.name,
+                units.format_rate(process.tx_rate),
+                units.format_rate(process.rx_rate)
+            )
+        })
+        .collect();
+    tooltip.push(format!(
+        "{} connections, {} active",
+        stats.connections, stats.active
+    ));
+    tooltip.push(format!(
+        "Session: ↑ {} ↓ {}",
+        units.format_total(stats.sent),
+        units.format_total(stats.received)
+    ));
+    let line = StatusbarLine {
+        text,
+        tooltip: tooltip.join("\n"),
+        class: if stats.tx_rate + stats.rx_rate > 0 {
+            "active"
+        } else {
+            "idle"
+        },
+    };
+    serde_json::to_string(&line).unwrap_or_default()
+}
+
+/// Collector and resolver set up from `settings`, for the modes that print
+/// to stdout instead of starting an interface
+fn start_headless(
+    settings: &Settings,
+    default_resolve: bool,
+) -> (
+    ConnectionCollector,
+    async_channel::Receiver<CollectorEvent>,
+    AddressResolver,
+) {
+    let cache_limits = CacheLimits::from_env();
+    let (collector, events) = ConnectionCollector::spawn(settings.backend.clone(), cache_limits);
+    if settings.capture_sni {
+        collector.request(CollectorRequest::EnableCapture);
+    }
+    if !settings.geoip.is_empty() {
+        collector.request(CollectorRequest::EnableGeoIp(settings.geoip.clone()));
+    }
+    if !settings.lan.is_empty() {
+        collector.request(CollectorRequest::AddLanRanges(settings.lan.clone()));
+    }
+    let resolver = AddressResolver::new(settings.resolve_hostnames.unwrap_or(default_resolve));
+    resolver.set_host_names(settings.host_names.clone());
+    resolver.set_cache_limit(cache_limits.resolver_entries);
+    let _ = resolver.load_cache(&settings.paths.resolver_cache());
+    (collector, events, resolver)
+}
+
+fn next_snapshot(
+    collector: &ConnectionCollector,
+    events: &async_channel::Receiver<CollectorEvent>,
+) -> Result<ConnectionSnapshot> {
+    collector.request(CollectorRequest::Refresh {
+        group_by_application: false,
+    });
+    loop {
+        match events.recv_blocking() {
+            Ok(CollectorEvent::Snapshot(result)) => return Ok(*result.map_err(io::Error::other)?),
+            Ok(CollectorEvent::Capture(Err(e))) => tracing::warn!("{e}"),
+            Ok(CollectorEvent::GeoIp(Err(e))) => return Err(io::Error::other(e).into()),
+            Ok(
+                CollectorEvent::PrivilegedHelper(_)
+                | CollectorEvent::Capture(Ok(()))
+                | CollectorEvent::GeoIp(Ok(()))
+                | CollectorEvent::Link(_)
+                | CollectorEvent::SocketsChanged
+                | CollectorEvent::BandwidthLimit { .. }
+                | CollectorEvent::BackendSwitched { .. },
+            ) => continue,
+            Err(e) => return Err(io::Error::other(e).into()),
+        }
+    }
+}
+
+/// Write `connections` restricted to `columns` in the given format
+pub fn write_connections(
+    out: &mut impl Write,
+    connections: &[Connection],
+    columns: &[usize],
+    format: OutputFormat,
+    units: Units,
+    remote: impl Fn(&Connection) -> String,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            let rows: Vec<Vec<String>> = connections
+                .iter()
+                .map(|conn| {
+                    columns
+                        .iter()
+                        .map(|&column| match column {
+                            5 => units.format_rate(conn.tx_rate),
+                            6 => units.format_rate(conn.rx_rate),
+                            _ => cell_text(conn, column, units, &remote),
+                        })
+                        .collect()
+                })
+                .collect();
+            let titles: Vec<String> = columns
+                .iter()
+                .map(|&column| COLUMN_TITLES[column].to_string())
+                .collect();
+            let widths: Vec<usize> = (0..columns.len())
+                .map(|i| {
+                    std::iter::once(&titles)
+                        .chain(&rows)
+                        .map(|row| row[i].chars().count())
+                        .max()
+                        .unwrap_or(0)
+                })
+                .collect();
+            for row in std::iter::once(&titles).chain(&rows) {
+                let last = row.len().saturating_sub(1);
+                for (i, cell) in row.iter().enumerate() {
+                    if i == last {
+                        writeln!(out, "{cell}")?;
+                    } else {
+                        write!(out, "{cell:<width$}  ", width = widths[i])?;
+                    }
+                }
+            }
+        }
+        OutputFormat::Json => {
+            let rows: Vec<serde_json::Map<String, serde_json::Value>> = connections
+                .iter()
+                .map(|conn| {
+                    columns
+                        .iter()
+                        .map(|&column| {
+                            let value = match column {
+                                5 => units.count(conn.tx_rate).into(),
+                                6 => units.count(conn.rx_rate).into(),
+                                8 => conn.send_queue.into(),
+                                9 => conn.recv_queue.into(),
+                                10 => conn.direction.map(Direction::as_str).into(),
+                                11 => serde_json::to_value(conn.timer).unwrap_or_default(),
+                                12 => conn.scope.map(Scope::as_str).into(),
+                                13 => conn.host.as_deref().into(),
+                                _ => cell_text(conn, column, units, &remote).into(),
+                            };
+                            (COLUMN_NAMES[column].to_string(), value)
+                        })
+                        .collect()
+                })
+                .collect();
+            serde_json::to_writer_pretty(&mut *out, &rows)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let header: Vec<&str> = columns.iter().map(|&column| COLUMN_NAMES[column]).collect();
+            writeln!(out, "{}", header.join(","))?;
+            for conn in connections {
+                let row: Vec<String> = columns
+                    .iter()
+                    .map(|&column| csv_field(&cell_text(conn, column, units, &remote)))
+                    .collect();
+                writeln!(out, "{}", row.join(","))?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Write the programs of an audit and their destinations in the given
+/// format: grouped by program as text, one row per destination as CSV
+pub fn write_audit(
+    out: &mut impl Write,
+    programs: &[AuditProgram],
+    format: OutputFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Text => {
+            if programs.is_empty() {
+                writeln!(out, "No outbound connections")?;
+            }
+            for program in programs {
+                match &program.path {
+                    Some(path) => writeln!(out, "{} ({path})", program.program)?,
+                    None => writeln!(out, "{}", program.program)?,
+                }
+                for dest in &program.destinations {
+                    let endpoint = SocketAddr::new(dest.address, dest.port).to_string();
+                    let name = dest.host.as_ref().or(dest.server_name.as_ref());
+                    writeln!(
+                        out,
+                        "  {:<4} {:<40} {:<4} {:>5}  {}",
+                        dest.protocol,
+                        endpoint,
+                        dest.scope.map_or("", Scope::as_str),
+                        dest.connections,
+                        name.map_or("", String::as_str)
+                    )?;
+                }
+            }
+        }
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, programs)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            writeln!(
+                out,
+                "program,path,protocol,address,port,host,server_name,scope,connections"
+            )?;
+            for program in programs {
+                for dest in &program.destinations {
+                    let row = [
+                        program.program.clone(),
+                        program.path.clone().unwrap_or_default(),
+                        dest.protocol.to_string(),
+                        dest.address.to_string(),
+                        dest.port.to_string(),
+                        dest.host.clone().unwrap_or_default(),
+                        dest.server_name.clone().unwrap_or_default(),
+                        dest.scope.map_or("", Scope::as_str).to_string(),
+                        dest.connections.to_string(),
+                    ];
+                    let row: Vec<String> = row.iter().map(|field| csv_field(field)).collect();
+                    writeln!(out, "{}", row.join(","))?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Unformatted cell value; rates are plain bytes or bits per second
+fn cell_text(
+    conn: &Connection,
+    column: usize,
+    units: Units,
+    remote: &impl Fn(&Connection) -> String,
+) -> String {
+    match column {
+        0 => conn.get_process_display(),
+        1 => conn.protocol_label(),
+        2 => conn.local.to_string(),
+        3 => remote(conn),
+        4 => conn.state.to_string(),
+        5 => units.count(conn.tx_rate).to_string(),
+        6 => units.count(conn.rx_rate).to_string(),
+        8 => conn.send_queue.to_string(),
+        9 => conn.recv_queue.to_string(),
+        10 => conn.direction.map_or("", Direction::as_str).to_string(),
+        11 => conn.timer.map(|timer| timer.label()).unwrap_or_default(),
+        12 => conn.scope.map_or("", Scope::as_str).to_string(),
+        13 => conn.host.as_deref().unwrap_or_default().to_string(),
+        _ => conn.command.to_string(),
+    }
+}
+
+/// Session totals and rates of LAN and WAN traffic, and of VPN traffic once
+/// there has been some, e.g. "LAN ↑1.0KB/s ↓2.0KB/s (3.5 MB) · WAN …"
+pub fn scope_summary(scopes: &[ScopeTraffic], units: Units) -> String {
+    scopes
+        .iter()
+        .filter(|traffic| {
+            traffic.scope != Scope::Vpn || traffic.totals.sent + traffic.totals.received > 0
+        })
+        .map(|traffic| {
+            format!(
+                "{} ↑{} ↓{} ({})",
+                traffic.scope.label(),
+                units.format_rate(traffic.tx_rate),
+                units.format_rate(traffic.rx_rate),
+                units.format_total(traffic.totals.sent + traffic.totals.received)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(" · ")
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol, SocketTimer, TimerKind, TrafficTotals};
+    use clap::Parser;
+
+    #[derive(Parser)]
+    struct TestCli {
+        #[command(flatten)]
+        options: CommonArgs,
+    }
+
+    fn parse(args: &[&str]) -> std::result::Result<CommonArgs, clap::Error> {
+        TestCli::try_parse_from(std::iter::once("test").chain(args.iter().copied()))
+            .map(|cli| cli.options)
+    }
+
+    fn sample_connections() -> Vec<Connection> {
+        let mut firefox = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "10.0.0.2:51000",
+            "93.184.216.34:443",
+            "firefox",
+            "1200",
+            "/usr/lib/firefox/firefox",
+        );
+        firefox.rx_rate = 2048;
+        firefox.send_queue = 1448;
+        firefox.direction = Some(Direction::Outbound);
+        firefox.timer = Some(SocketTimer {
+            kind: TimerKind::Retransmit,
+            expires_ms: 1200,
+            retries: 3,
+        });
+        firefox.scope = Some(Scope::Wan);
+        let dns = Connection::new(
+            Protocol::Udp,
+            ConnectionState::Close,
+            "0.0.0.0:53",
+            "0.0.0.0:*",
+            "dnsmasq",
+            "300",
+            "dnsmasq --conf-file=/etc/dnsmasq.conf,extra",
+        );
+        vec![firefox, dns]
+    }
+
+    #[test]
+    fn test_parse_sort_spec() {
+        assert_eq!(
+            "program:asc".parse(),
+            Ok(SortSpec {
+                column: 0,
+                ascending: true
+            })
+        );
+        assert_eq!("download".parse(), Ok(SortSpec::default()));
+        assert!("rx:sideways".parse::<SortSpec>().is_err());
+        assert!("bandwidth".parse::<SortSpec>().is_err());
+    }
+
+    #[test]
+    fn test_parse_command_line() {
+        let args = parse(&[
+            "--refresh",
+            "0.5",
+            "--no-resolve",
+            "--filter",
+            "FireFox",
+            "--columns",
+            "program,remote,rx",
+            "--once",
+            "--output",
+            "csv",
+        ])
+        .unwrap();
+        assert_eq!(args.refresh, Some(Duration::from_millis(500)));
+        assert_eq!(args.columns, Some(vec![0, 3, 6]));
+        assert_eq!(args.output, Some(OutputFormat::Csv));
+
+        let settings = Settings::merge(&args, Config::default()).unwrap();
+        assert_eq!(settings.resolve_hostnames, Some(false));
+        assert_eq!(settings.filter.as_deref(), Some("firefox"));
+
+        // The socket queues are only shown when asked for
+        let settings = Settings::merge(&parse(&[]).unwrap(), Config::default()).unwrap();
+        assert_eq!(settings.columns(), DEFAULT_COLUMNS);
+        let args = parse(&["--columns", "remote,Send-Q,recvq"]).unwrap();
+        assert_eq!(args.columns, Some(vec![3, 8, 9]));
+        let args = parse(&["--columns", "dir,timers"]).unwrap();
+        assert_eq!(args.columns, Some(vec![10, 11]));
+        let args = parse(&["--columns", "network"]).unwrap();
+        assert_eq!(args.columns, Some(vec![12]));
+
+        let args = parse(&["--units", "bits", "--prefixes", "si", "--numbers", "plain"]).unwrap();
+        let settings = Settings::merge(&args, Config::default()).unwrap();
+        assert_eq!(settings.units(), Units::new(DataUnit::Bits, Prefixes::Si));
+        let config = Config {
+            numbers: Some("comma".to_string()),
+            ..Config::default()
+        };
+        let settings = Settings::merge(&parse(&[]).unwrap(), config).unwrap();
+        assert_eq!(settings.units().numbers, NumberStyle::Comma);
+        assert_eq!(settings.idle_after(), IDLE_AFTER);
+
+        let args = parse(&["--idle-minutes", "0", "--hide-idle"]).unwrap();
+        let settings = Settings::merge(&args, Config::default()).unwrap();
+        assert_eq!(settings.idle_after(), Duration::ZERO);
+        assert_eq!(settings.hide_idle, Some(true));
+
+        let settings = Settings::merge(&parse(&["-vv"]).unwrap(), Config::default()).unwrap();
+        assert_eq!(settings.log_level, Some(LevelFilter::DEBUG));
+        let args = parse(&["--log-level", "error"]).unwrap();
+        assert_eq!(args.log_level, Some(LevelFilter::ERROR));
+
+        assert!(parse(&["--refresh", "0"]).is_err());
+        assert_eq!(parse(&["--view", "dns"]).unwrap().view, Some(View::Dns));
+        assert_eq!(
+            parse(&["--view", "routes"]).unwrap().view,
+            Some(View::Routes)
+        );
+        assert_eq!(
+            parse(&["--view", "multicast"]).unwrap().view,
+            Some(View::Multicast)
+        );
+        assert_eq!(
+            parse(&["--view", "countries"]).unwrap().view,
+            Some(View::Countries)
+        );
+        assert!(parse(&["-v", "--log-level", "info"]).is_err());
+        assert!(parse(&["--units", "nibbles"]).is_err());
+        assert!(parse(&["--columns", "program,bogus"]).is_err());
+        // --output only applies to --once and --audit
+        assert!(parse(&["--output", "json"]).is_err());
+        let args = parse(&["--audit", "60", "--output", "csv"]).unwrap();
+        assert_eq!(args.audit, Some(60));
+        assert!(parse(&["--audit", "0"]).is_err());
+        assert!(parse(&["--audit", "5", "--once"]).is_err());
+        assert!(parse(&["--doctor"]).unwrap().doctor);
+        assert!(parse(&["--doctor", "--once"]).is_err());
+        assert!(parse(&["--doctor", "--output", "json"]).is_err());
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let config = Config {
+            refresh: Some(5.0),
+            resolve_hostnames: Some(true),
+            filter: Some("ssh".to_string()),
+            sort: Some("state".to_string()),
+            columns: Some(vec!["program".to_string(), "path".to_string()]),
+            backend: Some("netlink".to_string()),
+            idle_minutes: Some(5),
+            ..Config::default()
+        };
+        let args = parse(&["--sort", "tx:asc"]).unwrap();
+        let settings = Settings::merge(&args, config.clone()).unwrap();
+        assert_eq!(settings.refresh, Some(Duration::from_secs(5)));
+        assert_eq!(settings.backend, Backend::Netlink);
+        assert_eq!(settings.idle_after(), Duration::from_secs(300));
+        assert_eq!(settings.resolve_hostnames, Some(true));
+        assert_eq!(settings.filter.as_deref(), Some("ssh"));
+        assert_eq!(
+            settings.sort,
+            Some(SortSpec {
+                column: 5,
+                ascending: true
+            })
+        );
+        assert_eq!(settings.columns(), vec![0, 7]);
+        assert!(settings.geoip.is_empty());
+        let fixture = parse(&["--backend", "fixture:/tmp/capture.json"]).unwrap();
+        let settings = Settings::merge(&fixture, config.clone()).unwrap();
+        assert_eq!(
+            settings.backend,
+            Backend::Fixture(PathBuf::from("/tmp/capture.json"))
+        );
+
+        let bad = Config {
+            sort: Some("nowhere".to_string()),
+            ..config
+        };
+        assert!(Settings::merge(&args.clone(), bad.clone()).is_ok());
+        assert!(Settings::merge(&CommonArgs::default(), bad).is_err());
+    }
+
+    #[test]
+    fn test_host_names_from_config() {
+        let config = Config {
+            hosts: BTreeMap::from([
+                ("192.168.1.10".to_string(), "NAS".to_string()),
+                ("10.8.0.0/24".to_string(), "Office VPN".to_string()),
+            ]),
+            ..Config::default()
+        };
+        let settings = Settings::merge(&CommonArgs::default(), config).unwrap();
+        let name = |ip: &str| settings.host_names.name_of(ip.parse().unwrap());
+        assert_eq!(name("192.168.1.10"), Some("NAS"));
+        assert_eq!(name("10.8.0.42"), Some("Office VPN"));
+
+        let bad = Config {
+            hosts: BTreeMap::from([("nas.local".to_string(), "NAS".to_string())]),
+            ..Config::default()
+        };
+        let error = Settings::merge(&CommonArgs::default(), bad).unwrap_err();
+        assert!(error.to_string().contains("[hosts]"), "{error}");
+    }
+
+    #[test]
+    fn test_remotes_from_config() {
+        let config = Config {
+            remotes: BTreeMap::from([
+                ("web1".to_string(), "admin@web1.example.com".to_string()),
+                ("db".to_string(), "db".to_string()),
+            ]),
+            remote_command: Some("/opt/nm/nmt --serve".to_string()),
+            backend: Some("ssh:gateway".to_string()),
+            ..Config::default()
+        };
+        let settings = Settings::merge(&CommonArgs::default(), config).unwrap();
+        let names: Vec<&str> = settings.remotes.keys().map(String::as_str).collect();
+        assert_eq!(names, ["db", "web1"]);
+        assert_eq!(
+            settings.remotes["web1"],
+            Backend::Ssh {
+                destination: "admin@web1.example.com".to_string(),
+                command: "/opt/nm/nmt --serve".to_string()
+            }
+        );
+        assert_eq!(
+            settings.backend,
+            Backend::Ssh {
+                destination: "gateway".to_string(),
+                command: "/opt/nm/nmt --serve".to_string()
+            }
+        );
+        let hosts = settings.hosts();
+        let names: Vec<&str> = hosts.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, ["local", "db", "web1", "all"]);
+        assert_eq!(hosts[3].1, Backend::Combined(hosts[..3].to_vec()));
+
+        for (name, destination) in [("web1", " "), ("all", "web1")] {
+            let bad = Config {
+                remotes: BTreeMap::from([(name.to_string(), destination.to_string())]),
+                ..Config::default()
+            };
+            let error = Settings::merge(&CommonArgs::default(), bad).unwrap_err();
+            assert!(error.to_string().contains("[remotes]"), "{error}");
+        }
+        let alone = Settings::merge(&CommonArgs::default(), Config::default()).unwrap();
+        assert_eq!(alone.hosts().len(), 1);
+    }
+
+    #[test]
+    fn test_recording_schedule_from_config() {
+        let settings = Settings::merge(&CommonArgs::default(), Config::default()).unwrap();
+        assert_eq!(settings.recording, None);
+
+        let config = |recording| Config {
+            recording,
+            ..Config::default()
+        };
+        let settings = Settings::merge(
+            &CommonArgs::default(),
+            config(RecordingConfig {
+                every_minutes: Some(60),
+                record: Some("summary".to_string()),
+                ..RecordingConfig::default()
+            }),
+        )
+        .unwrap();
+        let schedule = settings.recording.unwrap();
+        assert_eq!(schedule.every, Duration::from_secs(3600));
+        assert_eq!(schedule.kind, RecordKind::Summary);
+        assert_eq!(schedule.format, OutputFormat::Json);
+        assert_eq!(schedule.dir, settings.paths.export_dir);
+
+        for bad in [
+            RecordingConfig {
+                every_minutes: Some(0),
+                ..RecordingConfig::default()
+            },
+            RecordingConfig {
+                every_minutes: Some(5),
+                format: Some("xml".to_string()),
+                ..RecordingConfig::default()
+            },
+        ] {
+            let error = Settings::merge(&CommonArgs::default(), config(bad)).unwrap_err();
+            assert!(error.to_string().contains("[recording]"), "{error}");
+        }
+    }
+
+    #[test]
+    fn test_profile_between_config_and_command_line() {
+        let path = std::env::temp_dir().join(format!("nm-profiles-{}.toml", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+            refresh = 2.0
+            view = "blocked"
+
+            [profiles.gaming]
+            refresh = 0.5
+            filter = "Steam"
+            "#,
+        )
+        .unwrap();
+        let config = path.to_str().unwrap();
+
+        let settings = parse(&["--config", config]).unwrap().settings().unwrap();
+        assert_eq!(settings.refresh, Some(Duration::from_secs(2)));
+        assert_eq!(settings.profiles["gaming"].filter.as_deref(), Some("steam"));
+
+        let args = parse(&["--config", config, "--profile", "gaming", "--refresh", "1"]).unwrap();
+        let settings = args.settings().unwrap();
+        assert_eq!(settings.profile.as_deref(), Some("gaming"));
+        assert_eq!(settings.refresh, Some(Duration::from_secs(1)));
+        assert_eq!(settings.filter.as_deref(), Some("steam"));
+        assert_eq!(settings.view, Some(View::Blocked));
+
+        let args = parse(&["--config", config, "--profile", "work"]).unwrap();
+        assert!(args.settings().is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_filter_matches_any_field() {
+        let args = parse(&["--filter", "DNSMASQ"]).unwrap();
+        let settings = Settings::merge(&args, Config::default()).unwrap();
+        let connections = sample_connections();
+        assert!(!settings.matches(&connections[0]));
+        assert!(settings.matches(&connections[1]));
+        assert!(Settings::default().matches(&connections[0]));
+    }
+
+    #[test]
+    fn test_write_csv_and_json() {
+        let connections = sample_connections();
+        let remote = |conn: &Connection| conn.remote.replace("93.184.216.34", "example.com");
+
+        let mut csv = Vec::new();
+        write_connections(
+            &mut csv,
+            &connections,
+            &[0, 3, 6, 7],
+            OutputFormat::Csv,
+            Units::default(),
+            remote,
+        )
+        .unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines[0], "program,remote,rx,command");
+        assert_eq!(
+            lines[1],
+            "firefox(1200),example.com:443,2048,/usr/lib/firefox/firefox"
+        );
+        assert!(lines[2].ends_with(",\"dnsmasq --conf-file=/etc/dnsmasq.conf,extra\""));
+
+        let mut json = Vec::new();
+        write_connections(
+            &mut json,
+            &connections,
+            &[0, 6],
+            OutputFormat::Json,
+            Units::default(),
+            remote,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["program"], "firefox(1200)");
+        assert_eq!(value[0]["rx"], 2048);
+        assert_eq!(value[1].as_object().unwrap().len(), 2);
+
+        let mut json = Vec::new();
+        write_connections(
+            &mut json,
+            &connections,
+            &[8, 9, 10, 11],
+            OutputFormat::Json,
+            Units::default(),
+            remote,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["sendq"], 1448);
+        assert_eq!(value[0]["recvq"], 0);
+        assert_eq!(value[0]["direction"], "outbound");
+        assert!(value[1]["direction"].is_null());
+        assert_eq!(value[0]["timer"]["kind"], "retransmit");
+        assert_eq!(value[0]["timer"]["retries"], 3);
+        assert!(value[1]["timer"].is_null());
+
+        let mut json = Vec::new();
+        write_connections(
+            &mut json,
+            &connections,
+            &[12],
+            OutputFormat::Json,
+            Units::default(),
+            remote,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["scope"], "wan");
+        assert!(value[1]["scope"].is_null());
+
+        // Exports follow the unit setting too
+        let mut json = Vec::new();
+        let bits = Units::new(DataUnit::Bits, Prefixes::Si);
+        write_connections(
+            &mut json,
+            &connections,
+            &[6],
+            OutputFormat::Json,
+            bits,
+            remote,
+        )
+        .unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["rx"], 16384);
+
+        // but not the number style of the locale
+        let comma = Units::default().with_numbers(NumberStyle::Comma);
+        for format in [OutputFormat::Json, OutputFormat::Csv] {
+            let export = |units| {
+                let mut out = Vec::new();
+                write_connections(&mut out, &connections, &[5, 6, 8], format, units, remote)
+                    .unwrap();
+                out
+            };
+            assert_eq!(export(comma), export(Units::default()));
+        }
+    }
+
+    #[test]
+    fn test_write_text_aligns_columns() {
+        let connections = sample_connections();
+        let mut text = Vec::new();
+        write_connections(
+            &mut text,
+            &connections,
+            &[0, 1],
+            OutputFormat::Text,
+            Units::default(),
+            |conn| conn.remote.to_string(),
+        )
+        .unwrap();
+        let text = String::from_utf8(text).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "Process(ID)    Protocol");
+        assert_eq!(lines[1], "firefox(1200)  tcp");
+
+        // Text is for reading, so it follows the number style
+        let mut text = Vec::new();
+        write_connections(
+            &mut text,
+            &connections,
+            &[6],
+            OutputFormat::Text,
+            Units::default().with_numbers(NumberStyle::Comma),
+            |conn| conn.remote.to_string(),
+        )
+        .unwrap();
+        assert!(String::from_utf8(text).unwrap().contains("2,0KB/s"));
+    }
+
+    #[test]
+    fn test_write_audit_report() {
+        let mut conn = sample_connections().remove(0);
+        conn.remote = "[2606:2800:220:1::1]:443".into();
+        let mut dest = AuditDestination::of(&conn).unwrap();
+        dest.host = Some("example.com".to_string());
+        dest.connections = 4;
+        let (program, path) = AuditProgram::identity(&conn);
+        let report = [AuditProgram {
+            program,
+            path,
+            destinations: vec![dest],
+        }];
+
+        let mut text = Vec::new();
+        write_audit(&mut text, &report, OutputFormat::Text).unwrap();
+        let text = String::from_utf8(text).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert_eq!(lines[0], "firefox (/usr/lib/firefox/firefox)");
+        assert!(lines[1].starts_with("  tcp  [2606:2800:220:1::1]:443"));
+        assert!(lines[1].ends_with(" wan      4  example.com"));
+
+        let mut csv = Vec::new();
+        write_audit(&mut csv, &report, OutputFormat::Csv).unwrap();
+        let csv = String::from_utf8(csv).unwrap();
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("firefox,/usr/lib/firefox/firefox,tcp,2606:2800:220:1::1,443,example.com,,wan,4")
+        );
+
+        let mut json = Vec::new();
+        write_audit(&mut json, &report, OutputFormat::Json).unwrap();
+        let value: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        assert_eq!(value[0]["destinations"][0]["port"], 443);
+        assert_eq!(value[0]["destinations"][0]["host"], "example.com");
+    }
+
+    #[test]
+    fn test_statusbar_line_is_waybar_json() {
+        let snapshot = ConnectionSnapshot::new(
+            sample_connections(),
+            TrafficTotals {
+                sent: 0,
+                received: 4096,
+            },
+        );
+        let line = statusbar_line(&snapshot, Units::default());
+        assert!(!line.contains('\n'));
+        let value: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(value["text"], "↑ 0.0B/s ↓ 2.0KB/s firefox(1200)");
+        assert_eq!(
+            value["tooltip"],
+            "firefox(1200): ↑ 0.0B/s ↓ 2.0KB/s\n2 connections, 1 active\nSession: ↑ 0.0 B ↓ 4.0 KB"
+        );
+        assert_eq!(value["class"], "active");
+
+        let idle = ConnectionSnapshot::new(Vec::new(), TrafficTotals::default());
+        let value: serde_json::Value =
+            serde_json::from_str(&statusbar_line(&idle, Units::default())).unwrap();
+        assert_eq!(value["text"], "↑ 0.0B/s ↓ 0.0B/s");
+        assert_eq!(value["class"], "idle");
+    }
+
+    #[test]
+    fn test_top_processes_busiest_first() {
+        let mut connections = sample_connections();
+        connections[1].tx_rate = 4096;
+        assert_eq!(
+            top_processes(&connections, 5, Units::default()),
+            "dnsmasq(300)                4.0KB/s     0.0B/s\n\
+             firefox(1200)                0.0B/s    2.0KB/s"
+        );
+        assert_eq!(
+            top_processes(&connections, 1, Units::default())
+                .lines()
+                .count(),
+            1
+        );
+        assert_eq!(top_processes(&[], 5, Units::default()), "");
+    }
+
+    #[test]
+    fn test_scope_summary_skips_unused_vpn() {
+        let mut scopes = Scope::ALL.map(ScopeTraffic::new);
+        scopes[0].rx_rate = 2048;
+        scopes[0].totals.received = 512;
+        assert_eq!(
+            scope_summary(&scopes, Units::default()),
+            "LAN ↑0.0B/s ↓2.0KB/s (512.0 B) · WAN ↑0.0B/s ↓0.0B/s (0.0 B)"
+        );
+        scopes[2].totals.sent = 1;
+        assert!(
+            scope_summary(&scopes, Units::default()).ends_with(" · VPN ↑0.0B/s ↓0.0B/s (1.0 B)")
+        );
+    }
+}
@@ -0,0 +1,611 @@
+//! Comparison of two recorded sessions, each a range of the JSON snapshots
+//! the `[recording]` section writes (`--once --output json` files work too):
+//! the destinations only the second session reached, the listening ports it
+//! no longer had, and how the traffic of each program changed
+
+use crate::utils::formatter::Units;
+use crate::utils::parse_endpoint;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs::{self, File};
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// The columns of a recorded snapshot row a comparison looks at
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct Row {
+    program: String,
+    protocol: String,
+    local: String,
+    remote: String,
+    state: String,
+    tx: u64,
+    rx: u64,
+}
+
+/// The snapshots of one session, oldest first
+#[derive(Debug, Clone, Default)]
+pub struct Session {
+    /// Times of the first and last snapshot, from their file names
+    first: String,
+    last: String,
+    snapshots: Vec<Vec<Row>>,
+}
+
+impl Session {
+    /// Read the session `spec` names: a snapshot file, or a directory of
+    /// recorded snapshots narrowed down to a UTC range with
+    /// `DIR@2026-10-14..2026-10-15T12`. Either end of the range can be left
+    /// out, and a single time covers everything it is a prefix of, so
+    /// `@2026-10-14` is that whole day. An empty DIR is `default_dir`.
+    pub fn load(spec: &str, default_dir: &Path) -> io::Result<Self> {
+        let (path, range) = match spec.rsplit_once('@') {
+            Some((path, range)) => (path, range),
+            None => (spec, ""),
+        };
+        let (from, to) = range.split_once("..").unwrap_or((range, range));
+        let (from, to) = (stamp_prefix(from), stamp_prefix(to));
+        let path = if path.is_empty() {
+            default_dir.to_path_buf()
+        } else {
+            PathBuf::from(path)
+        };
+
+        let mut files: Vec<(String, PathBuf)> = if path.is_dir() {
+            fs::read_dir(&path)?
+                .filter_map(|entry| {
+                    let entry = entry.ok()?;
+                    let name = entry.file_name().into_string().ok()?;
+                    let stamp = name.strip_prefix("snapshot-")?.strip_suffix(".json")?;
+                    in_range(stamp, &from, &to).then(|| (stamp.to_string(), entry.path()))
+                })
+                .collect()
+        } else {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            let stamp = name.strip_prefix("snapshot-").unwrap_or(&name);
+            vec![(stamp.trim_end_matches(".json").to_string(), path.clone())]
+        };
+        files.sort();
+        let (Some((first, _)), Some((last, _))) = (files.first(), files.last()) else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("no recorded JSON snapshots in {spec}"),
+            ));
+        };
+        let (first, last) = (first.clone(), last.clone());
+
+        let snapshots = files
+            .into_iter()
+            .map(|(_, file)| {
+                let reader = BufReader::new(File::open(&file)?);
+                serde_json::from_reader(reader).map_err(|e| {
+                    io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("{}: {e}", file.display()),
+                    )
+                })
+            })
+            .collect::<io::Result<_>>()?;
+        Ok(Self {
+            first,
+            last,
+            snapshots,
+        })
+    }
+
+    fn summary(&self) -> Summary {
+        let mut summary = Summary::default();
+        let mut totals: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        for rows in &self.snapshots {
+            // Sockets of a process all carry its rate, so it counts once;
+            // rows without a PID have rates of their own
+            let mut processes: HashMap<&str, (u64, u64)> = HashMap::new();
+            for row in rows {
+                let (program, has_pid) = program_name(&row.program);
+                if is_listener(row) {
+                    if let Some(port) = row
+                        .local
+                        .rsplit_once(':')
+                        .and_then(|(_, port)| port.parse().ok())
+                    {
+                        summary
+                            .listening
+                            .entry((transport(&row.protocol), port))
+                            .or_default()
+                            .insert(program.to_string());
+                    }
+                } else if !is_loopback(&row.remote) {
+                    summary
+                        .destinations
+                        .entry(row.remote.clone())
+                        .or_default()
+                        .insert(program.to_string());
+                }
+                let (tx, rx) = processes.entry(&row.program).or_default();
+                if has_pid {
+                    *tx = (*tx).max(row.tx);
+                    *rx = (*rx).max(row.rx);
+                } else {
+                    *tx += row.tx;
+                    *rx += row.rx;
+                }
+            }
+            for (process, (tx, rx)) in processes {
+                let total = totals
+                    .entry(program_name(process).0.to_string())
+                    .or_default();
+                total.0 += tx;
+                total.1 += rx;
+            }
+        }
+        let snapshots = self.snapshots.len().max(1) as u64;
+        summary.traffic = totals
+            .into_iter()
+            .map(|(program, (tx, rx))| (program, (tx / snapshots, rx / snapshots)))
+            .collect();
+        summary
+    }
+}
+
+/// What a session reached, listened on and transferred
+#[derive(Debug, Default)]
+struct Summary {
+    /// Remote endpoints connected to, with the programs that did
+    destinations: BTreeMap<String, BTreeSet<String>>,
+    /// Ports listened on by transport, with the programs listening
+    listening: BTreeMap<(String, u16), BTreeSet<String>>,
+    /// Average upload and download rates of each program
+    traffic: BTreeMap<String, (u64, u64)>,
+}
+
+/// How the traffic of one program changed between the sessions
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficChange {
+    pub program: String,
+    /// Average upload and download rates, as recorded
+    pub before: (u64, u64),
+    pub after: (u64, u64),
+}
+
+impl TrafficChange {
+    /// Change of the combined rate, what changes are ranked by
+    fn change(&self) -> i128 {
+        i128::from(self.after.0 + self.after.1) - i128::from(self.before.0 + self.before.1)
+    }
+}
+
+/// The differences between two sessions
+#[derive(Debug)]
+pub struct Comparison {
+    before: Session,
+    after: Session,
+    /// Destinations only reached after, with the programs that did
+    pub new_destinations: Vec<(String, Vec<String>)>,
+    /// Transports and ports only listened on before, with the programs that
+    /// did
+    pub removed_ports: Vec<(String, u16, Vec<String>)>,
+    /// Biggest change first
+    pub traffic: Vec<TrafficChange>,
+}
+
+/// A titled table of a report
+struct Section {
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Comparison {
+    pub fn new(before: Session, after: Session) -> Self {
+        let (old, new) = (before.summary(), after.summary());
+        let new_destinations = new
+            .destinations
+            .iter()
+            .filter(|(destination, _)| !old.destinations.contains_key(*destination))
+            .map(|(destination, programs)| {
+                (destination.clone(), programs.iter().cloned().collect())
+            })
+            .collect();
+        let removed_ports = old
+            .listening
+            .iter()
+            .filter(|(port, _)| !new.listening.contains_key(*port))
+            .map(|((transport, port), programs)| {
+                (transport.clone(), *port, programs.iter().cloned().collect())
+            })
+            .collect();
+        let programs: BTreeSet<&String> = old.traffic.keys().chain(new.traffic.keys()).collect();
+        let mut traffic: Vec<TrafficChange> = programs
+            .into_iter()
+            .map(|program| TrafficChange {
+                program: program.clone(),
+                before: old.traffic.get(program).copied().unwrap_or_default(),
+                after: new.traffic.get(program).copied().unwrap_or_default(),
+            })
+            .filter(|change| change.before != (0, 0) || change.after != (0, 0))
+            .collect();
+        traffic.sort_by_key(|change| std::cmp::Reverse(change.change().abs()));
+        Self {
+            before,
+            after,
+            new_destinations,
+            removed_ports,
+            traffic,
+        }
+    }
+
+    /// Rates are taken as recorded with the same `units`, so recordings
+    /// made in bits are shown in bits
+    fn sections(&self, units: Units) -> Vec<Section> {
+        let rate = |recorded: u64| units.format_rate(recorded / units.count(1).max(1));
+        let change = |change: &TrafficChange| {
+            let sign = if change.change() < 0 { "-" } else { "+" };
+            let amount = u64::try_from(change.change().unsigned_abs()).unwrap_or(u64::MAX);
+            format!("{sign}{}", rate(amount))
+        };
+        vec![
+            Section {
+                title: "Sessions",
+                headers: vec!["", "Before", "After"],
+                rows: vec![
+                    vec![
+                        "Snapshots".to_string(),
+                        self.before.snapshots.len().to_string(),
+                        self.after.snapshots.len().to_string(),
+                    ],
+                    vec![
+                        "First".to_string(),
+                        display_stamp(&self.before.first),
+                        display_stamp(&self.after.first),
+                    ],
+                    vec![
+                        "Last".to_string(),
+                        display_stamp(&self.before.last),
+                        display_stamp(&self.after.last),
+                    ],
+                ],
+            },
+            Section {
+                title: "New Destinations",
+                headers: vec!["Destination", "Programs"],
+                rows: self
+                    .new_destinations
+                    .iter()
+                    .map(|(destination, programs)| vec![destination.clone(), programs.join(", ")])
+                    .collect(),
+            },
+            Section {
+                title: "Removed Listening Ports",
+                headers: vec!["Port", "Protocol", "Programs"],
+                rows: self
+                    .removed_ports
+                    .iter()
+                    .map(|(transport, port, programs)| {
+                        vec![port.to_string(), transport.clone(), programs.join(", ")]
+                    })
+                    .collect(),
+            },
+            Section {
+                title: "Traffic by Program",
+                headers: vec![
+                    "Program",
+                    "Upload Before",
+                    "Upload After",
+                    "Download Before",
+                    "Download After",
+                    "Change",
+                ],
+                rows: self
+                    .traffic
+                    .iter()
+                    .map(|traffic| {
+                        vec![
+                            traffic.program.clone(),
+                            rate(traffic.before.0),
+                            rate(traffic.after.0),
+                            rate(traffic.before.1),
+                            rate(traffic.after.1),
+                            change(traffic),
+                        ]
+                    })
+                    .collect(),
+            },
+        ]
+    }
+
+    pub fn write_markdown(&self, out: &mut impl Write, units: Units) -> io::Result<()> {
+        writeln!(out, "# Session Comparison")?;
+        for section in self.sections(units) {
+            writeln!(out, "\n## {}\n", section.title)?;
+            if section.rows.is_empty() {
+                writeln!(out, "None")?;
+                continue;
+            }
+            let row = |cells: &[String]| {
+                let cells: Vec<String> =
+                    cells.iter().map(|cell| cell.replace('|', "\\|")).collect();
+                format!("| {} |", cells.join(" | "))
+            };
+            let headers: Vec<String> = section.headers.iter().map(|h| h.to_string()).collect();
+            writeln!(out, "{}", row(&headers))?;
+            writeln!(out, "|{}", "---|".repeat(headers.len()))?;
+            for cells in &section.rows {
+                writeln!(out, "{}", row(cells))?;
+            }
+        }
+        Ok(())
+    }
+
+    /// A standalone page, styled just enough to read in a browser or paste
+    /// into a report
+    pub fn write_html(&self, out: &mut impl Write, units: Units) -> io::Result<()> {
+        writeln!(out, "<!DOCTYPE html>")?;
+        writeln!(out, "<html>\n<head>\n<meta charset=\"utf-8\">")?;
+        writeln!(out, "<title>Session Comparison</title>")?;
+        writeln!(
+            out,
+            "<style>body {{ font-family: sans-serif; }} \
+             table {{ border-collapse: collapse; }} \
+             th, td {{ border: 1px solid #ccc; padding: 0.2em 0.6em; text-align: left; }}</style>"
+        )?;
+        writeln!(out, "</head>\n<body>\n<h1>Session Comparison</h1>")?;
+        for section in self.sections(units) {
+            writeln!(out, "<h2>{}</h2>", section.title)?;
+            if section.rows.is_empty() {
+                writeln!(out, "<p>None</p>")?;
+                continue;
+            }
+            writeln!(out, "<table>")?;
+            let headers: String = section
+                .headers
+                .iter()
+                .map(|header| format!("<th>{}</th>", escape_html(header)))
+                .collect();
+            writeln!(out, "<tr>{headers}</tr>")?;
+            for cells in &section.rows {
+                let cells: String = cells
+                    .iter()
+                    .map(|cell| format!("<td>{}</td>", escape_html(cell)))
+                    .collect();
+                writeln!(out, "<tr>{cells}</tr>")?;
+            }
+            writeln!(out, "</table>")?;
+        }
+        writeln!(out, "</body>\n</html>")
+    }
+}
+
+/// A time as written in snapshot file names, "20261014T13" for
+/// "2026-10-14T13"
+fn stamp_prefix(time: &str) -> String {
+    time.trim()
+        .chars()
+        .filter(|c| !matches!(c, '-' | ':'))
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// Whether a snapshot taken at `stamp` falls between `from` and `to`, both
+/// prefixes of stamps and empty when open
+fn in_range(stamp: &str, from: &str, to: &str) -> bool {
+    stamp >= from && (to.is_empty() || stamp.get(..to.len()).unwrap_or(stamp) <= to)
+}
+
+/// "2026-10-14 15:04:05 UTC" for "20261014T150405Z", other names as they are
+fn display_stamp(stamp: &str) -> String {
+    let digits = |range: std::ops::Range<usize>| {
+        stamp
+            .get(range)
+            .filter(|part| part.bytes().all(|b| b.is_ascii_digit()))
+    };
+    match (
+        digits(0..4),
+        digits(4..6),
+        digits(6..8),
+        digits(9..11),
+        digits(11..13),
+        digits(13..15),
+    ) {
+        (Some(year), Some(month), Some(day), Some(hour), Some(minute), Some(second))
+            if stamp.len() == 16 =>
+        {
+            format!("{year}-{month}-{day} {hour}:{minute}:{second} UTC")
+        }
+        _ => stamp.to_string(),
+    }
+}
+
+/// Program of a "name(pid)" cell, and whether it had a PID
+fn program_name(cell: &str) -> (&str, bool) {
+    match cell
+        .strip_suffix(')')
+        .and_then(|rest| rest.rsplit_once('('))
+    {
+        Some((name, pid)) if !pid.is_empty() && pid.bytes().all(|b| b.is_ascii_digit()) => {
+            (name, true)
+        }
+        _ => (cell, false),
+    }
+}
+
+/// "tcp" for "tcp6" and "udp" for "udp (QUIC)"
+fn transport(protocol: &str) -> String {
+    protocol
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .trim_end_matches('6')
+        .to_lowercase()
+}
+
+/// Listening TCP sockets and UDP sockets without a peer; remote ends are
+/// often hostnames in recordings, which always have a peer
+fn is_listener(row: &Row) -> bool {
+    row.state == "LISTEN"
+        || row.remote.ends_with(":*")
+        || parse_endpoint(&row.remote)
+            .is_some_and(|remote| remote.ip().is_unspecified() || remote.port() == 0)
+}
+
+fn is_loopback(remote: &str) -> bool {
+    remote.starts_with("localhost:")
+        || parse_endpoint(remote).is_some_and(|remote| remote.ip().to_canonical().is_loopback())
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(program: &str, protocol: &str, local: &str, remote: &str, state: &str) -> Row {
+        Row {
+            program: program.to_string(),
+            protocol: protocol.to_string(),
+            local: local.to_string(),
+            remote: remote.to_string(),
+            state: state.to_string(),
+            ..Row::default()
+        }
+    }
+
+    fn rates(mut row: Row, tx: u64, rx: u64) -> Row {
+        row.tx = tx;
+        row.rx = rx;
+        row
+    }
+
+    fn session(snapshots: Vec<Vec<Row>>) -> Session {
+        Session {
+            first: "20261014T130000Z".to_string(),
+            last: "20261014T140000Z".to_string(),
+            snapshots,
+        }
+    }
+
+    #[test]
+    fn test_ranges_match_stamp_prefixes() {
+        let (from, to) = (stamp_prefix("2026-10-14"), stamp_prefix("2026-10-15T12"));
+        assert_eq!(to, "20261015T12");
+        assert!(in_range("20261014T000000Z", &from, &to));
+        assert!(in_range("20261015T125959Z", &from, &to));
+        assert!(!in_range("20261013T235959Z", &from, &to));
+        assert!(!in_range("20261015T130000Z", &from, &to));
+        assert!(in_range("20261015T130000Z", &from, ""));
+        assert_eq!(display_stamp("20261014T150405Z"), "2026-10-14 15:04:05 UTC");
+        assert_eq!(display_stamp("before"), "before");
+    }
+
+    #[test]
+    fn test_sessions_load_from_recordings() {
+        let dir = std::env::temp_dir().join(format!("nm-comparison-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let snapshot = r#"[{"program": "sshd(812)", "protocol": "tcp", "local": "0.0.0.0:22",
+            "remote": "0.0.0.0:*", "state": "LISTEN", "tx": 0, "rx": 0}]"#;
+        for stamp in ["20261013T230000Z", "20261014T000000Z", "20261014T010000Z"] {
+            fs::write(dir.join(format!("snapshot-{stamp}.json")), snapshot).unwrap();
+        }
+        fs::write(dir.join("snapshot-20261014T020000Z.csv"), "program\n").unwrap();
+
+        let day = Session::load("@2026-10-14", &dir).unwrap();
+        assert_eq!(day.snapshots.len(), 2);
+        assert_eq!(day.first, "20261014T000000Z");
+        assert_eq!(day.last, "20261014T010000Z");
+        let all = Session::load(&dir.display().to_string(), Path::new("/nonexistent")).unwrap();
+        assert_eq!(all.snapshots.len(), 3);
+        let file = dir.join("snapshot-20261013T230000Z.json");
+        assert_eq!(
+            Session::load(&file.display().to_string(), &dir)
+                .unwrap()
+                .first,
+            "20261013T230000Z"
+        );
+        assert!(Session::load("@2026-10-15", &dir).is_err());
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_comparison_finds_changes() {
+        let sshd = row("sshd(812)", "tcp", "0.0.0.0:22", "0.0.0.0:*", "LISTEN");
+        let dns = row("dnsmasq(300)", "udp", "0.0.0.0:53", "0.0.0.0:*", "CLOSE");
+        let firefox = |remote: &str, rx| {
+            rates(
+                row(
+                    "firefox(1200)",
+                    "tcp",
+                    "10.0.0.2:51000",
+                    remote,
+                    "ESTABLISHED",
+                ),
+                0,
+                rx,
+            )
+        };
+        let before = session(vec![
+            vec![sshd.clone(), dns, firefox("example.com:443", 1000)],
+            vec![sshd.clone(), firefox("example.com:443", 3000)],
+        ]);
+        let after = session(vec![vec![
+            sshd,
+            firefox("example.com:443", 500),
+            // A second socket of the same process carries the same rate
+            firefox("tracker.example.net:443", 500),
+            rates(
+                row(
+                    "curl(77)",
+                    "tcp",
+                    "127.0.0.1:40000",
+                    "127.0.0.1:8080",
+                    "ESTABLISHED",
+                ),
+                100,
+                0,
+            ),
+        ]]);
+        let comparison = Comparison::new(before, after);
+        assert_eq!(
+            comparison.new_destinations,
+            [(
+                "tracker.example.net:443".to_string(),
+                vec!["firefox".to_string()]
+            )]
+        );
+        assert_eq!(
+            comparison.removed_ports,
+            [("udp".to_string(), 53, vec!["dnsmasq".to_string()])]
+        );
+        let change = |program: &str, before, after| TrafficChange {
+            program: program.to_string(),
+            before,
+            after,
+        };
+        assert_eq!(
+            comparison.traffic,
+            [
+                change("firefox", (0, 2000), (0, 500)),
+                change("curl", (0, 0), (100, 0))
+            ]
+        );
+
+        let mut markdown = Vec::new();
+        comparison
+            .write_markdown(&mut markdown, Units::default())
+            .unwrap();
+        let markdown = String::from_utf8(markdown).unwrap();
+        assert!(markdown.contains("| Snapshots | 2 | 1 |"), "{markdown}");
+        assert!(markdown.contains("| tracker.example.net:443 | firefox |"));
+        assert!(markdown.contains("| firefox | 0.0B/s | 0.0B/s | 2.0KB/s | 500.0B/s | -1.5KB/s |"));
+
+        let mut html = Vec::new();
+        comparison.write_html(&mut html, Units::default()).unwrap();
+        let html = String::from_utf8(html).unwrap();
+        assert!(html.contains("<tr><td>53</td><td>udp</td><td>dnsmasq</td></tr>"));
+        assert!(html.trim_end().ends_with("</html>"));
+    }
+}
@@ -0,0 +1,364 @@
+use crate::error::{NetworkMonitorError, Result};
+use crate::paths::Paths;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+
+/// Initial config file: every key commented out with its default
+const TEMPLATE: &str = r#"# Network Monitor settings, read by network-monitor and nmt at startup.
+# Command line options override these; uncomment a key to change it.
+
+# Seconds between automatic refreshes (3 in the window, 2 in the TUI)
+# refresh = 3
+# resolve_hostnames = true
+# filter = "firefox"
+# sort = "rx:desc"
+# Shown columns; "sendq" and "recvq" add the socket queues, "direction"
+# whether each connection is inbound or outbound, "timer" the retransmit or
+# keepalive timer of TCP sockets
+# columns = ["program", "protocol", "local", "remote", "state", "tx", "rx", "command"]
+# units = "bytes"        # or "bits"
+# prefixes = "jedec"     # or "si", "iec"
+# numbers = "locale"     # or "plain" (1234.5), "point" (1,234.5),
+#                        # "comma" (1.234,5), "space" (1 234,5)
+# view = "connections"   # or "blocked", "dns", "routes", "events", "multicast",
+#                        # "countries"
+# Gray out connections without traffic or state changes for this many
+# minutes (0 never does), or leave them out with hide_idle
+# idle_minutes = 10
+# hide_idle = false
+# backend = "procfs"     # or "netlink", "libproc", "android", "router", "fixture:PATH",
+#                        # "ssh:HOST"
+# Name HTTPS servers from the TLS handshakes of new connections; needs root
+# or CAP_NET_RAW
+# capture_sni = false
+# CSV files of IP ranges by country, such as Tor's geoip and geoip6, for
+# the countries view
+# geoip = ["/usr/share/tor/geoip", "/usr/share/tor/geoip6"]
+# log_level = "warn"
+# Ranges counted as LAN besides the private, link-local and loopback ones and
+# the networks of local interfaces, e.g. a mesh VPN's CGNAT range
+# lan = ["100.64.0.0/10"]
+
+# Names shown instead of addresses, in the tables and ahead of DNS names,
+# for single hosts or whole networks
+# [hosts]
+# "192.168.1.10" = "NAS"
+# "10.8.0.0/24" = "Office VPN"
+
+# Other machines to switch to from the host selector, collected by running
+# nmt --serve there over SSH; ssh must log in without a password prompt
+# remote_command = "nmt --serve"
+# [remotes]
+# web1 = "admin@web1.example.com"
+# db = "db-alias-from-ssh-config"
+
+# Record the connections on a schedule, including while the window only runs
+# in the background (--background)
+# [recording]
+# every_minutes = 60     # aligned to the clock: on the hour
+# record = "snapshot"    # or "summary", a line of totals added to summary.csv
+# format = "json"        # of snapshots: or "csv", "text"
+# dir = "~/network-recordings"  # instead of the export directory
+
+# Presets selected with --profile NAME or from the window menu
+# [profiles.gaming]
+# refresh = 1
+# filter = "steam"
+
+# TUI key bindings by action
+# [keys]
+# quit = ["q", "Ctrl+c"]
+
+# Directories replacing the XDG defaults
+# [paths]
+# cache_dir = "~/.cache/network-monitor"
+"#;
+
+/// Settings file shared by both binaries. Every key is optional; command line
+/// options take precedence over it.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Seconds between automatic refreshes
+    pub refresh: Option<f64>,
+    pub resolve_hostnames: Option<bool>,
+    pub filter: Option<String>,
+    /// "COLUMN" or "COLUMN:asc|desc"
+    pub sort: Option<String>,
+    pub columns: Option<Vec<String>>,
+    /// "bytes" or "bits"
+    pub units: Option<String>,
+    /// "jedec", "si" or "iec"
+    pub prefixes: Option<String>,
+    /// "locale", "plain", "point", "comma" or "space"
+    pub numbers: Option<String>,
+    /// "connections" or "blocked"
+    pub view: Option<String>,
+    /// Minutes without activity before a connection counts as idle, 0 for never
+    pub idle_minutes: Option<u64>,
+    /// Leave idle connections out instead of dimming them
+    pub hide_idle: Option<bool>,
+    /// "procfs", "netlink", "fixture:PATH" or "ssh:HOST"
+    pub backend: Option<String>,
+    /// Capture TLS ClientHellos for server names
+    pub capture_sni: Option<bool>,
+    /// GeoIP range files to look up remote countries in
+    pub geoip: Option<Vec<PathBuf>>,
+    /// "ADDRESS/PREFIX" ranges counted as LAN on top of the private ones
+    pub lan: Option<Vec<String>>,
+    /// Profile applied when --profile isn't given
+    pub profile: Option<String>,
+    /// Named presets from `[profiles.NAME]` sections
+    pub profiles: BTreeMap<String, Profile>,
+    /// Names by address or "ADDRESS/PREFIX" network from the `[hosts]` section
+    pub hosts: BTreeMap<String, String>,
+    /// SSH destinations of other machines by name, from the `[remotes]`
+    /// section
+    pub remotes: BTreeMap<String, String>,
+    /// Collector run on remote machines, "nmt --serve" by default
+    pub remote_command: Option<String>,
+    /// "off", "error", "warn", "info", "debug" or "trace"
+    pub log_level: Option<String>,
+    pub log_file: Option<PathBuf>,
+    /// TUI key bindings by action, e.g. `quit = "x"` or `quit = ["x", "Ctrl+c"]`
+    pub keys: BTreeMap<String, KeyList>,
+    pub paths: PathsConfig,
+    pub recording: RecordingConfig,
+}
+
+/// Display settings bundled under a name, e.g. `[profiles.gaming]`. Keys set
+/// here replace the top-level ones while the profile is selected.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Profile {
+    pub refresh: Option<f64>,
+    pub resolve_hostnames: Option<bool>,
+    pub filter: Option<String>,
+    pub sort: Option<String>,
+    pub columns: Option<Vec<String>>,
+    pub units: Option<String>,
+    pub prefixes: Option<String>,
+    pub view: Option<String>,
+    pub idle_minutes: Option<u64>,
+    pub hide_idle: Option<bool>,
+}
+
+/// Directories replacing the XDG defaults; a leading "~" is expanded
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathsConfig {
+    pub cache_dir: Option<PathBuf>,
+    pub data_dir: Option<PathBuf>,
+    pub state_dir: Option<PathBuf>,
+    pub export_dir: Option<PathBuf>,
+}
+
+/// Snapshots or summaries written on a schedule; nothing is recorded without
+/// `every_minutes`
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RecordingConfig {
+    pub every_minutes: Option<u64>,
+    /// "snapshot" or "summary"
+    pub record: Option<String>,
+    /// "text", "json" or "csv", for snapshots
+    pub format: Option<String>,
+    /// Directory written to instead of the export directory
+    pub dir: Option<PathBuf>,
+}
+
+/// One key or several for the same action
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    pub fn into_vec(self) -> Vec<String> {
+        match self {
+            KeyList::One(key) => vec![key],
+            KeyList::Many(keys) => keys,
+        }
+    }
+}
+
+impl Config {
+    /// Read `path`, or the default location when None. A missing default file
+    /// is an empty config; a missing explicit one is an error.
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Paths::config_file() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::parse(&text)
+                .map_err(|e| NetworkMonitorError::Config(format!("{}: {e}", path.display()))),
+            Err(e) if e.kind() == ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(NetworkMonitorError::Config(format!(
+                "{}: {e}",
+                path.display()
+            ))),
+        }
+    }
+
+    /// Write the commented template to `path` unless a file is already there.
+    /// Returns whether it was written.
+    pub fn write_template(path: &Path) -> io::Result<bool> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        match fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(path)
+        {
+            Ok(mut file) => file.write_all(TEMPLATE.as_bytes()).map(|()| true),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// This config with the keys of profile `name` applied over the top level
+    pub fn with_profile(&self, name: &str) -> Result<Self> {
+        let profile = self.profiles.get(name).cloned().ok_or_else(|| {
+            let available: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            NetworkMonitorError::Config(if available.is_empty() {
+                format!("unknown profile '{name}' (no profiles are configured)")
+            } else {
+                format!(
+                    "unknown profile '{name}' (expected one of: {})",
+                    available.join(", ")
+                )
+            })
+        })?;
+        let mut config = self.clone();
+        config.refresh = profile.refresh.or(config.refresh);
+        config.resolve_hostnames = profile.resolve_hostnames.or(config.resolve_hostnames);
+        config.filter = profile.filter.or(config.filter);
+        config.sort = profile.sort.or(config.sort);
+        config.columns = profile.columns.or(config.columns);
+        config.units = profile.units.or(config.units);
+        config.prefixes = profile.prefixes.or(config.prefixes);
+        config.view = profile.view.or(config.view);
+        config.idle_minutes = profile.idle_minutes.or(config.idle_minutes);
+        config.hide_idle = profile.hide_idle.or(config.hide_idle);
+        Ok(config)
+    }
+
+    fn parse(text: &str) -> std::result::Result<Self, String> {
+        toml::from_str(text).map_err(|e| e.message().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse(
+            r#"
+            refresh = 1.5
+            resolve_hostnames = false
+            sort = "rx:desc"
+            columns = ["program", "remote", "rx"]
+            units = "bits"
+
+            [keys]
+            quit = "x"
+            refresh = ["F5", "Ctrl+r"]
+
+            [paths]
+            cache_dir = "~/.cache/nm"
+
+            [recording]
+            every_minutes = 60
+            record = "summary"
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.refresh, Some(1.5));
+        assert_eq!(config.resolve_hostnames, Some(false));
+        assert_eq!(config.filter, None);
+        assert_eq!(config.columns.unwrap().len(), 3);
+        assert_eq!(config.units.as_deref(), Some("bits"));
+        assert_eq!(config.prefixes, None);
+        assert_eq!(config.keys["quit"], KeyList::One("x".to_string()));
+        assert_eq!(config.keys["refresh"].clone().into_vec(), ["F5", "Ctrl+r"]);
+        assert_eq!(
+            config.paths.cache_dir.as_deref(),
+            Some(Path::new("~/.cache/nm"))
+        );
+        assert_eq!(config.recording.every_minutes, Some(60));
+        assert_eq!(config.recording.record.as_deref(), Some("summary"));
+        assert_eq!(config.recording.dir, None);
+    }
+
+    #[test]
+    fn test_profile_overrides_top_level() {
+        let config = Config::parse(
+            r#"
+            refresh = 2
+            sort = "rx:desc"
+
+            idle_minutes = 10
+
+            [profiles.gaming]
+            refresh = 0.5
+            filter = "steam"
+
+            [profiles.server-audit]
+            columns = ["program", "local", "state"]
+            idle_minutes = 30
+            hide_idle = true
+            "#,
+        )
+        .unwrap();
+        let gaming = config.with_profile("gaming").unwrap();
+        assert_eq!(gaming.refresh, Some(0.5));
+        assert_eq!(gaming.filter.as_deref(), Some("steam"));
+        assert_eq!(gaming.sort.as_deref(), Some("rx:desc"));
+        assert_eq!(gaming.idle_minutes, Some(10));
+
+        let audit = config.with_profile("server-audit").unwrap();
+        assert_eq!(audit.idle_minutes, Some(30));
+        assert_eq!(audit.hide_idle, Some(true));
+
+        let error = config.with_profile("work").unwrap_err().to_string();
+        assert!(error.contains("gaming, server-audit"), "{error}");
+    }
+
+    #[test]
+    fn test_template_is_an_empty_config() {
+        assert_eq!(Config::parse(TEMPLATE), Ok(Config::default()));
+        // Uncommenting the example keys gives a valid config
+        let uncommented: String = TEMPLATE
+            .lines()
+            .filter_map(|line| line.strip_prefix("# "))
+            .filter(|line| line.contains(" = ") || line.starts_with('['))
+            .map(|line| format!("{line}\n"))
+            .collect();
+        assert!(Config::parse(&uncommented).is_ok(), "{uncommented}");
+    }
+
+    #[test]
+    fn test_unknown_keys_are_rejected() {
+        let error = Config::parse("refresh_rate = 2").unwrap_err();
+        assert!(error.contains("refresh_rate"), "{error}");
+    }
+
+    #[test]
+    fn test_missing_explicit_file_is_an_error() {
+        let path = Path::new("/nonexistent/network-monitor.toml");
+        assert!(Config::load(Some(path)).is_err());
+    }
+}
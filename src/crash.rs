@@ -0,0 +1,67 @@
+//! Reports of panics with what a bug report needs, for the panic hooks of
+//! both binaries
+
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::env::consts::{ARCH, OS};
+use std::panic::{self, Location};
+use std::process;
+use std::thread;
+
+/// Exit status after a panic, as Rust uses for panicked tests and threads
+const PANIC_EXIT_CODE: i32 = 101;
+
+/// Log every panic and pass its report to `handler` instead of printing it,
+/// then exit: a panicked collector or UI thread leaves nothing worth keeping
+/// open
+pub fn set_hook(handler: impl Fn(&str) + Send + Sync + 'static) {
+    panic::set_hook(Box::new(move |info| {
+        let thread = thread::current();
+        let report = report(
+            message(info.payload()),
+            info.location(),
+            thread.name().unwrap_or("unnamed"),
+            &Backtrace::force_capture().to_string(),
+        );
+        tracing::error!("{}", report);
+        handler(&report);
+        process::exit(PANIC_EXIT_CODE);
+    }));
+}
+
+/// What was passed to `panic!`, when it is text
+fn message(payload: &dyn Any) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("Box<dyn Any>")
+}
+
+fn report(message: &str, location: Option<&Location<'_>>, thread: &str, backtrace: &str) -> String {
+    let location = location.map_or_else(|| "an unknown location".to_string(), |l| l.to_string());
+    format!(
+        "{} {} on {OS} {ARCH} panicked in thread '{thread}' at {location}:\n{message}\n\nBacktrace:\n{backtrace}",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_names_the_panic() {
+        let text: Box<dyn Any> = Box::new("index out of bounds");
+        let formatted: Box<dyn Any> = Box::new(format!("no row {}", 3));
+        assert_eq!(message(text.as_ref()), "index out of bounds");
+        assert_eq!(message(formatted.as_ref()), "no row 3");
+        assert_eq!(message(&42), "Box<dyn Any>");
+
+        let report = report("no row 3", None, "main", "0: main");
+        assert!(report.starts_with(&format!("network-monitor {}", env!("CARGO_PKG_VERSION"))));
+        assert!(report.contains("in thread 'main' at an unknown location:\nno row 3\n"));
+        assert!(report.ends_with("Backtrace:\n0: main"));
+    }
+}
@@ -0,0 +1,337 @@
+//! Checks behind `--doctor`: whether this system gives the monitor what it
+//! needs, with a fix for each problem found
+
+use crate::cli::{CommonArgs, Settings};
+use crate::paths::Paths;
+use crate::services::resolver::lookup_hostname;
+use crate::services::{Backend, GeoIpDatabase, NetworkService, PermissionReport};
+use std::fmt;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Socket tables read by the procfs backend
+const SOCKET_TABLES: [&str; 4] = [
+    "/proc/net/tcp",
+    "/proc/net/tcp6",
+    "/proc/net/udp",
+    "/proc/net/udp6",
+];
+
+/// Address with a well-known reverse name, to check that lookups work
+const LOOKUP_ADDRESS: &str = "1.1.1.1";
+
+const RESOLV_CONF: &str = "/etc/resolv.conf";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Status {
+    Ok,
+    /// Works, but with less detail than it could
+    Warning,
+    /// Stops the monitor from starting or showing anything
+    Error,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Ok => " ok ",
+            Status::Warning => "warn",
+            Status::Error => "fail",
+        })
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq)]
+pub struct Finding {
+    pub check: &'static str,
+    pub status: Status,
+    pub detail: String,
+    /// What to do about a warning or error
+    pub fix: Option<String>,
+}
+
+impl Finding {
+    fn ok(check: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            check,
+            status: Status::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn problem(
+        check: &'static str,
+        status: Status,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            check,
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Run every check, print the findings and return the exit status: 1 when
+/// any check failed
+pub fn run(args: &CommonArgs) -> i32 {
+    let findings = check_all(args);
+    let mut stdout = io::stdout().lock();
+    if let Err(e) = write_findings(&mut stdout, &findings) {
+        eprintln!("Error: {}", e);
+        return 1;
+    }
+    i32::from(findings.iter().any(|f| f.status == Status::Error))
+}
+
+/// Findings of every check, in the order they're printed
+pub fn check_all(args: &CommonArgs) -> Vec<Finding> {
+    let (config, settings) = check_config(args);
+    let mut findings = vec![config];
+    if Path::new("/proc/net").exists() {
+        findings.push(check_socket_tables(&settings.backend));
+        findings.push(check_processes());
+        findings.push(check_netlink(&settings.backend));
+    } else {
+        findings.push(Finding::ok(
+            "Socket tables",
+            format!(
+                "no /proc on this system, the {} backend is used",
+                settings.backend
+            ),
+        ));
+    }
+    findings.push(check_dns(&settings));
+    findings.push(check_geoip(&settings.geoip));
+    findings
+}
+
+/// Print one line per finding, followed by its fix
+pub fn write_findings(out: &mut impl Write, findings: &[Finding]) -> io::Result<()> {
+    for finding in findings {
+        writeln!(
+            out,
+            "[{}] {}: {}",
+            finding.status, finding.check, finding.detail
+        )?;
+        if let Some(fix) = &finding.fix {
+            writeln!(out, "       → {}", fix)?;
+        }
+    }
+    let problems = findings.iter().filter(|f| f.status != Status::Ok).count();
+    if problems == 0 {
+        writeln!(out, "\nEverything looks fine")
+    } else {
+        writeln!(
+            out,
+            "\n{} of {} checks need attention",
+            problems,
+            findings.len()
+        )
+    }
+}
+
+/// The config finding and the settings the other checks go by, the defaults
+/// when the config is broken
+fn check_config(args: &CommonArgs) -> (Finding, Settings) {
+    let path = args.config.clone().or_else(Paths::config_file);
+    let location = path.as_ref().map_or_else(
+        || "no config directory".to_string(),
+        |p| p.display().to_string(),
+    );
+    match args.settings() {
+        Ok(settings) if path.as_ref().is_some_and(|p| p.exists()) => (
+            Finding::ok("Config", format!("{location} is valid")),
+            settings,
+        ),
+        Ok(settings) => (
+            Finding::ok(
+                "Config",
+                format!("{location} does not exist, using the defaults"),
+            ),
+            settings,
+        ),
+        Err(e) => (
+            Finding::problem(
+                "Config",
+                Status::Error,
+                e.to_string(),
+                format!("Fix or remove the setting named above in {location}"),
+            ),
+            Settings::default(),
+        ),
+    }
+}
+
+fn check_socket_tables(backend: &Backend) -> Finding {
+    let unreadable: Vec<String> = SOCKET_TABLES
+        .iter()
+        .filter_map(|table| fs::read(table).err().map(|e| format!("{table}: {e}")))
+        .collect();
+    if unreadable.is_empty() {
+        return Finding::ok(
+            "Socket tables",
+            "/proc/net/tcp, tcp6, udp and udp6 are readable",
+        );
+    }
+    // Netlink lists sockets without the tables; IPv6 may just be disabled
+    let status = if matches!(backend, Backend::Procfs) && unreadable.len() == SOCKET_TABLES.len() {
+        Status::Error
+    } else {
+        Status::Warning
+    };
+    Finding::problem(
+        "Socket tables",
+        status,
+        unreadable.join("; "),
+        "Mount /proc without hidepid, or start with --backend netlink",
+    )
+}
+
+fn check_processes() -> Finding {
+    let report = PermissionReport::check();
+    if report.missing.is_empty() {
+        return Finding::ok("Processes", "every process and socket can be inspected");
+    }
+    Finding::problem(
+        "Processes",
+        Status::Warning,
+        report.missing.join("; "),
+        "Run as root, start the privileged helper (nmt --elevate) or grant the helper \
+         capabilities in the window's setup assistant",
+    )
+}
+
+fn check_netlink(backend: &Backend) -> Finding {
+    match NetworkService::new().get_connections_netlink() {
+        Ok(connections) => Finding::ok(
+            "Netlink",
+            format!("sock_diag listed {} sockets", connections.len()),
+        ),
+        Err(e) if matches!(backend, Backend::Netlink) => Finding::problem(
+            "Netlink",
+            Status::Error,
+            e.to_string(),
+            "Start with --backend procfs, or allow netlink sockets in the sandbox",
+        ),
+        Err(e) => Finding::problem(
+            "Netlink",
+            Status::Warning,
+            e.to_string(),
+            "Only --backend netlink needs it; the default procfs backend works without",
+        ),
+    }
+}
+
+fn check_dns(settings: &Settings) -> Finding {
+    if settings.resolve_hostnames == Some(false) {
+        return Finding::ok("DNS", "hostname resolution is turned off");
+    }
+    let has_nameserver = fs::read_to_string(RESOLV_CONF).is_ok_and(|text| {
+        text.lines()
+            .any(|line| line.trim_start().starts_with("nameserver"))
+    });
+    if !has_nameserver {
+        return Finding::problem(
+            "DNS",
+            Status::Warning,
+            format!("{RESOLV_CONF} lists no nameserver"),
+            "Configure a nameserver, or start with --no-resolve to show addresses only",
+        );
+    }
+    match lookup_hostname(LOOKUP_ADDRESS) {
+        Ok(Some(name)) => Finding::ok("DNS", format!("{LOOKUP_ADDRESS} resolved to {name}")),
+        Ok(None) => Finding::ok(
+            "DNS",
+            format!("lookups work, though {LOOKUP_ADDRESS} has no name here"),
+        ),
+        Err(e) => Finding::problem(
+            "DNS",
+            Status::Warning,
+            e.to_string(),
+            "Install the host command (bind9-host or bind-utils), check the nameservers in \
+             /etc/resolv.conf, or start with --no-resolve",
+        ),
+    }
+}
+
+fn check_geoip(paths: &[impl AsRef<Path>]) -> Finding {
+    if paths.is_empty() {
+        return Finding::ok(
+            "GeoIP",
+            "no database configured, countries are not shown (see --geoip)",
+        );
+    }
+    match GeoIpDatabase::load(paths) {
+        Ok(database) if database.is_empty() => Finding::problem(
+            "GeoIP",
+            Status::Warning,
+            "the configured files have no ranges",
+            "Point --geoip at Tor's geoip and geoip6 or DB-IP's lite country CSV",
+        ),
+        Ok(database) => Finding::ok(
+            "GeoIP",
+            format!("{} ranges from {} files", database.len(), paths.len()),
+        ),
+        Err(e) => Finding::problem(
+            "GeoIP",
+            Status::Error,
+            e.to_string(),
+            "Install the file (the tor-geoipdb package has Tor's) or fix the geoip path",
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_broken_config_is_an_error_with_a_fix() {
+        let path = std::env::temp_dir().join(format!("nm-doctor-{}.toml", std::process::id()));
+        fs::write(&path, "refresh = \"soon\"\n").unwrap();
+        let args = CommonArgs {
+            config: Some(path.clone()),
+            ..CommonArgs::default()
+        };
+        let (finding, settings) = check_config(&args);
+        fs::remove_file(&path).unwrap();
+        assert_eq!(finding.status, Status::Error);
+        assert!(finding.fix.unwrap().contains(&path.display().to_string()));
+        assert_eq!(settings, Settings::default());
+    }
+
+    #[test]
+    fn test_missing_geoip_file_is_an_error() {
+        assert_eq!(check_geoip(&[] as &[&Path]).status, Status::Ok);
+        let finding = check_geoip(&["/nonexistent/geoip"]);
+        assert_eq!(finding.status, Status::Error);
+        assert!(finding.detail.contains("/nonexistent/geoip"));
+    }
+
+    #[test]
+    fn test_write_findings() {
+        let findings = [
+            Finding::ok("Config", "defaults"),
+            Finding::problem(
+                "DNS",
+                Status::Warning,
+                "lookup timed out",
+                "use --no-resolve",
+            ),
+        ];
+        let mut out = Vec::new();
+        write_findings(&mut out, &findings).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "[ ok ] Config: defaults\n\
+             [warn] DNS: lookup timed out\n       → use --no-resolve\n\
+             \n1 of 2 checks need attention\n"
+        );
+    }
+}
@@ -0,0 +1,240 @@
+use adw::prelude::*;
+use adw::ActionRow;
+use gtk::{Align, Box as GtkBox, Label, ListBox, Orientation, Revealer, SelectionMode};
+use gtk4 as gtk;
+
+use crate::models::{Connection, Direction, RoutingTable};
+use crate::services::bandwidth_limit::{self, LIMIT_PRESETS};
+use crate::services::ProcessInspector;
+use crate::utils::formatter::{Formatter, Units};
+use gtk::gio;
+use std::cell::{Cell, RefCell};
+use std::sync::Arc;
+
+/// Slide-up pane with the process behind a connection: parents, command,
+/// working directory, environment and sandbox, and the route it takes once
+/// [`set_routes`](Self::set_routes) was given the routing table. Its
+/// bandwidth menu activates `win.limit-bandwidth` and its copy button
+/// `win.copy-json`.
+pub struct ConnectionDetails {
+    revealer: Revealer,
+    list: ListBox,
+    routes: RefCell<RoutingTable>,
+    /// TCP connections by congestion control algorithm
+    congestion_controls: RefCell<Vec<(Arc<str>, usize)>>,
+    units: Cell<Units>,
+}
+
+impl ConnectionDetails {
+    pub fn new() -> Self {
+        let list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        list.add_css_class("boxed-list");
+
+        let revealer = Revealer::builder()
+            .transition_type(gtk::RevealerTransitionType::SlideUp)
+            .reveal_child(false)
+            .build();
+
+        let title = Label::builder()
+            .label("Connection Details")
+            .halign(Align::Start)
+            .hexpand(true)
+            .build();
+        title.add_css_class("heading");
+
+        let copy_button = gtk::Button::builder()
+            .icon_name("edit-copy-symbolic")
+            .tooltip_text("Copy as JSON (Ctrl+Shift+C)")
+            .action_name("win.copy-json")
+            .build();
+        copy_button.add_css_class("flat");
+
+        let close_button = gtk::Button::builder()
+            .icon_name("window-close-symbolic")
+            .tooltip_text("Close Details")
+            .build();
+        close_button.add_css_class("flat");
+        let revealer_clone = revealer.clone();
+        close_button.connect_clicked(move |_| revealer_clone.set_reveal_child(false));
+
+        let title_box = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .build();
+        title_box.append(&title);
+        title_box.append(&copy_button);
+        title_box.append(&close_button);
+
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(6)
+            .margin_top(12)
+            .build();
+        content.add_css_class("detail-pane");
+        content.append(&title_box);
+        content.append(&list);
+        revealer.set_child(Some(&content));
+
+        Self {
+            revealer,
+            list,
+            routes: RefCell::new(RoutingTable::default()),
+            congestion_controls: RefCell::new(Vec::new()),
+            units: Cell::new(Units::default()),
+        }
+    }
+
+    /// The pane, to pack below a [`ConnectionTable`](super::ConnectionTable)
+    pub fn widget(&self) -> &Revealer {
+        &self.revealer
+    }
+
+    /// Routing table to look up the route of the connections shown next
+    pub fn set_routes(&self, routes: RoutingTable) {
+        *self.routes.borrow_mut() = routes;
+    }
+
+    /// How many TCP connections use each congestion control algorithm, to
+    /// compare the connections shown next with
+    pub fn set_congestion_controls(&self, counts: Vec<(Arc<str>, usize)>) {
+        *self.congestion_controls.borrow_mut() = counts;
+    }
+
+    /// Units of the bandwidth limits shown next
+    pub fn set_units(&self, units: Units) {
+        self.units.set(units);
+    }
+
+    /// Fill the pane with `conn` and its process, then slide it in
+    pub fn show(&self, conn: &Connection) {
+        self.list.remove_all();
+        let add_row = |title: &str, subtitle: &str| {
+            let row = ActionRow::builder()
+                .title(title)
+                .subtitle(subtitle)
+                .subtitle_selectable(true)
+                .build();
+            row.add_css_class("property");
+            self.list.append(&row);
+        };
+
+        add_row(
+            "Connection",
+            &format!(
+                "{} {} {} {} · {}",
+                Formatter::format_protocol(&conn.protocol_label()),
+                conn.local,
+                conn.direction.map_or("→", Direction::arrow),
+                conn.remote,
+                conn.state
+            ),
+        );
+        match conn.direction {
+            Some(Direction::Inbound) => add_row(
+                "Direction",
+                "Inbound: the remote host connected to a listening port",
+            ),
+            Some(Direction::Outbound) => {
+                add_row("Direction", "Outbound: opened by a process on this machine")
+            }
+            None => {}
+        }
+        if let Some(algorithm) = &conn.congestion {
+            add_row(
+                "Congestion Control",
+                &format!(
+                    "{algorithm} · all TCP connections: {}",
+                    Formatter::format_counts(&self.congestion_controls.borrow())
+                ),
+            );
+        }
+        if let Some(timer) = conn.timer {
+            let mut text = format!(
+                "{} in {:.1} s",
+                timer.kind.as_str(),
+                timer.expires_ms as f64 / 1000.0
+            );
+            if timer.retries > 0 {
+                text.push_str(&format!(", {} unanswered so far", timer.retries));
+            }
+            add_row("Timer", &text);
+        }
+        let routes = self.routes.borrow();
+        if !routes.is_empty() {
+            match routes.route_for(conn) {
+                Some(route) => add_row("Route", &route.to_string()),
+                None => add_row("Route", "None: local traffic, or no route matches"),
+            }
+        }
+
+        match ProcessInspector::inspect(&conn.pid) {
+            Ok(details) => {
+                add_row("Process", &format!("{} ({})", details.name, details.pid));
+                add_row("Parents", &details.parent_chain_display());
+                add_row("Command", &details.command);
+                add_row("Working Directory", &details.cwd_display());
+                add_row("Environment", &details.environment_display());
+                add_row("Sandbox", &details.sandbox_display());
+            }
+            Err(e) => add_row("Process", &format!("{} ({e})", conn.get_process_display())),
+        }
+        if bandwidth_limit::parse_pid(&conn.pid).is_ok() {
+            self.list.append(&bandwidth_row(conn, self.units.get()));
+        }
+
+        self.revealer.set_reveal_child(true);
+    }
+
+    pub fn hide(&self) {
+        self.revealer.set_reveal_child(false);
+    }
+}
+
+/// Current limit of the process, with a menu to set or lift it
+fn bandwidth_row(conn: &Connection, units: Units) -> ActionRow {
+    let subtitle = match conn.bandwidth_limit {
+        Some(limit) => format!("Limited to {} each way", units.format_rate(limit)),
+        None => "Not limited".to_string(),
+    };
+    let row = ActionRow::builder()
+        .title("Bandwidth")
+        .subtitle(subtitle)
+        .build();
+    row.add_css_class("property");
+
+    // A rate of 0 lifts the limit
+    let menu = gio::Menu::new();
+    for rate in LIMIT_PRESETS {
+        let item = gio::MenuItem::new(Some(&format!("Limit to {}", units.format_rate(rate))), None);
+        item.set_action_and_target_value(
+            Some("win.limit-bandwidth"),
+            Some(&(conn.pid.to_string(), rate).to_variant()),
+        );
+        menu.append_item(&item);
+    }
+    if conn.bandwidth_limit.is_some() {
+        let item = gio::MenuItem::new(Some("Remove Limit"), None);
+        item.set_action_and_target_value(
+            Some("win.limit-bandwidth"),
+            Some(&(conn.pid.to_string(), 0u64).to_variant()),
+        );
+        menu.append_item(&item);
+    }
+    let button = gtk::MenuButton::builder()
+        .icon_name("speedometer-symbolic")
+        .tooltip_text("Limit Bandwidth (experimental)")
+        .menu_model(&menu)
+        .valign(Align::Center)
+        .build();
+    button.add_css_class("flat");
+    row.add_suffix(&button);
+    row
+}
+
+impl Default for ConnectionDetails {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,804 @@
+use gtk::prelude::*;
+use gtk::{
+    Adjustment, Align, Box as GtkBox, Grid, Label, Orientation, PopoverMenu, ScrolledWindow,
+};
+use gtk4 as gtk;
+use std::cell::{Cell, RefCell};
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use crate::cli::DEFAULT_COLUMNS;
+use crate::models::{
+    Connection, ConnectionDiff, ConnectionKey, ConnectionState, Direction, Protocol, Quic, Scope,
+};
+use crate::services::{AddressResolver, BanList};
+use crate::utils::formatter::Units;
+use crate::utils::heatmap::{RatePeaks, HEAT_LEVELS};
+use crate::utils::viewport::visible_range;
+
+/// Columns of the table: process, protocol, source, destination, status,
+/// TX, RX, path, the send and receive queues, the direction, the timer, the
+/// scope and the host
+pub const COLUMN_COUNT: usize = 14;
+
+pub const TITLES: [&str; COLUMN_COUNT] = [
+    "Process(ID)",
+    "Protocol",
+    "Source",
+    "Destination",
+    "Status",
+    "TX",
+    "RX",
+    "Path",
+    "SendQ",
+    "RecvQ",
+    "Dir",
+    "Timer",
+    "Scope",
+    "Host",
+];
+
+/// Every class [`column_class`] hands out
+pub const COLUMN_CLASSES: [&str; 11] = [
+    "column-process",
+    "column-protocol",
+    "column-address",
+    "column-status",
+    "column-rate",
+    "column-path",
+    "column-queue",
+    "column-direction",
+    "column-timer",
+    "column-scope",
+    "column-host",
+];
+
+/// CSS class setting the minimum width of `column`
+pub fn column_class(column: usize) -> Option<&'static str> {
+    match column {
+        0 => Some("column-process"),
+        1 => Some("column-protocol"),
+        2 | 3 => Some("column-address"),
+        4 => Some("column-status"),
+        5 | 6 => Some("column-rate"),
+        7 => Some("column-path"),
+        8 | 9 => Some("column-queue"),
+        10 => Some("column-direction"),
+        11 => Some("column-timer"),
+        12 => Some("column-scope"),
+        13 => Some("column-host"),
+        _ => None,
+    }
+}
+
+/// Header label of `column`, left aligned
+pub fn header_label(column: usize) -> Label {
+    let label = Label::builder()
+        .label(TITLES.get(column).copied().unwrap_or(""))
+        .halign(Align::Start)
+        .xalign(0.0)
+        .build();
+    label.add_css_class("table-header");
+    if let Some(class) = column_class(column) {
+        label.add_css_class(class);
+    }
+    label
+}
+
+/// New cell of `column`: rates and queues right aligned, everything but
+/// the path ellipsized. Its state colors come from [`style_cell`].
+pub fn cell_label(column: usize, text: &str) -> Label {
+    let label = Label::builder().label(text).xalign(0.0).build();
+    if column != 7 {
+        label.set_ellipsize(gtk::pango::EllipsizeMode::End);
+    }
+    match column {
+        5 | 6 | 8 | 9 => {
+            label.set_halign(Align::End);
+            label.set_xalign(1.0);
+        }
+        _ => label.set_halign(Align::Start),
+    }
+    match column {
+        0 => label.add_css_class("caption"),
+        7 => {
+            label.add_css_class("caption");
+            label.add_css_class("dim-label");
+        }
+        _ => {}
+    }
+    if let Some(class) = column_class(column) {
+        label.add_css_class(class);
+    }
+    label.add_css_class("table-cell");
+    label
+}
+
+/// Text of `column` for `conn`, with addresses through `resolver`
+pub fn cell_text(
+    conn: &Connection,
+    column: usize,
+    resolver: Option<&AddressResolver>,
+    units: Units,
+) -> String {
+    let address = |addr: &str| match resolver {
+        Some(resolver) => resolver.resolve_address(addr),
+        None => addr.to_string(),
+    };
+    match column {
+        0 if conn.bandwidth_limit.is_some() => {
+            format!("{} · Limited", conn.get_process_display())
+        }
+        0 => conn.get_process_display(),
+        1 => conn.protocol_label(),
+        2 => address(&conn.local),
+        3 => match resolver {
+            Some(resolver) => resolver.resolve_remote(conn),
+            None => conn.remote.to_string(),
+        },
+        4 => conn.state.to_string(),
+        5 => units.format_rate(conn.tx_rate),
+        6 => units.format_rate(conn.rx_rate),
+        7 => conn.command.to_string(),
+        8 => conn.send_queue.to_string(),
+        9 => conn.recv_queue.to_string(),
+        10 => conn.direction.map_or("", Direction::label).to_string(),
+        11 => conn.timer.map(|timer| timer.label()).unwrap_or_default(),
+        12 => conn.scope.map_or("", Scope::label).to_string(),
+        13 => conn.host.as_deref().unwrap_or_default().to_string(),
+        _ => String::new(),
+    }
+}
+
+/// Update the classes of a cell that depend on its connection: protocol and
+/// state colors, traffic heat relative to `peaks`, queued data, inbound
+/// connections, retransmitting sockets, banned destinations, hidden and
+/// bandwidth-limited processes, whose limit is given in `units`
+pub fn style_cell(
+    label: &Label,
+    column: usize,
+    conn: &Connection,
+    ban_list: &BanList,
+    peaks: RatePeaks,
+    units: Units,
+) {
+    match column {
+        0 => {
+            // Process hidden by /proc permissions
+            label.remove_css_class("limited");
+            if conn.is_permission_denied() {
+                label.add_css_class("permission-denied");
+                label.set_tooltip_text(Some(
+                    "Owned by another user; run as root to see the process",
+                ));
+            } else if let Some(limit) = conn.bandwidth_limit {
+                label.remove_css_class("permission-denied");
+                label.add_css_class("limited");
+                label.set_tooltip_text(Some(&format!(
+                    "Limited to {} each way",
+                    units.format_rate(limit)
+                )));
+            } else {
+                label.remove_css_class("permission-denied");
+                label.set_tooltip_text(None);
+            }
+        }
+        1 => {
+            // Protocol color
+            label.remove_css_class("success");
+            label.remove_css_class("warning");
+            label.remove_css_class("dim-label");
+            label.remove_css_class("accent");
+            match conn.protocol {
+                Protocol::Tcp => label.add_css_class("success"),
+                Protocol::Udp => label.add_css_class("warning"),
+                Protocol::Tcp6 | Protocol::Udp6 => label.add_css_class("dim-label"),
+                Protocol::Sctp | Protocol::Sctp6 | Protocol::Dccp | Protocol::Dccp6 => {
+                    label.add_css_class("accent")
+                }
+            }
+            label.set_tooltip_text(match conn.quic {
+                Some(Quic::Likely) => Some("Likely QUIC (HTTP/3): UDP on the HTTPS port"),
+                Some(Quic::Confirmed) => Some("QUIC (HTTP/3): its Initial packet was captured"),
+                None => None,
+            });
+        }
+        3 => {
+            // Destination rate color, banned addresses take precedence
+            label.remove_css_class("accent");
+            label.remove_css_class("dim-label");
+            label.remove_css_class("banned");
+            label.set_tooltip_text(None);
+            if let Some(jail) = ban_list.jail_for(&conn.remote) {
+                label.add_css_class("banned");
+                label.set_tooltip_text(Some(&format!("Banned by fail2ban jail \"{jail}\"")));
+            } else if conn.rx_rate > 0 || conn.tx_rate > 0 {
+                label.add_css_class("accent");
+            } else {
+                label.add_css_class("dim-label");
+            }
+        }
+        4 => {
+            // Status color
+            label.remove_css_class("success");
+            label.remove_css_class("warning");
+            label.remove_css_class("error");
+            label.remove_css_class("dim-label");
+            label.set_tooltip_text(
+                conn.short_lived
+                    .then_some("Opened and closed again between two refreshes"),
+            );
+            match conn.state {
+                ConnectionState::Established => label.add_css_class("success"),
+                ConnectionState::Listen => label.add_css_class("warning"),
+                ConnectionState::TimeWait => label.add_css_class("error"),
+                _ => label.add_css_class("dim-label"),
+            }
+        }
+        5 | 6 => {
+            // Rate heat, from pale for a trickle to bold red for the busiest
+            let heat = if column == 5 {
+                peaks.tx_heat(conn)
+            } else {
+                peaks.rx_heat(conn)
+            };
+            label.remove_css_class("dim-label");
+            for level in 1..=HEAT_LEVELS {
+                label.remove_css_class(&format!("heat-{level}"));
+            }
+            match heat {
+                0 => label.add_css_class("dim-label"),
+                level => label.add_css_class(&format!("heat-{level}")),
+            }
+        }
+        8 | 9 => {
+            // Queued bytes stand out, empty queues are the norm
+            let queued = if column == 8 {
+                conn.send_queue
+            } else {
+                conn.recv_queue
+            };
+            label.remove_css_class("warning");
+            label.remove_css_class("dim-label");
+            if queued > 0 {
+                label.add_css_class("warning");
+            } else {
+                label.add_css_class("dim-label");
+            }
+        }
+        10 => {
+            // Connections from outside are the ones to review
+            label.remove_css_class("warning");
+            label.remove_css_class("dim-label");
+            match conn.direction {
+                Some(Direction::Inbound) => {
+                    label.add_css_class("warning");
+                    label.set_tooltip_text(Some("Opened by the remote host"));
+                }
+                Some(Direction::Outbound) => {
+                    label.add_css_class("dim-label");
+                    label.set_tooltip_text(Some("Opened by this machine"));
+                }
+                None => label.set_tooltip_text(None),
+            }
+        }
+        11 => {
+            // Retransmissions nobody acknowledges point at a hung peer
+            label.remove_css_class("error");
+            label.remove_css_class("dim-label");
+            match conn.timer {
+                Some(timer) if timer.is_retrying() => {
+                    label.add_css_class("error");
+                    label.set_tooltip_text(Some(&format!(
+                        "Sent {} times without an answer",
+                        timer.retries + 1
+                    )));
+                }
+                _ => {
+                    label.add_css_class("dim-label");
+                    label.set_tooltip_text(None);
+                }
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Gray out a cell of a connection without recent activity
+pub fn style_idle(label: &Label, idle: bool) {
+    if idle {
+        label.add_css_class("idle-connection");
+    } else {
+        label.remove_css_class("idle-connection");
+    }
+}
+
+/// Row height used for culling until a rendered row can be measured
+const DEFAULT_ROW_HEIGHT: f64 = 28.0;
+/// Tables up to this many rows get widgets for every row
+const CULL_AFTER: usize = 100;
+/// Rows rendered above and below the viewport so small scrolls don't re-render
+const VIEWPORT_OVERSCAN: usize = 20;
+/// Widest a column grows to when sized to its cells; the path column is
+/// left wide so long paths scroll instead
+const MAX_COLUMN_WIDTHS: [i32; 11] = [150, 45, 140, 140, 80, 70, 70, 500, 70, 70, 60];
+
+/// Called with the connection of a clicked row
+type SelectedCallback = Box<dyn Fn(&Connection)>;
+/// Called with the column of a clicked header
+type HeaderCallback = Box<dyn Fn(usize)>;
+
+/// Connection table for embedding in other GTK apps, as used by the
+/// monitor's own window: a sticky header over the scrolled rows. Only the
+/// rows around the viewport get widgets, the others are stood in for by
+/// grid margins so the scrollbar still spans the whole list.
+pub struct ConnectionTable {
+    container: GtkBox,
+    header_grid: Grid,
+    grid: Grid,
+    vadjustment: Adjustment,
+    headers: Vec<Label>,
+    /// Cells of the rendered rows, [`COLUMN_COUNT`] per row
+    cells: RefCell<Vec<Label>>,
+    connections: RefCell<Vec<Connection>>,
+    /// First and past-the-last row that have cells
+    rendered: Cell<(usize, usize)>,
+    row_height: Cell<f64>,
+    columns: RefCell<Vec<usize>>,
+    column_widths: RefCell<Vec<i32>>,
+    /// Followed to whatever row it moves to when connections are set
+    selected: RefCell<Option<ConnectionKey>>,
+    units: Cell<Units>,
+    ban_list: RefCell<BanList>,
+    idle: RefCell<HashSet<ConnectionKey>>,
+    resolver: RefCell<Option<AddressResolver>>,
+    /// "Copied!" popovers still up, dropped before cells are reused
+    popovers: Rc<RefCell<Vec<PopoverMenu>>>,
+    on_selected: RefCell<Option<SelectedCallback>>,
+    on_header_clicked: RefCell<Option<HeaderCallback>>,
+}
+
+impl ConnectionTable {
+    pub fn new() -> Rc<Self> {
+        let header_grid = Grid::builder()
+            .column_spacing(0)
+            .row_spacing(0)
+            .halign(Align::Start)
+            .hexpand(false)
+            .build();
+        let grid = Grid::builder()
+            .column_spacing(0)
+            .row_spacing(0)
+            .halign(Align::Start)
+            .hexpand(false)
+            .build();
+
+        // The header sits outside the scrolled window so it stays put, and
+        // follows horizontal scrolling through its margin
+        let header_wrapper = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .build();
+        header_wrapper.append(&header_grid);
+        let header_container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .hexpand(true)
+            .build();
+        header_container.add_css_class("header-container");
+        header_container.append(&header_wrapper);
+
+        let vadjustment = Adjustment::new(0.0, 0.0, 0.0, 0.0, 0.0, 0.0);
+        let scrolled = ScrolledWindow::builder()
+            .vexpand(true)
+            .hexpand(true)
+            .halign(Align::Fill)
+            .height_request(400)
+            .vadjustment(&vadjustment)
+            .child(&grid)
+            .build();
+        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
+        scrolled.add_css_class("table-container");
+        scrolled.add_css_class("responsive-table");
+        let header = header_grid.clone();
+        scrolled
+            .hadjustment()
+            .connect_value_notify(move |hadjustment| {
+                header.set_margin_start(-(hadjustment.value().round() as i32));
+            });
+        let header = header_grid.clone();
+        scrolled.connect_edge_overshot(move |scrolled, pos| {
+            if pos == gtk::PositionType::Left || pos == gtk::PositionType::Right {
+                header.set_margin_start(-(scrolled.hadjustment().value().round() as i32));
+            }
+        });
+
+        let container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .hexpand(true)
+            .build();
+        container.append(&header_container);
+        container.append(&scrolled);
+
+        let headers = (0..COLUMN_COUNT).map(header_label).collect();
+        let table = Rc::new(Self {
+            container,
+            header_grid,
+            grid,
+            vadjustment,
+            headers,
+            cells: RefCell::new(Vec::new()),
+            connections: RefCell::new(Vec::new()),
+            rendered: Cell::new((0, 0)),
+            row_height: Cell::new(DEFAULT_ROW_HEIGHT),
+            columns: RefCell::new(DEFAULT_COLUMNS.to_vec()),
+            column_widths: RefCell::new(vec![0; COLUMN_COUNT]),
+            selected: RefCell::new(None),
+            units: Cell::new(Units::default()),
+            ban_list: RefCell::new(BanList::default()),
+            idle: RefCell::new(HashSet::new()),
+            resolver: RefCell::new(None),
+            popovers: Rc::new(RefCell::new(Vec::new())),
+            on_selected: RefCell::new(None),
+            on_header_clicked: RefCell::new(None),
+        });
+        for (column, header) in table.headers.iter().enumerate() {
+            header.set_visible(DEFAULT_COLUMNS.contains(&column));
+            header.add_controller(table.header_click(column));
+            table.header_grid.attach(header, column as i32, 0, 1, 1);
+        }
+
+        // Scrolling or resizing can uncover rows without cells
+        let weak = Rc::downgrade(&table);
+        let on_viewport_change = move |adjustment: &Adjustment| {
+            let Some(table) = weak.upgrade() else {
+                return;
+            };
+            let (rendered_start, rendered_end) = table.rendered.get();
+            let row_height = table.row_height.get();
+            let first_visible = (adjustment.value() / row_height) as usize;
+            let last_visible =
+                ((adjustment.value() + adjustment.page_size()) / row_height).ceil() as usize;
+            let connection_count = table.connections.borrow().len();
+            if first_visible < rendered_start || last_visible.min(connection_count) > rendered_end {
+                table.render();
+            }
+        };
+        table
+            .vadjustment
+            .connect_value_notify(on_viewport_change.clone());
+        table
+            .vadjustment
+            .connect_page_size_notify(on_viewport_change);
+        table
+    }
+
+    /// The header and the scrolled rows, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `connections` in the given order, reusing the existing cells
+    pub fn set_connections(self: &Rc<Self>, connections: Vec<Connection>) {
+        *self.connections.borrow_mut() = connections;
+        self.render();
+    }
+
+    /// Column indices to show; the others keep their slot but take no space
+    pub fn set_columns(&self, columns: Vec<usize>) {
+        for (column, header) in self.headers.iter().enumerate() {
+            header.set_visible(columns.contains(&column));
+        }
+        for (index, label) in self.cells.borrow().iter().enumerate() {
+            label.set_visible(columns.contains(&(index % COLUMN_COUNT)));
+        }
+        *self.columns.borrow_mut() = columns;
+    }
+
+    pub fn columns(&self) -> Vec<usize> {
+        self.columns.borrow().clone()
+    }
+
+    /// Mark the header of the column rows are sorted by
+    pub fn set_sort(&self, column: usize, ascending: bool) {
+        for (index, header) in self.headers.iter().enumerate() {
+            let arrow = if index != column {
+                ""
+            } else if ascending {
+                " ▲"
+            } else {
+                " ▼"
+            };
+            header.set_text(&format!("{}{arrow}", TITLES[index]));
+        }
+    }
+
+    /// Units for the TX and RX rates, from the next [`Self::set_connections`]
+    pub fn set_units(&self, units: Units) {
+        self.units.set(units);
+    }
+
+    /// Mark destinations banned by fail2ban, from the next [`Self::set_connections`]
+    pub fn set_ban_list(&self, ban_list: BanList) {
+        *self.ban_list.borrow_mut() = ban_list;
+    }
+
+    /// Gray out these connections, e.g. from
+    /// [`ConnectionSnapshot::idle_connections`](crate::models::ConnectionSnapshot::idle_connections),
+    /// from the next [`Self::set_connections`]
+    pub fn set_idle(&self, idle: HashSet<ConnectionKey>) {
+        *self.idle.borrow_mut() = idle;
+    }
+
+    /// Show hostnames instead of addresses, from the next [`Self::set_connections`]
+    pub fn set_resolver(&self, resolver: Option<AddressResolver>) {
+        *self.resolver.borrow_mut() = resolver;
+    }
+
+    /// Run `callback` when a row is clicked, e.g. to fill a
+    /// [`ConnectionDetails`](super::ConnectionDetails) pane
+    pub fn connect_selected(&self, callback: impl Fn(&Connection) + 'static) {
+        *self.on_selected.borrow_mut() = Some(Box::new(callback));
+    }
+
+    /// Run `callback` with the column of a clicked header, e.g. to sort by it
+    pub fn connect_header_clicked(&self, callback: impl Fn(usize) + 'static) {
+        *self.on_header_clicked.borrow_mut() = Some(Box::new(callback));
+    }
+
+    pub fn selected(&self) -> Option<Connection> {
+        let selected = self.selected.borrow();
+        let key = selected.as_ref()?;
+        self.connections
+            .borrow()
+            .iter()
+            .find(|conn| conn.key() == *key)
+            .cloned()
+    }
+
+    /// Keep the selection on its connection when `diff` gave it a new key,
+    /// e.g. going to TIME_WAIT; call before setting the new connections
+    pub fn follow_selected(&self, diff: &ConnectionDiff) {
+        let selected = self.selected.take();
+        *self.selected.borrow_mut() = selected.map(|key| diff.follow(key));
+    }
+
+    pub fn clear_selection(&self) {
+        *self.selected.borrow_mut() = None;
+        self.highlight_selected();
+    }
+
+    /// Size the columns to their header and a sample of their cells
+    pub fn sync_column_widths(&self) {
+        let mut widths = vec![60; COLUMN_COUNT];
+        for (column, header) in self.headers.iter().enumerate() {
+            widths[column] = widths[column].max(estimate_text_width(&header.text()) + 16);
+        }
+        // Every 5th cell is enough to size a column
+        let cells = self.cells.borrow();
+        for (index, label) in cells.iter().enumerate().step_by(5) {
+            let column = index % COLUMN_COUNT;
+            widths[column] = widths[column].max(estimate_text_width(&label.text()) + 16);
+        }
+        for (width, max) in widths.iter_mut().zip(MAX_COLUMN_WIDTHS) {
+            *width = (*width).min(max);
+        }
+
+        for (column, header) in self.headers.iter().enumerate() {
+            header.set_width_request(widths[column]);
+            for class in COLUMN_CLASSES {
+                header.remove_css_class(class);
+            }
+            if let Some(class) = column_class(column) {
+                header.add_css_class(class);
+            }
+        }
+        for (index, label) in cells.iter().enumerate() {
+            label.set_width_request(widths[index % COLUMN_COUNT]);
+        }
+        *self.column_widths.borrow_mut() = widths;
+    }
+
+    /// Widths of the columns as of the last [`Self::sync_column_widths`]
+    pub fn column_widths(&self) -> Vec<i32> {
+        self.column_widths.borrow().clone()
+    }
+
+    /// Rows that should have cells: everything for small tables, otherwise
+    /// the scrolled viewport plus overscan
+    fn viewport_range(&self, connection_count: usize) -> std::ops::Range<usize> {
+        if connection_count <= CULL_AFTER {
+            return 0..connection_count;
+        }
+        let row_height = self.row_height.get();
+        let first_row = (self.vadjustment.value() / row_height) as usize;
+        let page_size = self.vadjustment.page_size();
+        let viewport_rows = if page_size > 0.0 {
+            (page_size / row_height).ceil() as usize
+        } else {
+            // Not laid out yet
+            CULL_AFTER
+        };
+        visible_range(
+            first_row,
+            viewport_rows,
+            VIEWPORT_OVERSCAN,
+            connection_count,
+        )
+    }
+
+    /// Bind cells to the rows inside the viewport only, keeping the widget
+    /// count flat however many connections exist
+    fn render(self: &Rc<Self>) {
+        for popover in self.popovers.borrow_mut().drain(..) {
+            popover.unparent();
+        }
+
+        // Measure the real row height from the previous render
+        let (previous_start, previous_end) = self.rendered.get();
+        let previous_rows = previous_end - previous_start;
+        let grid_height = self.grid.height();
+        if previous_rows > 0 && grid_height > 0 {
+            self.row_height
+                .set(f64::from(grid_height) / previous_rows as f64);
+        }
+
+        let connections = self.connections.borrow();
+        let range = self.viewport_range(connections.len());
+        let row_height = self.row_height.get();
+        self.grid
+            .set_margin_top((range.start as f64 * row_height) as i32);
+        self.grid
+            .set_margin_bottom(((connections.len() - range.end) as f64 * row_height) as i32);
+        self.rendered.set((range.start, range.end));
+
+        let ban_list = self.ban_list.borrow();
+        let idle = self.idle.borrow();
+        let resolver = self.resolver.borrow();
+        let columns = self.columns.borrow();
+        let units = self.units.get();
+        let peaks = RatePeaks::of(&connections);
+        let mut cells = self.cells.borrow_mut();
+        let visible = &connections[range];
+
+        for (row, conn) in visible.iter().enumerate() {
+            let is_idle = conn.short_lived || idle.contains(&conn.key());
+            for column in 0..COLUMN_COUNT {
+                let text = cell_text(conn, column, resolver.as_ref(), units);
+                let index = row * COLUMN_COUNT + column;
+                if index >= cells.len() {
+                    let label = cell_label(column, &text);
+                    label.add_controller(self.row_click(row));
+                    label.add_controller(self.copy_click());
+                    label.add_controller(copy_key());
+                    self.grid
+                        .attach(&label, column as i32, row as i32 + 1, 1, 1);
+                    cells.push(label);
+                }
+                let label = &cells[index];
+                if label.text() != text.as_str() {
+                    label.set_text(&text);
+                }
+                label.set_visible(columns.contains(&column));
+                style_cell(label, column, conn, &ban_list, peaks, units);
+                style_idle(label, is_idle);
+            }
+        }
+        for label in cells.drain(visible.len() * COLUMN_COUNT..) {
+            self.grid.remove(&label);
+        }
+        drop(cells);
+        drop(connections);
+
+        self.highlight_selected();
+    }
+
+    /// Select the connection of the `row`th rendered row
+    fn row_click(self: &Rc<Self>, row: usize) -> gtk::GestureClick {
+        let gesture = gtk::GestureClick::new();
+        let table = Rc::downgrade(self);
+        gesture.connect_pressed(move |_, _, _, _| {
+            let Some(table) = table.upgrade() else {
+                return;
+            };
+            let (rendered_start, _) = table.rendered.get();
+            let Some(conn) = table
+                .connections
+                .borrow()
+                .get(rendered_start + row)
+                .cloned()
+            else {
+                return;
+            };
+            *table.selected.borrow_mut() = Some(conn.key());
+            table.highlight_selected();
+            let on_selected = table.on_selected.borrow();
+            if let Some(callback) = &*on_selected {
+                callback(&conn);
+            }
+        });
+        gesture
+    }
+
+    fn header_click(self: &Rc<Self>, column: usize) -> gtk::GestureClick {
+        let gesture = gtk::GestureClick::new();
+        let table = Rc::downgrade(self);
+        gesture.connect_pressed(move |_, _, _, _| {
+            let Some(table) = table.upgrade() else {
+                return;
+            };
+            let on_header_clicked = table.on_header_clicked.borrow();
+            if let Some(callback) = &*on_header_clicked {
+                callback(column);
+            }
+        });
+        gesture
+    }
+
+    /// Copy a cell's text on right click, confirmed by a short-lived popover
+    fn copy_click(&self) -> gtk::GestureClick {
+        let gesture = gtk::GestureClick::new();
+        gesture.set_button(3);
+        let popovers = self.popovers.clone();
+        gesture.connect_pressed(move |gesture, _, x, y| {
+            let Some(label) = gesture.widget().and_downcast::<Label>() else {
+                return;
+            };
+            label.clipboard().set_text(&label.text());
+
+            let menu_model = gtk::gio::Menu::new();
+            menu_model.append(Some("Copied!"), None);
+            let menu = PopoverMenu::builder().menu_model(&menu_model).build();
+            menu.set_parent(&label);
+            menu.set_pointing_to(Some(&gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1)));
+            popovers.borrow_mut().push(menu.clone());
+
+            let popovers = popovers.clone();
+            let shown = menu.clone();
+            gtk::glib::timeout_add_seconds_local_once(1, move || {
+                shown.unparent();
+                popovers.borrow_mut().retain(|popover| popover != &shown);
+            });
+            menu.popup();
+        });
+        gesture
+    }
+
+    fn highlight_selected(&self) {
+        let (rendered_start, _) = self.rendered.get();
+        let selected = self.selected.borrow();
+        let connections = self.connections.borrow();
+        let selected_row = selected.as_ref().and_then(|key| {
+            connections
+                .get(rendered_start..)?
+                .iter()
+                .position(|conn| conn.key() == *key)
+        });
+        for (index, label) in self.cells.borrow().iter().enumerate() {
+            if Some(index / COLUMN_COUNT) == selected_row {
+                label.add_css_class("row-selected");
+            } else {
+                label.remove_css_class("row-selected");
+            }
+        }
+    }
+}
+
+/// Copy a focused cell's text with Ctrl+C
+fn copy_key() -> gtk::EventControllerKey {
+    let controller = gtk::EventControllerKey::new();
+    controller.connect_key_pressed(|controller, key, _, modifier| {
+        if key != gtk::gdk::Key::c || modifier != gtk::gdk::ModifierType::CONTROL_MASK {
+            return gtk::glib::Propagation::Proceed;
+        }
+        if let Some(label) = controller.widget().and_downcast::<Label>() {
+            label.clipboard().set_text(&label.text());
+        }
+        gtk::glib::Propagation::Stop
+    });
+    controller
+}
+
+/// Rough width of `text` for column sizing, at about 7 pixels a character;
+/// GTK does the actual layout
+fn estimate_text_width(text: &str) -> i32 {
+    (text.chars().count() * 7).max(40) as i32
+}
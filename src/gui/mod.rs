@@ -0,0 +1,53 @@
+//! GTK widgets of the network-monitor window, for GNOME apps that embed the
+//! network view. Both expect [`STYLE`] on their display.
+//!
+//! ```no_run
+//! # use gtk4 as gtk;
+//! # use gtk::prelude::*;
+//! use network_monitor::gui::{self, ConnectionDetails, ConnectionTable};
+//! use std::rc::Rc;
+//!
+//! # fn build(container: &gtk::Box, connections: Vec<network_monitor::models::Connection>) {
+//! gui::load_style(&gtk::gdk::Display::default().unwrap());
+//! let table = ConnectionTable::new();
+//! let details = Rc::new(ConnectionDetails::new());
+//! let pane = details.clone();
+//! table.connect_selected(move |conn| pane.show(conn));
+//! container.append(table.widget());
+//! container.append(details.widget());
+//! table.set_connections(connections);
+//! # }
+//! ```
+pub mod connection_details;
+pub mod connection_table;
+pub mod port_gauge;
+pub mod protocol_stats;
+pub mod top_talkers;
+pub mod tunnels;
+pub mod wireless;
+pub mod world_map;
+
+pub use connection_details::ConnectionDetails;
+pub use connection_table::{ConnectionTable, COLUMN_COUNT};
+pub use port_gauge::PortGauge;
+pub use protocol_stats::ProtocolStatsRow;
+pub use top_talkers::TopTalkersPanel;
+pub use tunnels::TunnelRows;
+pub use wireless::WirelessRows;
+pub use world_map::WorldMap;
+
+use gtk4 as gtk;
+
+/// Column widths, cell colors and the selected row highlight of the widgets
+pub const STYLE: &str = include_str!("style.css");
+
+/// Add [`STYLE`] to `display`, for apps that don't load the monitor's own stylesheet
+pub fn load_style(display: &gtk::gdk::Display) {
+    let css_provider = gtk::CssProvider::new();
+    css_provider.load_from_string(STYLE);
+    gtk::style_context_add_provider_for_display(
+        display,
+        &css_provider,
+        gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
+    );
+}
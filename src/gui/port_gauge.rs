@@ -0,0 +1,102 @@
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Label, LevelBar, Orientation};
+use gtk4 as gtk;
+
+use crate::models::ports::EXHAUSTION_WARNING;
+use crate::models::EphemeralPorts;
+
+/// Use of the ephemeral port range as a level bar with the port count,
+/// turning to the warning color when the range is nearly exhausted. Hidden
+/// until a backend reports it.
+pub struct PortGauge {
+    container: GtkBox,
+    bar: LevelBar,
+    label: Label,
+}
+
+impl PortGauge {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .halign(Align::Start)
+            .visible(false)
+            .build();
+        container.add_css_class("info-row");
+
+        let icon = gtk::Image::from_icon_name("network-server-symbolic");
+        icon.add_css_class("caption");
+        container.append(&icon);
+
+        let bar = LevelBar::builder()
+            .min_value(0.0)
+            .max_value(1.0)
+            .width_request(60)
+            .valign(Align::Center)
+            .build();
+        bar.add_css_class("ephemeral-ports");
+        // Blocks take the class of the first offset at or above the value
+        for offset in [
+            gtk::LEVEL_BAR_OFFSET_LOW,
+            gtk::LEVEL_BAR_OFFSET_HIGH,
+            gtk::LEVEL_BAR_OFFSET_FULL,
+        ] {
+            bar.remove_offset_value(Some(offset));
+        }
+        bar.add_offset_value("ports-normal", EXHAUSTION_WARNING);
+        bar.add_offset_value("ports-warning", 1.0);
+        container.append(&bar);
+
+        let label = Label::builder().halign(Align::Start).build();
+        label.add_css_class("caption");
+        container.append(&label);
+
+        Self {
+            container,
+            bar,
+            label,
+        }
+    }
+
+    /// The gauge, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `ports`, or hide the gauge when the backend doesn't report them
+    pub fn update(&self, ports: Option<&EphemeralPorts>) {
+        let Some(ports) = ports else {
+            self.container.set_visible(false);
+            return;
+        };
+        self.container.set_visible(true);
+        self.bar.set_value(ports.usage());
+        self.label.set_text(&format!(
+            "{} of {} ephemeral ports",
+            ports.in_use(),
+            ports.capacity()
+        ));
+        let mut tooltip = format!(
+            "TCP: {}, UDP: {} in use of ports {}-{}",
+            ports.tcp,
+            ports.udp,
+            ports.range.start(),
+            ports.range.end()
+        );
+        if ports.is_near_exhaustion() {
+            self.label.add_css_class("warning");
+            tooltip.push_str(
+                "\nNearly exhausted: new outgoing connections will fail once none are left",
+            );
+        } else {
+            self.label.remove_css_class("warning");
+        }
+        self.container.set_tooltip_text(Some(&tooltip));
+    }
+}
+
+impl Default for PortGauge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,76 @@
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Label, Orientation};
+use gtk4 as gtk;
+
+use crate::models::ProtocolStats;
+
+/// Kernel-wide TCP retransmission, reset and error rates on one line, with
+/// the totals since boot in the tooltip. Turns to the warning color when
+/// retransmissions point at loss; hidden until a backend reports them.
+pub struct ProtocolStatsRow {
+    container: GtkBox,
+    label: Label,
+}
+
+impl ProtocolStatsRow {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .halign(Align::Start)
+            .visible(false)
+            .build();
+        container.add_css_class("info-row");
+
+        let icon = gtk::Image::from_icon_name("view-refresh-symbolic");
+        icon.add_css_class("caption");
+        container.append(&icon);
+
+        let label = Label::builder().halign(Align::Start).build();
+        label.add_css_class("caption");
+        label.add_css_class("numeric");
+        container.append(&label);
+
+        Self { container, label }
+    }
+
+    /// The row, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `stats`, or hide the row when the backend doesn't report them
+    pub fn update(&self, stats: Option<&ProtocolStats>) {
+        let Some(stats) = stats else {
+            self.container.set_visible(false);
+            return;
+        };
+        self.container.set_visible(true);
+        self.label.set_text(&format!(
+            "{:.1} retransmits/s ({:.1}%) · {:.1} resets/s · {:.1} errors/s",
+            stats.retransmits_per_sec,
+            stats.retransmit_ratio * 100.0,
+            stats.resets_per_sec,
+            stats.errors_per_sec
+        ));
+        let totals = &stats.totals;
+        let mut tooltip = format!(
+            "Since boot: {} of {} TCP segments retransmitted, {} retransmission timeouts, \
+             {} connections reset, {} TCP and UDP receive errors",
+            totals.retransmits, totals.segments_sent, totals.timeouts, totals.resets, totals.errors
+        );
+        if stats.is_degraded() {
+            self.label.add_css_class("warning");
+            tooltip.push_str("\nMany retransmissions: packets are being lost or delayed");
+        } else {
+            self.label.remove_css_class("warning");
+        }
+        self.container.set_tooltip_text(Some(&tooltip));
+    }
+}
+
+impl Default for ProtocolStatsRow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,135 @@
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Label, Orientation};
+use gtk4 as gtk;
+
+use crate::models::{Talker, TopTalkers};
+use crate::services::AddressResolver;
+use crate::utils::formatter::Units;
+
+/// Compact two-column summary of the busiest processes and remote hosts,
+/// sized for a footer, with a third for applications when cgroup accounting
+/// counts them
+pub struct TopTalkersPanel {
+    container: GtkBox,
+    processes: GtkBox,
+    hosts: GtkBox,
+    applications: GtkBox,
+}
+
+impl TopTalkersPanel {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(18)
+            .halign(Align::Center)
+            .valign(Align::Start)
+            .build();
+        container.add_css_class("top-talkers");
+
+        let processes = Self::column();
+        let hosts = Self::column();
+        let applications = Self::column();
+        container.append(&processes);
+        container.append(&hosts);
+        container.append(&applications);
+
+        let panel = Self {
+            container,
+            processes,
+            hosts,
+            applications,
+        };
+        panel.update(&TopTalkers::default(), Units::default(), None);
+        panel
+    }
+
+    fn column() -> GtkBox {
+        GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(1)
+            .build()
+    }
+
+    /// The panel, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `talkers`, with host addresses through `resolver`
+    pub fn update(&self, talkers: &TopTalkers, units: Units, resolver: Option<&AddressResolver>) {
+        Self::fill(
+            &self.processes,
+            "Top Processes",
+            &talkers.processes,
+            units,
+            |name| name.to_string(),
+        );
+        Self::fill(
+            &self.hosts,
+            "Top Hosts",
+            &talkers.hosts,
+            units,
+            |host| match resolver {
+                Some(resolver) => resolver.resolve_host(host),
+                None => host.to_string(),
+            },
+        );
+        self.applications
+            .set_visible(!talkers.applications.is_empty());
+        Self::fill(
+            &self.applications,
+            "Top Applications",
+            &talkers.applications,
+            units,
+            |name| name.to_string(),
+        );
+    }
+
+    fn fill(
+        column: &GtkBox,
+        heading: &str,
+        talkers: &[Talker],
+        units: Units,
+        name: impl Fn(&str) -> String,
+    ) {
+        while let Some(child) = column.first_child() {
+            column.remove(&child);
+        }
+
+        let title = Label::builder().label(heading).halign(Align::Start).build();
+        title.add_css_class("caption-heading");
+        column.append(&title);
+
+        if talkers.is_empty() {
+            let empty = Label::builder()
+                .label("No traffic")
+                .halign(Align::Start)
+                .build();
+            empty.add_css_class("caption");
+            empty.add_css_class("dim-label");
+            column.append(&empty);
+        }
+        for talker in talkers {
+            let row = Label::builder()
+                .label(format!(
+                    "{}  ↑{} ↓{}",
+                    name(&talker.name),
+                    units.format_rate(talker.tx_rate),
+                    units.format_rate(talker.rx_rate)
+                ))
+                .halign(Align::Start)
+                .max_width_chars(48)
+                .ellipsize(gtk::pango::EllipsizeMode::Middle)
+                .build();
+            row.add_css_class("caption");
+            row.add_css_class("numeric");
+            column.append(&row);
+        }
+    }
+}
+
+impl Default for TopTalkersPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,120 @@
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Label, Orientation};
+use gtk4 as gtk;
+use std::time::SystemTime;
+
+use crate::models::{Tunnel, TunnelKind};
+use crate::utils::formatter::{format_duration, Units};
+
+/// One line per VPN tunnel: the peer endpoint, how long ago the last
+/// handshake was and the bytes through the interface. Tunnels whose peers
+/// stopped answering turn to the warning color; hidden without tunnels.
+pub struct TunnelRows {
+    container: GtkBox,
+}
+
+impl TunnelRows {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(3)
+            .visible(false)
+            .build();
+        Self { container }
+    }
+
+    /// The rows, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `tunnels` as of `now`, replacing the previous rows
+    pub fn update(&self, tunnels: &[Tunnel], units: Units, now: SystemTime) {
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+        self.container.set_visible(!tunnels.is_empty());
+        for tunnel in tunnels {
+            self.container.append(&tunnel_row(tunnel, units, now));
+        }
+    }
+}
+
+impl Default for TunnelRows {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn tunnel_row(tunnel: &Tunnel, units: Units, now: SystemTime) -> GtkBox {
+    let row = GtkBox::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .halign(Align::Start)
+        .build();
+    row.add_css_class("info-row");
+
+    let icon = gtk::Image::from_icon_name("network-vpn-symbolic");
+    icon.add_css_class("caption");
+    row.append(&icon);
+
+    let mut text = tunnel.name.clone();
+    let peers = tunnel.peers.as_deref().unwrap_or_default();
+    match peers {
+        [] => text.push_str(&format!(" ({})", tunnel.kind)),
+        [peer] => {
+            if let Some(endpoint) = peer.endpoint {
+                text.push_str(&format!(" → {endpoint}"));
+            }
+        }
+        _ => text.push_str(&format!(" → {} peers", peers.len())),
+    }
+    if tunnel.peers.is_some() {
+        match tunnel
+            .last_handshake()
+            .and_then(|at| now.duration_since(at).ok())
+        {
+            Some(age) => text.push_str(&format!(
+                " · handshake {} ago",
+                format_duration(age.as_secs())
+            )),
+            None => text.push_str(" · no handshake"),
+        }
+    }
+    text.push_str(&format!(
+        " · ↓{} ↑{}",
+        units.format_total(tunnel.rx_bytes),
+        units.format_total(tunnel.tx_bytes)
+    ));
+    let label = Label::builder().label(text).halign(Align::Start).build();
+    label.add_css_class("caption");
+    label.add_css_class("numeric");
+    row.append(&label);
+
+    let addresses: Vec<String> = tunnel.addresses.iter().map(|ip| ip.to_string()).collect();
+    let mut tooltip = format!("{} tunnel", tunnel.kind);
+    if !addresses.is_empty() {
+        tooltip.push_str(&format!(
+            "; connections from {} go through it",
+            addresses.join(", ")
+        ));
+    }
+    for peer in peers {
+        let endpoint = peer
+            .endpoint
+            .map_or_else(|| "unknown endpoint".to_string(), |e| e.to_string());
+        tooltip.push_str(&format!(
+            "\nPeer {endpoint}: ↓{} ↑{}",
+            units.format_total(peer.rx_bytes),
+            units.format_total(peer.tx_bytes)
+        ));
+    }
+    if tunnel.is_stale(now) {
+        label.add_css_class("warning");
+        tooltip.push_str("\nNo recent handshake: traffic isn't getting through");
+    } else if tunnel.kind == TunnelKind::WireGuard && tunnel.peers.is_none() {
+        tooltip.push_str("\nPeers need root or CAP_NET_ADMIN to read");
+    }
+    row.set_tooltip_text(Some(&tooltip));
+    row
+}
@@ -0,0 +1,98 @@
+use gtk::prelude::*;
+use gtk::{Align, Box as GtkBox, Label, Orientation};
+use gtk4 as gtk;
+
+use crate::models::{InterfaceStats, SignalQuality, WirelessLink};
+use crate::utils::formatter::Units;
+
+/// One line per Wi-Fi interface: the network, signal, bitrate and band,
+/// with the interface's throughput when the backend reports it. A weak
+/// signal turns to the warning color, telling bad Wi-Fi from slow servers;
+/// hidden without wireless interfaces.
+pub struct WirelessRows {
+    container: GtkBox,
+}
+
+impl WirelessRows {
+    pub fn new() -> Self {
+        let container = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(3)
+            .visible(false)
+            .build();
+        Self { container }
+    }
+
+    /// The rows, ready to pack
+    pub fn widget(&self) -> &GtkBox {
+        &self.container
+    }
+
+    /// Show `links`, replacing the previous rows
+    pub fn update(&self, links: &[WirelessLink], interfaces: &[InterfaceStats], units: Units) {
+        while let Some(child) = self.container.first_child() {
+            self.container.remove(&child);
+        }
+        self.container.set_visible(!links.is_empty());
+        for link in links {
+            let stats = interfaces.iter().find(|i| i.name == link.interface);
+            self.container.append(&wireless_row(link, stats, units));
+        }
+    }
+}
+
+impl Default for WirelessRows {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn wireless_row(link: &WirelessLink, stats: Option<&InterfaceStats>, units: Units) -> GtkBox {
+    let row = GtkBox::builder()
+        .orientation(Orientation::Horizontal)
+        .spacing(6)
+        .halign(Align::Start)
+        .build();
+    row.add_css_class("info-row");
+
+    let icon = gtk::Image::from_icon_name(match link.quality() {
+        _ if !link.is_connected() => "network-wireless-offline-symbolic",
+        Some(SignalQuality::Excellent) => "network-wireless-signal-excellent-symbolic",
+        Some(SignalQuality::Good) => "network-wireless-signal-good-symbolic",
+        Some(SignalQuality::Fair) => "network-wireless-signal-ok-symbolic",
+        Some(SignalQuality::Poor) | None => "network-wireless-signal-weak-symbolic",
+    });
+    icon.add_css_class("caption");
+    row.append(&icon);
+
+    let mut text = format!("{} {link}", link.interface);
+    if let Some(stats) = stats {
+        text.push_str(&format!(
+            " · ↑{} ↓{}",
+            units.format_rate(stats.tx_rate),
+            units.format_rate(stats.rx_rate)
+        ));
+    }
+    let label = Label::builder().label(text).halign(Align::Start).build();
+    label.add_css_class("caption");
+    label.add_css_class("numeric");
+    row.append(&label);
+
+    let mut tooltip = String::from("Wi-Fi link to the access point");
+    if let Some(frequency) = link.frequency {
+        tooltip.push_str(&format!("\nChannel frequency {frequency} MHz"));
+    }
+    if let Some(rate) = link.rx_bitrate {
+        tooltip.push_str(&format!(
+            "\nReceive bitrate {:.1} Mbit/s",
+            f64::from(rate) / 1000.0
+        ));
+    }
+    if link.quality() == Some(SignalQuality::Poor) {
+        label.add_css_class("warning");
+        tooltip
+            .push_str("\nWeak signal: slow or dropped traffic may be the Wi-Fi, not the servers");
+    }
+    row.set_tooltip_text(Some(&tooltip));
+    row
+}
@@ -0,0 +1,612 @@
+use gtk::prelude::*;
+use gtk::{cairo, DrawingArea};
+use gtk4 as gtk;
+use std::cell::{Cell, RefCell};
+use std::collections::hash_map::DefaultHasher;
+use std::f64::consts::TAU;
+use std::hash::{Hash, Hasher};
+use std::net::IpAddr;
+use std::rc::Rc;
+
+use crate::models::{centroid, Connection, LocatedHost};
+use crate::utils::formatter::Units;
+
+/// Coarse outlines of the continents and larger islands as longitude and
+/// latitude: enough to tell where a dot is, without shipping map data
+const COASTLINES: &[&[(f64, f64)]] = &[
+    // North and Central America
+    &[
+        (-168.0, 66.0),
+        (-156.0, 71.5),
+        (-141.0, 69.6),
+        (-115.0, 68.5),
+        (-95.0, 72.0),
+        (-82.0, 69.0),
+        (-87.0, 64.0),
+        (-94.0, 59.0),
+        (-82.0, 55.0),
+        (-79.0, 51.0),
+        (-76.0, 56.0),
+        (-78.0, 62.0),
+        (-64.0, 60.0),
+        (-56.0, 52.0),
+        (-60.0, 48.0),
+        (-66.0, 45.0),
+        (-71.0, 41.0),
+        (-76.0, 37.0),
+        (-81.0, 31.0),
+        (-80.0, 25.2),
+        (-84.0, 30.0),
+        (-94.0, 29.5),
+        (-97.5, 22.0),
+        (-96.0, 19.0),
+        (-90.5, 21.0),
+        (-87.0, 21.5),
+        (-88.0, 16.0),
+        (-83.5, 11.0),
+        (-77.5, 8.5),
+        (-80.0, 7.5),
+        (-86.0, 11.0),
+        (-92.0, 14.5),
+        (-98.0, 16.0),
+        (-105.0, 19.5),
+        (-109.5, 26.5),
+        (-114.7, 31.7),
+        (-110.0, 23.0),
+        (-114.5, 28.0),
+        (-117.0, 32.5),
+        (-122.5, 37.5),
+        (-124.0, 46.0),
+        (-123.0, 49.0),
+        (-131.0, 54.5),
+        (-140.0, 59.8),
+        (-152.0, 59.0),
+        (-164.0, 54.5),
+        (-158.0, 58.5),
+        (-165.0, 62.5),
+        (-168.0, 66.0),
+    ],
+    // Greenland
+    &[
+        (-73.0, 78.0),
+        (-45.0, 82.5),
+        (-22.0, 82.0),
+        (-18.0, 77.0),
+        (-22.0, 70.0),
+        (-40.0, 65.0),
+        (-43.0, 60.0),
+        (-52.0, 65.0),
+        (-60.0, 76.0),
+        (-73.0, 78.0),
+    ],
+    // South America
+    &[
+        (-77.5, 8.5),
+        (-71.5, 12.3),
+        (-60.0, 8.5),
+        (-50.0, 0.0),
+        (-35.0, -5.0),
+        (-39.0, -14.0),
+        (-41.0, -22.0),
+        (-48.5, -26.0),
+        (-53.0, -34.0),
+        (-57.5, -38.0),
+        (-65.0, -41.0),
+        (-67.5, -46.5),
+        (-69.0, -51.0),
+        (-71.0, -54.0),
+        (-75.5, -48.0),
+        (-73.5, -37.0),
+        (-70.5, -23.0),
+        (-70.3, -18.0),
+        (-77.0, -12.0),
+        (-81.0, -5.0),
+        (-80.0, 0.5),
+        (-77.3, 7.0),
+        (-77.5, 8.5),
+    ],
+    // Europe and Asia
+    &[
+        (-9.5, 37.0),
+        (-6.0, 36.2),
+        (0.0, 38.5),
+        (3.0, 43.3),
+        (10.0, 44.0),
+        (12.5, 41.8),
+        (15.6, 38.0),
+        (18.5, 40.2),
+        (13.5, 43.6),
+        (13.7, 45.6),
+        (19.5, 41.8),
+        (23.0, 36.5),
+        (22.5, 40.5),
+        (26.0, 40.8),
+        (26.0, 37.0),
+        (36.0, 36.5),
+        (34.2, 31.3),
+        (34.9, 29.5),
+        (39.0, 21.5),
+        (43.0, 13.0),
+        (52.0, 15.5),
+        (57.8, 19.0),
+        (59.8, 22.5),
+        (56.0, 26.0),
+        (51.5, 24.0),
+        (48.0, 29.5),
+        (57.0, 25.7),
+        (66.5, 25.4),
+        (72.8, 19.0),
+        (77.0, 8.0),
+        (80.3, 13.5),
+        (87.0, 21.0),
+        (91.8, 22.5),
+        (94.3, 16.0),
+        (98.5, 12.0),
+        (100.3, 5.2),
+        (103.5, 1.4),
+        (100.5, 7.5),
+        (100.0, 13.5),
+        (105.0, 8.7),
+        (109.2, 12.0),
+        (106.5, 18.0),
+        (108.0, 21.5),
+        (113.5, 22.3),
+        (119.5, 25.8),
+        (122.0, 31.0),
+        (119.0, 35.0),
+        (122.5, 37.0),
+        (118.0, 39.2),
+        (121.5, 40.8),
+        (124.3, 39.9),
+        (126.5, 34.5),
+        (129.5, 37.0),
+        (130.7, 42.3),
+        (135.5, 43.7),
+        (140.3, 50.0),
+        (135.0, 55.0),
+        (140.5, 58.0),
+        (151.0, 59.0),
+        (156.7, 61.6),
+        (156.6, 57.5),
+        (155.9, 51.0),
+        (162.5, 56.2),
+        (165.0, 60.0),
+        (177.0, 62.5),
+        (180.0, 65.0),
+        (180.0, 68.5),
+        (170.0, 70.0),
+        (150.0, 71.5),
+        (130.0, 71.0),
+        (113.0, 73.7),
+        (104.0, 77.7),
+        (88.0, 75.2),
+        (72.5, 72.8),
+        (68.5, 68.5),
+        (44.0, 68.4),
+        (40.0, 66.5),
+        (28.0, 71.0),
+        (15.0, 68.5),
+        (5.0, 62.0),
+        (5.5, 59.0),
+        (10.5, 59.3),
+        (12.5, 56.0),
+        (16.5, 56.5),
+        (18.8, 59.5),
+        (21.5, 64.5),
+        (25.3, 65.1),
+        (21.3, 60.8),
+        (28.5, 60.5),
+        (21.5, 57.4),
+        (19.5, 54.4),
+        (11.0, 54.0),
+        (8.5, 57.1),
+        (8.5, 53.5),
+        (4.0, 51.8),
+        (1.6, 50.2),
+        (-1.5, 49.7),
+        (-4.5, 48.6),
+        (-1.2, 45.5),
+        (-1.6, 43.4),
+        (-9.2, 43.0),
+        (-9.5, 38.8),
+        (-9.5, 37.0),
+    ],
+    // Africa
+    &[
+        (-5.9, 35.8),
+        (3.0, 36.8),
+        (10.0, 37.3),
+        (10.5, 33.7),
+        (15.2, 32.3),
+        (20.0, 30.9),
+        (20.0, 32.2),
+        (29.0, 30.9),
+        (32.3, 31.2),
+        (32.5, 29.8),
+        (35.0, 24.0),
+        (38.5, 18.0),
+        (43.3, 12.6),
+        (44.5, 10.4),
+        (51.2, 11.8),
+        (48.0, 4.5),
+        (40.0, -3.0),
+        (40.6, -15.5),
+        (35.5, -23.5),
+        (32.0, -29.0),
+        (27.5, -33.5),
+        (20.0, -34.8),
+        (17.8, -31.0),
+        (14.5, -22.5),
+        (11.8, -17.0),
+        (13.5, -10.5),
+        (9.0, -1.0),
+        (9.6, 3.0),
+        (6.0, 4.3),
+        (2.0, 6.3),
+        (-7.5, 4.4),
+        (-13.5, 9.5),
+        (-17.5, 14.7),
+        (-16.5, 20.0),
+        (-13.0, 27.7),
+        (-9.5, 32.5),
+        (-5.9, 35.8),
+    ],
+    // Australia
+    &[
+        (114.0, -22.0),
+        (115.0, -34.0),
+        (123.5, -33.9),
+        (129.0, -31.6),
+        (137.7, -35.6),
+        (140.0, -38.0),
+        (146.0, -39.0),
+        (150.0, -37.5),
+        (153.5, -25.8),
+        (146.0, -19.0),
+        (142.5, -10.7),
+        (141.5, -16.5),
+        (140.0, -17.7),
+        (136.0, -15.0),
+        (137.0, -12.0),
+        (130.0, -13.0),
+        (122.0, -17.5),
+        (114.0, -22.0),
+    ],
+    // Great Britain
+    &[
+        (-5.7, 50.0),
+        (1.5, 51.0),
+        (1.7, 52.6),
+        (-1.5, 55.0),
+        (-3.2, 58.6),
+        (-5.0, 58.6),
+        (-6.2, 57.0),
+        (-3.0, 54.9),
+        (-4.7, 52.8),
+        (-3.0, 51.4),
+        (-5.7, 50.0),
+    ],
+    // Ireland
+    &[
+        (-6.0, 52.2),
+        (-5.6, 54.6),
+        (-7.2, 55.3),
+        (-10.0, 54.0),
+        (-10.3, 51.6),
+        (-6.0, 52.2),
+    ],
+    // Iceland
+    &[
+        (-24.0, 65.5),
+        (-16.0, 66.5),
+        (-13.5, 65.3),
+        (-18.0, 63.4),
+        (-24.0, 65.5),
+    ],
+    // Japan
+    &[
+        (130.0, 31.2),
+        (132.0, 33.8),
+        (139.0, 34.8),
+        (141.0, 38.3),
+        (141.4, 41.4),
+        (143.5, 42.0),
+        (145.5, 43.3),
+        (141.7, 45.5),
+        (140.0, 41.5),
+        (139.6, 38.2),
+        (136.0, 35.7),
+        (130.8, 34.0),
+        (130.0, 31.2),
+    ],
+    // Sumatra
+    &[
+        (95.3, 5.6),
+        (104.0, -1.0),
+        (106.0, -5.9),
+        (101.0, -2.5),
+        (95.3, 5.6),
+    ],
+    // Java
+    &[
+        (105.2, -6.8),
+        (111.0, -6.5),
+        (114.5, -7.7),
+        (108.0, -7.8),
+        (105.2, -6.8),
+    ],
+    // Borneo
+    &[
+        (109.0, 1.8),
+        (116.0, 6.9),
+        (119.0, 5.2),
+        (116.0, -3.5),
+        (111.0, -3.0),
+        (109.0, 1.8),
+    ],
+    // New Guinea
+    &[
+        (131.0, -1.0),
+        (141.0, -2.6),
+        (147.5, -6.2),
+        (150.0, -10.5),
+        (144.0, -7.8),
+        (138.0, -8.4),
+        (133.0, -4.0),
+        (131.0, -1.0),
+    ],
+    // Philippines
+    &[
+        (120.6, 18.5),
+        (122.3, 18.4),
+        (124.0, 13.0),
+        (126.5, 7.0),
+        (122.0, 7.0),
+        (121.0, 13.8),
+        (120.6, 18.5),
+    ],
+    // Madagascar
+    &[
+        (49.3, -12.0),
+        (50.5, -15.5),
+        (47.1, -24.9),
+        (43.3, -22.0),
+        (44.4, -16.2),
+        (49.3, -12.0),
+    ],
+    // New Zealand
+    &[
+        (172.7, -34.4),
+        (178.5, -37.7),
+        (175.0, -41.5),
+        (174.3, -41.7),
+        (171.2, -44.5),
+        (169.0, -46.6),
+        (166.5, -46.0),
+        (172.7, -40.5),
+        (174.6, -39.8),
+        (172.7, -34.4),
+    ],
+    // Cuba and Hispaniola
+    &[
+        (-85.0, 21.9),
+        (-82.0, 23.1),
+        (-74.1, 20.2),
+        (-68.3, 18.6),
+        (-74.5, 18.4),
+        (-77.7, 19.9),
+        (-85.0, 21.9),
+    ],
+];
+
+/// Latitudes shown; beyond them there are no servers to plot
+const NORTH: f64 = 84.0;
+const SOUTH: f64 = -58.0;
+
+/// Furthest a host is moved from the middle of its country, in degrees, so
+/// the hosts of one country don't cover each other
+const SPREAD_DEGREES: f64 = 3.0;
+
+/// Host as drawn, for clicks and tooltips
+struct Dot {
+    host: LocatedHost,
+    x: f64,
+    y: f64,
+    radius: f64,
+}
+
+/// Callback for a click on a host
+type HostCallback = Box<dyn Fn(&IpAddr)>;
+
+/// World map of the remote hosts GeoIP placed, each a dot near the middle
+/// of its country, larger the more traffic it carries. Hosts in countries
+/// without a known middle are left out. Drawn offline from coarse built-in
+/// outlines.
+pub struct WorldMap {
+    area: DrawingArea,
+    hosts: RefCell<Vec<LocatedHost>>,
+    /// Where the hosts were last drawn, busiest first
+    dots: RefCell<Vec<Dot>>,
+    units: Cell<Units>,
+    on_host_activated: RefCell<Option<HostCallback>>,
+}
+
+impl WorldMap {
+    pub fn new() -> Rc<Self> {
+        let area = DrawingArea::builder()
+            .hexpand(true)
+            .content_height(240)
+            .has_tooltip(true)
+            .build();
+        area.add_css_class("world-map");
+
+        let map = Rc::new(Self {
+            area,
+            hosts: RefCell::new(Vec::new()),
+            dots: RefCell::new(Vec::new()),
+            units: Cell::new(Units::default()),
+            on_host_activated: RefCell::new(None),
+        });
+
+        let weak = Rc::downgrade(&map);
+        map.area.set_draw_func(move |area, cr, width, height| {
+            if let Some(map) = weak.upgrade() {
+                map.draw(area, cr, f64::from(width), f64::from(height));
+            }
+        });
+
+        let weak = Rc::downgrade(&map);
+        map.area.connect_query_tooltip(move |_, x, y, _, tooltip| {
+            let Some(map) = weak.upgrade() else {
+                return false;
+            };
+            let dots = map.dots.borrow();
+            let Some(dot) = hit(&dots, f64::from(x), f64::from(y)) else {
+                return false;
+            };
+            let units = map.units.get();
+            tooltip.set_text(Some(&format!(
+                "{} ({}) ↑{} ↓{}\nClick to show its connections",
+                dot.host.address,
+                dot.host.country,
+                units.format_rate(dot.host.tx_rate),
+                units.format_rate(dot.host.rx_rate)
+            )));
+            true
+        });
+
+        let click = gtk::GestureClick::new();
+        let weak = Rc::downgrade(&map);
+        click.connect_released(move |_, _, x, y| {
+            let Some(map) = weak.upgrade() else {
+                return;
+            };
+            let Some(address) = hit(&map.dots.borrow(), x, y).map(|dot| dot.host.address) else {
+                return;
+            };
+            let on_host_activated = map.on_host_activated.borrow();
+            if let Some(callback) = &*on_host_activated {
+                callback(&address);
+            }
+        });
+        map.area.add_controller(click);
+        map
+    }
+
+    /// The map, ready to pack
+    pub fn widget(&self) -> &DrawingArea {
+        &self.area
+    }
+
+    /// Plot the remote hosts of `connections` that have a country, with
+    /// rates in `units`
+    pub fn update(&self, connections: &[Connection], units: Units) {
+        *self.hosts.borrow_mut() = LocatedHost::from_connections(connections);
+        self.units.set(units);
+        self.area.queue_draw();
+    }
+
+    /// Run `callback` with the address of a host when its dot is clicked,
+    /// e.g. to filter a table to it
+    pub fn connect_host_activated(&self, callback: impl Fn(&IpAddr) + 'static) {
+        *self.on_host_activated.borrow_mut() = Some(Box::new(callback));
+    }
+
+    fn draw(&self, area: &DrawingArea, cr: &cairo::Context, width: f64, height: f64) {
+        let projection = Projection::fit(width, height);
+        let foreground = area.color();
+        let (red, green, blue) = (
+            f64::from(foreground.red()),
+            f64::from(foreground.green()),
+            f64::from(foreground.blue()),
+        );
+
+        for outline in COASTLINES {
+            for (index, &(longitude, latitude)) in outline.iter().enumerate() {
+                let (x, y) = projection.point(longitude, latitude);
+                if index == 0 {
+                    cr.move_to(x, y);
+                } else {
+                    cr.line_to(x, y);
+                }
+            }
+            cr.close_path();
+        }
+        cr.set_source_rgba(red, green, blue, 0.1);
+        // Cairo errors only stick to the context; a failed frame is redrawn
+        let _ = cr.fill_preserve();
+        cr.set_source_rgba(red, green, blue, 0.3);
+        cr.set_line_width(1.0);
+        let _ = cr.stroke();
+
+        let mut dots = self.dots.borrow_mut();
+        dots.clear();
+        for host in self.hosts.borrow().iter() {
+            let Some((latitude, longitude)) = centroid(&host.country) else {
+                continue;
+            };
+            let (east, north) = spread(&host.address);
+            let (x, y) = projection.point(longitude + east, latitude + north);
+            let radius = 3.0 + 3.0 * (host.rate() as f64 + 1.0).log10();
+            cr.arc(x, y, radius, 0.0, TAU);
+            if host.rate() > 0 {
+                cr.set_source_rgba(0.21, 0.52, 0.89, 0.55);
+            } else {
+                cr.set_source_rgba(red, green, blue, 0.35);
+            }
+            let _ = cr.fill_preserve();
+            cr.set_source_rgba(red, green, blue, 0.6);
+            let _ = cr.stroke();
+            dots.push(Dot {
+                host: host.clone(),
+                x,
+                y,
+                radius,
+            });
+        }
+    }
+}
+
+/// Equirectangular projection of the shown latitudes into the widget,
+/// centered and keeping the aspect ratio
+struct Projection {
+    scale: f64,
+    left: f64,
+    top: f64,
+}
+
+impl Projection {
+    fn fit(width: f64, height: f64) -> Self {
+        let scale = (width / 360.0).min(height / (NORTH - SOUTH));
+        Self {
+            scale,
+            left: (width - 360.0 * scale) / 2.0,
+            top: (height - (NORTH - SOUTH) * scale) / 2.0,
+        }
+    }
+
+    fn point(&self, longitude: f64, latitude: f64) -> (f64, f64) {
+        (
+            self.left + (longitude + 180.0) * self.scale,
+            self.top + (NORTH - latitude.clamp(SOUTH, NORTH)) * self.scale,
+        )
+    }
+}
+
+/// Offset in degrees east and north of a host from the middle of its
+/// country, the same for an address every time
+fn spread(address: &IpAddr) -> (f64, f64) {
+    let mut hasher = DefaultHasher::new();
+    address.hash(&mut hasher);
+    let hash = hasher.finish();
+    let angle = (hash & 0xffff) as f64 / 65536.0 * TAU;
+    let distance = ((hash >> 16) & 0xffff) as f64 / 65536.0 * SPREAD_DEGREES;
+    (distance * angle.cos(), distance * angle.sin())
+}
+
+/// Topmost dot under a point; quieter hosts are drawn last, on top
+fn hit(dots: &[Dot], x: f64, y: f64) -> Option<&Dot> {
+    dots.iter()
+        .rev()
+        .find(|dot| (dot.x - x).hypot(dot.y - y) <= dot.radius.max(6.0))
+}
@@ -0,0 +1,96 @@
+use std::io::{self, BufRead, Write};
+
+use network_monitor_core::services::privileged_helper::{
+    CONNECTS_REQUEST, SCAN_REQUEST, TRAFFIC_REQUEST,
+};
+use network_monitor_core::services::ProcessCache;
+#[cfg(target_os = "linux")]
+use network_monitor_core::services::{CgroupTraffic, ConnectTracer};
+
+/// Privileged helper started by the GUI/TUI through pkexec.
+///
+/// Reads one request per line on stdin and answers each `scan` with a single JSON
+/// line mapping socket inodes to their owning process, and each `traffic` with
+/// one mapping cgroup IDs to the bytes their sockets sent and received, and
+/// each `connects` with the connection attempts since the previous one; the
+/// last two answer null when their programs can't be attached. Exits when
+/// stdin closes.
+///
+/// Started with arguments, it carries out that one request instead and exits:
+/// `limit PID BYTES_PER_SEC` or `unlimit PID` for bandwidth limits.
+fn main() -> io::Result<()> {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        if let Err(e) = run_request(&args) {
+            eprintln!("{e}");
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let mut process_cache = ProcessCache::new();
+    let stdin = io::stdin();
+    let mut stdout = io::stdout().lock();
+
+    // Attached on the first request needing them, until the helper exits
+    #[cfg(target_os = "linux")]
+    let mut cgroup_traffic = None;
+    #[cfg(target_os = "linux")]
+    let mut connect_tracer = None;
+
+    for line in stdin.lock().lines() {
+        match line?.trim() {
+            SCAN_REQUEST => {
+                let owners = process_cache.export_owners();
+                serde_json::to_writer(&mut stdout, &owners)?;
+            }
+            TRAFFIC_REQUEST => {
+                #[cfg(target_os = "linux")]
+                let counters = cgroup_traffic
+                    .get_or_insert_with(|| {
+                        CgroupTraffic::attach().map_err(|e| eprintln!("{e}")).ok()
+                    })
+                    .as_ref()
+                    .map(CgroupTraffic::counters);
+                #[cfg(not(target_os = "linux"))]
+                let counters = serde_json::Value::Null;
+                serde_json::to_writer(&mut stdout, &counters)?;
+            }
+            CONNECTS_REQUEST => {
+                #[cfg(target_os = "linux")]
+                let events = connect_tracer
+                    .get_or_insert_with(|| {
+                        ConnectTracer::attach().map_err(|e| eprintln!("{e}")).ok()
+                    })
+                    .as_ref()
+                    .map(ConnectTracer::events);
+                #[cfg(not(target_os = "linux"))]
+                let events = serde_json::Value::Null;
+                serde_json::to_writer(&mut stdout, &events)?;
+            }
+            _ => continue,
+        }
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+
+    Ok(())
+}
+
+fn run_request(args: &[String]) -> Result<(), String> {
+    use network_monitor_core::services::bandwidth_limit::{
+        self, parse_pid, LIMIT_REQUEST, UNLIMIT_REQUEST,
+    };
+
+    let result = match args {
+        [request, pid, rate] if request == LIMIT_REQUEST => {
+            let rate = rate.parse().map_err(|_| format!("invalid rate '{rate}'"))?;
+            parse_pid(pid).and_then(|pid| bandwidth_limit::apply(pid, rate))
+        }
+        [request, pid] if request == UNLIMIT_REQUEST => {
+            parse_pid(pid).and_then(bandwidth_limit::remove)
+        }
+        _ => return Err(format!("unknown request '{}'", args.join(" "))),
+    };
+    result.map_err(|e| e.to_string())
+}
@@ -0,0 +1,422 @@
+use crate::error::{NetworkMonitorError, Result};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+
+/// Something a key can do in the TUI. Names are the keys of the `[keys]`
+/// config section.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    Quit,
+    Resolver,
+    Refresh,
+    AutoRefresh,
+    Blocked,
+    Dns,
+    Routes,
+    Events,
+    Multicast,
+    Countries,
+    Fail2ban,
+    Group,
+    Diagnostics,
+    TopTalkers,
+    HideIdle,
+    /// Open the bandwidth limit menu for the selected connection's process
+    LimitBandwidth,
+    /// Open the `:` command line
+    Command,
+    /// Copy the selected connection to the clipboard as JSON
+    CopyJson,
+    /// Collect from the next of the `[remotes]` hosts, then this machine again
+    NextHost,
+    Details,
+    CloseDetails,
+    Up,
+    Down,
+    Left,
+    Right,
+    FirstColumn,
+    LastColumn,
+    /// Sort by a table column, toggling direction when already sorted by it
+    Sort(usize),
+}
+
+impl Action {
+    const ALL: [Action; 37] = [
+        Action::Quit,
+        Action::Resolver,
+        Action::Refresh,
+        Action::AutoRefresh,
+        Action::Blocked,
+        Action::Dns,
+        Action::Routes,
+        Action::Events,
+        Action::Multicast,
+        Action::Countries,
+        Action::Fail2ban,
+        Action::Group,
+        Action::Diagnostics,
+        Action::TopTalkers,
+        Action::HideIdle,
+        Action::LimitBandwidth,
+        Action::Command,
+        Action::CopyJson,
+        Action::NextHost,
+        Action::Details,
+        Action::CloseDetails,
+        Action::Up,
+        Action::Down,
+        Action::Left,
+        Action::Right,
+        Action::FirstColumn,
+        Action::LastColumn,
+        Action::Sort(0),
+        Action::Sort(1),
+        Action::Sort(2),
+        Action::Sort(3),
+        Action::Sort(4),
+        Action::Sort(5),
+        Action::Sort(6),
+        Action::Sort(7),
+        Action::Sort(8),
+        Action::Sort(9),
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Resolver => "resolver",
+            Action::Refresh => "refresh",
+            Action::AutoRefresh => "auto_refresh",
+            Action::Blocked => "blocked",
+            Action::Dns => "dns",
+            Action::Routes => "routes",
+            Action::Events => "events",
+            Action::Multicast => "multicast",
+            Action::Countries => "countries",
+            Action::Fail2ban => "fail2ban",
+            Action::Group => "group",
+            Action::Diagnostics => "diagnostics",
+            Action::TopTalkers => "top_talkers",
+            Action::HideIdle => "hide_idle",
+            Action::LimitBandwidth => "limit_bandwidth",
+            Action::Command => "command",
+            Action::CopyJson => "copy_json",
+            Action::NextHost => "next_host",
+            Action::Details => "details",
+            Action::CloseDetails => "close_details",
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::FirstColumn => "first_column",
+            Action::LastColumn => "last_column",
+            Action::Sort(0) => "sort_program",
+            Action::Sort(1) => "sort_protocol",
+            Action::Sort(2) => "sort_local",
+            Action::Sort(3) => "sort_remote",
+            Action::Sort(4) => "sort_state",
+            Action::Sort(5) => "sort_tx",
+            Action::Sort(6) => "sort_rx",
+            Action::Sort(7) => "sort_command",
+            Action::Sort(8) => "sort_sendq",
+            Action::Sort(_) => "sort_recvq",
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// Keys bound when the config doesn't rebind the action
+    fn default_keys(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Resolver => &["r"],
+            Action::Refresh => &["R"],
+            Action::AutoRefresh => &["a"],
+            Action::Blocked => &["b"],
+            Action::Dns => &["n"],
+            Action::Routes => &["o"],
+            Action::Events => &["e"],
+            Action::Multicast => &["m"],
+            Action::Countries => &["c"],
+            Action::Fail2ban => &["f"],
+            Action::Group => &["g"],
+            Action::Diagnostics => &["D"],
+            Action::TopTalkers => &["t"],
+            Action::HideIdle => &["i"],
+            Action::LimitBandwidth => &["L"],
+            Action::Command => &[":"],
+            Action::CopyJson => &["y"],
+            Action::NextHost => &["H"],
+            Action::Details => &["Enter"],
+            Action::CloseDetails => &["Esc"],
+            Action::Up => &["Up"],
+            Action::Down => &["Down"],
+            Action::Left => &["Left"],
+            Action::Right => &["Right"],
+            Action::FirstColumn => &["Home", "Shift+Left", "Ctrl+Left"],
+            Action::LastColumn => &["End", "Shift+Right", "Ctrl+Right"],
+            Action::Sort(0) => &["1"],
+            Action::Sort(1) => &["2"],
+            Action::Sort(2) => &["3"],
+            Action::Sort(3) => &["4"],
+            Action::Sort(4) => &["5"],
+            Action::Sort(5) => &["6"],
+            Action::Sort(6) => &["7"],
+            Action::Sort(7) => &["8"],
+            Action::Sort(8) => &["9"],
+            Action::Sort(_) => &["0"],
+        }
+    }
+}
+
+/// A key with its modifiers, e.g. "Ctrl+r", "F5" or "R"
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    /// Parse "[Ctrl+][Alt+][Shift+]KEY". KEY is a single character, which is
+    /// case sensitive, or a named key such as Enter, Esc, Up, PageDown or F1.
+    pub fn parse(spec: &str) -> std::result::Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = spec.trim();
+        // "+" on its own (or after a modifier) is the plus key, not a separator
+        while let Some((modifier, key)) = rest.split_once('+').filter(|(_, key)| !key.is_empty()) {
+            modifiers |= match modifier.to_ascii_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("unknown modifier '{modifier}' in '{spec}'")),
+            };
+            rest = key;
+        }
+
+        let mut chars = rest.chars();
+        let code = match (chars.next(), chars.next()) {
+            (Some(c), None) => KeyCode::Char(c),
+            _ => match rest.to_ascii_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                "pageup" => KeyCode::PageUp,
+                "pagedown" => KeyCode::PageDown,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n @ 1..=12) => KeyCode::F(n),
+                    _ => return Err(format!("unknown key '{rest}' in '{spec}'")),
+                },
+            },
+        };
+        Ok(Self::new(code, modifiers))
+    }
+
+    /// Characters carry their own case, so Shift is folded into them
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => Self {
+                code: KeyCode::Char(c.to_ascii_uppercase()),
+                modifiers: modifiers - KeyModifiers::SHIFT,
+            },
+            code => Self { code, modifiers },
+        }
+    }
+}
+
+impl From<&KeyEvent> for KeyBinding {
+    fn from(event: &KeyEvent) -> Self {
+        Self::new(event.code, event.modifiers)
+    }
+}
+
+impl fmt::Display for KeyBinding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (modifier, name) in [
+            (KeyModifiers::CONTROL, "Ctrl+"),
+            (KeyModifiers::ALT, "Alt+"),
+            (KeyModifiers::SHIFT, "Shift+"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                f.write_str(name)?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => f.write_str("Space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::Up => f.write_str("↑"),
+            KeyCode::Down => f.write_str("↓"),
+            KeyCode::Left => f.write_str("←"),
+            KeyCode::Right => f.write_str("→"),
+            KeyCode::PageUp => f.write_str("PageUp"),
+            KeyCode::PageDown => f.write_str("PageDown"),
+            KeyCode::F(n) => write!(f, "F{n}"),
+            code => write!(f, "{code:?}"),
+        }
+    }
+}
+
+/// Key to action lookup built from the defaults and the `[keys]` config section
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    actions: HashMap<KeyBinding, Action>,
+    /// Bindings per action in the order given, for the footer
+    keys: BTreeMap<Action, Vec<KeyBinding>>,
+}
+
+impl Keymap {
+    /// Defaults, with each action named in `overrides` bound to the listed
+    /// keys instead. Fails on unknown actions or keys and on keys bound to
+    /// more than one action.
+    pub fn with_overrides(overrides: &BTreeMap<String, Vec<String>>) -> Result<Self> {
+        let invalid = |message: String| NetworkMonitorError::Config(format!("[keys] {message}"));
+
+        let mut keys = BTreeMap::new();
+        for action in Action::ALL {
+            let defaults = action
+                .default_keys()
+                .iter()
+                .map(|spec| KeyBinding::parse(spec).expect("default key bindings are valid"));
+            keys.insert(action, defaults.collect::<Vec<_>>());
+        }
+        for (name, specs) in overrides {
+            let action = Action::from_name(name).ok_or_else(|| {
+                let names: Vec<_> = Action::ALL.iter().map(|action| action.name()).collect();
+                invalid(format!(
+                    "unknown action '{name}' (expected one of: {})",
+                    names.join(", ")
+                ))
+            })?;
+            let bindings = specs
+                .iter()
+                .map(|spec| KeyBinding::parse(spec).map_err(|e| invalid(format!("{name}: {e}"))))
+                .collect::<Result<Vec<_>>>()?;
+            keys.insert(action, bindings);
+        }
+
+        let mut actions = HashMap::new();
+        for (&action, bindings) in &keys {
+            for &binding in bindings {
+                if let Some(other) = actions.insert(binding, action).filter(|&a| a != action) {
+                    return Err(invalid(format!(
+                        "'{binding}' is bound to both {} and {}; rebind one of them",
+                        other.name(),
+                        action.name()
+                    )));
+                }
+            }
+        }
+        Ok(Self { actions, keys })
+    }
+
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.actions.get(&KeyBinding::from(event)).copied()
+    }
+
+    /// First key bound to `action`, as shown in the footer; empty if unbound
+    pub fn label(&self, action: Action) -> String {
+        self.keys
+            .get(&action)
+            .and_then(|bindings| bindings.first())
+            .map(ToString::to_string)
+            .unwrap_or_default()
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::with_overrides(&BTreeMap::new()).expect("default key bindings don't conflict")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(pairs: &[(&str, &[&str])]) -> BTreeMap<String, Vec<String>> {
+        pairs
+            .iter()
+            .map(|(action, keys)| {
+                (
+                    action.to_string(),
+                    keys.iter().map(|key| key.to_string()).collect(),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_key_specs() {
+        let ctrl_r = KeyBinding::parse("Ctrl+r").unwrap();
+        assert_eq!(ctrl_r.code, KeyCode::Char('r'));
+        assert_eq!(ctrl_r.modifiers, KeyModifiers::CONTROL);
+        assert_eq!(KeyBinding::parse("shift+r"), KeyBinding::parse("R"));
+        assert_eq!(KeyBinding::parse("F5").unwrap().code, KeyCode::F(5));
+        assert_eq!(KeyBinding::parse("+").unwrap().code, KeyCode::Char('+'));
+        assert_eq!(KeyBinding::parse("Alt++").unwrap().code, KeyCode::Char('+'));
+        assert_eq!(
+            KeyBinding::parse("PageDown").unwrap().to_string(),
+            "PageDown"
+        );
+        assert!(KeyBinding::parse("Hyper+x").is_err());
+        assert!(KeyBinding::parse("F13").is_err());
+    }
+
+    #[test]
+    fn test_events_match_bindings() {
+        let keymap = Keymap::default();
+        let shifted = KeyEvent::new(KeyCode::Char('R'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&shifted), Some(Action::Refresh));
+        let jump = KeyEvent::new(KeyCode::Right, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&jump), Some(Action::LastColumn));
+        let unbound = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::NONE);
+        assert_eq!(keymap.action(&unbound), None);
+        assert_eq!(keymap.label(Action::Sort(6)), "7");
+    }
+
+    #[test]
+    fn test_overrides_replace_default_keys() {
+        let keymap = Keymap::with_overrides(&overrides(&[("quit", &["x", "Ctrl+c"])])).unwrap();
+        let q = KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE);
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action(&q), None);
+        assert_eq!(keymap.action(&ctrl_c), Some(Action::Quit));
+        assert_eq!(keymap.label(Action::Quit), "x");
+    }
+
+    #[test]
+    fn test_conflicts_and_unknown_names_are_errors() {
+        let error = Keymap::with_overrides(&overrides(&[("refresh", &["q"])]))
+            .unwrap_err()
+            .to_string();
+        assert!(
+            error.contains("'q' is bound to both quit and refresh"),
+            "{error}"
+        );
+
+        let error = Keymap::with_overrides(&overrides(&[("reload", &["x"])]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("unknown action 'reload'"), "{error}");
+
+        let error = Keymap::with_overrides(&overrides(&[("quit", &["Meta+q"])]))
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("quit: unknown modifier 'Meta'"), "{error}");
+    }
+}
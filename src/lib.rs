@@ -0,0 +1,19 @@
+//! Command line, config file, health checks, logging, panic reports, signal
+//! handling, file locations, scheduled recordings and their comparison shared by the `network-monitor` and `nmt`
+//! binaries. Connection monitoring itself lives in the `network-monitor-core`
+//! crate. With the default `gui` feature, [`gui`] has the GTK connection table and detail
+//! pane for embedding.
+
+pub mod cli;
+pub mod comparison;
+pub mod config;
+pub mod crash;
+pub mod doctor;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod logging;
+pub mod paths;
+pub mod recording;
+pub mod signals;
+
+pub use network_monitor_core::{error, models, services, utils, widgets};
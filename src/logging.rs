@@ -0,0 +1,60 @@
+use std::fs::{self, OpenOptions};
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::sync::Mutex;
+use tracing::level_filters::LevelFilter;
+use tracing_subscriber::fmt;
+use tracing_subscriber::prelude::*;
+
+/// Default level; each -v raises it by one step
+pub const DEFAULT_LEVEL: LevelFilter = LevelFilter::WARN;
+
+/// Level for `verbose` repetitions of -v on top of the default
+pub fn verbosity_level(verbose: u8) -> LevelFilter {
+    match verbose {
+        0 => DEFAULT_LEVEL,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// Install the global subscriber. Events at `level` or above go to stderr
+/// when `stderr` is set, which the TUI turns off while it owns the terminal,
+/// and are appended to `file` if given.
+pub fn init(level: LevelFilter, file: Option<&Path>, stderr: bool) -> io::Result<()> {
+    let file_layer = match file {
+        Some(path) => {
+            if let Some(dir) = path.parent() {
+                fs::create_dir_all(dir)?;
+            }
+            let file = OpenOptions::new().create(true).append(true).open(path)?;
+            Some(fmt::layer().with_ansi(false).with_writer(Mutex::new(file)))
+        }
+        None => None,
+    };
+    let stderr_layer = stderr.then(|| {
+        fmt::layer()
+            .with_ansi(io::stderr().is_terminal())
+            .with_writer(io::stderr)
+    });
+
+    tracing_subscriber::registry()
+        .with(level)
+        .with(file_layer)
+        .with(stderr_layer)
+        .try_init()
+        .map_err(io::Error::other)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verbosity_level() {
+        assert_eq!(verbosity_level(0), LevelFilter::WARN);
+        assert_eq!(verbosity_level(2), LevelFilter::DEBUG);
+        assert_eq!(verbosity_level(9), LevelFilter::TRACE);
+    }
+}
@@ -1,30 +1,55 @@
 use adw::{prelude::*, Application};
+use clap::Parser;
 use gio::ActionEntry;
 use gtk4 as gtk;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
+use std::time::Duration;
 
-// Import modules
-mod error;
-mod error_tests;
-mod models;
-mod services;
 mod ui;
-mod utils;
 
-use ui::NetworkMonitorWindow;
+use network_monitor::{
+    cli, config, crash, doctor, error, gui, logging, models, paths, recording, services, signals,
+    utils,
+};
+
+use cli::{CommonArgs, Settings};
+use ui::{NetworkMonitorWindow, REFRESH_INTERVAL};
+
+/// GTK network connection monitor
+#[derive(Parser)]
+#[command(name = "network-monitor", version)]
+struct Cli {
+    #[command(flatten)]
+    options: CommonArgs,
+
+    /// Start monitoring without a window; launching the app again shows it
+    #[arg(long, conflicts_with = "report")]
+    background: bool,
+
+    /// Start in the background at login by adding an autostart entry, then exit
+    #[arg(long, conflicts_with_all = ["remove_autostart", "background", "report"])]
+    install_autostart: bool,
+
+    /// Remove the autostart entry added by --install-autostart, then exit
+    #[arg(long, conflicts_with_all = ["background", "report"])]
+    remove_autostart: bool,
+}
 
 /// Main application structure
 struct NetworkMonitorApp {
     app: Application,
     window: Rc<RefCell<Option<Rc<NetworkMonitorWindow>>>>,
+    settings: Rc<Settings>,
+    /// Started with --background: the first activation shows no window
+    background: bool,
 }
 
 impl NetworkMonitorApp {
-    fn new() -> Self {
+    fn new(settings: Settings, background: bool) -> Self {
         let app = Application::builder()
             .application_id("org.grigio.NetworkMonitor")
-            .flags(gio::ApplicationFlags::HANDLES_OPEN)
+            .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE)
             .build();
 
         // Set up style manager at application level
@@ -34,6 +59,8 @@ impl NetworkMonitorApp {
         let monitor = NetworkMonitorApp {
             app,
             window: Rc::new(RefCell::new(None)),
+            settings: Rc::new(settings),
+            background,
         };
 
         monitor.setup_actions();
@@ -81,6 +108,10 @@ impl NetworkMonitorApp {
     fn run(&self) {
         let window = self.window.clone();
         let window_for_shutdown = window.clone();
+        let window_for_command_line = window.clone();
+        let settings = self.settings.clone();
+        let background = self.background;
+        let start_hidden = Cell::new(background);
 
         // Set keyboard accelerators
         self.app.set_accels_for_action("app.about", &["F1"]);
@@ -96,9 +127,13 @@ impl NetworkMonitorApp {
             let mut window_guard = window.borrow_mut();
 
             if window_guard.is_none() {
-                // First activation - create window
-                let monitor_window = NetworkMonitorWindow::new(app);
-                monitor_window.window.present();
+                // First activation - create window, hidden when started in the background
+                let monitor_window = NetworkMonitorWindow::new(app, &settings, background);
+                if !start_hidden.replace(false) {
+                    monitor_window.window.present();
+                    monitor_window.show_first_run_setup();
+                    monitor_window.show_crash_report(&settings.paths.crash_report());
+                }
                 *window_guard = Some(monitor_window);
             } else {
                 // Already running - bring existing window to front
@@ -108,17 +143,147 @@ impl NetworkMonitorApp {
             }
         });
 
+        // Every launch ends up here in the primary instance: its own command
+        // line, already applied through `settings`, and those of later
+        // launches, which GApplication forwards instead of starting a new process
+        self.app.connect_command_line(move |app, command_line| {
+            if !command_line.is_remote() {
+                app.activate();
+                return glib::ExitCode::SUCCESS;
+            }
+            // The launching process has already validated these with clap
+            let cli = match Cli::try_parse_from(command_line.arguments()) {
+                Ok(cli) => cli,
+                Err(e) => {
+                    tracing::warn!("Ignoring forwarded command line: {}", e);
+                    return glib::ExitCode::from(2);
+                }
+            };
+            app.activate();
+            if let Some(monitor) = window_for_command_line.borrow().as_ref() {
+                // A forwarded profile goes first so the other options refine it
+                if let Some(profile) = &cli.options.profile {
+                    monitor.select_profile(profile);
+                }
+                monitor.apply_args(&cli.options);
+            }
+            glib::ExitCode::SUCCESS
+        });
+
+        // Answer GNOME Shell overview searches once registered on the bus
+        let window_for_search = self.window.clone();
+        self.app.connect_startup(move |app| {
+            if let Err(e) = ui::search_provider::register(app, window_for_search.clone()) {
+                tracing::warn!("Could not export the search provider: {}", e);
+            }
+        });
+
         // Handle shutdown to properly clean up resources
         self.app.connect_shutdown(move |_| {
             // Clean up window reference
-            *window_for_shutdown.borrow_mut() = None;
+            let monitor = window_for_shutdown.borrow_mut().take();
+            if let Some(monitor) = monitor {
+                monitor.stop_collecting();
+            }
         });
 
-        self.app.run();
+        // Killed from outside, e.g. at logout, quit the way Ctrl+Q does so
+        // the window state, caches and warm-start state are saved
+        match signals::stop_signals() {
+            Ok(stop_signals) => {
+                let app = self.app.clone();
+                glib::spawn_future_local(async move {
+                    if stop_signals.recv().await.is_ok() {
+                        if app.lookup_action("quit").is_some() {
+                            app.activate_action("quit", None);
+                        } else {
+                            app.quit();
+                        }
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("Could not handle signals: {}", e),
+        }
+
+        // A second --background start has nothing to do; any other launch is
+        // forwarded to the running instance, which shows its window
+        if self.background {
+            if let Err(e) = self.app.register(gio::Cancellable::NONE) {
+                eprintln!("Failed to register application: {}", e);
+                std::process::exit(1);
+            }
+            if self.app.is_remote() {
+                println!("Network Monitor is already running");
+                return;
+            }
+        }
+
+        // Without registered option entries GApplication passes the arguments
+        // through untouched, so clap parses them on both ends
+        let args: Vec<String> = std::env::args().collect();
+        self.app.run_with_args(&args);
     }
 }
 
 fn main() {
+    let cli = Cli::parse();
+    // Before loading the settings, so a broken config is one of the findings
+    if cli.options.doctor {
+        std::process::exit(doctor::run(&cli.options));
+    }
+    let settings = match cli.options.settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if let Err(e) = logging::init(
+        settings.log_level.unwrap_or(logging::DEFAULT_LEVEL),
+        settings.log_file.as_deref(),
+        true,
+    ) {
+        eprintln!("Warning: Could not set up logging: {}", e);
+    }
+    if cli.install_autostart {
+        match ui::autostart::install() {
+            Ok(path) => println!("Installed {}", path.display()),
+            Err(e) => {
+                eprintln!("Error: Could not install the autostart entry: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if cli.remove_autostart {
+        match ui::autostart::remove() {
+            Ok(true) => println!("Removed the autostart entry"),
+            Ok(false) => println!("No autostart entry was installed"),
+            Err(e) => {
+                eprintln!("Error: Could not remove the autostart entry: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+    if cli.options.once {
+        let format = cli.options.output.unwrap_or_default();
+        if let Err(e) = cli::run_once(&settings, format, REFRESH_INTERVAL, true) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+    if let Some(minutes) = cli.options.audit {
+        let format = cli.options.output.unwrap_or_default();
+        let period = Duration::from_secs(minutes * 60);
+        if let Err(e) = cli::run_audit(&settings, period, format, REFRESH_INTERVAL, true) {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+
     // Initialize GTK with proper error handling
     if let Err(e) = gtk::init() {
         eprintln!("Failed to initialize GTK: {}", e);
@@ -127,6 +292,16 @@ fn main() {
         std::process::exit(1);
     }
 
-    let app = NetworkMonitorApp::new();
+    // Panics in GTK callbacks abort, so the report is shown on the next start
+    let crash_report = settings.paths.crash_report();
+    crash::set_hook(move |report| {
+        eprintln!("{report}");
+        if let Some(dir) = crash_report.parent() {
+            let _ = std::fs::create_dir_all(dir);
+        }
+        let _ = std::fs::write(&crash_report, report);
+    });
+
+    let app = NetworkMonitorApp::new(settings, cli.background);
     app.run();
 }
@@ -1,3 +0,0 @@
-pub mod connection;
-
-pub use connection::{Connection, ProcessIO};
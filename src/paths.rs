@@ -0,0 +1,150 @@
+use crate::config::PathsConfig;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// Subdirectory of each XDG base directory used by both binaries
+const APP_DIR: &str = "network-monitor";
+
+/// Where files are kept, following the XDG base directory spec unless the
+/// `[paths]` config section says otherwise:
+/// - cache: data that can be rebuilt, like resolved hostnames
+/// - data: recorded history and exported snapshots
+/// - state: logs
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paths {
+    pub cache_dir: PathBuf,
+    pub data_dir: PathBuf,
+    pub state_dir: PathBuf,
+    pub export_dir: PathBuf,
+}
+
+impl Default for Paths {
+    fn default() -> Self {
+        Self::new(&PathsConfig::default())
+    }
+}
+
+impl Paths {
+    pub fn new(config: &PathsConfig) -> Self {
+        let configured = |dir: &Option<PathBuf>| dir.as_deref().map(expand_home);
+        let data_dir = configured(&config.data_dir)
+            .unwrap_or_else(|| xdg_dir("XDG_DATA_HOME", ".local/share"));
+        Self {
+            cache_dir: configured(&config.cache_dir)
+                .unwrap_or_else(|| xdg_dir("XDG_CACHE_HOME", ".cache")),
+            state_dir: configured(&config.state_dir)
+                .unwrap_or_else(|| xdg_dir("XDG_STATE_HOME", ".local/state")),
+            export_dir: configured(&config.export_dir).unwrap_or_else(|| data_dir.join("exports")),
+            data_dir,
+        }
+    }
+
+    /// $XDG_CONFIG_HOME/network-monitor/config.toml, falling back to ~/.config
+    pub fn config_file() -> Option<PathBuf> {
+        xdg_base("XDG_CONFIG_HOME", ".config").map(|base| base.join(APP_DIR).join("config.toml"))
+    }
+
+    /// Desktop entry that starts the GTK app in the background at login
+    pub fn autostart_file() -> Option<PathBuf> {
+        xdg_base("XDG_CONFIG_HOME", ".config").map(|base| {
+            base.join("autostart")
+                .join("org.grigio.NetworkMonitor.desktop")
+        })
+    }
+
+    /// Hostnames from previous sessions
+    pub fn resolver_cache(&self) -> PathBuf {
+        self.cache_dir.join("resolver.json")
+    }
+
+    /// Socket owners, I/O counters and connections at the last exit, for a
+    /// quick restart
+    pub fn warm_start(&self) -> PathBuf {
+        self.cache_dir.join("warm-start.json")
+    }
+
+    /// What made the GTK4 app crash, shown on its next start
+    pub fn crash_report(&self) -> PathBuf {
+        self.state_dir.join("crash-report.txt")
+    }
+
+    /// Recorded connection history
+    pub fn history_db(&self) -> PathBuf {
+        self.data_dir.join("history.db")
+    }
+
+    /// Default log of the TUI, which can't log to the terminal it draws on
+    pub fn log_file(&self) -> PathBuf {
+        self.state_dir.join("network-monitor.log")
+    }
+
+    /// Destination for an exported snapshot called `name`
+    pub fn export_file(&self, name: &str) -> PathBuf {
+        self.export_dir.join(name)
+    }
+}
+
+/// Base directory from `var` when it is set to an absolute path, as the spec
+/// requires, otherwise `fallback` under $HOME
+fn xdg_base(var: &str, fallback: &str) -> Option<PathBuf> {
+    env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|dir| dir.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(fallback)))
+}
+
+/// Our directory under an XDG base, or under the temp dir without a $HOME
+fn xdg_dir(var: &str, fallback: &str) -> PathBuf {
+    xdg_base(var, fallback)
+        .unwrap_or_else(env::temp_dir)
+        .join(APP_DIR)
+}
+
+/// Replace a leading "~" with $HOME
+pub(crate) fn expand_home(path: &Path) -> PathBuf {
+    match (path.strip_prefix("~"), env::var_os("HOME")) {
+        (Ok(rest), Some(home)) => Path::new(&home).join(rest),
+        _ => path.to_path_buf(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_configured_dirs_override_xdg() {
+        let config = PathsConfig {
+            cache_dir: Some(PathBuf::from("/var/cache/nm")),
+            data_dir: Some(PathBuf::from("/srv/nm")),
+            ..PathsConfig::default()
+        };
+        let paths = Paths::new(&config);
+        assert_eq!(
+            paths.resolver_cache(),
+            Path::new("/var/cache/nm/resolver.json")
+        );
+        assert_eq!(
+            paths.warm_start(),
+            Path::new("/var/cache/nm/warm-start.json")
+        );
+        assert_eq!(paths.history_db(), Path::new("/srv/nm/history.db"));
+        // Exports follow the data directory unless set themselves
+        assert_eq!(
+            paths.export_file("a.csv"),
+            Path::new("/srv/nm/exports/a.csv")
+        );
+        assert!(paths
+            .log_file()
+            .ends_with("network-monitor/network-monitor.log"));
+    }
+
+    #[test]
+    fn test_expand_home() {
+        let Some(home) = env::var_os("HOME") else {
+            return;
+        };
+        assert_eq!(expand_home(Path::new("~/nm")), Path::new(&home).join("nm"));
+        assert_eq!(expand_home(Path::new("/tmp/~")), Path::new("/tmp/~"));
+    }
+}
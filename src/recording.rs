@@ -0,0 +1,262 @@
+//! Snapshots or summaries written on a schedule from the `[recording]` config
+//! section, by whichever interface is running, the window started with
+//! `--background` included
+
+use crate::cli::{write_connections, OutputFormat, COLUMN_NAMES};
+use crate::models::{Connection, ConnectionSnapshot, QuickStats, Scope};
+use crate::utils::formatter::Units;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// File summaries are appended to, in the recording directory
+const SUMMARY_FILE: &str = "summary.csv";
+
+const SUMMARY_HEADER: &str = "time,connections,active,tx,rx,sent,received,lan,wan,vpn";
+
+/// What each recording writes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RecordKind {
+    /// Every connection with every column, one file per recording
+    #[default]
+    Snapshot,
+    /// A line of totals appended to summary.csv
+    Summary,
+}
+
+impl FromStr for RecordKind {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        match text.to_ascii_lowercase().as_str() {
+            "snapshot" => Ok(Self::Snapshot),
+            "summary" => Ok(Self::Summary),
+            _ => Err(format!(
+                "unknown recording '{text}' (use snapshot or summary)"
+            )),
+        }
+    }
+}
+
+/// When and what to record, from the `[recording]` config section
+#[derive(Debug, Clone, PartialEq)]
+pub struct Schedule {
+    /// Time between recordings, aligned to the clock: hourly ones happen on
+    /// the hour
+    pub every: Duration,
+    pub kind: RecordKind,
+    /// Format of snapshot files
+    pub format: OutputFormat,
+    pub dir: PathBuf,
+}
+
+/// Writes a recording with the first snapshot taken once each one is due
+#[derive(Debug)]
+pub struct Recorder {
+    schedule: Schedule,
+    next: SystemTime,
+}
+
+impl Recorder {
+    pub fn new(schedule: Schedule, now: SystemTime) -> Self {
+        let next = next_slot(now, schedule.every);
+        Self { schedule, next }
+    }
+
+    /// Record `snapshot` when a recording is due, returning the file written
+    /// to. Rates and totals are plain bytes or bits, as in exports.
+    pub fn record_if_due(
+        &mut self,
+        snapshot: &ConnectionSnapshot,
+        units: Units,
+        remote: impl Fn(&Connection) -> String,
+    ) -> io::Result<Option<PathBuf>> {
+        if snapshot.taken_at < self.next {
+            return Ok(None);
+        }
+        // Slots missed while suspended are skipped rather than caught up on
+        self.next = next_slot(snapshot.taken_at, self.schedule.every);
+
+        fs::create_dir_all(&self.schedule.dir)?;
+        let path = match self.schedule.kind {
+            RecordKind::Snapshot => {
+                let extension = match self.schedule.format {
+                    OutputFormat::Text => "txt",
+                    OutputFormat::Json => "json",
+                    OutputFormat::Csv => "csv",
+                };
+                let name = format!("snapshot-{}.{extension}", file_stamp(snapshot.taken_at));
+                let path = self.schedule.dir.join(name);
+                let mut out = BufWriter::new(File::create(&path)?);
+                let columns: Vec<usize> = (0..COLUMN_NAMES.len()).collect();
+                write_connections(
+                    &mut out,
+                    &snapshot.connections,
+                    &columns,
+                    self.schedule.format,
+                    units,
+                    remote,
+                )?;
+                out.flush()?;
+                path
+            }
+            RecordKind::Summary => {
+                let path = self.schedule.dir.join(SUMMARY_FILE);
+                let mut out = OpenOptions::new().create(true).append(true).open(&path)?;
+                if out.metadata()?.len() == 0 {
+                    writeln!(out, "{SUMMARY_HEADER}")?;
+                }
+                writeln!(out, "{}", summary_row(snapshot, units))?;
+                path
+            }
+        };
+        Ok(Some(path))
+    }
+}
+
+/// First multiple of `every` since the epoch after `now`
+fn next_slot(now: SystemTime, every: Duration) -> SystemTime {
+    let every = every.as_secs().max(1);
+    let secs = now.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+    UNIX_EPOCH + Duration::from_secs((secs / every + 1) * every)
+}
+
+/// One line of summary.csv: connection counts, current rates, session totals
+/// and their split by scope
+fn summary_row(snapshot: &ConnectionSnapshot, units: Units) -> String {
+    let stats = QuickStats::of(snapshot);
+    let scope_total = |scope: Scope| {
+        snapshot
+            .scopes
+            .iter()
+            .find(|traffic| traffic.scope == scope)
+            .map_or(0, |traffic| {
+                units.count(traffic.totals.sent + traffic.totals.received)
+            })
+    };
+    format!(
+        "{},{},{},{},{},{},{},{},{},{}",
+        utc_stamp(snapshot.taken_at),
+        stats.connections,
+        stats.active,
+        units.count(stats.tx_rate),
+        units.count(stats.rx_rate),
+        units.count(stats.sent),
+        units.count(stats.received),
+        scope_total(Scope::Lan),
+        scope_total(Scope::Wan),
+        scope_total(Scope::Vpn),
+    )
+}
+
+/// ISO 8601 UTC time to the second, e.g. "2026-10-15T13:00:00Z"
+fn utc_stamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (year, month, day) = civil_date(secs / 86400);
+    let secs = secs % 86400;
+    format!(
+        "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60
+    )
+}
+
+/// [`utc_stamp`] without the separators that file systems dislike, e.g.
+/// "20261015T130000Z"
+fn file_stamp(time: SystemTime) -> String {
+    utc_stamp(time).replace(['-', ':'], "")
+}
+
+/// Year, month and day of the Gregorian calendar `days` after 1970-01-01
+fn civil_date(days: u64) -> (u64, u64, u64) {
+    // Howard Hinnant's days_from_civil inverted, with eras of 400 years
+    // starting on March 1st, 0000
+    let days = days + 719_468;
+    let era = days / 146_097;
+    let day_of_era = days % 146_097;
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    };
+    let year = year_of_era + era * 400 + u64::from(month <= 2);
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{ConnectionState, Protocol, ScopeTraffic, TrafficTotals};
+
+    fn at(secs: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(secs)
+    }
+
+    #[test]
+    fn test_times_are_utc() {
+        assert_eq!(utc_stamp(at(0)), "1970-01-01T00:00:00Z");
+        assert_eq!(utc_stamp(at(951_782_400)), "2000-02-29T00:00:00Z");
+        assert_eq!(utc_stamp(at(1_791_990_245)), "2026-10-14T15:04:05Z");
+        assert_eq!(file_stamp(at(1_791_990_245)), "20261014T150405Z");
+    }
+
+    #[test]
+    fn test_recordings_follow_the_schedule() {
+        let dir = std::env::temp_dir().join(format!("nm-recording-{}", std::process::id()));
+        let schedule = Schedule {
+            every: Duration::from_secs(3600),
+            kind: RecordKind::Summary,
+            format: OutputFormat::Csv,
+            dir: dir.clone(),
+        };
+        let mut recorder = Recorder::new(schedule, at(7_000));
+        let mut conn = Connection::new(
+            Protocol::Tcp,
+            ConnectionState::Established,
+            "192.168.1.2:50000",
+            "93.184.216.34:443",
+            "curl",
+            "100",
+            "curl",
+        );
+        conn.rx_rate = 2048;
+        let mut snapshot = ConnectionSnapshot::new(vec![conn], TrafficTotals::default());
+        snapshot.scopes = vec![ScopeTraffic::new(Scope::Lan), ScopeTraffic::new(Scope::Wan)];
+        snapshot.scopes[1].totals.received = 4096;
+
+        let mut record = |secs| {
+            snapshot.taken_at = at(secs);
+            recorder
+                .record_if_due(&snapshot, Units::default(), |conn| conn.remote.to_string())
+                .unwrap()
+        };
+        // Due on the hour, then not again until the next one
+        assert_eq!(record(7_100), None);
+        assert_eq!(record(7_205), Some(dir.join(SUMMARY_FILE)));
+        assert_eq!(record(9_000), None);
+        assert!(record(10_800).is_some());
+
+        let summary = fs::read_to_string(dir.join(SUMMARY_FILE)).unwrap();
+        let lines: Vec<&str> = summary.lines().collect();
+        assert_eq!(
+            lines,
+            [
+                SUMMARY_HEADER,
+                "1970-01-01T02:00:05Z,1,1,0,2048,0,0,0,4096,0",
+                "1970-01-01T03:00:00Z,1,1,0,2048,0,0,0,4096,0",
+            ]
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
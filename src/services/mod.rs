@@ -1,9 +0,0 @@
-pub mod network;
-pub mod process_cache;
-pub mod resolver;
-#[cfg(test)]
-mod tests;
-
-pub use network::NetworkService;
-pub use process_cache::ProcessCache;
-pub use resolver::AddressResolver;
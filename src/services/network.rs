@@ -1,325 +0,0 @@
-use crate::error::Result;
-use crate::models::{Connection, ProcessIO};
-use crate::utils::{
-    parse_decimal, parse_ipv4_hex, parse_ipv6_hex, parse_port, parse_tcp_state, split_socket_addr,
-    ErrorRecovery,
-};
-use std::collections::HashMap;
-use std::fs;
-use std::net::IpAddr;
-use std::path::Path;
-use std::time::Instant;
-
-/// Service for monitoring network connections
-pub struct NetworkService {
-    last_update_time: std::cell::RefCell<Instant>,
-    process_cache: std::cell::RefCell<crate::services::ProcessCache>,
-}
-
-impl NetworkService {
-    pub fn new() -> Self {
-        Self {
-            last_update_time: std::cell::RefCell::new(Instant::now()),
-            process_cache: std::cell::RefCell::new(crate::services::ProcessCache::new()),
-        }
-    }
-
-    /// Get all network connections using native Rust socket APIs
-    pub fn get_connections(&self) -> Result<Vec<Connection>> {
-        let connections = ErrorRecovery::get_connections_with_fallback(
-            || self.get_tcp_connections(),
-            || self.get_udp_connections(),
-        );
-
-        Ok(connections)
-    }
-
-    /// Get TCP connections from /proc/net/tcp
-    fn get_tcp_connections(&self) -> Result<Vec<Connection>> {
-        let mut connections = Vec::new();
-
-        // Try IPv4 TCP connections
-        if let Ok(tcp_data) = fs::read_to_string("/proc/net/tcp") {
-            for line in tcp_data.lines().skip(1) {
-                if let Some(conn) = self.parse_proc_net_line(line, "tcp", "LISTEN")? {
-                    connections.push(conn);
-                }
-            }
-        } else {
-            eprintln!("Warning: Could not read /proc/net/tcp");
-        }
-
-        // Try IPv6 TCP connections
-        if let Ok(tcp6_data) = fs::read_to_string("/proc/net/tcp6") {
-            for line in tcp6_data.lines().skip(1) {
-                if let Some(conn) = self.parse_proc_net_line(line, "tcp6", "LISTEN")? {
-                    connections.push(conn);
-                }
-            }
-        } else {
-            eprintln!("Warning: Could not read /proc/net/tcp6");
-        }
-
-        Ok(connections)
-    }
-
-    /// Get UDP connections from /proc/net/udp
-    fn get_udp_connections(&self) -> Result<Vec<Connection>> {
-        let mut connections = Vec::new();
-
-        // Try IPv4 UDP connections
-        if let Ok(udp_data) = fs::read_to_string("/proc/net/udp") {
-            for line in udp_data.lines().skip(1) {
-                if let Some(conn) = self.parse_proc_net_line(line, "udp", "")? {
-                    connections.push(conn);
-                }
-            }
-        } else {
-            eprintln!("Warning: Could not read /proc/net/udp");
-        }
-
-        // Try IPv6 UDP connections
-        if let Ok(udp6_data) = fs::read_to_string("/proc/net/udp6") {
-            for line in udp6_data.lines().skip(1) {
-                if let Some(conn) = self.parse_proc_net_line(line, "udp6", "")? {
-                    connections.push(conn);
-                }
-            }
-        } else {
-            eprintln!("Warning: Could not read /proc/net/udp6");
-        }
-
-        Ok(connections)
-    }
-
-    /// Parse a line from /proc/net/tcp|udp
-    fn parse_proc_net_line(
-        &self,
-        line: &str,
-        protocol: &str,
-        default_state: &str,
-    ) -> Result<Option<Connection>> {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() < 10 {
-            return Ok(None);
-        }
-
-        let local_addr = self.parse_socket_addr(parts[1])?;
-        let remote_addr = self.parse_socket_addr(parts[2])?;
-
-        let state = if parts.len() > 3 {
-            self.parse_tcp_state(parts[3])
-        } else {
-            default_state.to_string()
-        };
-
-        // Get the inode from the connection
-        let inode = if parts.len() > 9 {
-            parse_decimal(parts[9], "inode").unwrap_or(0)
-        } else {
-            0
-        };
-
-        let (program, pid, command) = self.process_cache.borrow_mut().get_process_info(inode);
-
-        Ok(Some(Connection::new(
-            protocol.to_string(),
-            state,
-            local_addr,
-            remote_addr,
-            program,
-            pid,
-            command,
-        )))
-    }
-
-    /// Parse socket address from /proc/net format
-    fn parse_socket_addr(&self, addr_str: &str) -> Result<String> {
-        let (ip_hex, port_hex) = split_socket_addr(addr_str)?;
-        let port = parse_port(port_hex)?;
-
-        let ip = if ip_hex.len() == 8 {
-            // IPv4 (hex is in little-endian format)
-            IpAddr::V4(parse_ipv4_hex(ip_hex)?)
-        } else if ip_hex.len() == 32 {
-            // IPv6
-            IpAddr::V6(parse_ipv6_hex(ip_hex)?)
-        } else {
-            return Err(crate::error::NetworkMonitorError::InvalidAddress(format!(
-                "Invalid IP hex length: {} (expected 8 or 32)",
-                ip_hex.len()
-            )));
-        };
-
-        Ok(format!("{ip}:{port}"))
-    }
-
-    /// Parse TCP state from hex value
-    fn parse_tcp_state(&self, state_hex: &str) -> String {
-        parse_tcp_state(state_hex)
-    }
-
-    /// Get process info for a given socket inode
-    #[allow(dead_code)]
-    fn get_process_info_for_inode(&self, inode: u64) -> (String, String, String) {
-        if inode == 0 {
-            return ("N/A".to_string(), "N/A".to_string(), "N/A".to_string());
-        }
-
-        // Scan /proc/*/fd for socket inodes
-        if let Ok(proc_dir) = fs::read_dir("/proc") {
-            for entry in proc_dir.flatten() {
-                let path = entry.path();
-                if let Some(pid_str) = path.file_name().and_then(|n| n.to_str()) {
-                    if pid_str.chars().all(|c| c.is_ascii_digit()) {
-                        if let Some((program, command)) = self.check_process_fd(&path, inode) {
-                            return (program, pid_str.to_string(), command);
-                        }
-                    }
-                }
-            }
-        }
-
-        ("N/A".to_string(), "N/A".to_string(), "N/A".to_string())
-    }
-
-    /// Check process file descriptors for matching socket inode
-    #[allow(dead_code)]
-    fn check_process_fd(&self, proc_path: &Path, target_inode: u64) -> Option<(String, String)> {
-        let fd_path = proc_path.join("fd");
-        if let Ok(fd_dir) = fs::read_dir(&fd_path) {
-            for fd_entry in fd_dir.flatten() {
-                let fd_link_path = fd_entry.path();
-                if let Ok(link_target) = fs::read_link(&fd_link_path) {
-                    if let Some(link_str) = link_target.to_str() {
-                        if link_str.starts_with("socket:[") && link_str.ends_with(']') {
-                            let inode_str = &link_str[8..link_str.len() - 1];
-                            if let Ok(inode) = inode_str.parse::<u64>() {
-                                if inode == target_inode {
-                                    let pid_str = proc_path
-                                        .file_name()
-                                        .and_then(|n| n.to_str())
-                                        .unwrap_or("unknown")
-                                        .to_string();
-                                    let program = self.get_process_name(&pid_str);
-                                    let command = self.get_process_path(&pid_str);
-                                    return Some((program, command));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        None
-    }
-
-    /// Get process name from /proc/[pid]/status
-    #[allow(dead_code)]
-    fn get_process_name(&self, pid: &str) -> String {
-        let status_path = format!("/proc/{pid}/status");
-        if let Ok(status_data) = fs::read_to_string(&status_path) {
-            for line in status_data.lines() {
-                if let Some(name) = line.strip_prefix("Name:\t") {
-                    return name.to_string();
-                }
-            }
-        }
-        "N/A".to_string()
-    }
-
-    /// Get I/O statistics for a process
-    pub fn get_process_io(&self, pid: &str) -> ProcessIO {
-        let io_path = format!("/proc/{pid}/io");
-        // Skip if we can't access the process io file (permission denied for other users' processes)
-        if let Ok(io_data) = fs::read_to_string(&io_path) {
-            let mut rx_bytes = 0u64;
-            let mut tx_bytes = 0u64;
-
-            for line in io_data.lines() {
-                if line.starts_with("rchar:") {
-                    if let Some(value) = line.split_whitespace().nth(1) {
-                        rx_bytes = value.parse().unwrap_or(0);
-                    }
-                } else if line.starts_with("wchar:") {
-                    if let Some(value) = line.split_whitespace().nth(1) {
-                        tx_bytes = value.parse().unwrap_or(0);
-                    }
-                }
-            }
-
-            ProcessIO::new(rx_bytes, tx_bytes)
-        } else {
-            ProcessIO::zero()
-        }
-    }
-
-    /// Get process command line
-    #[allow(dead_code)]
-    pub fn get_process_path(&self, pid: &str) -> String {
-        let cmdline_path = format!("/proc/{pid}/cmdline");
-        if let Ok(cmdline) = fs::read_to_string(&cmdline_path) {
-            if !cmdline.is_empty() {
-                cmdline.replace('\0', " ")
-            } else {
-                format!("[{pid}]")
-            }
-        } else {
-            "N/A".to_string()
-        }
-    }
-
-    /// Update connection rates based on previous I/O data
-    pub fn update_connection_rates(
-        &self,
-        connections: Vec<Connection>,
-        prev_io: &HashMap<String, ProcessIO>,
-    ) -> Result<(Vec<Connection>, HashMap<String, ProcessIO>)> {
-        let mut current_io = HashMap::new();
-        let mut updated_connections = Vec::new();
-
-        // Calculate time elapsed since last update
-        let now = Instant::now();
-        let time_elapsed = {
-            let last_time = *self.last_update_time.borrow();
-            let elapsed = now.duration_since(last_time);
-            // Update the last update time
-            *self.last_update_time.borrow_mut() = now;
-            elapsed
-        };
-
-        // Convert elapsed time to seconds as f64 for rate calculation
-        let elapsed_seconds = time_elapsed.as_secs_f64();
-        // Avoid division by zero
-        let elapsed_seconds = elapsed_seconds.max(0.001);
-
-        for mut conn in connections {
-            if conn.pid != "N/A" {
-                let io = self.get_process_io(&conn.pid);
-                let pid_key = conn.pid.clone();
-
-                // Calculate rates based on previous I/O data and time elapsed
-                if let Some(prev) = prev_io.get(&pid_key) {
-                    let rx_diff = io.rx.saturating_sub(prev.rx) as f64;
-                    let tx_diff = io.tx.saturating_sub(prev.tx) as f64;
-
-                    // Calculate per-second rates
-                    conn.rx_rate = (rx_diff / elapsed_seconds) as u64;
-                    conn.tx_rate = (tx_diff / elapsed_seconds) as u64;
-                }
-
-                current_io.insert(pid_key, io);
-            }
-
-            updated_connections.push(conn);
-        }
-
-        Ok((updated_connections, current_io))
-    }
-}
-
-impl Default for NetworkService {
-    fn default() -> Self {
-        Self::new()
-    }
-}
@@ -0,0 +1,80 @@
+//! SIGTERM, SIGINT and SIGHUP turned into a flag or a message the main loops
+//! of both binaries check, so a killed session ends like a quit: the
+//! terminal is restored and window state, caches, warm-start state and
+//! reports are written. A second signal exits at once.
+
+use signal_hook::consts::{SIGHUP, SIGINT, SIGTERM};
+use signal_hook::iterator::Signals;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Signals that end a session
+pub const STOP_SIGNALS: [i32; 3] = [SIGTERM, SIGINT, SIGHUP];
+
+/// How often [`sleep_unless_stopped`] looks at the flag
+const STOP_POLL: Duration = Duration::from_millis(100);
+
+/// Set the returned flag on the first [`STOP_SIGNALS`], and exit on the next
+pub fn stop_on_signals() -> io::Result<Arc<AtomicBool>> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in STOP_SIGNALS {
+        // Registered first, so it only sees the flag set by an earlier signal
+        signal_hook::flag::register_conditional_shutdown(signal, 1, stop.clone())?;
+        signal_hook::flag::register(signal, stop.clone())?;
+    }
+    Ok(stop)
+}
+
+/// Receive a message on the first [`STOP_SIGNALS`], for event loops to quit
+/// on; the process exits on the next
+pub fn stop_signals() -> io::Result<async_channel::Receiver<()>> {
+    let stop = Arc::new(AtomicBool::new(false));
+    for signal in STOP_SIGNALS {
+        signal_hook::flag::register_conditional_shutdown(signal, 1, stop.clone())?;
+    }
+    let mut signals = Signals::new(STOP_SIGNALS)?;
+    let (sender, receiver) = async_channel::bounded(1);
+    thread::Builder::new()
+        .name("signals".to_string())
+        .spawn(move || {
+            if signals.forever().next().is_some() {
+                stop.store(true, Ordering::Relaxed);
+                let _ = sender.send_blocking(());
+            }
+        })?;
+    Ok(receiver)
+}
+
+/// Sleep for `duration`, waking early once `stop` is set. Returns whether it is.
+pub fn sleep_unless_stopped(stop: &AtomicBool, duration: Duration) -> bool {
+    let deadline = Instant::now() + duration;
+    loop {
+        if stop.load(Ordering::Relaxed) {
+            return true;
+        }
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return false;
+        }
+        thread::sleep(remaining.min(STOP_POLL));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sleep_ends_early_once_stopped() {
+        let stop = AtomicBool::new(false);
+        assert!(!sleep_unless_stopped(&stop, Duration::from_millis(10)));
+
+        stop.store(true, Ordering::Relaxed);
+        let started = Instant::now();
+        assert!(sleep_unless_stopped(&stop, Duration::from_secs(60)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+}
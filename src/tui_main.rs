@@ -1,166 +1,527 @@
+use clap::Parser;
+use cli::{CommonArgs, OutputFormat, Settings, SortSpec, View};
+use comparison::{Comparison, Session};
 use crossterm::{
     event::{
-        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
     },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
 use error::Result;
-use models::Connection;
-use services::{AddressResolver, NetworkService};
-use std::collections::HashMap;
-use std::env;
-use std::io;
-use std::time::{Duration, Instant};
+use keymap::{Action, Keymap};
+use models::{
+    port_summary, BlockedAttempt, Connection, ConnectionKey, ConnectionSnapshot, CountryTraffic,
+    DnsClient, DnsTransport, EphemeralPorts, InterfaceStats, LinkEvent, MulticastGroup,
+    ProcessDetails, ProtocolStats, Route, RoutingTable, ScopeTraffic, SignalQuality, Talker,
+    TopTalkers, Tunnel, WirelessLink, COUNTRY_COLUMNS, LINK_EVENTS_KEPT,
+};
+use recording::Recorder;
+use services::bandwidth_limit::{self, LIMIT_PRESETS};
+use services::remote;
+use services::{
+    AddressResolver, Backend, BanList, CacheLimits, CollectorEvent, CollectorRequest,
+    ConnectionCollector, Fail2banService, FirewallLogService, Metrics, PermissionReport,
+    ProcessInspector, RefreshPacer, SortedConnections,
+};
+use std::collections::{HashSet, VecDeque};
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc;
 use tui::{
     backend::CrosstermBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, Row, Table, TableState},
+    widgets::{Block, Borders, Clear, LineGauge, Row, Table},
     Frame, Terminal,
 };
-use utils::formatter::Formatter;
-
-// Import shared modules
-mod error;
-mod error_tests;
-mod models;
-mod services;
-mod utils;
-
-/// Layout cache for TUI performance
-#[derive(Debug, Clone)]
-#[allow(dead_code)]
-struct LayoutCache {
-    available_width: u16,
-    visible_columns: Vec<usize>,
-    column_constraints: Vec<Constraint>,
-    last_calculation: Instant,
-    last_connection_count: usize,
-}
+use utils::formatter::{format_duration, Formatter, Units};
+use widgets::{ConnectionsTable, ConnectionsTableState, TopTalkersPanel};
 
-impl LayoutCache {
-    fn new() -> Self {
-        Self {
-            available_width: 0,
-            visible_columns: Vec::new(),
-            column_constraints: Vec::new(),
-            last_calculation: Instant::now(),
-            last_connection_count: 0,
-        }
-    }
+mod clipboard;
+mod keymap;
 
-    fn is_valid(&self, width: u16, connection_count: usize) -> bool {
-        self.available_width == width
-            && self.last_calculation.elapsed() < Duration::from_millis(500)
-            && (connection_count == 0 || self.last_connection_count == connection_count)
-    }
+use network_monitor::{
+    cli, comparison, crash, doctor, error, logging, models, recording, services, signals, utils,
+    widgets,
+};
+
+/// Auto-refresh interval while collection keeps within its budget, where
+/// socket changes can't be watched, and sampling interval of the modes that
+/// print to stdout
+const REFRESH_INTERVAL: Duration = Duration::from_secs(2);
+/// Auto-refresh interval where socket changes refresh sooner, though never
+/// within [`REFRESH_INTERVAL`] of the last refresh
+const WATCHED_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Processes and hosts listed in the top talkers pane
+const TOP_TALKERS: usize = 5;
+
+/// How long the latest interface change, or the outcome of a bandwidth
+/// limit, stays in the header
+const NOTICE_FOR: Duration = Duration::from_secs(10);
+
+/// Terminal network connection monitor
+#[derive(Parser)]
+#[command(name = "nmt", version)]
+struct Cli {
+    #[command(flatten)]
+    options: CommonArgs,
+
+    /// Start the privileged helper through pkexec to see every process's sockets
+    #[arg(long)]
+    elevate: bool,
+
+    /// Answer collection requests on stdin with connections on stdout instead
+    /// of starting the interface; the ssh:HOST backend runs this remotely,
+    /// SSH authenticating and encrypting the session, so no port is opened
+    #[arg(long, conflicts_with_all = ["elevate", "report"])]
+    serve: bool,
+
+    /// Print the total rates and busiest process as a line of JSON every
+    /// refresh interval instead of starting the interface, for a waybar
+    /// custom module
+    #[arg(long, conflicts_with_all = ["elevate", "report", "serve"])]
+    statusbar: bool,
+
+    /// List the N busiest processes with their rates every refresh interval
+    /// until interrupted instead of starting the interface
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["elevate", "report", "serve", "statusbar"],
+        value_parser = clap::value_parser!(u16).range(1..)
+    )]
+    top_processes: Option<u16>,
+
+    /// With --top-processes, print bare lines with a blank line after each
+    /// list instead of redrawing the terminal, for conky and scripts
+    #[arg(long, requires = "top_processes")]
+    plain: bool,
+
+    /// Compare two recorded sessions and print the destinations new in AFTER,
+    /// the listening ports gone from it and each program's traffic change as
+    /// Markdown. A session is a snapshot file, or a directory of recordings
+    /// with an optional UTC range as in DIR@2026-10-14..2026-10-15; an empty
+    /// DIR is the recording directory.
+    #[arg(
+        long,
+        num_args = 2,
+        value_names = ["BEFORE", "AFTER"],
+        conflicts_with_all = ["elevate", "report", "serve", "statusbar", "top_processes"]
+    )]
+    compare: Option<Vec<String>>,
+
+    /// With --compare, write an HTML page instead of Markdown
+    #[arg(long, requires = "compare")]
+    html: bool,
 }
 
 /// Application state for the TUI
 struct App {
-    connections: Vec<Connection>,
-    network_service: NetworkService,
+    /// Connections of the last snapshot, filtered and sorted for the table
+    rows: SortedConnections,
+    collector: ConnectionCollector,
+    refresh_pending: bool,
+    refresh_pacer: RefreshPacer,
     resolver: AddressResolver,
-    previous_io: HashMap<String, models::ProcessIO>,
-    table_state: TableState,
+    /// Selection, sort order and column scroll of the connection table
+    table: ConnectionsTableState,
     last_update: Instant,
     auto_refresh: bool,
-    sort_column: usize,
-    sort_ascending: bool,
-    horizontal_scroll: usize,
-    layout_cache: LayoutCache,
-    last_render_time: Instant,
-    render_count: usize,
-    skip_next_render: bool,
+    firewall_service: FirewallLogService,
+    blocked_attempts: Vec<BlockedAttempt>,
+    blocked_error: Option<String>,
+    /// Page on screen: connections, or the blocked or DNS view toggled with
+    /// b and n
+    view: View,
+    fail2ban_service: Fail2banService,
+    ban_list: BanList,
+    permission_report: PermissionReport,
+    process_details: Option<std::result::Result<ProcessDetails, String>>,
+    group_by_application: bool,
+    show_diagnostics: bool,
+    metrics: Metrics,
+    /// Busiest processes and hosts of the last snapshot, for the pane toggled with t
+    top_talkers: TopTalkers,
+    show_top_talkers: bool,
+    /// Connections without activity for `idle_after`, as of the last snapshot
+    idle: HashSet<ConnectionKey>,
+    idle_after: Duration,
+    /// Leave idle connections out instead of dimming them, toggled with i
+    hide_idle: bool,
+    /// Table columns shown, in display order
+    columns: Vec<usize>,
+    /// Bytes or bits and their multiples, for rates
+    units: Units,
+    keymap: Keymap,
+    /// Interface traffic, only reported by the router backend
+    interfaces: Vec<InterfaceStats>,
+    /// Use of the ephemeral port range, from backends that see local sockets
+    ephemeral_ports: Option<EphemeralPorts>,
+    /// Kernel-wide retransmissions, resets and errors
+    protocol_stats: Option<ProtocolStats>,
+    /// VPN tunnel interfaces and their peers
+    tunnels: Vec<Tunnel>,
+    /// Wi-Fi interfaces and the quality of their link
+    wireless: Vec<WirelessLink>,
+    /// Processes querying resolvers over the session, for the DNS view
+    dns_clients: Vec<DnsClient>,
+    /// Main routing table, for the routes view
+    routes: RoutingTable,
+    /// TCP connections by congestion control algorithm, for the details
+    congestion_controls: Vec<(Arc<str>, usize)>,
+    /// Route of the connection selected when the details or the routes view
+    /// were last opened
+    selected_route: Option<Route>,
+    /// Multicast groups and broadcast listeners, for the multicast view
+    multicast: Vec<MulticastGroup>,
+    /// Traffic per remote country, for the countries view
+    countries: Vec<CountryTraffic>,
+    /// LAN, WAN and VPN traffic over the session, for the footer
+    scopes: Vec<ScopeTraffic>,
+    /// Column of the countries view sorted by and whether ascending; None
+    /// keeps the most traffic first
+    country_sort: Option<(usize, bool)>,
+    /// Whether --geoip gave a database to place remote hosts with
+    geoip: bool,
+    /// Interface changes of the session, oldest first
+    link_events: VecDeque<LinkEvent>,
+    /// Bandwidth limit menu opened with L on a connection
+    limit_menu: Option<LimitMenu>,
+    /// Outcome of the last bandwidth limit request or command, failed or
+    /// not, and when
+    notice: Option<(String, bool, Instant)>,
+    /// Command line opened with `:`, as typed so far
+    command: Option<String>,
+    /// Scheduled recordings from the `[recording]` config section
+    recorder: Option<Recorder>,
+    /// This machine, then the `[remotes]` of the config and all of them
+    /// combined, by name
+    hosts: Vec<(String, Backend)>,
+    /// Index in `hosts` of the machine collected from
+    host: usize,
+    /// Whether the host column was put in front for the combined table,
+    /// and goes again when leaving it
+    host_column_added: bool,
+}
+
+/// Process the bandwidth limit menu acts on
+struct LimitMenu {
+    pid: String,
+    program: String,
+    limit: Option<u64>,
 }
 
 impl App {
-    fn new() -> Self {
+    fn new(
+        collector: ConnectionCollector,
+        cache_limits: CacheLimits,
+        settings: &Settings,
+        keymap: Keymap,
+    ) -> Self {
+        let sort = settings.sort.unwrap_or_default();
+        let resolver = AddressResolver::new(settings.resolve_hostnames.unwrap_or(false));
+        resolver.set_host_names(settings.host_names.clone());
+        let mut rows =
+            SortedConnections::new(sort.column, sort.ascending).with_resolver(resolver.clone());
+        rows.set_filter(settings.filter.clone());
         let mut app = Self {
-            connections: Vec::new(),
-            network_service: NetworkService::new(),
-            resolver: AddressResolver::new(false),
-            previous_io: HashMap::new(),
-            table_state: TableState::default(),
+            rows,
+            collector,
+            refresh_pending: false,
+            refresh_pacer: RefreshPacer::new(settings.refresh.unwrap_or(
+                if settings.backend.watches_sockets() {
+                    WATCHED_REFRESH_INTERVAL
+                } else {
+                    REFRESH_INTERVAL
+                },
+            )),
+            resolver,
+            table: ConnectionsTableState::new(sort.column, sort.ascending),
             last_update: Instant::now(),
             auto_refresh: true,
-            sort_column: 6,        // RX column
-            sort_ascending: false, // Descending order
-            horizontal_scroll: 0,
-            layout_cache: LayoutCache::new(),
-            last_render_time: Instant::now(),
-            render_count: 0,
-            skip_next_render: false,
+            firewall_service: FirewallLogService::new(),
+            blocked_attempts: Vec::new(),
+            blocked_error: None,
+            view: View::Connections,
+            fail2ban_service: Fail2banService::new(false),
+            ban_list: BanList::default(),
+            permission_report: PermissionReport::check(),
+            process_details: None,
+            group_by_application: false,
+            show_diagnostics: false,
+            metrics: Metrics::default(),
+            top_talkers: TopTalkers::default(),
+            show_top_talkers: false,
+            idle: HashSet::new(),
+            idle_after: settings.idle_after(),
+            hide_idle: settings.hide_idle.unwrap_or(false),
+            columns: settings.columns(),
+            units: settings.units(),
+            keymap,
+            interfaces: Vec::new(),
+            ephemeral_ports: None,
+            protocol_stats: None,
+            tunnels: Vec::new(),
+            wireless: Vec::new(),
+            dns_clients: Vec::new(),
+            routes: RoutingTable::default(),
+            congestion_controls: Vec::new(),
+            selected_route: None,
+            multicast: Vec::new(),
+            countries: Vec::new(),
+            scopes: Vec::new(),
+            country_sort: None,
+            geoip: !settings.geoip.is_empty(),
+            link_events: VecDeque::new(),
+            limit_menu: None,
+            notice: None,
+            command: None,
+            recorder: settings
+                .recording
+                .clone()
+                .map(|schedule| Recorder::new(schedule, SystemTime::now())),
+            hosts: settings.hosts(),
+            host: 0,
+            host_column_added: false,
         };
-        app.update_connections();
+        app.resolver.set_cache_limit(cache_limits.resolver_entries);
+        if let Some(view) = settings.view {
+            app.show_view(view);
+        }
+        // Best effort: a damaged cache file is replaced on exit
+        let _ = app.resolver.load_cache(&settings.paths.resolver_cache());
+        app.request_refresh();
         app
     }
 
-    fn update_connections(&mut self) {
-        match self.network_service.get_connections() {
-            Ok(connections) => {
-                match self
-                    .network_service
-                    .update_connection_rates(connections, &self.previous_io)
-                {
-                    Ok((updated_connections, current_io)) => {
-                        // Skip render if connection count hasn't changed significantly
-                        let significant_change = (updated_connections.len() as isize
-                            - self.connections.len() as isize)
-                            .abs()
-                            > 5;
-
-                        self.connections = updated_connections;
-                        self.previous_io = current_io;
-                        self.last_update = Instant::now();
-                        self.sort_connections();
+    /// Ask the collector thread for a new snapshot, unless one is already on its way
+    fn request_refresh(&mut self) {
+        if self.refresh_pending {
+            return;
+        }
+        self.refresh_pending = true;
+        self.collector.request(CollectorRequest::Refresh {
+            group_by_application: self.group_by_application,
+        });
+    }
 
-                        // Skip next render if no significant changes to improve performance
-                        self.skip_next_render = !significant_change && self.connections.len() > 50;
+    fn handle_collector_event(&mut self, event: CollectorEvent) {
+        match event {
+            CollectorEvent::Snapshot(result) => {
+                self.refresh_pending = false;
+                match result {
+                    Ok(snapshot) => {
+                        self.refresh_pacer.record(snapshot.collection_time);
+                        self.apply_snapshot(*snapshot);
                     }
+                    // Log error but continue with existing data
                     Err(e) => {
-                        // Log error but continue with existing data
-                        eprintln!("Failed to update connection rates: {}", e);
+                        tracing::warn!("Collection failed: {}", e);
+                        // Other machines fail for reasons worth seeing, like
+                        // ssh refusing to log in
+                        if self.host > 0 {
+                            self.notice = Some((e, false, Instant::now()));
+                        }
                     }
                 }
             }
-            Err(e) => {
-                // Log error but continue with existing data - handle permission errors gracefully
-                eprintln!("Failed to get connections: {}", e);
-                // Don't update connections on error, keep existing data
-                eprintln!("Failed to get connections: {}", e);
+            CollectorEvent::Capture(Err(e)) => tracing::warn!("{}", e),
+            CollectorEvent::GeoIp(Err(e)) => tracing::warn!("{}", e),
+            CollectorEvent::PrivilegedHelper(_)
+            | CollectorEvent::Capture(Ok(()))
+            | CollectorEvent::GeoIp(Ok(())) => {}
+            // Interface views show the change right away instead of at the
+            // next refresh
+            CollectorEvent::Link(event) => {
+                if self.link_events.len() == LINK_EVENTS_KEPT {
+                    self.link_events.pop_front();
+                }
+                self.link_events.push_back(event);
+                self.request_refresh();
+            }
+            // New and closed connections show up without waiting for the
+            // timer, no more often than it used to poll
+            CollectorEvent::SocketsChanged => {
+                if self.auto_refresh
+                    && !self.refresh_pacer.is_backed_off()
+                    && self.last_update.elapsed() >= REFRESH_INTERVAL
+                {
+                    self.request_refresh();
+                }
+            }
+            CollectorEvent::BandwidthLimit { pid, result } => {
+                let notice = match &result {
+                    Ok(()) => format!("Bandwidth limit of {pid} changed"),
+                    Err(e) => format!("Bandwidth limit of {pid}: {e}"),
+                };
+                self.notice = Some((notice, result.is_ok(), Instant::now()));
+                self.request_refresh();
+            }
+            CollectorEvent::BackendSwitched { backend, result } => {
+                let name = self
+                    .hosts
+                    .iter()
+                    .find(|(_, host)| *host == backend)
+                    .map_or_else(|| backend.to_string(), |(name, _)| name.clone());
+                let notice = match &result {
+                    Ok(()) => format!("Collecting from {name}"),
+                    Err(e) => format!("Could not collect from {name}: {e}"),
+                };
+                self.notice = Some((notice, result.is_ok(), Instant::now()));
+                self.show_host_column(matches!(backend, Backend::Combined(_)));
+                self.rows.clear();
+                self.idle.clear();
+                self.request_refresh();
             }
         }
     }
 
-    fn sort_connections(&mut self) {
-        self.connections.sort_by(|a, b| {
-            let ordering = match self.sort_column {
-                0 => a.program.cmp(&b.program),
-                1 => a.protocol.cmp(&b.protocol),
-                2 => a.local.cmp(&b.local),
-                3 => a.remote.cmp(&b.remote),
-                4 => a.state.cmp(&b.state),
-                5 => a.tx_rate.cmp(&b.tx_rate),
-                6 => a.rx_rate.cmp(&b.rx_rate),
-                7 => a.command.cmp(&b.command),
-                _ => std::cmp::Ordering::Equal,
-            };
-
-            if self.sort_ascending {
-                ordering
-            } else {
-                ordering.reverse()
+    /// Collect from the machine at `index` in `hosts` from now on
+    fn switch_host(&mut self, index: usize) {
+        let Some((_, backend)) = self.hosts.get(index) else {
+            return;
+        };
+        self.host = index;
+        self.collector
+            .request(CollectorRequest::SwitchBackend(backend.clone()));
+    }
+
+    /// Put the host column first while connections come from several
+    /// machines, unless it is shown already
+    fn show_host_column(&mut self, show: bool) {
+        if show && !self.columns.contains(&cli::HOST_COLUMN) {
+            self.columns.insert(0, cli::HOST_COLUMN);
+            self.host_column_added = true;
+        } else if !show && self.host_column_added {
+            self.columns.retain(|&column| column != cli::HOST_COLUMN);
+            self.host_column_added = false;
+        }
+    }
+
+    /// Switch to the host after the current one, back to this machine after
+    /// the last
+    fn next_host(&mut self) {
+        if self.hosts.len() < 2 {
+            self.notice = Some((
+                "No [remotes] configured to switch to".to_string(),
+                false,
+                Instant::now(),
+            ));
+            return;
+        }
+        self.switch_host((self.host + 1) % self.hosts.len());
+    }
+
+    fn apply_snapshot(&mut self, snapshot: ConnectionSnapshot) {
+        self.last_update = Instant::now();
+        if let Some(recorder) = &mut self.recorder {
+            let resolver = &self.resolver;
+            match recorder
+                .record_if_due(&snapshot, self.units, |conn| resolver.resolve_remote(conn))
+            {
+                Ok(Some(path)) => tracing::info!("Recorded {}", path.display()),
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Could not write the scheduled recording: {}", e),
+            }
+        }
+        self.metrics.collection.record(snapshot.collection_time);
+        self.metrics.process_cache = snapshot.process_cache;
+        self.top_talkers = snapshot.top_talkers(TOP_TALKERS);
+        self.congestion_controls = snapshot.congestion_controls();
+        // Connections going idle only change the rows when they are hidden
+        self.idle = snapshot.idle_connections(self.idle_after);
+        self.rows.set_hidden(self.hidden());
+        self.change_rows(|rows| rows.update(&snapshot));
+        self.interfaces = snapshot.interface_stats;
+        self.ephemeral_ports = snapshot.ephemeral_ports;
+        self.protocol_stats = snapshot.protocol_stats;
+        self.tunnels = snapshot.tunnels;
+        self.wireless = snapshot.wireless;
+        self.dns_clients = snapshot.dns_clients;
+        self.routes = snapshot.routes;
+        self.multicast = snapshot.multicast;
+        self.countries = snapshot.countries;
+        self.scopes = snapshot.scopes;
+        self.sort_countries();
+
+        if self.view == View::Blocked {
+            self.update_blocked_attempts();
+        }
+
+        // Keep the previous ban list if fail2ban can't be queried
+        if let Ok(ban_list) = self.fail2ban_service.get_ban_list() {
+            self.ban_list = ban_list;
+        }
+    }
+
+    /// Connections left out of the table: the idle ones while they're hidden
+    fn hidden(&self) -> HashSet<ConnectionKey> {
+        if self.hide_idle {
+            self.idle.clone()
+        } else {
+            HashSet::new()
+        }
+    }
+
+    fn toggle_hide_idle(&mut self) {
+        self.hide_idle = !self.hide_idle;
+        let hidden = self.hidden();
+        self.change_rows(|rows| {
+            rows.set_hidden(hidden);
+            rows.refresh()
+        });
+    }
+
+    /// Apply `change` to the rows, keeping the selection on its connection
+    /// when they were rebuilt
+    fn change_rows(&mut self, change: impl FnOnce(&mut SortedConnections) -> bool) {
+        let selected_key = self
+            .table
+            .rows
+            .selected()
+            .filter(|_| self.view == View::Connections)
+            .and_then(|i| self.rows.rows().get(i))
+            .map(Connection::key);
+        if !change(&mut self.rows) {
+            return;
+        }
+        if let Some(key) = selected_key.map(|key| self.rows.follow(key)) {
+            if let Some(i) = self.rows.rows().iter().position(|conn| conn.key() == key) {
+                self.table.rows.select(Some(i));
             }
+        }
+    }
+
+    /// Order the rows as the table header shows
+    fn sort_connections(&mut self) {
+        let (column, ascending) = (self.table.sort_column, self.table.sort_ascending);
+        self.change_rows(|rows| {
+            rows.set_sort(column, ascending);
+            rows.refresh()
         });
     }
 
+    fn row_count(&self) -> usize {
+        match self.view {
+            View::Connections => self.rows.rows().len(),
+            View::Blocked => self.blocked_attempts.len(),
+            View::Dns => self.dns_clients.len(),
+            View::Routes => self.routes.routes.len(),
+            View::Events => self.link_events.len(),
+            View::Multicast => self.multicast.len(),
+            View::Countries => self.countries.len(),
+        }
+    }
+
     fn next_row(&mut self) {
-        let i = match self.table_state.selected() {
+        let i = match self.table.rows.selected() {
             Some(i) => {
-                if i >= self.connections.len().saturating_sub(1) {
+                if i >= self.row_count().saturating_sub(1) {
                     0
                 } else {
                     i + 1
@@ -168,87 +529,392 @@ impl App {
             }
             None => 0,
         };
-        self.table_state.select(Some(i));
+        self.table.rows.select(Some(i));
     }
 
     fn previous_row(&mut self) {
-        let i = match self.table_state.selected() {
+        let i = match self.table.rows.selected() {
             Some(i) => {
                 if i == 0 {
-                    self.connections.len().saturating_sub(1)
+                    self.row_count().saturating_sub(1)
                 } else {
                     i - 1
                 }
             }
             None => 0,
         };
-        self.table_state.select(Some(i));
+        self.table.rows.select(Some(i));
     }
 
     fn toggle_sort(&mut self, column: usize) {
-        if self.sort_column == column {
-            self.sort_ascending = !self.sort_ascending;
-        } else {
-            self.sort_column = column;
-            self.sort_ascending = true;
+        if self.view == View::Countries {
+            self.toggle_country_sort(column);
+            return;
         }
+        self.table.toggle_sort(column);
         self.sort_connections();
     }
 
-    fn scroll_left(&mut self) {
-        // Scroll 5 columns at a time for faster navigation
-        if self.horizontal_scroll > 0 {
-            self.horizontal_scroll = self.horizontal_scroll.saturating_sub(5);
+    /// Sort the countries view by `column`, flipping the direction when
+    /// already sorted by it; numbers start with the largest
+    fn toggle_country_sort(&mut self, column: usize) {
+        if column >= COUNTRY_COLUMNS.len() {
+            return;
         }
+        self.country_sort = Some(match self.country_sort {
+            Some((sorted, ascending)) if sorted == column => (column, !ascending),
+            _ => (column, column == 0),
+        });
+        self.sort_countries();
     }
 
-    fn scroll_right(&mut self) {
-        // Scroll 5 columns at a time for faster navigation, but don't exceed bounds
-        self.horizontal_scroll = (self.horizontal_scroll + 5).min(7);
+    fn sort_countries(&mut self) {
+        if let Some((column, ascending)) = self.country_sort {
+            self.countries.sort_by(|a, b| {
+                let ordering = a.cmp_by_column(b, column);
+                if ascending {
+                    ordering
+                } else {
+                    ordering.reverse()
+                }
+            });
+        }
+    }
+
+    fn update_blocked_attempts(&mut self) {
+        // Every connection, so attempts of filtered or idle processes keep their owner
+        match self
+            .firewall_service
+            .get_blocked_attempts(self.rows.collected())
+        {
+            Ok(attempts) => {
+                self.blocked_attempts = attempts;
+                self.blocked_error = None;
+            }
+            Err(e) => self.blocked_error = Some(e.to_string()),
+        }
+    }
+
+    /// Switch to `view`, or back to the connections when it is on screen
+    fn toggle_view(&mut self, view: View) {
+        if self.view == view {
+            self.show_view(View::Connections);
+        } else {
+            self.show_view(view);
+        }
+    }
+
+    fn show_view(&mut self, view: View) {
+        if view == View::Routes && self.view == View::Connections {
+            self.selected_route = self.route_of_selection();
+        }
+        self.view = view;
+        self.table.rows.select(None);
+        if view == View::Blocked {
+            self.update_blocked_attempts();
+        }
+    }
+
+    /// Open or close the detail pane for the selected connection
+    fn toggle_process_details(&mut self) {
+        if self.process_details.take().is_some() || self.view != View::Connections {
+            return;
+        }
+        let Some(conn) = self
+            .table
+            .rows
+            .selected()
+            .and_then(|index| self.rows.rows().get(index))
+        else {
+            return;
+        };
+        self.process_details =
+            Some(ProcessInspector::inspect(&conn.pid).map_err(|e| e.to_string()));
+        self.selected_route = self.route_of_selection();
+    }
+
+    /// Put the selected connection on the clipboard as JSON, for bug reports
+    /// and scripts
+    fn copy_selection_json(&mut self) {
+        if self.view != View::Connections {
+            return;
+        }
+        let Some(conn) = self
+            .table
+            .rows
+            .selected()
+            .and_then(|index| self.rows.rows().get(index))
+        else {
+            return;
+        };
+        let (notice, copied) = match serde_json::to_string_pretty(std::slice::from_ref(conn))
+            .map_err(io::Error::other)
+            .and_then(|json| clipboard::copy(&json))
+        {
+            Ok(()) => (
+                format!("Copied {} as JSON", conn.get_process_display()),
+                true,
+            ),
+            Err(e) => (format!("Could not copy: {e}"), false),
+        };
+        self.notice = Some((notice, copied, Instant::now()));
+    }
+
+    /// Open the bandwidth limit menu for the selected connection's process
+    fn open_limit_menu(&mut self) {
+        if self.view != View::Connections {
+            return;
+        }
+        let Some(conn) = self
+            .table
+            .rows
+            .selected()
+            .and_then(|index| self.rows.rows().get(index))
+        else {
+            return;
+        };
+        if let Err(e) = bandwidth_limit::parse_pid(&conn.pid) {
+            self.notice = Some((e.to_string(), false, Instant::now()));
+            return;
+        }
+        self.limit_menu = Some(LimitMenu {
+            pid: conn.pid.to_string(),
+            program: conn.get_process_display(),
+            limit: conn.bandwidth_limit,
+        });
+    }
+
+    /// Apply the menu entry picked with `key`: a preset's number, r to lift
+    /// the limit; any other key closes the menu
+    fn choose_limit(&mut self, key: &KeyEvent) {
+        let Some(menu) = self.limit_menu.take() else {
+            return;
+        };
+        let preset = |c: char| {
+            let index = (c.to_digit(10)? as usize).checked_sub(1)?;
+            LIMIT_PRESETS.get(index).copied()
+        };
+        let bytes_per_sec = match key.code {
+            KeyCode::Char('r') if menu.limit.is_some() => None,
+            KeyCode::Char(c) => match preset(c) {
+                Some(rate) => Some(rate),
+                None => return,
+            },
+            _ => return,
+        };
+        self.collector.request(CollectorRequest::LimitBandwidth {
+            pid: menu.pid,
+            bytes_per_sec,
+        });
+    }
+
+    /// Run a line typed after `:`, reporting the outcome in the header
+    fn run_command(&mut self, line: &str) {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let result = match words[..] {
+            [] => return,
+            ["filter"] | ["port"] => {
+                self.set_filter(None);
+                Ok("Filter cleared".to_string())
+            }
+            ["filter", ..] => {
+                let filter = words[1..].join(" ").to_lowercase();
+                self.set_filter(Some(filter.clone()));
+                Ok(format!(
+                    "{} connections match '{filter}'",
+                    self.rows.rows().len()
+                ))
+            }
+            ["sort", column] | ["sort", column, _] => {
+                let direction = words.get(2).unwrap_or(&"desc");
+                format!("{column}:{direction}")
+                    .parse::<SortSpec>()
+                    .map(|sort| {
+                        self.table.sort_column = sort.column;
+                        self.table.sort_ascending = sort.ascending;
+                        self.sort_connections();
+                        format!("Sorted by {column} {direction}")
+                    })
+            }
+            ["export", path] => self.export(path),
+            ["interval", seconds] => cli::parse_refresh(seconds).map(|interval| {
+                self.refresh_pacer.set_base(interval);
+                format!("Refreshing every {}s", interval.as_secs_f64())
+            }),
+            ["port", port] => match port.parse() {
+                Ok(port) => Ok(self.find_port(port)),
+                Err(_) => Err(format!("'{port}' is not a port number")),
+            },
+            ["host", name] => match self.hosts.iter().position(|(host, _)| host == name) {
+                Some(index) => {
+                    self.switch_host(index);
+                    Ok(format!("Switching to {name}"))
+                }
+                None => Err(format!(
+                    "No host '{name}' (try {})",
+                    self.hosts
+                        .iter()
+                        .map(|(host, _)| host.as_str())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )),
+            },
+            [name, ..] => Err(format!(
+                "Unknown command '{name}' (try filter, sort, export, interval, port or host)"
+            )),
+        };
+        self.notice = Some(match result {
+            Ok(notice) => (notice, true, Instant::now()),
+            Err(notice) => (notice, false, Instant::now()),
+        });
+    }
+
+    /// Write the connections table as shown to `path`, as CSV or JSON by its
+    /// extension and as aligned text otherwise
+    fn export(&self, path: &str) -> std::result::Result<String, String> {
+        let format = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some("csv") => OutputFormat::Csv,
+            Some("json") => OutputFormat::Json,
+            _ => OutputFormat::Text,
+        };
+        let write = || -> io::Result<()> {
+            let mut file = io::BufWriter::new(File::create(path)?);
+            cli::write_connections(
+                &mut file,
+                self.rows.rows(),
+                &self.columns,
+                format,
+                self.units,
+                |conn| self.resolver.resolve_remote(conn),
+            )?;
+            file.flush()
+        };
+        write()
+            .map(|()| format!("Exported {} connections to {path}", self.rows.rows().len()))
+            .map_err(|e| format!("Could not export to {path}: {e}"))
+    }
+
+    /// Show only the sockets on local `port` and name the processes holding it
+    fn find_port(&mut self, port: u16) -> String {
+        self.set_filter(Some(format!("port={port}")));
+        self.show_view(View::Connections);
+        port_summary(self.rows.collected(), port)
+    }
+
+    fn set_filter(&mut self, filter: Option<String>) {
+        self.change_rows(|rows| {
+            rows.set_filter(filter);
+            rows.refresh()
+        });
+    }
+
+    /// Route taken by the selected connection
+    fn route_of_selection(&self) -> Option<Route> {
+        let conn = self
+            .table
+            .rows
+            .selected()
+            .and_then(|index| self.rows.rows().get(index))?;
+        self.routes.route_for(conn).cloned()
+    }
+
+    fn toggle_grouping(&mut self) {
+        self.group_by_application = !self.group_by_application;
+        self.request_refresh();
+    }
+
+    fn toggle_fail2ban(&mut self) {
+        let enabled = !self.fail2ban_service.is_enabled();
+        self.fail2ban_service.set_enabled(enabled);
+        self.ban_list = BanList::default();
+        self.request_refresh();
     }
 
     fn toggle_resolver(&mut self) {
         let current_state = self.resolver.get_resolve_hosts();
         self.resolver.set_resolve_hosts(!current_state);
-        // Force refresh to update display with new resolver state
-        self.update_connections();
+        self.change_rows(SortedConnections::refresh);
     }
 }
 
-// Use the consolidated formatter from utils
-fn format_bytes(bytes: u64) -> String {
-    Formatter::format_bytes(bytes)
-}
-
 fn ui(f: &mut Frame, app: &mut App) {
+    // Extra header lines explaining what an unprivileged session can't show,
+    // with the interface rates of the router backend, the kernel's protocol
+    // counters, the VPN tunnels, the Wi-Fi links and the ephemeral port gauge
+    let header_height = 3
+        + u16::from(app.permission_report.is_limited())
+        + u16::from(!app.interfaces.is_empty())
+        + u16::from(app.protocol_stats.is_some())
+        + u16::from(!app.tunnels.is_empty())
+        + u16::from(!app.wireless.is_empty())
+        + u16::from(app.ephemeral_ports.is_some());
     let chunks = Layout::default()
         .direction(Direction::Vertical)
         .constraints([
-            Constraint::Length(3),
+            Constraint::Length(header_height),
             Constraint::Min(0),
             Constraint::Length(3),
         ])
         .split(f.area());
 
     // Header
-    let header_text = vec![Line::from(vec![
+    let total = Talker::total(app.rows.rows());
+    let mut title = vec![
         Span::styled(
             "Network Monitor TUI",
             Style::default().add_modifier(Modifier::BOLD),
         ),
         Span::raw(" | "),
+    ];
+    if app.hosts.len() > 1 {
+        title.push(Span::styled(
+            format!("Host: {}", app.hosts[app.host].0),
+            Style::default()
+                .fg(Color::Magenta)
+                .add_modifier(Modifier::BOLD),
+        ));
+        title.push(Span::raw(" | "));
+    }
+    title.extend([
+        Span::styled(
+            match app.rows.filter() {
+                Some(filter) => format!("Connections: {} ({filter})", app.rows.rows().len()),
+                None => format!("Connections: {}", app.rows.rows().len()),
+            },
+            Style::default().fg(Color::Cyan),
+        ),
+        Span::raw(" | "),
         Span::styled(
-            format!("Connections: {}", app.connections.len()),
+            format!(
+                "↑{} ↓{}",
+                app.units.format_rate(total.tx_rate),
+                app.units.format_rate(total.rx_rate)
+            ),
             Style::default().fg(Color::Cyan),
         ),
         Span::raw(" | "),
+        if !app.auto_refresh {
+            Span::styled("Auto-refresh: OFF", Style::default().fg(Color::Red))
+        } else if app.refresh_pacer.is_backed_off() {
+            // Slow collection passes stretched the interval
+            Span::styled(
+                format!(
+                    "Auto-refresh: {}s (slow)",
+                    app.refresh_pacer.interval().as_secs()
+                ),
+                Style::default().fg(Color::Yellow),
+            )
+        } else {
+            Span::styled("Auto-refresh: ON", Style::default().fg(Color::Green))
+        },
+        Span::raw(" | "),
         Span::styled(
-            if app.auto_refresh {
-                "Auto-refresh: ON"
+            if app.resolver.get_resolve_hosts() {
+                "Resolver: ON"
             } else {
-                "Auto-refresh: OFF"
+                "Resolver: OFF"
             },
-            Style::default().fg(if app.auto_refresh {
+            Style::default().fg(if app.resolver.get_resolve_hosts() {
                 Color::Green
             } else {
                 Color::Red
@@ -256,12 +922,12 @@ fn ui(f: &mut Frame, app: &mut App) {
         ),
         Span::raw(" | "),
         Span::styled(
-            if app.resolver.get_resolve_hosts() {
-                "Resolver: ON"
+            if app.fail2ban_service.is_enabled() {
+                format!("Fail2ban: {} banned", app.ban_list.len())
             } else {
-                "Resolver: OFF"
+                "Fail2ban: OFF".to_string()
             },
-            Style::default().fg(if app.resolver.get_resolve_hosts() {
+            Style::default().fg(if app.fail2ban_service.is_enabled() {
                 Color::Green
             } else {
                 Color::Red
@@ -272,349 +938,1138 @@ fn ui(f: &mut Frame, app: &mut App) {
             format!("Last: {:.1}s ago", app.last_update.elapsed().as_secs_f64()),
             Style::default().fg(Color::Yellow),
         ),
-    ])];
+    ]);
+    let mut header_text = vec![Line::from(title)];
+    // The latest interface change, for a few seconds
+    if let Some(event) = app.link_events.back().filter(|event| {
+        SystemTime::now()
+            .duration_since(event.at)
+            .is_ok_and(|age| age < NOTICE_FOR)
+    }) {
+        header_text[0].spans.push(Span::raw(" | "));
+        header_text[0].spans.push(Span::styled(
+            event.to_string(),
+            Style::default()
+                .fg(if event.is_loss() {
+                    Color::Red
+                } else {
+                    Color::Green
+                })
+                .add_modifier(Modifier::BOLD),
+        ));
+    }
+    if let Some((notice, succeeded, _)) = app
+        .notice
+        .as_ref()
+        .filter(|(_, _, at)| at.elapsed() < NOTICE_FOR)
+    {
+        header_text[0].spans.push(Span::raw(" | "));
+        header_text[0].spans.push(Span::styled(
+            notice.clone(),
+            Style::default().fg(if *succeeded { Color::Green } else { Color::Red }),
+        ));
+    }
+    if app.permission_report.is_limited() {
+        header_text.push(Line::from(Span::styled(
+            app.permission_report.summary(),
+            Style::default().fg(Color::Yellow),
+        )));
+    }
+    if !app.interfaces.is_empty() {
+        let mut spans = Vec::new();
+        for interface in app.interfaces.iter().filter(|i| !i.is_loopback()) {
+            if !spans.is_empty() {
+                spans.push(Span::raw(" | "));
+            }
+            spans.push(Span::styled(
+                format!(
+                    "{} ↑{} ↓{}",
+                    interface.name,
+                    app.units.format_rate(interface.tx_rate),
+                    app.units.format_rate(interface.rx_rate)
+                ),
+                Style::default().fg(Color::Cyan),
+            ));
+        }
+        header_text.push(Line::from(spans));
+    }
+    if let Some(stats) = &app.protocol_stats {
+        header_text.push(protocol_line(stats));
+    }
+    if !app.tunnels.is_empty() {
+        header_text.push(tunnel_line(&app.tunnels, app.units, SystemTime::now()));
+    }
+    if !app.wireless.is_empty() {
+        header_text.push(wireless_line(&app.wireless, &app.interfaces, app.units));
+    }
 
     let header =
         tui::widgets::Paragraph::new(header_text).block(Block::default().borders(Borders::ALL));
     f.render_widget(header, chunks[0]);
-
-    // Connections table
-    let header_cells = [
-        "Process(ID)",
-        "Protocol",
-        "Source",
-        "Destination",
-        "Status",
-        "TX",
-        "RX",
-        "Path",
-    ]
-    .iter()
-    .enumerate()
-    .map(|(i, &title)| {
-        let style = if i == app.sort_column {
-            Style::default()
-                .fg(Color::Cyan)
-                .add_modifier(Modifier::BOLD)
-        } else {
-            Style::default().fg(Color::Gray)
+    if let Some(ports) = &app.ephemeral_ports {
+        // Last line inside the header border
+        let area = Rect {
+            x: chunks[0].x + 1,
+            y: chunks[0].bottom().saturating_sub(2),
+            width: chunks[0].width.saturating_sub(2),
+            height: 1,
         };
+        f.render_widget(port_gauge(ports), area);
+    }
 
-        let arrow = if i == app.sort_column {
-            if app.sort_ascending {
-                " ↑"
-            } else {
-                " ↓"
-            }
-        } else {
-            ""
-        };
-
-        Span::styled(format!("{}{}", title, arrow), style)
-    });
-
-    let _header = Row::new(header_cells)
-        .style(Style::default().add_modifier(Modifier::REVERSED))
-        .height(1);
+    match app.view {
+        View::Connections => {}
+        View::Blocked => {
+            render_blocked_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+        View::Dns => {
+            render_dns_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+        View::Routes => {
+            render_routes_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+        View::Events => {
+            render_events_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+        View::Multicast => {
+            render_multicast_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+        View::Countries => {
+            render_countries_table(f, app, chunks[1]);
+            render_footer(f, chunks[2], app);
+            return;
+        }
+    }
 
-    let _rows = app.connections.iter().enumerate().map(|(i, conn)| {
-        let color = match conn.protocol.as_str() {
-            "tcp" | "tcp6" => Color::Green,
-            "udp" | "udp6" => Color::Yellow,
-            _ => Color::White,
-        };
+    // Details of the selected connection's process below the table
+    let table_area = match &app.process_details {
+        Some(details) => {
+            let areas = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Min(0), Constraint::Length(10)])
+                .split(chunks[1]);
+            // Not shown when the backend doesn't read the routing table
+            let route = (!app.routes.is_empty()).then(|| {
+                app.selected_route.as_ref().map_or_else(
+                    || "none: local traffic, or no route matches".to_string(),
+                    Route::to_string,
+                )
+            });
+            let congestion = app
+                .table
+                .rows
+                .selected()
+                .and_then(|index| app.rows.rows().get(index))
+                .and_then(|conn| conn.congestion.as_ref())
+                .map(|algorithm| {
+                    format!(
+                        "{algorithm} (all TCP: {})",
+                        Formatter::format_counts(&app.congestion_controls)
+                    )
+                });
+            render_process_details(f, details, route, congestion, areas[1]);
+            areas[0]
+        }
+        None => chunks[1],
+    };
 
-        let is_selected = app
-            .table_state
-            .selected()
-            .map(|sel| sel == i)
-            .unwrap_or(false);
+    // Top talkers between the table and the details, a heading plus one
+    // line per talker
+    let table_area = if app.show_top_talkers {
+        let areas = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Min(0),
+                Constraint::Length(TOP_TALKERS as u16 + 3),
+            ])
+            .split(table_area);
+        let host_label = |host: &str| app.resolver.resolve_host(host);
+        let panel = TopTalkersPanel::new(&app.top_talkers)
+            .units(app.units)
+            .title("Top Talkers (t to close)")
+            .host_label(&host_label);
+        f.render_widget(panel, areas[1]);
+        areas[0]
+    } else {
+        table_area
+    };
 
-        let style = if is_selected {
-            Style::default()
-                .fg(color)
-                .add_modifier(Modifier::BOLD)
-                .bg(Color::DarkGray)
-        } else if conn.is_active() {
-            Style::default().fg(color).add_modifier(Modifier::BOLD)
+    // Connections table, banned remote addresses in red
+    let remote_label = |conn: &Connection| {
+        let remote = app.resolver.resolve_remote(conn);
+        match app.ban_list.jail_for(&conn.remote) {
+            Some(jail) => format!("{} [banned:{}]", remote, jail),
+            None => remote,
+        }
+    };
+    let banned = |conn: &Connection| app.ban_list.jail_for(&conn.remote).is_some();
+    let idle = |conn: &Connection| app.idle.contains(&conn.key());
+    let table = ConnectionsTable::new(app.rows.rows())
+        .columns(&app.columns)
+        .units(app.units)
+        .title(if app.group_by_application {
+            "Network Connections (grouped by application)"
         } else {
-            Style::default().fg(color)
-        };
-
-        let cells = vec![
-            Span::raw(conn.get_process_display()),
-            Span::raw(&conn.protocol),
-            Span::raw(&conn.local),
-            Span::raw(&conn.remote),
-            Span::raw(&conn.state),
-            Span::raw(format_bytes(conn.tx_rate)),
-            Span::raw(format_bytes(conn.rx_rate)),
-            Span::raw(&conn.command),
-        ];
-
-        Row::new(cells).style(style)
-    });
-
-    // Calculate visible columns based on horizontal scroll with caching
-    let total_columns: usize = 8;
-    let available_width = chunks[1].width.saturating_sub(2) as usize; // Subtract borders
-    let column_widths = [15, 10, 18, 22, 12, 10, 12, 40]; // Stable minimum widths - increased Path column width
-    let start_col = app.horizontal_scroll.min(total_columns.saturating_sub(1));
+            "Network Connections"
+        })
+        .remote_label(&remote_label)
+        .flagged(&banned)
+        .dimmed(&idle);
+    f.render_stateful_widget(table, table_area, &mut app.table);
 
-    // Check if we can use cached layout
-    let (visible_columns, remaining_width) = if app
-        .layout_cache
-        .is_valid(chunks[1].width, app.connections.len())
-    {
-        (
-            app.layout_cache.visible_columns.clone(),
-            available_width.saturating_sub(
-                app.layout_cache
-                    .visible_columns
-                    .iter()
-                    .enumerate()
-                    .map(|(i, &col_idx)| {
-                        if i < column_widths.len() {
-                            column_widths[col_idx]
-                        } else {
-                            10
-                        }
-                    })
-                    .sum::<usize>()
-                    + app.layout_cache.visible_columns.len().saturating_sub(1),
-            ),
-        )
-    } else {
-        // Recalculate layout
-        let mut visible_columns = Vec::new();
-        let mut current_width = 0;
+    render_footer(f, chunks[2], app);
+}
 
-        // Determine which columns to show - be more conservative to avoid frequent changes
-        for (i, &width) in column_widths
-            .iter()
-            .enumerate()
-            .skip(start_col)
-            .take(total_columns - start_col)
-        {
-            // Add small buffer to prevent flickering when width is borderline
-            let required_width = width + 2; // +2 for padding and buffer
-            if current_width + required_width <= available_width || visible_columns.is_empty() {
-                visible_columns.push(i);
-                current_width += required_width;
-            } else {
-                break;
+/// Each tunnel with its endpoint, handshake age and transfer totals, in
+/// yellow once its peers stop answering
+fn tunnel_line(tunnels: &[Tunnel], units: Units, now: SystemTime) -> Line<'static> {
+    let mut spans = Vec::new();
+    for tunnel in tunnels {
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+        let mut text = format!("{} ({})", tunnel.name, tunnel.kind);
+        let peers = tunnel.peers.as_deref().unwrap_or_default();
+        if let [peer] = peers {
+            if let Some(endpoint) = peer.endpoint {
+                text.push_str(&format!(" → {endpoint}"));
             }
+        } else if peers.len() > 1 {
+            text.push_str(&format!(" → {} peers", peers.len()));
         }
+        if tunnel.peers.is_some() {
+            match tunnel
+                .last_handshake()
+                .and_then(|at| now.duration_since(at).ok())
+            {
+                Some(age) => text.push_str(&format!(
+                    " handshake {} ago",
+                    format_duration(age.as_secs())
+                )),
+                None => text.push_str(" no handshake"),
+            }
+        }
+        text.push_str(&format!(
+            " ↓{} ↑{}",
+            units.format_total(tunnel.rx_bytes),
+            units.format_total(tunnel.tx_bytes)
+        ));
+        spans.push(Span::styled(
+            text,
+            Style::default().fg(if tunnel.is_stale(now) {
+                Color::Yellow
+            } else {
+                Color::Magenta
+            }),
+        ));
+    }
+    Line::from(spans)
+}
 
-        // If no columns fit, show at least the first one
-        if visible_columns.is_empty() && start_col < total_columns {
-            visible_columns.push(start_col);
+/// Each Wi-Fi interface with its network, signal, bitrate and band, and its
+/// throughput when known; the signal in yellow or red as it weakens
+fn wireless_line(
+    links: &[WirelessLink],
+    interfaces: &[InterfaceStats],
+    units: Units,
+) -> Line<'static> {
+    let mut spans = Vec::new();
+    for link in links {
+        if !spans.is_empty() {
+            spans.push(Span::raw(" | "));
+        }
+        let mut text = format!("{} {link}", link.interface);
+        if let Some(stats) = interfaces.iter().find(|i| i.name == link.interface) {
+            text.push_str(&format!(
+                " ↑{} ↓{}",
+                units.format_rate(stats.tx_rate),
+                units.format_rate(stats.rx_rate)
+            ));
         }
+        let color = match link.quality() {
+            Some(SignalQuality::Poor) => Color::Red,
+            Some(SignalQuality::Fair) => Color::Yellow,
+            _ if !link.is_connected() => Color::Gray,
+            _ => Color::Blue,
+        };
+        spans.push(Span::styled(text, Style::default().fg(color)));
+    }
+    Line::from(spans)
+}
 
-        let remaining_width = available_width.saturating_sub(current_width);
+/// Kernel-wide TCP retransmission, reset and error rates, retransmissions
+/// in yellow when they point at loss
+fn protocol_line(stats: &ProtocolStats) -> Line<'static> {
+    let rate = |per_sec: f64| {
+        if per_sec > 0.0 {
+            Color::Yellow
+        } else {
+            Color::Gray
+        }
+    };
+    Line::from(vec![
+        Span::styled(
+            format!(
+                "Retransmits: {:.1}/s ({:.1}%)",
+                stats.retransmits_per_sec,
+                stats.retransmit_ratio * 100.0
+            ),
+            Style::default().fg(if stats.is_degraded() {
+                Color::Yellow
+            } else {
+                Color::Gray
+            }),
+        ),
+        Span::raw(" | "),
+        Span::styled(
+            format!("Resets: {:.1}/s", stats.resets_per_sec),
+            Style::default().fg(rate(stats.resets_per_sec)),
+        ),
+        Span::raw(" | "),
+        Span::styled(
+            format!("Errors: {:.1}/s", stats.errors_per_sec),
+            Style::default().fg(rate(stats.errors_per_sec)),
+        ),
+    ])
+}
 
-        // Update cache
-        app.layout_cache.available_width = chunks[1].width;
-        app.layout_cache.visible_columns = visible_columns.clone();
-        app.layout_cache.last_calculation = Instant::now();
-        app.layout_cache.last_connection_count = app.connections.len();
+/// Ephemeral port usage, yellow when the range is nearly exhausted
+fn port_gauge(ports: &EphemeralPorts) -> LineGauge<'static> {
+    let (color, note) = if ports.is_near_exhaustion() {
+        (Color::Yellow, " (nearly exhausted)")
+    } else {
+        (Color::Green, "")
+    };
+    LineGauge::default()
+        .ratio(ports.usage())
+        .label(format!(
+            "Ephemeral ports {}/{}{note} ",
+            ports.in_use(),
+            ports.capacity()
+        ))
+        .filled_style(Style::default().fg(color))
+        .unfilled_style(Style::default().fg(Color::DarkGray))
+}
+
+/// Render the self-profiling overlay toggled with D, above the footer on the right
+fn render_diagnostics(f: &mut Frame, app: &App) {
+    let mut metrics = app.metrics.clone();
+    metrics.resolver_latency = app.resolver.lookup_latency();
+    metrics.resolver_cache = app.resolver.cache_stats();
+
+    let lines: Vec<Line> = metrics
+        .rows()
+        .into_iter()
+        .map(|(name, value)| {
+            Line::from(vec![
+                Span::styled(format!("{name:<15} "), Style::default().fg(Color::Gray)),
+                Span::raw(value),
+            ])
+        })
+        .collect();
 
-        (visible_columns, remaining_width)
+    let screen = f.area();
+    let width = 84.min(screen.width);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect {
+        x: screen.right().saturating_sub(width),
+        y: screen.bottom().saturating_sub(height + 3),
+        width,
+        height,
     };
 
-    // Create header with visible columns only
-    let header_titles = [
-        "Process(ID)",
-        "Protocol",
-        "Source",
-        "Destination",
-        "Status",
-        "TX",
-        "RX",
-        "Path",
-    ];
-    let visible_header_cells: Vec<_> = visible_columns
-        .iter()
-        .map(|&col_idx| {
-            let style = if col_idx == app.sort_column {
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD)
-            } else {
-                Style::default().fg(Color::Gray)
-            };
+    let overlay = tui::widgets::Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Diagnostics (D to close)"),
+    );
+    f.render_widget(Clear, area);
+    f.render_widget(overlay, area);
+}
 
-            let arrow = if col_idx == app.sort_column {
-                if app.sort_ascending {
-                    " ↑"
-                } else {
-                    " ↓"
-                }
-            } else {
-                ""
-            };
+/// Render the bandwidth limit menu over the table, centered
+fn render_limit_menu(f: &mut Frame, menu: &LimitMenu, units: Units) {
+    let key = |key: String| Span::styled(format!("{key:>3}  "), Style::default().fg(Color::Green));
+    let mut lines = vec![Line::from(Span::styled(
+        match menu.limit {
+            Some(limit) => format!("Limited to {} each way", units.format_rate(limit)),
+            None => "Not limited".to_string(),
+        },
+        Style::default().fg(Color::Gray),
+    ))];
+    for (i, rate) in LIMIT_PRESETS.iter().enumerate() {
+        lines.push(Line::from(vec![
+            key((i + 1).to_string()),
+            Span::raw(format!("Limit to {}", units.format_rate(*rate))),
+        ]));
+    }
+    if menu.limit.is_some() {
+        lines.push(Line::from(vec![key("r".into()), Span::raw("Remove limit")]));
+    }
+    lines.push(Line::from(vec![key("Esc".into()), Span::raw("Cancel")]));
+
+    let screen = f.area();
+    let width = 48.min(screen.width);
+    let height = (lines.len() as u16 + 2).min(screen.height);
+    let area = Rect {
+        x: screen.x + (screen.width - width) / 2,
+        y: screen.y + (screen.height - height) / 2,
+        width,
+        height,
+    };
+    let overlay =
+        tui::widgets::Paragraph::new(lines).block(Block::default().borders(Borders::ALL).title(
+            format!("Limit {} ({}), experimental", menu.program, menu.pid),
+        ));
+    f.render_widget(Clear, area);
+    f.render_widget(overlay, area);
+}
 
-            let title = if col_idx < header_titles.len() {
-                header_titles[col_idx]
-            } else {
-                ""
-            };
+/// Render the detail pane opened with Enter
+fn render_process_details(
+    f: &mut Frame,
+    details: &std::result::Result<ProcessDetails, String>,
+    route: Option<String>,
+    congestion: Option<String>,
+    area: tui::layout::Rect,
+) {
+    let label = |text: &'static str| Span::styled(text, Style::default().fg(Color::Gray));
+    let mut lines = match details {
+        Ok(details) => vec![
+            Line::from(vec![
+                label("Process: "),
+                Span::raw(format!("{} ({})", details.name, details.pid)),
+            ]),
+            Line::from(vec![
+                label("Parents: "),
+                Span::raw(details.parent_chain_display()),
+            ]),
+            Line::from(vec![label("Command: "), Span::raw(details.command.clone())]),
+            Line::from(vec![label("Cwd: "), Span::raw(details.cwd_display())]),
+            Line::from(vec![
+                label("Environment: "),
+                Span::raw(details.environment_display()),
+            ]),
+            Line::from(vec![
+                label("Sandbox: "),
+                Span::raw(details.sandbox_display()),
+            ]),
+        ],
+        Err(error) => vec![Line::from(Span::styled(
+            error.clone(),
+            Style::default().fg(Color::Yellow),
+        ))],
+    };
 
-            Span::styled(format!("{}{}", title, arrow), style)
-        })
-        .collect();
+    if let Some(route) = route {
+        lines.push(Line::from(vec![label("Route: "), Span::raw(route)]));
+    }
+    if let Some(congestion) = congestion {
+        lines.push(Line::from(vec![
+            label("Congestion control: "),
+            Span::raw(congestion),
+        ]));
+    }
 
-    let visible_header = Row::new(visible_header_cells)
-        .style(Style::default().add_modifier(Modifier::REVERSED))
-        .height(1);
+    let pane = tui::widgets::Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title("Details (Enter/Esc to close)"),
+    );
+    f.render_widget(pane, area);
+}
 
-    // Create rows with visible columns only
-    let visible_rows = app.connections.iter().enumerate().map(|(i, conn)| {
-        let color = match conn.protocol.as_str() {
-            "tcp" | "tcp6" => Color::Green,
-            "udp" | "udp6" => Color::Yellow,
-            _ => Color::White,
+/// Render firewall drop/reject entries in place of the connections table
+fn render_blocked_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(
+        [
+            "Time",
+            "Action",
+            "Protocol",
+            "Source",
+            "Destination",
+            "Interface",
+            "Process(ID)",
+        ]
+        .iter()
+        .map(|&title| Span::styled(title, Style::default().fg(Color::Gray))),
+    )
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let rows = app.blocked_attempts.iter().map(|attempt| {
+        let interface = if attempt.is_outbound() {
+            format!("out {}", attempt.out_interface)
+        } else {
+            format!("in {}", attempt.in_interface)
+        };
+        let remote = if attempt.is_outbound() {
+            &attempt.destination
+        } else {
+            &attempt.source
+        };
+        let action = match app.ban_list.jail_for(remote) {
+            Some(jail) => format!("{} [banned:{}]", attempt.action, jail),
+            None => attempt.action.clone(),
         };
+        Row::new(vec![
+            Span::raw(attempt.timestamp.clone()),
+            Span::raw(action),
+            Span::raw(attempt.protocol.clone()),
+            Span::raw(attempt.source.clone()),
+            Span::raw(attempt.destination.clone()),
+            Span::raw(interface),
+            Span::raw(attempt.get_process_display()),
+        ])
+        .style(Style::default().fg(Color::Red))
+    });
 
-        let is_selected = app
-            .table_state
-            .selected()
-            .map(|sel| sel == i)
-            .unwrap_or(false);
+    let title = match &app.blocked_error {
+        Some(error) => format!("Blocked ({})", error),
+        None => format!("Blocked ({} attempts)", app.blocked_attempts.len()),
+    };
 
-        let style = if is_selected {
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(24),
+            Constraint::Length(16),
+            Constraint::Length(8),
+            Constraint::Length(22),
+            Constraint::Length(22),
+            Constraint::Length(12),
+            Constraint::Min(15),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
+
+fn render_dns_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(
+        [
+            "Process(ID)",
+            "Resolver",
+            "Transport",
+            "Queries",
+            "Per min",
+            "Last seen",
+        ]
+        .iter()
+        .map(|&title| Span::styled(title, Style::default().fg(Color::Gray))),
+    )
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let now = SystemTime::now();
+    let rows = app.dns_clients.iter().map(|client| {
+        let ago = now.duration_since(client.last_seen).unwrap_or_default();
+        Row::new(vec![
+            Span::raw(client.process.clone()),
+            Span::raw(app.resolver.resolve_host(&client.resolver)),
+            Span::raw(client.transport.to_string()),
+            Span::raw(client.queries.to_string()),
+            Span::raw(format!("{:.1}", client.queries_per_minute(now))),
+            Span::raw(format!("{}s ago", ago.as_secs())),
+        ])
+        .style(Style::default().fg(match client.transport {
+            DnsTransport::Plain => Color::Yellow,
+            DnsTransport::Tls => Color::Green,
+        }))
+    });
+
+    let title = format!(
+        "DNS ({} clients, sampled each refresh; query names need packet capture)",
+        app.dns_clients.len()
+    );
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(24),
+            Constraint::Length(28),
+            Constraint::Length(10),
+            Constraint::Length(8),
+            Constraint::Length(8),
+            Constraint::Min(10),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
+
+fn render_routes_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(
+        ["Destination", "Gateway", "Interface", "Metric"]
+            .iter()
+            .map(|&title| Span::styled(title, Style::default().fg(Color::Gray))),
+    )
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let rows = app.routes.routes.iter().map(|route| {
+        let destination = if route.is_default() {
+            "default".to_string()
+        } else {
+            format!("{}/{}", route.destination, route.prefix_len)
+        };
+        let style = if app.selected_route.as_ref() == Some(route) {
             Style::default()
-                .fg(color)
+                .fg(Color::Green)
                 .add_modifier(Modifier::BOLD)
-                .bg(Color::DarkGray)
-        } else if conn.is_active() {
-            Style::default().fg(color).add_modifier(Modifier::BOLD)
+        } else if route.is_default() {
+            Style::default().fg(Color::Cyan)
         } else {
-            Style::default().fg(color)
+            Style::default()
         };
+        Row::new(vec![
+            Span::raw(destination),
+            Span::raw(route.gateway.map_or_else(String::new, |ip| ip.to_string())),
+            Span::raw(route.interface.clone()),
+            Span::raw(route.metric.to_string()),
+        ])
+        .style(style)
+    });
 
-        let all_cells = [
-            conn.get_process_display(),
-            conn.protocol.clone(),
-            conn.local.clone(),
-            app.resolver.resolve_address(&conn.remote),
-            conn.state.clone(),
-            format_bytes(conn.tx_rate),
-            format_bytes(conn.rx_rate),
-            conn.command.clone(),
-        ];
-
-        let visible_cells: Vec<_> = visible_columns
-            .iter()
-            .enumerate()
-            .map(|(i, &col_idx)| {
-                let cell_content = if col_idx < all_cells.len() {
-                    all_cells[col_idx].clone()
-                } else {
-                    "".to_string()
-                };
+    let title = match app.routes.default_routes().next() {
+        Some(default) => format!(
+            "Routes ({}, default via {} dev {}; the selected connection's in green)",
+            app.routes.routes.len(),
+            default
+                .gateway
+                .map_or_else(|| "link".to_string(), |ip| ip.to_string()),
+            default.interface
+        ),
+        None if app.routes.is_empty() => "Routes (not available with this backend)".to_string(),
+        None => format!("Routes ({}, no default route)", app.routes.routes.len()),
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(44),
+            Constraint::Length(40),
+            Constraint::Length(16),
+            Constraint::Min(8),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
 
-                // Don't truncate last column - give it full remaining space
-                let is_last_column = i == visible_columns.len().saturating_sub(1);
-                let max_width = if is_last_column {
-                    // For last column, use remaining width or a large number
-                    remaining_width.max(100)
-                } else if col_idx < column_widths.len() {
-                    column_widths[col_idx]
-                } else {
-                    10
-                };
+fn render_events_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(
+        ["When", "Interface", "Event"]
+            .iter()
+            .map(|&title| Span::styled(title, Style::default().fg(Color::Gray))),
+    )
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let now = SystemTime::now();
+    let rows = app.link_events.iter().rev().map(|event| {
+        let ago = now.duration_since(event.at).unwrap_or_default();
+        Row::new(vec![
+            Span::raw(format!("{} ago", format_duration(ago.as_secs()))),
+            Span::raw(event.interface.clone()),
+            Span::raw(event.to_string()),
+        ])
+        .style(Style::default().fg(if event.is_loss() {
+            Color::Yellow
+        } else {
+            Color::Green
+        }))
+    });
 
-                let truncated = if !is_last_column && cell_content.len() > max_width {
-                    format!("{}...", &cell_content[..max_width.saturating_sub(3)])
-                } else {
-                    cell_content
-                };
-                Span::raw(truncated)
-            })
-            .collect();
+    let title = format!("Interface events ({}, newest first)", app.link_events.len());
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Length(16),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
 
-        Row::new(visible_cells).style(style)
+fn render_multicast_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(
+        ["Group", "Interface", "Protocol", "Users", "Processes"]
+            .iter()
+            .map(|&title| Span::styled(title, Style::default().fg(Color::Gray))),
+    )
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let rows = app.multicast.iter().map(|group| {
+        let style = if group.processes.is_empty() {
+            Style::default().fg(Color::DarkGray)
+        } else if group.is_broadcast() {
+            Style::default().fg(Color::Magenta)
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Span::raw(group.address.to_string()),
+            Span::raw(group.interface.as_deref().unwrap_or("any").to_string()),
+            Span::raw(group.service.unwrap_or("").to_string()),
+            Span::raw(if group.is_broadcast() {
+                String::new()
+            } else {
+                group.users.to_string()
+            }),
+            Span::raw(group.processes.join(", ")),
+        ])
+        .style(style)
     });
 
-    // Calculate constraints for visible columns with more stable sizing
-    let visible_constraints: Vec<_> = visible_columns
-        .iter()
-        .enumerate()
-        .map(|(i, &col_idx)| {
-            // Give the last column the remaining width
-            if i == visible_columns.len().saturating_sub(1) && remaining_width > 0 {
-                Constraint::Min(remaining_width as u16)
-            } else if col_idx < column_widths.len() {
-                // Use fixed widths for better stability
-                Constraint::Length(column_widths[col_idx] as u16)
-            } else {
-                Constraint::Length(10)
+    let title = if app.multicast.is_empty() {
+        "Multicast (not available with this backend)".to_string()
+    } else {
+        format!(
+            "Multicast ({} groups joined, broadcasts in magenta)",
+            app.multicast.iter().filter(|g| !g.is_broadcast()).count()
+        )
+    };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(40),
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Length(6),
+            Constraint::Min(20),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
+
+fn render_countries_table(f: &mut Frame, app: &mut App, area: tui::layout::Rect) {
+    let header = Row::new(COUNTRY_COLUMNS.iter().enumerate().map(|(column, &title)| {
+        let title = match app.country_sort {
+            Some((sorted, ascending)) if sorted == column => {
+                format!("{title} {}", if ascending { "▲" } else { "▼" })
             }
-        })
-        .collect();
+            _ => title.to_string(),
+        };
+        Span::styled(title, Style::default().fg(Color::Gray))
+    }))
+    .style(Style::default().add_modifier(Modifier::REVERSED))
+    .height(1);
+
+    let rows = app.countries.iter().map(|country| {
+        let style = if country.connections == 0 {
+            Style::default().fg(Color::DarkGray)
+        } else {
+            Style::default()
+        };
+        Row::new(vec![
+            Span::raw(country.country.to_string()),
+            Span::raw(country.connections.to_string()),
+            Span::raw(country.hosts.to_string()),
+            Span::raw(app.units.format_rate(country.tx_rate)),
+            Span::raw(app.units.format_rate(country.rx_rate)),
+            Span::raw(app.units.format_total(country.totals.sent)),
+            Span::raw(app.units.format_total(country.totals.received)),
+        ])
+        .style(style)
+    });
 
-    let table = if !visible_constraints.is_empty() {
-        Table::new(visible_rows, visible_constraints)
-            .header(visible_header)
-            .block(Block::default().borders(Borders::ALL).title(format!(
-                "Network Connections (scroll: ← → | col {}/{})",
-                start_col + 1,
-                total_columns
-            )))
-            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    let title = if !app.geoip {
+        "Countries (start with --geoip PATH to look up where hosts are)".to_string()
     } else {
-        // Fallback table if no columns fit
-        let empty_rows: Vec<Row> = vec![];
-        Table::new(empty_rows, [Constraint::Min(10)])
-            .header(Row::new([Span::raw("No space")]))
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .title("Network Connections"),
-            )
-            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        format!(
+            "Countries ({}, totals since start, sort with {}-{})",
+            app.countries.len(),
+            app.keymap.label(Action::Sort(0)),
+            app.keymap.label(Action::Sort(COUNTRY_COLUMNS.len() - 1))
+        )
     };
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(9),
+            Constraint::Length(13),
+            Constraint::Length(7),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Length(12),
+            Constraint::Min(12),
+        ],
+    )
+    .header(header)
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+
+    f.render_stateful_widget(table, area, &mut app.table.rows);
+}
 
-    f.render_stateful_widget(table, chunks[1], &mut app.table_state);
-
+fn render_footer(f: &mut Frame, area: tui::layout::Rect, app: &App) {
+    if let Some(command) = &app.command {
+        let line = Line::from(vec![
+            Span::styled(
+                format!(":{command}█"),
+                Style::default().add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                "  Enter:run Esc:cancel | filter TEXT, sort COLUMN [asc|desc], export FILE, interval SECS, port N, host NAME",
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        let prompt =
+            tui::widgets::Paragraph::new(line).block(Block::default().borders(Borders::ALL));
+        f.render_widget(prompt, area);
+        return;
+    }
+    let keys = &app.keymap;
     // Footer with help
-    let footer_text = vec![Line::from(vec![
-        Span::styled("Keys: ", Style::default().add_modifier(Modifier::BOLD)),
-        Span::styled("q", Style::default().fg(Color::Red)),
-        Span::raw(":quit "),
-        Span::styled("r", Style::default().fg(Color::Cyan)),
-        Span::raw(":resolver "),
-        Span::styled("R", Style::default().fg(Color::Cyan)),
-        Span::raw(":refresh "),
-        Span::styled("a", Style::default().fg(Color::Yellow)),
-        Span::raw(":auto-refresh "),
-        Span::styled("↑↓", Style::default().fg(Color::Green)),
-        Span::raw(":navigate "),
-        Span::styled("←→", Style::default().fg(Color::Blue)),
-        Span::raw(":scroll(5) "),
-        Span::styled("Shift+←→", Style::default().fg(Color::Blue)),
-        Span::raw(":jump "),
-        Span::styled("Home/End", Style::default().fg(Color::Blue)),
-        Span::raw(":jump "),
-        Span::styled("1-8", Style::default().fg(Color::Magenta)),
-        Span::raw(":sort "),
-    ])];
-
-    let footer =
-        tui::widgets::Paragraph::new(footer_text).block(Block::default().borders(Borders::ALL));
-    f.render_widget(footer, chunks[2]);
+    // Show whatever keys the [keys] config section bound
+    let entries = [
+        (keys.label(Action::Quit), Color::Red, "quit"),
+        (keys.label(Action::Resolver), Color::Cyan, "resolver"),
+        (keys.label(Action::Refresh), Color::Cyan, "refresh"),
+        (
+            keys.label(Action::AutoRefresh),
+            Color::Yellow,
+            "auto-refresh",
+        ),
+        (keys.label(Action::Blocked), Color::Yellow, "blocked"),
+        (keys.label(Action::Dns), Color::Yellow, "dns"),
+        (keys.label(Action::Routes), Color::Yellow, "routes"),
+        (keys.label(Action::Events), Color::Yellow, "events"),
+        (keys.label(Action::Multicast), Color::Yellow, "multicast"),
+        (keys.label(Action::Countries), Color::Yellow, "countries"),
+        (keys.label(Action::Fail2ban), Color::Yellow, "fail2ban"),
+        (keys.label(Action::Group), Color::Yellow, "group apps"),
+        (
+            keys.label(Action::Diagnostics),
+            Color::Yellow,
+            "diagnostics",
+        ),
+        (keys.label(Action::TopTalkers), Color::Yellow, "top talkers"),
+        (keys.label(Action::HideIdle), Color::Yellow, "hide idle"),
+        (keys.label(Action::LimitBandwidth), Color::Green, "limit"),
+        (keys.label(Action::Command), Color::Green, "command"),
+        (keys.label(Action::CopyJson), Color::Green, "copy json"),
+        (keys.label(Action::NextHost), Color::Green, "host"),
+        (keys.label(Action::Details), Color::Green, "details"),
+        (
+            keys.label(Action::Up) + &keys.label(Action::Down),
+            Color::Green,
+            "navigate",
+        ),
+        (
+            keys.label(Action::Left) + &keys.label(Action::Right),
+            Color::Blue,
+            "scroll(5)",
+        ),
+        (
+            format!(
+                "{}/{}",
+                keys.label(Action::FirstColumn),
+                keys.label(Action::LastColumn)
+            ),
+            Color::Blue,
+            "jump",
+        ),
+        (
+            format!(
+                "{}-{}",
+                keys.label(Action::Sort(0)),
+                keys.label(Action::Sort(7))
+            ),
+            Color::Magenta,
+            "sort",
+        ),
+    ];
+    let mut spans = vec![Span::styled(
+        "Keys: ",
+        Style::default().add_modifier(Modifier::BOLD),
+    )];
+    for (key, color, description) in entries {
+        spans.push(Span::styled(key, Style::default().fg(color)));
+        spans.push(Span::raw(format!(":{description} ")));
+    }
+    let footer_text = vec![Line::from(spans)];
+
+    // Split LAN/WAN totals on the border
+    let mut block = Block::default().borders(Borders::ALL);
+    if !app.scopes.is_empty() {
+        block = block.title(format!(" {} ", cli::scope_summary(&app.scopes, app.units)));
+    }
+    let footer = tui::widgets::Paragraph::new(footer_text).block(block);
+    f.render_widget(footer, area);
+}
+
+/// Forward terminal events to the main loop; runs on a blocking task so reading
+/// input never waits behind a refresh
+fn read_input(sender: mpsc::UnboundedSender<Event>) {
+    loop {
+        match event::poll(Duration::from_millis(250)) {
+            Ok(true) => match event::read() {
+                Ok(event) => {
+                    if sender.send(event).is_err() {
+                        return;
+                    }
+                }
+                Err(_) => return,
+            },
+            Ok(false) if sender.is_closed() => return,
+            Ok(false) => {}
+            Err(_) => return,
+        }
+    }
 }
 
-const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// Apply a key press; returns false when the user asked to quit
+fn handle_key(app: &mut App, key: KeyEvent) -> bool {
+    if key.kind != KeyEventKind::Press {
+        return true;
+    }
+    if app.limit_menu.is_some() {
+        app.choose_limit(&key);
+        return true;
+    }
+    if let Some(command) = &mut app.command {
+        match key.code {
+            KeyCode::Enter => {
+                let line = app.command.take().unwrap_or_default();
+                app.run_command(&line);
+            }
+            KeyCode::Esc => app.command = None,
+            KeyCode::Backspace if command.pop().is_none() => app.command = None,
+            KeyCode::Char(c) => command.push(c),
+            _ => {}
+        }
+        return true;
+    }
+    let Some(action) = app.keymap.action(&key) else {
+        return true;
+    };
+    match action {
+        Action::Quit => return false,
+        Action::Resolver => app.toggle_resolver(),
+        Action::Refresh => app.request_refresh(),
+        Action::AutoRefresh => app.auto_refresh = !app.auto_refresh,
+        Action::Blocked => app.toggle_view(View::Blocked),
+        Action::Dns => app.toggle_view(View::Dns),
+        Action::Routes => app.toggle_view(View::Routes),
+        Action::Events => app.toggle_view(View::Events),
+        Action::Multicast => app.toggle_view(View::Multicast),
+        Action::Countries => app.toggle_view(View::Countries),
+        Action::Fail2ban => app.toggle_fail2ban(),
+        Action::Group => app.toggle_grouping(),
+        Action::Diagnostics => app.show_diagnostics = !app.show_diagnostics,
+        Action::TopTalkers => app.show_top_talkers = !app.show_top_talkers,
+        Action::HideIdle => app.toggle_hide_idle(),
+        Action::LimitBandwidth => app.open_limit_menu(),
+        Action::Command => app.command = Some(String::new()),
+        Action::CopyJson => app.copy_selection_json(),
+        Action::NextHost => app.next_host(),
+        Action::Details => app.toggle_process_details(),
+        Action::CloseDetails => app.process_details = None,
+        Action::Up => app.previous_row(),
+        Action::Down => app.next_row(),
+        Action::Left => app.table.scroll_left(),
+        Action::Right => app.table.scroll_right(app.columns.len()),
+        Action::FirstColumn => app.table.horizontal_scroll = 0,
+        Action::LastColumn => app.table.horizontal_scroll = app.columns.len().saturating_sub(1),
+        Action::Sort(column) => app.toggle_sort(column),
+    }
+    true
+}
 
-fn main() -> Result<()> {
-    // Check for --version argument
-    let args: Vec<String> = env::args().collect();
-    if args.len() > 1 && args[1] == "--version" {
-        println!("nmt version {}", VERSION);
+/// Print the comparison of two recorded sessions to stdout
+fn compare_sessions(settings: &Settings, before: &str, after: &str, html: bool) -> Result<()> {
+    let recordings = settings
+        .recording
+        .as_ref()
+        .map_or(&settings.paths.export_dir, |schedule| &schedule.dir);
+    let comparison = Comparison::new(
+        Session::load(before, recordings)?,
+        Session::load(after, recordings)?,
+    );
+    let mut stdout = io::stdout().lock();
+    if html {
+        comparison.write_html(&mut stdout, settings.units())?;
+    } else {
+        comparison.write_markdown(&mut stdout, settings.units())?;
+    }
+    stdout.flush()?;
+    Ok(())
+}
+
+/// Wait for the collector's reply to a startup request, the event `is_reply`
+/// picks, exiting when it failed. Other events arriving first are kept in
+/// `early`.
+async fn await_startup_reply(
+    events: &async_channel::Receiver<CollectorEvent>,
+    early: &mut Vec<CollectorEvent>,
+    is_reply: impl Fn(&CollectorEvent) -> bool,
+) {
+    while let Ok(event) = events.recv().await {
+        if !is_reply(&event) {
+            early.push(event);
+            continue;
+        }
+        if let CollectorEvent::PrivilegedHelper(Err(e))
+        | CollectorEvent::Capture(Err(e))
+        | CollectorEvent::GeoIp(Err(e)) = event
+        {
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+        return;
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+    // Before loading the settings, so a broken config is one of the findings
+    if cli.options.doctor {
+        std::process::exit(doctor::run(&cli.options));
+    }
+    let settings = match cli.options.settings() {
+        Ok(settings) => settings,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+    // Logging to stderr would corrupt the alternate screen, so the TUI logs
+    // to a file unless it only prints a snapshot or a report
+    let headless = cli.options.once
+        || cli.options.audit.is_some()
+        || cli.statusbar
+        || cli.top_processes.is_some()
+        || cli.compare.is_some();
+    let log_file = settings
+        .log_file
+        .clone()
+        .or_else(|| (!headless).then(|| settings.paths.log_file()));
+    if let Err(e) = logging::init(
+        settings.log_level.unwrap_or(logging::DEFAULT_LEVEL),
+        log_file.as_deref(),
+        headless,
+    ) {
+        eprintln!("Warning: Could not set up logging: {}", e);
+    }
+    let keymap = match Keymap::with_overrides(&settings.keys) {
+        Ok(keymap) => keymap,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            std::process::exit(2);
+        }
+    };
+    if cli.serve {
+        let mut collector = settings.backend.open(&CacheLimits::from_env())?;
+        remote::serve(collector.as_mut(), io::stdin().lock(), io::stdout().lock())?;
         return Ok(());
     }
+    if cli.statusbar {
+        return cli::run_statusbar(&settings, REFRESH_INTERVAL);
+    }
+    if let Some(sessions) = &cli.compare {
+        return compare_sessions(&settings, &sessions[0], &sessions[1], cli.html);
+    }
+    if let Some(count) = cli.top_processes {
+        return cli::run_top_processes(&settings, count.into(), cli.plain, REFRESH_INTERVAL);
+    }
+    if cli.options.once {
+        return cli::run_once(
+            &settings,
+            cli.options.output.unwrap_or_default(),
+            REFRESH_INTERVAL,
+            false,
+        );
+    }
+    if let Some(minutes) = cli.options.audit {
+        return cli::run_audit(
+            &settings,
+            Duration::from_secs(minutes * 60),
+            cli.options.output.unwrap_or_default(),
+            REFRESH_INTERVAL,
+            false,
+        );
+    }
+
+    // Collection runs on the collector thread, off the input/render loop
+    let cache_limits = CacheLimits::from_env();
+    let (collector, collector_events) =
+        ConnectionCollector::spawn(settings.backend.clone(), cache_limits);
+    collector.request(CollectorRequest::WarmStart(settings.paths.warm_start()));
+
+    // Start the privileged helper before raw mode so pkexec can prompt on the terminal.
+    // Events coming in meanwhile are handled once the app is set up.
+    let mut early_events = Vec::new();
+    let elevated = cli.elevate;
+    if elevated {
+        collector.request(CollectorRequest::EnablePrivilegedHelper);
+        await_startup_reply(&collector_events, &mut early_events, |event| {
+            matches!(event, CollectorEvent::PrivilegedHelper(_))
+        })
+        .await;
+    }
+    if settings.capture_sni {
+        collector.request(CollectorRequest::EnableCapture);
+        await_startup_reply(&collector_events, &mut early_events, |event| {
+            matches!(event, CollectorEvent::Capture(_))
+        })
+        .await;
+    }
+    if !settings.geoip.is_empty() {
+        collector.request(CollectorRequest::EnableGeoIp(settings.geoip.clone()));
+        await_startup_reply(&collector_events, &mut early_events, |event| {
+            matches!(event, CollectorEvent::GeoIp(_))
+        })
+        .await;
+    }
+    if !settings.lan.is_empty() {
+        collector.request(CollectorRequest::AddLanRanges(settings.lan.clone()));
+    }
+
+    // Killed from outside, quit as if `q` was pressed
+    let stop_signals = signals::stop_signals()?;
+
+    // Hand the terminal back before reporting a panic, which would otherwise
+    // be lost with the alternate screen and leave the shell in raw mode
+    crash::set_hook(|report| {
+        let _ = disable_raw_mode();
+        let _ = execute!(
+            io::stdout(),
+            LeaveAlternateScreen,
+            DisableMouseCapture,
+            crossterm::cursor::Show
+        );
+        eprintln!("{report}");
+    });
 
     // Try to enable raw mode with better error handling
     match enable_raw_mode() {
@@ -634,93 +2089,64 @@ fn main() -> Result<()> {
     let backend = CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new();
-    let mut last_tick = Instant::now();
+    let mut app = App::new(collector, cache_limits, &settings, keymap);
+    if elevated {
+        app.permission_report.missing.clear();
+    }
+    for event in early_events {
+        app.handle_collector_event(event);
+    }
+
+    let (input_sender, mut input_events) = mpsc::unbounded_channel();
+    tokio::task::spawn_blocking(move || read_input(input_sender));
+
+    // Redraw when a hostname lookup finishes; one queued wakeup is enough
+    let (resolved_sender, resolved_events) = async_channel::bounded(1);
+    app.resolver.set_notifier(resolved_sender);
 
-    let mut last_input_time = Instant::now();
-    let mut needs_data_update = false;
+    let mut refresh_timer = tokio::time::interval(app.refresh_pacer.interval());
+    // Keeps the "Last: Xs ago" header current
+    let mut redraw_timer = tokio::time::interval(Duration::from_secs(1));
 
     loop {
-        // Check for user input first - this is the priority
-        let timeout = Duration::from_millis(16); // ~60 FPS
-
-        if crossterm::event::poll(timeout)? {
-            last_input_time = Instant::now();
-
-            if let Event::Key(key) = event::read()? {
-                if key.kind == KeyEventKind::Press {
-                    match key.code {
-                        KeyCode::Char('q') => break,
-                        KeyCode::Char('r') => app.toggle_resolver(),
-                        KeyCode::Char('R') => needs_data_update = true, // Mark for update, don't block
-                        KeyCode::Char('a') => app.auto_refresh = !app.auto_refresh,
-                        KeyCode::Up => app.previous_row(),
-                        KeyCode::Down => app.next_row(),
-                        KeyCode::Left => {
-                            if key.modifiers.contains(KeyModifiers::SHIFT)
-                                || key.modifiers.contains(KeyModifiers::CONTROL)
-                            {
-                                app.horizontal_scroll = app.horizontal_scroll.saturating_sub(7);
-                            // Fast scroll to start
-                            } else {
-                                app.scroll_left(); // Normal scroll moves 5 columns
-                            }
-                        }
-                        KeyCode::Right => {
-                            if key.modifiers.contains(KeyModifiers::SHIFT)
-                                || key.modifiers.contains(KeyModifiers::CONTROL)
-                            {
-                                app.horizontal_scroll = 7; // Fast scroll to end
-                            } else {
-                                app.scroll_right(); // Normal scroll moves 5 columns
-                            }
-                        }
-                        KeyCode::Char('1') => app.toggle_sort(0),
-                        KeyCode::Char('2') => app.toggle_sort(1),
-                        KeyCode::Char('3') => app.toggle_sort(2),
-                        KeyCode::Char('4') => app.toggle_sort(3),
-                        KeyCode::Char('5') => app.toggle_sort(4),
-                        KeyCode::Char('6') => app.toggle_sort(5),
-                        KeyCode::Char('7') => app.toggle_sort(6),
-                        KeyCode::Char('8') => app.toggle_sort(7),
-                        KeyCode::Home => app.horizontal_scroll = 0,
-                        KeyCode::End => app.horizontal_scroll = 7, // Last column index
-                        _ => {}
+        let render_started = Instant::now();
+        terminal.draw(|f| {
+            ui(f, &mut app);
+            if app.show_diagnostics {
+                render_diagnostics(f, &app);
+            }
+            if let Some(menu) = &app.limit_menu {
+                render_limit_menu(f, menu, app.units);
+            }
+        })?;
+        app.metrics.render.record(render_started.elapsed());
+
+        tokio::select! {
+            Some(event) = input_events.recv() => {
+                if let Event::Key(key) = event {
+                    if !handle_key(&mut app, key) {
+                        break;
                     }
                 }
             }
-        }
-
-        // Only update data when user is idle AND we need to update
-        if needs_data_update
-            || (app.auto_refresh
-                && last_input_time.elapsed() >= Duration::from_millis(500)
-                && last_tick.elapsed() >= Duration::from_secs(2))
-        {
-            app.update_connections();
-            last_tick = Instant::now();
-            needs_data_update = false;
-        }
-
-        // Skip rendering if no significant changes to improve performance
-        if app.skip_next_render && app.connections.len() > 50 {
-            app.skip_next_render = false;
-        } else {
-            // Always draw last - this ensures instant UI response
-            terminal.draw(|f| ui(f, &mut app))?;
-
-            // Track render performance
-            app.render_count += 1;
-            let now = Instant::now();
-            if now.duration_since(app.last_render_time).as_secs() >= 5 {
-                let fps = app.render_count as f64
-                    / now.duration_since(app.last_render_time).as_secs_f64();
-                if fps < 30.0 {
-                    eprintln!("Performance warning: Low FPS ({:.1}) detected", fps);
+            Ok(event) = collector_events.recv() => {
+                app.handle_collector_event(event);
+                // Follow the pacer when slow passes change the interval
+                let interval = app.refresh_pacer.interval();
+                if refresh_timer.period() != interval {
+                    refresh_timer =
+                        tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+                }
+            }
+            // Rows sorted by address follow the names as they come in
+            Ok(()) = resolved_events.recv() => app.change_rows(SortedConnections::refresh),
+            _ = refresh_timer.tick() => {
+                if app.auto_refresh {
+                    app.request_refresh();
                 }
-                app.render_count = 0;
-                app.last_render_time = now;
             }
+            _ = redraw_timer.tick() => {}
+            Ok(()) = stop_signals.recv() => break,
         }
     }
 
@@ -732,5 +2158,11 @@ fn main() -> Result<()> {
     )?;
     terminal.show_cursor()?;
 
+    app.collector.stop();
+    let resolver_cache = settings.paths.resolver_cache();
+    if let Err(e) = app.resolver.save_cache(&resolver_cache) {
+        tracing::warn!("Could not save {}: {}", resolver_cache.display(), e);
+    }
+
     Ok(())
 }
@@ -0,0 +1,71 @@
+use crate::paths::Paths;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Desktop entry starting the monitor in the background at login
+fn desktop_entry(exec: &Path) -> String {
+    format!(
+        "[Desktop Entry]\n\
+         Type=Application\n\
+         Name=Network Monitor\n\
+         Comment=Keep monitoring network connections in the background\n\
+         Exec={} --background\n\
+         Icon=network-monitor\n\
+         Terminal=false\n\
+         NoDisplay=true\n\
+         X-GNOME-Autostart-enabled=true\n",
+        quote_exec_arg(&exec.to_string_lossy())
+    )
+}
+
+/// Quote an Exec argument as the desktop entry spec requires when it holds
+/// reserved characters
+fn quote_exec_arg(arg: &str) -> String {
+    let reserved = |c: char| " \t\n\"'\\><~|&;$*?#()`".contains(c);
+    if !arg.contains(reserved) {
+        return arg.replace('%', "%%");
+    }
+    let mut quoted = String::from("\"");
+    for c in arg.chars() {
+        match c {
+            '"' | '`' | '$' | '\\' => {
+                quoted.push('\\');
+                quoted.push(c);
+            }
+            '%' => quoted.push_str("%%"),
+            _ => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub fn is_installed() -> bool {
+    Paths::autostart_file().is_some_and(|path| path.exists())
+}
+
+/// Write the autostart entry for the running executable, returning its path
+pub fn install() -> io::Result<PathBuf> {
+    let path = Paths::autostart_file()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?;
+    let exec = env::current_exe()?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(&path, desktop_entry(&exec))?;
+    Ok(path)
+}
+
+/// Delete the autostart entry, returning false if there was none
+pub fn remove() -> io::Result<bool> {
+    let Some(path) = Paths::autostart_file() else {
+        return Ok(false);
+    };
+    match fs::remove_file(&path) {
+        Ok(()) => Ok(true),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(false),
+        Err(e) => Err(e),
+    }
+}
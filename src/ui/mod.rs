@@ -1,3 +1,9 @@
+pub mod autostart;
+pub mod onboarding;
+pub mod power;
+pub mod quick_stats;
+pub mod search_provider;
+pub mod settings;
 pub mod window;
 
-pub use window::NetworkMonitorWindow;
+pub use window::{NetworkMonitorWindow, REFRESH_INTERVAL};
@@ -0,0 +1,200 @@
+use adw::prelude::*;
+use adw::{
+    ActionRow, Dialog, HeaderBar, NavigationPage, NavigationView, PreferencesGroup,
+    PreferencesPage, SwitchRow, ToolbarView,
+};
+use gio::Settings;
+use gtk4 as gtk;
+use std::env;
+use std::rc::Rc;
+
+use crate::config::Config;
+use crate::paths::Paths;
+use crate::services::privileged_helper::{HELPER_GROUP, POLKIT_RULE_PATH};
+use crate::services::{PermissionReport, PrivilegedHelper};
+
+/// Whether the setup assistant hasn't been through yet
+pub fn is_pending(settings: &Settings) -> bool {
+    !settings.boolean("setup-done")
+}
+
+/// Assistant shown on first launch: explains what needs privileges, offers
+/// to set up the helper so it starts without a password, then asks for the
+/// basic preferences. `on_granted` runs once the helper can be started.
+pub fn setup_dialog(
+    settings: &Settings,
+    report: &PermissionReport,
+    on_granted: impl Fn() + 'static,
+) -> Dialog {
+    let navigation = NavigationView::new();
+    let dialog = Dialog::builder()
+        .title("Welcome to Network Monitor")
+        .content_width(520)
+        .content_height(560)
+        .child(&navigation)
+        .build();
+
+    let defaults = defaults_page(settings, &dialog);
+    navigation.add(&permissions_page(
+        report,
+        &navigation,
+        &defaults,
+        Rc::new(on_granted),
+    ));
+
+    // Closing early counts too: everything here can be changed later
+    let settings = settings.clone();
+    dialog.connect_closed(move |_| {
+        let _ = settings.set_boolean("setup-done", true);
+        if let Some(path) = Paths::config_file() {
+            match Config::write_template(&path) {
+                Ok(true) => tracing::info!("Wrote initial config to {}", path.display()),
+                Ok(false) => {}
+                Err(e) => tracing::warn!("Could not write {}: {}", path.display(), e),
+            }
+        }
+    });
+    dialog
+}
+
+/// Page with a header bar, scrolling content and one button at the bottom
+fn page(title: &str, content: &PreferencesPage, button: &gtk::Button) -> NavigationPage {
+    button.add_css_class("pill");
+    button.add_css_class("suggested-action");
+    button.set_halign(gtk::Align::Center);
+    button.set_margin_top(12);
+    button.set_margin_bottom(12);
+
+    let toolbar = ToolbarView::new();
+    toolbar.add_top_bar(&HeaderBar::new());
+    toolbar.set_content(Some(content));
+    toolbar.add_bottom_bar(button);
+    NavigationPage::new(&toolbar, title)
+}
+
+fn permissions_page(
+    report: &PermissionReport,
+    navigation: &NavigationView,
+    next: &NavigationPage,
+    on_granted: Rc<dyn Fn()>,
+) -> NavigationPage {
+    let group = PreferencesGroup::builder().title("Permissions").build();
+    if report.is_limited() {
+        group.set_description(Some(&format!(
+            "Without root privileges some details stay hidden:\n• {}\n\n\
+             The privileged helper can fill them in. Set it up now, or \
+             authenticate from the banner in each session instead.",
+            report.missing.join("\n• ")
+        )));
+        group.add(&setup_row(
+            "Allow Without Password",
+            &format!("Install a polkit rule in {POLKIT_RULE_PATH} so you can start the helper without authenticating"),
+            "Install",
+            || {
+                let user = env::var("USER").unwrap_or_default();
+                PrivilegedHelper::install_polkit_rule(&user)
+            },
+            on_granted.clone(),
+        ));
+        group.add(&setup_row(
+            "Grant Capabilities to the Helper",
+            &format!("Set CAP_NET_ADMIN and CAP_SYS_PTRACE on network-monitor-helper and let only members of the {HELPER_GROUP} group start it, adding you from your next login. Members can see every process' connections"),
+            "Grant",
+            || {
+                let user = env::var("USER").unwrap_or_default();
+                PrivilegedHelper::grant_capabilities(&user).map(drop)
+            },
+            on_granted,
+        ));
+    } else {
+        group.set_description(Some(
+            "Network Monitor can already see the connections of every process.",
+        ));
+    }
+
+    let content = PreferencesPage::new();
+    content.add(&group);
+    let button = gtk::Button::with_label("Next");
+    let navigation = navigation.clone();
+    let next = next.clone();
+    button.connect_clicked(move |_| navigation.push(&next));
+    page("Permissions", &content, &button)
+}
+
+/// Row whose button runs `setup` as root off the main thread, prompting
+/// through pkexec, and reports how it went in the subtitle
+fn setup_row(
+    title: &str,
+    subtitle: &str,
+    label: &str,
+    setup: fn() -> crate::error::Result<()>,
+    on_granted: Rc<dyn Fn()>,
+) -> ActionRow {
+    let row = ActionRow::builder()
+        .title(title)
+        .subtitle(subtitle)
+        .subtitle_lines(0)
+        .build();
+    let button = gtk::Button::builder()
+        .label(label)
+        .valign(gtk::Align::Center)
+        .build();
+    row.add_suffix(&button);
+
+    let row_clone = row.clone();
+    button.connect_clicked(move |button| {
+        button.set_sensitive(false);
+        let button = button.clone();
+        let row = row_clone.clone();
+        let on_granted = on_granted.clone();
+        glib::spawn_future_local(async move {
+            let result = gio::spawn_blocking(move || setup().map_err(|e| e.to_string())).await;
+            match result {
+                Ok(Ok(())) => {
+                    row.set_subtitle("Done");
+                    on_granted();
+                }
+                Ok(Err(e)) => {
+                    tracing::warn!("Permission setup failed: {}", e);
+                    row.set_subtitle(&e);
+                    button.set_sensitive(true);
+                }
+                Err(_) => button.set_sensitive(true),
+            }
+        });
+    });
+    row
+}
+
+fn defaults_page(settings: &Settings, dialog: &Dialog) -> NavigationPage {
+    let resolve_row = SwitchRow::builder()
+        .title("Resolve Hostnames")
+        .subtitle("Show remote addresses as names using reverse DNS")
+        .build();
+    settings
+        .bind("resolve-hostnames", &resolve_row, "active")
+        .build();
+    let loopback_row = SwitchRow::builder()
+        .title("Hide Loopback Connections")
+        .subtitle("Leave out connections to 127.0.0.1 and ::1")
+        .build();
+    settings
+        .bind("hide-loopback", &loopback_row, "active")
+        .build();
+
+    let group = PreferencesGroup::builder()
+        .title("Defaults")
+        .description("These can be changed any time in Preferences.")
+        .build();
+    group.add(&resolve_row);
+    group.add(&loopback_row);
+
+    let content = PreferencesPage::new();
+    content.add(&group);
+    let button = gtk::Button::with_label("Start Monitoring");
+    let dialog = dialog.clone();
+    button.connect_clicked(move |_| {
+        dialog.close();
+    });
+    page("Defaults", &content, &button)
+}
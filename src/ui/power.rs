@@ -0,0 +1,49 @@
+//! Whether the system runs on battery, from UPower on the system bus, so the
+//! window can refresh less often while it does
+
+use adw::prelude::*;
+
+const UPOWER_NAME: &str = "org.freedesktop.UPower";
+const UPOWER_PATH: &str = "/org/freedesktop/UPower";
+
+/// Call `changed` with whether the system is on battery once UPower answers,
+/// then whenever that changes. Nothing is called on machines without UPower.
+pub fn watch_battery(changed: impl Fn(bool) + 'static) {
+    gio::DBusProxy::for_bus(
+        gio::BusType::System,
+        gio::DBusProxyFlags::DO_NOT_AUTO_START,
+        None,
+        UPOWER_NAME,
+        UPOWER_PATH,
+        UPOWER_NAME,
+        None::<&gio::Cancellable>,
+        move |proxy| {
+            let proxy = match proxy {
+                Ok(proxy) => proxy,
+                Err(e) => {
+                    tracing::debug!("UPower is not available: {}", e);
+                    return;
+                }
+            };
+            // Without an owner the cached properties are all missing
+            if proxy.name_owner().is_none() {
+                return;
+            }
+            changed(on_battery(&proxy));
+            // The handler keeps the proxy, and so the watch, for the life of
+            // the process
+            let watched = proxy.clone();
+            proxy.connect_local("g-properties-changed", false, move |_| {
+                changed(on_battery(&watched));
+                None
+            });
+        },
+    );
+}
+
+fn on_battery(proxy: &gio::DBusProxy) -> bool {
+    proxy
+        .cached_property("OnBattery")
+        .and_then(|value| value.get::<bool>())
+        .unwrap_or(false)
+}
@@ -0,0 +1,105 @@
+//! Current rates and totals as read-only D-Bus properties, so panel applets
+//! and status bars can show them without scraping the window. Each refresh
+//! that changes them emits org.freedesktop.DBus.Properties.PropertiesChanged.
+
+use adw::prelude::*;
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use crate::models::QuickStats;
+
+/// Where the stats are exported on the application's bus connection
+pub const OBJECT_PATH: &str = "/org/grigio/NetworkMonitor/Stats";
+
+pub const INTERFACE_NAME: &str = "org.grigio.NetworkMonitor.Stats";
+
+const INTERFACE: &str = r#"
+<node>
+  <interface name="org.grigio.NetworkMonitor.Stats">
+    <property name="TxRate" type="t" access="read"/>
+    <property name="RxRate" type="t" access="read"/>
+    <property name="Sent" type="t" access="read"/>
+    <property name="Received" type="t" access="read"/>
+    <property name="Connections" type="u" access="read"/>
+    <property name="ActiveConnections" type="u" access="read"/>
+  </interface>
+</node>
+"#;
+
+/// The exported object, updated with every snapshot
+pub struct StatsObject {
+    connection: gio::DBusConnection,
+    stats: Rc<Cell<QuickStats>>,
+}
+
+impl StatsObject {
+    /// Export on `app`'s bus connection; `None` when it has none
+    pub fn register(app: &adw::Application) -> Result<Option<Self>, glib::Error> {
+        let Some(connection) = app.dbus_connection() else {
+            return Ok(None);
+        };
+        let node = gio::DBusNodeInfo::for_xml(INTERFACE)?;
+        let Some(interface) = node.lookup_interface(INTERFACE_NAME) else {
+            return Ok(None);
+        };
+        let stats = Rc::new(Cell::new(QuickStats::default()));
+        let current = stats.clone();
+        connection
+            .register_object(OBJECT_PATH, &interface)
+            .property(move |_, _, _, _, property| {
+                property_value(&current.get(), property).unwrap_or_else(|| 0u64.to_variant())
+            })
+            .build()?;
+        Ok(Some(Self { connection, stats }))
+    }
+
+    /// Publish `stats`, signalling the properties that changed
+    pub fn update(&self, stats: QuickStats) {
+        let previous = self.stats.replace(stats);
+        let changed: HashMap<&str, glib::Variant> = PROPERTIES
+            .iter()
+            .filter_map(|&property| {
+                let value = property_value(&stats, property)?;
+                (property_value(&previous, property).as_ref() != Some(&value))
+                    .then_some((property, value))
+            })
+            .collect();
+        if changed.is_empty() {
+            return;
+        }
+        let signal = (INTERFACE_NAME, changed, Vec::<String>::new()).to_variant();
+        if let Err(e) = self.connection.emit_signal(
+            None,
+            OBJECT_PATH,
+            "org.freedesktop.DBus.Properties",
+            "PropertiesChanged",
+            Some(&signal),
+        ) {
+            tracing::debug!("Could not signal the stats: {}", e);
+        }
+    }
+}
+
+const PROPERTIES: [&str; 6] = [
+    "TxRate",
+    "RxRate",
+    "Sent",
+    "Received",
+    "Connections",
+    "ActiveConnections",
+];
+
+/// Rates are bytes per second and totals bytes, whatever the display units
+fn property_value(stats: &QuickStats, property: &str) -> Option<glib::Variant> {
+    let count = |n: usize| u32::try_from(n).unwrap_or(u32::MAX).to_variant();
+    Some(match property {
+        "TxRate" => stats.tx_rate.to_variant(),
+        "RxRate" => stats.rx_rate.to_variant(),
+        "Sent" => stats.sent.to_variant(),
+        "Received" => stats.received.to_variant(),
+        "Connections" => count(stats.connections),
+        "ActiveConnections" => count(stats.active),
+        _ => return None,
+    })
+}
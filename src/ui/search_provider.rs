@@ -0,0 +1,147 @@
+//! GNOME Shell search provider: connections matching what is typed in the
+//! overview, from the running monitor, opening the window filtered to them.
+//! The Shell finds it through
+//! data/org.grigio.NetworkMonitor.search-provider.ini.
+
+use adw::prelude::*;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use super::NetworkMonitorWindow;
+
+/// Where the provider is exported on the application's bus connection, as
+/// named in the .ini file
+pub const OBJECT_PATH: &str = "/org/grigio/NetworkMonitor/SearchProvider";
+
+const INTERFACE: &str = r#"
+<node>
+  <interface name="org.gnome.Shell.SearchProvider2">
+    <method name="GetInitialResultSet">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetSubsearchResultSet">
+      <arg type="as" name="previous_results" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="as" name="results" direction="out"/>
+    </method>
+    <method name="GetResultMetas">
+      <arg type="as" name="identifiers" direction="in"/>
+      <arg type="aa{sv}" name="metas" direction="out"/>
+    </method>
+    <method name="ActivateResult">
+      <arg type="s" name="identifier" direction="in"/>
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+    <method name="LaunchSearch">
+      <arg type="as" name="terms" direction="in"/>
+      <arg type="u" name="timestamp" direction="in"/>
+    </method>
+  </interface>
+</node>
+"#;
+
+/// Shortest search the provider answers; single letters match nearly
+/// every connection
+const MIN_QUERY: usize = 2;
+
+/// One connection found by a search, as the Shell shows it
+#[derive(Debug, Clone)]
+pub struct SearchResult {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+}
+
+/// Export the provider on `app`'s bus connection, answering from the window
+/// once there is one. Call after the application registered.
+pub fn register(
+    app: &adw::Application,
+    window: Rc<RefCell<Option<Rc<NetworkMonitorWindow>>>>,
+) -> Result<(), glib::Error> {
+    let Some(connection) = app.dbus_connection() else {
+        return Ok(());
+    };
+    let node = gio::DBusNodeInfo::for_xml(INTERFACE)?;
+    let Some(interface) = node.lookup_interface("org.gnome.Shell.SearchProvider2") else {
+        return Ok(());
+    };
+    // Results of the last searches by id, for GetResultMetas
+    let found: RefCell<HashMap<String, SearchResult>> = RefCell::new(HashMap::new());
+    let app = app.clone();
+    connection
+        .register_object(OBJECT_PATH, &interface)
+        .method_call(move |_, _, _, _, method, parameters, invocation| {
+            let monitor = window.borrow().clone();
+            match method {
+                "GetInitialResultSet" | "GetSubsearchResultSet" => {
+                    let terms = if method == "GetInitialResultSet" {
+                        parameters.child_value(0)
+                    } else {
+                        parameters.child_value(1)
+                    };
+                    let query = query(&terms.get::<Vec<String>>().unwrap_or_default());
+                    let results = match &monitor {
+                        Some(monitor) if query.chars().count() >= MIN_QUERY => {
+                            monitor.search(&query)
+                        }
+                        _ => Vec::new(),
+                    };
+                    let ids: Vec<String> = results.iter().map(|result| result.id.clone()).collect();
+                    let mut found = found.borrow_mut();
+                    found.clear();
+                    found.extend(
+                        results
+                            .into_iter()
+                            .map(|result| (result.id.clone(), result)),
+                    );
+                    invocation.return_value(Some(&(ids,).to_variant()));
+                }
+                "GetResultMetas" => {
+                    let ids = parameters.child_value(0).get::<Vec<String>>();
+                    let found = found.borrow();
+                    let metas: Vec<HashMap<String, glib::Variant>> = ids
+                        .unwrap_or_default()
+                        .iter()
+                        .filter_map(|id| found.get(id))
+                        .map(|result| {
+                            HashMap::from([
+                                ("id".to_string(), result.id.to_variant()),
+                                ("name".to_string(), result.name.to_variant()),
+                                ("description".to_string(), result.description.to_variant()),
+                                ("gicon".to_string(), "network-monitor".to_variant()),
+                            ])
+                        })
+                        .collect();
+                    invocation.return_value(Some(&(metas,).to_variant()));
+                }
+                "ActivateResult" | "LaunchSearch" => {
+                    let terms = if method == "ActivateResult" {
+                        parameters.child_value(1)
+                    } else {
+                        parameters.child_value(0)
+                    };
+                    let query = query(&terms.get::<Vec<String>>().unwrap_or_default());
+                    // A monitor started by D-Bus activation has no window yet
+                    app.activate();
+                    if let Some(monitor) = window.borrow().clone() {
+                        monitor.show_search(&query);
+                    }
+                    invocation.return_value(None);
+                }
+                _ => invocation.return_error(
+                    gio::DBusError::UnknownMethod,
+                    &format!("No method {method}"),
+                ),
+            }
+        })
+        .build()?;
+    Ok(())
+}
+
+/// The words typed in the overview as a connection filter
+fn query(terms: &[String]) -> String {
+    terms.join(" ").trim().to_lowercase()
+}
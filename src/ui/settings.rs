@@ -0,0 +1,232 @@
+use adw::prelude::*;
+use adw::{ComboRow, PreferencesDialog, PreferencesGroup, PreferencesPage, SpinRow, SwitchRow};
+use clap::ValueEnum;
+use gio::{Settings, SettingsSchemaSource};
+use gtk4 as gtk;
+use std::time::Duration;
+
+use crate::cli::{SortSpec, View};
+use crate::ui::autostart;
+use crate::utils::formatter::{NumberStyle, Units};
+
+/// Schema installed from data/org.grigio.NetworkMonitor.gschema.xml
+pub const SCHEMA_ID: &str = "org.grigio.NetworkMonitor";
+
+/// Values of the "theme" key with their labels, in preferences order
+const THEMES: [(&str, &str); 3] = [
+    ("auto", "Follow System"),
+    ("light", "Light"),
+    ("dark", "Dark"),
+];
+
+/// Values of the "units" key with their labels
+const UNITS: [(&str, &str); 2] = [("bytes", "Bytes"), ("bits", "Bits")];
+
+/// Values of the "prefixes" key with their labels
+const PREFIXES: [(&str, &str); 3] = [
+    ("jedec", "1024 (KB, MB)"),
+    ("si", "1000 (kB, MB)"),
+    ("iec", "1024 (KiB, MiB)"),
+];
+
+/// Values of the "numbers" key with their labels
+const NUMBERS: [(&str, &str); 5] = [
+    ("locale", "Follow Locale"),
+    ("plain", "1234.5"),
+    ("point", "1,234.5"),
+    ("comma", "1.234,5"),
+    ("space", "1 234,5"),
+];
+
+/// Values of the "view" key with their labels
+const VIEWS: [(&str, &str); 7] = [
+    ("connections", "Connections"),
+    ("blocked", "Blocked"),
+    ("dns", "DNS"),
+    ("routes", "Routes"),
+    ("events", "Events"),
+    ("multicast", "Multicast"),
+    ("countries", "Countries"),
+];
+
+/// Usual values of the "sort" key with their labels; dconf takes any column
+const SORTS: [(&str, &str); 6] = [
+    ("rx:desc", "Download, Busiest First"),
+    ("tx:desc", "Upload, Busiest First"),
+    ("program:asc", "Process"),
+    ("remote:asc", "Destination"),
+    ("protocol:asc", "Protocol"),
+    ("state:asc", "Status"),
+];
+
+/// Saved preferences, or None when the schema isn't installed (running from
+/// the build tree). The app then starts with defaults and saves nothing.
+pub fn load() -> Option<Settings> {
+    let installed = SettingsSchemaSource::default()
+        .and_then(|source| source.lookup(SCHEMA_ID, true))
+        .is_some();
+    if !installed {
+        tracing::warn!("GSettings schema {SCHEMA_ID} is not installed; preferences won't be saved");
+        return None;
+    }
+    Some(Settings::new(SCHEMA_ID))
+}
+
+/// Style for a "theme" value
+pub fn color_scheme(theme: &str) -> adw::ColorScheme {
+    match theme {
+        "light" => adw::ColorScheme::PreferLight,
+        "dark" => adw::ColorScheme::PreferDark,
+        _ => adw::ColorScheme::Default,
+    }
+}
+
+/// Units from the "units", "prefixes" and "numbers" keys
+pub fn units(settings: &Settings) -> Units {
+    Units::new(
+        settings.string("units").parse().unwrap_or_default(),
+        settings.string("prefixes").parse().unwrap_or_default(),
+    )
+    .with_numbers(
+        settings
+            .string("numbers")
+            .parse()
+            .unwrap_or_else(|_| NumberStyle::system()),
+    )
+}
+
+/// Page to open with from the "view" key
+pub fn view(settings: &Settings) -> View {
+    View::from_str(&settings.string("view"), true).unwrap_or_default()
+}
+
+/// Sort to open with from the "sort" key, busiest downloads first when unset
+/// or invalid
+pub fn sort(settings: &Settings) -> SortSpec {
+    settings.string("sort").parse().unwrap_or_default()
+}
+
+/// Idle threshold from the "idle-minutes" key
+pub fn idle_after(settings: &Settings) -> Duration {
+    Duration::from_secs(u64::from(settings.int("idle-minutes").unsigned_abs()) * 60)
+}
+
+/// Combo row showing the labels of `choices`, bound to the string `key`
+fn choice_row(
+    settings: &Settings,
+    key: &str,
+    title: &str,
+    choices: &'static [(&'static str, &'static str)],
+) -> ComboRow {
+    let labels: Vec<&str> = choices.iter().map(|(_, label)| *label).collect();
+    let row = ComboRow::builder()
+        .title(title)
+        .model(&gtk::StringList::new(&labels))
+        .build();
+    settings
+        .bind(key, &row, "selected")
+        .mapping(move |variant, _| {
+            let value = variant.str()?;
+            let index = choices.iter().position(|(choice, _)| *choice == value)?;
+            Some((index as u32).to_value())
+        })
+        .set_mapping(move |value, _| {
+            let index = value.get::<u32>().ok()? as usize;
+            choices.get(index).map(|(choice, _)| choice.to_variant())
+        })
+        .build();
+    row
+}
+
+/// Preferences bound to `settings`: edits are saved at once and the window
+/// picks them up through its change handlers
+pub fn preferences_dialog(settings: &Settings) -> PreferencesDialog {
+    let theme_row = choice_row(settings, "theme", "Style", &THEMES);
+
+    let appearance = PreferencesGroup::builder().title("Appearance").build();
+    appearance.add(&theme_row);
+    appearance.add(&choice_row(settings, "units", "Traffic Unit", &UNITS));
+    appearance.add(&choice_row(settings, "prefixes", "Multiples", &PREFIXES));
+    appearance.add(&choice_row(settings, "numbers", "Number Format", &NUMBERS));
+
+    let resolve_row = SwitchRow::builder()
+        .title("Resolve Hostnames")
+        .subtitle("Show remote addresses as names using reverse DNS")
+        .build();
+    settings
+        .bind("resolve-hostnames", &resolve_row, "active")
+        .build();
+
+    let loopback_row = SwitchRow::builder()
+        .title("Hide Loopback Connections")
+        .subtitle("Leave out connections to 127.0.0.1 and ::1")
+        .build();
+    settings
+        .bind("hide-loopback", &loopback_row, "active")
+        .build();
+
+    let idle_row = SpinRow::builder()
+        .title("Idle After")
+        .subtitle("Minutes without traffic before a connection is grayed out, 0 for never")
+        .adjustment(&gtk::Adjustment::new(10.0, 0.0, 1440.0, 1.0, 10.0, 0.0))
+        .build();
+    settings.bind("idle-minutes", &idle_row, "value").build();
+
+    let hide_idle_row = SwitchRow::builder()
+        .title("Hide Idle Connections")
+        .subtitle("Leave them out instead of graying them out")
+        .build();
+    settings.bind("hide-idle", &hide_idle_row, "active").build();
+
+    let refresh_row = SpinRow::builder()
+        .title("Refresh Interval")
+        .subtitle("Seconds between automatic refreshes")
+        .adjustment(&gtk::Adjustment::new(3.0, 0.5, 3600.0, 0.5, 5.0, 0.0))
+        .digits(1)
+        .build();
+    settings
+        .bind("refresh-interval", &refresh_row, "value")
+        .build();
+
+    let connections = PreferencesGroup::builder().title("Connections").build();
+    connections.add(&resolve_row);
+    connections.add(&loopback_row);
+    connections.add(&idle_row);
+    connections.add(&hide_idle_row);
+    connections.add(&refresh_row);
+
+    // Not a GSettings key: the autostart entry itself is the setting
+    let autostart_row = SwitchRow::builder()
+        .title("Run in Background at Login")
+        .subtitle("Keep monitoring without a window; opening the app shows it")
+        .active(autostart::is_installed())
+        .build();
+    autostart_row.connect_active_notify(|row| {
+        let result = if row.is_active() {
+            autostart::install().map(drop)
+        } else {
+            autostart::remove().map(drop)
+        };
+        if let Err(e) = result {
+            tracing::warn!("Could not update the autostart entry: {}", e);
+            row.set_active(autostart::is_installed());
+        }
+    });
+
+    let startup = PreferencesGroup::builder().title("Startup").build();
+    startup.add(&choice_row(settings, "view", "View", &VIEWS));
+    startup.add(&choice_row(settings, "sort", "Sort By", &SORTS));
+    startup.add(&autostart_row);
+
+    let page = PreferencesPage::builder()
+        .title("General")
+        .icon_name("preferences-system-symbolic")
+        .build();
+    page.add(&appearance);
+    page.add(&connections);
+    page.add(&startup);
+
+    let dialog = PreferencesDialog::new();
+    dialog.add(&page);
+    dialog
+}
@@ -1,53 +1,181 @@
-use adw::{prelude::*, AboutWindow, Application, ApplicationWindow, HeaderBar};
+use adw::{
+    prelude::*, AboutWindow, ActionRow, AlertDialog, Application, ApplicationWindow, Banner,
+    HeaderBar, ViewStack, ViewSwitcher,
+};
 use gio::{ActionEntry, Menu};
-use glib::{timeout_add_local, timeout_add_seconds_local};
+use glib::timeout_add_local;
 use gtk::{
-    Align, Box as GtkBox, Grid, Label, MenuButton, Orientation, PopoverMenu, ScrolledWindow,
+    Align, Box as GtkBox, Label, ListBox, MenuButton, Orientation, ScrolledWindow, SelectionMode,
 };
 use gtk4 as gtk;
-use std::cell::RefCell;
-use std::collections::HashMap;
+use std::cell::{Cell, RefCell};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
-use std::sync::{Arc, Mutex};
-use std::time::{Duration, Instant};
-
-use crate::models::{Connection, ProcessIO};
-use crate::services::{AddressResolver, NetworkService};
-use crate::utils::formatter::Formatter;
+use std::time::{Duration, Instant, SystemTime};
+
+use super::quick_stats::StatsObject;
+use super::search_provider::SearchResult;
+use super::{onboarding, power, settings};
+use crate::cli::{self, CommonArgs, Settings, SortSpec, IDLE_AFTER};
+use crate::gui::{
+    self, ConnectionDetails, ConnectionTable, PortGauge, ProtocolStatsRow, TopTalkersPanel,
+    TunnelRows, WirelessRows, WorldMap,
+};
+use crate::models::{
+    port_summary, Connection, ConnectionKey, ConnectionSnapshot, LinkEvent, QuickStats, Route,
+    ScopeTraffic, Talker, TopTalkers, TrafficTotals, COUNTRY_COLUMNS, LINK_EVENTS_KEPT,
+};
+use crate::recording::Recorder;
+use crate::services::{
+    AddressResolver, Backend, BanList, CacheLimits, CollectorEvent, CollectorRequest,
+    ConnectionCollector, Fail2banService, FirewallLogService, Metrics, PermissionReport,
+    RefreshPacer, SortedConnections,
+};
+use crate::utils::formatter::{format_duration, Formatter, Units};
+use crate::utils::fuzzy::fuzzy_score;
+
+/// Auto-refresh interval while collection keeps within its budget, where
+/// socket changes can't be watched
+pub const REFRESH_INTERVAL: Duration = Duration::from_secs(3);
+/// Auto-refresh interval where socket changes refresh sooner, though never
+/// within [`REFRESH_INTERVAL`] of the last refresh; the default of the
+/// refresh-interval setting
+const WATCHED_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+/// Processes and hosts listed in the footer's top talkers
+const TOP_TALKERS: usize = 3;
+/// Connections offered to the GNOME Shell overview for one search
+const SEARCH_RESULTS: usize = 20;
 
 /// Main application window
 pub struct NetworkMonitorWindow {
     pub window: ApplicationWindow,
-    header_grid: Grid,
-    content_grid: Grid,
+    table: Rc<ConnectionTable>,
     resolve_toggle: gtk::CheckButton,
-    header_labels: Rc<RefCell<Vec<Label>>>,
-    prev_io: Arc<Mutex<HashMap<String, ProcessIO>>>,
+    ban_toggle: gtk::CheckButton,
+    group_toggle: gtk::CheckButton,
     resolver: AddressResolver,
-    network_service: NetworkService,
+    collector: ConnectionCollector,
+    collector_events: async_channel::Receiver<CollectorEvent>,
+    latest_snapshot: Rc<RefCell<ConnectionSnapshot>>,
+    refresh_pending: Rc<Cell<bool>>,
+    refresh_pacer: Rc<RefCell<RefreshPacer>>,
+    refresh_timer: Rc<RefCell<Option<glib::SourceId>>>,
+    refresh_indicator: Label,
+    // Self-profiling overlay toggled with Ctrl+Shift+D
+    diagnostics_label: Label,
+    metrics: Rc<RefCell<Metrics>>,
     sort_column: Rc<RefCell<usize>>,
     sort_ascending: Rc<RefCell<bool>>,
-    row_widgets: Rc<RefCell<Vec<Label>>>,
-    selected_row: Rc<RefCell<Option<usize>>>,
     connection_labels: Rc<RefCell<(Label, Label, Label, Label)>>,
-    column_widths: Rc<RefCell<Vec<i32>>>,
-    active_popovers: Rc<RefCell<Vec<PopoverMenu>>>,
+    /// LAN, WAN and VPN traffic, below the totals
+    scope_label: Label,
+    top_talkers: TopTalkersPanel,
+    port_gauge: PortGauge,
+    protocol_stats: ProtocolStatsRow,
+    tunnels: TunnelRows,
+    wireless: WirelessRows,
+    view_stack: ViewStack,
+    permission_report: PermissionReport,
+    permission_banner: Banner,
+    /// Shown while the table is filtered, with a button to clear the filter
+    filter_banner: Banner,
+
+    // Blocked tab
+    firewall_service: FirewallLogService,
+    blocked_list: ListBox,
+    blocked_status: Label,
+    /// Connections of the last snapshot, filtered and sorted
+    rows: RefCell<SortedConnections>,
+
+    // DNS tab
+    dns_list: ListBox,
+
+    // Routes tab, with the route of the connection last opened in the
+    // detail pane highlighted
+    routes_list: ListBox,
+    selected_route: RefCell<Option<Route>>,
+
+    // Events tab: interface changes of the session, oldest first
+    events_list: ListBox,
+    link_events: RefCell<VecDeque<LinkEvent>>,
+
+    // Multicast tab
+    multicast_list: ListBox,
+
+    // Countries tab, ordered by the column picked in its sort menu, under a
+    // map of the hosts when GeoIP is enabled
+    countries_list: ListBox,
+    countries_sort: gtk::DropDown,
+    world_map: Rc<WorldMap>,
+    geoip: bool,
+
+    // Detail pane for the selected connection
+    details: ConnectionDetails,
+
+    // Fail2ban correlation
+    fail2ban_service: Fail2banService,
+    ban_list: Rc<RefCell<BanList>>,
 
     // Performance optimization fields
     last_update_time: Rc<RefCell<Instant>>,
     debounce_timeout: Rc<RefCell<Option<glib::SourceId>>>,
     column_width_cache: Rc<RefCell<HashMap<usize, i32>>>,
-    virtualization_enabled: Rc<RefCell<bool>>,
-    max_visible_rows: Rc<RefCell<usize>>,
+
+    // Options from the command line and config file, updated when the app
+    // is launched again with new ones
+    filter: RefCell<Option<String>>,
+    /// Config file profiles offered in the menu
+    profiles: BTreeMap<String, Settings>,
+    /// This machine, then the `[remotes]` of the config and all of them
+    /// combined, by name
+    hosts: Vec<(String, Backend)>,
+    /// Header bar menu picking the host to collect from, shown with remotes
+    host_button: MenuButton,
+    /// Whether the host column was shown for the combined table, and is
+    /// hidden again when leaving it
+    host_column_added: Cell<bool>,
+
+    // Saved preferences; None when the GSettings schema isn't installed
+    gsettings: Option<gio::Settings>,
+    hide_loopback: Rc<Cell<bool>>,
+    /// Time without activity before a row is grayed out, zero for never
+    idle_after: Cell<Duration>,
+    /// Leave idle rows out instead of graying them out
+    hide_idle: Cell<bool>,
+    /// Connections idle for `idle_after` as of the latest snapshot
+    idle: RefCell<HashSet<ConnectionKey>>,
+    units: Rc<Cell<Units>>,
+    /// Hostnames are saved here on close for the next session
+    resolver_cache: PathBuf,
+    /// Where images of the view are saved unless another folder is picked
+    export_dir: PathBuf,
+    keep_running: bool,
+    /// Scheduled recordings from the `[recording]` config section
+    recorder: RefCell<Option<Recorder>>,
+    /// Rates and totals exported on D-Bus for panel applets
+    stats_object: Option<StatsObject>,
+    /// Refresh interval while the preference is left at its default
+    default_refresh: Duration,
+    /// Whether UPower reports the system on battery
+    on_battery: Cell<bool>,
 }
 
 impl NetworkMonitorWindow {
-    pub fn new(app: &Application) -> Rc<Self> {
+    /// With `keep_running`, closing the window hides it and monitoring goes
+    /// on in the background until Quit
+    pub fn new(app: &Application, options: &Settings, keep_running: bool) -> Rc<Self> {
+        let gsettings = settings::load();
         let window = ApplicationWindow::builder()
             .application(app)
             .title("Network Monitor")
-            .default_width(800) // Set to a standard width
-            .default_height(600)
+            .default_width(gsettings.as_ref().map_or(800, |s| s.int("window-width")))
+            .default_height(gsettings.as_ref().map_or(600, |s| s.int("window-height")))
+            .maximized(
+                gsettings
+                    .as_ref()
+                    .is_some_and(|s| s.boolean("window-maximized")),
+            )
             .resizable(true)
             .build();
 
@@ -58,26 +186,60 @@ impl NetworkMonitorWindow {
 
         // Set up Adwaita style manager
         let style_manager = adw::StyleManager::default();
-        style_manager.set_color_scheme(adw::ColorScheme::Default);
-
-        // Create separate grids for sticky header and scrollable content
-        let header_grid = Grid::builder()
-            .column_spacing(0)
-            .row_spacing(0)
-            .halign(Align::Start) // Align to start
-            .hexpand(false) // Let the natural size be determined by children's width requests
+        style_manager.set_color_scheme(settings::color_scheme(
+            &gsettings
+                .as_ref()
+                .map_or_else(String::new, |s| s.string("theme").to_string()),
+        ));
+
+        // Command line options win over saved preferences for this session
+        let resolve_hostnames = options
+            .resolve_hostnames
+            .or_else(|| gsettings.as_ref().map(|s| s.boolean("resolve-hostnames")))
+            .unwrap_or(true);
+        // Left at its default, the interval depends on whether socket
+        // changes can refresh sooner
+        let default_refresh = if options.backend.watches_sockets() {
+            WATCHED_REFRESH_INTERVAL
+        } else {
+            REFRESH_INTERVAL
+        };
+        let refresh_interval = options
+            .refresh
+            .or_else(|| {
+                gsettings
+                    .as_ref()
+                    .filter(|s| s.user_value("refresh-interval").is_some())
+                    .map(|s| Duration::from_secs_f64(s.double("refresh-interval")))
+            })
+            .unwrap_or(default_refresh);
+        let saved_units = gsettings.as_ref().map(settings::units).unwrap_or_default();
+        let units = Units::new(
+            options.unit.unwrap_or(saved_units.unit),
+            options.prefixes.unwrap_or(saved_units.prefixes),
+        )
+        .with_numbers(options.numbers.unwrap_or(saved_units.numbers));
+        let idle_after = options
+            .idle_after
+            .or_else(|| gsettings.as_ref().map(settings::idle_after))
+            .unwrap_or(IDLE_AFTER);
+        let hide_idle = options
+            .hide_idle
+            .or_else(|| gsettings.as_ref().map(|s| s.boolean("hide-idle")))
+            .unwrap_or(false);
+        let resolve_toggle = gtk::CheckButton::builder()
+            .label("Resolve Hostnames")
+            .active(resolve_hostnames)
             .build();
 
-        let content_grid = Grid::builder()
-            .column_spacing(0)
-            .row_spacing(0)
-            .halign(Align::Start) // Align to start
-            .hexpand(false) // Let the natural size be determined by children's width requests
+        let ban_toggle = gtk::CheckButton::builder()
+            .label("Mark Fail2ban Bans")
+            .active(false)
             .build();
 
-        let resolve_toggle = gtk::CheckButton::builder()
-            .label("Resolve Hostnames")
-            .active(true)
+        let group_toggle = gtk::CheckButton::builder()
+            .label("Group by Application")
+            .active(false)
             .build();
 
         // Create connection labels
@@ -106,139 +268,911 @@ impl NetworkMonitorWindow {
             .build();
         received_label.add_css_class("caption");
 
+        // LAN and WAN traffic, split by scope
+        let scope_label = Label::builder().halign(Align::Start).build();
+        scope_label.add_css_class("caption");
+
+        // Shown while slow collection passes have stretched the refresh interval
+        let refresh_indicator = Label::builder().halign(Align::Start).visible(false).build();
+        refresh_indicator.add_css_class("caption");
+        refresh_indicator.add_css_class("warning");
+
+        let diagnostics_label = Label::builder()
+            .halign(Align::End)
+            .valign(Align::End)
+            .xalign(0.0)
+            .margin_end(24)
+            .margin_bottom(24)
+            .can_target(false)
+            .visible(false)
+            .build();
+        diagnostics_label.add_css_class("osd");
+        diagnostics_label.add_css_class("diagnostics-overlay");
+
+        let blocked_status = Label::builder()
+            .label("No blocked connection attempts")
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        blocked_status.add_css_class("dim-label");
+
+        let blocked_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        blocked_list.add_css_class("boxed-list");
+        blocked_list.set_placeholder(Some(&blocked_status));
+
+        let dns_status = Label::builder()
+            .label("No DNS queries seen yet")
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        dns_status.add_css_class("dim-label");
+        let dns_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        dns_list.add_css_class("boxed-list");
+        dns_list.set_placeholder(Some(&dns_status));
+
+        let routes_status = Label::builder()
+            .label("The routing table can't be read with this backend")
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        routes_status.add_css_class("dim-label");
+        let routes_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        routes_list.add_css_class("boxed-list");
+        routes_list.set_placeholder(Some(&routes_status));
+
+        let events_status = Label::builder()
+            .label("No interface changes yet")
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        events_status.add_css_class("dim-label");
+        let events_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        events_list.add_css_class("boxed-list");
+        events_list.set_placeholder(Some(&events_status));
+
+        let multicast_status = Label::builder()
+            .label("Multicast groups can't be read with this backend")
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        multicast_status.add_css_class("dim-label");
+        let multicast_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        multicast_list.add_css_class("boxed-list");
+        multicast_list.set_placeholder(Some(&multicast_status));
+
+        let countries_status = Label::builder()
+            .label(if options.geoip.is_empty() {
+                "Start with --geoip PATH to look up the country of remote hosts"
+            } else {
+                "No connections to hosts with a known country yet"
+            })
+            .wrap(true)
+            .margin_top(24)
+            .margin_bottom(24)
+            .build();
+        countries_status.add_css_class("dim-label");
+        let countries_list = ListBox::builder()
+            .selection_mode(SelectionMode::None)
+            .build();
+        countries_list.add_css_class("boxed-list");
+        countries_list.set_placeholder(Some(&countries_status));
+        // "Total" keeps the collector's order, the rest are table columns
+        let sort_names: Vec<&str> = std::iter::once("Total").chain(COUNTRY_COLUMNS).collect();
+        let countries_sort = gtk::DropDown::from_strings(&sort_names);
+        countries_sort.set_tooltip_text(Some("Sort By"));
+        countries_sort.set_halign(gtk::Align::End);
+
+        let cache_limits = CacheLimits::from_env();
+        let (collector, collector_events) =
+            ConnectionCollector::spawn(options.backend.clone(), cache_limits);
+        collector.request(CollectorRequest::WarmStart(options.paths.warm_start()));
+        if options.capture_sni {
+            collector.request(CollectorRequest::EnableCapture);
+        }
+        if !options.geoip.is_empty() {
+            collector.request(CollectorRequest::EnableGeoIp(options.geoip.clone()));
+        }
+        if !options.lan.is_empty() {
+            collector.request(CollectorRequest::AddLanRanges(options.lan.clone()));
+        }
+        let resolver = AddressResolver::new(resolve_hostnames);
+        resolver.set_host_names(options.host_names.clone());
+
+        let hosts = options.hosts();
+        let host_menu = Menu::new();
+        for (name, _) in &hosts {
+            let item = gio::MenuItem::new(Some(name), None);
+            item.set_action_and_target_value(Some("win.host"), Some(&name.to_variant()));
+            host_menu.append_item(&item);
+        }
+        let host_button = MenuButton::builder()
+            .label("local")
+            .tooltip_text("Host")
+            .menu_model(&host_menu)
+            .visible(hosts.len() > 1)
+            .build();
+        host_button.add_css_class("flat");
+        let sort = options
+            .sort
+            .or_else(|| gsettings.as_ref().map(settings::sort))
+            .unwrap_or_default();
+        resolver.set_cache_limit(cache_limits.resolver_entries);
+        let resolver_cache = options.paths.resolver_cache();
+        if let Err(e) = resolver.load_cache(&resolver_cache) {
+            tracing::warn!("Could not load {}: {}", resolver_cache.display(), e);
+        }
+
         let monitor = Rc::new(NetworkMonitorWindow {
             window,
-            header_grid,
-            content_grid,
+            table: ConnectionTable::new(),
             resolve_toggle,
-            header_labels: Rc::new(RefCell::new(Vec::new())),
-            prev_io: Arc::new(Mutex::new(HashMap::new())),
-            resolver: AddressResolver::new(true),
-            network_service: NetworkService::new(),
-            sort_column: Rc::new(RefCell::new(6)),
-            sort_ascending: Rc::new(RefCell::new(false)),
-            row_widgets: Rc::new(RefCell::new(Vec::new())),
-            selected_row: Rc::new(RefCell::new(None)),
+            ban_toggle,
+            group_toggle,
+            resolver: resolver.clone(),
+            collector,
+            collector_events,
+            latest_snapshot: Rc::new(RefCell::new(ConnectionSnapshot::default())),
+            refresh_pending: Rc::new(Cell::new(false)),
+            refresh_pacer: Rc::new(RefCell::new(RefreshPacer::new(refresh_interval))),
+            refresh_timer: Rc::new(RefCell::new(None)),
+            refresh_indicator,
+            diagnostics_label,
+            metrics: Rc::new(RefCell::new(Metrics::default())),
+            sort_column: Rc::new(RefCell::new(sort.column)),
+            sort_ascending: Rc::new(RefCell::new(sort.ascending)),
             connection_labels: Rc::new(RefCell::new((
                 total_label,
                 active_label,
                 sent_label,
                 received_label,
             ))),
-            column_widths: Rc::new(RefCell::new(vec![0; 8])), // 8 columns
-            active_popovers: Rc::new(RefCell::new(Vec::new())),
+            scope_label,
+            top_talkers: TopTalkersPanel::new(),
+            port_gauge: PortGauge::new(),
+            protocol_stats: ProtocolStatsRow::new(),
+            tunnels: TunnelRows::new(),
+            wireless: WirelessRows::new(),
+            view_stack: ViewStack::new(),
+            permission_report: PermissionReport::check(),
+            permission_banner: Banner::builder()
+                .title("Some process details are hidden without root privileges")
+                .button_label("Details")
+                .revealed(true)
+                .build(),
+            filter_banner: Banner::builder()
+                .title(filter_banner_title(
+                    options.filter.as_deref().unwrap_or_default(),
+                ))
+                .button_label("Show All")
+                .revealed(options.filter.is_some())
+                .build(),
+
+            // Blocked tab
+            firewall_service: FirewallLogService::new(),
+            blocked_list,
+            blocked_status,
+            rows: RefCell::new(
+                SortedConnections::new(sort.column, sort.ascending).with_resolver(resolver),
+            ),
+
+            // DNS tab
+            dns_list,
+
+            // Routes tab
+            routes_list,
+            selected_route: RefCell::new(None),
+
+            // Events tab
+            events_list,
+            link_events: RefCell::new(VecDeque::new()),
+
+            // Multicast tab
+            multicast_list,
+
+            // Countries tab
+            countries_list,
+            countries_sort,
+            world_map: WorldMap::new(),
+            geoip: !options.geoip.is_empty(),
+
+            // Detail pane for the selected connection
+            details: ConnectionDetails::new(),
+
+            // Fail2ban correlation
+            fail2ban_service: Fail2banService::new(false),
+            ban_list: Rc::new(RefCell::new(BanList::default())),
 
             // Performance optimization fields
             last_update_time: Rc::new(RefCell::new(Instant::now())),
             debounce_timeout: Rc::new(RefCell::new(None)),
             column_width_cache: Rc::new(RefCell::new(HashMap::new())),
-            virtualization_enabled: Rc::new(RefCell::new(true)),
-            max_visible_rows: Rc::new(RefCell::new(100)), // Show max 100 rows at once
+            filter: RefCell::new(options.filter.clone()),
+            profiles: options.profiles.clone(),
+            hosts,
+            host_button,
+            host_column_added: Cell::new(false),
+            hide_loopback: Rc::new(Cell::new(
+                gsettings
+                    .as_ref()
+                    .is_none_or(|s| s.boolean("hide-loopback")),
+            )),
+            idle_after: Cell::new(idle_after),
+            hide_idle: Cell::new(hide_idle),
+            idle: RefCell::new(HashSet::new()),
+            units: Rc::new(Cell::new(units)),
+            resolver_cache,
+            export_dir: options.paths.export_dir.clone(),
+            keep_running,
+            recorder: RefCell::new(
+                options
+                    .recording
+                    .clone()
+                    .map(|schedule| Recorder::new(schedule, SystemTime::now())),
+            ),
+            stats_object: StatsObject::register(app).unwrap_or_else(|e| {
+                tracing::warn!("Could not export the stats on D-Bus: {}", e);
+                None
+            }),
+            default_refresh,
+            on_battery: Cell::new(false),
+            gsettings,
         });
 
-        monitor.setup_grid();
+        monitor.setup_table(options.columns());
         monitor.setup_ui();
         monitor.setup_actions();
+        monitor.setup_settings();
         monitor.setup_column_sync();
         monitor.setup_close_handler();
         monitor.start_monitoring();
+        if let Some(view) = options
+            .view
+            .or_else(|| monitor.gsettings.as_ref().map(settings::view))
+        {
+            monitor.view_stack.set_visible_child_name(view.page_name());
+        }
+        if let Some(profile) = &options.profile {
+            monitor.show_selected_profile(profile);
+        }
         monitor
     }
 
-    fn setup_grid(self: &Rc<Self>) {
-        // Create all column headers as clickable labels
-        let headers = [
-            ("Process(ID)", 0),
-            ("Protocol", 1),
-            ("Source", 2),
-            ("Destination", 3),
-            ("Status", 4),
-            ("TX", 5),
-            ("RX", 6),
-            ("Path", 7),
-        ];
-
-        for (text, col) in headers {
-            let label = Label::builder().label(text).build();
-            label.add_css_class("table-header");
-
-            // Set alignment and width constraints for header labels
-            match col {
-                0 => {
-                    // Process(ID) - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-process");
-                }
-                1 => {
-                    // Protocol - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-protocol");
-                }
-                2 | 3 => {
-                    // Source/Destination - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-address");
-                }
-                4 => {
-                    // Status - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-status");
-                }
-                5 | 6 => {
-                    // TX/RX - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-rate");
-                }
-                7 => {
-                    // Path - left aligned with specific width
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
-                    label.add_css_class("column-path");
+    /// Apply the options of a later launch, e.g. `network-monitor --filter
+    /// firefox` while already running. Only options given on that command
+    /// line change; an empty --filter clears the filter.
+    pub fn apply_args(self: &Rc<Self>, args: &CommonArgs) {
+        if let Some(filter) = &args.filter {
+            self.set_filter(Some(filter.trim().to_lowercase()));
+        }
+        if let Some(sort) = args.sort {
+            self.set_sort(sort);
+        }
+        if let Some(columns) = args.columns.clone() {
+            self.set_columns(columns);
+        }
+        if let Some(refresh) = args.refresh {
+            self.set_refresh(refresh);
+        }
+        if args.no_resolve {
+            self.resolve_toggle.set_active(false);
+        }
+        if args.units.is_some() || args.prefixes.is_some() || args.numbers.is_some() {
+            let units = self.units.get();
+            self.units.set(
+                Units::new(
+                    args.units.unwrap_or(units.unit),
+                    args.prefixes.unwrap_or(units.prefixes),
+                )
+                .with_numbers(args.numbers.unwrap_or(units.numbers)),
+            );
+        }
+        if let Some(minutes) = args.idle_minutes {
+            self.set_idle_after(Duration::from_secs(minutes.saturating_mul(60)));
+        }
+        if args.hide_idle {
+            self.set_hide_idle(true);
+        }
+        if let Some(view) = args.view {
+            self.view_stack.set_visible_child_name(view.page_name());
+        }
+        self.update_connections();
+    }
+
+    /// Switch to a profile from the config file. Its settings replace the
+    /// current ones; what it leaves unset goes back to the saved preferences.
+    pub fn select_profile(self: &Rc<Self>, name: &str) {
+        let Some(profile) = self.profiles.get(name) else {
+            tracing::warn!("Unknown profile '{}'", name);
+            return;
+        };
+        self.set_filter(profile.filter.clone());
+        self.set_sort(profile.sort.unwrap_or_else(|| self.saved_sort()));
+        self.set_columns(profile.columns());
+        self.set_refresh(profile.refresh.unwrap_or_else(|| self.saved_refresh()));
+        if let Some(resolve) = profile.resolve_hostnames {
+            self.resolve_toggle.set_active(resolve);
+        }
+        let saved_units = self
+            .gsettings
+            .as_ref()
+            .map(settings::units)
+            .unwrap_or_default();
+        self.units.set(
+            Units::new(
+                profile.unit.unwrap_or(saved_units.unit),
+                profile.prefixes.unwrap_or(saved_units.prefixes),
+            )
+            .with_numbers(profile.numbers.unwrap_or(saved_units.numbers)),
+        );
+        self.set_idle_after(
+            profile
+                .idle_after
+                .unwrap_or_else(|| self.saved_idle_after()),
+        );
+        self.set_hide_idle(profile.hide_idle.unwrap_or_else(|| {
+            self.gsettings
+                .as_ref()
+                .is_some_and(|s| s.boolean("hide-idle"))
+        }));
+        if let Some(view) = profile.view {
+            self.view_stack.set_visible_child_name(view.page_name());
+        }
+        self.show_selected_profile(name);
+        self.update_connections();
+    }
+
+    /// Walk through permissions and basic preferences on first launch.
+    /// Without saved settings there's nothing to remember it by, so skip it.
+    pub fn show_first_run_setup(self: &Rc<Self>) {
+        let Some(gsettings) = &self.gsettings else {
+            return;
+        };
+        if !onboarding::is_pending(gsettings) {
+            return;
+        }
+        let monitor_weak = Rc::downgrade(self);
+        let dialog = onboarding::setup_dialog(gsettings, &self.permission_report, move || {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor
+                    .collector
+                    .request(CollectorRequest::EnablePrivilegedHelper);
+            }
+        });
+        dialog.present(Some(&self.window));
+    }
+
+    /// Show what made the previous session crash, once, with a way to copy
+    /// it into a bug report
+    pub fn show_crash_report(&self, path: &Path) {
+        let Ok(report) = std::fs::read_to_string(path) else {
+            return;
+        };
+        if let Err(e) = std::fs::remove_file(path) {
+            tracing::warn!("Could not remove {}: {}", path.display(), e);
+        }
+        let dialog = AlertDialog::new(
+            Some("Network Monitor Quit Unexpectedly"),
+            Some("The last session stopped because of an internal error. Please include these details when reporting it."),
+        );
+        let details = gtk::TextView::builder()
+            .editable(false)
+            .monospace(true)
+            .wrap_mode(gtk::WrapMode::WordChar)
+            .build();
+        details.buffer().set_text(&report);
+        let scrolled = gtk::ScrolledWindow::builder()
+            .min_content_height(240)
+            .child(&details)
+            .build();
+        dialog.set_extra_child(Some(&scrolled));
+        dialog.add_response("close", "Close");
+        dialog.add_response("copy", "Copy Details");
+        dialog.set_default_response(Some("close"));
+        let window = self.window.clone();
+        dialog.connect_response(Some("copy"), move |_, _| {
+            window.clipboard().set_text(&report);
+        });
+        dialog.present(Some(&self.window));
+    }
+
+    /// Collect from the host called `name` from now on
+    fn switch_host(&self, name: &str) {
+        let Some((_, backend)) = self.hosts.iter().find(|(host, _)| host == name) else {
+            tracing::warn!("Unknown host '{}'", name);
+            return;
+        };
+        self.collector
+            .request(CollectorRequest::SwitchBackend(backend.clone()));
+    }
+
+    /// Show the host column while connections come from several machines,
+    /// unless it is shown already
+    fn show_host_column(&self, show: bool) {
+        let mut columns = self.table.columns();
+        if show && !columns.contains(&cli::HOST_COLUMN) {
+            columns.push(cli::HOST_COLUMN);
+            self.host_column_added.set(true);
+        } else if !show && self.host_column_added.get() {
+            columns.retain(|&column| column != cli::HOST_COLUMN);
+            self.host_column_added.set(false);
+        } else {
+            return;
+        }
+        self.set_columns(columns);
+    }
+
+    /// Mark `name` as the current profile in the menu
+    fn show_selected_profile(&self, name: &str) {
+        if let Some(action) = self
+            .window
+            .lookup_action("profile")
+            .and_downcast::<gio::SimpleAction>()
+        {
+            action.set_state(&name.to_variant());
+        }
+    }
+
+    fn set_filter(&self, filter: Option<String>) {
+        let filter = filter.filter(|filter| !filter.is_empty());
+        if let Some(filter) = &filter {
+            // A port search names the processes holding the port
+            let title = match filter.strip_prefix("port=").map(str::parse) {
+                Some(Ok(port)) => port_summary(&self.latest_snapshot.borrow().connections, port),
+                _ => filter_banner_title(filter),
+            };
+            self.filter_banner.set_title(&title);
+        }
+        self.filter_banner.set_revealed(filter.is_some());
+        *self.filter.borrow_mut() = filter;
+    }
+
+    /// Ask where to save the page on screen as a PNG image, for reports
+    fn export_png(self: &Rc<Self>) {
+        let page = self
+            .view_stack
+            .visible_child_name()
+            .unwrap_or_else(|| "view".into());
+        let stamp = glib::DateTime::now_local()
+            .and_then(|now| now.format("%Y%m%d-%H%M%S"))
+            .map_or_else(|_| String::new(), |stamp| format!("-{stamp}"));
+        let dialog = gtk::FileDialog::builder()
+            .title("Export View as PNG")
+            .initial_name(format!("network-monitor-{page}{stamp}.png"))
+            .modal(true)
+            .build();
+        if std::fs::create_dir_all(&self.export_dir).is_ok() {
+            dialog.set_initial_folder(Some(&gio::File::for_path(&self.export_dir)));
+        }
+        let monitor_weak = Rc::downgrade(self);
+        dialog.save(Some(&self.window), gio::Cancellable::NONE, move |file| {
+            let (Some(monitor), Some(path)) = (
+                monitor_weak.upgrade(),
+                file.ok().and_then(|file| file.path()),
+            ) else {
+                return;
+            };
+            if let Err(e) = monitor.render_png(&path) {
+                let error = AlertDialog::new(
+                    Some("Could Not Export View"),
+                    Some(&format!("{}: {e}", path.display())),
+                );
+                error.add_response("close", "Close");
+                error.present(Some(&monitor.window));
+            }
+        });
+    }
+
+    /// Render the page on screen as it is drawn, window background included,
+    /// to a PNG file at `path`
+    fn render_png(&self, path: &Path) -> Result<(), String> {
+        let page = self.view_stack.visible_child().ok_or("No page is shown")?;
+        let bounds = page
+            .compute_bounds(&self.window)
+            .ok_or("The page is not shown")?;
+        let renderer = self.window.renderer().ok_or("The window is not shown")?;
+        let snapshot = gtk::Snapshot::new();
+        gtk::WidgetPaintable::new(Some(&self.window)).snapshot(
+            &snapshot,
+            f64::from(self.window.width()),
+            f64::from(self.window.height()),
+        );
+        let node = snapshot.to_node().ok_or("Nothing is drawn")?;
+        renderer
+            .render_texture(&node, Some(&bounds))
+            .save_to_png(path)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Ask for a local port and show only the sockets using it
+    fn show_port_dialog(self: &Rc<Self>) {
+        let dialog = AlertDialog::new(
+            Some("Who Is Using a Port?"),
+            Some("Shows the processes with a socket on a local port"),
+        );
+        let entry = gtk::Entry::builder()
+            .placeholder_text("Port, e.g. 8080")
+            .input_purpose(gtk::InputPurpose::Digits)
+            .activates_default(true)
+            .build();
+        dialog.set_extra_child(Some(&entry));
+        dialog.add_response("cancel", "Cancel");
+        dialog.add_response("find", "Find");
+        dialog.set_response_appearance("find", adw::ResponseAppearance::Suggested);
+        dialog.set_default_response(Some("find"));
+        dialog.set_response_enabled("find", false);
+
+        let dialog_weak = dialog.downgrade();
+        entry.connect_changed(move |entry| {
+            if let Some(dialog) = dialog_weak.upgrade() {
+                let valid = entry.text().trim().parse::<u16>().is_ok();
+                dialog.set_response_enabled("find", valid);
+            }
+        });
+        let monitor_weak = Rc::downgrade(self);
+        dialog.connect_response(Some("find"), move |_, _| {
+            let (Some(monitor), Ok(port)) =
+                (monitor_weak.upgrade(), entry.text().trim().parse::<u16>())
+            else {
+                return;
+            };
+            monitor.set_filter(Some(format!("port={port}")));
+            monitor.view_stack.set_visible_child_name("connections");
+            monitor.update_connections();
+        });
+        dialog.present(Some(&self.window));
+    }
+
+    /// Connections of the latest snapshot matching `query` like the filter
+    /// does, busiest first, for the GNOME Shell search provider
+    pub fn search(&self, query: &str) -> Vec<SearchResult> {
+        let snapshot = self.latest_snapshot.borrow();
+        let mut matches: Vec<&Connection> = snapshot
+            .connections
+            .iter()
+            .filter(|conn| conn.matches_filter(query))
+            .collect();
+        matches.sort_by_key(|conn| std::cmp::Reverse(conn.tx_rate + conn.rx_rate));
+        let units = self.units.get();
+        matches
+            .into_iter()
+            .take(SEARCH_RESULTS)
+            .map(|conn| {
+                let host = conn
+                    .host
+                    .as_deref()
+                    .map(|host| format!("{host}: "))
+                    .unwrap_or_default();
+                SearchResult {
+                    id: format!(
+                        "{}|{}|{}|{}|{host}",
+                        conn.protocol.as_str(),
+                        conn.local,
+                        conn.remote,
+                        conn.pid
+                    ),
+                    name: format!(
+                        "{host}{} → {}",
+                        conn.get_process_display(),
+                        self.resolver.resolve_remote(conn)
+                    ),
+                    description: format!(
+                        "{} {} · {} · ↑{} ↓{}",
+                        conn.protocol_label(),
+                        self.resolver.resolve_address(&conn.local),
+                        conn.state,
+                        units.format_rate(conn.tx_rate),
+                        units.format_rate(conn.rx_rate)
+                    ),
                 }
-                _ => {
-                    label.set_halign(Align::Start);
-                    label.set_xalign(0.0);
+            })
+            .collect()
+    }
+
+    /// Bring the window up on the connections matching `query`, for a search
+    /// picked in the GNOME Shell overview
+    pub fn show_search(self: &Rc<Self>, query: &str) {
+        self.set_filter(Some(query.to_string()));
+        self.view_stack.set_visible_child_name("connections");
+        self.update_connections();
+        self.window.present();
+    }
+
+    /// Everything the command palette offers: its title, the action and the
+    /// action's parameter
+    fn palette_commands(&self) -> Vec<(String, &'static str, Option<glib::Variant>)> {
+        let mut commands = Vec::new();
+        let pages = self.view_stack.pages();
+        for page in (0..pages.n_items()).filter_map(|i| pages.item(i)) {
+            let Some(page) = page.downcast_ref::<adw::ViewStackPage>() else {
+                continue;
+            };
+            if let (Some(name), Some(title)) = (page.name(), page.title()) {
+                commands.push((format!("Show {title}"), "win.view", Some(name.to_variant())));
+            }
+        }
+        for (title, filter) in [
+            ("Filter: Inbound Connections", "inbound"),
+            ("Filter: Outbound Connections", "outbound"),
+            ("Filter: TCP", "tcp"),
+            ("Filter: UDP", "udp"),
+            ("Filter: LAN", "scope=lan"),
+            ("Filter: WAN", "scope=wan"),
+            ("Filter: VPN", "scope=vpn"),
+            ("Clear Filter", ""),
+        ] {
+            commands.push((title.into(), "win.filter", Some(filter.to_variant())));
+        }
+        commands.push(("Who Is Using a Port…".into(), "win.find-port", None));
+        commands.push(("Refresh".into(), "win.refresh", None));
+        commands.push(("Copy Selection as JSON".into(), "win.copy-json", None));
+        commands.push(("Export View as PNG…".into(), "win.export-png", None));
+        for name in self.profiles.keys() {
+            commands.push((
+                format!("Profile: {name}"),
+                "win.profile",
+                Some(name.to_variant()),
+            ));
+        }
+        if self.hosts.len() > 1 {
+            for (name, _) in &self.hosts {
+                commands.push((format!("Host: {name}"), "win.host", Some(name.to_variant())));
+            }
+            // Narrows the combined table down to one machine
+            for (name, backend) in &self.hosts {
+                if !matches!(backend, Backend::Combined(_)) {
+                    let filter = format!("host={}", name.to_lowercase());
+                    commands.push((
+                        format!("Filter: Host {name}"),
+                        "win.filter",
+                        Some(filter.to_variant()),
+                    ));
                 }
             }
+        }
+        for (title, action) in [
+            ("Theme: Light", "app.theme-light"),
+            ("Theme: Dark", "app.theme-dark"),
+            ("Theme: Auto", "app.theme-auto"),
+        ] {
+            commands.push((title.into(), action, None));
+        }
+        if self.gsettings.is_some() {
+            commands.push(("Preferences".into(), "win.preferences", None));
+        }
+        commands.push(("Diagnostics".into(), "win.diagnostics", None));
+        commands.push(("About".into(), "win.about", None));
+        commands.push(("Quit".into(), "app.quit", None));
+        commands
+    }
 
-            // Connect click handler for sorting
-            let monitor_clone = self.clone();
-            let col_index = col;
-
-            let gesture = gtk::GestureClick::new();
-            gesture.connect_pressed(move |_, _, _, _| {
-                let mut sort_col = monitor_clone.sort_column.borrow_mut();
-                let mut sort_asc = monitor_clone.sort_ascending.borrow_mut();
-
-                if *sort_col == col_index {
-                    *sort_asc = !*sort_asc;
-                } else {
-                    *sort_col = col_index;
-                    *sort_asc = false; // First click should be descending
+    /// List every command, narrowed down and ranked by fuzzy search on what
+    /// is typed; Enter runs the best match
+    fn show_command_palette(&self) {
+        let search = gtk::SearchEntry::builder()
+            .placeholder_text("Search commands")
+            .build();
+        let list = ListBox::builder()
+            .selection_mode(SelectionMode::Browse)
+            .css_classes(["boxed-list"])
+            .build();
+        let dialog = adw::Dialog::builder()
+            .title("Commands")
+            .content_width(420)
+            .content_height(480)
+            .build();
+
+        let app = self.window.application();
+        let mut titles = Vec::new();
+        for (title, action, target) in self.palette_commands() {
+            let row = ActionRow::builder().title(&title).activatable(true).build();
+            // Shortcuts of actions without a parameter, as the menu shows them
+            let shortcut = app
+                .as_ref()
+                .filter(|_| target.is_none())
+                .and_then(|app| app.accels_for_action(action).into_iter().next())
+                .and_then(|accel| gtk::accelerator_parse(&accel))
+                .map(|(key, mods)| gtk::accelerator_get_label(key, mods));
+            if let Some(shortcut) = shortcut {
+                row.set_subtitle(&shortcut);
+            }
+            let window = self.window.clone();
+            let dialog_weak = dialog.downgrade();
+            row.connect_activated(move |_| {
+                if let Some(dialog) = dialog_weak.upgrade() {
+                    dialog.close();
                 }
+                let _ = WidgetExt::activate_action(&window, action, target.as_ref());
+            });
+            list.append(&row);
+            titles.push(title);
+        }
 
-                drop(sort_col);
-                drop(sort_asc);
+        // Rank the rows by their score for the current search
+        let scores: Rc<RefCell<HashMap<String, u32>>> = Rc::default();
+        let score_of = |scores: &HashMap<String, u32>, row: &gtk::ListBoxRow| {
+            row.downcast_ref::<ActionRow>()
+                .and_then(|row| scores.get(row.title().as_str()).copied())
+        };
+        let filter_scores = scores.clone();
+        list.set_filter_func(move |row| score_of(&filter_scores.borrow(), row).is_some());
+        let sort_scores = scores.clone();
+        list.set_sort_func(move |a, b| {
+            let scores = sort_scores.borrow();
+            // Equal scores keep the order of the list
+            score_of(&scores, b)
+                .cmp(&score_of(&scores, a))
+                .then_with(|| a.index().cmp(&b.index()))
+                .into()
+        });
+        let rank = move |list: &ListBox, query: &str| {
+            *scores.borrow_mut() = titles
+                .iter()
+                .filter_map(|title| Some((title.clone(), fuzzy_score(query, title)?)))
+                .collect();
+            list.invalidate_filter();
+            list.invalidate_sort();
+            // Rows without a match sort last, so the first one is the best
+            let best = list.row_at_index(0).filter(|row| row.is_child_visible());
+            list.select_row(best.as_ref());
+        };
+        rank(&list, "");
+        let list_weak = list.downgrade();
+        search.connect_search_changed(move |search| {
+            if let Some(list) = list_weak.upgrade() {
+                rank(&list, &search.text());
+            }
+        });
+        let list_weak = list.downgrade();
+        search.connect_activate(move |_| {
+            if let Some(row) = list_weak.upgrade().and_then(|list| list.selected_row()) {
+                row.activate();
+            }
+        });
 
-                let monitor_clone2 = monitor_clone.clone();
-                glib::idle_add_local_once(move || {
-                    monitor_clone2.update_connections();
-                    monitor_clone2.update_header_labels();
-                });
-            });
+        let scrolled = ScrolledWindow::builder()
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .vexpand(true)
+            .child(&list)
+            .build();
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_bottom(12)
+            .build();
+        content.append(&search);
+        content.append(&scrolled);
+        let toolbar = adw::ToolbarView::new();
+        toolbar.add_top_bar(&HeaderBar::new());
+        toolbar.set_content(Some(&content));
+        dialog.set_child(Some(&toolbar));
+        dialog.set_focus(Some(&search));
+        dialog.present(Some(&self.window));
+    }
+
+    fn set_sort(&self, sort: SortSpec) {
+        *self.sort_column.borrow_mut() = sort.column;
+        *self.sort_ascending.borrow_mut() = sort.ascending;
+        self.update_header_labels();
+    }
+
+    fn set_columns(&self, columns: Vec<usize>) {
+        if columns.is_empty() {
+            return;
+        }
+        self.table.set_columns(columns);
+    }
+
+    fn set_refresh(self: &Rc<Self>, refresh: Duration) {
+        self.refresh_pacer.borrow_mut().set_base(refresh);
+        self.schedule_refresh_timer();
+    }
+
+    /// Refresh interval from the preferences, or the built-in default
+    fn saved_refresh(&self) -> Duration {
+        self.gsettings
+            .as_ref()
+            .filter(|s| s.user_value("refresh-interval").is_some())
+            .map(|s| Duration::from_secs_f64(s.double("refresh-interval")))
+            .unwrap_or(self.default_refresh)
+    }
+
+    /// Startup sort from the preferences, or the built-in default
+    fn saved_sort(&self) -> SortSpec {
+        self.gsettings
+            .as_ref()
+            .map(settings::sort)
+            .unwrap_or_default()
+    }
+
+    /// Idle threshold from the preferences, or the built-in default
+    fn saved_idle_after(&self) -> Duration {
+        self.gsettings
+            .as_ref()
+            .map(settings::idle_after)
+            .unwrap_or(IDLE_AFTER)
+    }
 
-            label.add_controller(gesture);
+    /// Change the idle threshold, re-marking the rows of the latest snapshot
+    fn set_idle_after(&self, idle_after: Duration) {
+        self.idle_after.set(idle_after);
+        let idle = self.latest_snapshot.borrow().idle_connections(idle_after);
+        if idle != *self.idle.borrow() {
+            self.set_idle(idle);
+        }
+    }
 
-            self.header_grid.attach(&label, col as i32, 0, 1, 1);
+    /// Mark the connections that went idle, leaving them out of the rows
+    /// while idle ones are hidden
+    fn set_idle(&self, idle: HashSet<ConnectionKey>) {
+        if self.hide_idle.get() {
+            self.rows.borrow_mut().set_hidden(idle.clone());
+        }
+        self.table.set_idle(idle.clone());
+        *self.idle.borrow_mut() = idle;
+    }
 
-            // Store header labels for styling
-            self.header_labels.borrow_mut().push(label);
+    fn set_hide_idle(&self, hide: bool) {
+        if hide == self.hide_idle.get() {
+            return;
         }
+        self.hide_idle.set(hide);
+        self.rows.borrow_mut().set_hidden(if hide {
+            self.idle.borrow().clone()
+        } else {
+            HashSet::new()
+        });
+    }
+
+    fn setup_table(self: &Rc<Self>, columns: Vec<usize>) {
+        self.table.set_columns(columns);
+        self.table.set_resolver(Some(self.resolver.clone()));
+
+        // A header click sorts by its column, descending first
+        let monitor_weak = Rc::downgrade(self);
+        self.table.connect_header_clicked(move |column| {
+            let Some(monitor) = monitor_weak.upgrade() else {
+                return;
+            };
+            let mut sort_col = monitor.sort_column.borrow_mut();
+            let mut sort_asc = monitor.sort_ascending.borrow_mut();
+            if *sort_col == column {
+                *sort_asc = !*sort_asc;
+            } else {
+                *sort_col = column;
+                *sort_asc = false;
+            }
+            drop(sort_col);
+            drop(sort_asc);
+
+            glib::idle_add_local_once(move || {
+                monitor.update_connections();
+                monitor.update_header_labels();
+            });
+        });
+
+        let monitor_weak = Rc::downgrade(self);
+        self.table.connect_selected(move |conn| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.show_connection_details(conn);
+            }
+        });
     }
 
     fn setup_ui(self: &Rc<Self>) {
@@ -253,13 +1187,18 @@ impl NetworkMonitorWindow {
             .halign(Align::Fill) // Fill available space
             .build();
 
-        self.window.set_content(Some(&main_box));
+        // Overlay so the diagnostics panel can float above the content
+        let overlay = gtk::Overlay::builder().child(&main_box).build();
+        overlay.add_overlay(&self.diagnostics_label);
+        self.window.set_content(Some(&overlay));
 
-        // Enhanced header bar with better styling
-        let title_label = Label::builder().label("Network Monitor").build();
-        title_label.add_css_class("title");
+        // Enhanced header bar with a switcher between the connection and blocked views
+        let view_switcher = ViewSwitcher::builder()
+            .stack(&self.view_stack)
+            .policy(adw::ViewSwitcherPolicy::Wide)
+            .build();
 
-        let header_bar = HeaderBar::builder().title_widget(&title_label).build();
+        let header_bar = HeaderBar::builder().title_widget(&view_switcher).build();
         header_bar.add_css_class("flat");
 
         // Create enhanced menu button
@@ -276,8 +1215,49 @@ impl NetworkMonitorWindow {
         menu_button.set_menu_model(Some(&menu_model));
         header_bar.pack_end(&menu_button);
 
+        // Refresh now instead of waiting for the timer (win.refresh)
+        let refresh_button = gtk::Button::builder()
+            .icon_name("view-refresh-symbolic")
+            .tooltip_text("Refresh (Ctrl+R)")
+            .action_name("win.refresh")
+            .build();
+        refresh_button.add_css_class("flat");
+        header_bar.pack_end(&refresh_button);
+        header_bar.pack_start(&self.host_button);
+
         main_box.append(&header_bar);
 
+        // Explain up front what an unprivileged session can't show
+        if self.permission_report.is_limited() {
+            let monitor_clone = self.clone();
+            self.permission_banner.connect_button_clicked(move |_| {
+                let body = format!(
+                    "The following will show as \"permission denied\" or stay empty:\n\n• {}\n\nAuthenticate to resolve them through the privileged helper, or run as root.",
+                    monitor_clone.permission_report.missing.join("\n• ")
+                );
+                let dialog = AlertDialog::new(Some("Limited Permissions"), Some(&body));
+                dialog.add_response("close", "Close");
+                dialog.add_response("elevate", "Authenticate…");
+                dialog.set_response_appearance("elevate", adw::ResponseAppearance::Suggested);
+
+                let monitor_clone2 = monitor_clone.clone();
+                dialog.connect_response(Some("elevate"), move |_, _| {
+                    // pkexec waits for the polkit prompt, so this runs on the collector thread
+                    monitor_clone2
+                        .collector
+                        .request(CollectorRequest::EnablePrivilegedHelper);
+                });
+                dialog.present(Some(&monitor_clone.window));
+            });
+            main_box.append(&self.permission_banner);
+        }
+        let monitor_clone = self.clone();
+        self.filter_banner.connect_button_clicked(move |_| {
+            monitor_clone.set_filter(None);
+            monitor_clone.update_connections();
+        });
+        main_box.append(&self.filter_banner);
+
         // Create responsive table container
         let table_container = GtkBox::builder()
             .orientation(Orientation::Vertical)
@@ -290,69 +1270,77 @@ impl NetworkMonitorWindow {
             .build();
         table_container.add_css_class("table-container");
         table_container.add_css_class("responsive-table");
+        table_container.append(self.table.widget());
+        table_container.append(self.details.widget());
+
+        self.view_stack.set_vexpand(true);
+        self.view_stack.add_titled_with_icon(
+            &table_container,
+            Some("connections"),
+            "Connections",
+            "network-wired-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_blocked_page(),
+            Some("blocked"),
+            "Blocked",
+            "action-unavailable-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_dns_page(),
+            Some("dns"),
+            "DNS",
+            "network-server-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_routes_page(),
+            Some("routes"),
+            "Routes",
+            "network-workgroup-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_events_page(),
+            Some("events"),
+            "Events",
+            "document-open-recent-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_multicast_page(),
+            Some("multicast"),
+            "Multicast",
+            "network-wireless-hotspot-symbolic",
+        );
+        self.view_stack.add_titled_with_icon(
+            &self.build_countries_page(),
+            Some("countries"),
+            "Countries",
+            "mark-location-symbolic",
+        );
 
-        // Create header container with sticky behavior and overflow handling
-        let header_container = GtkBox::builder()
-            .orientation(Orientation::Vertical)
-            .hexpand(true)
-            .build();
-        header_container.add_css_class("header-container");
-        header_container.add_css_class("sticky-header");
-
-        // Wrap header grid in a container that allows horizontal overflow
-        let header_wrapper = GtkBox::builder()
-            .orientation(Orientation::Horizontal)
-            .build();
-        header_wrapper.append(&self.header_grid);
-        header_container.append(&header_wrapper);
-
-        // Create scrolled window for content with proper constraints
-        let scrolled = ScrolledWindow::builder()
-            .vexpand(true)
-            .hexpand(true)
-            .halign(Align::Fill)
-            .height_request(400)
-            .width_request(-1) // Let it be constrained by parent
-            .build();
-        scrolled.set_policy(gtk::PolicyType::Automatic, gtk::PolicyType::Automatic);
-        scrolled.add_css_class("table-container");
-        scrolled.add_css_class("responsive-table");
-        scrolled.set_child(Some(&self.content_grid));
-
-        // Proper horizontal scrolling synchronization
-        let header_grid_clone = self.header_grid.clone();
-        let scrolled_clone = scrolled.clone();
-
-        // Get horizontal adjustment for scrolling sync
-        let hadjustment = scrolled.hadjustment();
-
-        // Sync header position with content horizontal scroll
-        hadjustment.connect_value_notify(move |hadj| {
-            let scroll_value = hadj.value();
-
-            // Apply negative margin to header grid to simulate horizontal scrolling
-            // This keeps header aligned with content columns
-            header_grid_clone.set_margin_start(-(scroll_value.round() as i32));
-        });
-
-        // Handle edge cases for overscroll to maintain alignment
-        let header_grid_clone2 = self.header_grid.clone();
-        let scrolled_clone2 = scrolled.clone();
-        scrolled_clone.connect_edge_overshot(move |_, pos| {
-            if pos == gtk::PositionType::Left || pos == gtk::PositionType::Right {
-                let hadjustment = scrolled_clone2.hadjustment();
-                let scroll_value = hadjustment.value();
-                header_grid_clone2.set_margin_start(-(scroll_value.round() as i32));
+        // Only read the firewall log while the Blocked tab is on screen
+        let monitor_clone = self.clone();
+        self.view_stack.connect_visible_child_name_notify(move |_| {
+            if monitor_clone.is_blocked_view_visible() {
+                monitor_clone.update_blocked_attempts();
+            }
+            if monitor_clone.is_dns_view_visible() {
+                monitor_clone.update_dns_clients();
+            }
+            if monitor_clone.is_routes_view_visible() {
+                monitor_clone.update_routes();
+            }
+            if monitor_clone.is_events_view_visible() {
+                monitor_clone.update_link_events();
+            }
+            if monitor_clone.is_multicast_view_visible() {
+                monitor_clone.update_multicast();
+            }
+            if monitor_clone.is_countries_view_visible() {
+                monitor_clone.update_countries();
             }
         });
 
-        // Remove fixed size constraints to let grid be more flexible
-        // The CSS min-widths will handle the minimum sizing
-
-        table_container.append(&header_container);
-        table_container.append(&scrolled);
-
-        main_box.append(&table_container);
+        main_box.append(&self.view_stack);
 
         // Update header labels after UI is rendered
         let monitor_clone = self.clone();
@@ -469,10 +1457,33 @@ impl NetworkMonitorWindow {
         }
         info_group.append(&received_box);
 
+        // LAN/WAN split with icon
+        let scope_box = gtk::Box::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(6)
+            .halign(Align::Start)
+            .build();
+        scope_box.add_css_class("info-row");
+        let scope_icon = gtk::Image::from_icon_name("network-workgroup-symbolic");
+        scope_icon.add_css_class("caption");
+        scope_box.append(&scope_icon);
+        scope_box.append(&self.scope_label);
+        info_group.append(&scope_box);
+        info_group.append(self.protocol_stats.widget());
+        info_group.append(self.tunnels.widget());
+        info_group.append(self.wireless.widget());
+        info_group.append(self.port_gauge.widget());
+        info_group.append(&self.refresh_indicator);
+
         left_box.append(&info_group);
 
         control_box.append(&left_box);
 
+        // Middle column: busiest processes and hosts of the last refresh
+        self.top_talkers.widget().set_hexpand(true);
+        self.top_talkers.widget().set_margin_top(2);
+        control_box.append(self.top_talkers.widget());
+
         // Right column: Host resolution checkbox
         let right_box = gtk::Box::builder()
             .orientation(Orientation::Vertical)
@@ -493,16 +1504,49 @@ impl NetworkMonitorWindow {
         });
 
         right_box.append(&self.resolve_toggle);
+
+        self.ban_toggle
+            .set_tooltip_text(Some("Highlight addresses banned by fail2ban"));
+
+        let monitor_clone = self.clone();
+        self.ban_toggle.connect_toggled(move |button| {
+            monitor_clone
+                .fail2ban_service
+                .set_enabled(button.is_active());
+            if !button.is_active() {
+                button.set_tooltip_text(Some("Highlight addresses banned by fail2ban"));
+            }
+            let monitor_clone2 = monitor_clone.clone();
+            glib::idle_add_local_once(move || {
+                monitor_clone2.perform_debounced_update();
+            });
+        });
+
+        right_box.append(&self.ban_toggle);
+
+        self.group_toggle.set_tooltip_text(Some(
+            "Attribute connections of helper processes to their parent application",
+        ));
+
+        let monitor_clone = self.clone();
+        self.group_toggle.connect_toggled(move |_| {
+            let monitor_clone2 = monitor_clone.clone();
+            glib::idle_add_local_once(move || {
+                monitor_clone2.perform_debounced_update();
+            });
+        });
+
+        right_box.append(&self.group_toggle);
         control_box.append(&right_box);
 
         // Update status
-        self.update_status(0, 0, 0, 0);
+        self.update_status(0, 0, TrafficTotals::default(), Talker::default(), &[]);
     }
 
     fn apply_custom_css(&self) {
         let css_provider = gtk::CssProvider::new();
-        let css = include_str!("styles.css");
-        css_provider.load_from_string(css);
+        let css = format!("{}{}", gui::STYLE, include_str!("styles.css"));
+        css_provider.load_from_string(&css);
 
         // Get display with proper error handling
         if let Some(display) = gtk::gdk::Display::default() {
@@ -512,51 +1556,219 @@ impl NetworkMonitorWindow {
                 gtk::STYLE_PROVIDER_PRIORITY_APPLICATION,
             );
         } else {
-            eprintln!("Warning: Could not get default display for CSS provider");
+            tracing::warn!("Could not get default display for CSS provider");
         }
     }
 
-    fn setup_actions(&self) {
+    fn setup_actions(self: &Rc<Self>) {
         // About action for the window (win.* action)
         let action_about = ActionEntry::builder("about")
             .activate(move |window: &ApplicationWindow, _, _| {
                 NetworkMonitorWindow::show_about_dialog(window);
             })
             .build();
-        self.window.add_action_entries([action_about]);
-
-        if let Some(app) = self.window.application() {
-            // Theme actions (app.* actions)
-            let style_manager = adw::StyleManager::default();
-
-            let style_manager_clone = style_manager.clone();
-            let action_light = ActionEntry::builder("theme-light")
-                .activate(move |_, _, _| {
-                    style_manager_clone.set_color_scheme(adw::ColorScheme::PreferLight);
-                })
-                .build();
 
-            let style_manager_clone = style_manager.clone();
-            let action_dark = ActionEntry::builder("theme-dark")
-                .activate(move |_, _, _| {
-                    style_manager_clone.set_color_scheme(adw::ColorScheme::PreferDark);
-                })
-                .build();
+        // Self-profiling overlay (win.diagnostics)
+        let monitor_clone = self.clone();
+        let action_diagnostics = ActionEntry::builder("diagnostics")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                let label = &monitor_clone.diagnostics_label;
+                label.set_visible(!label.is_visible());
+                monitor_clone.update_diagnostics();
+            })
+            .build();
+        // Preferences dialog (win.preferences), only with saved settings
+        let monitor_clone = self.clone();
+        let action_preferences = ActionEntry::builder("preferences")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                if let Some(gsettings) = &monitor_clone.gsettings {
+                    settings::preferences_dialog(gsettings).present(Some(&monitor_clone.window));
+                }
+            })
+            .build();
+        // Profile switching (win.profile), its state naming the current one
+        let monitor_weak = Rc::downgrade(self);
+        let action_profile = ActionEntry::builder("profile")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .state(String::new().to_variant())
+            .activate(move |_: &ApplicationWindow, _, parameter| {
+                let name = parameter.and_then(|parameter| parameter.str());
+                if let (Some(monitor), Some(name)) = (monitor_weak.upgrade(), name) {
+                    monitor.select_profile(name);
+                }
+            })
+            .build();
+        // Host switching (win.host), its state naming the one collected from
+        let monitor_weak = Rc::downgrade(self);
+        let action_host = ActionEntry::builder("host")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .state("local".to_variant())
+            .activate(move |_: &ApplicationWindow, _, parameter| {
+                let name = parameter.and_then(|parameter| parameter.str());
+                if let (Some(monitor), Some(name)) = (monitor_weak.upgrade(), name) {
+                    monitor.switch_host(name);
+                }
+            })
+            .build();
+        // Bandwidth limits from the details pane (win.limit-bandwidth), taking
+        // the PID and a rate in bytes per second, 0 lifting the limit
+        let monitor_weak = Rc::downgrade(self);
+        let action_limit = ActionEntry::builder("limit-bandwidth")
+            .parameter_type(Some(glib::VariantTy::new("(st)").unwrap()))
+            .activate(move |_: &ApplicationWindow, _, parameter| {
+                let target = parameter.and_then(|parameter| parameter.get::<(String, u64)>());
+                if let (Some(monitor), Some((pid, rate))) = (monitor_weak.upgrade(), target) {
+                    monitor.collector.request(CollectorRequest::LimitBandwidth {
+                        pid,
+                        bytes_per_sec: (rate > 0).then_some(rate),
+                    });
+                }
+            })
+            .build();
+        // Manual refresh (win.refresh); the timer restarts so the next
+        // automatic refresh comes a full interval later
+        let monitor_weak = Rc::downgrade(self);
+        let action_refresh = ActionEntry::builder("refresh")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    monitor.request_connections();
+                    monitor.schedule_refresh_timer();
+                }
+            })
+            .build();
+        // Page and filter switching for the command palette (win.view,
+        // win.filter), an empty filter clearing it
+        let monitor_weak = Rc::downgrade(self);
+        let action_view = ActionEntry::builder("view")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |_: &ApplicationWindow, _, parameter| {
+                let name = parameter.and_then(|parameter| parameter.str());
+                if let (Some(monitor), Some(name)) = (monitor_weak.upgrade(), name) {
+                    monitor.view_stack.set_visible_child_name(name);
+                }
+            })
+            .build();
+        let monitor_weak = Rc::downgrade(self);
+        let action_filter = ActionEntry::builder("filter")
+            .parameter_type(Some(glib::VariantTy::STRING))
+            .activate(move |_: &ApplicationWindow, _, parameter| {
+                let filter = parameter.and_then(|parameter| parameter.str());
+                if let (Some(monitor), Some(filter)) = (monitor_weak.upgrade(), filter) {
+                    monitor.set_filter(Some(filter.to_string()));
+                    monitor.view_stack.set_visible_child_name("connections");
+                    monitor.update_connections();
+                }
+            })
+            .build();
+        // Selected connection to the clipboard (win.copy-json)
+        let monitor_weak = Rc::downgrade(self);
+        let action_copy_json = ActionEntry::builder("copy-json")
+            .activate(move |window: &ApplicationWindow, _, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    if let Some(json) = monitor.selection_json() {
+                        window.clipboard().set_text(&json);
+                    }
+                }
+            })
+            .build();
+        // Page on screen to an image (win.export-png)
+        let monitor_weak = Rc::downgrade(self);
+        let action_export_png = ActionEntry::builder("export-png")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    monitor.export_png();
+                }
+            })
+            .build();
+        // Command palette (win.command-palette)
+        let monitor_weak = Rc::downgrade(self);
+        let action_palette = ActionEntry::builder("command-palette")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    monitor.show_command_palette();
+                }
+            })
+            .build();
+        // Port search (win.find-port)
+        let monitor_weak = Rc::downgrade(self);
+        let action_find_port = ActionEntry::builder("find-port")
+            .activate(move |_: &ApplicationWindow, _, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    monitor.show_port_dialog();
+                }
+            })
+            .build();
+        self.window.add_action_entries([
+            action_about,
+            action_diagnostics,
+            action_preferences,
+            action_profile,
+            action_host,
+            action_limit,
+            action_refresh,
+            action_find_port,
+            action_view,
+            action_filter,
+            action_palette,
+            action_copy_json,
+            action_export_png,
+        ]);
+        if self.gsettings.is_none() {
+            if let Some(action) = self
+                .window
+                .lookup_action("preferences")
+                .and_downcast::<gio::SimpleAction>()
+            {
+                action.set_enabled(false);
+            }
+        }
 
-            let style_manager_clone = style_manager.clone();
-            let action_auto = ActionEntry::builder("theme-auto")
-                .activate(move |_, _, _| {
-                    style_manager_clone.set_color_scheme(adw::ColorScheme::Default);
+        if let Some(app) = self.window.application() {
+            // Theme actions (app.* actions), saved when settings are available
+            let theme_actions = [
+                ("theme-light", "light"),
+                ("theme-dark", "dark"),
+                ("theme-auto", "auto"),
+            ]
+            .map(|(name, theme)| {
+                let gsettings = self.gsettings.clone();
+                ActionEntry::builder(name)
+                    .activate(move |_, _, _| match &gsettings {
+                        Some(gsettings) => {
+                            let _ = gsettings.set_string("theme", theme);
+                        }
+                        None => adw::StyleManager::default()
+                            .set_color_scheme(settings::color_scheme(theme)),
+                    })
+                    .build()
+            });
+            app.add_action_entries(theme_actions);
+
+            // Quit (app.quit) also stops background monitoring
+            let monitor_weak = Rc::downgrade(self);
+            let action_quit = ActionEntry::builder("quit")
+                .activate(move |app: &gtk::Application, _, _| {
+                    if let Some(monitor) = monitor_weak.upgrade() {
+                        monitor.save_state();
+                    }
+                    app.quit();
                 })
                 .build();
-
-            app.add_action_entries([action_light, action_dark, action_auto]);
+            app.add_action_entries([action_quit]);
 
             // Set keyboard accelerators
             app.set_accels_for_action("win.about", &["F1"]);
+            app.set_accels_for_action("win.diagnostics", &["<Ctrl><Shift>D"]);
+            app.set_accels_for_action("win.preferences", &["<Ctrl>comma"]);
+            app.set_accels_for_action("win.refresh", &["<Ctrl>R", "F5"]);
+            app.set_accels_for_action("win.find-port", &["<Ctrl>P"]);
+            app.set_accels_for_action("win.command-palette", &["<Ctrl><Shift>P"]);
+            app.set_accels_for_action("win.copy-json", &["<Ctrl><Shift>C"]);
+            app.set_accels_for_action("win.export-png", &["<Ctrl><Shift>S"]);
             app.set_accels_for_action("app.theme-light", &["<Ctrl>L"]);
             app.set_accels_for_action("app.theme-dark", &["<Ctrl>D"]);
             app.set_accels_for_action("app.theme-auto", &["<Ctrl>M"]);
+            app.set_accels_for_action("app.quit", &["<Ctrl>Q"]);
         }
     }
 
@@ -571,527 +1783,740 @@ impl NetworkMonitorWindow {
 
         menu.append_section(Some("Theme"), &theme_section);
 
+        if !self.profiles.is_empty() {
+            let profile_section = Menu::new();
+            for name in self.profiles.keys() {
+                let item = gio::MenuItem::new(Some(name), None);
+                item.set_action_and_target_value(Some("win.profile"), Some(&name.to_variant()));
+                profile_section.append_item(&item);
+            }
+            menu.append_section(Some("Profile"), &profile_section);
+        }
+
+        let preferences_section = Menu::new();
+        preferences_section.append(Some("Commands…"), Some("win.command-palette"));
+        preferences_section.append(Some("Who Is Using a Port…"), Some("win.find-port"));
+        preferences_section.append(Some("Export View as PNG…"), Some("win.export-png"));
+        preferences_section.append(Some("Preferences"), Some("win.preferences"));
+        menu.append_section(None, &preferences_section);
+
         // About section
         let about_section = Menu::new();
+        about_section.append(Some("Diagnostics"), Some("win.diagnostics"));
         about_section.append(Some("About"), Some("win.about"));
 
         menu.append_section(Some("Help"), &about_section);
+
+        let quit_section = Menu::new();
+        quit_section.append(Some("Quit"), Some("app.quit"));
+        menu.append_section(None, &quit_section);
         menu
     }
 
-    pub fn update_connections(self: &Rc<Self>) {
-        // Clean up any active popovers before updating widgets
-        {
-            let mut popovers = self.active_popovers.borrow_mut();
-            for popover in popovers.drain(..) {
-                popover.unparent();
-            }
+    /// Ask the collector thread for a fresh snapshot, unless one is already on its way
+    fn request_connections(&self) {
+        if self.refresh_pending.replace(true) {
+            return;
         }
+        self.collector.request(CollectorRequest::Refresh {
+            group_by_application: self.group_toggle.is_active(),
+        });
+    }
 
-        // Get mutable access to row widgets and clear selection styling
-        {
-            let row_widgets = self.row_widgets.borrow_mut();
-            for widget in row_widgets.iter() {
-                widget.remove_css_class("row-selected");
+    /// Handle results coming back from the collector thread
+    fn handle_collector_event(self: &Rc<Self>, event: CollectorEvent) {
+        match event {
+            CollectorEvent::Snapshot(result) => {
+                self.refresh_pending.set(false);
+                match result {
+                    Ok(snapshot) => {
+                        let paced = self
+                            .refresh_pacer
+                            .borrow_mut()
+                            .record(snapshot.collection_time);
+                        if paced {
+                            self.schedule_refresh_timer();
+                            self.update_refresh_indicator();
+                        }
+                        {
+                            let mut metrics = self.metrics.borrow_mut();
+                            metrics.collection.record(snapshot.collection_time);
+                            metrics.process_cache = snapshot.process_cache;
+                        }
+
+                        // Rows only need touching when something was added,
+                        // removed or changed since the last refresh, or went idle
+                        let idle = snapshot.idle_connections(self.idle_after.get());
+                        let idle_changed = idle != *self.idle.borrow();
+                        if idle_changed {
+                            self.set_idle(idle);
+                        }
+                        let rows_changed = !snapshot.diff.is_empty() || idle_changed;
+                        // The selection stays on a connection going to TIME_WAIT
+                        self.table.follow_selected(&snapshot.diff);
+                        *self.latest_snapshot.borrow_mut() = *snapshot;
+                        self.record_scheduled();
+                        if let Some(stats_object) = &self.stats_object {
+                            stats_object.update(QuickStats::of(&self.latest_snapshot.borrow()));
+                        }
+                        // A hidden window catches up when it is shown again
+                        if rows_changed && self.window.is_visible() {
+                            self.update_connections();
+                        }
+                        self.update_header_labels();
+                        self.update_diagnostics();
+                        self.host_button.remove_css_class("error");
+                        self.host_button.set_tooltip_text(Some("Host"));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Collection failed: {}", e);
+                        // Other machines fail for reasons worth seeing, like
+                        // ssh refusing to log in
+                        self.host_button.add_css_class("error");
+                        self.host_button.set_tooltip_text(Some(&e));
+                    }
+                }
+            }
+            CollectorEvent::BackendSwitched { backend, result } => {
+                let name = self
+                    .hosts
+                    .iter()
+                    .find(|(_, host)| *host == backend)
+                    .map_or_else(|| backend.to_string(), |(name, _)| name.clone());
+                if let Err(e) = result {
+                    let error = AlertDialog::new(
+                        Some("Could Not Switch Host"),
+                        Some(&format!("{name}: {e}")),
+                    );
+                    error.add_response("close", "Close");
+                    error.present(Some(&self.window));
+                }
+                if let Some(action) = self
+                    .window
+                    .lookup_action("host")
+                    .and_downcast::<gio::SimpleAction>()
+                {
+                    action.set_state(&name.to_variant());
+                }
+                self.host_button.set_label(&name);
+                self.show_host_column(matches!(backend, Backend::Combined(_)));
+                // Rows of the previous machine go until the first pass here
+                *self.latest_snapshot.borrow_mut() = ConnectionSnapshot::default();
+                self.rows.borrow_mut().clear();
+                self.set_idle(HashSet::new());
+                self.details.hide();
+                self.table.clear_selection();
+                self.update_connections();
+                self.update_header_labels();
+                self.request_connections();
+            }
+            CollectorEvent::PrivilegedHelper(Ok(())) => {
+                self.permission_banner.set_revealed(false);
+                self.request_connections();
+            }
+            CollectorEvent::PrivilegedHelper(Err(e)) => {
+                let error = AlertDialog::new(Some("Could Not Start Privileged Helper"), Some(&e));
+                error.add_response("close", "Close");
+                error.present(Some(&self.window));
+            }
+            CollectorEvent::Capture(Ok(())) | CollectorEvent::GeoIp(Ok(())) => {}
+            CollectorEvent::Link(event) => self.record_link_event(event),
+            // New and closed connections show up without waiting for the
+            // timer, no more often than it used to poll, unless slowed down
+            CollectorEvent::SocketsChanged => {
+                let since = SystemTime::now()
+                    .duration_since(self.latest_snapshot.borrow().taken_at)
+                    .unwrap_or_default();
+                if self.window.is_visible()
+                    && !self.refresh_pacer.borrow().is_backed_off()
+                    && !self.refresh_pacer.borrow().is_slowed()
+                    && since >= REFRESH_INTERVAL
+                {
+                    self.request_connections();
+                    self.schedule_refresh_timer();
+                }
+            }
+            CollectorEvent::BandwidthLimit { result: Ok(()), .. } => {
+                // Its bandwidth row is stale now
+                self.details.hide();
+                self.request_connections();
+            }
+            CollectorEvent::BandwidthLimit {
+                pid,
+                result: Err(e),
+            } => {
+                let error = AlertDialog::new(
+                    Some("Could Not Change Bandwidth Limit"),
+                    Some(&format!("Process {pid}: {e}")),
+                );
+                error.add_response("close", "Close");
+                error.present(Some(&self.window));
+            }
+            CollectorEvent::Capture(Err(e)) => {
+                let error = AlertDialog::new(Some("Could Not Capture Server Names"), Some(&e));
+                error.add_response("close", "Close");
+                error.present(Some(&self.window));
+            }
+            CollectorEvent::GeoIp(Err(e)) => {
+                let error = AlertDialog::new(Some("Could Not Load GeoIP Database"), Some(&e));
+                error.add_response("close", "Close");
+                error.present(Some(&self.window));
             }
         }
+    }
 
-        // Clear selection state
+    /// Render the latest snapshot into the table
+    pub fn update_connections(self: &Rc<Self>) {
+        let started = Instant::now();
+
+        // Latest snapshot from the collector thread
+        let snapshot = self.latest_snapshot.borrow();
+        self.port_gauge.update(snapshot.ephemeral_ports.as_ref());
+        self.protocol_stats.update(snapshot.protocol_stats.as_ref());
+        self.tunnels
+            .update(&snapshot.tunnels, self.units.get(), SystemTime::now());
+        self.wireless.update(
+            &snapshot.wireless,
+            &snapshot.interface_stats,
+            self.units.get(),
+        );
+        self.top_talkers.update(
+            &TopTalkers::from_connections(&snapshot.connections, TOP_TALKERS)
+                .with_applications(&snapshot.applications, TOP_TALKERS),
+            self.units.get(),
+            Some(&self.resolver),
+        );
+
+        // Leave out localhost connections, idle ones when hidden (see
+        // `set_idle`) and those not matching --filter; the rows are only
+        // sorted again when any of that or the connections changed
         {
-            let mut selected = self.selected_row.borrow_mut();
-            *selected = None;
+            let mut rows = self.rows.borrow_mut();
+            rows.set_sort(*self.sort_column.borrow(), *self.sort_ascending.borrow());
+            let filter = self.filter.borrow();
+            if rows.filter() != filter.as_deref() {
+                rows.set_filter(filter.clone());
+            }
+            rows.set_hide_loopback(self.hide_loopback.get());
+            rows.update(&snapshot);
         }
-
-        // Get connections
-        let connections = match self.network_service.get_connections() {
-            Ok(conn) => conn,
-            Err(e) => {
-                eprintln!("Failed to get connections: {}", e);
-                return;
+        let rows = self.rows.borrow();
+        let connection_count = rows.rows().len();
+
+        // Refresh fail2ban bans (rate limited inside the service)
+        match self.fail2ban_service.get_ban_list() {
+            Ok(ban_list) => {
+                if self.fail2ban_service.is_enabled() {
+                    self.ban_toggle.set_tooltip_text(Some(&format!(
+                        "{} addresses banned by fail2ban",
+                        ban_list.len()
+                    )));
+                }
+                *self.ban_list.borrow_mut() = ban_list;
             }
-        };
-
-        // Update I/O data for rate calculations
-        let prev_io = self
-            .prev_io
-            .lock()
-            .unwrap_or_else(|e| e.into_inner())
-            .clone();
-        let (updated_connections, current_io) = match self
-            .network_service
-            .update_connection_rates(connections, &prev_io)
-        {
-            Ok(result) => result,
             Err(e) => {
-                eprintln!("Failed to update connection rates: {}", e);
-                return;
+                self.ban_toggle.set_tooltip_text(Some(&e.to_string()));
+                tracing::warn!("Could not read fail2ban bans: {}", e);
             }
-        };
+        }
 
-        // Calculate total sent/received data
-        let mut total_sent = 0u64;
-        let mut total_received = 0u64;
-        for io in current_io.values() {
-            total_sent += io.tx;
-            total_received += io.rx;
+        let active_connections = rows.rows().iter().filter(|conn| conn.is_active()).count();
+        let rates = Talker::total(rows.rows());
+        self.table.set_units(self.units.get());
+        self.table.set_ban_list(self.ban_list.borrow().clone());
+        self.table.set_connections(rows.rows().to_vec());
+        drop(rows);
+        self.update_status(
+            connection_count,
+            active_connections,
+            snapshot.totals,
+            rates,
+            &snapshot.scopes,
+        );
+        if self.is_dns_view_visible() {
+            self.update_dns_clients();
+        }
+        if self.is_routes_view_visible() {
+            self.update_routes();
+        }
+        if self.is_multicast_view_visible() {
+            self.update_multicast();
+        }
+        if self.is_countries_view_visible() {
+            self.update_countries();
         }
 
-        // Update previous I/O data for next iteration
-        {
-            let mut prev_io = self.prev_io.lock().unwrap_or_else(|e| e.into_inner());
-            *prev_io = current_io;
+        // Update column width cache periodically
+        if self.last_update_time.borrow().elapsed().as_secs() > 10 {
+            self.update_column_width_cache();
         }
 
-        // Filter out localhost connections
-        let filtered_connections: Vec<Connection> = updated_connections
-            .into_iter()
-            .filter(|conn| self.resolver.resolve_address(&conn.remote) != "LOCALHOST")
-            .collect();
+        self.metrics.borrow_mut().render.record(started.elapsed());
+    }
 
-        // Sort connections
-        let sorted_connections = self.sort_connections(filtered_connections);
-        let connection_count = sorted_connections.len();
+    fn build_blocked_page(&self) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        content.append(&self.blocked_list);
 
-        // Apply virtualization for large datasets
-        let virtualized_connections = if *self.virtualization_enabled.borrow()
-            && connection_count > *self.max_visible_rows.borrow()
-        {
-            // Show first 50 and last 50 rows with a virtualized middle section
-            let max_rows = *self.max_visible_rows.borrow();
-            let half_rows = max_rows / 2;
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
 
-            if connection_count <= max_rows {
-                sorted_connections.clone()
-            } else {
-                let mut result = Vec::new();
-                // Add first half_rows
-                result.extend(sorted_connections.iter().take(half_rows).cloned());
-                // Add a placeholder for the middle
-                result.push(Connection {
-                    program: "...".to_string(),
-                    pid: "...".to_string(),
-                    protocol: "...".to_string(),
-                    local: "...".to_string(),
-                    remote: "...".to_string(),
-                    state: "...".to_string(),
-                    tx_rate: 0,
-                    rx_rate: 0,
-                    command: "...".to_string(),
-                }); // This will be styled as "..."
-                    // Add last half_rows
-                result.extend(
-                    sorted_connections
-                        .iter()
-                        .skip(connection_count - half_rows)
-                        .cloned(),
-                );
-                result
+    fn build_dns_page(&self) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let note = Label::builder()
+            .label(
+                "Processes with connections to DNS resolvers (port 53, or 853 for DNS over \
+                 TLS), sampled at each refresh. Query names would need packet capture.",
+            )
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        note.add_css_class("dim-label");
+        note.add_css_class("caption");
+        content.append(&note);
+        content.append(&self.dns_list);
+
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
+
+    fn is_dns_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("dns")
+    }
+
+    /// Show the resolver clients of the latest snapshot in the DNS tab
+    fn update_dns_clients(&self) {
+        self.dns_list.remove_all();
+        let now = SystemTime::now();
+        for client in &self.latest_snapshot.borrow().dns_clients {
+            let ago = now.duration_since(client.last_seen).unwrap_or_default();
+            let row = ActionRow::builder()
+                .title(format!(
+                    "{} → {} ({})",
+                    client.process,
+                    self.resolver.resolve_host(&client.resolver),
+                    client.transport
+                ))
+                .subtitle(format!(
+                    "{} queries seen · {:.1} per minute · last {}s ago",
+                    client.queries,
+                    client.queries_per_minute(now),
+                    ago.as_secs()
+                ))
+                .build();
+            row.add_css_class("dns-row");
+            self.dns_list.append(&row);
+        }
+    }
+
+    fn build_routes_page(&self) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let note = Label::builder()
+            .label(
+                "Main routing table. The route of the connection last opened in the \
+                 Connections tab is highlighted; policy routing rules aren't considered.",
+            )
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        note.add_css_class("dim-label");
+        note.add_css_class("caption");
+        content.append(&note);
+        content.append(&self.routes_list);
+
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
+
+    fn is_routes_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("routes")
+    }
+
+    /// Show the routes of the latest snapshot in the Routes tab
+    fn update_routes(&self) {
+        self.routes_list.remove_all();
+        let selected = self.selected_route.borrow();
+        for route in &self.latest_snapshot.borrow().routes.routes {
+            let row = ActionRow::builder().title(route.to_string()).build();
+            if route.is_default() {
+                row.set_subtitle("Default gateway");
             }
-        } else {
-            sorted_connections.clone()
-        };
+            row.add_css_class("route-row");
+            if selected.as_ref() == Some(route) {
+                row.add_css_class("route-used");
+                row.set_subtitle("Used by the selected connection");
+            }
+            self.routes_list.append(&row);
+        }
+    }
 
-        let mut active_connections = 0;
-        let num_columns = 8;
-        let mut row = 1; // Start from row 1 (row 0 is headers)
+    fn build_events_page(&self) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let note = Label::builder()
+            .label(
+                "Interfaces connecting, disconnecting and changing addresses during this \
+                 session, newest first, as the kernel reports them.",
+            )
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        note.add_css_class("dim-label");
+        note.add_css_class("caption");
+        content.append(&note);
+        content.append(&self.events_list);
 
-        // Get mutable access to row widgets
-        let mut row_widgets = self.row_widgets.borrow_mut();
-        let existing_widget_count = row_widgets.len();
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
 
-        // Use cached column widths when available
-        let _cached_widths = self.column_width_cache.borrow().clone();
+    fn is_events_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("events")
+    }
 
-        for (conn_index, conn) in virtualized_connections.iter().enumerate() {
-            // Skip placeholder rows in virtualized mode
-            if *self.virtualization_enabled.borrow()
-                && conn_index > *self.max_visible_rows.borrow() / 2
-                && conn_index < virtualized_connections.len() - *self.max_visible_rows.borrow() / 2
-                && conn_index != virtualized_connections.len() / 2
-            {
-                continue;
+    /// Log an interface change, tell the desktop and refresh right away
+    /// instead of at the next poll
+    fn record_link_event(&self, event: LinkEvent) {
+        if let Some(app) = self.window.application() {
+            let notification = gio::Notification::new("Network Monitor");
+            notification.set_body(Some(&event.to_string()));
+            // Replaces the previous one rather than piling up
+            app.send_notification(Some("link-event"), &notification);
+        }
+        {
+            let mut events = self.link_events.borrow_mut();
+            if events.len() == LINK_EVENTS_KEPT {
+                events.pop_front();
             }
+            events.push_back(event);
+        }
+        if self.is_events_view_visible() {
+            self.update_link_events();
+        }
+        self.request_connections();
+    }
 
-            // Calculate the starting index for this row's widgets in the row_widgets vector
-            let start_widget_index = conn_index * num_columns;
+    /// Show the interface changes in the Events tab
+    fn update_link_events(&self) {
+        self.events_list.remove_all();
+        let now = SystemTime::now();
+        for event in self.link_events.borrow().iter().rev() {
+            let ago = now.duration_since(event.at).unwrap_or_default();
+            let row = ActionRow::builder()
+                .title(event.to_string())
+                .subtitle(format!("{} ago", format_duration(ago.as_secs())))
+                .build();
+            if event.is_loss() {
+                row.add_css_class("warning");
+            }
+            self.events_list.append(&row);
+        }
+    }
 
-            // Format display values
-            let prog_pid = if *self.virtualization_enabled.borrow()
-                && conn_index == virtualized_connections.len() / 2
-            {
-                "...".to_string()
-            } else {
-                conn.get_process_display()
-            };
+    fn build_multicast_page(&self) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let note = Label::builder()
+            .label(
+                "Multicast groups joined on each interface and the broadcasts processes \
+                 wait for, with the processes listening on them. Processes are matched by \
+                 the group address or the port of its protocol.",
+            )
+            .wrap(true)
+            .xalign(0.0)
+            .build();
+        note.add_css_class("dim-label");
+        note.add_css_class("caption");
+        content.append(&note);
+        content.append(&self.multicast_list);
 
-            let local_resolved = if *self.virtualization_enabled.borrow()
-                && conn_index == virtualized_connections.len() / 2
-            {
-                "...".to_string()
-            } else {
-                self.resolver.resolve_address(&conn.local)
-            };
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
 
-            let remote_resolved = if *self.virtualization_enabled.borrow()
-                && conn_index == virtualized_connections.len() / 2
-            {
-                "...".to_string()
+    fn is_multicast_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("multicast")
+    }
+
+    /// Show the multicast groups of the latest snapshot in the Multicast tab
+    fn update_multicast(&self) {
+        self.multicast_list.remove_all();
+        for group in &self.latest_snapshot.borrow().multicast {
+            let listeners = if group.processes.is_empty() {
+                "No process found listening".to_string()
             } else {
-                self.resolver.resolve_address(&conn.remote)
+                group.processes.join(", ")
             };
-
-            let process_path = if *self.virtualization_enabled.borrow()
-                && conn_index == virtualized_connections.len() / 2
-            {
-                "...".to_string()
+            let subtitle = if group.is_broadcast() {
+                format!("Broadcast · {listeners}")
             } else {
-                conn.command.clone()
+                format!("Joined by {} · {listeners}", group.users)
             };
+            let row = ActionRow::builder()
+                .title(group.to_string())
+                .subtitle(subtitle)
+                .build();
+            row.add_css_class("multicast-row");
+            if group.processes.is_empty() {
+                row.add_css_class("dim-label");
+            }
+            self.multicast_list.append(&row);
+        }
+    }
 
-            // Process each column separately
-            let columns = [
-                prog_pid,
-                conn.protocol.clone(),
-                local_resolved,
-                remote_resolved,
-                conn.state.clone(),
-                if *self.virtualization_enabled.borrow()
-                    && conn_index == virtualized_connections.len() / 2
-                {
-                    "...".to_string()
-                } else {
-                    Formatter::format_bytes(conn.tx_rate)
-                },
-                if *self.virtualization_enabled.borrow()
-                    && conn_index == virtualized_connections.len() / 2
-                {
-                    "...".to_string()
-                } else {
-                    Formatter::format_bytes(conn.rx_rate)
-                },
-                process_path,
-            ];
-
-            for (col, text) in columns.iter().enumerate() {
-                let widget_index = start_widget_index + col;
-                let label: &Label;
-
-                if widget_index < existing_widget_count {
-                    // Reuse existing widget: only update text
-                    if let Some(widget) = row_widgets[widget_index].downcast_ref::<Label>() {
-                        label = widget;
-                        label.set_text(text);
-                    } else {
-                        eprintln!("Warning: Widget at index {} is not a Label", widget_index);
-                        continue;
-                    }
-                } else {
-                    // Create new widget if needed (only happens when new connections appear)
-                    let text_for_closures = text.clone();
-
-                    let new_label = if col == 7 {
-                        // Path column - don't ellipsize
-                        Label::builder().label(text).xalign(0.0).build()
-                    } else {
-                        // Other columns - ellipsize
-                        Label::builder()
-                            .label(text)
-                            .ellipsize(gtk::pango::EllipsizeMode::End)
-                            .xalign(0.0)
-                            .build()
-                    };
-
-                    // Apply initial styling and alignment (only once)
-                    match col {
-                        0 => {
-                            new_label.add_css_class("caption");
-                            new_label.add_css_class("column-process");
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                        1 => {
-                            new_label.add_css_class("column-protocol");
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                        2 | 3 => {
-                            new_label.add_css_class("column-address");
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                        4 => {
-                            new_label.add_css_class("column-status");
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                        5 => {
-                            new_label.add_css_class("column-rate");
-                            new_label.set_halign(Align::End);
-                            new_label.set_xalign(1.0);
-                        }
-                        6 => {
-                            new_label.add_css_class("column-rate");
-                            new_label.set_halign(Align::End);
-                            new_label.set_xalign(1.0);
-                        }
-                        7 => {
-                            new_label.add_css_class("caption");
-                            new_label.add_css_class("dim-label");
-                            new_label.add_css_class("column-path");
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                        _ => {
-                            new_label.set_halign(Align::Start);
-                            new_label.set_xalign(0.0);
-                        }
-                    }
-                    new_label.add_css_class("table-cell");
-
-                    // Add click gesture for row selection (only once)
-                    let gesture = gtk::GestureClick::new();
-                    let selected_row = self.selected_row.clone();
-                    let row_widgets_ref = self.row_widgets.clone();
-                    let row_num = row; // This row number is constant for the closure
+    fn build_countries_page(self: &Rc<Self>) -> ScrolledWindow {
+        let content = GtkBox::builder()
+            .orientation(Orientation::Vertical)
+            .spacing(12)
+            .margin_start(12)
+            .margin_end(12)
+            .margin_top(12)
+            .margin_bottom(12)
+            .build();
+        let note = Label::builder()
+            .label(
+                "Traffic with the remote hosts of each country since the monitor started, \
+                 placed by the GeoIP database. Rates of a process are split evenly between \
+                 its connections.",
+            )
+            .wrap(true)
+            .xalign(0.0)
+            .hexpand(true)
+            .build();
+        note.add_css_class("dim-label");
+        note.add_css_class("caption");
+        let header = GtkBox::builder()
+            .orientation(Orientation::Horizontal)
+            .spacing(12)
+            .build();
+        header.append(&note);
+        if self.geoip {
+            let map_toggle = gtk::ToggleButton::builder()
+                .icon_name("mark-location-symbolic")
+                .tooltip_text("Show Map")
+                .active(true)
+                .valign(gtk::Align::Center)
+                .build();
+            map_toggle
+                .bind_property("active", self.world_map.widget(), "visible")
+                .sync_create()
+                .build();
+            header.append(&map_toggle);
+        }
+        header.append(&self.countries_sort);
+        content.append(&header);
+        if self.geoip {
+            content.append(self.world_map.widget());
+        }
+        content.append(&self.countries_list);
+
+        // A click on a host shows its connections
+        let monitor = Rc::downgrade(self);
+        self.world_map.connect_host_activated(move |address| {
+            if let Some(monitor) = monitor.upgrade() {
+                monitor.set_filter(Some(address.to_string()));
+                monitor.view_stack.set_visible_child_name("connections");
+                monitor.update_connections();
+            }
+        });
 
-                    gesture.connect_pressed(move |_, _, _, _| {
-                        // Update selected row and apply visual styling
-                        {
-                            let mut selected = selected_row.borrow_mut();
-                            *selected = Some(row_num);
-                        }
+        let monitor = Rc::downgrade(self);
+        self.countries_sort.connect_selected_notify(move |_| {
+            if let Some(monitor) = monitor.upgrade() {
+                monitor.update_countries();
+            }
+        });
 
-                        // Update visual styling for all rows
-                        let widgets = row_widgets_ref.borrow();
-                        for (idx, widget) in widgets.iter().enumerate() {
-                            let widget_row = idx / num_columns;
-                            if widget_row == (row_num - 1) {
-                                widget.add_css_class("row-selected");
-                            } else {
-                                widget.remove_css_class("row-selected");
-                            }
-                        }
-                    });
-                    new_label.add_controller(gesture);
-
-                    // Add right-click gesture for context menu (only once)
-                    let right_click_gesture = gtk::GestureClick::new();
-                    right_click_gesture.set_button(3);
-
-                    let text_for_right_click = text_for_closures.clone(); // Clone for right click closure
-                    let active_popovers = self.active_popovers.clone();
-                    right_click_gesture.connect_pressed(move |gesture, _, x, y| {
-                        let copy_text = text_for_right_click.clone();
-
-                        if let Some(display) = gtk::gdk::Display::default() {
-                            let clipboard = display.clipboard();
-                            clipboard.set_text(&copy_text);
-                        } else {
-                            eprintln!(
-                                "Warning: Could not access clipboard - display not available"
-                            );
-                        }
+        ScrolledWindow::builder()
+            .vexpand(true)
+            .hscrollbar_policy(gtk::PolicyType::Never)
+            .child(&content)
+            .build()
+    }
 
-                        let menu = PopoverMenu::builder().build();
-                        let menu_model = Menu::new();
-                        menu_model.append(Some("Copied!"), None);
-                        menu.set_menu_model(Some(&menu_model));
-
-                        if let Some(parent) = gesture.widget() {
-                            menu.set_parent(&parent);
-                            let rect = gtk::gdk::Rectangle::new(x as i32, y as i32, 1, 1);
-                            menu.set_pointing_to(Some(&rect));
-
-                            let active_popovers_clone = active_popovers.clone();
-                            let menu_clone = menu.clone();
-                            active_popovers_clone.borrow_mut().push(menu_clone.clone());
-
-                            let menu_for_timeout = menu.clone();
-                            let active_popovers_for_timeout = active_popovers.clone();
-                            glib::timeout_add_seconds_local_once(1, move || {
-                                menu_for_timeout.unparent();
-                                let mut popovers = active_popovers_for_timeout.borrow_mut();
-                                popovers.retain(|p| !p.eq(&menu_for_timeout));
-                            });
-
-                            menu.popup();
-                        }
-                    });
-                    new_label.add_controller(right_click_gesture);
-
-                    // Add keyboard shortcut for Ctrl+C (only once)
-                    let key_controller = gtk::EventControllerKey::new();
-                    let text_for_keyboard = text_for_closures.clone(); // Clone for keyboard closure
-                    key_controller.connect_key_pressed(move |_, key, _, modifier| {
-                        if key == gtk::gdk::Key::c
-                            && modifier == gtk::gdk::ModifierType::CONTROL_MASK
-                        {
-                            if let Some(display) = gtk::gdk::Display::default() {
-                                let clipboard = display.clipboard();
-                                clipboard.set_text(&text_for_keyboard);
-                            } else {
-                                eprintln!(
-                                    "Warning: Could not access clipboard - display not available"
-                                );
-                            }
-                            return glib::Propagation::Stop;
-                        }
-                        glib::Propagation::Proceed
-                    });
-                    new_label.add_controller(key_controller);
-
-                    // Attach to grid and store
-                    self.content_grid
-                        .attach(&new_label, col as i32, row as i32, 1, 1);
-                    row_widgets.push(new_label.clone());
-                    // Get reference from the newly pushed widget in the vector
-                    if let Some(widget) = row_widgets.last().and_then(|w| w.downcast_ref::<Label>())
-                    {
-                        label = widget;
-                    } else {
-                        eprintln!("Warning: Failed to get reference to newly created Label widget");
-                        continue;
-                    }
-                }
+    fn is_countries_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("countries")
+    }
 
-                // Update dynamic styling (must be done every update)
-                match col {
-                    1 => {
-                        // Protocol color
-                        label.remove_css_class("success");
-                        label.remove_css_class("warning");
-                        label.remove_css_class("dim-label");
-                        if *self.virtualization_enabled.borrow()
-                            && conn_index == virtualized_connections.len() / 2
-                        {
-                            label.add_css_class("dim-label");
-                        } else {
-                            match conn.protocol.as_str() {
-                                "tcp" => label.add_css_class("success"),
-                                "udp" => label.add_css_class("warning"),
-                                _ => label.add_css_class("dim-label"),
-                            }
-                        }
-                    }
-                    3 => {
-                        // Destination rate color
-                        label.remove_css_class("accent");
-                        if *self.virtualization_enabled.borrow()
-                            && conn_index == virtualized_connections.len() / 2
-                        {
-                            label.add_css_class("dim-label");
-                        } else if conn.rx_rate > 0 || conn.tx_rate > 0 {
-                            label.add_css_class("accent");
-                        } else {
-                            label.add_css_class("dim-label");
-                        }
-                    }
-                    4 => {
-                        // Status color
-                        label.remove_css_class("success");
-                        label.remove_css_class("warning");
-                        label.remove_css_class("error");
-                        label.remove_css_class("dim-label");
-                        if *self.virtualization_enabled.borrow()
-                            && conn_index == virtualized_connections.len() / 2
-                        {
-                            label.add_css_class("dim-label");
-                        } else {
-                            match conn.state.as_str() {
-                                "ESTABLISHED" => label.add_css_class("success"),
-                                "LISTEN" => label.add_css_class("warning"),
-                                "TIME_WAIT" => label.add_css_class("error"),
-                                _ => label.add_css_class("dim-label"),
-                            }
-                        }
-                    }
-                    5 => {
-                        // TX Rate color
-                        label.remove_css_class("error");
-                        label.remove_css_class("dim-label");
-                        if *self.virtualization_enabled.borrow()
-                            && conn_index == virtualized_connections.len() / 2
-                        {
-                            label.add_css_class("dim-label");
-                        } else if conn.tx_rate > 0 {
-                            label.add_css_class("error");
-                        } else {
-                            label.add_css_class("dim-label");
-                        }
-                    }
-                    6 => {
-                        // RX Rate color
-                        label.remove_css_class("accent");
-                        label.remove_css_class("dim-label");
-                        if *self.virtualization_enabled.borrow()
-                            && conn_index == virtualized_connections.len() / 2
-                        {
-                            label.add_css_class("dim-label");
-                        } else if conn.rx_rate > 0 {
-                            label.add_css_class("accent");
-                        } else {
-                            label.add_css_class("dim-label");
-                        }
-                    }
-                    7 => {
-                        // Path color
-                        label.remove_css_class("dim-label");
-                        label.add_css_class("dim-label");
-                    }
-                    _ => {}
-                }
+    /// Show the traffic per country of the latest snapshot in the Countries
+    /// tab, sorted by the column picked; numbers start with the largest
+    fn update_countries(&self) {
+        self.countries_list.remove_all();
+        let snapshot = self.latest_snapshot.borrow();
+        if self.geoip {
+            self.world_map
+                .update(&snapshot.connections, self.units.get());
+        }
+        let mut countries = snapshot.countries.clone();
+        drop(snapshot);
+        match self.countries_sort.selected() {
+            0 => {}
+            1 => countries.sort_by(|a, b| a.cmp_by_column(b, 0)),
+            column => countries.sort_by(|a, b| b.cmp_by_column(a, column as usize - 1)),
+        }
+        let units = self.units.get();
+        for country in &countries {
+            let row = ActionRow::builder()
+                .title(&*country.country)
+                .subtitle(match country.hosts {
+                    0 => "No open connections".to_string(),
+                    hosts => format!(
+                        "{} to {hosts} host{} · ↑{} ↓{}",
+                        Formatter::format_connection_count(country.connections),
+                        if hosts == 1 { "" } else { "s" },
+                        units.format_rate(country.tx_rate),
+                        units.format_rate(country.rx_rate)
+                    ),
+                })
+                .build();
+            let totals = Label::new(Some(&format!(
+                "{} sent · {} received",
+                units.format_total(country.totals.sent),
+                units.format_total(country.totals.received)
+            )));
+            totals.add_css_class("numeric");
+            row.add_suffix(&totals);
+            row.add_css_class("country-row");
+            if country.connections == 0 {
+                row.add_css_class("dim-label");
             }
+            self.countries_list.append(&row);
+        }
+    }
 
-            if (!*self.virtualization_enabled.borrow()
-                || conn_index <= *self.max_visible_rows.borrow() / 2
-                || conn_index
-                    >= virtualized_connections.len() - *self.max_visible_rows.borrow() / 2)
-                && conn.is_active()
-            {
-                active_connections += 1;
-            }
+    /// The selected connection serialized as JSON, as an array so several
+    /// pasted selections read alike
+    fn selection_json(&self) -> Option<String> {
+        let conn = self.table.selected()?;
+        serde_json::to_string_pretty(std::slice::from_ref(&conn)).ok()
+    }
 
-            row += 1;
-        }
+    /// Fill the detail pane with the process behind the clicked table row
+    fn show_connection_details(&self, conn: &Connection) {
+        let snapshot = self.latest_snapshot.borrow();
+        let routes = snapshot.routes.clone();
+        *self.selected_route.borrow_mut() = routes.route_for(conn).cloned();
+        self.details.set_routes(routes);
+        self.details
+            .set_congestion_controls(snapshot.congestion_controls());
+        self.details.set_units(self.units.get());
+        self.details.show(conn);
+    }
 
-        // Hide excess widgets if the number of connections decreased
-        let total_widgets_needed = virtualized_connections.len() * num_columns;
-        if existing_widget_count > total_widgets_needed {
-            for widget in row_widgets.drain(total_widgets_needed..) {
-                self.content_grid.remove(&widget);
-            }
-        }
+    fn is_blocked_view_visible(&self) -> bool {
+        self.view_stack.visible_child_name().as_deref() == Some("blocked")
+    }
 
-        // Update status
-        let display_count = if *self.virtualization_enabled.borrow()
-            && connection_count > *self.max_visible_rows.borrow()
+    /// Reload firewall drop/reject entries into the Blocked tab
+    fn update_blocked_attempts(&self) {
+        self.blocked_list.remove_all();
+
+        // Every connection, so attempts of filtered or idle processes keep their owner
+        let attempts = match self
+            .firewall_service
+            .get_blocked_attempts(self.rows.borrow().collected())
         {
-            format!(
-                "{} (showing {})",
-                connection_count,
-                *self.max_visible_rows.borrow()
-            )
-        } else {
-            connection_count.to_string()
+            Ok(attempts) => attempts,
+            Err(e) => {
+                self.blocked_status.set_text(&e.to_string());
+                return;
+            }
         };
+        self.blocked_status
+            .set_text("No blocked connection attempts");
+        let ban_list = self.ban_list.borrow();
 
-        self.update_status(
-            display_count.parse().unwrap_or(sorted_connections.len()),
-            active_connections,
-            total_sent,
-            total_received,
-        );
-
-        // Update column width cache periodically
-        if self.last_update_time.borrow().elapsed().as_secs() > 10 {
-            self.update_column_width_cache();
+        for attempt in attempts {
+            let interface = if attempt.is_outbound() {
+                format!("out {}", attempt.out_interface)
+            } else {
+                format!("in {}", attempt.in_interface)
+            };
+            let remote = if attempt.is_outbound() {
+                &attempt.destination
+            } else {
+                &attempt.source
+            };
+            let banned = ban_list
+                .jail_for(remote)
+                .map(|jail| format!(" · banned ({jail})"))
+                .unwrap_or_default();
+            let row = ActionRow::builder()
+                .title(format!(
+                    "{} {} → {}",
+                    Formatter::format_protocol(&attempt.protocol),
+                    self.resolver.resolve_address(&attempt.source),
+                    self.resolver.resolve_address(&attempt.destination)
+                ))
+                .subtitle(format!(
+                    "{} · {} · {} · {}{}",
+                    attempt.timestamp,
+                    attempt.action,
+                    interface,
+                    attempt.get_process_display(),
+                    banned
+                ))
+                .build();
+            row.add_css_class("blocked-row");
+            if !banned.is_empty() {
+                row.add_css_class("banned");
+            }
+            self.blocked_list.append(&row);
         }
     }
 
     /// Update column width cache for better performance
     fn update_column_width_cache(self: &Rc<Self>) {
         let mut cache = self.column_width_cache.borrow_mut();
-        let current_widths = self.column_widths.borrow();
+        let current_widths = self.table.column_widths();
 
         // Update cache only if widths have changed significantly
         for (i, &width) in current_widths.iter().enumerate() {
@@ -1105,60 +2530,38 @@ impl NetworkMonitorWindow {
         }
     }
 
-    fn update_status(&self, total: usize, active: usize, total_sent: u64, total_received: u64) {
+    /// Connection counts, data sent and received so far and, next to them,
+    /// the current upload and download rates of all connections
+    fn update_status(
+        &self,
+        total: usize,
+        active: usize,
+        totals: TrafficTotals,
+        rates: Talker,
+        scopes: &[ScopeTraffic],
+    ) {
         // Update connection labels in bottom container
         {
             let labels = self.connection_labels.borrow();
             labels.0.set_text(&format!("{total} total connections"));
             labels.1.set_text(&format!("{active} active connections"));
+            let units = self.units.get();
             labels.2.set_text(&format!(
-                "Sent: {}",
-                Formatter::format_bytes_total(total_sent)
+                "Sent: {} · ↑{}",
+                units.format_total(totals.sent),
+                units.format_rate(rates.tx_rate)
             ));
             labels.3.set_text(&format!(
-                "Received: {}",
-                Formatter::format_bytes_total(total_received)
+                "Received: {} · ↓{}",
+                units.format_total(totals.received),
+                units.format_rate(rates.rx_rate)
             ));
         }
-    }
-
-    fn sort_connections(&self, connections: Vec<Connection>) -> Vec<Connection> {
-        if connections.is_empty() {
-            return connections;
+        self.scope_label
+            .set_text(&cli::scope_summary(scopes, self.units.get()));
+        if let Some(row) = self.scope_label.parent() {
+            row.set_visible(!scopes.is_empty());
         }
-
-        let sort_column = *self.sort_column.borrow();
-        let sort_ascending = *self.sort_ascending.borrow();
-
-        let mut sorted_connections = connections;
-
-        sorted_connections.sort_by(|a, b| {
-            let comparison = match sort_column {
-                0 => a.get_process_display().cmp(&b.get_process_display()),
-                1 => a.protocol.cmp(&b.protocol),
-                2 => self
-                    .resolver
-                    .resolve_address(&a.local)
-                    .cmp(&self.resolver.resolve_address(&b.local)),
-                3 => self
-                    .resolver
-                    .resolve_address(&a.remote)
-                    .cmp(&self.resolver.resolve_address(&b.remote)),
-                4 => a.state.cmp(&b.state),
-                5 => a.tx_rate.cmp(&b.tx_rate),
-                6 => a.rx_rate.cmp(&b.rx_rate),
-                7 => a.command.cmp(&b.command),
-                _ => std::cmp::Ordering::Equal,
-            };
-
-            if sort_ascending {
-                comparison
-            } else {
-                comparison.reverse()
-            }
-        });
-
-        sorted_connections
     }
 
     pub fn show_about_dialog(parent: &ApplicationWindow) {
@@ -1178,234 +2581,269 @@ impl NetworkMonitorWindow {
         about.present();
     }
 
-    fn update_header_labels(&self) {
-        let sort_column = *self.sort_column.borrow();
-        let sort_ascending = *self.sort_ascending.borrow();
-        let header_labels = self.header_labels.borrow();
-
-        // Define base labels for each column
-        let base_labels = [
-            "Process(ID)",
-            "Protocol",
-            "Source",
-            "Destination",
-            "Status",
-            "TX",
-            "RX",
-            "Path",
-        ];
-
-        for (index, label) in header_labels.iter().enumerate() {
-            let base_label = base_labels.get(index).unwrap_or(&"");
-            let triangle = if index == sort_column {
-                if sort_ascending {
-                    " ▲"
-                } else {
-                    " ▼"
-                }
-            } else {
-                ""
-            };
-            label.set_text(&format!("{base_label}{triangle}"));
+    /// Write the scheduled recording when one is due, window shown or not
+    fn record_scheduled(&self) {
+        let mut recorder = self.recorder.borrow_mut();
+        let Some(recorder) = recorder.as_mut() else {
+            return;
+        };
+        let snapshot = self.latest_snapshot.borrow();
+        match recorder.record_if_due(&snapshot, self.units.get(), |conn| {
+            self.resolver.resolve_remote(conn)
+        }) {
+            Ok(Some(path)) => tracing::info!("Recorded {}", path.display()),
+            Ok(None) => {}
+            Err(e) => tracing::warn!("Could not write the scheduled recording: {}", e),
         }
     }
 
+    fn update_header_labels(&self) {
+        self.table
+            .set_sort(*self.sort_column.borrow(), *self.sort_ascending.borrow());
+    }
+
     fn setup_column_sync(self: &Rc<Self>) {
-        // Set up column width synchronization with debouncing
-        let header_grid1 = self.header_grid.clone();
-        let content_grid1 = self.content_grid.clone();
-        let column_widths1 = self.column_widths.clone();
+        // Size the columns again when the window is resized, debounced
         let last_sync_time = Rc::new(RefCell::new(Instant::now()));
-
-        // Connect to window size changes
-        let window_clone = self.window.clone();
-        let sync_time_clone = last_sync_time.clone();
-        window_clone.connect_default_width_notify(move |_| {
-            // Debounce column width updates
+        let table = self.table.clone();
+        self.window.connect_default_width_notify(move |_| {
             let now = Instant::now();
-            let last_sync = *sync_time_clone.borrow();
+            let last_sync = *last_sync_time.borrow();
 
             if now.duration_since(last_sync).as_millis() > 200 {
-                *sync_time_clone.borrow_mut() = now;
+                *last_sync_time.borrow_mut() = now;
 
                 // Schedule column width update after layout is complete
-                let header_grid = header_grid1.clone();
-                let content_grid = content_grid1.clone();
-                let column_widths = column_widths1.clone();
-
-                glib::idle_add_local_once(move || {
-                    Self::sync_column_widths(&header_grid, &content_grid, &column_widths);
-                });
+                let table = table.clone();
+                glib::idle_add_local_once(move || table.sync_column_widths());
             }
         });
 
         // Initial sync
-        let header_grid2 = self.header_grid.clone();
-        let content_grid2 = self.content_grid.clone();
-        let column_widths2 = self.column_widths.clone();
-        glib::idle_add_local_once(move || {
-            Self::sync_column_widths(&header_grid2, &content_grid2, &column_widths2);
-        });
+        let table = self.table.clone();
+        glib::idle_add_local_once(move || table.sync_column_widths());
     }
 
-    fn sync_column_widths(
-        header_grid: &Grid,
-        content_grid: &Grid,
-        column_widths: &Rc<RefCell<Vec<i32>>>,
-    ) {
-        // Get all children from both grids
-        let header_labels = header_grid.observe_children();
-        let content_children = content_grid.observe_children();
-
-        // Start with very conservative defaults to allow smaller windows
-        let mut max_widths = vec![60; 8]; // Even smaller defaults
-
-        // Define maximum reasonable widths to prevent excessive expansion
-        // Increased Path (index 7) width to allow for long paths and horizontal scrolling
-        let max_reasonable_widths = [150, 45, 140, 140, 80, 70, 70, 500];
-
-        // Measure header widths first with sampling for performance
-        let _header_sample_size = ((header_labels.n_items().min(8) as f32 * 0.3).max(1.0)) as i32;
-        for i in 0..header_labels.n_items().min(8) {
-            let idx = i as usize;
-            if let Some(header_child) = header_labels.item(i) {
-                if let Some(header_label) = header_child.downcast_ref::<Label>() {
-                    // Use text width estimation as fallback
-                    let header_text = header_label.text();
-                    let header_width = estimate_text_width(&header_text) + 16; // Reduced padding
-                    max_widths[idx] = max_widths[idx].max(header_width);
-                }
+    fn setup_close_handler(self: &Rc<Self>) {
+        // Closing quits, or only hides the window while running in the background
+        let monitor_weak = Rc::downgrade(self);
+        self.window.connect_close_request(move |window| {
+            let Some(monitor) = monitor_weak.upgrade() else {
+                return glib::Propagation::Proceed;
+            };
+            monitor.save_state();
+
+            if monitor.keep_running {
+                window.set_visible(false);
+            } else if let Some(app) = window.application() {
+                app.quit();
             }
-        }
 
-        // Measure content column widths with sampling for better performance
-        // Only sample every 5th row to reduce computation
-        let total_content_items = content_children.n_items();
-        let num_columns = 8;
-        let sample_rate = 5;
+            // Return true to indicate we've handled the close request
+            glib::Propagation::Stop
+        });
 
-        for item_idx in (0..total_content_items).step_by(sample_rate) {
-            if let Some(content_child) = content_children.item(item_idx) {
-                if let Some(content_label) = content_child.downcast_ref::<Label>() {
-                    let col_idx = (item_idx % num_columns) as usize;
-                    let content_text = content_label.text();
-                    let content_width = estimate_text_width(&content_text) + 16; // Reduced padding
-                    max_widths[col_idx] = max_widths[col_idx].max(content_width);
+        // Render what was collected while the window was hidden, then
+        // catch up at full speed
+        let monitor_weak = Rc::downgrade(self);
+        self.window.connect_visible_notify(move |window| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.update_slowdown();
+                if window.is_visible() {
+                    monitor.update_connections();
+                    monitor.request_connections();
                 }
             }
+        });
+        let monitor_weak = Rc::downgrade(self);
+        self.window.connect_suspended_notify(move |window| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.update_slowdown();
+                if !window.is_suspended() {
+                    monitor.request_connections();
+                }
+            }
+        });
+        let monitor_weak = Rc::downgrade(self);
+        power::watch_battery(move |on_battery| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.on_battery.set(on_battery);
+                monitor.update_slowdown();
+            }
+        });
+        // Started with --background, the window is never shown
+        self.update_slowdown();
+    }
+
+    /// Refresh less often while the window is hidden or minimized, or the
+    /// system is on battery
+    fn update_slowdown(self: &Rc<Self>) {
+        let unseen = !self.window.is_visible() || self.window.is_suspended();
+        let slowed = unseen || self.on_battery.get();
+        if self.refresh_pacer.borrow_mut().set_slowed(slowed) {
+            self.schedule_refresh_timer();
+            self.update_refresh_indicator();
         }
+    }
 
-        // Apply maximum reasonable width constraints
-        for (idx, width) in max_widths.iter_mut().enumerate() {
-            if idx < max_reasonable_widths.len() {
-                *width = (*width).min(max_reasonable_widths[idx]);
-            }
+    /// Wait for the collector to finish, saving what the next start picks up
+    /// from
+    pub fn stop_collecting(&self) {
+        self.collector.stop();
+    }
+
+    /// Save the window geometry and resolved hostnames for the next session
+    fn save_state(&self) {
+        if let Some(gsettings) = &self.gsettings {
+            Self::save_window_state(&self.window, gsettings);
+        }
+        if let Err(e) = self.resolver.save_cache(&self.resolver_cache) {
+            tracing::warn!("Could not save {}: {}", self.resolver_cache.display(), e);
         }
+    }
 
-        // Apply measured widths to header labels
-        for i in 0..header_labels.n_items().min(8) {
-            let idx = i as usize;
-            let target_width = max_widths[idx];
-
-            if let Some(header_child) = header_labels.item(i) {
-                if let Some(header_label) = header_child.downcast_ref::<Label>() {
-                    header_label.set_width_request(target_width);
-
-                    // Apply appropriate CSS class for each column
-                    match idx {
-                        0 => {
-                            header_label.add_css_class("column-process");
-                            header_label.remove_css_class("column-protocol");
-                            header_label.remove_css_class("column-address");
-                            header_label.remove_css_class("column-status");
-                            header_label.remove_css_class("column-rate");
-                            header_label.remove_css_class("column-path");
-                        }
-                        1 => {
-                            header_label.remove_css_class("column-process");
-                            header_label.add_css_class("column-protocol");
-                            header_label.remove_css_class("column-address");
-                            header_label.remove_css_class("column-status");
-                            header_label.remove_css_class("column-rate");
-                            header_label.remove_css_class("column-path");
-                        }
-                        2 | 3 => {
-                            header_label.remove_css_class("column-process");
-                            header_label.remove_css_class("column-protocol");
-                            header_label.add_css_class("column-address");
-                            header_label.remove_css_class("column-status");
-                            header_label.remove_css_class("column-rate");
-                            header_label.remove_css_class("column-path");
-                        }
-                        4 => {
-                            header_label.remove_css_class("column-process");
-                            header_label.remove_css_class("column-protocol");
-                            header_label.remove_css_class("column-address");
-                            header_label.add_css_class("column-status");
-                            header_label.remove_css_class("column-rate");
-                            header_label.remove_css_class("column-path");
-                        }
-                        5 | 6 => {
-                            header_label.remove_css_class("column-process");
-                            header_label.remove_css_class("column-protocol");
-                            header_label.remove_css_class("column-address");
-                            header_label.remove_css_class("column-status");
-                            header_label.add_css_class("column-rate");
-                            header_label.remove_css_class("column-path");
-                        }
-                        7 => {
-                            header_label.remove_css_class("column-process");
-                            header_label.remove_css_class("column-protocol");
-                            header_label.remove_css_class("column-address");
-                            header_label.remove_css_class("column-status");
-                            header_label.remove_css_class("column-rate");
-                            header_label.add_css_class("column-path");
-                        }
-                        _ => {}
-                    }
-                }
-            }
+    /// Remember the size of the unmaximized window and whether it was maximized
+    fn save_window_state(window: &ApplicationWindow, gsettings: &gio::Settings) {
+        let maximized = window.is_maximized();
+        if !maximized {
+            let (width, height) = window.default_size();
+            let _ = gsettings.set_int("window-width", width);
+            let _ = gsettings.set_int("window-height", height);
         }
+        let _ = gsettings.set_boolean("window-maximized", maximized);
+    }
 
-        // Apply measured widths to content labels with batch update
-        for item_idx in 0..total_content_items {
-            if let Some(content_child) = content_children.item(item_idx) {
-                if let Some(content_label) = content_child.downcast_ref::<Label>() {
-                    let col_idx = (item_idx % num_columns) as usize;
-                    let target_width = max_widths[col_idx];
-                    content_label.set_width_request(target_width);
-                }
+    /// Apply preference changes as they are saved, whether they come from the
+    /// preferences dialog, the toolbar or another process editing dconf
+    fn setup_settings(self: &Rc<Self>) {
+        let Some(gsettings) = &self.gsettings else {
+            return;
+        };
+
+        // The toolbar toggle saves its state; changes made elsewhere move it
+        gsettings
+            .bind("resolve-hostnames", &self.resolve_toggle, "active")
+            .set_only()
+            .build();
+        let resolve_toggle = self.resolve_toggle.clone();
+        gsettings.connect_changed(Some("resolve-hostnames"), move |gsettings, key| {
+            resolve_toggle.set_active(gsettings.boolean(key));
+        });
+
+        let monitor_weak = Rc::downgrade(self);
+        gsettings.connect_changed(Some("hide-loopback"), move |gsettings, key| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.hide_loopback.set(gsettings.boolean(key));
+                monitor.update_connections();
             }
-        }
+        });
 
-        // Store the measured widths
-        *column_widths.borrow_mut() = max_widths;
-    }
+        let monitor_weak = Rc::downgrade(self);
+        gsettings.connect_changed(Some("idle-minutes"), move |gsettings, _| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.set_idle_after(settings::idle_after(gsettings));
+                monitor.update_connections();
+            }
+        });
 
-    fn setup_close_handler(self: &Rc<Self>) {
-        // Handle window close event to properly quit the application
-        self.window.connect_close_request(move |window| {
-            // Quit the application directly
-            if let Some(app) = window.application() {
-                app.quit();
+        let monitor_weak = Rc::downgrade(self);
+        gsettings.connect_changed(Some("hide-idle"), move |gsettings, key| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                monitor.set_hide_idle(gsettings.boolean(key));
+                monitor.update_connections();
             }
+        });
 
-            // Return true to indicate we've handled the close request
-            glib::Propagation::Stop
+        let monitor_weak = Rc::downgrade(self);
+        gsettings.connect_changed(Some("refresh-interval"), move |gsettings, key| {
+            if let Some(monitor) = monitor_weak.upgrade() {
+                let interval = Duration::from_secs_f64(gsettings.double(key));
+                monitor.refresh_pacer.borrow_mut().set_base(interval);
+                monitor.schedule_refresh_timer();
+            }
+        });
+
+        for key in ["units", "prefixes", "numbers"] {
+            let monitor_weak = Rc::downgrade(self);
+            gsettings.connect_changed(Some(key), move |gsettings, _| {
+                if let Some(monitor) = monitor_weak.upgrade() {
+                    monitor.units.set(settings::units(gsettings));
+                    monitor.update_connections();
+                }
+            });
+        }
+
+        gsettings.connect_changed(Some("theme"), move |gsettings, key| {
+            adw::StyleManager::default()
+                .set_color_scheme(settings::color_scheme(&gsettings.string(key)));
         });
     }
 
     fn start_monitoring(self: &Rc<Self>) {
+        // Render snapshots as the collector thread delivers them
+        let monitor_clone = self.clone();
+        let events = self.collector_events.clone();
+        glib::spawn_future_local(async move {
+            while let Ok(event) = events.recv().await {
+                monitor_clone.handle_collector_event(event);
+            }
+        });
+
         // Initial update
-        self.update_connections();
-        self.update_header_labels();
+        self.request_connections();
 
         // Set up periodic updates with debouncing
-        let monitor_clone = self.clone();
-        timeout_add_seconds_local(3, move || {
-            monitor_clone.schedule_debounced_update();
+        self.schedule_refresh_timer();
+    }
+
+    /// (Re)start the periodic refresh at the interval chosen by the pacer
+    fn schedule_refresh_timer(self: &Rc<Self>) {
+        if let Some(source) = self.refresh_timer.borrow_mut().take() {
+            source.remove();
+        }
+        let interval = self.refresh_pacer.borrow().interval();
+        let monitor_weak = Rc::downgrade(self);
+        let source = timeout_add_local(interval, move || {
+            let Some(monitor) = monitor_weak.upgrade() else {
+                return glib::ControlFlow::Break;
+            };
+            monitor.schedule_debounced_update();
             glib::ControlFlow::Continue
         });
+        *self.refresh_timer.borrow_mut() = Some(source);
+    }
+
+    /// Refresh the diagnostics overlay text while it is shown
+    fn update_diagnostics(&self) {
+        if !self.diagnostics_label.is_visible() {
+            return;
+        }
+        let mut metrics = self.metrics.borrow().clone();
+        metrics.resolver_latency = self.resolver.lookup_latency();
+        metrics.resolver_cache = self.resolver.cache_stats();
+
+        let text = metrics
+            .rows()
+            .into_iter()
+            .map(|(name, value)| format!("{name:<15} {value}"))
+            .collect::<Vec<_>>()
+            .join("\n");
+        self.diagnostics_label.set_text(&text);
+    }
+
+    fn update_refresh_indicator(&self) {
+        let pacer = self.refresh_pacer.borrow();
+        let reason = if pacer.is_backed_off() {
+            "collection is slow"
+        } else {
+            "on battery"
+        };
+        self.refresh_indicator
+            .set_visible(pacer.is_backed_off() || pacer.is_slowed());
+        self.refresh_indicator.set_text(&format!(
+            "Refreshing every {}s ({reason})",
+            pacer.interval().as_secs()
+        ));
     }
 
     /// Schedule a debounced update to prevent excessive UI updates
@@ -1441,20 +2879,18 @@ impl NetworkMonitorWindow {
             }
         }
 
-        // Perform the update
-        self.update_connections();
-        self.update_header_labels();
+        // Perform the update; the table is redrawn when the snapshot arrives
+        self.request_connections();
+        if self.is_blocked_view_visible() {
+            self.update_blocked_attempts();
+        }
 
         // Update last update time
         *self.last_update_time.borrow_mut() = Instant::now();
     }
 }
 
-/// Helper function to estimate text width for column sizing
-fn estimate_text_width(text: &str) -> i32 {
-    // More conservative estimation: average character width ~ 7 pixels
-    // This is a simple approximation - GTK will handle actual layout
-    let char_count = text.chars().count();
-    // Cap at reasonable minimum to prevent too narrow columns
-    (char_count * 7).max(40) as i32
+/// What the filter banner says while `filter` applies
+fn filter_banner_title(filter: &str) -> String {
+    format!("Only showing connections matching “{filter}”")
 }
@@ -1,7 +0,0 @@
-pub mod formatter;
-pub mod parsing;
-pub mod recovery;
-
-// Export formatter for both GTK and TUI
-pub use parsing::*;
-pub use recovery::*;